@@ -0,0 +1,67 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Rebuild when a migration is added, so `sqlx::migrate!` embeds it, and
+// stamp the build for GET /api/version (see src/build_info.rs):
+//
+//   GIT_SHA          the commit, with "-dirty" for uncommitted changes
+//                    (set GIT_SHA to override, e.g. in a Docker build
+//                    without .git)
+//   BUILD_UNIX_TIME  when this was built (SOURCE_DATE_EPOCH if set, for
+//                    reproducible builds)
+//   BUILD_FEATURES   the Cargo features compiled in, comma separated
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+    println!("cargo:rerun-if-changed=.git/index");
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let git_sha = std::env::var("GIT_SHA").ok().filter(|sha| !sha.is_empty());
+    println!("cargo:rustc-env=GIT_SHA={}", git_sha.unwrap_or_else(git_sha_from_repo));
+
+    let built_at = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    println!("cargo:rustc-env=BUILD_UNIX_TIME={}", built_at);
+
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(str::to_string))
+        .map(|feature| feature.to_lowercase().replace('_', "-"))
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=BUILD_FEATURES={}", features.join(","));
+}
+
+/// `git rev-parse HEAD` (+ "-dirty"), or "unknown" outside a checkout
+fn git_sha_from_repo() -> String {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    match git(&["rev-parse", "HEAD"]) {
+        Some(sha) => {
+            let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+                .is_some_and(|status| !status.is_empty());
+            if dirty {
+                format!("{}-dirty", sha)
+            } else {
+                sha
+            }
+        }
+        None => "unknown".to_string(),
+    }
+}
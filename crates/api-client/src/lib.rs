@@ -0,0 +1,283 @@
+use api_types::{
+    CreateUserRequest, DepositRequest, ErrorBody, ErrorCode, FieldError, HistoryQuery,
+    LoginRequest, LoginResponse, TransactionPageResponse, TransferRequest, WalletResponse,
+    WithdrawRequest,
+};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::time::Duration;
+use uuid::Uuid;
+
+pub use api_types;
+
+// ============================================================================
+// API CLIENT
+// ============================================================================
+// A typed client for the JSON API (/api/*), using the request and response
+// types from api-types:
+//
+//   let client = ApiClient::new("https://api.example.com", Duration::from_secs(10))?;
+//   let session = client.login(&LoginRequest { email, password }).await?;
+//   let wallet = client.wallet(&session).await?;
+//
+// Errors come back as `ClientError::Status`, with the server's error code
+// parsed into an `ErrorCode`, so callers can match on it:
+//
+//   Err(e) if e.code() == Some(ErrorCode::InsufficientBalance) => ...
+//
+// The server's own `smoketest` and `loadgen` commands are built on it.
+
+/// What went wrong with a request
+#[derive(Debug)]
+pub enum ClientError {
+    /// No response (connection refused, timeout, bad URL, ...)
+    Http(reqwest::Error),
+    /// The server answered with an error
+    Status {
+        status: StatusCode,
+        /// The error code from the body; None if there was no JSON body
+        /// (e.g. 429 from the rate limiter) or the code is newer than this
+        /// client
+        code: Option<ErrorCode>,
+        message: String,
+        /// The failing fields, for VALIDATION_ERROR
+        fields: Vec<FieldError>,
+        /// The body's `details`, for the codes that have them
+        details: Option<serde_json::Value>,
+    },
+    /// The response wasn't what the endpoint returns
+    Unexpected(String),
+}
+
+impl ClientError {
+    /// The server's error code, if it sent one
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            ClientError::Status { code, .. } => *code,
+            _ => None,
+        }
+    }
+
+    /// The HTTP status, if the server answered
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ClientError::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Whether the rate limiter turned the request away (try again later)
+    pub fn is_rate_limited(&self) -> bool {
+        self.status() == Some(StatusCode::TOO_MANY_REQUESTS)
+    }
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Http(e) => write!(f, "request failed: {}", e),
+            ClientError::Status { status, code: Some(code), message, .. } => {
+                write!(f, "{} {}: {}", status, code, message)
+            }
+            ClientError::Status { status, code: None, message, .. } => {
+                write!(f, "{}: {}", status, message)
+            }
+            ClientError::Unexpected(message) => write!(f, "unexpected response: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(e: reqwest::Error) -> Self {
+        ClientError::Http(e)
+    }
+}
+
+/// A signed-in user
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub user_id: Uuid,
+    pub email: String,
+    pub token: String,
+}
+
+impl From<LoginResponse> for Session {
+    fn from(response: LoginResponse) -> Self {
+        Session {
+            user_id: response.user.id,
+            email: response.user.email,
+            token: response.token,
+        }
+    }
+}
+
+/// Talks to one instance, e.g. https://api.example.com
+#[derive(Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl ApiClient {
+    /// A client for the instance at `base_url`, giving up on requests after
+    /// `timeout`
+    pub fn new(base_url: &str, timeout: Duration) -> Result<Self, ClientError> {
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .user_agent(concat!("api-client/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// GET /health
+    pub async fn health(&self) -> Result<(), ClientError> {
+        let response = self.http.get(format!("{}/health", self.base_url)).send().await?;
+        if !response.status().is_success() {
+            return Err(error_from(response).await);
+        }
+        Ok(())
+    }
+
+    /// POST /api/register
+    pub async fn register(&self, request: &CreateUserRequest) -> Result<Session, ClientError> {
+        let response: LoginResponse = self
+            .send(Method::POST, "/api/register", None, Some(request))
+            .await?;
+        Ok(response.into())
+    }
+
+    /// POST /api/login
+    pub async fn login(&self, request: &LoginRequest) -> Result<Session, ClientError> {
+        let response: LoginResponse =
+            self.send(Method::POST, "/api/login", None, Some(request)).await?;
+        Ok(response.into())
+    }
+
+    /// GET /api/wallet
+    pub async fn wallet(&self, session: &Session) -> Result<WalletResponse, ClientError> {
+        self.send::<(), _>(Method::GET, "/api/wallet", Some(session), None)
+            .await
+    }
+
+    /// POST /api/wallet/deposit
+    pub async fn deposit(
+        &self,
+        session: &Session,
+        request: &DepositRequest,
+    ) -> Result<WalletResponse, ClientError> {
+        self.send(Method::POST, "/api/wallet/deposit", Some(session), Some(request))
+            .await
+    }
+
+    /// POST /api/wallet/withdraw
+    pub async fn withdraw(
+        &self,
+        session: &Session,
+        request: &WithdrawRequest,
+    ) -> Result<WalletResponse, ClientError> {
+        self.send(Method::POST, "/api/wallet/withdraw", Some(session), Some(request))
+            .await
+    }
+
+    /// POST /api/wallet/transfer
+    pub async fn transfer(
+        &self,
+        session: &Session,
+        request: &TransferRequest,
+    ) -> Result<WalletResponse, ClientError> {
+        self.send(Method::POST, "/api/wallet/transfer", Some(session), Some(request))
+            .await
+    }
+
+    /// GET /api/transactions (one page, newest first)
+    pub async fn history(
+        &self,
+        session: &Session,
+        query: &HistoryQuery,
+    ) -> Result<TransactionPageResponse, ClientError> {
+        let response = self
+            .http
+            .get(format!("{}/api/transactions", self.base_url))
+            .bearer_auth(&session.token)
+            .query(query)
+            .send()
+            .await?;
+        parse(response, "/api/transactions").await
+    }
+
+    /// DELETE /api/admin/users/:id (needs an admin's token)
+    pub async fn delete_user(&self, admin_token: &str, user_id: Uuid) -> Result<(), ClientError> {
+        let response = self
+            .http
+            .delete(format!("{}/api/admin/users/{}", self.base_url, user_id))
+            .bearer_auth(admin_token)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(error_from(response).await);
+        }
+        Ok(())
+    }
+
+    async fn send<B: Serialize, T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        session: Option<&Session>,
+        body: Option<&B>,
+    ) -> Result<T, ClientError> {
+        let mut request = self.http.request(method, format!("{}{}", self.base_url, path));
+        if let Some(session) = session {
+            request = request.bearer_auth(&session.token);
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        parse(request.send().await?, path).await
+    }
+}
+
+/// The body of a successful response, or the error
+async fn parse<T: DeserializeOwned>(
+    response: reqwest::Response,
+    path: &str,
+) -> Result<T, ClientError> {
+    if !response.status().is_success() {
+        return Err(error_from(response).await);
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| ClientError::Unexpected(format!("{} {}", path, e)))
+}
+
+/// Read our error body (`{"error": ..., "code": ...}`) if there is one
+async fn error_from(response: reqwest::Response) -> ClientError {
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+
+    match serde_json::from_str::<ErrorBody>(&text) {
+        Ok(body) => ClientError::Status {
+            status,
+            code: ErrorCode::parse(&body.code),
+            message: body.error,
+            fields: body.errors,
+            details: body.details,
+        },
+        Err(_) => ClientError::Status {
+            status,
+            code: None,
+            message: text.chars().take(200).collect(),
+            fields: Vec::new(),
+            details: None,
+        },
+    }
+}
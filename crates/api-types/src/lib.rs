@@ -0,0 +1,532 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+// ============================================================================
+// API TYPES
+// ============================================================================
+// The requests and responses of the JSON API (/api/*), shared by the server
+// (my-fintech-app) and its clients (api-client), so both sides agree on
+// the wire format by construction.
+//
+// Requests derive `Validate` with the same rules the server enforces, so a
+// client can check a request before sending it. Amounts are `Decimal`s and
+// travel as strings ("25.00"), never as floats.
+//
+// Only the core endpoints live here: register, login, wallet, transfers,
+// history and the error body. Build with the `sqlx` feature to store
+// `Currency` as the Postgres `currency` enum (the server does), and with
+// `openapi` to derive their schemas for the server's OpenAPI spec.
+
+// ============================================================================
+// AUTH
+// ============================================================================
+
+/// POST /api/register
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateUserRequest {
+    #[validate(email(message = "must be a valid email address"))]
+    pub email: String,
+    #[validate(
+        length(min = 8, message = "must be at least 8 characters"),
+        custom(function = "validate_password")
+    )]
+    pub password: String,
+    #[validate(
+        custom(function = "not_blank", message = "cannot be empty"),
+        length(max = 255, message = "must be at most 255 characters")
+    )]
+    pub full_name: String,
+    /// Someone's referral code, when they invited the new user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(length(max = 16, message = "must be at most 16 characters"))]
+    pub referral_code: Option<String>,
+}
+
+/// POST /api/login
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LoginRequest {
+    #[validate(email(message = "must be a valid email address"))]
+    pub email: String,
+    #[validate(length(min = 1, message = "cannot be empty"))]
+    pub password: String,
+}
+
+/// What register and login return
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LoginResponse {
+    /// JWT, sent back as `Authorization: Bearer <token>`
+    pub token: String,
+    pub user: UserResponse,
+}
+
+/// A user, as clients see them (no password hash)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UserResponse {
+    pub id: Uuid,
+    pub email: String,
+    pub full_name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// ============================================================================
+// WALLET
+// ============================================================================
+
+/// ISO 4217 currency of a wallet
+///
+/// Serialized as the three-letter code, e.g. "USD". Requests that name a
+/// currency are rejected during deserialization if the code isn't one of
+/// these, and money only moves between wallets of the same currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "currency", rename_all = "UPPERCASE"))]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Usd,
+    Eur,
+    Gbp,
+    Jpy,
+    Chf,
+    Cad,
+    Aud,
+    Inr,
+}
+
+impl Currency {
+    /// Every supported currency
+    pub const ALL: [Currency; 8] = [
+        Currency::Usd,
+        Currency::Eur,
+        Currency::Gbp,
+        Currency::Jpy,
+        Currency::Chf,
+        Currency::Cad,
+        Currency::Aud,
+        Currency::Inr,
+    ];
+
+    /// The ISO 4217 code, e.g. "USD"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Gbp => "GBP",
+            Currency::Jpy => "JPY",
+            Currency::Chf => "CHF",
+            Currency::Cad => "CAD",
+            Currency::Aud => "AUD",
+            Currency::Inr => "INR",
+        }
+    }
+}
+
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// GET /api/wallet, and what every money movement returns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WalletResponse {
+    pub id: Uuid,
+    pub balance: Decimal,
+    pub currency: Currency,
+}
+
+/// POST /api/wallets: open a wallet in another currency
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateWalletRequest {
+    pub currency: Currency,
+}
+
+/// POST /api/wallet/deposit
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DepositRequest {
+    #[validate(custom(function = "validate_amount"))]
+    pub amount: Decimal,
+    /// Optional; picks the user's wallet in that currency (default: primary)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Currency>,
+}
+
+/// POST /api/wallet/withdraw
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WithdrawRequest {
+    #[validate(custom(function = "validate_amount"))]
+    pub amount: Decimal,
+    /// Optional; picks the user's wallet in that currency (default: primary)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Currency>,
+}
+
+/// POST /api/wallet/transfer
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransferRequest {
+    #[validate(email(message = "must be a valid email address"))]
+    pub recipient_email: String,
+    #[serde(deserialize_with = "deserialize_decimal_from_string")]
+    #[validate(custom(function = "validate_amount"))]
+    pub amount: Decimal,
+    /// Optional; picks the sender's wallet in that currency (default:
+    /// primary). The recipient's wallet in another currency gets the
+    /// amount converted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Currency>,
+}
+
+/// Largest amount a single request can move
+pub const MAX_AMOUNT: Decimal = Decimal::from_parts(1_000_000_000, 0, 0, false, 0);
+
+/// Validator: money amounts are positive, in whole cents, and at most
+/// `MAX_AMOUNT` (the server also uses it for its own requests)
+pub fn validate_amount(amount: &Decimal) -> Result<(), ValidationError> {
+    if *amount <= Decimal::ZERO {
+        return Err(invalid("positive", "must be greater than 0"));
+    }
+    if amount.normalize().scale() > 2 {
+        return Err(invalid("scale", "must have at most 2 decimal places"));
+    }
+    if *amount > MAX_AMOUNT {
+        return Err(invalid("max", "must be at most 1000000000"));
+    }
+    Ok(())
+}
+
+/// Validator: passwords mix at least two kinds of character (letters,
+/// digits, symbols); length is checked separately
+pub fn validate_password(password: &str) -> Result<(), ValidationError> {
+    let kinds = [
+        password.chars().any(char::is_alphabetic),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_alphanumeric()),
+    ];
+    if kinds.iter().filter(|&&has| has).count() < 2 {
+        return Err(invalid("weak", "must mix letters with digits or symbols"));
+    }
+    Ok(())
+}
+
+/// A failed rule whose message depends on what failed
+fn invalid(code: &'static str, message: &'static str) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(message.into());
+    error
+}
+
+/// Validator: text must contain something other than whitespace
+fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(ValidationError::new("not_blank"));
+    }
+    Ok(())
+}
+
+/// Amounts in transfers must be strings ("25.00")
+fn deserialize_decimal_from_string<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+    let s = String::deserialize(deserializer)?;
+    s.parse::<Decimal>()
+        .map_err(|e| Error::custom(format!("Invalid decimal: {}", e)))
+}
+
+// ============================================================================
+// TRANSACTION HISTORY
+// ============================================================================
+// History is paged with a keyset cursor: pass a page's `next_cursor` back
+// to get the next (older) one. Clients treat it as an opaque string.
+
+/// Page size when the client doesn't ask for one
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+
+/// Largest page a client can ask for
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// Where a page of transactions starts (exclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TransactionCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+// Encoded as "<microseconds since epoch>_<uuid>" (URL safe, no padding)
+impl From<TransactionCursor> for String {
+    fn from(cursor: TransactionCursor) -> Self {
+        format!("{}_{}", cursor.created_at.timestamp_micros(), cursor.id)
+    }
+}
+
+// Documented as the string it travels as
+#[cfg(feature = "openapi")]
+impl utoipa::PartialSchema for TransactionCursor {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        utoipa::openapi::ObjectBuilder::new()
+            .schema_type(utoipa::openapi::Type::String)
+            .description(Some("Opaque cursor from a page's `next_cursor`"))
+            .into()
+    }
+}
+
+#[cfg(feature = "openapi")]
+impl utoipa::ToSchema for TransactionCursor {}
+
+impl TryFrom<String> for TransactionCursor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || "is not a valid cursor".to_string();
+        let (micros, id) = value.split_once('_').ok_or_else(invalid)?;
+        let micros = micros.parse::<i64>().map_err(|_| invalid())?;
+
+        Ok(TransactionCursor {
+            created_at: DateTime::from_timestamp_micros(micros).ok_or_else(invalid)?,
+            id: Uuid::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Query string for history endpoints: `?limit=20&cursor=...`, optionally
+/// narrowed down with `type`, `status`, `from`/`to`, `min_amount`/`max_amount`
+/// and `q`; `total` and the cursors then cover the matching transactions only
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct HistoryQuery {
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<TransactionCursor>,
+    /// DEPOSIT, WITHDRAWAL or TRANSFER
+    #[validate(custom(function = "known_transaction_type", message = "must be DEPOSIT, WITHDRAWAL or TRANSFER"))]
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub transaction_type: Option<String>,
+    /// PENDING, COMPLETED, FAILED or REVERSED
+    #[validate(custom(
+        function = "known_transaction_status",
+        message = "must be PENDING, COMPLETED, FAILED or REVERSED"
+    ))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// First day (UTC, inclusive)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<NaiveDate>,
+    /// Last day (UTC, inclusive)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_amount: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_amount: Option<Decimal>,
+    /// Words in the description; quotes, `or` and `-word` work like a web search
+    #[validate(length(max = 200, message = "must be at most 200 characters"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub q: Option<String>,
+}
+
+/// Validator: a `transaction_type` history filter
+fn known_transaction_type(value: &str) -> Result<(), ValidationError> {
+    match value {
+        "DEPOSIT" | "WITHDRAWAL" | "TRANSFER" => Ok(()),
+        _ => Err(ValidationError::new("transaction_type")),
+    }
+}
+
+/// Validator: a `status` history filter
+fn known_transaction_status(value: &str) -> Result<(), ValidationError> {
+    match value {
+        "PENDING" | "COMPLETED" | "FAILED" | "REVERSED" => Ok(()),
+        _ => Err(ValidationError::new("transaction_status")),
+    }
+}
+
+/// GET /api/transactions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransactionPageResponse {
+    pub transactions: Vec<TransactionResponse>,
+    /// Transactions in the whole history, not just this page
+    pub total: u64,
+    /// None on the last page
+    pub next_cursor: Option<TransactionCursor>,
+}
+
+/// One transaction in a user's history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransactionResponse {
+    pub id: Uuid,
+    /// "DEPOSIT", "WITHDRAWAL" or "TRANSFER"
+    pub transaction_type: String,
+    pub amount: Decimal,
+    pub description: Option<String>,
+    /// PENDING, COMPLETED, FAILED or REVERSED
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub failed_at: Option<DateTime<Utc>>,
+    pub reversed_at: Option<DateTime<Utc>>,
+    /// For a transfer between two currencies, what one unit of the
+    /// sender's currency was converted to
+    #[serde(default)]
+    pub exchange_rate: Option<Decimal>,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+// Every error response carries a stable `code` next to the human message:
+//
+//   { "error": "Insufficient balance", "code": "INSUFFICIENT_BALANCE", "status": 422 }
+//
+// Clients should switch on `code`; the message wording may change.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// 500: a query failed; retrying later may work
+    DatabaseError,
+    /// 401: wrong email or password
+    InvalidCredentials,
+    /// 401: the token is missing, expired or revoked; sign in again
+    InvalidToken,
+    /// 403: the signed-in user may not do this
+    Forbidden,
+    /// 403: the account (yours, or the recipient's) was deleted
+    AccountDeleted,
+    /// 403: details `{ "required_scope" }`, which the token wasn't granted
+    /// (e.g. a read-only token trying to move money)
+    InsufficientScope,
+    /// 400: the request was malformed; `errors` lists failing fields
+    ValidationError,
+    /// 409: an account with this email exists
+    UserAlreadyExists,
+    /// 404: details `{ "resource" }`
+    NotFound,
+    /// 422: the wallet can't cover the amount
+    InsufficientBalance,
+    /// 422: details `{ "expected", "got" }` (currency codes)
+    CurrencyMismatch,
+    /// 422: details `{ "period": "transaction" | "day", "limit", "remaining"? }`
+    LimitExceeded,
+    /// 422: the transaction was refused for another business reason
+    TransactionFailed,
+    /// 423: the account is frozen; support has to unfreeze it
+    AccountFrozen,
+    /// 429: details `{ "retry_after_secs" }`, or `{ "locked": true }`
+    /// until the emailed unlock link is used
+    TooManyAttempts,
+    /// 429: details `{ "retry_after_secs" }`, done too often lately
+    RateLimited,
+    /// 503: details `{ "feature" }`, switched off for now
+    FeatureDisabled,
+    /// 500: something broke on the server
+    InternalError,
+}
+
+impl ErrorCode {
+    /// The code as it appears in JSON, e.g. "INSUFFICIENT_BALANCE"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::DatabaseError => "DATABASE_ERROR",
+            ErrorCode::InvalidCredentials => "INVALID_CREDENTIALS",
+            ErrorCode::InvalidToken => "INVALID_TOKEN",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::AccountDeleted => "ACCOUNT_DELETED",
+            ErrorCode::InsufficientScope => "INSUFFICIENT_SCOPE",
+            ErrorCode::ValidationError => "VALIDATION_ERROR",
+            ErrorCode::UserAlreadyExists => "USER_ALREADY_EXISTS",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::InsufficientBalance => "INSUFFICIENT_BALANCE",
+            ErrorCode::CurrencyMismatch => "CURRENCY_MISMATCH",
+            ErrorCode::LimitExceeded => "LIMIT_EXCEEDED",
+            ErrorCode::TransactionFailed => "TRANSACTION_FAILED",
+            ErrorCode::AccountFrozen => "ACCOUNT_FROZEN",
+            ErrorCode::TooManyAttempts => "TOO_MANY_ATTEMPTS",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::FeatureDisabled => "FEATURE_DISABLED",
+            ErrorCode::InternalError => "INTERNAL_ERROR",
+        }
+    }
+
+    /// Parse a code from a response (None for codes this version doesn't
+    /// know yet)
+    pub fn parse(code: &str) -> Option<Self> {
+        match code {
+            "DATABASE_ERROR" => Some(ErrorCode::DatabaseError),
+            "INVALID_CREDENTIALS" => Some(ErrorCode::InvalidCredentials),
+            "INVALID_TOKEN" => Some(ErrorCode::InvalidToken),
+            "FORBIDDEN" => Some(ErrorCode::Forbidden),
+            "ACCOUNT_DELETED" => Some(ErrorCode::AccountDeleted),
+            "INSUFFICIENT_SCOPE" => Some(ErrorCode::InsufficientScope),
+            "VALIDATION_ERROR" => Some(ErrorCode::ValidationError),
+            "USER_ALREADY_EXISTS" => Some(ErrorCode::UserAlreadyExists),
+            "NOT_FOUND" => Some(ErrorCode::NotFound),
+            "INSUFFICIENT_BALANCE" => Some(ErrorCode::InsufficientBalance),
+            "CURRENCY_MISMATCH" => Some(ErrorCode::CurrencyMismatch),
+            "LIMIT_EXCEEDED" => Some(ErrorCode::LimitExceeded),
+            "TRANSACTION_FAILED" => Some(ErrorCode::TransactionFailed),
+            "ACCOUNT_FROZEN" => Some(ErrorCode::AccountFrozen),
+            "TOO_MANY_ATTEMPTS" => Some(ErrorCode::TooManyAttempts),
+            "RATE_LIMITED" => Some(ErrorCode::RateLimited),
+            "FEATURE_DISABLED" => Some(ErrorCode::FeatureDisabled),
+            "INTERNAL_ERROR" => Some(ErrorCode::InternalError),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One failing field in a request (400 VALIDATION_ERROR lists them)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    pub field: String,
+    /// Short rule name, e.g. "email", "length", "positive"
+    pub code: String,
+    pub message: String,
+}
+
+/// The body of every error response
+///
+/// `code` is kept as text so a client doesn't fail on a code added after
+/// it was built; `ErrorCode::parse` it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ErrorBody {
+    pub error: String,
+    pub code: String,
+    pub status: u16,
+    /// Only for VALIDATION_ERROR from field checks
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    /// Facts about the error, for the codes that have them (see
+    /// `ErrorCode`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    pub details: Option<serde_json::Value>,
+}
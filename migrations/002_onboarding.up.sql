@@ -0,0 +1,20 @@
+-- Post-registration onboarding wizard
+-- One row per user created at signup; users registered before this
+-- migration have no row and are treated as already onboarded.
+CREATE TABLE IF NOT EXISTS onboarding_progress (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    current_step VARCHAR(20) NOT NULL DEFAULT 'VERIFY_EMAIL'
+        CHECK (current_step IN ('VERIFY_EMAIL', 'NOTIFICATIONS', 'TWO_FACTOR', 'FIRST_DEPOSIT', 'COMPLETE')),
+    verification_code VARCHAR(6),
+    verification_expires_at TIMESTAMP WITH TIME ZONE,
+    email_verified_at TIMESTAMP WITH TIME ZONE,
+    notify_email BOOLEAN NOT NULL DEFAULT TRUE,
+    notify_realtime BOOLEAN NOT NULL DEFAULT TRUE,
+    two_factor_opt_in BOOLEAN NOT NULL DEFAULT FALSE,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL
+);
+
+CREATE OR REPLACE TRIGGER update_onboarding_progress_updated_at BEFORE UPDATE ON onboarding_progress
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,2 @@
+-- Preferred language for error messages (NULL = use the browser's Accept-Language)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locale VARCHAR(10);
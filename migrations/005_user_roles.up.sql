@@ -0,0 +1,4 @@
+-- Roles for admin-only features; bootstrap the first admin with
+-- `my-fintech-app create-admin <email>`
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role VARCHAR(20) NOT NULL DEFAULT 'USER'
+    CHECK (role IN ('USER', 'ADMIN'));
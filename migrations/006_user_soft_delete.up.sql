@@ -0,0 +1,5 @@
+-- Soft delete: deleted accounts keep their row (and money history) and can
+-- be restored by an admin within the retention window
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS idx_users_deleted_at ON users(deleted_at) WHERE deleted_at IS NOT NULL;
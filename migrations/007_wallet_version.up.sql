@@ -0,0 +1,3 @@
+-- Row version for optimistic (compare-and-swap) balance updates; every
+-- UPDATE of a wallet bumps it
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 0;
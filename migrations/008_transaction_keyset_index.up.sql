@@ -0,0 +1,4 @@
+-- Keyset pagination of a wallet's history: WHERE wallet_id = $1 AND
+-- (created_at, id) < cursor ORDER BY created_at DESC, id DESC
+CREATE INDEX IF NOT EXISTS idx_transactions_wallet_created_id
+    ON transactions(wallet_id, created_at DESC, id DESC);
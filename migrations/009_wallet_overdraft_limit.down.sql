@@ -0,0 +1,4 @@
+ALTER TABLE wallets DROP CONSTRAINT IF EXISTS balance_within_overdraft;
+ALTER TABLE wallets DROP CONSTRAINT IF EXISTS overdraft_limit_not_negative;
+ALTER TABLE wallets DROP COLUMN IF EXISTS overdraft_limit;
+ALTER TABLE wallets ADD CONSTRAINT positive_balance CHECK (balance >= 0);
@@ -0,0 +1,11 @@
+-- Defense in depth: the database itself refuses to take a wallet below
+-- its overdraft limit (0 unless granted), whatever the application code does
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS overdraft_limit DECIMAL(15, 2) NOT NULL DEFAULT 0.00;
+
+ALTER TABLE wallets DROP CONSTRAINT IF EXISTS overdraft_limit_not_negative;
+ALTER TABLE wallets ADD CONSTRAINT overdraft_limit_not_negative CHECK (overdraft_limit >= 0);
+
+-- Replaces the original `balance >= 0` check
+ALTER TABLE wallets DROP CONSTRAINT IF EXISTS positive_balance;
+ALTER TABLE wallets DROP CONSTRAINT IF EXISTS balance_within_overdraft;
+ALTER TABLE wallets ADD CONSTRAINT balance_within_overdraft CHECK (balance >= -overdraft_limit);
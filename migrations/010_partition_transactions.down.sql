@@ -0,0 +1,25 @@
+-- Back to a single plain table
+CREATE TABLE transactions_unpartitioned (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    transaction_type VARCHAR(20) NOT NULL CHECK (transaction_type IN ('DEPOSIT', 'WITHDRAWAL', 'TRANSFER')),
+    amount DECIMAL(15, 2) NOT NULL,
+    description TEXT,
+    status VARCHAR(20) DEFAULT 'PENDING' CHECK (status IN ('PENDING', 'COMPLETED', 'FAILED')),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT positive_amount CHECK (amount > 0)
+);
+
+INSERT INTO transactions_unpartitioned SELECT * FROM transactions;
+
+-- Drops every partition too
+DROP TABLE transactions;
+DROP FUNCTION IF EXISTS create_transaction_partition(DATE);
+
+ALTER TABLE transactions_unpartitioned RENAME TO transactions;
+ALTER INDEX transactions_unpartitioned_pkey RENAME TO transactions_pkey;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_wallet_id ON transactions(wallet_id);
+CREATE INDEX IF NOT EXISTS idx_transactions_created_at ON transactions(created_at);
+CREATE INDEX IF NOT EXISTS idx_transactions_wallet_created_id
+    ON transactions(wallet_id, created_at DESC, id DESC);
@@ -0,0 +1,106 @@
+-- Partition transactions by month (on created_at) so history queries and
+-- maintenance only touch the months they need as the table grows.
+--
+-- create_transaction_partition(month) adds one monthly partition. The app
+-- calls it at startup and daily for the next few months (see
+-- maintenance_service); rows for months without a partition land in
+-- transactions_default and are moved when their partition is created.
+
+CREATE OR REPLACE FUNCTION create_transaction_partition(month DATE)
+RETURNS VOID AS $$
+DECLARE
+    -- Month boundaries in UTC, whatever the session's time zone
+    start_at TIMESTAMPTZ := date_trunc('month', month::TIMESTAMP) AT TIME ZONE 'UTC';
+    end_at TIMESTAMPTZ := (date_trunc('month', month::TIMESTAMP) + INTERVAL '1 month') AT TIME ZONE 'UTC';
+    partition_name TEXT := 'transactions_' || to_char(month, 'YYYY_MM');
+BEGIN
+    IF to_regclass(partition_name) IS NOT NULL THEN
+        RETURN;
+    END IF;
+
+    EXECUTE format(
+        'CREATE TABLE %I (LIKE transactions INCLUDING DEFAULTS INCLUDING CONSTRAINTS)',
+        partition_name
+    );
+
+    -- Rows that arrived before the partition existed
+    EXECUTE format(
+        'WITH moved AS (
+             DELETE FROM transactions_default
+             WHERE created_at >= $1 AND created_at < $2
+             RETURNING *
+         )
+         INSERT INTO %I SELECT * FROM moved',
+        partition_name
+    ) USING start_at, end_at;
+
+    EXECUTE format(
+        'ALTER TABLE transactions ATTACH PARTITION %I FOR VALUES FROM (%L) TO (%L)',
+        partition_name, start_at, end_at
+    );
+END;
+$$ LANGUAGE plpgsql;
+
+DO $$
+DECLARE
+    month DATE;
+BEGIN
+    -- Already partitioned (the migration is idempotent)
+    IF EXISTS (
+        SELECT 1 FROM pg_partitioned_table p
+        JOIN pg_class c ON c.oid = p.partrelid
+        WHERE c.relname = 'transactions'
+    ) THEN
+        RETURN;
+    END IF;
+
+    ALTER TABLE transactions RENAME TO transactions_unpartitioned;
+    ALTER INDEX IF EXISTS transactions_pkey RENAME TO transactions_unpartitioned_pkey;
+    DROP INDEX IF EXISTS idx_transactions_wallet_id;
+    DROP INDEX IF EXISTS idx_transactions_created_at;
+    DROP INDEX IF EXISTS idx_transactions_wallet_created_id;
+
+    -- The partition key has to be part of the primary key
+    CREATE TABLE transactions (
+        id UUID NOT NULL DEFAULT gen_random_uuid(),
+        wallet_id UUID NOT NULL
+            CONSTRAINT transactions_wallet_id_fkey REFERENCES wallets(id) ON DELETE CASCADE,
+        transaction_type VARCHAR(20) NOT NULL
+            CONSTRAINT transactions_transaction_type_check
+            CHECK (transaction_type IN ('DEPOSIT', 'WITHDRAWAL', 'TRANSFER')),
+        amount DECIMAL(15, 2) NOT NULL,
+        description TEXT,
+        status VARCHAR(20) DEFAULT 'PENDING'
+            CONSTRAINT transactions_status_check
+            CHECK (status IN ('PENDING', 'COMPLETED', 'FAILED')),
+        created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+        CONSTRAINT positive_amount CHECK (amount > 0),
+        CONSTRAINT transactions_pkey PRIMARY KEY (id, created_at)
+    ) PARTITION BY RANGE (created_at);
+
+    CREATE TABLE transactions_default PARTITION OF transactions DEFAULT;
+
+    -- One partition per (UTC) month of existing history, plus this month and next
+    FOR month IN
+        SELECT DISTINCT date_trunc('month', COALESCE(created_at, NOW()) AT TIME ZONE 'UTC')::DATE
+        FROM transactions_unpartitioned
+        UNION
+        SELECT date_trunc('month', NOW() AT TIME ZONE 'UTC')::DATE
+        UNION
+        SELECT (date_trunc('month', NOW() AT TIME ZONE 'UTC') + INTERVAL '1 month')::DATE
+    LOOP
+        PERFORM create_transaction_partition(month);
+    END LOOP;
+
+    INSERT INTO transactions (id, wallet_id, transaction_type, amount, description, status, created_at)
+    SELECT id, wallet_id, transaction_type, amount, description, status, COALESCE(created_at, NOW())
+    FROM transactions_unpartitioned;
+
+    DROP TABLE transactions_unpartitioned;
+END $$;
+
+-- Indexes on the parent apply to every partition
+CREATE INDEX IF NOT EXISTS idx_transactions_wallet_id ON transactions(wallet_id);
+CREATE INDEX IF NOT EXISTS idx_transactions_created_at ON transactions(created_at);
+CREATE INDEX IF NOT EXISTS idx_transactions_wallet_created_id
+    ON transactions(wallet_id, created_at DESC, id DESC);
@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS dashboard_summary_transaction ON transactions;
+DROP TRIGGER IF EXISTS dashboard_summary_wallet ON wallets;
+DROP FUNCTION IF EXISTS dashboard_summary_on_transaction();
+DROP FUNCTION IF EXISTS dashboard_summary_on_wallet();
+DROP FUNCTION IF EXISTS transaction_is_credit(VARCHAR, TEXT);
+DROP FUNCTION IF EXISTS dashboard_recent_limit();
+DROP TABLE IF EXISTS dashboard_summaries;
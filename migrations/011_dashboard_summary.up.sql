@@ -0,0 +1,124 @@
+-- One precomputed row per user for the dashboard: balance, the ids of the
+-- last five transactions and this month's money in/out.
+--
+-- Kept up to date by triggers on wallets and transactions, so every write
+-- path (API, web, seed, admin) maintains it without going through the app.
+-- Month-to-date totals are reset lazily: the first transaction of a new
+-- month starts them over, and readers ignore totals from an older month.
+
+CREATE TABLE IF NOT EXISTS dashboard_summaries (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    wallet_id UUID NOT NULL UNIQUE REFERENCES wallets(id) ON DELETE CASCADE,
+    balance DECIMAL(20, 2) NOT NULL DEFAULT 0.00,
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    recent_transaction_ids UUID[] NOT NULL DEFAULT '{}',
+    month_start DATE NOT NULL DEFAULT date_trunc('month', NOW() AT TIME ZONE 'UTC')::DATE,
+    month_in DECIMAL(20, 2) NOT NULL DEFAULT 0.00,
+    month_out DECIMAL(20, 2) NOT NULL DEFAULT 0.00,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- How many transaction ids the summary keeps
+CREATE OR REPLACE FUNCTION dashboard_recent_limit()
+RETURNS INT AS $$ SELECT 5 $$ LANGUAGE sql IMMUTABLE;
+
+-- Money coming into the wallet (deposits and received transfers)
+CREATE OR REPLACE FUNCTION transaction_is_credit(transaction_type VARCHAR, description TEXT)
+RETURNS BOOLEAN AS $$
+    SELECT transaction_type = 'DEPOSIT'
+        OR (transaction_type = 'TRANSFER' AND description = 'Transfer received')
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION dashboard_summary_on_wallet()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO dashboard_summaries (user_id, wallet_id, balance, currency)
+    VALUES (NEW.user_id, NEW.id, NEW.balance, NEW.currency)
+    ON CONFLICT (user_id) DO UPDATE
+    SET balance = EXCLUDED.balance,
+        currency = EXCLUDED.currency,
+        updated_at = NOW();
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION dashboard_summary_on_transaction()
+RETURNS TRIGGER AS $$
+DECLARE
+    tx_month DATE := date_trunc('month', NEW.created_at AT TIME ZONE 'UTC')::DATE;
+    credit DECIMAL(20, 2) := 0.00;
+    debit DECIMAL(20, 2) := 0.00;
+BEGIN
+    -- Only settled money counts towards the month's totals
+    IF NEW.status = 'COMPLETED' THEN
+        IF transaction_is_credit(NEW.transaction_type, NEW.description) THEN
+            credit := NEW.amount;
+        ELSE
+            debit := NEW.amount;
+        END IF;
+    END IF;
+
+    UPDATE dashboard_summaries
+    SET recent_transaction_ids =
+            (ARRAY[NEW.id] || recent_transaction_ids)[1:dashboard_recent_limit()],
+        month_in = CASE
+            WHEN month_start = tx_month THEN month_in + credit
+            WHEN month_start < tx_month THEN credit
+            ELSE month_in
+        END,
+        month_out = CASE
+            WHEN month_start = tx_month THEN month_out + debit
+            WHEN month_start < tx_month THEN debit
+            ELSE month_out
+        END,
+        month_start = GREATEST(month_start, tx_month),
+        updated_at = NOW()
+    WHERE wallet_id = NEW.wallet_id;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS dashboard_summary_wallet ON wallets;
+CREATE TRIGGER dashboard_summary_wallet
+    AFTER INSERT OR UPDATE OF balance, currency ON wallets
+    FOR EACH ROW EXECUTE FUNCTION dashboard_summary_on_wallet();
+
+DROP TRIGGER IF EXISTS dashboard_summary_transaction ON transactions;
+CREATE TRIGGER dashboard_summary_transaction
+    AFTER INSERT ON transactions
+    FOR EACH ROW EXECUTE FUNCTION dashboard_summary_on_transaction();
+
+-- Backfill wallets that existed before the triggers
+INSERT INTO dashboard_summaries (
+    user_id, wallet_id, balance, currency, recent_transaction_ids, month_start, month_in, month_out
+)
+SELECT
+    w.user_id,
+    w.id,
+    w.balance,
+    w.currency,
+    COALESCE(recent.ids, '{}'),
+    date_trunc('month', NOW() AT TIME ZONE 'UTC')::DATE,
+    COALESCE(totals.month_in, 0.00),
+    COALESCE(totals.month_out, 0.00)
+FROM wallets w
+LEFT JOIN LATERAL (
+    SELECT array_agg(t.id ORDER BY t.created_at DESC, t.id DESC) AS ids
+    FROM (
+        SELECT id, created_at FROM transactions
+        WHERE wallet_id = w.id
+        ORDER BY created_at DESC, id DESC
+        LIMIT dashboard_recent_limit()
+    ) t
+) recent ON TRUE
+LEFT JOIN LATERAL (
+    SELECT
+        SUM(amount) FILTER (WHERE transaction_is_credit(transaction_type, description)) AS month_in,
+        SUM(amount) FILTER (WHERE NOT transaction_is_credit(transaction_type, description)) AS month_out
+    FROM transactions
+    WHERE wallet_id = w.id
+      AND status = 'COMPLETED'
+      AND created_at >= date_trunc('month', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+) totals ON TRUE
+ON CONFLICT (user_id) DO NOTHING;
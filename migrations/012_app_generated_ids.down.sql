@@ -0,0 +1,3 @@
+ALTER TABLE users ALTER COLUMN id SET DEFAULT gen_random_uuid();
+ALTER TABLE wallets ALTER COLUMN id SET DEFAULT gen_random_uuid();
+ALTER TABLE transactions ALTER COLUMN id SET DEFAULT gen_random_uuid();
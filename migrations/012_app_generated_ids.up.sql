@@ -0,0 +1,10 @@
+-- Ids for users, wallets and transactions are now UUIDv7 generated by the
+-- application (src/domain/ids.rs), so drop the random database defaults.
+-- An INSERT that forgets the id now fails instead of quietly getting a v4.
+--
+-- Existing rows keep their v4 ids: history is ordered by created_at first,
+-- so old and new ids can live side by side.
+
+ALTER TABLE users ALTER COLUMN id DROP DEFAULT;
+ALTER TABLE wallets ALTER COLUMN id DROP DEFAULT;
+ALTER TABLE transactions ALTER COLUMN id DROP DEFAULT;
@@ -0,0 +1,15 @@
+DROP TRIGGER IF EXISTS dashboard_summary_wallet ON wallets;
+
+ALTER TABLE dashboard_summaries ALTER COLUMN currency DROP DEFAULT;
+ALTER TABLE dashboard_summaries ALTER COLUMN currency TYPE VARCHAR(3) USING currency::text;
+ALTER TABLE dashboard_summaries ALTER COLUMN currency SET DEFAULT 'USD';
+
+ALTER TABLE wallets ALTER COLUMN currency DROP DEFAULT;
+ALTER TABLE wallets ALTER COLUMN currency TYPE VARCHAR(3) USING currency::text;
+ALTER TABLE wallets ALTER COLUMN currency SET DEFAULT 'USD';
+
+CREATE TRIGGER dashboard_summary_wallet
+    AFTER INSERT OR UPDATE OF balance, currency ON wallets
+    FOR EACH ROW EXECUTE FUNCTION dashboard_summary_on_wallet();
+
+DROP TYPE IF EXISTS currency;
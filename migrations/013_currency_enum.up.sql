@@ -0,0 +1,39 @@
+-- Wallet currencies become a Postgres enum of ISO 4217 codes (matching
+-- domain::models::Currency) instead of free text.
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'currency') THEN
+        CREATE TYPE currency AS ENUM ('USD', 'EUR', 'GBP', 'JPY', 'CHF', 'CAD', 'AUD', 'INR');
+    END IF;
+END $$;
+
+-- The dashboard summary trigger (011) watches wallets.currency, which
+-- blocks changing the column's type; recreate it afterwards
+DROP TRIGGER IF EXISTS dashboard_summary_wallet ON wallets;
+
+DO $$
+DECLARE
+    target TEXT;
+BEGIN
+    FOREACH target IN ARRAY ARRAY['wallets', 'dashboard_summaries'] LOOP
+        -- Already converted (the migration is idempotent)
+        IF EXISTS (
+            SELECT 1 FROM information_schema.columns
+            WHERE table_name = target AND column_name = 'currency' AND udt_name = 'currency'
+        ) THEN
+            CONTINUE;
+        END IF;
+
+        EXECUTE format('ALTER TABLE %I ALTER COLUMN currency DROP DEFAULT', target);
+        EXECUTE format(
+            'ALTER TABLE %I ALTER COLUMN currency TYPE currency USING upper(currency)::currency',
+            target
+        );
+        EXECUTE format('ALTER TABLE %I ALTER COLUMN currency SET DEFAULT ''USD''', target);
+    END LOOP;
+END $$;
+
+CREATE TRIGGER dashboard_summary_wallet
+    AFTER INSERT OR UPDATE OF balance, currency ON wallets
+    FOR EACH ROW EXECUTE FUNCTION dashboard_summary_on_wallet();
@@ -0,0 +1,49 @@
+-- Move archived transactions back before dropping the archive (as old
+-- history, so the dashboard summary trigger leaves them out)
+SET LOCAL app.restoring_archive = 'on';
+
+INSERT INTO transactions (id, wallet_id, transaction_type, amount, description, status, created_at)
+SELECT id, wallet_id, transaction_type, amount, description, status, created_at
+FROM transactions_archive
+ON CONFLICT DO NOTHING;
+
+DROP TABLE IF EXISTS transaction_restores;
+DROP TABLE IF EXISTS transactions_archive;
+
+-- Back to the trigger function from migration 011
+CREATE OR REPLACE FUNCTION dashboard_summary_on_transaction()
+RETURNS TRIGGER AS $$
+DECLARE
+    tx_month DATE := date_trunc('month', NEW.created_at AT TIME ZONE 'UTC')::DATE;
+    credit DECIMAL(20, 2) := 0.00;
+    debit DECIMAL(20, 2) := 0.00;
+BEGIN
+    -- Only settled money counts towards the month's totals
+    IF NEW.status = 'COMPLETED' THEN
+        IF transaction_is_credit(NEW.transaction_type, NEW.description) THEN
+            credit := NEW.amount;
+        ELSE
+            debit := NEW.amount;
+        END IF;
+    END IF;
+
+    UPDATE dashboard_summaries
+    SET recent_transaction_ids =
+            (ARRAY[NEW.id] || recent_transaction_ids)[1:dashboard_recent_limit()],
+        month_in = CASE
+            WHEN month_start = tx_month THEN month_in + credit
+            WHEN month_start < tx_month THEN credit
+            ELSE month_in
+        END,
+        month_out = CASE
+            WHEN month_start = tx_month THEN month_out + debit
+            WHEN month_start < tx_month THEN debit
+            ELSE month_out
+        END,
+        month_start = GREATEST(month_start, tx_month),
+        updated_at = NOW()
+    WHERE wallet_id = NEW.wallet_id;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,86 @@
+-- Cold storage for old transactions (src/services/archive_service.rs).
+--
+-- The archival job moves transactions older than
+-- TRANSACTION_ARCHIVE_AFTER_YEARS out of the partitioned `transactions`
+-- table into `transactions_archive`, so day-to-day queries and indexes only
+-- cover recent history. Nothing is deleted: the ledger check still adds
+-- both tables up, and admins can move a range back.
+
+CREATE TABLE IF NOT EXISTS transactions_archive (
+    id UUID PRIMARY KEY,
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    transaction_type VARCHAR(20) NOT NULL,
+    amount DECIMAL(15, 2) NOT NULL,
+    description TEXT,
+    status VARCHAR(20),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    archived_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_transactions_archive_wallet_created
+    ON transactions_archive(wallet_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_transactions_archive_created_at
+    ON transactions_archive(created_at);
+
+-- Every restore an admin asked for. Restored rows are left alone by the
+-- archival job until `keep_until`, otherwise the next nightly run would
+-- move them straight back.
+CREATE TABLE IF NOT EXISTS transaction_restores (
+    id UUID PRIMARY KEY,
+    -- NULL = every wallet
+    wallet_id UUID REFERENCES wallets(id) ON DELETE CASCADE,
+    from_time TIMESTAMP WITH TIME ZONE NOT NULL,
+    to_time TIMESTAMP WITH TIME ZONE NOT NULL,
+    restored_count BIGINT NOT NULL,
+    restored_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    restored_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    keep_until TIMESTAMP WITH TIME ZONE NOT NULL,
+    CONSTRAINT transaction_restores_range CHECK (from_time < to_time)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_restores_keep_until
+    ON transaction_restores(keep_until);
+
+-- Restored rows are old history, not new activity: they must not show up
+-- as "recent" on the dashboard. The restore sets `app.restoring_archive`
+-- for its own transaction and the summary trigger skips those inserts.
+CREATE OR REPLACE FUNCTION dashboard_summary_on_transaction()
+RETURNS TRIGGER AS $$
+DECLARE
+    tx_month DATE := date_trunc('month', NEW.created_at AT TIME ZONE 'UTC')::DATE;
+    credit DECIMAL(20, 2) := 0.00;
+    debit DECIMAL(20, 2) := 0.00;
+BEGIN
+    IF current_setting('app.restoring_archive', true) = 'on' THEN
+        RETURN NULL;
+    END IF;
+
+    -- Only settled money counts towards the month's totals
+    IF NEW.status = 'COMPLETED' THEN
+        IF transaction_is_credit(NEW.transaction_type, NEW.description) THEN
+            credit := NEW.amount;
+        ELSE
+            debit := NEW.amount;
+        END IF;
+    END IF;
+
+    UPDATE dashboard_summaries
+    SET recent_transaction_ids =
+            (ARRAY[NEW.id] || recent_transaction_ids)[1:dashboard_recent_limit()],
+        month_in = CASE
+            WHEN month_start = tx_month THEN month_in + credit
+            WHEN month_start < tx_month THEN credit
+            ELSE month_in
+        END,
+        month_out = CASE
+            WHEN month_start = tx_month THEN month_out + debit
+            WHEN month_start < tx_month THEN debit
+            ELSE month_out
+        END,
+        month_start = GREATEST(month_start, tx_month),
+        updated_at = NOW()
+    WHERE wallet_id = NEW.wallet_id;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
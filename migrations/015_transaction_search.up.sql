@@ -0,0 +1,13 @@
+-- Full-text search over transaction descriptions (src/repository/search_repo.rs).
+--
+-- A generated column keeps the tsvector in step with `description` without
+-- triggers, and the GIN index on the partitioned parent is created on every
+-- partition (present and future). English stemming, so "grocery" finds
+-- "Groceries".
+
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (to_tsvector('english', COALESCE(description, ''))) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_search
+    ON transactions USING GIN (search_vector);
@@ -0,0 +1 @@
+DROP MATERIALIZED VIEW IF EXISTS monthly_wallet_totals;
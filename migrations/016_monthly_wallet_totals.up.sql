@@ -0,0 +1,47 @@
+-- Per-wallet monthly totals (src/repository/statement_repo.rs).
+--
+-- Statements and analytics want "money in/out per month" for a wallet;
+-- summing the raw transactions for that means scanning every partition.
+-- This materialized view does the scan once a night instead (refreshed by
+-- the maintenance job, CONCURRENTLY so readers are never blocked).
+--
+-- Archived transactions are included, so old months stay available after
+-- the rows move to cold storage. The closing balance is the running total
+-- since the wallet was opened (wallets start at 0.00).
+
+CREATE MATERIALIZED VIEW IF NOT EXISTS monthly_wallet_totals AS
+SELECT
+    wallet_id,
+    month,
+    money_in,
+    money_out,
+    transaction_count,
+    SUM(money_in - money_out) OVER (
+        PARTITION BY wallet_id ORDER BY month
+    ) AS closing_balance,
+    NOW() AS refreshed_at
+FROM (
+    SELECT
+        t.wallet_id,
+        date_trunc('month', t.created_at AT TIME ZONE 'UTC')::DATE AS month,
+        COALESCE(SUM(t.amount) FILTER (
+            WHERE transaction_is_credit(t.transaction_type, t.description)
+        ), 0) AS money_in,
+        COALESCE(SUM(t.amount) FILTER (
+            WHERE transaction_is_credit(t.transaction_type, t.description) IS NOT TRUE
+        ), 0) AS money_out,
+        COUNT(*) AS transaction_count
+    FROM (
+        SELECT wallet_id, transaction_type, amount, description, created_at
+        FROM transactions WHERE status = 'COMPLETED'
+        UNION ALL
+        SELECT wallet_id, transaction_type, amount, description, created_at
+        FROM transactions_archive WHERE status = 'COMPLETED'
+    ) t
+    GROUP BY 1, 2
+) months
+WITH DATA;
+
+-- Required for REFRESH ... CONCURRENTLY, and the lookup path
+CREATE UNIQUE INDEX IF NOT EXISTS idx_monthly_wallet_totals_wallet_month
+    ON monthly_wallet_totals(wallet_id, month);
@@ -0,0 +1,23 @@
+DROP TRIGGER IF EXISTS dashboard_summary_transaction_status ON transactions;
+DROP FUNCTION IF EXISTS dashboard_summary_on_status_change();
+
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transactions_status_timestamps;
+
+-- REVERSED has no equivalent before this migration; the money is back, so
+-- the closest is FAILED (never counted by the ledger)
+UPDATE transactions SET status = 'FAILED' WHERE status = 'REVERSED';
+UPDATE transactions_archive SET status = 'FAILED' WHERE status = 'REVERSED';
+
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transactions_status_check;
+ALTER TABLE transactions ADD CONSTRAINT transactions_status_check
+    CHECK (status IN ('PENDING', 'COMPLETED', 'FAILED'));
+
+ALTER TABLE transactions ALTER COLUMN status DROP NOT NULL;
+
+ALTER TABLE transactions DROP COLUMN IF EXISTS completed_at;
+ALTER TABLE transactions DROP COLUMN IF EXISTS failed_at;
+ALTER TABLE transactions DROP COLUMN IF EXISTS reversed_at;
+
+ALTER TABLE transactions_archive DROP COLUMN IF EXISTS completed_at;
+ALTER TABLE transactions_archive DROP COLUMN IF EXISTS failed_at;
+ALTER TABLE transactions_archive DROP COLUMN IF EXISTS reversed_at;
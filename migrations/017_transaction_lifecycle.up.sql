@@ -0,0 +1,81 @@
+-- Transactions get a lifecycle instead of being born COMPLETED
+-- (src/repository/transaction_repo.rs):
+--
+--   PENDING --> COMPLETED --> REVERSED
+--      \
+--       `----> FAILED
+--
+-- Each state after PENDING has its own timestamp (PENDING is `created_at`),
+-- and a check keeps the status and the timestamps from disagreeing.
+-- Only COMPLETED transactions move money, so the ledger check and the
+-- monthly totals need no changes.
+
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS completed_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS failed_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS reversed_at TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE transactions_archive ADD COLUMN IF NOT EXISTS completed_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE transactions_archive ADD COLUMN IF NOT EXISTS failed_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE transactions_archive ADD COLUMN IF NOT EXISTS reversed_at TIMESTAMP WITH TIME ZONE;
+
+-- Until now every transaction settled the moment it was written
+UPDATE transactions SET completed_at = created_at
+WHERE status = 'COMPLETED' AND completed_at IS NULL;
+UPDATE transactions SET failed_at = created_at
+WHERE status = 'FAILED' AND failed_at IS NULL;
+UPDATE transactions_archive SET completed_at = created_at
+WHERE status = 'COMPLETED' AND completed_at IS NULL;
+UPDATE transactions_archive SET failed_at = created_at
+WHERE status = 'FAILED' AND failed_at IS NULL;
+
+ALTER TABLE transactions ALTER COLUMN status SET NOT NULL;
+
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transactions_status_check;
+ALTER TABLE transactions ADD CONSTRAINT transactions_status_check
+    CHECK (status IN ('PENDING', 'COMPLETED', 'FAILED', 'REVERSED'));
+
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transactions_status_timestamps;
+ALTER TABLE transactions ADD CONSTRAINT transactions_status_timestamps CHECK (
+    CASE status
+        WHEN 'PENDING' THEN completed_at IS NULL AND failed_at IS NULL AND reversed_at IS NULL
+        WHEN 'COMPLETED' THEN completed_at IS NOT NULL AND failed_at IS NULL AND reversed_at IS NULL
+        WHEN 'FAILED' THEN completed_at IS NULL AND failed_at IS NOT NULL AND reversed_at IS NULL
+        WHEN 'REVERSED' THEN completed_at IS NOT NULL AND failed_at IS NULL AND reversed_at IS NOT NULL
+    END
+);
+
+-- The summary trigger from migration 011 only sees inserts. A transaction
+-- that settles (or is reversed) later changes its month's totals too.
+CREATE OR REPLACE FUNCTION dashboard_summary_on_status_change()
+RETURNS TRIGGER AS $$
+DECLARE
+    tx_month DATE := date_trunc('month', NEW.created_at AT TIME ZONE 'UTC')::DATE;
+    delta DECIMAL(20, 2) := 0.00;
+BEGIN
+    IF NEW.status = 'COMPLETED' AND OLD.status <> 'COMPLETED' THEN
+        delta := NEW.amount;
+    ELSIF OLD.status = 'COMPLETED' AND NEW.status <> 'COMPLETED' THEN
+        delta := -NEW.amount;
+    ELSE
+        RETURN NULL;
+    END IF;
+
+    -- Totals from an earlier month are stale anyway (see 011)
+    UPDATE dashboard_summaries
+    SET month_in = month_in
+            + CASE WHEN transaction_is_credit(NEW.transaction_type, NEW.description)
+                   THEN delta ELSE 0.00 END,
+        month_out = month_out
+            + CASE WHEN transaction_is_credit(NEW.transaction_type, NEW.description)
+                   THEN 0.00 ELSE delta END,
+        updated_at = NOW()
+    WHERE wallet_id = NEW.wallet_id AND month_start = tx_month;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS dashboard_summary_transaction_status ON transactions;
+CREATE TRIGGER dashboard_summary_transaction_status
+    AFTER UPDATE OF status ON transactions
+    FOR EACH ROW EXECUTE FUNCTION dashboard_summary_on_status_change();
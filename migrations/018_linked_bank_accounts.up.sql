@@ -0,0 +1,46 @@
+-- External bank accounts linked through a bank provider (Plaid), and the
+-- deposits pulled from them (src/services/bank_service.rs).
+
+CREATE TABLE IF NOT EXISTS linked_bank_accounts (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- "plaid" or "fake"
+    provider VARCHAR(20) NOT NULL,
+    -- The provider's login at the institution (a Plaid Item); every account
+    -- from one login shares it and its access token
+    provider_item_id TEXT NOT NULL,
+    provider_account_id TEXT NOT NULL,
+    -- Cleared when the account is unlinked
+    access_token TEXT,
+    institution_name TEXT,
+    account_name TEXT NOT NULL,
+    -- Last digits of the account number, e.g. "0000"
+    mask VARCHAR(10),
+    -- "checking", "savings", ...
+    subtype VARCHAR(40),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    unlinked_at TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT linked_bank_accounts_token CHECK ((access_token IS NULL) = (unlinked_at IS NOT NULL))
+);
+
+-- Linking the same account again refreshes it instead of adding a copy
+CREATE UNIQUE INDEX IF NOT EXISTS idx_linked_bank_accounts_active
+    ON linked_bank_accounts(user_id, provider, provider_account_id)
+    WHERE unlinked_at IS NULL;
+
+-- One row per deposit from a linked account. The wallet transaction stays
+-- PENDING until the provider reports the transfer settled or failed.
+CREATE TABLE IF NOT EXISTS bank_deposits (
+    -- The PENDING DEPOSIT in `transactions` (no foreign key: transactions
+    -- are partitioned and eventually archived)
+    transaction_id UUID PRIMARY KEY,
+    linked_account_id UUID NOT NULL REFERENCES linked_bank_accounts(id) ON DELETE CASCADE,
+    -- NULL until the provider has accepted the transfer
+    provider_transfer_id TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    settled_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_bank_deposits_unsettled
+    ON bank_deposits(created_at)
+    WHERE settled_at IS NULL;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_bank_deposits_provider_transfer;
+DROP TABLE IF EXISTS bank_payouts;
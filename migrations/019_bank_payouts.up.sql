@@ -0,0 +1,39 @@
+-- Payouts from a wallet to a linked bank account (ACH credits), see
+-- src/services/bank_service.rs.
+--
+-- The wallet is debited up front with a COMPLETED WITHDRAWAL; the payout
+-- row then follows the bank transfer. A RETURNED (or FAILED) payout has
+-- its withdrawal REVERSED, which puts the money back in the wallet.
+
+CREATE TABLE IF NOT EXISTS bank_payouts (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    linked_account_id UUID NOT NULL REFERENCES linked_bank_accounts(id) ON DELETE CASCADE,
+    -- The WITHDRAWAL in `transactions` (no foreign key: transactions are
+    -- partitioned and eventually archived)
+    transaction_id UUID NOT NULL UNIQUE,
+    amount DECIMAL(15, 2) NOT NULL CHECK (amount > 0),
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING'
+        CHECK (status IN ('PENDING', 'SETTLED', 'RETURNED', 'FAILED')),
+    -- NULL until the provider has accepted the transfer
+    provider_transfer_id TEXT UNIQUE,
+    -- Why the bank returned it (or the provider failed it), if they said
+    return_reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    submitted_at TIMESTAMP WITH TIME ZONE,
+    settled_at TIMESTAMP WITH TIME ZONE,
+    returned_at TIMESTAMP WITH TIME ZONE,
+    failed_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_bank_payouts_user
+    ON bank_payouts(user_id, created_at DESC);
+
+-- Checked by the settlement job
+CREATE INDEX IF NOT EXISTS idx_bank_payouts_pending
+    ON bank_payouts(created_at)
+    WHERE status = 'PENDING';
+
+-- Webhooks name deposits by the provider's transfer id
+CREATE INDEX IF NOT EXISTS idx_bank_deposits_provider_transfer
+    ON bank_deposits(provider_transfer_id);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS crypto_trades;
+DROP TABLE IF EXISTS crypto_holdings;
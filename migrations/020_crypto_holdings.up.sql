@@ -0,0 +1,37 @@
+-- Crypto balances held as ledger assets (no on-chain custody), bought and
+-- sold against the USD wallet (src/services/crypto_service.rs).
+--
+-- Quantities keep 18 decimal places (an ETH wei); BTC only ever uses 8 (a
+-- satoshi). The USD side of every trade is an ordinary WITHDRAWAL (buy) or
+-- DEPOSIT (sell) in `transactions`, so balances, statements and the ledger
+-- check keep working unchanged.
+
+CREATE TABLE IF NOT EXISTS crypto_holdings (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    asset VARCHAR(10) NOT NULL CHECK (asset IN ('BTC', 'ETH')),
+    quantity NUMERIC(38, 18) NOT NULL DEFAULT 0 CHECK (quantity >= 0),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, asset)
+);
+
+CREATE TABLE IF NOT EXISTS crypto_trades (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    asset VARCHAR(10) NOT NULL CHECK (asset IN ('BTC', 'ETH')),
+    side VARCHAR(4) NOT NULL CHECK (side IN ('BUY', 'SELL')),
+    quantity NUMERIC(38, 18) NOT NULL CHECK (quantity > 0),
+    -- The provider's quote, in USD per whole coin
+    price NUMERIC(20, 2) NOT NULL CHECK (price > 0),
+    -- What the wallet paid (buy) or received (sell)
+    usd_amount DECIMAL(15, 2) NOT NULL CHECK (usd_amount > 0),
+    -- "coinbase" or "fixed"
+    provider VARCHAR(20) NOT NULL,
+    -- The USD transaction (no foreign key: transactions are partitioned
+    -- and eventually archived)
+    transaction_id UUID NOT NULL UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_crypto_trades_user
+    ON crypto_trades(user_id, created_at DESC);
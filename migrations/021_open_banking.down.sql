@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS open_banking_tokens;
+DROP TABLE IF EXISTS open_banking_consents;
+DROP TABLE IF EXISTS open_banking_clients;
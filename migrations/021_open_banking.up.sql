@@ -0,0 +1,54 @@
+-- Open Banking (PSD2-style) account information for third parties
+-- (src/services/open_banking_service.rs).
+--
+-- A registered client authenticates with client credentials, asks for a
+-- consent, and the user authorises it from their own session. Access
+-- tokens are bound to one client and (for account data) one consent, and
+-- stop working as soon as either is revoked.
+--
+-- Client secrets and access tokens are stored as SHA-256 hex: both are
+-- long random strings, so a plain hash is enough and a leaked table
+-- can't be replayed.
+
+CREATE TABLE IF NOT EXISTS open_banking_clients (
+    id UUID PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    -- The public identifier the client sends with its secret
+    client_id VARCHAR(64) NOT NULL UNIQUE,
+    client_secret_hash CHAR(64) NOT NULL,
+    -- The data scopes its consents may ask for
+    scopes TEXT[] NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE TABLE IF NOT EXISTS open_banking_consents (
+    id UUID PRIMARY KEY,
+    client_id UUID NOT NULL REFERENCES open_banking_clients(id) ON DELETE CASCADE,
+    -- Set once the user answers
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    scopes TEXT[] NOT NULL,
+    status VARCHAR(30) NOT NULL DEFAULT 'AWAITING_AUTHORISATION'
+        CHECK (status IN ('AWAITING_AUTHORISATION', 'AUTHORISED', 'REJECTED', 'REVOKED')),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    authorised_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    CHECK (status = 'AWAITING_AUTHORISATION' OR user_id IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_open_banking_consents_user
+    ON open_banking_consents(user_id, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS open_banking_tokens (
+    token_hash CHAR(64) PRIMARY KEY,
+    client_id UUID NOT NULL REFERENCES open_banking_clients(id) ON DELETE CASCADE,
+    -- NULL for tokens that can only manage consents
+    consent_id UUID REFERENCES open_banking_consents(id) ON DELETE CASCADE,
+    scopes TEXT[] NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_open_banking_tokens_expires
+    ON open_banking_tokens(expires_at);
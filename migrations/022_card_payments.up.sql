@@ -0,0 +1,25 @@
+-- Wallet top-ups paid with Apple Pay or Google Pay through the card
+-- processor (src/services/card_service.rs).
+--
+-- One row per top-up, next to its DEPOSIT transaction. The transaction is
+-- PENDING while the processor is charging the token, then COMPLETED or
+-- FAILED; this row keeps what the processor said, for refunds and
+-- disputes.
+
+CREATE TABLE IF NOT EXISTS card_payments (
+    -- The DEPOSIT in `transactions` (no foreign key: transactions are
+    -- partitioned and eventually archived)
+    transaction_id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    method VARCHAR(20) NOT NULL CHECK (method IN ('APPLE_PAY', 'GOOGLE_PAY')),
+    -- "checkout" or "fake"
+    processor VARCHAR(20) NOT NULL,
+    -- NULL if the processor never created a payment
+    processor_payment_id TEXT UNIQUE,
+    decline_reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    settled_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_card_payments_user
+    ON card_payments(user_id, created_at DESC);
@@ -0,0 +1 @@
+DROP FUNCTION IF EXISTS transaction_category(VARCHAR, TEXT);
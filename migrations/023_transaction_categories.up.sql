@@ -0,0 +1,26 @@
+-- What kind of money movement a transaction is (src/repository/tax_repo.rs).
+--
+-- The ledger only has three transaction types, so - like
+-- `transaction_is_credit` (migration 011) for transfers - fees and interest
+-- are told apart by their description:
+--
+-- - INTEREST: a DEPOSIT whose description mentions interest
+--   ("Interest payment", "Savings interest")
+-- - FEE: a WITHDRAWAL whose description mentions a fee ("Monthly account
+--   fee", "Wire fees"); whole words only, so "Coffee shop" is a purchase
+-- - TRANSFER_IN / TRANSFER_OUT: received / sent transfers
+-- - DEPOSIT / WITHDRAWAL: everything else
+--
+-- Anything that books fees or interest must describe them this way.
+
+CREATE OR REPLACE FUNCTION transaction_category(transaction_type VARCHAR, description TEXT)
+RETURNS VARCHAR AS $$
+    SELECT CASE
+        WHEN transaction_type = 'DEPOSIT' AND description ~* '\minterest\M' THEN 'INTEREST'
+        WHEN transaction_type = 'WITHDRAWAL' AND description ~* '\mfees?\M' THEN 'FEE'
+        WHEN transaction_type = 'TRANSFER' THEN
+            CASE WHEN transaction_is_credit(transaction_type, description)
+                 THEN 'TRANSFER_IN' ELSE 'TRANSFER_OUT' END
+        ELSE transaction_type
+    END
+$$ LANGUAGE sql IMMUTABLE;
@@ -0,0 +1,20 @@
+-- Account codes for the accounting export
+-- (src/services/accounting_service.rs).
+--
+-- Journal entries are booked against the chart of accounts in the user's
+-- own bookkeeping (QuickBooks, Xero). The server config has a default
+-- code for every kind of entry; a user can replace any of them with their
+-- own code here. NULL = use the default.
+
+CREATE TABLE IF NOT EXISTS accounting_account_codes (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    -- The wallet itself (a bank/asset account)
+    wallet_account VARCHAR(32),
+    deposit_account VARCHAR(32),
+    withdrawal_account VARCHAR(32),
+    interest_account VARCHAR(32),
+    fee_account VARCHAR(32),
+    transfer_in_account VARCHAR(32),
+    transfer_out_account VARCHAR(32),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS sso_login_requests;
+DROP TABLE IF EXISTS sso_identities;
+DROP TABLE IF EXISTS sso_connections;
+ALTER TABLE users DROP COLUMN IF EXISTS organization_id;
+DROP TABLE IF EXISTS organizations;
@@ -0,0 +1,76 @@
+-- Enterprise single sign-on (src/sso/, src/services/sso_service.rs).
+--
+-- An organization (an enterprise customer) can have one identity provider,
+-- spoken to over OIDC (authorization code flow) or SAML 2.0 (Web Browser
+-- SSO, HTTP-POST). Users who sign in through it are matched to a local
+-- user by the IdP's stable subject id; the first sign-in links an existing
+-- account with the same email or, with just-in-time provisioning on,
+-- creates one.
+--
+-- Only emails in the connection's `email_domains` are accepted, so an IdP
+-- can't vouch for addresses its organization doesn't own.
+
+CREATE TABLE IF NOT EXISTS organizations (
+    id UUID PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    -- What users type on the login page, e.g. "acme"
+    slug VARCHAR(50) NOT NULL UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS organization_id UUID REFERENCES organizations(id) ON DELETE SET NULL;
+
+CREATE TABLE IF NOT EXISTS sso_connections (
+    organization_id UUID PRIMARY KEY REFERENCES organizations(id) ON DELETE CASCADE,
+    protocol VARCHAR(10) NOT NULL CHECK (protocol IN ('OIDC', 'SAML')),
+    -- OIDC: the issuer (discovery at {issuer}/.well-known/openid-configuration)
+    -- and this app's client registration. The secret is needed in clear to
+    -- redeem codes, like the bank provider's access tokens.
+    oidc_issuer TEXT,
+    oidc_client_id TEXT,
+    oidc_client_secret TEXT,
+    -- SAML: the IdP's entity id, where to send AuthnRequests, and the PEM
+    -- certificate its assertions must be signed with
+    saml_idp_entity_id TEXT,
+    saml_sso_url TEXT,
+    saml_idp_certificate TEXT,
+    email_domains TEXT[] NOT NULL,
+    jit_provisioning BOOLEAN NOT NULL DEFAULT TRUE,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT sso_connections_settings CHECK (
+        (protocol = 'OIDC' AND oidc_issuer IS NOT NULL AND oidc_client_id IS NOT NULL
+            AND oidc_client_secret IS NOT NULL)
+        OR (protocol = 'SAML' AND saml_idp_entity_id IS NOT NULL AND saml_sso_url IS NOT NULL
+            AND saml_idp_certificate IS NOT NULL)
+    )
+);
+
+-- Which IdP identity is which local user
+CREATE TABLE IF NOT EXISTS sso_identities (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    -- OIDC `sub` / SAML NameID
+    subject TEXT NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_login_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, subject),
+    UNIQUE (organization_id, user_id)
+);
+
+-- Sign-ins in flight: the OIDC `state` or the SAML AuthnRequest ID. Each
+-- is answered at most once and expires after a few minutes.
+CREATE TABLE IF NOT EXISTS sso_login_requests (
+    id VARCHAR(100) PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    -- OIDC only: the id_token nonce and the PKCE code verifier
+    nonce VARCHAR(64),
+    code_verifier VARCHAR(128),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_sso_login_requests_expires_at
+    ON sso_login_requests(expires_at);
@@ -0,0 +1,26 @@
+-- Login history (src/services/login_history_service.rs).
+--
+-- One row per successful sign-in (password or SSO) with the address and
+-- browser it came from. Country and city are looked up in the GeoIP
+-- database (GEOIP_DATABASE) at sign-in time and stay NULL without one.
+--
+-- A sign-in from a browser and country the user hasn't signed in from
+-- before is marked `new_device` and gets an alert email.
+
+CREATE TABLE IF NOT EXISTS login_events (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    method VARCHAR(10) NOT NULL CHECK (method IN ('PASSWORD', 'SSO')),
+    -- IPv4 or IPv6, as text
+    ip_address VARCHAR(45) NOT NULL,
+    user_agent VARCHAR(512),
+    -- ISO 3166-1 alpha-2
+    country_code VARCHAR(2),
+    country VARCHAR(100),
+    city VARCHAR(100),
+    new_device BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_events_user_created
+    ON login_events (user_id, created_at DESC);
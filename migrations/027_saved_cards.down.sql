@@ -0,0 +1,2 @@
+ALTER TABLE card_payments DROP COLUMN IF EXISTS saved_card_id;
+DROP TABLE IF EXISTS saved_cards;
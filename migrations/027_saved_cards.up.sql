@@ -0,0 +1,38 @@
+-- Saved funding cards (src/services/card_service.rs).
+--
+-- When a user tops up with "save_card", the card processor keeps the card
+-- and gives us a reusable token for it (Checkout.com: a "src_..." source
+-- id). That token plus what the user needs to recognise the card (brand,
+-- last four digits, expiry) is all we store: never a card number or
+-- cryptogram, which keeps this server out of PCI DSS card data scope.
+--
+-- One card per user can be the default funding source, used when a top-up
+-- from a saved card doesn't name one.
+
+CREATE TABLE IF NOT EXISTS saved_cards (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Only the processor that issued the token can charge it
+    processor VARCHAR(20) NOT NULL,
+    processor_token TEXT NOT NULL,
+    -- The device wallet the card was first used through
+    method VARCHAR(20) NOT NULL CHECK (method IN ('APPLE_PAY', 'GOOGLE_PAY')),
+    -- e.g. "Visa"
+    brand VARCHAR(30),
+    last4 VARCHAR(4) NOT NULL CHECK (last4 ~ '^[0-9]{4}$'),
+    expiry_month SMALLINT NOT NULL CHECK (expiry_month BETWEEN 1 AND 12),
+    expiry_year SMALLINT NOT NULL,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (processor, processor_token)
+);
+
+CREATE INDEX IF NOT EXISTS idx_saved_cards_user ON saved_cards(user_id);
+
+-- At most one default per user
+CREATE UNIQUE INDEX IF NOT EXISTS idx_saved_cards_one_default
+    ON saved_cards(user_id) WHERE is_default;
+
+-- Which saved card a top-up charged (NULL for device wallet tokens)
+ALTER TABLE card_payments
+    ADD COLUMN IF NOT EXISTS saved_card_id UUID REFERENCES saved_cards(id) ON DELETE SET NULL;
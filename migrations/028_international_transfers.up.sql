@@ -0,0 +1,48 @@
+-- Transfers to bank accounts abroad by IBAN and BIC, see
+-- src/services/international_service.rs.
+--
+-- Like bank payouts (019), the wallet is debited up front with a COMPLETED
+-- WITHDRAWAL of amount + correspondent fee. The transfer then moves
+-- SUBMITTED -> IN_TRANSIT -> SETTLED as operations hands it to the
+-- correspondent bank and hears back; a RETURNED transfer has its
+-- withdrawal REVERSED, which puts the money back in the wallet.
+--
+-- The quote the sender accepted (rates, fee, converted amount) is kept as
+-- it was, so the transfer can always be explained later.
+
+CREATE TABLE IF NOT EXISTS international_transfers (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- The WITHDRAWAL in `transactions` (no foreign key: transactions are
+    -- partitioned and eventually archived)
+    transaction_id UUID NOT NULL UNIQUE,
+    beneficiary_name VARCHAR(140) NOT NULL,
+    -- Compact form: upper case, no spaces
+    iban VARCHAR(34) NOT NULL,
+    bic VARCHAR(11) NOT NULL CHECK (length(bic) IN (8, 11)),
+    reference VARCHAR(140),
+    source_currency currency NOT NULL,
+    amount DECIMAL(15, 2) NOT NULL CHECK (amount > 0),
+    correspondent_fee DECIMAL(15, 2) NOT NULL CHECK (correspondent_fee >= 0),
+    destination_currency currency NOT NULL,
+    mid_rate NUMERIC(20, 10) NOT NULL CHECK (mid_rate > 0),
+    rate NUMERIC(20, 10) NOT NULL CHECK (rate > 0),
+    destination_amount DECIMAL(15, 2) NOT NULL CHECK (destination_amount > 0),
+    status VARCHAR(20) NOT NULL DEFAULT 'SUBMITTED'
+        CHECK (status IN ('SUBMITTED', 'IN_TRANSIT', 'SETTLED', 'RETURNED')),
+    -- The payment's reference in the correspondent network (e.g. a UETR)
+    tracking_reference VARCHAR(100),
+    return_reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    in_transit_at TIMESTAMP WITH TIME ZONE,
+    settled_at TIMESTAMP WITH TIME ZONE,
+    returned_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_international_transfers_user
+    ON international_transfers(user_id, created_at DESC);
+
+-- The operations queue: everything not finished yet, oldest first
+CREATE INDEX IF NOT EXISTS idx_international_transfers_open
+    ON international_transfers(status, created_at)
+    WHERE status IN ('SUBMITTED', 'IN_TRANSIT');
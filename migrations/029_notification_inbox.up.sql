@@ -0,0 +1,27 @@
+-- Notification inbox (src/services/notification_service.rs).
+--
+-- Every live notification (e.g. "you received $25") is kept here with a
+-- sequence number that counts up per user. A WebSocket client that lost
+-- its connection reconnects with the last sequence it saw and is sent what
+-- it missed before anything new (see handlers/ws.rs).
+--
+-- Sequences come from `notification_sequences`: bumping the user's row
+-- locks it until the notification is committed, so a user's notifications
+-- become visible in sequence order.
+
+CREATE TABLE IF NOT EXISTS notification_sequences (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    last_sequence BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS notifications (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    sequence BIGINT NOT NULL,
+    -- The message as JSON text, without "sequence" (added when it's sent)
+    payload TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, sequence)
+);
+
+-- Pruning old notifications (maintenance_service)
+CREATE INDEX IF NOT EXISTS idx_notifications_created_at ON notifications(created_at);
@@ -0,0 +1,21 @@
+DROP TABLE IF EXISTS organization_invitations;
+DROP TABLE IF EXISTS organization_members;
+
+CREATE OR REPLACE FUNCTION dashboard_summary_on_wallet()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO dashboard_summaries (user_id, wallet_id, balance, currency)
+    VALUES (NEW.user_id, NEW.id, NEW.balance, NEW.currency)
+    ON CONFLICT (user_id) DO UPDATE
+    SET balance = EXCLUDED.balance,
+        currency = EXCLUDED.currency,
+        updated_at = NOW();
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DELETE FROM wallets WHERE organization_id IS NOT NULL;
+DROP INDEX IF EXISTS idx_wallets_organization_id;
+ALTER TABLE wallets DROP CONSTRAINT IF EXISTS wallets_one_owner;
+ALTER TABLE wallets DROP COLUMN IF EXISTS organization_id;
+ALTER TABLE wallets ALTER COLUMN user_id SET NOT NULL;
@@ -0,0 +1,67 @@
+-- Business accounts (src/services/organization_service.rs).
+--
+-- An organization (025) can own a wallet and have members. Members act on
+-- the organization's wallet according to their role:
+--
+--   OWNER    everything, including inviting and removing members
+--   FINANCE  deposits, withdrawals and transfers
+--   VIEWER   the balance and history only
+--
+-- People join by invitation: an owner invites an email address, and the
+-- user with that address accepts with the token from the email.
+
+-- A wallet belongs to a user or to an organization, never both
+ALTER TABLE wallets ALTER COLUMN user_id DROP NOT NULL;
+ALTER TABLE wallets
+    ADD COLUMN IF NOT EXISTS organization_id UUID REFERENCES organizations(id) ON DELETE CASCADE;
+ALTER TABLE wallets DROP CONSTRAINT IF EXISTS wallets_one_owner;
+ALTER TABLE wallets
+    ADD CONSTRAINT wallets_one_owner CHECK (num_nonnulls(user_id, organization_id) = 1);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_wallets_organization_id
+    ON wallets(organization_id) WHERE organization_id IS NOT NULL;
+
+-- The dashboard summary (011) is per user; organization wallets have none
+CREATE OR REPLACE FUNCTION dashboard_summary_on_wallet()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.user_id IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    INSERT INTO dashboard_summaries (user_id, wallet_id, balance, currency)
+    VALUES (NEW.user_id, NEW.id, NEW.balance, NEW.currency)
+    ON CONFLICT (user_id) DO UPDATE
+    SET balance = EXCLUDED.balance,
+        currency = EXCLUDED.currency,
+        updated_at = NOW();
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TABLE IF NOT EXISTS organization_members (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(10) NOT NULL CHECK (role IN ('OWNER', 'FINANCE', 'VIEWER')),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, user_id)
+);
+
+-- "Which organizations am I in?"
+CREATE INDEX IF NOT EXISTS idx_organization_members_user_id ON organization_members(user_id);
+
+CREATE TABLE IF NOT EXISTS organization_invitations (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    -- Lowercase; only the user with this email can accept
+    email VARCHAR(255) NOT NULL,
+    role VARCHAR(10) NOT NULL CHECK (role IN ('OWNER', 'FINANCE', 'VIEWER')),
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    -- SHA-256 of the token sent in the email
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    accepted_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_organization_invitations_organization_id
+    ON organization_invitations(organization_id);
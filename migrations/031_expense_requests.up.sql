@@ -0,0 +1,25 @@
+-- Expense approval for business accounts: any member of an organization
+-- can ask for a transfer out of its wallet, and an owner or finance
+-- member (someone other than the requester) approves or rejects it.
+-- Approving makes the transfer in the same transaction as the status
+-- change, so a request is paid out at most once.
+
+CREATE TABLE IF NOT EXISTS organization_expense_requests (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    requested_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    recipient_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    amount DECIMAL(15, 2) NOT NULL CHECK (amount > 0),
+    description VARCHAR(140) NOT NULL,
+    status VARCHAR(10) NOT NULL DEFAULT 'PENDING'
+        CHECK (status IN ('PENDING', 'APPROVED', 'REJECTED')),
+    decided_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    decided_at TIMESTAMP WITH TIME ZONE,
+    -- Why it was rejected (or any note from the approver)
+    decision_note VARCHAR(500),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- "What's waiting for approval?", newest first
+CREATE INDEX IF NOT EXISTS idx_organization_expense_requests_organization
+    ON organization_expense_requests(organization_id, status, created_at DESC);
@@ -0,0 +1,35 @@
+-- Referral program: every user can have a code to share; signing up with
+-- it records a referral, and once the new user makes a qualifying first
+-- deposit both sides get a bonus (see referral_service).
+
+CREATE TABLE IF NOT EXISTS referral_codes (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    -- Uppercase letters and digits, e.g. "K7Q2M9XD"
+    code VARCHAR(16) NOT NULL UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS referrals (
+    id UUID PRIMARY KEY,
+    referrer_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- A user can only ever be referred once
+    referee_id UUID NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    code VARCHAR(16) NOT NULL,
+    -- Where the referee signed up from, for the anti-abuse checks
+    signup_ip VARCHAR(45) NOT NULL,
+    status VARCHAR(10) NOT NULL DEFAULT 'PENDING'
+        CHECK (status IN ('PENDING', 'REWARDED', 'REJECTED', 'EXPIRED')),
+    -- Why a referral was rejected (anti-abuse checks, reward limit)
+    rejected_reason VARCHAR(200),
+    -- What each side was paid, once REWARDED
+    referrer_reward DECIMAL(15, 2),
+    referee_reward DECIMAL(15, 2),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    rewarded_at TIMESTAMP WITH TIME ZONE
+);
+
+-- "My referrals", and the reward limit per referrer
+CREATE INDEX IF NOT EXISTS idx_referrals_referrer_id ON referrals(referrer_id, created_at DESC);
+
+-- Pending referrals, checked for a qualifying deposit
+CREATE INDEX IF NOT EXISTS idx_referrals_pending ON referrals(created_at) WHERE status = 'PENDING';
@@ -0,0 +1,17 @@
+-- Server-side logout: a JWT stays valid until it expires, so logging out
+-- puts its id (the `jti` claim) on a denylist, and "log out everywhere"
+-- stamps the user so every token issued until then stops working.
+
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    jti VARCHAR(64) PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- When the token would have expired anyway; the row can go after that
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    revoked_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- The maintenance job deletes rows of tokens that have expired
+CREATE INDEX IF NOT EXISTS idx_revoked_tokens_expires_at ON revoked_tokens(expires_at);
+
+-- Tokens issued at or before this (to the second) are no longer accepted
+ALTER TABLE users ADD COLUMN IF NOT EXISTS tokens_revoked_at TIMESTAMP WITH TIME ZONE;
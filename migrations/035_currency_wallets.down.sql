@@ -0,0 +1,24 @@
+CREATE OR REPLACE FUNCTION dashboard_summary_on_wallet()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.user_id IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    INSERT INTO dashboard_summaries (user_id, wallet_id, balance, currency)
+    VALUES (NEW.user_id, NEW.id, NEW.balance, NEW.currency)
+    ON CONFLICT (user_id) DO UPDATE
+    SET balance = EXCLUDED.balance,
+        currency = EXCLUDED.currency,
+        updated_at = NOW();
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE transactions_archive DROP COLUMN IF EXISTS exchange_rate;
+ALTER TABLE transactions DROP COLUMN IF EXISTS exchange_rate;
+
+DELETE FROM wallets WHERE user_id IS NOT NULL AND NOT is_primary;
+DROP INDEX IF EXISTS idx_wallets_user_currency;
+DROP INDEX IF EXISTS idx_wallets_user_primary;
+ALTER TABLE wallets DROP COLUMN IF EXISTS is_primary;
@@ -0,0 +1,39 @@
+-- Users can hold a wallet in each currency (src/services/wallet_service.rs).
+--
+-- The wallet made at signup stays the user's PRIMARY wallet: deposits,
+-- withdrawals, payouts, the dashboard and everything else that works with
+-- "the user's wallet" keep using it. Wallets in other currencies are added
+-- with POST /api/wallets, at most one per currency.
+--
+-- A transfer between two currencies records the rate it was converted at
+-- on both sides (`exchange_rate`, recipient currency per unit of the
+-- sender's); NULL for everything else.
+
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS is_primary BOOLEAN NOT NULL DEFAULT TRUE;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_wallets_user_primary
+    ON wallets(user_id) WHERE user_id IS NOT NULL AND is_primary;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_wallets_user_currency
+    ON wallets(user_id, currency) WHERE user_id IS NOT NULL;
+
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS exchange_rate NUMERIC(20, 10)
+    CHECK (exchange_rate > 0);
+ALTER TABLE transactions_archive ADD COLUMN IF NOT EXISTS exchange_rate NUMERIC(20, 10);
+
+-- The dashboard summary (011) shows the primary wallet only
+CREATE OR REPLACE FUNCTION dashboard_summary_on_wallet()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.user_id IS NULL OR NOT NEW.is_primary THEN
+        RETURN NULL;
+    END IF;
+
+    INSERT INTO dashboard_summaries (user_id, wallet_id, balance, currency)
+    VALUES (NEW.user_id, NEW.id, NEW.balance, NEW.currency)
+    ON CONFLICT (user_id) DO UPDATE
+    SET balance = EXCLUDED.balance,
+        currency = EXCLUDED.currency,
+        updated_at = NOW();
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
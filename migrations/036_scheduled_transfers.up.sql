@@ -0,0 +1,36 @@
+-- Transfers to another user that run later, see
+-- src/services/scheduled_transfer_service.rs.
+--
+-- A background task picks up SCHEDULED transfers once `next_attempt_at`
+-- has passed and runs them like any other transfer. A failed attempt
+-- (e.g. not enough money yet) is recorded and retried later; after
+-- MAX_ATTEMPTS the transfer is FAILED. Until it has run the sender may
+-- cancel it.
+
+CREATE TABLE IF NOT EXISTS scheduled_transfers (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    recipient_email VARCHAR(255) NOT NULL,
+    amount DECIMAL(15, 2) NOT NULL CHECK (amount > 0),
+    -- Which of the sender's wallets pays (NULL = the primary one)
+    currency currency,
+    execute_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'SCHEDULED'
+        CHECK (status IN ('SCHEDULED', 'COMPLETED', 'FAILED', 'CANCELLED')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- `execute_at` at first, then pushed back after each failed attempt
+    -- (and while an attempt is running, so only one instance makes it)
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    last_error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    cancelled_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_transfers_user
+    ON scheduled_transfers(user_id, execute_at);
+
+-- What the background task looks at: waiting transfers, soonest first
+CREATE INDEX IF NOT EXISTS idx_scheduled_transfers_due
+    ON scheduled_transfers(next_attempt_at)
+    WHERE status = 'SCHEDULED';
@@ -0,0 +1,12 @@
+-- Limits users set on their own spending, below the configured ones
+-- (LIMIT_PER_TRANSACTION, LIMIT_DAILY). A NULL limit, or no row at all,
+-- means the configured limit applies. See wallet_service.
+
+CREATE TABLE IF NOT EXISTS spending_limits (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    -- The most one withdrawal or transfer may move
+    per_transaction DECIMAL(15, 2) CHECK (per_transaction > 0),
+    -- The most a wallet may pay out in a day (UTC)
+    daily DECIMAL(15, 2) CHECK (daily > 0),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,42 @@
+-- Transfers waiting for the sender to confirm them, see the two-phase
+-- transfers in src/services/wallet_service.rs.
+--
+-- Transfers above LIMIT_CONFIRM_ABOVE don't settle at once. The money is
+-- taken from the sender (a COMPLETED "Transfer sent" debit) and the
+-- recipient's "Transfer received" credit waits as PENDING until the sender
+-- enters the code we emailed them. Confirming completes the credit;
+-- cancelling or letting it expire reverses the debit (a refund) and fails
+-- the credit.
+
+CREATE TABLE IF NOT EXISTS pending_transfers (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    recipient_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    recipient_email VARCHAR(255) NOT NULL,
+    -- Taken from the sender, in their wallet's currency
+    amount DECIMAL(15, 2) NOT NULL CHECK (amount > 0),
+    currency currency NOT NULL,
+    -- What the recipient gets, in their wallet's currency
+    recipient_amount DECIMAL(15, 2) NOT NULL CHECK (recipient_amount > 0),
+    -- The two transactions (no foreign keys: `transactions` is partitioned)
+    debit_id UUID NOT NULL,
+    credit_id UUID NOT NULL,
+    -- SHA-256 hex digest of the emailed code
+    code_hash VARCHAR(64) NOT NULL,
+    -- Wrong codes entered so far
+    attempts INTEGER NOT NULL DEFAULT 0,
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING'
+        CHECK (status IN ('PENDING', 'CONFIRMED', 'CANCELLED', 'EXPIRED')),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    confirmed_at TIMESTAMP WITH TIME ZONE,
+    cancelled_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_pending_transfers_user
+    ON pending_transfers(user_id, created_at);
+
+-- What the expiry task looks at
+CREATE INDEX IF NOT EXISTS idx_pending_transfers_expiry
+    ON pending_transfers(expires_at)
+    WHERE status = 'PENDING';
@@ -0,0 +1,27 @@
+-- One user asking another for money, see
+-- src/services/payment_request_service.rs.
+--
+-- The payer approves (which makes an ordinary transfer to the requester)
+-- or declines. Nothing moves until they approve.
+
+CREATE TABLE IF NOT EXISTS payment_requests (
+    id UUID PRIMARY KEY,
+    requester_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    payer_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    amount DECIMAL(15, 2) NOT NULL CHECK (amount > 0),
+    -- Which of the payer's wallets pays (NULL = the primary one)
+    currency currency,
+    -- What it's for, shown to the payer
+    note VARCHAR(140),
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING'
+        CHECK (status IN ('PENDING', 'PAID', 'DECLINED')),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    decided_at TIMESTAMP WITH TIME ZONE,
+    CHECK (requester_id <> payer_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_payment_requests_payer
+    ON payment_requests(payer_id, created_at);
+
+CREATE INDEX IF NOT EXISTS idx_payment_requests_requester
+    ON payment_requests(requester_id, created_at);
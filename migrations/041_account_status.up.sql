@@ -0,0 +1,7 @@
+-- Whether an account may move money. Admins freeze accounts under review
+-- (they can still sign in and look, but not deposit, withdraw or
+-- transfer); closed accounts can't sign in at all.
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS account_status VARCHAR(20) NOT NULL DEFAULT 'ACTIVE'
+        CHECK (account_status IN ('ACTIVE', 'FROZEN', 'CLOSED'));
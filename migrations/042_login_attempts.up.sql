@@ -0,0 +1,18 @@
+-- Failed sign-ins per email address, for brute-force protection (separate
+-- from the per-IP rate limiter, so spreading guesses over many addresses
+-- doesn't help). Keyed on the lowercased address, whether or not it has an
+-- account, so the answer doesn't reveal which ones do.
+
+CREATE TABLE IF NOT EXISTS login_attempts (
+    email VARCHAR(255) PRIMARY KEY,
+    -- Failures in a row (forgotten a day after the last one)
+    failures INTEGER NOT NULL DEFAULT 0,
+    last_failed_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    -- The next attempt isn't looked at before this (exponential back-off)
+    retry_after TIMESTAMP WITH TIME ZONE,
+    -- Set once too many attempts failed; cleared by the emailed unlock link
+    -- or a password reset
+    locked_at TIMESTAMP WITH TIME ZONE,
+    -- SHA-256 hex digest of the emailed unlock token
+    unlock_token_hash VARCHAR(64) UNIQUE
+);
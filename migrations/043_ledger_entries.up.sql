@@ -0,0 +1,94 @@
+-- Double-entry ledger (src/repository/ledger_repo.rs).
+--
+-- Every balance change is a journal: two or more entries whose debits and
+-- credits are equal in each currency. A wallet's balance is its credits
+-- minus its debits; `wallets.balance` is a cached copy of that, written in
+-- the same transaction as the entries and checked daily by the integrity
+-- job.
+--
+-- The other side of a wallet's entry is another wallet or a system account:
+-- - EXTERNAL: money coming into or leaving the platform (deposits,
+--   withdrawals, payouts, card payments, bonuses), or on its way
+-- - EXCHANGE: currency conversion; a converted transfer moves money into it
+--   in one currency and out of it in the other
+-- - OPENING: wallet balances from before the ledger
+--
+-- Entries are never changed or deleted; a mistake is put right with a new
+-- journal.
+
+CREATE TABLE IF NOT EXISTS ledger_entries (
+    id UUID PRIMARY KEY,
+    journal_id UUID NOT NULL,
+    account VARCHAR(16) NOT NULL,
+    -- Only for WALLET entries. No cascade: a wallet with history stays.
+    wallet_id UUID REFERENCES wallets(id),
+    currency currency NOT NULL,
+    debit DECIMAL(15, 2) NOT NULL DEFAULT 0.00,
+    credit DECIMAL(15, 2) NOT NULL DEFAULT 0.00,
+    description TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT ledger_entry_account CHECK (
+        account IN ('WALLET', 'EXTERNAL', 'EXCHANGE', 'OPENING')
+        AND (account = 'WALLET') = (wallet_id IS NOT NULL)
+    ),
+    -- Exactly one side, never negative
+    CONSTRAINT ledger_entry_one_side CHECK (
+        debit >= 0 AND credit >= 0 AND (debit = 0) <> (credit = 0)
+    )
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_wallet ON ledger_entries(wallet_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_journal ON ledger_entries(journal_id);
+
+-- Append-only
+CREATE OR REPLACE FUNCTION ledger_entries_append_only()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'ledger entries cannot be changed or deleted';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS ledger_entries_append_only ON ledger_entries;
+CREATE TRIGGER ledger_entries_append_only
+    BEFORE UPDATE OR DELETE ON ledger_entries
+    FOR EACH ROW EXECUTE FUNCTION ledger_entries_append_only();
+
+-- Every journal balances, checked at commit (its entries are inserted one
+-- by one, so it's only whole by then)
+CREATE OR REPLACE FUNCTION ledger_journal_balanced()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM ledger_entries
+        WHERE journal_id = NEW.journal_id
+        GROUP BY currency
+        HAVING SUM(debit) <> SUM(credit)
+    ) THEN
+        RAISE EXCEPTION 'ledger journal % does not balance', NEW.journal_id
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'ledger_journal_balanced';
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS ledger_journal_balanced ON ledger_entries;
+CREATE CONSTRAINT TRIGGER ledger_journal_balanced
+    AFTER INSERT ON ledger_entries
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION ledger_journal_balanced();
+
+-- Open the ledger with every wallet's current balance
+WITH opening AS (
+    SELECT id AS wallet_id, currency, balance, gen_random_uuid() AS journal_id
+    FROM wallets
+    WHERE balance <> 0
+      AND NOT EXISTS (SELECT 1 FROM ledger_entries e WHERE e.wallet_id = wallets.id)
+)
+INSERT INTO ledger_entries (id, journal_id, account, wallet_id, currency, debit, credit, description)
+SELECT gen_random_uuid(), journal_id, 'WALLET', wallet_id, currency,
+       GREATEST(-balance, 0), GREATEST(balance, 0), 'Opening balance'
+FROM opening
+UNION ALL
+SELECT gen_random_uuid(), journal_id, 'OPENING', NULL, currency,
+       GREATEST(balance, 0), GREATEST(-balance, 0), 'Opening balance'
+FROM opening;
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS email_suppressions;
+DROP TABLE IF EXISTS email_dead_letters;
+DROP TABLE IF EXISTS email_queue;
@@ -0,0 +1,43 @@
+-- Emails waiting to be delivered. A row is written before the first try
+-- and deleted once the mailer accepts the email, so nothing is lost when
+-- the mail server is down; failed tries are retried with exponential
+-- back-off (see services/email_service.rs).
+
+CREATE TABLE IF NOT EXISTS email_queue (
+    id UUID PRIMARY KEY,
+    to_address VARCHAR(255) NOT NULL,
+    subject TEXT NOT NULL,
+    text_body TEXT NOT NULL,
+    html_body TEXT,
+    -- Tries that failed so far
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- Not tried again before this (also pushed ahead while a try is running)
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    last_error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_queue_next_attempt_at ON email_queue (next_attempt_at);
+
+-- Emails given up on: too many failed tries, or refused outright. Kept for
+-- looking into (and resending by hand), never retried.
+CREATE TABLE IF NOT EXISTS email_dead_letters (
+    id UUID PRIMARY KEY,
+    to_address VARCHAR(255) NOT NULL,
+    subject TEXT NOT NULL,
+    text_body TEXT NOT NULL,
+    html_body TEXT,
+    attempts INTEGER NOT NULL,
+    last_error TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    failed_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+-- Addresses we no longer email because the mail server refused them for
+-- good (a hard bounce: no such mailbox, no such domain). Keyed on the
+-- lowercased address.
+CREATE TABLE IF NOT EXISTS email_suppressions (
+    address VARCHAR(255) PRIMARY KEY,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
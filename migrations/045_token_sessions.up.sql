@@ -0,0 +1,25 @@
+-- Devices signed in with a JWT (src/services/token_service.rs).
+--
+-- Every sign-in that hands out a token starts a session here, and the
+-- token carries its id (the `sid` claim). Users list their sessions and
+-- revoke one by deleting its row: its token is refused from then on.
+-- Tokens without a `sid` (from before sessions, or the seeder) aren't
+-- tracked.
+
+CREATE TABLE IF NOT EXISTS token_sessions (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- e.g. "Firefox on Windows", from the User-Agent
+    device_name VARCHAR(100) NOT NULL,
+    user_agent VARCHAR(512),
+    -- IPv4 or IPv6, as text
+    ip_address VARCHAR(45) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    -- Updated at most once a minute
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    -- When the token expires; the maintenance job deletes the row after
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_token_sessions_user_id ON token_sessions (user_id);
+CREATE INDEX IF NOT EXISTS idx_token_sessions_expires_at ON token_sessions (expires_at);
@@ -0,0 +1,9 @@
+-- Whether a user is told about sign-ins from a new device
+-- (src/services/login_history_service.rs): an email and an in-app
+-- notification. On unless they opt out in their notification preferences.
+--
+-- A new device is now an IP address and User-Agent the user hasn't
+-- signed in from together before (it used to be browser and country);
+-- `login_events.new_device` is marked the same way.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS login_alerts BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,16 @@
+-- Bank provider webhooks already acted on (src/services/bank_provider.rs).
+--
+-- A signed webhook is accepted for a few minutes after it was signed;
+-- within that window someone who captured one could send it again. Each
+-- one is recorded here by its signing time and body hash, and a repeat is
+-- refused. Rows older than the window are dropped as new ones arrive,
+-- since a webhook that old is refused anyway.
+
+CREATE TABLE IF NOT EXISTS bank_webhook_receipts (
+    -- The provider's name, the signing time (unix seconds) and the body's
+    -- SHA-256, e.g. "plaid:1760735000:9f86d0..."
+    receipt_key TEXT PRIMARY KEY,
+    signed_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_bank_webhook_receipts_signed_at ON bank_webhook_receipts (signed_at);
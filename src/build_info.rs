@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+
+// ============================================================================
+// BUILD INFO
+// ============================================================================
+// What exactly is running, stamped in at compile time by build.rs. Served
+// at GET /api/version and printed by `--version`, so a deployment can be
+// checked against the commit it should be, and a bug report can name it.
+
+/// The crate version, e.g. "0.1.0"
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The commit this was built from ("-dirty" if it had local changes,
+/// "unknown" if built outside a git checkout)
+pub const GIT_SHA: &str = env!("GIT_SHA");
+
+/// `--version` output, e.g. "0.1.0 (3f2a9c1e...)"
+pub const LONG_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (", env!("GIT_SHA"), ")");
+
+const BUILD_UNIX_TIME: &str = env!("BUILD_UNIX_TIME");
+
+const BUILD_FEATURES: &str = env!("BUILD_FEATURES");
+
+/// When this binary was built
+pub fn built_at() -> DateTime<Utc> {
+    BUILD_UNIX_TIME
+        .parse()
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .unwrap_or_default()
+}
+
+/// The Cargo features compiled in, e.g. ["memory-repo"]
+pub fn cargo_features() -> Vec<&'static str> {
+    BUILD_FEATURES.split(',').filter(|f| !f.is_empty()).collect()
+}
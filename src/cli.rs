@@ -0,0 +1,255 @@
+use clap::{Parser, Subcommand};
+use sqlx::{migrate::Migrator, PgPool};
+use std::path::PathBuf;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::loadgen::Mix;
+use crate::seeder;
+use crate::services::admin_service;
+use crate::utils::jwt::JwtKeys;
+
+// ============================================================================
+// COMMAND LINE
+// ============================================================================
+// Flags for the server binary. They sit on top of the config layers:
+//
+//   built-in defaults → config file → environment variables → CLI flags
+//
+// Example:
+//   my-fintech-app --port 8080 --log-level debug
+//   my-fintech-app --print-config
+//   my-fintech-app --demo           (built with --features memory-repo)
+//
+// Subcommands for operations (no subcommand = serve):
+//   my-fintech-app migrate [run|revert]
+//   my-fintech-app seed
+//   my-fintech-app create-admin alice@example.com
+//   my-fintech-app smoketest --url https://api.example.com
+//   my-fintech-app loadgen --url http://localhost:3000 --users 50
+//
+// `smoketest` and `loadgen` talk to a running instance over HTTP, so they
+// need no config or database.
+
+/// Every migration in ./migrations, embedded at compile time
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+#[derive(Debug, Parser)]
+#[command(version = crate::build_info::LONG_VERSION, about = "Fintech API and web UI server")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Port to listen on (overrides SERVER_PORT)
+    #[arg(long, global = true)]
+    pub port: Option<u16>,
+
+    /// Config file to read (overrides CONFIG_FILE / config/{APP_ENV}.toml)
+    #[arg(long, value_name = "FILE", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Tracing filter, e.g. "debug" or "info,my_fintech_app=trace"
+    /// (overrides LOG_LEVEL)
+    #[arg(long, value_name = "FILTER", global = true)]
+    pub log_level: Option<String>,
+
+    /// Don't run database migrations on startup
+    #[arg(long, global = true)]
+    pub no_migrate: bool,
+
+    /// Print the effective configuration (secrets redacted) and exit
+    #[arg(long)]
+    pub print_config: bool,
+
+    /// Serve the core API from memory with demo users: no database, SMTP
+    /// or config needed (build with --features memory-repo)
+    #[arg(long)]
+    pub demo: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the web server (the default)
+    Serve,
+
+    /// Apply or roll back database migrations
+    Migrate {
+        #[command(subcommand)]
+        action: Option<MigrateAction>,
+    },
+
+    /// Create demo users with some transactions (development only)
+    Seed,
+
+    /// Create an admin account, or promote an existing user to admin
+    CreateAdmin {
+        /// Email of the admin account
+        email: String,
+
+        /// Password for a new account (a random one is printed if omitted)
+        #[arg(long, env = "ADMIN_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+    },
+
+    /// Check a deployed instance end to end: register, deposit, transfer,
+    /// balances and history (exits non-zero on any failure)
+    Smoketest {
+        /// Base URL of the instance, e.g. https://api.example.com
+        #[arg(long, env = "SMOKETEST_URL")]
+        url: String,
+
+        /// Domain for the throwaway users' email addresses
+        #[arg(long, default_value = "example.com")]
+        email_domain: String,
+
+        /// An admin's token, to delete the throwaway users afterwards
+        #[arg(long, env = "SMOKETEST_ADMIN_TOKEN", hide_env_values = true)]
+        admin_token: Option<String>,
+
+        /// Seconds to wait for each request
+        #[arg(long, default_value_t = 10)]
+        timeout_secs: u64,
+    },
+
+    /// Put a running instance under load: concurrent users logging in,
+    /// reading and transferring, with latency percentiles at the end
+    Loadgen {
+        /// Base URL of the instance (never production)
+        #[arg(long, env = "LOADGEN_URL")]
+        url: String,
+
+        /// Simulated users, each sending requests back to back
+        #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u32).range(1..))]
+        users: u32,
+
+        /// How long to run for
+        #[arg(long, default_value_t = 30)]
+        duration_secs: u64,
+
+        /// Relative weights of the operations
+        #[arg(long, default_value = "login=1,read=6,transfer=3")]
+        mix: Mix,
+
+        /// Domain for the load users' email addresses
+        #[arg(long, default_value = "example.com")]
+        email_domain: String,
+
+        /// Seconds to wait for each request
+        #[arg(long, default_value_t = 10)]
+        timeout_secs: u64,
+
+        /// Seed for the users' choices (same seed, same sequence of requests)
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Subcommand)]
+pub enum MigrateAction {
+    /// Apply all pending migrations (the default)
+    Run,
+
+    /// Roll back the most recent migration
+    Revert,
+}
+
+impl Cli {
+    /// Load the config (honouring `--config`) and apply the flag overrides
+    pub fn load_config(&self) -> Result<Config, AppError> {
+        let mut config = Config::load(self.config.as_deref())?;
+
+        if let Some(port) = self.port {
+            config.server_port = port;
+        }
+        if let Some(log_level) = &self.log_level {
+            config.log_level = log_level.clone();
+        }
+
+        // The overrides haven't been checked yet
+        config.ensure_valid()?;
+        Ok(config)
+    }
+}
+
+// ============================================================================
+// OPERATIONAL COMMANDS
+// ============================================================================
+
+/// `migrate run` / `migrate revert`
+pub async fn migrate(pool: &PgPool, action: MigrateAction) -> Result<(), AppError> {
+    match action {
+        MigrateAction::Run => {
+            MIGRATOR.run(pool).await.map_err(migrate_error)?;
+            println!("✅ Migrations applied");
+        }
+        MigrateAction::Revert => {
+            // (not query_scalar!: the table only exists once sqlx has run)
+            let applied: Option<i64> = sqlx::query_scalar(
+                "SELECT MAX(version) FROM _sqlx_migrations WHERE success",
+            )
+            .fetch_one(pool)
+            .await
+            .map_err(AppError::DatabaseError)?;
+
+            let Some(latest) = applied else {
+                println!("Nothing to revert");
+                return Ok(());
+            };
+
+            // Undo everything above the version before the latest one
+            let target = MIGRATOR
+                .iter()
+                .map(|m| m.version)
+                .filter(|&v| v < latest)
+                .max()
+                .unwrap_or(0);
+            MIGRATOR.undo(pool, target).await.map_err(migrate_error)?;
+            println!("✅ Reverted migration {}", latest);
+        }
+    }
+
+    Ok(())
+}
+
+/// `seed`: demo users with several months of history (see `seeder`)
+pub async fn seed(pool: &PgPool, config: &Config) -> Result<(), AppError> {
+    let summary = seeder::seed(pool, config).await?;
+
+    for email in &summary.skipped {
+        println!("• {} already exists, skipping", email);
+    }
+    for email in &summary.created {
+        println!("• Created {} (password: {})", email, seeder::SEED_PASSWORD);
+    }
+    println!("✅ Seed data created ({} transactions)", summary.transactions);
+    Ok(())
+}
+
+/// `create-admin <email>`
+pub async fn create_admin(
+    pool: &PgPool,
+    config: &Config,
+    email: &str,
+    password: Option<&str>,
+) -> Result<(), AppError> {
+    let created = admin_service::create_admin(
+        pool,
+        &JwtKeys::from_config(config)?,
+        email,
+        password,
+    )
+    .await?;
+
+    match created.generated_password {
+        Some(password) => println!(
+            "✅ Created admin {} with password: {}\n   (shown once, change it after logging in)",
+            created.user.email, password
+        ),
+        None => println!("✅ {} is now an admin", created.user.email),
+    }
+
+    Ok(())
+}
+
+fn migrate_error(e: sqlx::migrate::MigrateError) -> AppError {
+    AppError::internal(&format!("Migration failed: {}", e))
+}
@@ -0,0 +1,309 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::{request::Parts, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use crate::domain::models::{
+    CreateUserRequest, DepositRequest, HistoryQuery, LoginRequest, LoginResponse,
+    TransactionPageResponse, TransferRequest, WalletResponse, WithdrawRequest,
+};
+use crate::error::AppError;
+use crate::middleware::validation::{ValidatedJson, ValidatedQuery};
+use crate::repository::{InMemoryRepository, WalletRepository};
+use crate::seeder::SEED_PASSWORD;
+use crate::services::clock::SystemClock;
+use crate::services::email_service::{EmailService, LogTransport};
+use crate::services::notification_service::{LogNotifier, Notifier};
+use crate::services::wallet_service::{self, ConcurrencyPolicy, SpendingLimits};
+use crate::services::auth_service;
+use crate::utils::jwt::{validate_token, JwtKeys};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+
+// ============================================================================
+// IN-MEMORY DEMO (feature "memory-repo")
+// ============================================================================
+// `cargo run --features memory-repo -- --demo` serves the core JSON API
+// with nothing else running: no Postgres, no SMTP, no config file.
+//
+//   - users, wallets and transactions live in an `InMemoryRepository`
+//     and are gone when the process exits
+//   - emails and live notifications are written to the log
+//   - the JWT secret is random per run
+//   - a few demo users are created on startup (DEMO_USERS, password
+//     seeder::SEED_PASSWORD), with some money and transfers between them
+//
+// Served: /health, /api/register, /api/login, /api/wallet (+ /deposit,
+// /withdraw, /transfer) and /api/transactions, with the same requests and
+// responses as the real server, so `smoketest` and `loadgen` run against
+// it too.
+//
+// The web UI, sessions, admin, banking and the other features read
+// Postgres directly (not through the repository traits), so they aren't
+// part of the demo.
+
+/// (email, full name, opening deposit in cents)
+pub const DEMO_USERS: [(&str, &str, i64); 3] = [
+    ("alice@example.com", "Alice Demo", 250_000),
+    ("bob@example.com", "Bob Demo", 40_000),
+    ("carol@example.com", "Carol Martinez", 1_200_000),
+];
+
+/// Everything the demo handlers share
+#[derive(Clone)]
+pub struct DemoState {
+    pub repo: Arc<InMemoryRepository>,
+    pub jwt_keys: JwtKeys,
+    pub email_service: EmailService,
+    pub notifier: Arc<dyn Notifier>,
+    /// The configured limits' defaults (there is no config here)
+    pub limits: SpendingLimits,
+    /// The in-memory store can't lose a race, so nothing is retried
+    pub concurrency: ConcurrencyPolicy,
+}
+
+impl DemoState {
+    /// An empty store with log-only email and notifications
+    pub fn new() -> Self {
+        Self {
+            repo: Arc::new(InMemoryRepository::new()),
+            jwt_keys: JwtKeys::hmac(&format!("demo-{}", Uuid::new_v4().simple())),
+            email_service: EmailService::new(Arc::new(LogTransport)),
+            notifier: Arc::new(LogNotifier),
+            limits: SpendingLimits {
+                per_transaction: Decimal::new(10_000, 0),
+                daily: Decimal::new(25_000, 0),
+            },
+            concurrency: ConcurrencyPolicy { optimistic_attempts: 0 },
+        }
+    }
+}
+
+impl Default for DemoState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Create DEMO_USERS with their opening balance and a few transfers
+pub async fn seed(state: &DemoState) -> Result<(), AppError> {
+    let mut user_ids = Vec::with_capacity(DEMO_USERS.len());
+    for (email, full_name, cents) in DEMO_USERS {
+        let user = auth_service::register(
+            state.repo.as_ref(),
+            email,
+            SEED_PASSWORD,
+            full_name,
+            &state.jwt_keys,
+            None,
+            &SystemClock,
+        )
+        .await?
+        .user;
+        wallet_service::deposit(
+            state.repo.as_ref(),
+            &state.email_service,
+            state.notifier.as_ref(),
+            user.id,
+            Decimal::new(cents, 2),
+            None,
+        )
+        .await?;
+        user_ids.push(user.id);
+    }
+
+    // (sender, recipient, cents)
+    let transfers = [(0, 1, 4_250), (2, 0, 12_000), (1, 2, 1_599)];
+    for (from, to, cents) in transfers {
+        wallet_service::transfer(
+            state.repo.as_ref(),
+            &state.concurrency,
+            &state.email_service,
+            state.notifier.as_ref(),
+            None,
+            &state.limits,
+            &SystemClock,
+            user_ids[from],
+            DEMO_USERS[to].0,
+            Decimal::new(cents, 2),
+            None,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// The demo's routes
+pub fn router(state: DemoState) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/api/register", post(register))
+        .route("/api/login", post(login_handler))
+        .route("/api/wallet", get(get_wallet))
+        .route("/api/wallet/deposit", post(deposit))
+        .route("/api/wallet/withdraw", post(withdraw))
+        .route("/api/wallet/transfer", post(transfer))
+        .route("/api/transactions", get(get_history))
+        .with_state(state)
+}
+
+/// Seed, then serve the demo on `port` until Ctrl+C (or SIGTERM)
+pub async fn run(port: u16) -> Result<(), Box<dyn std::error::Error>> {
+    let state = DemoState::new();
+    seed(&state).await?;
+
+    let addr = format!("127.0.0.1:{}", port);
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    tracing::info!("🎭 Demo running on http://{} (in memory, nothing is kept)", addr);
+    for (email, _, _) in DEMO_USERS {
+        tracing::info!("   {} / {}", email, SEED_PASSWORD);
+    }
+
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(crate::shutdown::signal())
+        .await?;
+    Ok(())
+}
+
+// ============================================================================
+// HANDLERS
+// ============================================================================
+// Thin copies of handlers/auth.rs and handlers/wallet.rs over `DemoState`.
+
+/// A user authenticated by their `Bearer` token
+struct DemoUser(Uuid);
+
+#[async_trait]
+impl FromRequestParts<DemoState> for DemoUser {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &DemoState,
+    ) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get("Authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(AppError::InvalidToken)?;
+        let claims = validate_token(token, &state.jwt_keys, &SystemClock)?;
+        claims.user_id().map(DemoUser)
+    }
+}
+
+async fn health() -> Json<Value> {
+    Json(json!({ "status": "ok", "database": "in-memory", "environment": "demo" }))
+}
+
+async fn register(
+    State(state): State<DemoState>,
+    ValidatedJson(req): ValidatedJson<CreateUserRequest>,
+) -> Result<(StatusCode, Json<LoginResponse>), AppError> {
+    let response = auth_service::register(
+        state.repo.as_ref(),
+        &req.email,
+        &req.password,
+        &req.full_name,
+        &state.jwt_keys,
+        None,
+        &SystemClock,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+async fn login_handler(
+    State(state): State<DemoState>,
+    ValidatedJson(req): ValidatedJson<LoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let response = auth_service::login(
+        state.repo.as_ref(),
+        &req.email,
+        &req.password,
+        &state.jwt_keys,
+        None,
+        &SystemClock,
+    )
+    .await?;
+    Ok(Json(response))
+}
+
+async fn get_wallet(
+    DemoUser(user_id): DemoUser,
+    State(state): State<DemoState>,
+) -> Result<Json<WalletResponse>, AppError> {
+    let wallet = state.repo.get_wallet_by_user_id(user_id).await?;
+    Ok(Json(WalletResponse::from(wallet)))
+}
+
+async fn deposit(
+    DemoUser(user_id): DemoUser,
+    State(state): State<DemoState>,
+    ValidatedJson(req): ValidatedJson<DepositRequest>,
+) -> Result<Json<WalletResponse>, AppError> {
+    let wallet = wallet_service::deposit(
+        state.repo.as_ref(),
+        &state.email_service,
+        state.notifier.as_ref(),
+        user_id,
+        req.amount,
+        req.currency,
+    )
+    .await?;
+    Ok(Json(WalletResponse::from(wallet)))
+}
+
+async fn withdraw(
+    DemoUser(user_id): DemoUser,
+    State(state): State<DemoState>,
+    ValidatedJson(req): ValidatedJson<WithdrawRequest>,
+) -> Result<Json<WalletResponse>, AppError> {
+    let wallet = wallet_service::withdraw(
+        state.repo.as_ref(),
+        state.notifier.as_ref(),
+        &state.limits,
+        &SystemClock,
+        user_id,
+        req.amount,
+        req.currency,
+    )
+    .await?;
+    Ok(Json(WalletResponse::from(wallet)))
+}
+
+async fn transfer(
+    DemoUser(user_id): DemoUser,
+    State(state): State<DemoState>,
+    ValidatedJson(req): ValidatedJson<TransferRequest>,
+) -> Result<Json<WalletResponse>, AppError> {
+    let wallet = wallet_service::transfer(
+        state.repo.as_ref(),
+        &state.concurrency,
+        &state.email_service,
+        state.notifier.as_ref(),
+        None,
+        &state.limits,
+        &SystemClock,
+        user_id,
+        &req.recipient_email,
+        req.amount,
+        req.currency,
+    )
+    .await?;
+    Ok(Json(WalletResponse::from(wallet)))
+}
+
+async fn get_history(
+    DemoUser(user_id): DemoUser,
+    State(state): State<DemoState>,
+    ValidatedQuery(query): ValidatedQuery<HistoryQuery>,
+) -> Result<Json<TransactionPageResponse>, AppError> {
+    let page = wallet_service::get_history(state.repo.as_ref(), user_id, &query).await?;
+    Ok(Json(TransactionPageResponse::from(page)))
+}
@@ -1 +1,3 @@
+pub mod iban;
+pub mod ids;
 pub mod models;
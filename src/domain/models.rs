@@ -2,6 +2,16 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+// The JSON API's core requests and responses live in the api-types crate,
+// shared with the client; the server's conversions into them stay here.
+pub use api_types::{
+    CreateUserRequest, CreateWalletRequest, Currency, DepositRequest, HistoryQuery, LoginRequest, LoginResponse,
+    TransactionCursor, TransactionPageResponse, TransactionResponse, TransferRequest,
+    UserResponse, WalletResponse, WithdrawRequest, DEFAULT_PAGE_SIZE, MAX_AMOUNT, MAX_PAGE_SIZE,
+    validate_amount, validate_password,
+};
 
 // ============================================================================
 // USER MODEL
@@ -23,39 +33,6 @@ pub struct User {
     pub updated_at: DateTime<Utc>,   // When the account was last updated
 }
 
-// This is what we receive when a user wants to register
-// Notice: NO password_hash, NO id, NO timestamps - those are generated by the system
-#[derive(Debug, Deserialize)]
-pub struct CreateUserRequest {
-    pub email: String,
-    pub password: String,            // Plain password (we'll hash it before storing)
-    pub full_name: String,
-}
-
-// This is what we receive when a user wants to login
-#[derive(Debug, Deserialize)]
-pub struct LoginRequest {
-    pub email: String,
-    pub password: String,
-}
-
-// This is what we send back after successful login
-#[derive(Debug, Serialize)]
-pub struct LoginResponse {
-    pub token: String,               // JWT token for authentication
-    pub user: UserResponse,          // User info (without sensitive data)
-}
-
-// This is a "safe" version of User - without the password hash
-// We send this to the client so they never see the password hash
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UserResponse {
-    pub id: Uuid,
-    pub email: String,
-    pub full_name: String,
-    pub created_at: DateTime<Utc>,
-}
-
 // Convert from User to UserResponse (removes password_hash)
 impl From<User> for UserResponse {
     fn from(user: User) -> Self {
@@ -68,10 +45,88 @@ impl From<User> for UserResponse {
     }
 }
 
+/// What a user is allowed to do (users.role)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum UserRole {
+    User,
+    Admin,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::User => "USER",
+            UserRole::Admin => "ADMIN",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "USER" => Some(UserRole::User),
+            "ADMIN" => Some(UserRole::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// Whether an account may move money (users.account_status)
+///
+/// ```text
+/// ACTIVE <--> FROZEN     (by an admin; frozen accounts can still look)
+///    \
+///     `-----> CLOSED     (can't sign in any more)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AccountStatus {
+    Active,
+    Frozen,
+    Closed,
+}
+
+impl AccountStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountStatus::Active => "ACTIVE",
+            AccountStatus::Frozen => "FROZEN",
+            AccountStatus::Closed => "CLOSED",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ACTIVE" => Some(AccountStatus::Active),
+            "FROZEN" => Some(AccountStatus::Frozen),
+            "CLOSED" => Some(AccountStatus::Closed),
+            _ => None,
+        }
+    }
+}
+
+/// Request to change the language used for error messages
+#[derive(Debug, Deserialize)]
+pub struct UpdateLocaleRequest {
+    pub locale: crate::i18n::Locale,
+}
+
+/// The user's notification preferences (GET and PUT /me/notifications)
+///
+/// ```json
+/// { "login_alerts": true }
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotificationPreferences {
+    /// Email and notify them about sign-ins from a new device
+    pub login_alerts: bool,
+}
+
 // ============================================================================
 // WALLET MODEL
 // ============================================================================
-// This represents a user's wallet. Each user has one wallet to store money.
+// This represents a user's wallet. Each user has one wallet to store money,
+// and so can each organization (business accounts, see
+// organization_service).
 //
 // Why do we need this?
 // - To track how much money each user has
@@ -81,21 +136,14 @@ impl From<User> for UserResponse {
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Wallet {
     pub id: Uuid,                    // Unique identifier
-    pub user_id: Uuid,               // Which user owns this wallet
+    pub user_id: Option<Uuid>,       // Which user owns this wallet...
+    pub organization_id: Option<Uuid>, // ...or which organization
     pub balance: rust_decimal::Decimal, // Current balance (uses Decimal for precision with money)
-    pub currency: String,            // Currency type (USD, EUR, etc.)
+    pub currency: Currency,          // Which currency the balance is in
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-// Response when client asks for wallet info
-#[derive(Debug, Serialize)]
-pub struct WalletResponse {
-    pub id: Uuid,
-    pub balance: rust_decimal::Decimal,
-    pub currency: String,
-}
-
 impl From<Wallet> for WalletResponse {
     fn from(wallet: Wallet) -> Self {
         WalletResponse {
@@ -106,24 +154,21 @@ impl From<Wallet> for WalletResponse {
     }
 }
 
-/// Request to deposit money
-#[derive(Debug, Deserialize)]
-pub struct DepositRequest {
-    pub amount: rust_decimal::Decimal,
-}
-
-/// Request to withdraw money
-#[derive(Debug, Deserialize)]
-pub struct WithdrawRequest {
-    pub amount: rust_decimal::Decimal,
+/// Validator: quantities (of crypto, say) must be strictly positive; money
+/// amounts use `validate_amount`
+fn positive_amount(amount: &rust_decimal::Decimal) -> Result<(), ValidationError> {
+    if *amount <= rust_decimal::Decimal::ZERO {
+        return Err(ValidationError::new("positive"));
+    }
+    Ok(())
 }
 
-/// Request to transfer money
-#[derive(Debug, Deserialize)]
-pub struct TransferRequest {
-    pub recipient_email: String,
-    #[serde(deserialize_with = "deserialize_decimal_from_string")]
-    pub amount: rust_decimal::Decimal,
+/// Validator: text must contain something other than whitespace
+fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(ValidationError::new("not_blank"));
+    }
+    Ok(())
 }
 
 /// Custom deserializer for Decimal from form string
@@ -145,7 +190,7 @@ where
 //
 // Why do we need this?
 // - To keep a record of all money movements
-// - To track transaction status (pending, completed, failed)
+// - To track transaction status (see TransactionStatus)
 // - For audit trails and user transaction history
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -155,38 +200,2413 @@ pub struct Transaction {
     pub transaction_type: String,    // "DEPOSIT", "WITHDRAWAL", or "TRANSFER"
     pub amount: rust_decimal::Decimal,
     pub description: Option<String>, // Optional note about the transaction
-    pub status: String,              // "PENDING", "COMPLETED", or "FAILED"
-    pub created_at: DateTime<Utc>,
+    pub status: String,              // A TransactionStatus, e.g. "COMPLETED"
+    pub created_at: DateTime<Utc>,   // Also when it became PENDING
+    pub completed_at: Option<DateTime<Utc>>,
+    pub failed_at: Option<DateTime<Utc>>,
+    pub reversed_at: Option<DateTime<Utc>>,
+    /// The rate a cross-currency transfer was converted at (recipient
+    /// currency per unit of the sender's); None for everything else
+    pub exchange_rate: Option<rust_decimal::Decimal>,
 }
 
-// Request to create a new transaction
-#[derive(Debug, Deserialize)]
-pub struct CreateTransactionRequest {
-    pub transaction_type: String,    // DEPOSIT, WITHDRAWAL, TRANSFER
+/// Where a transaction is in its lifecycle (`transactions.status`)
+///
+/// ```text
+/// PENDING --> COMPLETED --> REVERSED
+///    \
+///     `----> FAILED
+/// ```
+///
+/// Only COMPLETED transactions have moved money. REVERSED ones did, and
+/// were given back; FAILED ones never did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionStatus {
+    Pending,
+    Completed,
+    Failed,
+    Reversed,
+}
+
+impl TransactionStatus {
+    /// The value stored in the database, e.g. "COMPLETED"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionStatus::Pending => "PENDING",
+            TransactionStatus::Completed => "COMPLETED",
+            TransactionStatus::Failed => "FAILED",
+            TransactionStatus::Reversed => "REVERSED",
+        }
+    }
+
+    /// Parse a stored status (None for anything unknown)
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "PENDING" => Some(TransactionStatus::Pending),
+            "COMPLETED" => Some(TransactionStatus::Completed),
+            "FAILED" => Some(TransactionStatus::Failed),
+            "REVERSED" => Some(TransactionStatus::Reversed),
+            _ => None,
+        }
+    }
+
+    /// Whether a transaction in this state may move to `next`
+    pub fn can_become(&self, next: TransactionStatus) -> bool {
+        matches!(
+            (self, next),
+            (TransactionStatus::Pending, TransactionStatus::Completed)
+                | (TransactionStatus::Pending, TransactionStatus::Failed)
+                | (TransactionStatus::Completed, TransactionStatus::Reversed)
+        )
+    }
+}
+
+impl std::fmt::Display for TransactionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// ============================================================================
+// TRANSACTION PAGINATION
+// ============================================================================
+// History is paged with a KEYSET cursor instead of OFFSET. The cursor is the
+// (created_at, id) of the last transaction on the page, and the next page is
+// "everything older than that". OFFSET would make the database walk past
+// every skipped row, so deep pages of a long history got slower and slower;
+// a keyset query jumps straight there using the index.
+//
+// Clients treat the cursor as an opaque string and pass back `next_cursor`.
+
+impl Transaction {
+    /// The cursor pointing just past this transaction
+    pub fn cursor(&self) -> TransactionCursor {
+        TransactionCursor {
+            created_at: self.created_at,
+            id: self.id,
+        }
+    }
+}
+
+/// One page of a wallet's history, newest first
+#[derive(Debug, Clone)]
+pub struct TransactionPage {
+    pub transactions: Vec<Transaction>,
+    /// Matching transactions in the whole history, not just this page
+    pub total: u64,
+    /// Pass this back to get the next (older) page; None on the last page
+    pub next_cursor: Option<TransactionCursor>,
+}
+
+impl From<TransactionPage> for TransactionPageResponse {
+    fn from(page: TransactionPage) -> Self {
+        TransactionPageResponse {
+            transactions: page
+                .transactions
+                .into_iter()
+                .map(TransactionResponse::from)
+                .collect(),
+            total: page.total,
+            next_cursor: page.next_cursor,
+        }
+    }
+}
+
+// ============================================================================
+// TRANSACTION FILTERS
+// ============================================================================
+// History can be narrowed down by type, status, dates, amount and words in
+// the description. The filters go into the SQL next to the cursor, so a
+// page still holds `limit` matching transactions and `total` counts only
+// those; the text filter uses the same full-text index as search.
+
+/// Which of a wallet's transactions to list (everything by default)
+#[derive(Debug, Clone, Default)]
+pub struct TransactionFilter {
+    /// "DEPOSIT", "WITHDRAWAL" or "TRANSFER"
+    pub transaction_type: Option<String>,
+    pub status: Option<TransactionStatus>,
+    /// Made at or after this
+    pub from: Option<DateTime<Utc>>,
+    /// Made before this
+    pub until: Option<DateTime<Utc>>,
+    pub min_amount: Option<rust_decimal::Decimal>,
+    pub max_amount: Option<rust_decimal::Decimal>,
+    /// Web-search style words to find in the description
+    pub text: Option<String>,
+}
+
+impl TryFrom<&HistoryQuery> for TransactionFilter {
+    type Error = String;
+
+    fn try_from(query: &HistoryQuery) -> Result<Self, Self::Error> {
+        if let (Some(from), Some(to)) = (query.from, query.to) {
+            if from > to {
+                return Err("`from` cannot be after `to`".to_string());
+            }
+        }
+        if let (Some(min), Some(max)) = (query.min_amount, query.max_amount) {
+            if min > max {
+                return Err("`min_amount` cannot be more than `max_amount`".to_string());
+            }
+        }
+
+        let start_of_day = |day: chrono::NaiveDate| day.and_time(chrono::NaiveTime::MIN).and_utc();
+        Ok(TransactionFilter {
+            transaction_type: query.transaction_type.clone(),
+            status: match query.status.as_deref() {
+                Some(status) => Some(
+                    TransactionStatus::parse(status).ok_or_else(|| format!("Unknown status {}", status))?,
+                ),
+                None => None,
+            },
+            from: query.from.map(start_of_day),
+            // `to` is inclusive: up to the start of the next day
+            until: query
+                .to
+                .and_then(|to| to.succ_opt())
+                .map(start_of_day),
+            min_amount: query.min_amount,
+            max_amount: query.max_amount,
+            text: query
+                .q
+                .as_deref()
+                .map(str::trim)
+                .filter(|q| !q.is_empty())
+                .map(str::to_string),
+        })
+    }
+}
+
+// ============================================================================
+// TRANSACTION SEARCH
+// ============================================================================
+
+/// Query string for search endpoints: `?q=coffee&limit=20`
+#[derive(Debug, Deserialize, Validate)]
+pub struct SearchQuery {
+    /// Search words; quotes, `or` and `-word` work like a web search
+    #[validate(length(min = 1, max = 200, message = "must be between 1 and 200 characters"))]
+    pub q: String,
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    pub limit: Option<u32>,
+}
+
+/// One search result: the transaction plus how well it matched
+#[derive(Debug, Serialize)]
+pub struct TransactionSearchResult {
+    #[serde(flatten)]
+    pub transaction: TransactionResponse,
+    /// Higher is more relevant; results come sorted by it
+    pub rank: f32,
+}
+
+// ============================================================================
+// MONTHLY STATEMENTS
+// ============================================================================
+// Per-month totals from the `monthly_wallet_totals` view, which is refreshed
+// nightly: cheap to read, but the current month can be up to a day behind.
+
+/// Query string for GET /wallet/statements: `?months=12`
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct StatementQuery {
+    /// How many months back, including the current one (default 12)
+    #[validate(range(min = 1, max = 120, message = "must be between 1 and 120"))]
+    pub months: Option<u32>,
+}
+
+/// One month on a statement
+#[derive(Debug, Serialize)]
+pub struct MonthlyStatement {
+    /// First day of the (UTC) month
+    pub month: chrono::NaiveDate,
+    pub opening_balance: rust_decimal::Decimal,
+    pub money_in: rust_decimal::Decimal,
+    pub money_out: rust_decimal::Decimal,
+    pub closing_balance: rust_decimal::Decimal,
+    pub transaction_count: i64,
+    /// Crypto trades this month, whose USD side is in `money_in`/`money_out`
+    pub crypto: Vec<CryptoStatementLine>,
+}
+
+/// What GET /wallet/statements returns
+#[derive(Debug, Serialize)]
+pub struct MonthlyStatementsResponse {
+    pub currency: Currency,
+    /// Oldest first; months without transactions are left out
+    pub months: Vec<MonthlyStatement>,
+    /// When the totals were computed (None if there are no months yet)
+    pub refreshed_at: Option<DateTime<Utc>>,
+}
+
+// ============================================================================
+// TAX SUMMARY
+// ============================================================================
+// A user's figures for one (UTC) calendar year, for their tax return:
+// interest earned, fees paid and how much they moved in transfers.
+
+/// How GET /tax-summary/:year answers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaxSummaryFormat {
+    #[default]
+    Json,
+    Csv,
+    Pdf,
+}
+
+/// Query string for GET /tax-summary/:year: `?format=csv` (default json)
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct TaxSummaryQuery {
+    pub format: Option<TaxSummaryFormat>,
+    /// Answer CSV/PDF with a `DownloadLink` instead of the file
+    pub link: Option<bool>,
+}
+
+/// One year's tax figures
+#[derive(Debug, Serialize)]
+pub struct TaxSummary {
+    pub year: i32,
+    pub full_name: String,
+    pub email: String,
+    pub currency: Currency,
+    pub interest_earned: rust_decimal::Decimal,
+    pub fees_paid: rust_decimal::Decimal,
+    pub transfers_received: rust_decimal::Decimal,
+    pub transfers_sent: rust_decimal::Decimal,
+    /// Received plus sent
+    pub transfer_volume: rust_decimal::Decimal,
+    pub transfer_count: i64,
+    /// False for the current year, whose figures can still change
+    pub complete: bool,
+    pub generated_at: DateTime<Utc>,
+}
+
+// ============================================================================
+// ACCOUNTING EXPORT
+// ============================================================================
+// Wallet activity as double-entry journal entries for the user's own
+// books: every COMPLETED transaction becomes one balanced entry between the
+// wallet's account and an account for its kind (deposit, fee, ...), using
+// the user's account codes.
+
+/// What kind of entry a transaction makes (from `transaction_category`,
+/// migration 023)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum JournalCategory {
+    Deposit,
+    Withdrawal,
+    Interest,
+    Fee,
+    TransferIn,
+    TransferOut,
+}
+
+impl JournalCategory {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "DEPOSIT" => Some(JournalCategory::Deposit),
+            "WITHDRAWAL" => Some(JournalCategory::Withdrawal),
+            "INTEREST" => Some(JournalCategory::Interest),
+            "FEE" => Some(JournalCategory::Fee),
+            "TRANSFER_IN" => Some(JournalCategory::TransferIn),
+            "TRANSFER_OUT" => Some(JournalCategory::TransferOut),
+            _ => None,
+        }
+    }
+
+    /// Money coming into the wallet (debits the wallet's account)
+    pub fn is_credit(&self) -> bool {
+        matches!(
+            self,
+            JournalCategory::Deposit | JournalCategory::Interest | JournalCategory::TransferIn
+        )
+    }
+}
+
+/// The account code used for each side of the journal entries
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AccountCodes {
+    /// The wallet itself (a bank account in the books)
+    pub wallet: String,
+    pub deposit: String,
+    pub withdrawal: String,
+    pub interest: String,
+    pub fee: String,
+    pub transfer_in: String,
+    pub transfer_out: String,
+}
+
+impl AccountCodes {
+    /// The account on the other side of the wallet for `category`
+    pub fn for_category(&self, category: JournalCategory) -> &str {
+        match category {
+            JournalCategory::Deposit => &self.deposit,
+            JournalCategory::Withdrawal => &self.withdrawal,
+            JournalCategory::Interest => &self.interest,
+            JournalCategory::Fee => &self.fee,
+            JournalCategory::TransferIn => &self.transfer_in,
+            JournalCategory::TransferOut => &self.transfer_out,
+        }
+    }
+}
+
+impl Default for AccountCodes {
+    /// Codes from Xero's standard chart of accounts
+    fn default() -> Self {
+        AccountCodes {
+            wallet: "090".to_string(),
+            deposit: "260".to_string(),
+            withdrawal: "429".to_string(),
+            interest: "270".to_string(),
+            fee: "404".to_string(),
+            transfer_in: "877".to_string(),
+            transfer_out: "877".to_string(),
+        }
+    }
+}
+
+/// A user's own account codes; None keeps the server's default
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct AccountCodeOverrides {
+    #[validate(length(min = 1, max = 32, message = "must be 1-32 characters"))]
+    pub wallet: Option<String>,
+    #[validate(length(min = 1, max = 32, message = "must be 1-32 characters"))]
+    pub deposit: Option<String>,
+    #[validate(length(min = 1, max = 32, message = "must be 1-32 characters"))]
+    pub withdrawal: Option<String>,
+    #[validate(length(min = 1, max = 32, message = "must be 1-32 characters"))]
+    pub interest: Option<String>,
+    #[validate(length(min = 1, max = 32, message = "must be 1-32 characters"))]
+    pub fee: Option<String>,
+    #[validate(length(min = 1, max = 32, message = "must be 1-32 characters"))]
+    pub transfer_in: Option<String>,
+    #[validate(length(min = 1, max = 32, message = "must be 1-32 characters"))]
+    pub transfer_out: Option<String>,
+}
+
+/// What GET/PUT /accounting/account-codes return
+#[derive(Debug, Serialize)]
+pub struct AccountCodesResponse {
+    /// The codes exports use: the user's own where set, otherwise the default
+    pub account_codes: AccountCodes,
+    /// The codes the user set themselves
+    pub overrides: AccountCodeOverrides,
+    pub defaults: AccountCodes,
+}
+
+/// How GET /accounting/journal answers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalFormat {
+    #[default]
+    Json,
+    /// Xero manual journal import (CSV)
+    Xero,
+    /// QuickBooks Online journal entry import (CSV)
+    Quickbooks,
+}
+
+/// Query string for GET /accounting/journal
+#[derive(Debug, Deserialize, Validate)]
+pub struct JournalQuery {
+    /// First day to export (UTC, inclusive)
+    pub from: chrono::NaiveDate,
+    /// Last day to export (UTC, inclusive)
+    pub to: chrono::NaiveDate,
+    pub format: Option<JournalFormat>,
+    /// Answer CSV formats with a `DownloadLink` instead of the file
+    pub link: Option<bool>,
+}
+
+/// One side of a journal entry; exactly one of debit/credit is non-zero
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalLine {
+    pub account_code: String,
+    pub debit: rust_decimal::Decimal,
+    pub credit: rust_decimal::Decimal,
+}
+
+/// One transaction as a balanced journal entry
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntry {
+    /// Short, stable reference for the books, e.g. "FA-8c2e41d90b7f"
+    pub reference: String,
+    pub transaction_id: Uuid,
+    pub date: chrono::NaiveDate,
+    pub category: JournalCategory,
+    pub description: String,
+    pub lines: Vec<JournalLine>,
+}
+
+/// What GET /accounting/journal returns as JSON
+#[derive(Debug, Serialize)]
+pub struct JournalResponse {
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+    pub currency: Currency,
+    /// Oldest first
+    pub entries: Vec<JournalEntry>,
+}
+
+// ============================================================================
+// LINKED BANK ACCOUNTS
+// ============================================================================
+// External bank accounts linked through the bank provider (Plaid), used as
+// funding sources for deposits and as destinations for payouts.
+//
+// Linking is two steps: POST /bank/link-token gives the client a token for
+// the provider's Link widget, and the public token Link returns is posted
+// to /bank/accounts. Access tokens never leave the server.
+
+/// What POST /bank/link-token returns
+#[derive(Debug, Serialize)]
+pub struct LinkTokenResponse {
+    pub link_token: String,
+    pub expiration: DateTime<Utc>,
+}
+
+/// Request to link the accounts the user picked in Link
+#[derive(Debug, Deserialize, Validate)]
+pub struct LinkBankAccountsRequest {
+    #[validate(length(min = 1, max = 500, message = "must be 1-500 characters"))]
+    pub public_token: String,
+}
+
+/// A linked bank account, as shown to its owner
+#[derive(Debug, Serialize)]
+pub struct LinkedBankAccountResponse {
+    pub id: Uuid,
+    pub institution_name: Option<String>,
+    pub account_name: String,
+    /// Last digits of the account number
+    pub mask: Option<String>,
+    pub subtype: Option<String>,
+    pub linked_at: DateTime<Utc>,
+}
+
+/// Request to deposit money from a linked bank account
+#[derive(Debug, Deserialize, Validate)]
+pub struct BankDepositRequest {
+    pub bank_account_id: Uuid,
+    #[validate(custom(function = "validate_amount"))]
     pub amount: rust_decimal::Decimal,
-    pub description: Option<String>,
 }
 
-// Response with transaction details
+/// Request to pay money out to a linked bank account
+#[derive(Debug, Deserialize, Validate)]
+pub struct BankPayoutRequest {
+    pub bank_account_id: Uuid,
+    #[validate(custom(function = "validate_amount"))]
+    pub amount: rust_decimal::Decimal,
+}
+
+/// Where a payout to a bank account is
+///
+/// ```text
+/// PENDING --> SETTLED
+///    |
+///    +------> RETURNED   (the bank sent it back; the withdrawal is reversed)
+///    +------> FAILED     (it never left; the withdrawal is reversed)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayoutStatus {
+    Pending,
+    Settled,
+    Returned,
+    Failed,
+}
+
+impl PayoutStatus {
+    /// The value stored in the database, e.g. "SETTLED"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PayoutStatus::Pending => "PENDING",
+            PayoutStatus::Settled => "SETTLED",
+            PayoutStatus::Returned => "RETURNED",
+            PayoutStatus::Failed => "FAILED",
+        }
+    }
+}
+
+/// A payout to a bank account, as shown to its owner
 #[derive(Debug, Serialize)]
-pub struct TransactionResponse {
+pub struct BankPayoutResponse {
     pub id: Uuid,
-    pub transaction_type: String,
+    pub bank_account_id: Uuid,
+    /// The WITHDRAWAL that took the money from the wallet
+    pub transaction_id: Uuid,
     pub amount: rust_decimal::Decimal,
-    pub description: Option<String>,
     pub status: String,
+    pub return_reason: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub settled_at: Option<DateTime<Utc>>,
+    pub returned_at: Option<DateTime<Utc>>,
+    pub failed_at: Option<DateTime<Utc>>,
 }
 
-impl From<Transaction> for TransactionResponse {
-    fn from(tx: Transaction) -> Self {
-        TransactionResponse {
-            id: tx.id,
-            transaction_type: tx.transaction_type,
-            amount: tx.amount,
-            description: tx.description,
-            status: tx.status,
-            created_at: tx.created_at,
+// ============================================================================
+// INTERNATIONAL TRANSFERS
+// ============================================================================
+// Payments to a bank account abroad, named by IBAN and BIC. The wallet is
+// debited in its own currency (amount plus the correspondent fee) and the
+// beneficiary receives the amount converted at our rate: the mid-market
+// rate less INTL_FX_MARGIN_BPS.
+
+/// Request for what an international transfer would cost
+///
+/// ```json
+/// { "amount": "500.00", "destination_currency": "EUR" }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct InternationalQuoteRequest {
+    /// What leaves the wallet before the fee, in the wallet's currency
+    #[serde(deserialize_with = "deserialize_decimal_from_string")]
+    #[validate(custom(function = "validate_amount"))]
+    pub amount: rust_decimal::Decimal,
+    pub destination_currency: Currency,
+}
+
+/// Request to send money to a bank account abroad
+///
+/// ```json
+/// {
+///   "beneficiary_name": "Erika Mustermann",
+///   "iban": "DE89 3704 0044 0532 0130 00",
+///   "bic": "COBADEFFXXX",
+///   "amount": "500.00",
+///   "destination_currency": "EUR",
+///   "reference": "Invoice 2026-114"
+/// }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct InternationalTransferRequest {
+    #[validate(
+        length(min = 1, max = 140, message = "must be between 1 and 140 characters"),
+        custom(function = "not_blank", message = "cannot be empty")
+    )]
+    pub beneficiary_name: String,
+    #[validate(custom(function = "valid_iban"))]
+    pub iban: String,
+    #[validate(custom(function = "valid_bic"))]
+    pub bic: String,
+    /// What leaves the wallet before the fee, in the wallet's currency
+    #[serde(deserialize_with = "deserialize_decimal_from_string")]
+    #[validate(custom(function = "validate_amount"))]
+    pub amount: rust_decimal::Decimal,
+    pub destination_currency: Currency,
+    /// Shown to the beneficiary on their statement
+    #[serde(default)]
+    #[validate(length(max = 140, message = "must be at most 140 characters"))]
+    pub reference: Option<String>,
+}
+
+/// Validator: an IBAN with the right length and check digits
+fn valid_iban(iban: &str) -> Result<(), ValidationError> {
+    crate::domain::iban::Iban::parse(iban).map(|_| ()).map_err(|message| {
+        let mut error = ValidationError::new("iban");
+        error.message = Some(message.into());
+        error
+    })
+}
+
+/// Validator: a well-formed BIC
+fn valid_bic(bic: &str) -> Result<(), ValidationError> {
+    crate::domain::iban::Bic::parse(bic).map(|_| ()).map_err(|message| {
+        let mut error = ValidationError::new("bic");
+        error.message = Some(message.into());
+        error
+    })
+}
+
+/// What an international transfer costs, shown before it is sent
+///
+/// The rate is only fixed when the transfer is submitted; the transfer's
+/// own response has the figures that were actually used.
+#[derive(Debug, Serialize)]
+pub struct InternationalQuoteResponse {
+    /// The wallet's currency
+    pub source_currency: Currency,
+    pub amount: rust_decimal::Decimal,
+    /// Charged by the correspondent banks along the way, in the wallet's
+    /// currency
+    pub correspondent_fee: rust_decimal::Decimal,
+    /// `amount` + `correspondent_fee`: what leaves the wallet
+    pub total_debit: rust_decimal::Decimal,
+    pub destination_currency: Currency,
+    /// The mid-market rate
+    pub mid_rate: rust_decimal::Decimal,
+    /// The rate the transfer is converted at (mid-market less our margin)
+    pub rate: rust_decimal::Decimal,
+    /// Our margin on the mid-market rate, in percent (e.g. "0.50")
+    pub fx_margin_percent: rust_decimal::Decimal,
+    /// What the beneficiary receives
+    pub destination_amount: rust_decimal::Decimal,
+    pub rate_fetched_at: DateTime<Utc>,
+}
+
+/// Where an international transfer is
+///
+/// ```text
+/// SUBMITTED --> IN_TRANSIT --> SETTLED
+///     |             |
+///     +-------------+------> RETURNED   (sent back; the withdrawal is reversed)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum InternationalTransferStatus {
+    /// Debited from the wallet, waiting to go out
+    Submitted,
+    /// Sent to the correspondent bank
+    InTransit,
+    /// Credited to the beneficiary's account
+    Settled,
+    Returned,
+}
+
+impl InternationalTransferStatus {
+    /// The value stored in the database, e.g. "IN_TRANSIT"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InternationalTransferStatus::Submitted => "SUBMITTED",
+            InternationalTransferStatus::InTransit => "IN_TRANSIT",
+            InternationalTransferStatus::Settled => "SETTLED",
+            InternationalTransferStatus::Returned => "RETURNED",
+        }
+    }
+
+    /// Parse a stored status (None for anything unknown)
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "SUBMITTED" => Some(InternationalTransferStatus::Submitted),
+            "IN_TRANSIT" => Some(InternationalTransferStatus::InTransit),
+            "SETTLED" => Some(InternationalTransferStatus::Settled),
+            "RETURNED" => Some(InternationalTransferStatus::Returned),
+            _ => None,
+        }
+    }
+
+    /// Whether a transfer may move from this status to `next`
+    pub fn can_become(&self, next: InternationalTransferStatus) -> bool {
+        use InternationalTransferStatus::*;
+        matches!(
+            (self, next),
+            (Submitted, InTransit) | (InTransit, Settled) | (Submitted | InTransit, Returned)
+        )
+    }
+
+    /// e.g. "In transit"
+    pub fn label(&self) -> &'static str {
+        match self {
+            InternationalTransferStatus::Submitted => "Submitted",
+            InternationalTransferStatus::InTransit => "In transit",
+            InternationalTransferStatus::Settled => "Settled",
+            InternationalTransferStatus::Returned => "Returned",
         }
     }
 }
+
+/// An international transfer, as shown to its sender
+#[derive(Debug, Serialize)]
+pub struct InternationalTransferResponse {
+    pub id: Uuid,
+    /// The WITHDRAWAL that took `total_debit` from the wallet
+    pub transaction_id: Uuid,
+    pub beneficiary_name: String,
+    /// Masked, e.g. "DE89 •••• 3000"
+    pub iban: String,
+    pub bic: String,
+    pub reference: Option<String>,
+    pub source_currency: Currency,
+    pub amount: rust_decimal::Decimal,
+    pub correspondent_fee: rust_decimal::Decimal,
+    pub total_debit: rust_decimal::Decimal,
+    pub destination_currency: Currency,
+    pub mid_rate: rust_decimal::Decimal,
+    pub rate: rust_decimal::Decimal,
+    pub destination_amount: rust_decimal::Decimal,
+    pub status: InternationalTransferStatus,
+    /// The payment's reference in the correspondent network (e.g. a SWIFT
+    /// UETR), once it is in transit
+    pub tracking_reference: Option<String>,
+    pub return_reason: Option<String>,
+    pub submitted_at: DateTime<Utc>,
+    pub in_transit_at: Option<DateTime<Utc>>,
+    pub settled_at: Option<DateTime<Utc>>,
+    pub returned_at: Option<DateTime<Utc>>,
+}
+
+/// Request (ops) to move an international transfer along
+///
+/// ```json
+/// { "status": "IN_TRANSIT", "tracking_reference": "b2c1f0e4-..." }
+/// { "status": "RETURNED", "reason": "Beneficiary account closed" }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct InternationalTransferStatusRequest {
+    pub status: InternationalTransferStatus,
+    #[serde(default)]
+    #[validate(length(min = 1, max = 100, message = "must be between 1 and 100 characters"))]
+    pub tracking_reference: Option<String>,
+    /// Why it was returned
+    #[serde(default)]
+    #[validate(length(min = 1, max = 500, message = "must be between 1 and 500 characters"))]
+    pub reason: Option<String>,
+}
+
+/// Filter for the ops queue of international transfers
+#[derive(Debug, Deserialize, Validate)]
+pub struct InternationalTransferQueueQuery {
+    /// Defaults to SUBMITTED
+    pub status: Option<InternationalTransferStatus>,
+}
+
+// ============================================================================
+// SCHEDULED TRANSFERS
+// ============================================================================
+// Transfers to another user that run at a time the sender picks. A
+// background task runs them when they fall due; one that fails (e.g. not
+// enough money yet) is retried a few times before it is given up.
+
+/// Request to transfer money later
+///
+/// ```json
+/// {
+///   "recipient_email": "bob@example.com",
+///   "amount": "25.00",
+///   "execute_at": "2026-11-01T09:00:00Z"
+/// }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct ScheduleTransferRequest {
+    #[validate(email(message = "must be a valid email address"))]
+    pub recipient_email: String,
+    #[serde(deserialize_with = "deserialize_decimal_from_string")]
+    #[validate(custom(function = "validate_amount"))]
+    pub amount: rust_decimal::Decimal,
+    /// Which wallet pays, as for transfers (default: the primary one)
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    /// When to send it (must be in the future)
+    pub execute_at: DateTime<Utc>,
+}
+
+/// Where a scheduled transfer is
+///
+/// ```text
+/// SCHEDULED --> COMPLETED
+///     |
+///     +------> FAILED      (every attempt failed)
+///     +------> CANCELLED   (by the sender, before it ran)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ScheduledTransferStatus {
+    /// Waiting for its time (or for another attempt)
+    Scheduled,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl ScheduledTransferStatus {
+    /// The value stored in the database, e.g. "SCHEDULED"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScheduledTransferStatus::Scheduled => "SCHEDULED",
+            ScheduledTransferStatus::Completed => "COMPLETED",
+            ScheduledTransferStatus::Failed => "FAILED",
+            ScheduledTransferStatus::Cancelled => "CANCELLED",
+        }
+    }
+
+    /// Parse a stored status (None for anything unknown)
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "SCHEDULED" => Some(ScheduledTransferStatus::Scheduled),
+            "COMPLETED" => Some(ScheduledTransferStatus::Completed),
+            "FAILED" => Some(ScheduledTransferStatus::Failed),
+            "CANCELLED" => Some(ScheduledTransferStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+/// A scheduled transfer, as shown to its sender
+#[derive(Debug, Serialize)]
+pub struct ScheduledTransferResponse {
+    pub id: Uuid,
+    pub recipient_email: String,
+    pub amount: rust_decimal::Decimal,
+    pub currency: Option<Currency>,
+    pub execute_at: DateTime<Utc>,
+    pub status: ScheduledTransferStatus,
+    /// Failed attempts so far
+    pub attempts: i32,
+    /// When it will be tried again, while it is SCHEDULED
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    /// Why the last attempt failed
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+// ============================================================================
+// TRANSFER CONFIRMATION
+// ============================================================================
+// Transfers above LIMIT_CONFIRM_ABOVE wait for the sender to enter a code
+// we email them. The money is held (taken from the sender, not yet given
+// to the recipient) until then; cancelling or letting the code expire
+// gives it back.
+
+/// Request to confirm a pending transfer
+///
+/// ```json
+/// { "code": "123456" }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct ConfirmTransferRequest {
+    #[validate(length(equal = 6, message = "must be the 6-digit code from the email"))]
+    pub code: String,
+}
+
+/// Where a transfer waiting for confirmation is
+///
+/// ```text
+/// PENDING --> CONFIRMED   (the recipient has the money)
+///    |
+///    +------> CANCELLED   (by the sender, or too many wrong codes; refunded)
+///    +------> EXPIRED     (not confirmed in time; refunded)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PendingTransferStatus {
+    Pending,
+    Confirmed,
+    Cancelled,
+    Expired,
+}
+
+impl PendingTransferStatus {
+    /// The value stored in the database, e.g. "PENDING"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PendingTransferStatus::Pending => "PENDING",
+            PendingTransferStatus::Confirmed => "CONFIRMED",
+            PendingTransferStatus::Cancelled => "CANCELLED",
+            PendingTransferStatus::Expired => "EXPIRED",
+        }
+    }
+
+    /// Parse a stored status (None for anything unknown)
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "PENDING" => Some(PendingTransferStatus::Pending),
+            "CONFIRMED" => Some(PendingTransferStatus::Confirmed),
+            "CANCELLED" => Some(PendingTransferStatus::Cancelled),
+            "EXPIRED" => Some(PendingTransferStatus::Expired),
+            _ => None,
+        }
+    }
+}
+
+/// A transfer waiting for confirmation, as shown to its sender
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct PendingTransferResponse {
+    pub id: Uuid,
+    pub recipient_email: String,
+    /// Taken from the sender, in their wallet's currency
+    pub amount: rust_decimal::Decimal,
+    pub currency: Currency,
+    pub status: PendingTransferStatus,
+    /// The code stops working then, and the money goes back
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+// ============================================================================
+// PAYMENT REQUESTS
+// ============================================================================
+// One user asking another for money. The payer sees it in their incoming
+// requests and approves it (an ordinary transfer to the requester) or
+// declines it.
+
+/// Request to ask another user for money
+///
+/// ```json
+/// {
+///   "payer_email": "bob@example.com",
+///   "amount": "12.50",
+///   "note": "Pizza on Friday"
+/// }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreatePaymentRequest {
+    #[validate(email(message = "must be a valid email address"))]
+    pub payer_email: String,
+    #[serde(deserialize_with = "deserialize_decimal_from_string")]
+    #[validate(custom(function = "validate_amount"))]
+    pub amount: rust_decimal::Decimal,
+    /// Which of the payer's wallets pays, as for transfers (default: their
+    /// primary one)
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    #[serde(default)]
+    #[validate(length(max = 140, message = "must be at most 140 characters"))]
+    pub note: Option<String>,
+}
+
+/// Where a payment request is
+///
+/// ```text
+/// PENDING --> PAID       (the payer approved; the money was sent)
+///    |
+///    +------> DECLINED   (by the payer)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PaymentRequestStatus {
+    Pending,
+    Paid,
+    Declined,
+}
+
+impl PaymentRequestStatus {
+    /// The value stored in the database, e.g. "PENDING"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentRequestStatus::Pending => "PENDING",
+            PaymentRequestStatus::Paid => "PAID",
+            PaymentRequestStatus::Declined => "DECLINED",
+        }
+    }
+
+    /// Parse a stored status (None for anything unknown)
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "PENDING" => Some(PaymentRequestStatus::Pending),
+            "PAID" => Some(PaymentRequestStatus::Paid),
+            "DECLINED" => Some(PaymentRequestStatus::Declined),
+            _ => None,
+        }
+    }
+}
+
+/// A payment request, as shown to either side
+#[derive(Debug, Serialize)]
+pub struct PaymentRequestResponse {
+    pub id: Uuid,
+    /// Who asked for the money (and gets it)
+    pub requester_email: String,
+    /// Who was asked to pay
+    pub payer_email: String,
+    pub amount: rust_decimal::Decimal,
+    pub currency: Option<Currency>,
+    pub note: Option<String>,
+    pub status: PaymentRequestStatus,
+    pub created_at: DateTime<Utc>,
+    /// When the payer approved or declined it
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+// ============================================================================
+// SPENDING LIMITS
+// ============================================================================
+// Every withdrawal and transfer is held to two limits: the most one may
+// move, and the most a wallet may pay out in a day (UTC). Both are
+// configured (LIMIT_PER_TRANSACTION, LIMIT_DAILY); users may set lower
+// ones for themselves. Amounts are in the paying wallet's currency.
+
+/// Request to change the user's own limits
+///
+/// ```json
+/// { "per_transaction": "500.00", "daily": "1000.00" }
+/// ```
+/// A missing or null limit goes back to the configured one.
+#[derive(Debug, Deserialize, Validate)]
+pub struct SpendingLimitsRequest {
+    #[serde(default)]
+    #[validate(custom(function = "validate_amount"))]
+    pub per_transaction: Option<rust_decimal::Decimal>,
+    #[serde(default)]
+    #[validate(custom(function = "validate_amount"))]
+    pub daily: Option<rust_decimal::Decimal>,
+}
+
+/// The limits that apply to the user, and how high they could set them
+#[derive(Debug, Serialize)]
+pub struct SpendingLimitsResponse {
+    pub per_transaction: rust_decimal::Decimal,
+    pub daily: rust_decimal::Decimal,
+    pub max_per_transaction: rust_decimal::Decimal,
+    pub max_daily: rust_decimal::Decimal,
+}
+
+// ============================================================================
+// CARD TOP-UPS
+// ============================================================================
+// Wallet top-ups with Apple Pay and Google Pay. The browser gets an
+// encrypted payment token from the device wallet and posts it here; the
+// card processor decrypts and charges it, so card numbers never reach
+// this server.
+
+/// The device wallet a payment token came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WalletPayMethod {
+    ApplePay,
+    GooglePay,
+}
+
+impl WalletPayMethod {
+    /// The value stored in the database, e.g. "APPLE_PAY"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WalletPayMethod::ApplePay => "APPLE_PAY",
+            WalletPayMethod::GooglePay => "GOOGLE_PAY",
+        }
+    }
+
+    /// Parse a stored method (None for anything unknown)
+    pub fn parse(method: &str) -> Option<Self> {
+        match method {
+            "APPLE_PAY" => Some(WalletPayMethod::ApplePay),
+            "GOOGLE_PAY" => Some(WalletPayMethod::GooglePay),
+            _ => None,
+        }
+    }
+
+    /// The name shown to users, e.g. "Apple Pay"
+    pub fn label(&self) -> &'static str {
+        match self {
+            WalletPayMethod::ApplePay => "Apple Pay",
+            WalletPayMethod::GooglePay => "Google Pay",
+        }
+    }
+}
+
+/// Request to top up the wallet with an Apple Pay or Google Pay token
+///
+/// ```json
+/// { "method": "APPLE_PAY", "amount": "50.00", "payment_data": { "version": "EC_v1", ... } }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct CardDepositRequest {
+    pub method: WalletPayMethod,
+    #[validate(custom(function = "validate_amount"))]
+    pub amount: rust_decimal::Decimal,
+    /// Apple Pay: `payment.token.paymentData`. Google Pay: the parsed
+    /// `paymentMethodData.tokenizationData.token`.
+    pub payment_data: serde_json::Value,
+    /// Keep the card for later top-ups (see `SavedCardResponse`)
+    #[serde(default)]
+    pub save_card: bool,
+}
+
+/// Request to top up the wallet from a saved card
+///
+/// ```json
+/// { "amount": "50.00", "card_id": "..." }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct SavedCardDepositRequest {
+    #[validate(custom(function = "validate_amount"))]
+    pub amount: rust_decimal::Decimal,
+    /// Which card; the default funding source when left out
+    pub card_id: Option<Uuid>,
+}
+
+/// A saved card, as shown to its owner
+///
+/// Only the masked details: the card number never reaches this server, and
+/// the processor's token for it is never sent out.
+#[derive(Debug, Serialize)]
+pub struct SavedCardResponse {
+    pub id: Uuid,
+    pub method: WalletPayMethod,
+    /// e.g. "Visa"
+    pub brand: Option<String>,
+    pub last4: String,
+    pub expiry_month: i16,
+    pub expiry_year: i16,
+    /// Charged when a top-up doesn't name a card
+    pub is_default: bool,
+    pub saved_at: DateTime<Utc>,
+}
+
+/// What the deposit page needs to offer Apple Pay and Google Pay
+///
+/// A wallet that isn't configured is null and its button isn't shown.
+#[derive(Debug, Serialize)]
+pub struct CardPayConfigResponse {
+    pub apple_pay: Option<ApplePayConfig>,
+    pub google_pay: Option<GooglePayConfig>,
+    /// The wallet's currency; top-ups are charged in it
+    pub currency: Currency,
+}
+
+/// Settings for an Apple Pay JS payment request
+#[derive(Debug, Serialize)]
+pub struct ApplePayConfig {
+    pub merchant_id: String,
+    pub display_name: String,
+    pub supported_networks: Vec<&'static str>,
+}
+
+/// Settings for a Google Pay API payment request
+#[derive(Debug, Serialize)]
+pub struct GooglePayConfig {
+    /// "TEST" or "PRODUCTION"
+    pub environment: &'static str,
+    pub merchant_id: Option<String>,
+    /// The tokenization gateway, e.g. "checkoutltd"
+    pub gateway: &'static str,
+    pub gateway_merchant_id: String,
+    pub allowed_card_networks: Vec<&'static str>,
+}
+
+/// Apple Pay JS's `onvalidatemerchant` URL, for a merchant session
+#[derive(Debug, Deserialize, Validate)]
+pub struct ApplePaySessionRequest {
+    #[validate(length(min = 1, max = 500, message = "must be 1-500 characters"))]
+    pub validation_url: String,
+}
+
+// ============================================================================
+// EXCHANGE RATES
+// ============================================================================
+
+/// Query string for GET /rates: `?base=EUR` (default USD)
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct RatesQuery {
+    pub base: Option<Currency>,
+}
+
+/// What GET /rates returns: what one unit of `base` buys in each currency
+#[derive(Debug, Serialize)]
+pub struct ExchangeRatesResponse {
+    pub base: Currency,
+    /// Keyed by currency code, e.g. "EUR": "0.9201"
+    pub rates: std::collections::BTreeMap<String, rust_decimal::Decimal>,
+    /// Where the rates came from, e.g. "frankfurter"
+    pub provider: String,
+    pub fetched_at: DateTime<Utc>,
+}
+
+// ============================================================================
+// CRYPTO
+// ============================================================================
+// BTC and ETH held as ledger balances, bought and sold against the USD
+// wallet at the crypto provider's quotes. Quantities carry their asset's
+// own precision (see `CryptoAsset::decimals`); USD amounts stay at cents.
+
+/// A crypto asset users can hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CryptoAsset {
+    Btc,
+    Eth,
+}
+
+impl CryptoAsset {
+    /// Every supported asset
+    pub const ALL: [CryptoAsset; 2] = [CryptoAsset::Btc, CryptoAsset::Eth];
+
+    /// The ticker stored in the database, e.g. "BTC"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CryptoAsset::Btc => "BTC",
+            CryptoAsset::Eth => "ETH",
+        }
+    }
+
+    /// Parse a stored ticker (None for anything unknown)
+    pub fn parse(asset: &str) -> Option<Self> {
+        match asset {
+            "BTC" => Some(CryptoAsset::Btc),
+            "ETH" => Some(CryptoAsset::Eth),
+            _ => None,
+        }
+    }
+
+    /// Decimal places of the smallest unit (a satoshi, a wei)
+    pub fn decimals(&self) -> u32 {
+        match self {
+            CryptoAsset::Btc => 8,
+            CryptoAsset::Eth => 18,
+        }
+    }
+}
+
+impl std::fmt::Display for CryptoAsset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Buying crypto with USD, or selling it for USD
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+impl TradeSide {
+    /// The value stored in the database, e.g. "BUY"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TradeSide::Buy => "BUY",
+            TradeSide::Sell => "SELL",
+        }
+    }
+
+    /// Parse a stored side (None for anything unknown)
+    pub fn parse(side: &str) -> Option<Self> {
+        match side {
+            "BUY" => Some(TradeSide::Buy),
+            "SELL" => Some(TradeSide::Sell),
+            _ => None,
+        }
+    }
+}
+
+/// Request to buy or sell crypto; give either `quantity` or `usd_amount`
+///
+/// ```json
+/// { "asset": "BTC", "side": "BUY", "usd_amount": "250.00" }
+/// { "asset": "ETH", "side": "SELL", "quantity": "0.5" }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct CryptoTradeRequest {
+    pub asset: CryptoAsset,
+    pub side: TradeSide,
+    /// Coins to buy or sell, in the asset's precision
+    #[validate(custom(function = "positive_amount", message = "must be greater than 0"))]
+    pub quantity: Option<rust_decimal::Decimal>,
+    /// USD to spend (buy) or raise (sell)
+    #[validate(custom(function = "validate_amount"))]
+    pub usd_amount: Option<rust_decimal::Decimal>,
+}
+
+/// A completed crypto trade
+#[derive(Debug, Serialize)]
+pub struct CryptoTradeResponse {
+    pub id: Uuid,
+    pub asset: CryptoAsset,
+    pub side: TradeSide,
+    pub quantity: rust_decimal::Decimal,
+    /// USD per whole coin
+    pub price: rust_decimal::Decimal,
+    pub usd_amount: rust_decimal::Decimal,
+    /// The USD WITHDRAWAL (buy) or DEPOSIT (sell) in the wallet history
+    pub transaction_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The current buy and sell price of one asset, in USD per coin
+#[derive(Debug, Serialize)]
+pub struct CryptoQuoteResponse {
+    pub asset: CryptoAsset,
+    pub buy_price: rust_decimal::Decimal,
+    pub sell_price: rust_decimal::Decimal,
+    pub provider: String,
+    pub quoted_at: DateTime<Utc>,
+}
+
+/// One crypto balance, as shown to its owner
+#[derive(Debug, Serialize)]
+pub struct CryptoHoldingResponse {
+    pub asset: CryptoAsset,
+    pub quantity: rust_decimal::Decimal,
+    /// What selling it all would raise now (None if no quote is available)
+    pub usd_value: Option<rust_decimal::Decimal>,
+}
+
+/// Crypto bought and sold in one statement month
+#[derive(Debug, Serialize)]
+pub struct CryptoStatementLine {
+    pub asset: CryptoAsset,
+    pub bought: rust_decimal::Decimal,
+    pub sold: rust_decimal::Decimal,
+    /// Part of the month's `money_out`
+    pub usd_spent: rust_decimal::Decimal,
+    /// Part of the month's `money_in`
+    pub usd_received: rust_decimal::Decimal,
+}
+
+// ============================================================================
+// OPEN BANKING
+// ============================================================================
+// Read-only account information for registered third parties, under
+// /open-banking. Third parties never see a user's password or JWT: they
+// get scope-limited tokens for a consent the user authorised themselves.
+
+/// What an Open Banking consent (and a token bound to it) may read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenBankingScope {
+    Accounts,
+    Balances,
+    Transactions,
+}
+
+impl OpenBankingScope {
+    /// Every scope, in the order they are listed back
+    pub const ALL: [OpenBankingScope; 3] = [
+        OpenBankingScope::Accounts,
+        OpenBankingScope::Balances,
+        OpenBankingScope::Transactions,
+    ];
+
+    /// The name used in the database and in OAuth `scope` strings
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OpenBankingScope::Accounts => "accounts",
+            OpenBankingScope::Balances => "balances",
+            OpenBankingScope::Transactions => "transactions",
+        }
+    }
+
+    /// Parse a scope name (None for anything unknown)
+    pub fn parse(scope: &str) -> Option<Self> {
+        OpenBankingScope::ALL.into_iter().find(|s| s.as_str() == scope)
+    }
+}
+
+impl std::fmt::Display for OpenBankingScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where an Open Banking consent stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ConsentStatus {
+    /// Created by the third party, waiting for the user
+    AwaitingAuthorisation,
+    Authorised,
+    Rejected,
+    /// Withdrawn by the user or the third party
+    Revoked,
+    /// Never stored: an awaiting or authorised consent past `expires_at`
+    Expired,
+}
+
+impl ConsentStatus {
+    /// The value stored in the database, e.g. "AUTHORISED"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsentStatus::AwaitingAuthorisation => "AWAITING_AUTHORISATION",
+            ConsentStatus::Authorised => "AUTHORISED",
+            ConsentStatus::Rejected => "REJECTED",
+            ConsentStatus::Revoked => "REVOKED",
+            ConsentStatus::Expired => "EXPIRED",
+        }
+    }
+
+    /// Parse a stored status (None for anything unknown)
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "AWAITING_AUTHORISATION" => Some(ConsentStatus::AwaitingAuthorisation),
+            "AUTHORISED" => Some(ConsentStatus::Authorised),
+            "REJECTED" => Some(ConsentStatus::Rejected),
+            "REVOKED" => Some(ConsentStatus::Revoked),
+            _ => None,
+        }
+    }
+}
+
+/// Request to register a third-party client (admins only)
+///
+/// ```json
+/// { "name": "Budget Buddy", "scopes": ["accounts", "balances"] }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct RegisterOpenBankingClientRequest {
+    #[validate(length(min = 1, max = 100, message = "must be 1-100 characters"))]
+    pub name: String,
+    /// The scopes this client's consents may ask for
+    #[validate(length(min = 1, message = "must name at least one scope"))]
+    pub scopes: Vec<OpenBankingScope>,
+}
+
+/// A registered third-party client
+#[derive(Debug, Serialize)]
+pub struct OpenBankingClientResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub client_id: String,
+    /// Only returned once, when the client is registered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    pub scopes: Vec<OpenBankingScope>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// OAuth 2.0 client credentials token request (form encoded)
+///
+/// Without `consent_id` the token can only create and manage consents;
+/// with one it can read what the user authorised.
+#[derive(Debug, Deserialize)]
+pub struct OpenBankingTokenRequest {
+    pub grant_type: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// Space-separated scopes (default: everything the consent allows)
+    pub scope: Option<String>,
+    pub consent_id: Option<Uuid>,
+}
+
+/// An issued access token (RFC 6749 section 5.1)
+#[derive(Debug, Serialize)]
+pub struct OpenBankingTokenResponse {
+    pub access_token: String,
+    pub token_type: &'static str,
+    /// Seconds until the token expires
+    pub expires_in: i64,
+    /// Space-separated scopes the token carries
+    pub scope: String,
+}
+
+/// A third party asking for access to a user's accounts
+///
+/// ```json
+/// { "scopes": ["accounts", "balances"], "expires_at": "2026-12-31T00:00:00Z" }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateConsentRequest {
+    #[validate(length(min = 1, message = "must name at least one scope"))]
+    pub scopes: Vec<OpenBankingScope>,
+    /// When access ends (default and maximum: 90 days from now)
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// An Open Banking consent, as shown to the third party and to the user
+#[derive(Debug, Serialize)]
+pub struct ConsentResponse {
+    pub id: Uuid,
+    /// The third party's public client id and name
+    pub client_id: String,
+    pub client_name: String,
+    pub scopes: Vec<OpenBankingScope>,
+    pub status: ConsentStatus,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub authorised_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// An account a third party may read (today: the user's wallet)
+#[derive(Debug, Serialize)]
+pub struct OpenBankingAccountResponse {
+    pub account_id: Uuid,
+    pub currency: Currency,
+}
+
+/// An account balance, for the `balances` scope
+#[derive(Debug, Serialize)]
+pub struct OpenBankingBalanceResponse {
+    pub account_id: Uuid,
+    pub balance: rust_decimal::Decimal,
+    pub currency: Currency,
+    pub as_of: DateTime<Utc>,
+}
+
+// ============================================================================
+// SINGLE SIGN-ON
+// ============================================================================
+// Enterprise customers (organizations) can have their people sign in
+// through their own identity provider, over OIDC or SAML 2.0 (src/sso/).
+// Admins set the identity provider up through /api/admin/organizations.
+
+/// What an organization's identity provider speaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SsoProtocol {
+    Oidc,
+    Saml,
+}
+
+impl SsoProtocol {
+    /// The value stored in the database, e.g. "OIDC"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SsoProtocol::Oidc => "OIDC",
+            SsoProtocol::Saml => "SAML",
+        }
+    }
+
+    /// Parse a stored protocol (None for anything unknown)
+    pub fn parse(protocol: &str) -> Option<Self> {
+        match protocol {
+            "OIDC" => Some(SsoProtocol::Oidc),
+            "SAML" => Some(SsoProtocol::Saml),
+            _ => None,
+        }
+    }
+}
+
+/// An enterprise customer
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Organization {
+    pub id: Uuid,
+    pub name: String,
+    /// What its people type on the login page, e.g. "acme"
+    pub slug: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request to create an organization (admins, or any user starting a
+/// business account)
+///
+/// ```json
+/// { "name": "Acme Corp", "slug": "acme" }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateOrganizationRequest {
+    #[validate(length(min = 1, max = 100, message = "must be 1-100 characters"))]
+    pub name: String,
+    #[validate(custom(
+        function = "valid_slug",
+        message = "must be 1-50 lowercase letters, digits and dashes"
+    ))]
+    pub slug: String,
+}
+
+fn valid_slug(slug: &str) -> Result<(), ValidationError> {
+    let valid = (1..=50).contains(&slug.len())
+        && slug.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-');
+    if !valid {
+        return Err(ValidationError::new("slug"));
+    }
+    Ok(())
+}
+
+/// Request to set up (or replace) an organization's identity provider
+///
+/// ```json
+/// {
+///   "protocol": "OIDC",
+///   "oidc_issuer": "https://acme.okta.com",
+///   "oidc_client_id": "0oa1b2c3",
+///   "oidc_client_secret": "...",
+///   "email_domains": ["acme.com"]
+/// }
+/// ```
+/// SAML takes `saml_idp_entity_id`, `saml_sso_url` and
+/// `saml_idp_certificate` (PEM) instead of the `oidc_*` fields.
+#[derive(Debug, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct SsoConnectionRequest {
+    pub protocol: SsoProtocol,
+    pub oidc_issuer: Option<String>,
+    pub oidc_client_id: Option<String>,
+    pub oidc_client_secret: Option<String>,
+    pub saml_idp_entity_id: Option<String>,
+    pub saml_sso_url: Option<String>,
+    pub saml_idp_certificate: Option<String>,
+    /// Only people with these email domains can sign in through it
+    #[validate(length(min = 1, message = "must name at least one domain"))]
+    pub email_domains: Vec<String>,
+    /// Create accounts for people signing in for the first time (default true)
+    pub jit_provisioning: Option<bool>,
+    /// Default true
+    pub enabled: Option<bool>,
+}
+
+/// An organization's identity provider, as shown to admins
+#[derive(Debug, Serialize)]
+pub struct SsoConnectionResponse {
+    pub organization_id: Uuid,
+    pub protocol: SsoProtocol,
+    pub oidc_issuer: Option<String>,
+    pub oidc_client_id: Option<String>,
+    pub saml_idp_entity_id: Option<String>,
+    pub saml_sso_url: Option<String>,
+    pub email_domains: Vec<String>,
+    pub jit_provisioning: bool,
+    pub enabled: bool,
+    /// Where the login page sends its people: {PUBLIC_URL}/sso/login?organization=<slug>
+    pub login_url: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An organization and its identity provider, if it has one
+#[derive(Debug, Serialize)]
+pub struct OrganizationResponse {
+    #[serde(flatten)]
+    pub organization: Organization,
+    pub sso: Option<SsoConnectionResponse>,
+}
+
+/// Query: start a single sign-on
+#[derive(Debug, Deserialize, Validate)]
+pub struct SsoLoginQuery {
+    #[validate(length(min = 1, max = 50, message = "must be 1-50 characters"))]
+    pub organization: String,
+}
+
+/// Query: where an OIDC identity provider sends the browser back to
+#[derive(Debug, Deserialize, Validate)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// Set instead of `code` when the sign-in failed at the provider
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// Form: what a SAML identity provider posts to the assertion consumer service
+#[derive(Debug, Deserialize, Validate)]
+pub struct SamlAcsForm {
+    #[serde(rename = "SAMLResponse")]
+    #[validate(length(min = 1, message = "is required"))]
+    pub saml_response: String,
+}
+
+// ============================================================================
+// BUSINESS ACCOUNTS
+// ============================================================================
+// An organization can own a wallet and have members, who act on it
+// according to their role (see organization_service). Any user can start
+// one; owners invite the rest by email.
+
+/// What a member may do with their organization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OrganizationRole {
+    /// Everything, including managing members
+    Owner,
+    /// Deposits, withdrawals and transfers
+    Finance,
+    /// The balance and history only
+    Viewer,
+}
+
+impl OrganizationRole {
+    /// The value stored in the database, e.g. "FINANCE"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrganizationRole::Owner => "OWNER",
+            OrganizationRole::Finance => "FINANCE",
+            OrganizationRole::Viewer => "VIEWER",
+        }
+    }
+
+    /// Parse a stored role (None for anything unknown)
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "OWNER" => Some(OrganizationRole::Owner),
+            "FINANCE" => Some(OrganizationRole::Finance),
+            "VIEWER" => Some(OrganizationRole::Viewer),
+            _ => None,
+        }
+    }
+
+    /// Deposit, withdraw and transfer from the organization's wallet
+    pub fn can_move_money(&self) -> bool {
+        matches!(self, OrganizationRole::Owner | OrganizationRole::Finance)
+    }
+
+    /// Invite, remove and change the role of members
+    pub fn can_manage_members(&self) -> bool {
+        *self == OrganizationRole::Owner
+    }
+}
+
+/// Someone in an organization
+#[derive(Debug, Clone, Serialize)]
+pub struct OrganizationMember {
+    pub user_id: Uuid,
+    pub email: String,
+    pub full_name: String,
+    pub role: OrganizationRole,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// An organization the caller belongs to
+#[derive(Debug, Serialize)]
+pub struct MembershipResponse {
+    #[serde(flatten)]
+    pub organization: Organization,
+    /// The caller's role in it
+    pub role: OrganizationRole,
+}
+
+/// An organization with its members (members only)
+#[derive(Debug, Serialize)]
+pub struct OrganizationDetailsResponse {
+    #[serde(flatten)]
+    pub organization: Organization,
+    pub role: OrganizationRole,
+    pub members: Vec<OrganizationMember>,
+}
+
+/// Request to invite someone to an organization
+///
+/// ```json
+/// { "email": "bob@acme.com", "role": "FINANCE" }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct InviteMemberRequest {
+    #[validate(email(message = "must be a valid email address"))]
+    pub email: String,
+    pub role: OrganizationRole,
+}
+
+/// An invitation waiting to be accepted
+#[derive(Debug, Clone, Serialize)]
+pub struct OrganizationInvitation {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub email: String,
+    pub role: OrganizationRole,
+    pub invited_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub accepted_at: Option<DateTime<Utc>>,
+}
+
+/// Request to accept an invitation, with the token from the email
+#[derive(Debug, Deserialize, Validate)]
+pub struct AcceptInvitationRequest {
+    #[validate(length(min = 1, max = 100, message = "must be 1-100 characters"))]
+    pub token: String,
+}
+
+/// Request to change a member's role
+///
+/// ```json
+/// { "role": "VIEWER" }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateMemberRequest {
+    pub role: OrganizationRole,
+}
+
+/// Where an expense request stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ExpenseStatus {
+    /// Waiting for an owner or finance member
+    Pending,
+    /// Approved and paid out
+    Approved,
+    Rejected,
+}
+
+impl ExpenseStatus {
+    /// The value stored in the database, e.g. "PENDING"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExpenseStatus::Pending => "PENDING",
+            ExpenseStatus::Approved => "APPROVED",
+            ExpenseStatus::Rejected => "REJECTED",
+        }
+    }
+
+    /// Parse a stored status (None for anything unknown)
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "PENDING" => Some(ExpenseStatus::Pending),
+            "APPROVED" => Some(ExpenseStatus::Approved),
+            "REJECTED" => Some(ExpenseStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// A member's request for a transfer out of the organization's wallet
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpenseRequest {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    /// None once the requester's account is gone
+    pub requested_by: Option<Uuid>,
+    pub requester_email: Option<String>,
+    pub recipient_id: Uuid,
+    pub recipient_email: String,
+    pub amount: rust_decimal::Decimal,
+    pub currency: Currency,
+    pub description: String,
+    pub status: ExpenseStatus,
+    pub decided_by: Option<Uuid>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub decision_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request to ask for a transfer out of an organization's wallet
+///
+/// ```json
+/// {
+///   "recipient_email": "supplier@example.com",
+///   "amount": "89.90",
+///   "description": "Office chairs"
+/// }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct SubmitExpenseRequest {
+    #[validate(email(message = "must be a valid email address"))]
+    pub recipient_email: String,
+    #[serde(deserialize_with = "deserialize_decimal_from_string")]
+    #[validate(custom(function = "validate_amount"))]
+    pub amount: rust_decimal::Decimal,
+    #[validate(
+        length(min = 1, max = 140, message = "must be between 1 and 140 characters"),
+        custom(function = "not_blank", message = "cannot be empty")
+    )]
+    pub description: String,
+}
+
+/// Request to approve or reject an expense, with an optional note
+#[derive(Debug, Deserialize, Validate)]
+pub struct DecideExpenseRequest {
+    #[validate(length(max = 500, message = "must be at most 500 characters"))]
+    pub note: Option<String>,
+}
+
+/// Query parameters of GET /organizations/:id/expenses
+#[derive(Debug, Deserialize, Validate)]
+pub struct ExpenseListQuery {
+    /// Only requests with this status (all of them otherwise)
+    pub status: Option<ExpenseStatus>,
+}
+
+// ============================================================================
+// REFERRALS
+// ============================================================================
+// Every user has a code to share. Signing up with it records a referral;
+// once the new user's first deposit reaches REFERRAL_MIN_DEPOSIT both sides
+// get a bonus (see referral_service).
+
+/// Where a referral stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ReferralStatus {
+    /// Waiting for the referee's qualifying deposit
+    Pending,
+    /// Both sides got their bonus
+    Rewarded,
+    /// Failed an anti-abuse check, or the referrer hit the reward limit
+    Rejected,
+    /// No qualifying deposit within REFERRAL_WINDOW_DAYS
+    Expired,
+}
+
+impl ReferralStatus {
+    /// The value stored in the database, e.g. "PENDING"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReferralStatus::Pending => "PENDING",
+            ReferralStatus::Rewarded => "REWARDED",
+            ReferralStatus::Rejected => "REJECTED",
+            ReferralStatus::Expired => "EXPIRED",
+        }
+    }
+
+    /// Parse a stored status (None for anything unknown)
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "PENDING" => Some(ReferralStatus::Pending),
+            "REWARDED" => Some(ReferralStatus::Rewarded),
+            "REJECTED" => Some(ReferralStatus::Rejected),
+            "EXPIRED" => Some(ReferralStatus::Expired),
+            _ => None,
+        }
+    }
+}
+
+/// Query parameters of the register page (GET /register?ref=K7Q2M9XD)
+#[derive(Debug, Deserialize)]
+pub struct RegisterPageQuery {
+    #[serde(rename = "ref")]
+    pub referral_code: Option<String>,
+}
+
+/// A signup attributed to someone's referral code
+#[derive(Debug, Clone, Serialize)]
+pub struct Referral {
+    pub id: Uuid,
+    pub referrer_id: Uuid,
+    pub referee_id: Uuid,
+    pub referee_email: String,
+    pub status: ReferralStatus,
+    pub rejected_reason: Option<String>,
+    /// What the referrer was paid, once rewarded
+    pub referrer_reward: Option<rust_decimal::Decimal>,
+    /// What the referee was paid, once rewarded
+    pub referee_reward: Option<rust_decimal::Decimal>,
+    pub created_at: DateTime<Utc>,
+    pub rewarded_at: Option<DateTime<Utc>>,
+}
+
+impl Referral {
+    /// The referee's address with most of the local part hidden,
+    /// e.g. "b••@example.com"
+    pub fn masked_referee_email(&self) -> String {
+        match self.referee_email.split_once('@') {
+            Some((local, domain)) => {
+                let first: String = local.chars().take(1).collect();
+                format!("{}••@{}", first, domain)
+            }
+            None => "••".to_string(),
+        }
+    }
+}
+
+/// One of my referrals, as the referrer sees it
+#[derive(Debug, Serialize)]
+pub struct ReferralResponse {
+    pub id: Uuid,
+    /// Masked, e.g. "b••@example.com"
+    pub referee_email: String,
+    pub status: ReferralStatus,
+    pub rejected_reason: Option<String>,
+    /// My bonus for this referral, once rewarded
+    pub reward: Option<rust_decimal::Decimal>,
+    pub created_at: DateTime<Utc>,
+    pub rewarded_at: Option<DateTime<Utc>>,
+}
+
+impl From<Referral> for ReferralResponse {
+    fn from(referral: Referral) -> Self {
+        ReferralResponse {
+            id: referral.id,
+            referee_email: referral.masked_referee_email(),
+            status: referral.status,
+            rejected_reason: referral.rejected_reason,
+            reward: referral.referrer_reward,
+            created_at: referral.created_at,
+            rewarded_at: referral.rewarded_at,
+        }
+    }
+}
+
+/// GET /referrals: my code, the program's terms and how I'm doing
+#[derive(Debug, Serialize)]
+pub struct ReferralOverview {
+    pub code: String,
+    /// The signup link to share, e.g. "https://app.example.com/register?ref=K7Q2M9XD"
+    pub link: String,
+    pub referrer_reward: rust_decimal::Decimal,
+    pub referee_reward: rust_decimal::Decimal,
+    pub min_deposit: rust_decimal::Decimal,
+    pub window_days: u32,
+    pub max_rewards: u32,
+    /// Referral bonuses earned so far (as referrer and as referee)
+    pub total_earned: rust_decimal::Decimal,
+    pub referrals: Vec<ReferralResponse>,
+}
+
+// ============================================================================
+// LIVE NOTIFICATIONS
+// ============================================================================
+// Connecting to GET /api/ws with `?since=<sequence>` replays the inbox
+// entries after that sequence before live ones (see handlers/ws.rs).
+
+/// Query parameters of GET /ws (and GET /events)
+#[derive(Debug, Deserialize, Validate)]
+pub struct WebSocketQuery {
+    /// The last notification sequence the client saw
+    #[validate(range(min = 0, message = "must not be negative"))]
+    pub since: Option<i64>,
+}
+
+// ============================================================================
+// DASHBOARD SUMMARY
+// ============================================================================
+// Everything the dashboard overview shows, read from the precomputed
+// `dashboard_summaries` row (kept current by database triggers) so the page
+// costs one query instead of a user, wallet and history lookup each.
+
+/// The dashboard overview for one user
+#[derive(Debug)]
+pub struct DashboardSummary {
+    pub user: UserResponse,
+    pub wallet: WalletResponse,
+    /// Newest first, at most five
+    pub recent_transactions: Vec<Transaction>,
+    /// Completed deposits and received transfers this month (UTC)
+    pub month_in: rust_decimal::Decimal,
+    /// Completed withdrawals and sent transfers this month (UTC)
+    pub month_out: rust_decimal::Decimal,
+}
+
+// Request to create a new transaction
+#[derive(Debug, Deserialize)]
+pub struct CreateTransactionRequest {
+    pub transaction_type: String,    // DEPOSIT, WITHDRAWAL, TRANSFER
+    pub amount: rust_decimal::Decimal,
+    pub description: Option<String>,
+}
+
+impl From<Transaction> for TransactionResponse {
+    fn from(tx: Transaction) -> Self {
+        TransactionResponse {
+            id: tx.id,
+            transaction_type: tx.transaction_type,
+            amount: tx.amount,
+            description: tx.description,
+            status: tx.status,
+            created_at: tx.created_at,
+            completed_at: tx.completed_at,
+            failed_at: tx.failed_at,
+            reversed_at: tx.reversed_at,
+            exchange_rate: tx.exchange_rate,
+        }
+    }
+}
+
+// ============================================================================
+// ONBOARDING MODEL
+// ============================================================================
+// Tracks where a new user is in the post-registration wizard.
+//
+// Why do we need this?
+// - So the wizard resumes at the right step if the user leaves halfway
+// - To remember the choices made along the way (notification prefs, 2FA)
+
+/// The steps of the onboarding wizard, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OnboardingStep {
+    VerifyEmail,
+    Notifications,
+    TwoFactor,
+    FirstDeposit,
+    Complete,
+}
+
+impl OnboardingStep {
+    /// All steps shown in the progress bar (excludes `Complete`)
+    pub const WIZARD: [OnboardingStep; 4] = [
+        OnboardingStep::VerifyEmail,
+        OnboardingStep::Notifications,
+        OnboardingStep::TwoFactor,
+        OnboardingStep::FirstDeposit,
+    ];
+
+    /// The value stored in the `current_step` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OnboardingStep::VerifyEmail => "VERIFY_EMAIL",
+            OnboardingStep::Notifications => "NOTIFICATIONS",
+            OnboardingStep::TwoFactor => "TWO_FACTOR",
+            OnboardingStep::FirstDeposit => "FIRST_DEPOSIT",
+            OnboardingStep::Complete => "COMPLETE",
+        }
+    }
+
+    /// Parse the value stored in the `current_step` column
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "VERIFY_EMAIL" => Some(OnboardingStep::VerifyEmail),
+            "NOTIFICATIONS" => Some(OnboardingStep::Notifications),
+            "TWO_FACTOR" => Some(OnboardingStep::TwoFactor),
+            "FIRST_DEPOSIT" => Some(OnboardingStep::FirstDeposit),
+            "COMPLETE" => Some(OnboardingStep::Complete),
+            _ => None,
+        }
+    }
+
+    /// The step that follows this one
+    pub fn next(&self) -> Self {
+        match self {
+            OnboardingStep::VerifyEmail => OnboardingStep::Notifications,
+            OnboardingStep::Notifications => OnboardingStep::TwoFactor,
+            OnboardingStep::TwoFactor => OnboardingStep::FirstDeposit,
+            OnboardingStep::FirstDeposit | OnboardingStep::Complete => OnboardingStep::Complete,
+        }
+    }
+
+    /// Human-readable label for the progress bar
+    pub fn label(&self) -> &'static str {
+        match self {
+            OnboardingStep::VerifyEmail => "Verify email",
+            OnboardingStep::Notifications => "Notifications",
+            OnboardingStep::TwoFactor => "Two-factor",
+            OnboardingStep::FirstDeposit => "First deposit",
+            OnboardingStep::Complete => "Done",
+        }
+    }
+
+    /// 1-based position in the wizard
+    pub fn number(&self) -> usize {
+        match self {
+            OnboardingStep::VerifyEmail => 1,
+            OnboardingStep::Notifications => 2,
+            OnboardingStep::TwoFactor => 3,
+            OnboardingStep::FirstDeposit => 4,
+            OnboardingStep::Complete => 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OnboardingProgress {
+    pub user_id: Uuid,
+    pub current_step: String,        // See OnboardingStep::as_str
+    pub verification_code: Option<String>,
+    pub verification_expires_at: Option<DateTime<Utc>>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub notify_email: bool,
+    pub notify_realtime: bool,
+    pub two_factor_opt_in: bool,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl OnboardingProgress {
+    /// The step the user should see next
+    pub fn step(&self) -> OnboardingStep {
+        OnboardingStep::parse(&self.current_step).unwrap_or(OnboardingStep::Complete)
+    }
+}
+
+/// Form: verify email with the emailed code
+#[derive(Debug, Deserialize, Validate)]
+pub struct VerifyEmailRequest {
+    #[validate(length(equal = 6, message = "must be the 6-digit code from the email"))]
+    pub code: String,
+}
+
+/// Form: notification preferences (checkboxes are absent when unticked)
+#[derive(Debug, Deserialize)]
+pub struct NotificationPrefsRequest {
+    #[serde(default)]
+    pub notify_email: Option<String>,
+    #[serde(default)]
+    pub notify_realtime: Option<String>,
+    #[serde(default)]
+    pub login_alerts: Option<String>,
+}
+
+/// Form: opt in to two-factor authentication or skip
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorChoiceRequest {
+    pub enable: bool,
+}
+
+// ============================================================================
+// PASSWORD RESET
+// ============================================================================
+// A forgotten password is replaced with an emailed one-time token (see
+// services::password_reset_service).
+
+/// Request to email a password reset link
+///
+/// ```json
+/// { "email": "alice@example.com" }
+/// ```
+#[derive(Debug, Deserialize, Validate, utoipa::ToSchema)]
+pub struct ForgotPasswordRequest {
+    #[validate(email(message = "must be a valid email address"))]
+    pub email: String,
+}
+
+/// Request to set a new password with an emailed reset token
+///
+/// ```json
+/// {
+///   "token": "3f9c...",
+///   "new_password": "correct-horse-battery"
+/// }
+/// ```
+#[derive(Debug, Deserialize, Validate, utoipa::ToSchema)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1, max = 128, message = "must be the code from the email"))]
+    pub token: String,
+    #[validate(
+        length(min = 8, message = "must be at least 8 characters"),
+        custom(function = "validate_password")
+    )]
+    pub new_password: String,
+}
+
+/// Query parameters of the reset page (the link in the email)
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordPageQuery {
+    pub token: Option<String>,
+}
+
+/// Request to unlock sign-in with the token from the account-locked email
+///
+/// ```json
+/// { "token": "7b1e..." }
+/// ```
+#[derive(Debug, Deserialize, Validate, utoipa::ToSchema)]
+pub struct UnlockAccountRequest {
+    #[validate(length(min = 1, max = 128, message = "must be the code from the email"))]
+    pub token: String,
+}
+
+/// Query parameters of the unlock page (the link in the email)
+#[derive(Debug, Deserialize)]
+pub struct UnlockAccountPageQuery {
+    pub token: Option<String>,
+}
+
+// ============================================================================
+// LOGIN HISTORY
+// ============================================================================
+// Every successful sign-in, with where it came from (see
+// services::login_history_service). Users see their own at
+// GET /api/me/logins.
+
+/// How a user signed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LoginMethod {
+    /// Email and password (API or web form)
+    Password,
+    /// Through the organization's identity provider
+    Sso,
+}
+
+impl LoginMethod {
+    /// The value stored in the database, e.g. "PASSWORD"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginMethod::Password => "PASSWORD",
+            LoginMethod::Sso => "SSO",
+        }
+    }
+}
+
+/// One sign-in
+///
+/// ```json
+/// {
+///   "id": "...",
+///   "method": "PASSWORD",
+///   "ip_address": "203.0.113.7",
+///   "user_agent": "Mozilla/5.0 ...",
+///   "country_code": "DE",
+///   "country": "Germany",
+///   "city": "Berlin",
+///   "new_device": false,
+///   "created_at": "..."
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct LoginEvent {
+    pub id: Uuid,
+    /// "PASSWORD" or "SSO"
+    pub method: String,
+    pub ip_address: String,
+    pub user_agent: Option<String>,
+    /// Location from GeoIP (null without GEOIP_DATABASE, or for private
+    /// addresses)
+    pub country_code: Option<String>,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    /// First sign-in from this IP address and browser
+    pub new_device: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+// ============================================================================
+// SIGNED-IN DEVICES
+// ============================================================================
+// Each sign-in that hands out a JWT is a session (see token_service); the
+// token carries its id, and revoking the session revokes the token.
+
+/// One signed-in device, as GET /sessions lists it
+///
+/// ```json
+/// {
+///   "id": "...",
+///   "device_name": "Firefox on Windows",
+///   "ip_address": "203.0.113.7",
+///   "user_agent": "Mozilla/5.0 ...",
+///   "created_at": "...",
+///   "last_seen_at": "...",
+///   "current": true
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TokenSession {
+    pub id: Uuid,
+    pub device_name: String,
+    pub ip_address: String,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// To the minute
+    pub last_seen_at: DateTime<Utc>,
+    /// The session the request was made with
+    pub current: bool,
+}
+
+/// Request to mint a token for another app, e.g. a read-only one
+///
+/// ```json
+/// { "name": "Budget app", "scope": "wallet:read" }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateTokenRequest {
+    /// Shown as the session's device name
+    #[validate(length(min = 1, max = 100, message = "must be 1-100 characters"))]
+    pub name: String,
+    /// Scopes separated by spaces; no more than the requesting token has
+    pub scope: String,
+}
+
+/// A minted token, and the session that revokes it
+#[derive(Debug, Serialize)]
+pub struct CreateTokenResponse {
+    pub token: String,
+    pub session_id: Uuid,
+    pub scope: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+// ============================================================================
+// FILE DOWNLOADS
+// ============================================================================
+// Files kept in storage (see services::storage) are handed out as
+// presigned links rather than streamed through the API.
+
+/// A time-limited link to a stored file
+#[derive(Debug, Serialize)]
+pub struct DownloadLink {
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Query string of a local storage link: GET /files/*key
+#[derive(Debug, Deserialize, Validate)]
+pub struct FileDownloadQuery {
+    /// Unix time the link stops working
+    pub expires: i64,
+    /// Hex HMAC-SHA256 of the key and `expires`
+    #[validate(length(equal = 64))]
+    pub signature: String,
+}
+
+// ============================================================================
+// SESSION MODEL
+// ============================================================================
+// A server-side login session for the web UI (only used when
+// WEB_AUTH_MODE=session). The browser only holds the random session id.
+//
+// Why do we need this?
+// - Deleting the row logs the user out instantly (JWTs stay valid until expiry)
+// - Somewhere to keep per-session data like the CSRF token and flash messages
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Session {
+    pub id: String,                  // Random token stored in the session_id cookie
+    pub user_id: Uuid,
+    pub csrf_token: String,          // Must be echoed in X-CSRF-Token on POSTs
+    pub flash: Option<String>,       // One-shot message shown on the next page
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
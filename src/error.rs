@@ -1,10 +1,12 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
 use thiserror::Error;
+use rust_decimal::Decimal;
+use crate::domain::models::Currency;
 
 // ============================================================================
 // CENTRALIZED ERROR HANDLING
@@ -44,6 +46,15 @@ pub enum AppError {
     /// When user tries to access something they don't own
     #[error("Unauthorized access")]
     Unauthorized,
+
+    /// When the account involved has been (soft) deleted
+    #[error("This account has been deleted")]
+    AccountDeleted,
+
+    /// When the token wasn't granted the scope an endpoint needs (e.g. a
+    /// read-only token and "wallet:write")
+    #[error("This token lacks the {0} scope")]
+    InsufficientScope(String),
     
     // ========================================================================
     // VALIDATION ERRORS
@@ -52,6 +63,11 @@ pub enum AppError {
     /// When user input is invalid (e.g., negative amount, invalid email)
     #[error("Validation error: {0}")]
     ValidationError(String),
+
+    /// When one or more request fields fail validation
+    /// (the response lists every failing field)
+    #[error("Validation error: {}", join_field_errors(.0))]
+    InvalidFields(Vec<FieldError>),
     
     /// When a user tries to register with an email that already exists
     #[error("User with this email already exists")]
@@ -69,9 +85,48 @@ pub enum AppError {
     #[error("Insufficient balance")]
     InsufficientBalance,
     
+    /// When money would move between different currencies
+    #[error("Currency mismatch: expected {expected}, got {got}")]
+    CurrencyMismatch { expected: Currency, got: Currency },
+
+    /// When money would go over a spending limit: per transaction, or for
+    /// the day (`remaining` is what's left of the daily limit)
+    #[error("Limit exceeded: {message}")]
+    LimitExceeded {
+        message: String,
+        limit: Decimal,
+        remaining: Option<Decimal>,
+    },
+
+    /// When a frozen account tries to move money, or a closed one tries
+    /// anything
+    #[error("Account is frozen")]
+    AccountFrozen,
+
+    /// When sign-ins for an address failed too often: it has to wait
+    /// `retry_after_secs`, or (None) it's locked until the emailed unlock
+    /// link is used
+    #[error("Too many failed sign-in attempts: {reason}")]
+    TooManyAttempts {
+        reason: String,
+        retry_after_secs: Option<i64>,
+    },
+
+    /// When something that's only allowed so often (e.g. `what` =
+    /// "broadcasts") was done too often lately
+    #[error("Too many {what}: try again in {retry_after_secs} seconds")]
+    RateLimited {
+        what: String,
+        retry_after_secs: i64,
+    },
+
     /// When a transaction fails for business reasons
     #[error("Transaction failed: {0}")]
     TransactionFailed(String),
+
+    /// When a feature has been switched off in the `[features]` config
+    #[error("{0} are temporarily disabled")]
+    FeatureDisabled(String),
     
     // ========================================================================
     // GENERAL ERRORS
@@ -92,14 +147,135 @@ pub enum AppError {
 //
 // We return:
 // - Appropriate HTTP status code (404, 401, 500, etc.)
-// - JSON error message for the client
+// - JSON error message and stable error code for the client
+// - `details`, when the error has facts a client can act on
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        // Determine the HTTP status code based on the error type
-        let status_code = match &self {
+        // Determine the HTTP status code and stable code based on the error type
+        let status_code = self.status_code();
+        let code = self.code();
+
+        // Create a JSON response with error details
+        let error_message = self.to_string();
+        
+        let mut body = json!({
+            "error": error_message,
+            "code": code,
+            "status": status_code.as_u16(),
+        });
+
+        // Field-level validation failures also list each field
+        if let AppError::InvalidFields(fields) = &self {
+            body["errors"] = json!(fields);
+        }
+        if let Some(details) = self.details() {
+            body["details"] = details;
+        }
+        let body = Json(body);
+
+        // Return the response with status code and JSON body.
+        // The message is also attached as an extension so the web UI can
+        // re-render it as HTML without parsing the JSON back.
+        let mut response = (status_code, body).into_response();
+        if let AppError::TooManyAttempts { retry_after_secs: Some(secs), .. }
+        | AppError::RateLimited { retry_after_secs: secs, .. } = &self
+        {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(*secs));
+        }
+        response.extensions_mut().insert(ErrorDetails {
+            code,
+            message: error_message,
+        });
+        response
+    }
+}
+
+/// The user-facing message of an `AppError`, attached to error responses
+#[derive(Debug, Clone)]
+pub struct ErrorDetails {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+// ============================================================================
+// FIELD VALIDATION ERRORS
+// ============================================================================
+// Request DTOs derive `validator::Validate`. When validation fails, the
+// 400 response lists every failing field:
+//
+//   {
+//     "error": "Validation error: amount: must be greater than 0",
+//     "code": "VALIDATION_ERROR",
+//     "status": 400,
+//     "errors": [{ "field": "amount", "code": "positive", "message": "must be greater than 0" }]
+//   }
+
+pub use api_types::FieldError;
+
+impl From<validator::ValidationErrors> for AppError {
+    fn from(errors: validator::ValidationErrors) -> Self {
+        let mut fields: Vec<FieldError> = errors
+            .field_errors()
+            .into_iter()
+            .flat_map(|(field, errors)| errors.iter().map(move |e| to_field_error(field, e)))
+            .collect();
+
+        // HashMap order is random; keep responses stable
+        fields.sort_by(|a, b| a.field.cmp(&b.field).then(a.code.cmp(&b.code)));
+        AppError::InvalidFields(fields)
+    }
+}
+
+fn to_field_error(field: &str, error: &validator::ValidationError) -> FieldError {
+    FieldError {
+        field: field.to_string(),
+        code: error.code.to_string(),
+        message: error
+            .message
+            .as_ref()
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| format!("is invalid ({})", error.code)),
+    }
+}
+
+fn join_field_errors(fields: &[FieldError]) -> String {
+    fields
+        .iter()
+        .map(|f| format!("{}: {}", f.field, f.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// ============================================================================
+// ERROR CODES
+// ============================================================================
+// Every error response carries a stable `code` next to the human message:
+//
+//   { "error": "Insufficient balance", "code": "INSUFFICIENT_BALANCE", "status": 422 }
+//
+// Clients should switch on `code`; the message wording may change. Some
+// errors add a `details` object with what a client needs to react without
+// parsing the message (see `AppError::details`):
+//
+//   { "error": "Too many failed sign-in attempts: try again in 4 seconds",
+//     "code": "TOO_MANY_ATTEMPTS", "status": 429, "details": { "retry_after_secs": 4 } }
+
+// The codes are part of the API, so they live in api-types with the
+// error body clients parse.
+pub use api_types::ErrorCode;
+
+// ============================================================================
+// HELPER FUNCTIONS
+// ============================================================================
+
+impl AppError {
+    /// The HTTP status code this error is sent with
+    pub fn status_code(&self) -> StatusCode {
+        match self {
             // 400 Bad Request - Client sent invalid data
             AppError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidFields(_) => StatusCode::BAD_REQUEST,
             
             // 401 Unauthorized - Authentication failed
             AppError::InvalidCredentials => StatusCode::UNAUTHORIZED,
@@ -107,6 +283,8 @@ impl IntoResponse for AppError {
             
             // 403 Forbidden - User doesn't have permission
             AppError::Unauthorized => StatusCode::FORBIDDEN,
+            AppError::AccountDeleted => StatusCode::FORBIDDEN,
+            AppError::InsufficientScope(_) => StatusCode::FORBIDDEN,
             
             // 404 Not Found - Resource doesn't exist
             AppError::NotFound(_) => StatusCode::NOT_FOUND,
@@ -116,31 +294,81 @@ impl IntoResponse for AppError {
             
             // 422 Unprocessable Entity - Business logic error
             AppError::InsufficientBalance => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::CurrencyMismatch { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::LimitExceeded { .. } => StatusCode::UNPROCESSABLE_ENTITY,
             AppError::TransactionFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+
+            // 423 Locked - The account is frozen; an admin has to unfreeze it
+            AppError::AccountFrozen => StatusCode::LOCKED,
+
+            // 429 Too Many Requests - Too many wrong passwords for this address
+            AppError::TooManyAttempts { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+
+            // 503 Service Unavailable - Switched off for now, try later
+            AppError::FeatureDisabled(_) => StatusCode::SERVICE_UNAVAILABLE,
             
             // 500 Internal Server Error - Something went wrong on our end
             AppError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
-
-        // Create a JSON response with error details
-        let error_message = self.to_string();
-        
-        let body = Json(json!({
-            "error": error_message,
-            "status": status_code.as_u16(),
-        }));
+        }
+    }
 
-        // Return the response with status code and JSON body
-        (status_code, body).into_response()
+    /// The stable, machine-readable code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::DatabaseError(_) => ErrorCode::DatabaseError,
+            AppError::InvalidCredentials => ErrorCode::InvalidCredentials,
+            AppError::InvalidToken => ErrorCode::InvalidToken,
+            AppError::Unauthorized => ErrorCode::Forbidden,
+            AppError::AccountDeleted => ErrorCode::AccountDeleted,
+            AppError::InsufficientScope(_) => ErrorCode::InsufficientScope,
+            AppError::ValidationError(_) => ErrorCode::ValidationError,
+            AppError::InvalidFields(_) => ErrorCode::ValidationError,
+            AppError::UserAlreadyExists => ErrorCode::UserAlreadyExists,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::InsufficientBalance => ErrorCode::InsufficientBalance,
+            AppError::CurrencyMismatch { .. } => ErrorCode::CurrencyMismatch,
+            AppError::LimitExceeded { .. } => ErrorCode::LimitExceeded,
+            AppError::TransactionFailed(_) => ErrorCode::TransactionFailed,
+            AppError::AccountFrozen => ErrorCode::AccountFrozen,
+            AppError::TooManyAttempts { .. } => ErrorCode::TooManyAttempts,
+            AppError::RateLimited { .. } => ErrorCode::RateLimited,
+            AppError::FeatureDisabled(_) => ErrorCode::FeatureDisabled,
+            AppError::InternalError(_) => ErrorCode::InternalError,
+        }
     }
-}
 
-// ============================================================================
-// HELPER FUNCTIONS
-// ============================================================================
+    /// Machine-readable facts about this error, sent as `details`
+    ///
+    /// Amounts are strings, like everywhere else in the API.
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            AppError::NotFound(resource) => Some(json!({ "resource": resource })),
+            AppError::InsufficientScope(scope) => Some(json!({ "required_scope": scope })),
+            AppError::CurrencyMismatch { expected, got } => {
+                Some(json!({ "expected": expected, "got": got }))
+            }
+            AppError::LimitExceeded { limit, remaining: None, .. } => {
+                Some(json!({ "period": "transaction", "limit": limit }))
+            }
+            AppError::LimitExceeded { limit, remaining: Some(remaining), .. } => {
+                Some(json!({ "period": "day", "limit": limit, "remaining": remaining }))
+            }
+            AppError::TooManyAttempts { retry_after_secs: Some(secs), .. } => {
+                Some(json!({ "retry_after_secs": secs }))
+            }
+            AppError::TooManyAttempts { retry_after_secs: None, .. } => {
+                Some(json!({ "locked": true }))
+            }
+            AppError::RateLimited { retry_after_secs, .. } => {
+                Some(json!({ "retry_after_secs": retry_after_secs }))
+            }
+            AppError::FeatureDisabled(feature) => Some(json!({ "feature": feature })),
+            _ => None,
+        }
+    }
 
-impl AppError {
     /// Helper to create a NotFound error with a custom message
     pub fn not_found(resource: &str) -> Self {
         AppError::NotFound(resource.to_string())
@@ -150,7 +378,23 @@ impl AppError {
     pub fn validation(message: &str) -> Self {
         AppError::ValidationError(message.to_string())
     }
+
+    /// Helper to report one failing field, e.g. from a shared validator
+    /// (`validate_amount(&amount).map_err(|e| AppError::invalid_field("amount", e))`)
+    pub fn invalid_field(field: &str, error: validator::ValidationError) -> Self {
+        AppError::InvalidFields(vec![to_field_error(field, &error)])
+    }
     
+    /// Helper to create a CurrencyMismatch error ("expected USD, got EUR")
+    pub fn currency_mismatch(expected: Currency, got: Currency) -> Self {
+        AppError::CurrencyMismatch { expected, got }
+    }
+
+    /// Helper to create a FeatureDisabled error, e.g. `feature_disabled("Transfers")`
+    pub fn feature_disabled(feature: &str) -> Self {
+        AppError::FeatureDisabled(feature.to_string())
+    }
+
     /// Helper to create an InternalError with a custom message
     pub fn internal(message: &str) -> Self {
         AppError::InternalError(message.to_string())
@@ -0,0 +1,128 @@
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    Json,
+};
+use crate::domain::models::{AccountCodeOverrides, AccountCodesResponse, JournalFormat, JournalQuery};
+use crate::error::AppError;
+use crate::handlers::{download, download_link};
+use crate::middleware::auth::{AuthUser, RequireScope, WalletWrite};
+use crate::middleware::validation::{ValidatedJson, ValidatedQuery};
+use crate::routes::auth_routes::AppState;
+use crate::services::accounting_service;
+
+// ============================================================================
+// ACCOUNTING EXPORT HANDLERS
+// ============================================================================
+
+/// The account codes the journal export uses
+///
+/// HTTP Endpoint: GET /accounting/account-codes
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "account_codes": {
+///     "wallet": "1010",
+///     "deposit": "260",
+///     "withdrawal": "429",
+///     "interest": "270",
+///     "fee": "404",
+///     "transfer_in": "877",
+///     "transfer_out": "877"
+///   },
+///   "overrides": { "wallet": "1010", "deposit": null, ... },
+///   "defaults": { "wallet": "090", "deposit": "260", ... }
+/// }
+/// ```
+pub async fn get_account_codes(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<AccountCodesResponse>, AppError> {
+    let codes = accounting_service::account_codes(&state.pool, &state.config, user_id).await?;
+
+    Ok(Json(codes))
+}
+
+/// Set the user's own account codes
+///
+/// HTTP Endpoint: PUT /accounting/account-codes
+///
+/// Request Body (every field optional; left out or null = the default):
+/// ```json
+/// { "wallet": "1010", "fee": "6100" }
+/// ```
+///
+/// Success Response (200 OK): same as GET. The body replaces all earlier
+/// codes, so send every code you want to keep.
+pub async fn set_account_codes(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<AccountCodeOverrides>,
+) -> Result<Json<AccountCodesResponse>, AppError> {
+    let codes =
+        accounting_service::set_account_codes(&state.pool, &state.config, user_id, req).await?;
+
+    Ok(Json(codes))
+}
+
+/// Wallet activity as journal entries
+///
+/// HTTP Endpoint: GET /accounting/journal?from=2026-01-01&to=2026-03-31
+///
+/// Query parameters:
+/// - `from`, `to`: first and last day (UTC, inclusive, at most 366 days)
+/// - `format`: `json` (default), `xero` or `quickbooks`; the last two come
+///   back as that tool's CSV import file
+/// - `link`: with `true`, the CSV is stored instead and the answer is a
+///   link valid for 15 minutes: `{ "url": "https://...", "expires_at": "..." }`
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "from": "2026-01-01",
+///   "to": "2026-03-31",
+///   "currency": "USD",
+///   "entries": [
+///     {
+///       "reference": "FA-8c2e41d90b7f",
+///       "transaction_id": "...",
+///       "date": "2026-01-28",
+///       "category": "FEE",
+///       "description": "Monthly account fee",
+///       "lines": [
+///         { "account_code": "404", "debit": "4.99", "credit": "0" },
+///         { "account_code": "090", "debit": "0", "credit": "4.99" }
+///       ]
+///     }
+///   ]
+/// }
+/// ```
+pub async fn get_journal(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<JournalQuery>,
+) -> Result<Response, AppError> {
+    let journal =
+        accounting_service::journal(&state.pool, &state.config, user_id, query.from, query.to)
+            .await?;
+
+    let (file_name, body) = match query.format.unwrap_or_default() {
+        JournalFormat::Json => return Ok(Json(journal).into_response()),
+        JournalFormat::Xero => (
+            accounting_service::file_name(&journal, "xero"),
+            accounting_service::to_xero_csv(&journal, &state.config.xero_tax_rate).into_bytes(),
+        ),
+        JournalFormat::Quickbooks => (
+            accounting_service::file_name(&journal, "quickbooks"),
+            accounting_service::to_quickbooks_csv(&journal).into_bytes(),
+        ),
+    };
+
+    let content_type = "text/csv; charset=utf-8";
+    if query.link.unwrap_or(false) {
+        download_link(state.storage.as_ref(), user_id, content_type, file_name, body).await
+    } else {
+        Ok(download(content_type, file_name, body))
+    }
+}
@@ -0,0 +1,584 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+use crate::domain::models::{AccountStatus, SearchQuery, TransactionResponse, UserResponse, WalletResponse};
+use crate::error::AppError;
+use crate::middleware::auth::AdminUser;
+use crate::middleware::validation::{AppJson, ValidatedJson, ValidatedQuery};
+use crate::repository::archive_repo::ArchiveRestore;
+use crate::repository::audit_repo::AuditEntry;
+use crate::repository::metrics::{self, QueryStats};
+use crate::repository::search_repo::OwnedTransactionMatch;
+use crate::repository::user_repo::UserSummary;
+use crate::routes::auth_routes::AppState;
+use crate::services::{admin_service, archive_service, search_service};
+use crate::services::debug_capture::{CaptureTarget, CapturedExchange};
+use crate::services::integrity_service::{self, BalanceVerification};
+
+// ============================================================================
+// ADMIN HANDLERS
+// ============================================================================
+// Operational endpoints. Every handler takes `AdminUser`, so only users
+// with the ADMIN role get in (see `my-fintech-app create-admin`). They are
+// served by the admin router (routes/admin_routes.rs), which writes every
+// change made through it to the audit log.
+
+/// The active tracing filter
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogLevel {
+    /// EnvFilter syntax, e.g. "info,my_fintech_app::services=debug"
+    pub filter: String,
+}
+
+/// Get the active log filter
+///
+/// HTTP Endpoint: GET /admin/log-level
+pub async fn get_log_level(
+    AdminUser(_): AdminUser,
+    State(state): State<AppState>,
+) -> Json<LogLevel> {
+    Json(LogLevel {
+        filter: state.log_level.current(),
+    })
+}
+
+/// Change the log filter without a restart
+///
+/// HTTP Endpoint: PUT /admin/log-level
+///
+/// Request Body:
+/// ```json
+/// { "filter": "info,my_fintech_app::services::wallet_service=debug" }
+/// ```
+///
+/// The change lasts until the next restart.
+pub async fn set_log_level(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    AppJson(req): AppJson<LogLevel>,
+) -> Result<Json<LogLevel>, AppError> {
+    let previous = state.log_level.current();
+    state.log_level.set(&req.filter)?;
+
+    tracing::warn!(
+        "🔧 Log filter changed by admin {}: {:?} -> {:?}",
+        admin_id,
+        previous,
+        req.filter
+    );
+
+    Ok(Json(LogLevel {
+        filter: state.log_level.current(),
+    }))
+}
+
+/// Query string for GET /admin/users: `?q=alice&limit=20`
+#[derive(Debug, Deserialize, Validate)]
+pub struct UserSearchQuery {
+    /// Part of an email address or name (default: every user)
+    #[validate(length(max = 200, message = "must be at most 200 characters"))]
+    pub q: Option<String>,
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    pub limit: Option<u32>,
+}
+
+/// Find users by email or name, newest first
+///
+/// HTTP Endpoint: GET /admin/users?q=alice&limit=20
+///
+/// Success Response (200 OK):
+/// ```json
+/// [
+///   {
+///     "id": "...",
+///     "email": "alice@example.com",
+///     "full_name": "Alice Smith",
+///     "role": "USER",
+///     "account_status": "ACTIVE",
+///     "created_at": "2026-10-17T09:30:00Z",
+///     "deleted_at": null
+///   }
+/// ]
+/// ```
+///
+/// Deleted accounts are listed too (with `deleted_at`), so they can be
+/// found and restored.
+pub async fn list_users(
+    AdminUser(_): AdminUser,
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<UserSearchQuery>,
+) -> Result<Json<Vec<UserSummary>>, AppError> {
+    let users = admin_service::search_users(&state.pool, query.q.as_deref(), query.limit).await?;
+
+    Ok(Json(users))
+}
+
+/// Any user's wallets, the primary one first
+///
+/// HTTP Endpoint: GET /admin/users/:id/wallets
+///
+/// Error Responses:
+/// - 404: no such user
+pub async fn get_user_wallets(
+    AdminUser(_): AdminUser,
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<Vec<WalletResponse>>, AppError> {
+    let wallets = admin_service::user_wallets(&state.pool, user_id).await?;
+
+    Ok(Json(wallets.into_iter().map(WalletResponse::from).collect()))
+}
+
+/// Response for a frozen or unfrozen account
+#[derive(Debug, Serialize)]
+pub struct AccountStatusChange {
+    pub id: Uuid,
+    pub account_status: AccountStatus,
+}
+
+/// Freeze a user's account
+///
+/// HTTP Endpoint: POST /admin/users/:id/freeze
+///
+/// The user can still sign in and see their wallets and history, but
+/// deposits (card and bank too), withdrawals, transfers, bank payouts,
+/// international transfers, crypto trades and their organizations' money
+/// answer 423 ACCOUNT_FROZEN until the account is unfrozen. Money can
+/// still be sent to them.
+///
+/// Error Responses:
+/// - 400: the admin's own account, or a closed one
+/// - 404: no such user
+pub async fn freeze_user(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<AccountStatusChange>, AppError> {
+    set_account_status(&state, admin_id, user_id, AccountStatus::Frozen).await
+}
+
+/// Unfreeze a user's account
+///
+/// HTTP Endpoint: POST /admin/users/:id/unfreeze
+pub async fn unfreeze_user(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<AccountStatusChange>, AppError> {
+    set_account_status(&state, admin_id, user_id, AccountStatus::Active).await
+}
+
+async fn set_account_status(
+    state: &AppState,
+    admin_id: Uuid,
+    user_id: Uuid,
+    status: AccountStatus,
+) -> Result<Json<AccountStatusChange>, AppError> {
+    admin_service::set_account_status(&state.pool, admin_id, user_id, status).await?;
+
+    tracing::warn!("🧊 User {} set to {} by admin {}", user_id, status.as_str(), admin_id);
+
+    Ok(Json(AccountStatusChange {
+        id: user_id,
+        account_status: status,
+    }))
+}
+
+/// Response for a deleted account
+#[derive(Debug, Serialize)]
+pub struct DeletedUser {
+    pub id: Uuid,
+    pub deleted_at: DateTime<Utc>,
+    /// Last moment the account can be restored
+    pub restorable_until: DateTime<Utc>,
+}
+
+/// Soft delete a user's account
+///
+/// HTTP Endpoint: DELETE /admin/users/:id
+///
+/// The user can no longer log in or receive transfers, and their web
+/// sessions end. Nothing is erased, see `restore_user`.
+pub async fn delete_user(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<DeletedUser>, AppError> {
+    let deleted_at = admin_service::delete_user(&state.pool, admin_id, user_id).await?;
+
+    tracing::warn!("🗑️ User {} deleted by admin {}", user_id, admin_id);
+
+    Ok(Json(DeletedUser {
+        id: user_id,
+        deleted_at,
+        restorable_until: deleted_at
+            + Duration::days(state.config.account_restore_window_days.into()),
+    }))
+}
+
+/// Restore a deleted account (within ACCOUNT_RESTORE_WINDOW_DAYS)
+///
+/// HTTP Endpoint: POST /admin/users/:id/restore
+pub async fn restore_user(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<UserResponse>, AppError> {
+    let user = admin_service::restore_user(
+        &state.pool,
+        user_id,
+        state.config.account_restore_window_days,
+        state.clock.as_ref(),
+    )
+    .await?;
+
+    tracing::warn!("♻️ User {} restored by admin {}", user_id, admin_id);
+
+    Ok(Json(UserResponse::from(user)))
+}
+
+/// Latency histograms for every database query since startup
+///
+/// HTTP Endpoint: GET /admin/metrics/queries
+pub async fn query_metrics(AdminUser(_): AdminUser) -> Json<Vec<QueryStats>> {
+    Json(metrics::snapshot())
+}
+
+/// Start a debug capture
+#[derive(Debug, Deserialize, Validate)]
+pub struct StartCaptureRequest {
+    /// Capture this user's requests
+    pub user_id: Option<Uuid>,
+    /// Capture requests whose path starts with this, e.g. "/api/wallet"
+    #[validate(length(max = 200))]
+    pub route: Option<String>,
+    /// How long to capture for (default 15, at most a day)
+    pub minutes: Option<i64>,
+}
+
+/// Which captured requests to list
+#[derive(Debug, Deserialize, Validate)]
+pub struct CaptureQuery {
+    /// Only this target's
+    pub target_id: Option<Uuid>,
+    /// How many, newest first (default 50)
+    #[validate(range(min = 1, max = 1000))]
+    pub limit: Option<usize>,
+}
+
+/// What the debug capture is doing and what it caught
+#[derive(Debug, Serialize)]
+pub struct DebugCaptureResponse {
+    /// How many exchanges are kept (DEBUG_CAPTURE_CAPACITY)
+    pub capacity: usize,
+    pub targets: Vec<CaptureTarget>,
+    pub exchanges: Vec<CapturedExchange>,
+}
+
+/// Running captures and the (sanitized) requests they recorded
+///
+/// HTTP Endpoint: GET /admin/debug-capture?target_id=...&limit=50
+pub async fn list_debug_captures(
+    AdminUser(_): AdminUser,
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<CaptureQuery>,
+) -> Json<DebugCaptureResponse> {
+    let capture = &state.debug_capture;
+    Json(DebugCaptureResponse {
+        capacity: capture.capacity(),
+        targets: capture.targets(),
+        exchanges: capture.exchanges(query.target_id, query.limit.unwrap_or(50)),
+    })
+}
+
+/// Start recording a user's or a route's requests and responses
+///
+/// HTTP Endpoint: POST /admin/debug-capture/targets
+///
+/// Request Body:
+/// ```json
+/// { "user_id": "550e8400-e29b-41d4-a716-446655440000", "route": "/api/wallet", "minutes": 30 }
+/// ```
+///
+/// Give a user, a route, or both. Credentials, passwords and card
+/// numbers are redacted before anything is kept.
+pub async fn start_debug_capture(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<StartCaptureRequest>,
+) -> Result<Json<CaptureTarget>, AppError> {
+    let target = state.debug_capture.add_target(
+        req.user_id,
+        req.route,
+        req.minutes.unwrap_or(15),
+        admin_id,
+    )?;
+
+    tracing::warn!(
+        "🔍 Debug capture {} started by admin {} (user {:?}, route {:?}) until {}",
+        target.id,
+        admin_id,
+        target.user_id,
+        target.route,
+        target.expires_at
+    );
+
+    Ok(Json(target))
+}
+
+/// Stop a capture before it expires (what it recorded is kept)
+///
+/// HTTP Endpoint: DELETE /admin/debug-capture/targets/:id
+pub async fn stop_debug_capture(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    Path(target_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    if !state.debug_capture.remove_target(target_id) {
+        return Err(AppError::not_found("Debug capture"));
+    }
+
+    tracing::warn!("🔍 Debug capture {} stopped by admin {}", target_id, admin_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Throw away everything captured so far
+///
+/// HTTP Endpoint: DELETE /admin/debug-capture
+pub async fn clear_debug_captures(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+) -> StatusCode {
+    let cleared = state.debug_capture.clear();
+
+    tracing::warn!("🔍 {} captured requests cleared by admin {}", cleared, admin_id);
+
+    StatusCode::NO_CONTENT
+}
+
+/// What an announcement is about (clients may show them differently)
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BroadcastKind {
+    #[default]
+    Announcement,
+    Maintenance,
+}
+
+impl BroadcastKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BroadcastKind::Announcement => "announcement",
+            BroadcastKind::Maintenance => "maintenance",
+        }
+    }
+}
+
+/// An announcement for everyone connected
+#[derive(Debug, Deserialize, Validate)]
+pub struct BroadcastRequest {
+    #[serde(default)]
+    pub kind: BroadcastKind,
+    #[validate(length(min = 1, max = 500))]
+    pub message: String,
+}
+
+/// How many open connections a broadcast went to
+#[derive(Debug, Serialize)]
+pub struct BroadcastResponse {
+    pub delivered_to: usize,
+}
+
+/// Send an announcement to every connected client
+///
+/// HTTP Endpoint: POST /admin/broadcast
+///
+/// Request Body:
+/// ```json
+/// { "kind": "maintenance", "message": "Transfers pause at 22:00 UTC for 10 minutes" }
+/// ```
+///
+/// Clients get `{ "type": "announcement", "kind": "maintenance", "message": "..." }`
+/// over the WebSocket. Only those connected now do: it isn't kept for later.
+///
+/// Error Responses:
+/// - 429 RATE_LIMITED: 5 broadcasts already went out in the last hour
+pub async fn broadcast(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<BroadcastRequest>,
+) -> Result<Json<BroadcastResponse>, AppError> {
+    let delivered_to = admin_service::broadcast(
+        &state.pool,
+        state.notification_service.as_ref(),
+        admin_id,
+        req.kind.as_str(),
+        &req.message,
+        state.clock.as_ref(),
+    )
+    .await?;
+
+    Ok(Json(BroadcastResponse { delivered_to }))
+}
+
+/// Replay a wallet's ledger and compare it to the stored balance
+///
+/// HTTP Endpoint: GET /admin/wallets/:id/verify
+///
+/// Read-only: a mismatch is reported (`consistent: false`, with the
+/// difference and the per-type totals), never corrected.
+pub async fn verify_wallet(
+    AdminUser(_): AdminUser,
+    State(state): State<AppState>,
+    Path(wallet_id): Path<Uuid>,
+) -> Result<Json<BalanceVerification>, AppError> {
+    let verification = integrity_service::verify_wallet(&state.pool, wallet_id).await?;
+
+    if !verification.consistent {
+        tracing::error!(
+            "🚨 Wallet {} balance is off by {} from its ledger",
+            wallet_id,
+            verification.difference
+        );
+    }
+
+    Ok(Json(verification))
+}
+
+/// Which archived transactions to bring back
+#[derive(Debug, Deserialize)]
+pub struct RestoreArchiveRequest {
+    /// Created at or after this moment
+    pub from: DateTime<Utc>,
+    /// Created before this moment
+    pub to: DateTime<Utc>,
+    /// Only this wallet (default: every wallet)
+    #[serde(default)]
+    pub wallet_id: Option<Uuid>,
+}
+
+/// Move archived transactions back into the live table
+///
+/// HTTP Endpoint: POST /admin/transactions/restore
+///
+/// Request Body:
+/// ```json
+/// { "from": "2017-01-01T00:00:00Z", "to": "2018-01-01T00:00:00Z", "wallet_id": "..." }
+/// ```
+///
+/// The restored rows show up in the wallet history again and are kept out
+/// of the archive for 30 days.
+pub async fn restore_archived_transactions(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    AppJson(req): AppJson<RestoreArchiveRequest>,
+) -> Result<Json<ArchiveRestore>, AppError> {
+    let restore = archive_service::restore_transactions(
+        &state.pool,
+        admin_id,
+        req.wallet_id,
+        req.from,
+        req.to,
+    )
+    .await?;
+
+    tracing::warn!(
+        "🗄️ {} archived transactions restored by admin {} ({} to {})",
+        restore.restored_count,
+        admin_id,
+        req.from,
+        req.to
+    );
+
+    Ok(Json(restore))
+}
+
+/// Reverse a completed transaction
+///
+/// HTTP Endpoint: POST /admin/transactions/:id/reverse
+///
+/// Takes the amount back out of the wallet it went into (or puts it back
+/// into the one it left) and answers with the transaction, now `REVERSED`.
+/// Only that one row changes: reversing a transfer means reversing both
+/// its sides.
+///
+/// Error Responses:
+/// - 404: no such transaction
+/// - 422 INSUFFICIENT_BALANCE: the wallet no longer holds the money
+/// - 422 TRANSACTION_FAILED: the transaction isn't COMPLETED, or is still
+///   held by a transfer waiting to be confirmed, a bank payout or an
+///   international transfer (cancel or return that instead)
+pub async fn reverse_transaction(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    Path(transaction_id): Path<Uuid>,
+) -> Result<Json<TransactionResponse>, AppError> {
+    let transaction =
+        admin_service::reverse_transaction(&state.pool, admin_id, transaction_id).await?;
+
+    Ok(Json(TransactionResponse::from(transaction)))
+}
+
+/// Query string for GET /admin/audit-log: `?admin_id=...&limit=50`
+#[derive(Debug, Deserialize, Validate)]
+pub struct AuditLogQuery {
+    /// Only this admin's actions (default: everyone's)
+    pub admin_id: Option<Uuid>,
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    pub limit: Option<u32>,
+}
+
+/// What admins changed, newest first
+///
+/// HTTP Endpoint: GET /admin/audit-log?admin_id=...&limit=50
+///
+/// Success Response (200 OK):
+/// ```json
+/// [
+///   {
+///     "id": "...",
+///     "admin_id": "...",
+///     "admin_email": "admin@example.com",
+///     "method": "DELETE",
+///     "path": "/api/admin/users/...",
+///     "status": 200,
+///     "created_at": "2026-10-17T09:30:00Z"
+///   }
+/// ]
+/// ```
+///
+/// Every non-GET request an admin makes to /api/admin is listed, including
+/// ones that were refused (see `status`).
+pub async fn get_audit_log(
+    AdminUser(_): AdminUser,
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<AuditLogQuery>,
+) -> Result<Json<Vec<AuditEntry>>, AppError> {
+    let entries = admin_service::audit_log(&state.pool, query.admin_id, query.limit).await?;
+
+    Ok(Json(entries))
+}
+
+/// Search every user's transactions by description, best matches first
+///
+/// HTTP Endpoint: GET /admin/transactions/search?q=refund&limit=50
+///
+/// Same query syntax as GET /transactions/search; each result also names
+/// the wallet's owner.
+pub async fn search_transactions(
+    AdminUser(_): AdminUser,
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<SearchQuery>,
+) -> Result<Json<Vec<OwnedTransactionMatch>>, AppError> {
+    let results =
+        search_service::search_all_transactions(&state.pool, &query.q, query.limit).await?;
+
+    Ok(Json(results))
+}
@@ -1,38 +1,303 @@
 use axum::{extract::State, http::StatusCode, Json};
-use crate::domain::models::{CreateUserRequest, LoginRequest, LoginResponse};
+use crate::domain::ids;
+use crate::domain::models::{
+    CreateUserRequest, ForgotPasswordRequest, LoginMethod, LoginRequest, LoginResponse,
+    ResetPasswordRequest, UnlockAccountRequest,
+};
 use crate::error::AppError;
+use crate::middleware::auth::{AuthToken, RequireScope, WalletWrite};
+use crate::middleware::client::ClientInfo;
+use crate::middleware::validation::ValidatedJson;
 use crate::routes::auth_routes::AppState;
-use crate::services::auth_service;
+use crate::services::referral_service::{self, ReferralTerms};
+use crate::services::login_throttle_service::{self, LockoutPolicy};
+use crate::services::{auth_service, login_history_service, password_reset_service, token_service};
+use jsonwebtoken::jwk::JwkSet;
 
 /// Register a new user
+///
+/// With a `referral_code`, the signup counts towards that user's referrals
+/// (an unknown code is a 400).
+#[utoipa::path(
+    post,
+    path = "/register",
+    tag = "auth",
+    request_body = CreateUserRequest,
+    responses(
+        (status = 201, description = "Registered and signed in", body = LoginResponse),
+        (status = 400, description = "Invalid fields, or an unknown referral code", body = api_types::ErrorBody),
+        (status = 409, description = "USER_ALREADY_EXISTS", body = api_types::ErrorBody),
+        (status = 503, description = "FEATURE_DISABLED: registrations are closed", body = api_types::ErrorBody)
+    )
+)]
 pub async fn register_handler(
     State(state): State<AppState>,
-    Json(req): Json<CreateUserRequest>,
+    client: ClientInfo,
+    ValidatedJson(req): ValidatedJson<CreateUserRequest>,
 ) -> Result<(StatusCode, Json<LoginResponse>), AppError> {
+    if !state.features.registrations_open {
+        return Err(AppError::feature_disabled("Registrations"));
+    }
+
+    let terms = ReferralTerms::from_config(&state.config);
+    let referrer =
+        referral_service::find_referrer(&state.pool, &terms, req.referral_code.as_deref()).await?;
+
+    let session_id = ids::new_id();
     let response = auth_service::register(
         &state.pool,
         &req.email,
         &req.password,
         &req.full_name,
-        &state.jwt_secret,
+        &state.jwt_keys,
+        Some(session_id),
+        state.clock.as_ref(),
+    )
+    .await?;
+    token_service::start_session(
+        &state.pool,
+        state.clock.as_ref(),
+        session_id,
+        response.user.id,
+        &client,
     )
     .await?;
 
+    if let Some(referrer) = referrer {
+        referral_service::attribute(
+            &state.pool,
+            state.clock.as_ref(),
+            &referrer,
+            response.user.id,
+            &response.user.email,
+            client.ip,
+        )
+        .await;
+    }
+
     Ok((StatusCode::CREATED, Json(response)))
 }
 
 /// Login an existing user
+///
+/// Repeated wrong passwords for an address slow it down, then lock it
+/// (see services/login_throttle_service.rs).
+///
+/// Error Responses:
+/// - 401 Unauthorized: Wrong email or password
+/// - 429 Too Many Requests (`TOO_MANY_ATTEMPTS`): Too many wrong passwords;
+///   wait and try again, or the account is locked until the emailed unlock
+///   link (or a password reset) is used
+#[utoipa::path(
+    post,
+    path = "/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Signed in", body = LoginResponse),
+        (status = 401, description = "INVALID_CREDENTIALS", body = api_types::ErrorBody),
+        (status = 429, description = "TOO_MANY_ATTEMPTS", body = api_types::ErrorBody)
+    )
+)]
 pub async fn login_handler(
     State(state): State<AppState>,
-    Json(req): Json<LoginRequest>,
+    client: ClientInfo,
+    ValidatedJson(req): ValidatedJson<LoginRequest>,
 ) -> Result<Json<LoginResponse>, AppError> {
-    let response = auth_service::login(
+    let session_id = ids::new_id();
+    let response = login_throttle_service::login(
         &state.pool,
+        &state.email_service,
+        state.clock.as_ref(),
+        &LockoutPolicy::from_config(&state.config),
         &req.email,
         &req.password,
-        &state.jwt_secret,
+        &state.jwt_keys,
+        Some(session_id),
+    )
+    .await?;
+    token_service::start_session(
+        &state.pool,
+        state.clock.as_ref(),
+        session_id,
+        response.user.id,
+        &client,
     )
     .await?;
 
+    login_history_service::record_login(
+        &state.pool,
+        state.geoip.as_ref(),
+        &state.email_service,
+        &state.notification_service,
+        response.user.id,
+        LoginMethod::Password,
+        client,
+    );
+
     Ok(Json(response))
 }
+
+/// Log out: revoke the token this request was made with
+///
+/// HTTP Endpoint: POST /logout
+///
+/// Headers:
+/// Authorization: Bearer <token>
+///
+/// Success Response: 204 No Content. The token is refused from now on;
+/// the user's other tokens keep working.
+#[utoipa::path(
+    post,
+    path = "/logout",
+    tag = "auth",
+    security(("bearer" = [])),
+    responses(
+        (status = 204, description = "This token is revoked"),
+        (status = 401, description = "INVALID_TOKEN", body = api_types::ErrorBody)
+    )
+)]
+pub async fn logout_handler(
+    State(state): State<AppState>,
+    AuthToken(claims): AuthToken,
+) -> Result<StatusCode, AppError> {
+    token_service::logout(&state.pool, &claims).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Log out of all devices: revoke every token and web session the user has
+///
+/// HTTP Endpoint: POST /logout/all
+///
+/// Headers:
+/// Authorization: Bearer <token>
+///
+/// Success Response: 204 No Content. Signing in again gives a new token.
+#[utoipa::path(
+    post,
+    path = "/logout/all",
+    tag = "auth",
+    security(("bearer" = [])),
+    responses(
+        (status = 204, description = "Every token and web session of the user is revoked"),
+        (status = 401, description = "INVALID_TOKEN", body = api_types::ErrorBody)
+    )
+)]
+pub async fn logout_all_handler(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, AppError> {
+    token_service::logout_everywhere(&state.pool, state.clock.as_ref(), user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Forgot password: email a reset link
+///
+/// HTTP Endpoint: POST /password/forgot
+///
+/// Request Body:
+/// ```json
+/// { "email": "alice@example.com" }
+/// ```
+///
+/// Success Response: 202 Accepted, whether or not the address has an
+/// account (so it can't be used to find out).
+#[utoipa::path(
+    post,
+    path = "/password/forgot",
+    tag = "auth",
+    request_body = ForgotPasswordRequest,
+    responses((status = 202, description = "A reset link is emailed if the address has an account"))
+)]
+pub async fn forgot_password_handler(
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<ForgotPasswordRequest>,
+) -> Result<StatusCode, AppError> {
+    password_reset_service::forgot(
+        &state.pool,
+        &state.email_service,
+        state.clock.as_ref(),
+        &state.config.public_url(),
+        &req.email,
+    )
+    .await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Set a new password with the emailed reset token
+///
+/// HTTP Endpoint: POST /password/reset
+///
+/// Request Body:
+/// ```json
+/// {
+///   "token": "3f9c...",
+///   "new_password": "correct-horse-battery"
+/// }
+/// ```
+///
+/// Success Response: 204 No Content. Every existing token and web session
+/// of the user is revoked; sign in with the new password.
+///
+/// Error Responses:
+/// - 400 Bad Request: Unknown, used or expired token
+#[utoipa::path(
+    post,
+    path = "/password/reset",
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 204, description = "Password changed; every session is signed out"),
+        (status = 400, description = "Unknown, used or expired token", body = api_types::ErrorBody)
+    )
+)]
+pub async fn reset_password_handler(
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<ResetPasswordRequest>,
+) -> Result<StatusCode, AppError> {
+    password_reset_service::reset(&state.pool, state.clock.as_ref(), &req.token, &req.new_password)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Unlock sign-in with the token from the account-locked email
+///
+/// HTTP Endpoint: POST /account/unlock
+///
+/// Request Body:
+/// ```json
+/// { "token": "7b1e..." }
+/// ```
+///
+/// Success Response: 204 No Content. The failed attempts are forgotten;
+/// sign in again.
+///
+/// Error Responses:
+/// - 400 Bad Request: Unknown or already used token
+#[utoipa::path(
+    post,
+    path = "/account/unlock",
+    tag = "auth",
+    request_body = UnlockAccountRequest,
+    responses(
+        (status = 204, description = "Sign-in unlocked"),
+        (status = 400, description = "Unknown or already used token", body = api_types::ErrorBody)
+    )
+)]
+pub async fn unlock_account_handler(
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<UnlockAccountRequest>,
+) -> Result<StatusCode, AppError> {
+    login_throttle_service::unlock(&state.pool, &req.token).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The public keys our tokens are signed with, as a JWK Set, so other
+/// services can check them (empty with HS256, which has no public key)
+///
+/// HTTP Endpoint: GET /.well-known/jwks.json
+pub async fn jwks(State(state): State<AppState>) -> Json<JwkSet> {
+    Json(state.jwt_keys.jwks())
+}
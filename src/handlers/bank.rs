@@ -0,0 +1,257 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use crate::domain::models::{
+    BankDepositRequest, BankPayoutRequest, BankPayoutResponse, LinkBankAccountsRequest,
+    LinkTokenResponse, LinkedBankAccountResponse, TransactionResponse,
+};
+use crate::error::AppError;
+use crate::middleware::auth::{AuthUser, RequireScope, WalletWrite};
+use crate::middleware::validation::ValidatedJson;
+use crate::routes::auth_routes::AppState;
+use crate::services::bank_provider::BankProvider;
+use crate::services::bank_service;
+
+// ============================================================================
+// BANK ACCOUNT HANDLERS
+// ============================================================================
+// Linking external bank accounts, depositing from them and paying out to
+// them. With BANK_PROVIDER=off every endpoint answers 503 "temporarily
+// disabled".
+
+/// The configured bank provider, or a 503 when bank linking is off
+fn provider(state: &AppState) -> Result<Arc<dyn BankProvider>, AppError> {
+    state
+        .bank_provider
+        .clone()
+        .ok_or_else(|| AppError::feature_disabled("Bank accounts"))
+}
+
+/// Get a token to open the bank provider's Link widget with
+///
+/// HTTP Endpoint: POST /bank/link-token
+///
+/// Headers:
+/// Authorization: Bearer <token>
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "link_token": "link-sandbox-...",
+///   "expiration": "..."
+/// }
+/// ```
+pub async fn create_link_token(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+) -> Result<Json<LinkTokenResponse>, AppError> {
+    let provider = provider(&state)?;
+    let token = bank_service::create_link_token(provider.as_ref(), user_id).await?;
+
+    Ok(Json(token))
+}
+
+/// Link the bank accounts the user picked in Link
+///
+/// HTTP Endpoint: POST /bank/accounts
+///
+/// Headers:
+/// Authorization: Bearer <token>
+///
+/// Request Body:
+/// ```json
+/// {
+///   "public_token": "public-sandbox-..."
+/// }
+/// ```
+///
+/// Success Response (201 Created):
+/// ```json
+/// [
+///   {
+///     "id": "...",
+///     "institution_name": "Chase",
+///     "account_name": "Plaid Checking",
+///     "mask": "0000",
+///     "subtype": "checking",
+///     "linked_at": "..."
+///   }
+/// ]
+/// ```
+/// Linking an account again refreshes it rather than adding a copy.
+pub async fn link_accounts(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<LinkBankAccountsRequest>,
+) -> Result<(StatusCode, Json<Vec<LinkedBankAccountResponse>>), AppError> {
+    let provider = provider(&state)?;
+    let accounts =
+        bank_service::link_accounts(&state.pool, provider.as_ref(), user_id, &req.public_token)
+            .await?;
+
+    Ok((StatusCode::CREATED, Json(accounts)))
+}
+
+/// List the authenticated user's linked bank accounts
+///
+/// HTTP Endpoint: GET /bank/accounts
+pub async fn list_accounts(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<LinkedBankAccountResponse>>, AppError> {
+    provider(&state)?;
+    let accounts = bank_service::list_accounts(&state.pool, user_id).await?;
+
+    Ok(Json(accounts))
+}
+
+/// Unlink a bank account
+///
+/// HTTP Endpoint: DELETE /bank/accounts/:id
+///
+/// Deposits already started from it still arrive.
+pub async fn unlink_account(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(account_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    provider(&state)?;
+    bank_service::unlink_account(&state.pool, user_id, account_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Deposit money from a linked bank account
+///
+/// HTTP Endpoint: POST /wallet/deposit/bank
+///
+/// Headers:
+/// Authorization: Bearer <token>
+///
+/// Request Body:
+/// ```json
+/// {
+///   "bank_account_id": "...",
+///   "amount": "100.00"
+/// }
+/// ```
+///
+/// Success Response (202 Accepted):
+/// ```json
+/// {
+///   "id": "...",
+///   "transaction_type": "DEPOSIT",
+///   "amount": "100.00",
+///   "description": "Bank deposit from Chase ••0000",
+///   "status": "PENDING",
+///   "created_at": "...",
+///   "completed_at": null,
+///   "failed_at": null,
+///   "reversed_at": null
+/// }
+/// ```
+/// The balance goes up once the bank transfer settles (usually a few
+/// business days); the transaction then shows as COMPLETED, or FAILED if
+/// the bank returned it. Only USD wallets can be funded from a bank.
+pub async fn deposit(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<BankDepositRequest>,
+) -> Result<(StatusCode, Json<TransactionResponse>), AppError> {
+    let provider = provider(&state)?;
+    let transaction = bank_service::deposit(
+        &state.pool,
+        provider.as_ref(),
+        user_id,
+        req.bank_account_id,
+        req.amount,
+    )
+    .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(TransactionResponse::from(transaction))))
+}
+
+/// Pay money out to a linked bank account
+///
+/// HTTP Endpoint: POST /wallet/payouts
+///
+/// Headers:
+/// Authorization: Bearer <token>
+///
+/// Request Body:
+/// ```json
+/// {
+///   "bank_account_id": "...",
+///   "amount": "100.00"
+/// }
+/// ```
+///
+/// Success Response (202 Accepted):
+/// ```json
+/// {
+///   "id": "...",
+///   "bank_account_id": "...",
+///   "transaction_id": "...",
+///   "amount": "100.00",
+///   "status": "PENDING",
+///   "return_reason": null,
+///   "created_at": "...",
+///   "settled_at": null,
+///   "returned_at": null,
+///   "failed_at": null
+/// }
+/// ```
+/// The money leaves the wallet at once (as a WITHDRAWAL). If the bank
+/// returns the payout it shows as RETURNED and the withdrawal is reversed.
+pub async fn payout(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<BankPayoutRequest>,
+) -> Result<(StatusCode, Json<BankPayoutResponse>), AppError> {
+    let provider = provider(&state)?;
+    let payout = bank_service::payout(
+        &state.pool,
+        provider.as_ref(),
+        user_id,
+        req.bank_account_id,
+        req.amount,
+    )
+    .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(payout)))
+}
+
+/// List the authenticated user's recent payouts, newest first
+///
+/// HTTP Endpoint: GET /wallet/payouts
+pub async fn list_payouts(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<BankPayoutResponse>>, AppError> {
+    provider(&state)?;
+    let payouts = bank_service::list_payouts(&state.pool, user_id).await?;
+
+    Ok(Json(payouts))
+}
+
+/// Transfer status callbacks from the bank provider
+///
+/// HTTP Endpoint: POST /webhooks/bank
+///
+/// Not authenticated with a user token: the provider signs each call
+/// (Plaid: the Plaid-Verification header), and unsigned calls get 401.
+pub async fn webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, AppError> {
+    let provider = provider(&state)?;
+    bank_service::handle_webhook(&state.pool, provider.as_ref(), &headers, &body).await?;
+
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,221 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use crate::domain::models::{
+    ApplePaySessionRequest, CardDepositRequest, CardPayConfigResponse, SavedCardDepositRequest,
+    SavedCardResponse, TransactionResponse,
+};
+use crate::error::AppError;
+use crate::middleware::auth::{AuthUser, RequireScope, WalletWrite};
+use crate::middleware::validation::ValidatedJson;
+use crate::routes::auth_routes::AppState;
+use crate::services::card_processor::CardProcessor;
+use crate::services::card_service;
+
+// ============================================================================
+// CARD TOP-UP HANDLERS
+// ============================================================================
+// Apple Pay and Google Pay top-ups. With CARD_PROCESSOR=off every endpoint
+// answers 503 "temporarily disabled".
+
+/// The configured card processor, or a 503 when card top-ups are off
+fn processor(state: &AppState) -> Result<Arc<dyn CardProcessor>, AppError> {
+    state
+        .card_processor
+        .clone()
+        .ok_or_else(|| AppError::feature_disabled("Card top-ups"))
+}
+
+/// Which device wallets the deposit page can offer
+///
+/// HTTP Endpoint: GET /wallet/deposit/card/config
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "apple_pay": {
+///     "merchant_id": "merchant.com.example.fintech",
+///     "display_name": "Fintech App",
+///     "supported_networks": ["visa", "masterCard", "amex", "discover"]
+///   },
+///   "google_pay": {
+///     "environment": "TEST",
+///     "merchant_id": null,
+///     "gateway": "checkoutltd",
+///     "gateway_merchant_id": "pk_...",
+///     "allowed_card_networks": ["VISA", "MASTERCARD", "AMEX", "DISCOVER"]
+///   },
+///   "currency": "USD"
+/// }
+/// ```
+pub async fn get_config(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<CardPayConfigResponse>, AppError> {
+    let processor = processor(&state)?;
+    let config = card_service::pay_config(
+        &state.pool,
+        processor.as_ref(),
+        state.apple_pay.as_deref(),
+        &state.config,
+        user_id,
+    )
+    .await?;
+
+    Ok(Json(config))
+}
+
+/// Top up the wallet with an Apple Pay or Google Pay payment token
+///
+/// HTTP Endpoint: POST /wallet/deposit/card
+///
+/// Request Body:
+/// ```json
+/// {
+///   "method": "APPLE_PAY",
+///   "amount": "50.00",
+///   "payment_data": { "version": "EC_v1", "data": "...", "signature": "...", "header": { ... } },
+///   "save_card": true
+/// }
+/// ```
+/// `save_card` (default false) keeps the card for later top-ups, see
+/// GET /wallet/cards.
+///
+/// Success Response (201 Created):
+/// ```json
+/// {
+///   "id": "...",
+///   "transaction_type": "DEPOSIT",
+///   "amount": "50.00",
+///   "description": "Apple Pay top-up",
+///   "status": "COMPLETED",
+///   ...
+/// }
+/// ```
+/// A declined payment answers 422 TRANSACTION_FAILED and shows up in the
+/// history as a FAILED deposit. Top-ups are charged in the wallet's currency.
+pub async fn deposit(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<CardDepositRequest>,
+) -> Result<(StatusCode, Json<TransactionResponse>), AppError> {
+    let processor = processor(&state)?;
+    let transaction =
+        card_service::deposit(&state.pool, processor.as_ref(), user_id, &req).await?;
+
+    Ok((StatusCode::CREATED, Json(TransactionResponse::from(transaction))))
+}
+
+/// Top up the wallet from a saved card
+///
+/// HTTP Endpoint: POST /wallet/deposit/saved-card
+///
+/// Request Body (`card_id` left out: the default card):
+/// ```json
+/// { "amount": "50.00", "card_id": "..." }
+/// ```
+///
+/// Success Response (201 Created): the deposit, as for POST
+/// /wallet/deposit/card, described e.g. "Visa •••• 4242 top-up"
+pub async fn deposit_saved(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<SavedCardDepositRequest>,
+) -> Result<(StatusCode, Json<TransactionResponse>), AppError> {
+    let processor = processor(&state)?;
+    let transaction =
+        card_service::deposit_saved(&state.pool, processor.as_ref(), user_id, &req).await?;
+
+    Ok((StatusCode::CREATED, Json(TransactionResponse::from(transaction))))
+}
+
+/// List the authenticated user's saved cards, the default first
+///
+/// HTTP Endpoint: GET /wallet/cards
+///
+/// Success Response (200 OK):
+/// ```json
+/// [
+///   {
+///     "id": "...",
+///     "method": "APPLE_PAY",
+///     "brand": "Visa",
+///     "last4": "4242",
+///     "expiry_month": 12,
+///     "expiry_year": 2029,
+///     "is_default": true,
+///     "saved_at": "2026-10-17T09:30:00Z"
+///   }
+/// ]
+/// ```
+pub async fn list_cards(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SavedCardResponse>>, AppError> {
+    processor(&state)?;
+    let cards = card_service::list_cards(&state.pool, user_id).await?;
+
+    Ok(Json(cards))
+}
+
+/// Remove a saved card
+///
+/// HTTP Endpoint: DELETE /wallet/cards/:id
+///
+/// If it was the default, the most recently saved remaining card becomes
+/// the default.
+pub async fn delete_card(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(card_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    processor(&state)?;
+    card_service::delete_card(&state.pool, user_id, card_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Make a saved card the default funding source
+///
+/// HTTP Endpoint: PUT /wallet/cards/:id/default
+pub async fn set_default_card(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(card_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    processor(&state)?;
+    card_service::set_default_card(&state.pool, user_id, card_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Start an Apple Pay merchant session (Apple Pay JS `onvalidatemerchant`)
+///
+/// HTTP Endpoint: POST /apple-pay/merchant-session
+///
+/// Request Body:
+/// ```json
+/// { "validation_url": "https://apple-pay-gateway.apple.com/paymentservices/startSession" }
+/// ```
+///
+/// Success Response (200 OK): Apple's merchant session object, to pass to
+/// `session.completeMerchantValidation()` as is. 503 when Apple Pay is not
+/// configured.
+pub async fn apple_pay_session(
+    AuthUser(_user_id): AuthUser,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<ApplePaySessionRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    processor(&state)?;
+    let merchant = state
+        .apple_pay
+        .as_ref()
+        .ok_or_else(|| AppError::feature_disabled("Apple Pay payments"))?;
+    let session = merchant.start_session(&req.validation_url).await?;
+
+    Ok(Json(session))
+}
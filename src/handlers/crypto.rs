@@ -0,0 +1,129 @@
+use axum::{extract::State, http::StatusCode, Json};
+use std::sync::Arc;
+use crate::domain::models::{
+    CryptoHoldingResponse, CryptoQuoteResponse, CryptoTradeRequest, CryptoTradeResponse,
+};
+use crate::error::AppError;
+use crate::middleware::auth::{AuthUser, RequireScope, WalletWrite};
+use crate::middleware::validation::ValidatedJson;
+use crate::routes::auth_routes::AppState;
+use crate::services::crypto_provider::CryptoProvider;
+use crate::services::crypto_service;
+
+// ============================================================================
+// CRYPTO HANDLERS
+// ============================================================================
+// BTC/ETH balances bought and sold against the USD wallet. With
+// CRYPTO_PROVIDER=off every endpoint answers 503 "temporarily disabled".
+
+/// The configured price provider, or a 503 when crypto trading is off
+fn provider(state: &AppState) -> Result<Arc<dyn CryptoProvider>, AppError> {
+    state
+        .crypto_provider
+        .clone()
+        .ok_or_else(|| AppError::feature_disabled("Crypto trading"))
+}
+
+/// Current buy and sell prices
+///
+/// HTTP Endpoint: GET /crypto/quotes
+///
+/// Success Response (200 OK):
+/// ```json
+/// [
+///   {
+///     "asset": "BTC",
+///     "buy_price": "60300.00",
+///     "sell_price": "59700.00",
+///     "provider": "coinbase",
+///     "quoted_at": "..."
+///   }
+/// ]
+/// ```
+pub async fn get_quotes(
+    AuthUser(_user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CryptoQuoteResponse>>, AppError> {
+    let provider = provider(&state)?;
+    let quotes = crypto_service::quotes(provider.as_ref()).await?;
+
+    Ok(Json(quotes))
+}
+
+/// The authenticated user's crypto balances
+///
+/// HTTP Endpoint: GET /crypto/holdings
+///
+/// Success Response (200 OK):
+/// ```json
+/// [
+///   {
+///     "asset": "BTC",
+///     "quantity": "0.00414593",
+///     "usd_value": "247.51"
+///   }
+/// ]
+/// ```
+/// `usd_value` is what selling it all would raise now (null when no price
+/// is available).
+pub async fn get_holdings(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CryptoHoldingResponse>>, AppError> {
+    let provider = provider(&state)?;
+    let holdings = crypto_service::holdings(&state.pool, provider.as_ref(), user_id).await?;
+
+    Ok(Json(holdings))
+}
+
+/// Buy or sell crypto against the USD wallet
+///
+/// HTTP Endpoint: POST /crypto/trades
+///
+/// Request Body (either `quantity` or `usd_amount`):
+/// ```json
+/// {
+///   "asset": "BTC",
+///   "side": "BUY",
+///   "usd_amount": "250.00"
+/// }
+/// ```
+///
+/// Success Response (201 Created):
+/// ```json
+/// {
+///   "id": "...",
+///   "asset": "BTC",
+///   "side": "BUY",
+///   "quantity": "0.00414593",
+///   "price": "60300.00",
+///   "usd_amount": "250.00",
+///   "transaction_id": "...",
+///   "created_at": "..."
+/// }
+/// ```
+/// Trades execute at once at the provider's current price. Only USD
+/// wallets can trade.
+pub async fn create_trade(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<CryptoTradeRequest>,
+) -> Result<(StatusCode, Json<CryptoTradeResponse>), AppError> {
+    let provider = provider(&state)?;
+    let trade = crypto_service::trade(&state.pool, provider.as_ref(), user_id, &req).await?;
+
+    Ok((StatusCode::CREATED, Json(trade)))
+}
+
+/// The authenticated user's recent trades, newest first
+///
+/// HTTP Endpoint: GET /crypto/trades
+pub async fn list_trades(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CryptoTradeResponse>>, AppError> {
+    provider(&state)?;
+    let trades = crypto_service::list_trades(&state.pool, user_id).await?;
+
+    Ok(Json(trades))
+}
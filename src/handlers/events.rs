@@ -0,0 +1,135 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::stream::{self, Stream};
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::domain::models::WebSocketQuery;
+use crate::error::AppError;
+use crate::middleware::auth::AuthUser;
+use crate::middleware::validation::ValidatedQuery;
+use crate::routes::auth_routes::AppState;
+use crate::services::notification_service::{self, LiveNotification, LiveSink};
+
+// ============================================================================
+// SERVER-SENT EVENTS
+// ============================================================================
+// The same live notifications as /ws, for clients behind proxies that block
+// WebSockets. Each one is a plain `message` event whose data is the JSON a
+// WebSocket client would get, and whose id is its sequence, so an
+// EventSource that reconnects sends it back as Last-Event-ID and is
+// replayed what it missed.
+
+/// Events waiting for a slow client before we stop reading its channel
+const BUFFERED_EVENTS: usize = 32;
+
+/// Stream live notifications as Server-Sent Events
+///
+/// HTTP Endpoint: GET /events?since=42
+///
+/// Resumes after the `Last-Event-ID` header (sent by a reconnecting
+/// EventSource) or else `since`: the notifications missed are sent first,
+/// then `{ "type": "connected", "sequence": 45 }` with the last sequence
+/// sent as its id, then live ones. A comment line every
+/// WS_PING_INTERVAL_SECS keeps proxies from closing it.
+///
+/// Error Responses:
+/// - 401 Unauthorized: No valid token or login cookie
+/// - 503 FEATURE_DISABLED: Live updates are switched off
+pub async fn events_handler(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<WebSocketQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    if !state.features.websocket_enabled {
+        return Err(AppError::feature_disabled("Live updates"));
+    }
+    let since = last_event_id(&headers).or(query.since);
+    let keep_alive = Duration::from_secs(state.config.ws_ping_interval_secs);
+
+    // Registered before the inbox is read, as for /ws, so nothing sent in
+    // between is lost
+    let (tx, rx) = mpsc::unbounded_channel::<LiveNotification>();
+    let connection_id = state.notification_service.add_client(user_id, tx).await;
+
+    let (events_tx, events_rx) = mpsc::channel::<Event>(BUFFERED_EVENTS);
+    tokio::spawn(forward(state, user_id, connection_id, since, rx, events_tx));
+
+    let events = stream::unfold(events_rx, |mut events_rx| async move {
+        events_rx.recv().await.map(|event| (Ok(event), events_rx))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::new().interval(keep_alive)))
+}
+
+/// The sequence a reconnecting EventSource last saw, if it sent a valid one
+fn last_event_id(headers: &HeaderMap) -> Option<i64> {
+    headers
+        .get("Last-Event-ID")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .filter(|sequence| *sequence >= 0)
+}
+
+/// An event for one notification, carrying its sequence as the id
+fn event(message: String, sequence: i64) -> Event {
+    let event = Event::default().data(message);
+    if sequence == 0 {
+        event
+    } else {
+        event.id(sequence.to_string())
+    }
+}
+
+/// Send the client what it missed, then live notifications, until it goes
+/// away or the server shuts down; then forget the connection
+async fn forward(
+    state: AppState,
+    user_id: Uuid,
+    connection_id: Uuid,
+    since: Option<i64>,
+    mut rx: mpsc::UnboundedReceiver<LiveNotification>,
+    events: mpsc::Sender<Event>,
+) {
+    let notifier = state.notification_service.clone();
+    let mut sink = events.clone();
+    let send = async {
+        let Some(replayed) = notification_service::catch_up(&state.pool, user_id, since, &mut sink).await else {
+            return;
+        };
+        while let Some(notification) = rx.recv().await {
+            if !replayed.is_new(&notification) {
+                continue;
+            }
+            if !sink.deliver(notification.message, notification.sequence).await {
+                return;
+            }
+        }
+    };
+
+    // The client hanging up drops the stream (and `events`' receiver)
+    tokio::select! {
+        _ = send => {}
+        _ = events.closed() => {}
+        _ = notifier.closing() => {
+            tracing::debug!("Closing event stream for user {}: shutting down", user_id);
+        }
+    }
+
+    notifier.remove_client(&user_id, connection_id).await;
+}
+
+#[async_trait::async_trait]
+impl LiveSink for mpsc::Sender<Event> {
+    async fn deliver(&mut self, message: String, sequence: i64) -> bool {
+        self.send(event(message, sequence)).await.is_ok()
+    }
+}
@@ -0,0 +1,42 @@
+use axum::{
+    extract::{Path, State},
+    response::Response,
+};
+use crate::domain::models::FileDownloadQuery;
+use crate::error::AppError;
+use crate::handlers::download;
+use crate::middleware::validation::ValidatedQuery;
+use crate::routes::auth_routes::AppState;
+use crate::services::storage;
+
+// ============================================================================
+// FILE DOWNLOAD HANDLERS
+// ============================================================================
+
+/// A stored file, through a link from the local storage backend
+///
+/// HTTP Endpoint: GET /files/*key?expires=1767225600&signature=9f2c...
+///
+/// No session needed: the signature is the permission. Links come from
+/// `StorageService::presigned_url` (e.g. an export with `?link=true`).
+///
+/// Success Response (200 OK): the file, as a download
+/// Error Response (401): expired link or bad signature
+pub async fn get_file(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    ValidatedQuery(query): ValidatedQuery<FileDownloadQuery>,
+) -> Result<Response, AppError> {
+    if !storage::verify_download(&state.jwt_secret, &key, query.expires, &query.signature) {
+        return Err(AppError::Unauthorized);
+    }
+
+    let object = state
+        .storage
+        .get(&key)
+        .await?
+        .ok_or_else(|| AppError::not_found("File"))?;
+    let file_name = key.rsplit('/').next().unwrap_or(&key).to_string();
+
+    Ok(download(&object.content_type, file_name, object.body))
+}
@@ -0,0 +1,204 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use crate::build_info;
+use crate::config::FeatureFlags;
+use crate::routes::auth_routes::AppState;
+use crate::services::rate_limiter::RateLimitTiers;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+
+// ============================================================================
+// HEALTH CHECK
+// ============================================================================
+// For load balancers and uptime checks. It isn't rate limited and needs no
+// login, so it only reports operational facts, never user data.
+
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    /// "ok" or "degraded"
+    pub status: &'static str,
+    /// "ok" if the database answered, "unavailable" otherwise
+    pub database: &'static str,
+    /// The active APP_ENV profile
+    pub environment: String,
+    /// The rate limit currently applied to each client IP
+    pub rate_limit: RateLimitInfo,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RateLimitInfo {
+    /// For routes in no other tier
+    pub max_requests: u32,
+    pub window_secs: u64,
+    /// Every tier's allowance per window
+    pub tiers: RateLimitTiers,
+}
+
+/// Report whether the server can reach the database, plus its active settings
+///
+/// HTTP Endpoint: GET /health
+///
+/// Returns 200 when healthy, 503 when the database is unreachable.
+pub async fn health(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+    let database_ok = sqlx::query("SELECT 1").execute(&state.pool).await.is_ok();
+
+    let status = if database_ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(HealthResponse {
+            status: if database_ok { "ok" } else { "degraded" },
+            database: if database_ok { "ok" } else { "unavailable" },
+            environment: state.config.app_env.to_string(),
+            rate_limit: RateLimitInfo {
+                max_requests: state.config.rate_limit_max_requests,
+                window_secs: state.config.rate_limit_window_secs,
+                tiers: RateLimitTiers::from_config(&state.config),
+            },
+        }),
+    )
+}
+
+// ============================================================================
+// KUBERNETES PROBES
+// ============================================================================
+// /healthz answers as long as the process can serve requests, so a restart
+// is only triggered when it really is stuck. /readyz checks the dependencies
+// (the database, and the SMTP relay if READINESS_CHECK_SMTP is on) so a
+// load balancer stops sending traffic while one of them is down. Error
+// details go to the log, not into the response.
+
+/// How long a single readiness check may take before it counts as failed
+const READINESS_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize)]
+pub struct LivenessResponse {
+    pub status: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    /// "ready" or "not_ready"
+    pub status: &'static str,
+    /// "ok" or "failed" for each dependency checked
+    pub checks: BTreeMap<&'static str, &'static str>,
+    /// The dependencies that failed, empty when ready
+    pub failed: Vec<&'static str>,
+}
+
+/// Report that the process is alive
+///
+/// HTTP Endpoint: GET /healthz
+///
+/// Always 200 `{"status": "ok"}`; it touches no dependencies.
+pub async fn healthz() -> Json<LivenessResponse> {
+    Json(LivenessResponse { status: "ok" })
+}
+
+/// Report whether the server can take traffic
+///
+/// HTTP Endpoint: GET /readyz
+///
+/// Success Response (200 OK):
+/// ```json
+/// {"status": "ready", "checks": {"database": "ok"}, "failed": []}
+/// ```
+///
+/// Error Responses:
+/// - 503 Service Unavailable: a dependency failed, e.g.
+///   `{"status": "not_ready", "checks": {"database": "ok", "smtp": "failed"}, "failed": ["smtp"]}`
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let mut checks = BTreeMap::new();
+
+    let database = check("database", async {
+        sqlx::query("SELECT 1")
+            .execute(&state.pool)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    });
+    checks.insert("database", database.await);
+
+    if state.config.readiness_check_smtp {
+        checks.insert("smtp", check("smtp", state.email_service.check()).await);
+    }
+
+    let failed: Vec<&'static str> = checks
+        .iter()
+        .filter(|(_, outcome)| **outcome != "ok")
+        .map(|(name, _)| *name)
+        .collect();
+    let (status_code, status) = if failed.is_empty() {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+    };
+
+    (status_code, Json(ReadinessResponse { status, checks, failed }))
+}
+
+/// Run one readiness check with a timeout, logging why it failed
+async fn check(
+    name: &str,
+    probe: impl Future<Output = Result<(), String>>,
+) -> &'static str {
+    match tokio::time::timeout(READINESS_CHECK_TIMEOUT, probe).await {
+        Ok(Ok(())) => "ok",
+        Ok(Err(e)) => {
+            tracing::warn!("Readiness check {} failed: {}", name, e);
+            "failed"
+        }
+        Err(_) => {
+            tracing::warn!("Readiness check {} timed out", name);
+            "failed"
+        }
+    }
+}
+
+/// What GET /api/version returns
+#[derive(Debug, Serialize)]
+pub struct VersionResponse {
+    pub version: &'static str,
+    pub git_sha: &'static str,
+    pub built_at: DateTime<Utc>,
+    /// Cargo features compiled in, e.g. ["memory-repo"]
+    pub cargo_features: Vec<&'static str>,
+    /// The `[features]` switches as currently configured
+    pub features: FeatureFlags,
+}
+
+/// Report exactly which build is running
+///
+/// HTTP Endpoint: GET /api/version
+///
+/// No login needed (it names no user data), so deploy scripts can check
+/// that the right commit went out:
+///
+/// ```json
+/// {
+///   "version": "0.1.0",
+///   "git_sha": "3f2a9c1e0b...",
+///   "built_at": "2026-10-17T06:00:00Z",
+///   "cargo_features": [],
+///   "features": {
+///     "transfers_enabled": true,
+///     "registrations_open": true,
+///     "websocket_enabled": true
+///   }
+/// }
+/// ```
+pub async fn version(State(state): State<AppState>) -> Json<VersionResponse> {
+    Json(VersionResponse {
+        version: build_info::VERSION,
+        git_sha: build_info::GIT_SHA,
+        built_at: build_info::built_at(),
+        cargo_features: build_info::cargo_features(),
+        features: state.features,
+    })
+}
@@ -0,0 +1,210 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+use crate::domain::models::{
+    InternationalQuoteRequest, InternationalQuoteResponse, InternationalTransferQueueQuery,
+    InternationalTransferRequest, InternationalTransferResponse, InternationalTransferStatus,
+    InternationalTransferStatusRequest,
+};
+use crate::error::AppError;
+use crate::middleware::auth::{AdminUser, AuthUser, RequireScope, WalletWrite};
+use crate::middleware::validation::{ValidatedJson, ValidatedQuery};
+use crate::routes::auth_routes::AppState;
+use crate::services::international_service;
+
+// ============================================================================
+// INTERNATIONAL TRANSFER HANDLERS
+// ============================================================================
+// Transfers to bank accounts abroad. Quoting and sending answer 503 while
+// FEATURE_TRANSFERS_ENABLED is off, like domestic transfers; tracking
+// keeps working.
+
+/// What an international transfer would cost
+///
+/// HTTP Endpoint: POST /wallet/international-transfers/quote
+///
+/// Request Body:
+/// ```json
+/// { "amount": "500.00", "destination_currency": "EUR" }
+/// ```
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "source_currency": "USD",
+///   "amount": "500.00",
+///   "correspondent_fee": "25.00",
+///   "total_debit": "525.00",
+///   "destination_currency": "EUR",
+///   "mid_rate": "0.921400",
+///   "rate": "0.916793",
+///   "fx_margin_percent": "0.50",
+///   "destination_amount": "458.40",
+///   "rate_fetched_at": "2026-10-17T09:00:00Z"
+/// }
+/// ```
+pub async fn quote(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<InternationalQuoteRequest>,
+) -> Result<Json<InternationalQuoteResponse>, AppError> {
+    if !state.features.transfers_enabled {
+        return Err(AppError::feature_disabled("Transfers"));
+    }
+
+    let quote = international_service::quote(
+        &state.pool,
+        &state.exchange_rates,
+        &state.config,
+        user_id,
+        req.amount,
+        req.destination_currency,
+    )
+    .await?;
+
+    Ok(Json(quote))
+}
+
+/// Send money to a bank account abroad
+///
+/// HTTP Endpoint: POST /wallet/international-transfers
+///
+/// Request Body:
+/// ```json
+/// {
+///   "beneficiary_name": "Erika Mustermann",
+///   "iban": "DE89 3704 0044 0532 0130 00",
+///   "bic": "COBADEFFXXX",
+///   "amount": "500.00",
+///   "destination_currency": "EUR",
+///   "reference": "Invoice 2026-114"
+/// }
+/// ```
+///
+/// Success Response (201 Created): the transfer (see GET
+/// /wallet/international-transfers/:id), SUBMITTED, with the rate and fee
+/// it was sent at. The wallet is debited `total_debit` straight away.
+///
+/// Error Responses:
+/// - 400: bad IBAN (wrong length or check digits), bad BIC, or a BIC from
+///   another country than the IBAN
+/// - 422 INSUFFICIENT_BALANCE: the wallet can't cover amount + fee
+/// - 503: exchange rates unavailable, or transfers paused
+pub async fn submit(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<InternationalTransferRequest>,
+) -> Result<(StatusCode, Json<InternationalTransferResponse>), AppError> {
+    if !state.features.transfers_enabled {
+        return Err(AppError::feature_disabled("Transfers"));
+    }
+
+    let transfer = international_service::submit(
+        &state.pool,
+        &state.exchange_rates,
+        &state.config,
+        user_id,
+        &req,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(transfer)))
+}
+
+/// List the authenticated user's international transfers, newest first
+///
+/// HTTP Endpoint: GET /wallet/international-transfers
+pub async fn list_transfers(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<InternationalTransferResponse>>, AppError> {
+    let transfers = international_service::list_transfers(&state.pool, user_id).await?;
+
+    Ok(Json(transfers))
+}
+
+/// Track one international transfer
+///
+/// HTTP Endpoint: GET /wallet/international-transfers/:id
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "id": "...",
+///   "transaction_id": "...",
+///   "beneficiary_name": "Erika Mustermann",
+///   "iban": "DE89 •••• 3000",
+///   "bic": "COBADEFFXXX",
+///   "reference": "Invoice 2026-114",
+///   "source_currency": "USD",
+///   "amount": "500.00",
+///   "correspondent_fee": "25.00",
+///   "total_debit": "525.00",
+///   "destination_currency": "EUR",
+///   "mid_rate": "0.921400",
+///   "rate": "0.916793",
+///   "destination_amount": "458.40",
+///   "status": "IN_TRANSIT",
+///   "tracking_reference": "b2c1f0e4-6a3d-4d8e-9f55-0c7a2e1d9b31",
+///   "return_reason": null,
+///   "submitted_at": "2026-10-17T09:30:00Z",
+///   "in_transit_at": "2026-10-17T14:00:00Z",
+///   "settled_at": null,
+///   "returned_at": null
+/// }
+/// ```
+pub async fn get_transfer(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    Path(transfer_id): Path<Uuid>,
+) -> Result<Json<InternationalTransferResponse>, AppError> {
+    let transfer = international_service::get_transfer(&state.pool, user_id, transfer_id).await?;
+
+    Ok(Json(transfer))
+}
+
+/// International transfers waiting for operations, oldest first
+///
+/// HTTP Endpoint: GET /admin/international-transfers?status=SUBMITTED
+///
+/// `status` defaults to SUBMITTED (not sent yet); IN_TRANSIT lists the
+/// ones waiting to settle.
+pub async fn queue(
+    AdminUser(_): AdminUser,
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<InternationalTransferQueueQuery>,
+) -> Result<Json<Vec<InternationalTransferResponse>>, AppError> {
+    let status = query.status.unwrap_or(InternationalTransferStatus::Submitted);
+    let transfers = international_service::queue(&state.pool, status).await?;
+
+    Ok(Json(transfers))
+}
+
+/// Move an international transfer along
+///
+/// HTTP Endpoint: PUT /admin/international-transfers/:id/status
+///
+/// Request Body:
+/// ```json
+/// { "status": "IN_TRANSIT", "tracking_reference": "b2c1f0e4-6a3d-4d8e-9f55-0c7a2e1d9b31" }
+/// ```
+/// or `{ "status": "SETTLED" }`, or
+/// `{ "status": "RETURNED", "reason": "Beneficiary account closed" }`
+/// (the withdrawal is reversed and the money goes back to the wallet).
+///
+/// Success Response (200 OK): the updated transfer. Repeating the current
+/// status changes nothing; any other step out of order answers 400.
+pub async fn update_status(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    Path(transfer_id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<InternationalTransferStatusRequest>,
+) -> Result<Json<InternationalTransferResponse>, AppError> {
+    let transfer =
+        international_service::advance(&state.pool, admin_id, transfer_id, &req).await?;
+
+    Ok(Json(transfer))
+}
@@ -1,5 +1,68 @@
+pub mod accounting;
+pub mod admin;
 pub mod auth;
+pub mod bank;
+pub mod card;
+pub mod crypto;
+pub mod events;
+pub mod files;
+pub mod health;
+pub mod international;
+pub mod open_banking;
+pub mod organization;
+pub mod payment_request;
+pub mod rates;
+pub mod referral;
+pub mod scheduled_transfer;
+pub mod sso;
+pub mod tax;
 pub mod user;
 pub mod wallet;
 pub mod web;
 pub mod ws;
+
+use crate::domain::models::DownloadLink;
+use crate::error::AppError;
+use crate::services::storage::{self, StorageService};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use uuid::Uuid;
+
+/// How long an export's download link works
+const EXPORT_LINK_TTL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+/// A file the browser saves instead of showing (CSV and PDF exports)
+pub(crate) fn download(content_type: &str, file_name: String, body: Vec<u8>) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Put an export in storage and answer with a link to it instead
+///
+/// For clients that fetch the file later or elsewhere (`?link=true`).
+pub(crate) async fn download_link(
+    storage: &dyn StorageService,
+    user_id: Uuid,
+    content_type: &str,
+    file_name: String,
+    body: Vec<u8>,
+) -> Result<Response, AppError> {
+    let key = storage::export_key(user_id, &file_name);
+    storage.put(&key, content_type, body).await?;
+
+    let link = DownloadLink {
+        url: storage.presigned_url(&key, EXPORT_LINK_TTL)?,
+        expires_at: chrono::Utc::now() + chrono::Duration::seconds(EXPORT_LINK_TTL.as_secs() as i64),
+    };
+    Ok(Json(link).into_response())
+}
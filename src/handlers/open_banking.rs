@@ -0,0 +1,296 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Form, Json,
+};
+use uuid::Uuid;
+use crate::domain::models::{
+    ConsentResponse, CreateConsentRequest, HistoryQuery, OpenBankingAccountResponse,
+    OpenBankingBalanceResponse, OpenBankingClientResponse, OpenBankingTokenRequest,
+    OpenBankingTokenResponse, RegisterOpenBankingClientRequest, TransactionPageResponse,
+};
+use crate::error::AppError;
+use crate::middleware::auth::{AdminUser, AuthUser, RequireScope, WalletWrite};
+use crate::middleware::open_banking::OpenBankingClient;
+use crate::middleware::validation::{ValidatedJson, ValidatedQuery};
+use crate::routes::auth_routes::AppState;
+use crate::services::open_banking_service;
+
+// ============================================================================
+// OPEN BANKING HANDLERS
+// ============================================================================
+// Three audiences:
+// - Third parties, under /open-banking, with Open Banking tokens
+//   (`OpenBankingClient`): the token endpoint, their consents, and the
+//   read-only account data those consents cover
+// - Users, under /api/open-banking, with their normal login: see,
+//   authorise, reject and revoke consents
+// - Admins, under /api/admin/open-banking: register and revoke clients
+
+// ============================================================================
+// THIRD PARTY ENDPOINTS (/open-banking)
+// ============================================================================
+
+/// OAuth 2.0 token endpoint (client credentials grant)
+///
+/// HTTP Endpoint: POST /open-banking/token
+///
+/// Request Body (application/x-www-form-urlencoded):
+/// ```text
+/// grant_type=client_credentials&client_id=ob_...&client_secret=...
+///     &consent_id=...&scope=accounts%20balances
+/// ```
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "access_token": "...",
+///   "token_type": "Bearer",
+///   "expires_in": 3600,
+///   "scope": "accounts balances"
+/// }
+/// ```
+/// Leave out `consent_id` for a token that manages consents
+/// (`"scope": "consents"`).
+pub async fn token(
+    State(state): State<AppState>,
+    Form(req): Form<OpenBankingTokenRequest>,
+) -> Result<Json<OpenBankingTokenResponse>, AppError> {
+    let token = open_banking_service::issue_token(&state.pool, &req).await?;
+
+    Ok(Json(token))
+}
+
+/// Ask a user for access to their accounts
+///
+/// HTTP Endpoint: POST /open-banking/consents
+///
+/// Headers:
+/// Authorization: Bearer <consent-management token>
+///
+/// Request Body:
+/// ```json
+/// { "scopes": ["accounts", "balances"], "expires_at": "2026-12-31T00:00:00Z" }
+/// ```
+///
+/// Success Response (201 Created):
+/// ```json
+/// {
+///   "id": "...",
+///   "client_id": "ob_...",
+///   "client_name": "Budget Buddy",
+///   "scopes": ["accounts", "balances"],
+///   "status": "AWAITING_AUTHORISATION",
+///   "expires_at": "2026-12-31T00:00:00Z",
+///   "created_at": "...",
+///   "authorised_at": null,
+///   "revoked_at": null
+/// }
+/// ```
+/// The user authorises it with POST /api/open-banking/consents/:id/authorise.
+pub async fn create_consent(
+    OpenBankingClient(access): OpenBankingClient,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<CreateConsentRequest>,
+) -> Result<(StatusCode, Json<ConsentResponse>), AppError> {
+    let consent = open_banking_service::create_consent(&state.pool, &access, &req).await?;
+
+    Ok((StatusCode::CREATED, Json(consent)))
+}
+
+/// One of the client's consents, e.g. to see whether it was authorised
+///
+/// HTTP Endpoint: GET /open-banking/consents/:id
+pub async fn get_consent(
+    OpenBankingClient(access): OpenBankingClient,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ConsentResponse>, AppError> {
+    let consent = open_banking_service::get_client_consent(&state.pool, &access, id).await?;
+
+    Ok(Json(consent))
+}
+
+/// Withdraw one of the client's consents
+///
+/// HTTP Endpoint: DELETE /open-banking/consents/:id
+pub async fn revoke_consent(
+    OpenBankingClient(access): OpenBankingClient,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    open_banking_service::revoke_client_consent(&state.pool, &access, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The consenting user's accounts (`accounts` scope)
+///
+/// HTTP Endpoint: GET /open-banking/accounts
+///
+/// Success Response (200 OK):
+/// ```json
+/// [ { "account_id": "...", "currency": "USD" } ]
+/// ```
+pub async fn list_accounts(
+    OpenBankingClient(access): OpenBankingClient,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<OpenBankingAccountResponse>>, AppError> {
+    let accounts = open_banking_service::accounts(&state.pool, &access).await?;
+
+    Ok(Json(accounts))
+}
+
+/// An account's balance (`balances` scope)
+///
+/// HTTP Endpoint: GET /open-banking/accounts/:id/balances
+///
+/// Success Response (200 OK):
+/// ```json
+/// { "account_id": "...", "balance": "250.00", "currency": "USD", "as_of": "..." }
+/// ```
+pub async fn get_balance(
+    OpenBankingClient(access): OpenBankingClient,
+    State(state): State<AppState>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<OpenBankingBalanceResponse>, AppError> {
+    let balance = open_banking_service::balance(&state.pool, &access, account_id).await?;
+
+    Ok(Json(balance))
+}
+
+/// One page of an account's transactions (`transactions` scope)
+///
+/// HTTP Endpoint: GET /open-banking/accounts/:id/transactions?limit=20&cursor=...
+///
+/// Same page format and filters as GET /api/transactions.
+pub async fn list_transactions(
+    OpenBankingClient(access): OpenBankingClient,
+    State(state): State<AppState>,
+    Path(account_id): Path<Uuid>,
+    ValidatedQuery(query): ValidatedQuery<HistoryQuery>,
+) -> Result<Json<TransactionPageResponse>, AppError> {
+    let page = open_banking_service::transactions(&state.pool, &access, account_id, &query).await?;
+
+    Ok(Json(TransactionPageResponse::from(page)))
+}
+
+// ============================================================================
+// USER ENDPOINTS (/api/open-banking)
+// ============================================================================
+
+/// Consents the authenticated user has answered, newest first
+///
+/// HTTP Endpoint: GET /open-banking/consents
+pub async fn list_my_consents(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ConsentResponse>>, AppError> {
+    let consents = open_banking_service::list_user_consents(&state.pool, user_id).await?;
+
+    Ok(Json(consents))
+}
+
+/// A consent the user was asked for (or answered), to show before deciding
+///
+/// HTTP Endpoint: GET /open-banking/consents/:id
+pub async fn get_my_consent(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ConsentResponse>, AppError> {
+    let consent = open_banking_service::get_user_consent(&state.pool, user_id, id).await?;
+
+    Ok(Json(consent))
+}
+
+/// Authorise a consent, giving the third party its scopes on this account
+///
+/// HTTP Endpoint: POST /open-banking/consents/:id/authorise
+pub async fn authorise_consent(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ConsentResponse>, AppError> {
+    let consent = open_banking_service::answer_consent(&state.pool, user_id, id, true).await?;
+
+    Ok(Json(consent))
+}
+
+/// Reject a consent
+///
+/// HTTP Endpoint: POST /open-banking/consents/:id/reject
+pub async fn reject_consent(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ConsentResponse>, AppError> {
+    let consent = open_banking_service::answer_consent(&state.pool, user_id, id, false).await?;
+
+    Ok(Json(consent))
+}
+
+/// Revoke a consent the user gave; the third party's tokens stop working
+///
+/// HTTP Endpoint: DELETE /open-banking/consents/:id
+pub async fn revoke_my_consent(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    open_banking_service::revoke_user_consent(&state.pool, user_id, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ============================================================================
+// ADMIN ENDPOINTS (/api/admin/open-banking)
+// ============================================================================
+
+/// Register a third-party client (admins only)
+///
+/// HTTP Endpoint: POST /admin/open-banking/clients
+///
+/// Request Body:
+/// ```json
+/// { "name": "Budget Buddy", "scopes": ["accounts", "balances", "transactions"] }
+/// ```
+///
+/// Success Response (201 Created):
+/// ```json
+/// {
+///   "id": "...",
+///   "name": "Budget Buddy",
+///   "client_id": "ob_...",
+///   "client_secret": "...",
+///   "scopes": ["accounts", "balances", "transactions"],
+///   "created_at": "..."
+/// }
+/// ```
+/// The secret is never shown again; hand it to the third party now.
+pub async fn register_client(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<RegisterOpenBankingClientRequest>,
+) -> Result<(StatusCode, Json<OpenBankingClientResponse>), AppError> {
+    let client = open_banking_service::register_client(&state.pool, &req).await?;
+
+    tracing::warn!("🏦 Open Banking client {} registered by admin {}", client.client_id, admin_id);
+
+    Ok((StatusCode::CREATED, Json(client)))
+}
+
+/// Revoke a third-party client and every token it holds (admins only)
+///
+/// HTTP Endpoint: DELETE /admin/open-banking/clients/:id
+pub async fn revoke_client(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    open_banking_service::revoke_client(&state.pool, id).await?;
+
+    tracing::warn!("🏦 Open Banking client {} revoked by admin {}", id, admin_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,482 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+use crate::domain::models::{
+    AcceptInvitationRequest, CreateOrganizationRequest, DecideExpenseRequest, DepositRequest,
+    ExpenseListQuery, ExpenseRequest, HistoryQuery, InviteMemberRequest, MembershipResponse,
+    Organization, OrganizationDetailsResponse, OrganizationInvitation, SubmitExpenseRequest,
+    TransactionPageResponse, TransferRequest, UpdateMemberRequest, WalletResponse,
+    WithdrawRequest,
+};
+use crate::error::AppError;
+use crate::middleware::auth::{AuthUser, RequireScope, WalletWrite};
+use crate::middleware::validation::{ValidatedJson, ValidatedQuery};
+use crate::repository::user_repo;
+use crate::routes::auth_routes::AppState;
+use crate::services::organization_service;
+
+// ============================================================================
+// BUSINESS ACCOUNTS (/api/organizations)
+// ============================================================================
+// What a member may do depends on their role (see organization_service).
+// Organizations the caller doesn't belong to answer 404; actions their role
+// doesn't allow answer 403.
+
+/// Start a business account; the caller becomes its owner
+///
+/// HTTP Endpoint: POST /organizations
+///
+/// Request Body:
+/// ```json
+/// { "name": "Acme Corp", "slug": "acme" }
+/// ```
+///
+/// Success Response (201 Created):
+/// ```json
+/// { "id": "...", "name": "Acme Corp", "slug": "acme", "created_at": "..." }
+/// ```
+///
+/// Error Responses:
+/// - 400 Bad Request: The slug is taken
+pub async fn create(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<CreateOrganizationRequest>,
+) -> Result<(StatusCode, Json<Organization>), AppError> {
+    let organization = organization_service::create(&state.pool, user_id, &req).await?;
+
+    tracing::info!("🏢 Organization {} created by user {}", organization.slug, user_id);
+
+    Ok((StatusCode::CREATED, Json(organization)))
+}
+
+/// The organizations the caller belongs to
+///
+/// HTTP Endpoint: GET /organizations
+///
+/// Success Response (200 OK):
+/// ```json
+/// [
+///   { "id": "...", "name": "Acme Corp", "slug": "acme", "created_at": "...", "role": "FINANCE" }
+/// ]
+/// ```
+pub async fn list_mine(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<MembershipResponse>>, AppError> {
+    let memberships = organization_service::list_mine(&state.pool, user_id).await?;
+    Ok(Json(memberships))
+}
+
+/// An organization and its members (members only)
+///
+/// HTTP Endpoint: GET /organizations/:id
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "id": "...",
+///   "name": "Acme Corp",
+///   "slug": "acme",
+///   "created_at": "...",
+///   "role": "OWNER",
+///   "members": [
+///     {
+///       "user_id": "...",
+///       "email": "alice@acme.com",
+///       "full_name": "Alice",
+///       "role": "OWNER",
+///       "joined_at": "..."
+///     }
+///   ]
+/// }
+/// ```
+pub async fn get_organization(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<OrganizationDetailsResponse>, AppError> {
+    let details = organization_service::details(&state.pool, id, user_id).await?;
+    Ok(Json(details))
+}
+
+/// The organization's wallet (members only)
+///
+/// HTTP Endpoint: GET /organizations/:id/wallet
+///
+/// Success Response (200 OK):
+/// ```json
+/// { "id": "...", "balance": "1200.00", "currency": "USD" }
+/// ```
+pub async fn get_wallet(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<WalletResponse>, AppError> {
+    let wallet = organization_service::wallet(&state.pool, id, user_id).await?;
+    Ok(Json(WalletResponse::from(wallet)))
+}
+
+/// Deposit into the organization's wallet (owners and finance)
+///
+/// HTTP Endpoint: POST /organizations/:id/wallet/deposit
+///
+/// Request and response as for POST /wallet/deposit.
+///
+/// Error Responses:
+/// - 403 Forbidden: The caller is a VIEWER
+pub async fn deposit(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<DepositRequest>,
+) -> Result<Json<WalletResponse>, AppError> {
+    let wallet =
+        organization_service::deposit(&state.pool, id, user_id, req.amount, req.currency).await?;
+    Ok(Json(WalletResponse::from(wallet)))
+}
+
+/// Withdraw from the organization's wallet (owners and finance)
+///
+/// HTTP Endpoint: POST /organizations/:id/wallet/withdraw
+///
+/// Request and response as for POST /wallet/withdraw.
+///
+/// Error Responses:
+/// - 403 Forbidden: The caller is a VIEWER
+/// - 422 Unprocessable Entity: Insufficient balance, or CURRENCY_MISMATCH
+pub async fn withdraw(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<WithdrawRequest>,
+) -> Result<Json<WalletResponse>, AppError> {
+    let wallet =
+        organization_service::withdraw(&state.pool, id, user_id, req.amount, req.currency).await?;
+    Ok(Json(WalletResponse::from(wallet)))
+}
+
+/// Send money from the organization's wallet to a user (owners and finance)
+///
+/// HTTP Endpoint: POST /organizations/:id/wallet/transfer
+///
+/// Request Body:
+/// ```json
+/// {
+///   "recipient_email": "supplier@example.com",
+///   "amount": "250.00"
+/// }
+/// ```
+///
+/// Success Response (200 OK): the organization's wallet
+///
+/// Error Responses:
+/// - 403 Forbidden: The caller is a VIEWER
+/// - 422 Unprocessable Entity: Insufficient balance, or CURRENCY_MISMATCH
+pub async fn transfer(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<TransferRequest>,
+) -> Result<Json<WalletResponse>, AppError> {
+    if !state.features.transfers_enabled {
+        return Err(AppError::feature_disabled("Transfers"));
+    }
+
+    let wallet = organization_service::transfer(
+        &state.pool,
+        &state.email_service,
+        state.notification_service.as_ref(),
+        id,
+        user_id,
+        &req.recipient_email,
+        req.amount,
+        req.currency,
+    )
+    .await?;
+    Ok(Json(WalletResponse::from(wallet)))
+}
+
+/// The organization's transaction history, newest first (members only)
+///
+/// HTTP Endpoint: GET /organizations/:id/transactions?limit=20&cursor=...
+///
+/// Paged and filtered like GET /transactions.
+pub async fn get_history(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    ValidatedQuery(query): ValidatedQuery<HistoryQuery>,
+) -> Result<Json<TransactionPageResponse>, AppError> {
+    let page = organization_service::history(&state.pool, id, user_id, &query).await?;
+
+    Ok(Json(TransactionPageResponse::from(page)))
+}
+
+/// Ask for a transfer out of the organization's wallet (any member)
+///
+/// HTTP Endpoint: POST /organizations/:id/expenses
+///
+/// Request Body:
+/// ```json
+/// {
+///   "recipient_email": "supplier@example.com",
+///   "amount": "89.90",
+///   "description": "Office chairs"
+/// }
+/// ```
+///
+/// Owners and finance members are notified; the money moves once one of
+/// them (not the requester) approves.
+///
+/// Success Response (201 Created):
+/// ```json
+/// {
+///   "id": "...",
+///   "organization_id": "...",
+///   "requested_by": "...",
+///   "requester_email": "carol@acme.com",
+///   "recipient_id": "...",
+///   "recipient_email": "supplier@example.com",
+///   "amount": "89.90",
+///   "currency": "USD",
+///   "description": "Office chairs",
+///   "status": "PENDING",
+///   "decided_by": null,
+///   "decided_at": null,
+///   "decision_note": null,
+///   "created_at": "..."
+/// }
+/// ```
+pub async fn submit_expense(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<SubmitExpenseRequest>,
+) -> Result<(StatusCode, Json<ExpenseRequest>), AppError> {
+    let expense = organization_service::submit_expense(
+        &state.pool,
+        state.notification_service.as_ref(),
+        id,
+        user_id,
+        &req,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(expense)))
+}
+
+/// The organization's expense requests, newest first (members only)
+///
+/// HTTP Endpoint: GET /organizations/:id/expenses?status=PENDING
+///
+/// `status` (PENDING, APPROVED or REJECTED) is optional.
+pub async fn list_expenses(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    ValidatedQuery(query): ValidatedQuery<ExpenseListQuery>,
+) -> Result<Json<Vec<ExpenseRequest>>, AppError> {
+    let expenses =
+        organization_service::list_expenses(&state.pool, id, user_id, query.status).await?;
+    Ok(Json(expenses))
+}
+
+/// Approve an expense request and send the money (owners and finance)
+///
+/// HTTP Endpoint: POST /organizations/:id/expenses/:expense_id/approve
+///
+/// Request Body (the note is optional):
+/// ```json
+/// { "note": "Thanks, booked under office costs" }
+/// ```
+///
+/// Success Response (200 OK): the request, now APPROVED
+///
+/// Error Responses:
+/// - 400 Bad Request: Already decided, or it's the caller's own request
+/// - 403 Forbidden: The caller is a VIEWER
+/// - 422 Unprocessable Entity: Insufficient balance (it stays PENDING)
+pub async fn approve_expense(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path((id, expense_id)): Path<(Uuid, Uuid)>,
+    ValidatedJson(req): ValidatedJson<DecideExpenseRequest>,
+) -> Result<Json<ExpenseRequest>, AppError> {
+    if !state.features.transfers_enabled {
+        return Err(AppError::feature_disabled("Transfers"));
+    }
+
+    let expense = organization_service::approve_expense(
+        &state.pool,
+        &state.email_service,
+        state.notification_service.as_ref(),
+        id,
+        user_id,
+        expense_id,
+        req.note.as_deref(),
+    )
+    .await?;
+    Ok(Json(expense))
+}
+
+/// Reject an expense request (owners and finance)
+///
+/// HTTP Endpoint: POST /organizations/:id/expenses/:expense_id/reject
+///
+/// Request Body (the note is optional, and shown to the requester):
+/// ```json
+/// { "note": "Please use the preferred supplier" }
+/// ```
+///
+/// Success Response (200 OK): the request, now REJECTED
+pub async fn reject_expense(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path((id, expense_id)): Path<(Uuid, Uuid)>,
+    ValidatedJson(req): ValidatedJson<DecideExpenseRequest>,
+) -> Result<Json<ExpenseRequest>, AppError> {
+    let expense = organization_service::reject_expense(
+        &state.pool,
+        &state.email_service,
+        state.notification_service.as_ref(),
+        id,
+        user_id,
+        expense_id,
+        req.note.as_deref(),
+    )
+    .await?;
+    Ok(Json(expense))
+}
+
+/// Invite someone to the organization by email (owners only)
+///
+/// HTTP Endpoint: POST /organizations/:id/invitations
+///
+/// Request Body:
+/// ```json
+/// { "email": "bob@acme.com", "role": "FINANCE" }
+/// ```
+///
+/// They're emailed a code to accept with (POST
+/// /organizations/invitations/accept), good for 7 days.
+///
+/// Success Response (201 Created):
+/// ```json
+/// {
+///   "id": "...",
+///   "organization_id": "...",
+///   "email": "bob@acme.com",
+///   "role": "FINANCE",
+///   "invited_by": "...",
+///   "created_at": "...",
+///   "expires_at": "...",
+///   "accepted_at": null
+/// }
+/// ```
+pub async fn invite(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<InviteMemberRequest>,
+) -> Result<(StatusCode, Json<OrganizationInvitation>), AppError> {
+    let invitation = organization_service::invite(
+        &state.pool,
+        &state.email_service,
+        state.clock.as_ref(),
+        id,
+        user_id,
+        &req.email,
+        req.role,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(invitation)))
+}
+
+/// Invitations not yet accepted (owners only)
+///
+/// HTTP Endpoint: GET /organizations/:id/invitations
+pub async fn list_invitations(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<OrganizationInvitation>>, AppError> {
+    let invitations =
+        organization_service::pending_invitations(&state.pool, state.clock.as_ref(), id, user_id)
+            .await?;
+    Ok(Json(invitations))
+}
+
+/// Accept an invitation, with the code from the email
+///
+/// HTTP Endpoint: POST /organizations/invitations/accept
+///
+/// Request Body:
+/// ```json
+/// { "token": "9f86d081884c7d65..." }
+/// ```
+///
+/// Success Response (200 OK): the organization, with the caller's role
+///
+/// Error Responses:
+/// - 404 Not Found: Unknown, expired or used code, or it was sent to
+///   another email address
+pub async fn accept_invitation(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<AcceptInvitationRequest>,
+) -> Result<Json<MembershipResponse>, AppError> {
+    let user = user_repo::find_user_by_id(&state.pool, user_id).await?;
+    let membership = organization_service::accept(
+        &state.pool,
+        state.clock.as_ref(),
+        user_id,
+        &user.email,
+        &req.token,
+    )
+    .await?;
+
+    tracing::info!("🏢 User {} joined organization {}", user_id, membership.organization.slug);
+
+    Ok(Json(membership))
+}
+
+/// Change a member's role (owners only)
+///
+/// HTTP Endpoint: PUT /organizations/:id/members/:user_id
+///
+/// Request Body:
+/// ```json
+/// { "role": "VIEWER" }
+/// ```
+///
+/// Error Responses:
+/// - 400 Bad Request: It would leave the organization without an owner
+pub async fn update_member(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path((id, member_id)): Path<(Uuid, Uuid)>,
+    ValidatedJson(req): ValidatedJson<UpdateMemberRequest>,
+) -> Result<StatusCode, AppError> {
+    organization_service::update_member(&state.pool, id, user_id, member_id, req.role).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove a member (owners), or leave the organization (your own user id)
+///
+/// HTTP Endpoint: DELETE /organizations/:id/members/:user_id
+///
+/// Error Responses:
+/// - 400 Bad Request: It would leave the organization without an owner
+pub async fn remove_member(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path((id, member_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, AppError> {
+    organization_service::remove_member(&state.pool, id, user_id, member_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
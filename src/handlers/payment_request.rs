@@ -0,0 +1,168 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+use crate::domain::models::{CreatePaymentRequest, PaymentRequestResponse};
+use crate::error::AppError;
+use crate::middleware::auth::{AuthUser, RequireScope, WalletWrite};
+use crate::middleware::validation::ValidatedJson;
+use crate::routes::auth_routes::AppState;
+use crate::services::payment_request_service;
+use crate::services::wallet_service::{ConcurrencyPolicy, SpendingLimits};
+
+// ============================================================================
+// PAYMENT REQUEST HANDLERS
+// ============================================================================
+// Users asking each other for money. Asking and paying answer 503 while
+// FEATURE_TRANSFERS_ENABLED is off, like transfers themselves; listing and
+// declining keep working.
+
+/// Ask another user for money
+///
+/// HTTP Endpoint: POST /requests
+///
+/// Request Body:
+/// ```json
+/// {
+///   "payer_email": "bob@example.com",
+///   "amount": "12.50",
+///   "note": "Pizza on Friday"
+/// }
+/// ```
+/// (`currency` is optional and picks the payer's paying wallet, as for
+/// transfers)
+///
+/// Success Response (201 Created):
+/// ```json
+/// {
+///   "id": "...",
+///   "requester_email": "alice@example.com",
+///   "payer_email": "bob@example.com",
+///   "amount": "12.50",
+///   "currency": null,
+///   "note": "Pizza on Friday",
+///   "status": "PENDING",
+///   "created_at": "2026-10-17T09:30:00Z",
+///   "decided_at": null
+/// }
+/// ```
+///
+/// Error Responses:
+/// - 400: unknown payer, or the requester themselves
+/// - 422 LIMIT_EXCEEDED: over LIMIT_CONFIRM_ABOVE (paying it would need
+///   the payer's emailed code)
+/// - 503: transfers paused
+///
+/// The payer is told straight away over their WebSocket
+/// (`"type": "payment_requested"`).
+pub async fn create(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<CreatePaymentRequest>,
+) -> Result<(StatusCode, Json<PaymentRequestResponse>), AppError> {
+    if !state.features.transfers_enabled {
+        return Err(AppError::feature_disabled("Transfers"));
+    }
+
+    let request = payment_request_service::create(
+        &state.pool,
+        state.notification_service.as_ref(),
+        state.config.limit_confirm_above,
+        user_id,
+        &req,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(request)))
+}
+
+/// List requests others made of the authenticated user, newest first
+///
+/// HTTP Endpoint: GET /requests/incoming
+///
+/// Answered ones are listed too, with their `status`.
+pub async fn incoming(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<PaymentRequestResponse>>, AppError> {
+    let requests = payment_request_service::incoming(&state.pool, user_id).await?;
+
+    Ok(Json(requests))
+}
+
+/// List requests the authenticated user made of others, newest first
+///
+/// HTTP Endpoint: GET /requests/outgoing
+pub async fn outgoing(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<PaymentRequestResponse>>, AppError> {
+    let requests = payment_request_service::outgoing(&state.pool, user_id).await?;
+
+    Ok(Json(requests))
+}
+
+/// Pay a request made of the authenticated user
+///
+/// HTTP Endpoint: POST /requests/:id/approve
+///
+/// Sends the requested amount to the requester as a normal transfer and
+/// answers with the request, now `PAID`.
+///
+/// Error Responses:
+/// - 400: the request was already answered
+/// - 404: no such request made of this user
+/// - 422 INSUFFICIENT_BALANCE / LIMIT_EXCEEDED / CURRENCY_MISMATCH: as for
+///   transfers (the request stays `PENDING`)
+/// - 503: transfers paused
+pub async fn approve(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(request_id): Path<Uuid>,
+) -> Result<Json<PaymentRequestResponse>, AppError> {
+    if !state.features.transfers_enabled {
+        return Err(AppError::feature_disabled("Transfers"));
+    }
+
+    let limits = SpendingLimits::from_config(&state.config);
+    let request = payment_request_service::approve(
+        &state.pool,
+        &ConcurrencyPolicy::from_config(&state.config),
+        &state.email_service,
+        state.notification_service.as_ref(),
+        &state.exchange_rates,
+        &limits,
+        state.clock.as_ref(),
+        user_id,
+        request_id,
+    )
+    .await?;
+
+    Ok(Json(request))
+}
+
+/// Decline a request made of the authenticated user
+///
+/// HTTP Endpoint: POST /requests/:id/decline
+///
+/// Error Responses:
+/// - 400: the request was already answered
+/// - 404: no such request made of this user
+pub async fn decline(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(request_id): Path<Uuid>,
+) -> Result<Json<PaymentRequestResponse>, AppError> {
+    let request = payment_request_service::decline(
+        &state.pool,
+        state.notification_service.as_ref(),
+        state.clock.as_ref(),
+        user_id,
+        request_id,
+    )
+    .await?;
+
+    Ok(Json(request))
+}
@@ -0,0 +1,41 @@
+use axum::{extract::State, Json};
+use crate::domain::models::{ExchangeRatesResponse, RatesQuery};
+use crate::error::AppError;
+use crate::middleware::validation::ValidatedQuery;
+use crate::routes::auth_routes::AppState;
+
+// ============================================================================
+// EXCHANGE RATE HANDLERS
+// ============================================================================
+
+/// The latest exchange rates from one currency to every other
+///
+/// HTTP Endpoint: GET /rates?base=USD
+///
+/// Query parameters:
+/// - `base`: the currency to quote from (default USD)
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "base": "USD",
+///   "rates": {
+///     "EUR": "0.92",
+///     "GBP": "0.79",
+///     "JPY": "150"
+///   },
+///   "provider": "frankfurter",
+///   "fetched_at": "..."
+/// }
+/// ```
+/// No login needed. Answers 503 while the rates are too old to use (the
+/// provider is down) or when RATES_PROVIDER=off.
+pub async fn get_rates(
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<RatesQuery>,
+) -> Result<Json<ExchangeRatesResponse>, AppError> {
+    let base = query.base.unwrap_or_default();
+    let rates = state.exchange_rates.rates_from(base).await?;
+
+    Ok(Json(rates))
+}
@@ -0,0 +1,52 @@
+use axum::{extract::State, Json};
+use crate::domain::models::ReferralOverview;
+use crate::error::AppError;
+use crate::middleware::auth::AuthUser;
+use crate::routes::auth_routes::AppState;
+use crate::services::referral_service;
+
+// ============================================================================
+// REFERRAL HANDLERS
+// ============================================================================
+
+/// My referral code, the program's terms and the signups made with my code
+///
+/// HTTP Endpoint: GET /referrals
+///
+/// Headers:
+/// Authorization: Bearer <token>
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "code": "K7Q2M9XD",
+///   "link": "https://app.example.com/register?ref=K7Q2M9XD",
+///   "referrer_reward": "10.00",
+///   "referee_reward": "10.00",
+///   "min_deposit": "20.00",
+///   "window_days": 90,
+///   "max_rewards": 20,
+///   "total_earned": "10.00",
+///   "referrals": [
+///     {
+///       "id": "...",
+///       "referee_email": "b••@example.com",
+///       "status": "REWARDED",
+///       "rejected_reason": null,
+///       "reward": "10.00",
+///       "created_at": "...",
+///       "rewarded_at": "..."
+///     }
+///   ]
+/// }
+/// ```
+///
+/// Error Responses:
+/// - 503 Service Unavailable: No referral program (both rewards are 0)
+pub async fn get_referrals(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<ReferralOverview>, AppError> {
+    let overview = referral_service::overview(&state.pool, &state.config, user_id).await?;
+    Ok(Json(overview))
+}
@@ -0,0 +1,115 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+use crate::domain::models::{ScheduleTransferRequest, ScheduledTransferResponse};
+use crate::error::AppError;
+use crate::middleware::auth::{AuthUser, RequireScope, WalletWrite};
+use crate::middleware::validation::ValidatedJson;
+use crate::routes::auth_routes::AppState;
+use crate::services::scheduled_transfer_service;
+
+// ============================================================================
+// SCHEDULED TRANSFER HANDLERS
+// ============================================================================
+// Transfers to another user at a later time, run by a background task.
+// Scheduling answers 503 while FEATURE_TRANSFERS_ENABLED is off, like
+// transfers themselves; listing and cancelling keep working.
+
+/// Schedule a transfer
+///
+/// HTTP Endpoint: POST /transfers/schedule
+///
+/// Request Body:
+/// ```json
+/// {
+///   "recipient_email": "bob@example.com",
+///   "amount": "25.00",
+///   "execute_at": "2026-11-01T09:00:00Z"
+/// }
+/// ```
+/// (`currency` is optional and picks the paying wallet, as for transfers)
+///
+/// Success Response (201 Created):
+/// ```json
+/// {
+///   "id": "...",
+///   "recipient_email": "bob@example.com",
+///   "amount": "25.00",
+///   "currency": null,
+///   "execute_at": "2026-11-01T09:00:00Z",
+///   "status": "SCHEDULED",
+///   "attempts": 0,
+///   "next_attempt_at": "2026-11-01T09:00:00Z",
+///   "last_error": null,
+///   "created_at": "2026-10-17T09:30:00Z",
+///   "completed_at": null,
+///   "cancelled_at": null
+/// }
+/// ```
+///
+/// Error Responses:
+/// - 400: `execute_at` not in the future (or more than a year ahead),
+///   unknown recipient, or the sender themselves
+/// - 422 CURRENCY_MISMATCH: no wallet in `currency`
+/// - 422 LIMIT_EXCEEDED: over LIMIT_CONFIRM_ABOVE, which only a transfer
+///   made by hand can confirm
+/// - 503: transfers paused
+///
+/// The money only moves when the transfer runs. If the wallet can't cover
+/// it then, it is tried again later (see `attempts` and `last_error`).
+pub async fn schedule(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<ScheduleTransferRequest>,
+) -> Result<(StatusCode, Json<ScheduledTransferResponse>), AppError> {
+    if !state.features.transfers_enabled {
+        return Err(AppError::feature_disabled("Transfers"));
+    }
+
+    let transfer = scheduled_transfer_service::schedule(
+        &state.pool,
+        state.clock.as_ref(),
+        state.config.limit_confirm_above,
+        user_id,
+        &req,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(transfer)))
+}
+
+/// List the authenticated user's scheduled transfers, in the order they run
+///
+/// HTTP Endpoint: GET /transfers/scheduled
+///
+/// Transfers that already ran, failed or were cancelled are listed too,
+/// with their `status`.
+pub async fn list(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ScheduledTransferResponse>>, AppError> {
+    let transfers = scheduled_transfer_service::list(&state.pool, user_id).await?;
+
+    Ok(Json(transfers))
+}
+
+/// Cancel a scheduled transfer before it runs
+///
+/// HTTP Endpoint: DELETE /transfers/scheduled/:id
+///
+/// Error Responses:
+/// - 400: the transfer already ran, failed or was cancelled
+/// - 404: no such transfer
+pub async fn cancel(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(transfer_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    scheduled_transfer_service::cancel(&state.pool, state.clock.as_ref(), user_id, transfer_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,286 @@
+use askama::Template;
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{AppendHeaders, IntoResponse, Redirect, Response},
+    Json,
+};
+use axum_extra::extract::cookie::CookieJar;
+use uuid::Uuid;
+use crate::domain::models::{
+    CreateOrganizationRequest, LoginMethod, OidcCallbackQuery, Organization, OrganizationResponse,
+    SamlAcsForm, SsoConnectionRequest, SsoConnectionResponse, SsoLoginQuery,
+};
+use crate::error::AppError;
+use crate::handlers::web::{browser_session, landing_page, login_cookies};
+use crate::middleware::auth::AdminUser;
+use crate::middleware::client::ClientInfo;
+use crate::middleware::validation::{ValidatedForm, ValidatedJson, ValidatedQuery};
+use crate::repository::user_repo;
+use crate::routes::auth_routes::AppState;
+use crate::services::{login_history_service, token_service};
+use crate::services::sso_service::{self, SsoLogin};
+use crate::utils::jwt::generate_token;
+
+// ============================================================================
+// SINGLE SIGN-ON (browser)
+// ============================================================================
+// These run in the user's browser, bounced between us and the identity
+// provider, and end with the user logged in on the dashboard like after a
+// password login. Failures show the HTML error page.
+
+/// Ties an OIDC callback to the browser that started the sign-in
+const SSO_STATE_COOKIE: &str = "sso_state";
+
+#[derive(Template)]
+#[template(path = "sso_post.html")]
+struct SsoPostTemplate {
+    sso_url: String,
+    saml_request: String,
+}
+
+/// Start signing in through an organization's identity provider
+///
+/// HTTP Endpoint: GET /sso/login?organization=acme
+///
+/// OIDC: redirects to the provider. SAML: a page that posts an
+/// AuthnRequest to the provider.
+pub async fn login(
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<SsoLoginQuery>,
+) -> Result<Response, AppError> {
+    let login = sso_service::start_login(
+        &state.pool,
+        &state.config,
+        state.clock.as_ref(),
+        &query.organization,
+    )
+    .await?;
+
+    let response = match login {
+        SsoLogin::Redirect { url, state: sso_state } => {
+            let cookie = format!(
+                "{}={}; Path=/sso; HttpOnly; SameSite=Lax; Max-Age={}{}",
+                SSO_STATE_COOKIE,
+                sso_state,
+                sso_service::LOGIN_REQUEST_LIFETIME_MINS * 60,
+                if state.config.secure_cookies { "; Secure" } else { "" }
+            );
+            (AppendHeaders([(header::SET_COOKIE, cookie)]), Redirect::to(&url)).into_response()
+        }
+        SsoLogin::SamlPost { sso_url, saml_request } => {
+            SsoPostTemplate { sso_url, saml_request }.into_response()
+        }
+    };
+
+    Ok(response)
+}
+
+/// Where an OIDC provider sends the browser back to
+///
+/// HTTP Endpoint: GET /sso/oidc/callback?code=...&state=...
+pub async fn oidc_callback(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    jar: CookieJar,
+    ValidatedQuery(query): ValidatedQuery<OidcCallbackQuery>,
+) -> Result<Response, AppError> {
+    let cookie_state = jar.get(SSO_STATE_COOKIE).map(|cookie| cookie.value().to_string());
+    let user_id = sso_service::finish_oidc(
+        &state.pool,
+        &state.config,
+        state.clock.as_ref(),
+        cookie_state.as_deref(),
+        &query,
+    )
+    .await?;
+
+    let mut response = logged_in(&state, user_id, client).await?;
+    response.headers_mut().append(
+        header::SET_COOKIE,
+        format!("{}=; Path=/sso; HttpOnly; Max-Age=0", SSO_STATE_COOKIE)
+            .parse()
+            .expect("valid header value"),
+    );
+    Ok(response)
+}
+
+/// SAML assertion consumer service: where a SAML provider posts its
+/// response
+///
+/// HTTP Endpoint: POST /sso/saml/acs (form: SAMLResponse)
+pub async fn saml_acs(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    ValidatedForm(form): ValidatedForm<SamlAcsForm>,
+) -> Result<Response, AppError> {
+    let user_id =
+        sso_service::finish_saml(&state.pool, &state.config, state.clock.as_ref(), &form).await?;
+
+    logged_in(&state, user_id, client).await
+}
+
+/// Our SAML service provider metadata, for the IdP admin to import
+///
+/// HTTP Endpoint: GET /sso/saml/metadata
+pub async fn saml_metadata(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "application/samlmetadata+xml")],
+        sso_service::saml_metadata(&state.config),
+    )
+}
+
+/// Log the user in and send them on to the app
+async fn logged_in(state: &AppState, user_id: Uuid, client: ClientInfo) -> Result<Response, AppError> {
+    let session_id = browser_session(state);
+    let role = user_repo::get_user_role(&state.pool, user_id).await?;
+    let token = generate_token(
+        user_id,
+        session_id,
+        role,
+        &state.jwt_keys,
+        state.clock.as_ref(),
+    )?;
+    if let Some(session_id) = session_id {
+        token_service::start_session(&state.pool, state.clock.as_ref(), session_id, user_id, &client)
+            .await?;
+    }
+    let cookies = login_cookies(state, user_id, &token).await?;
+    let redirect_to = landing_page(state, user_id).await?;
+
+    login_history_service::record_login(
+        &state.pool,
+        state.geoip.as_ref(),
+        &state.email_service,
+        &state.notification_service,
+        user_id,
+        LoginMethod::Sso,
+        client,
+    );
+
+    Ok((AppendHeaders(cookies), Redirect::to(redirect_to)).into_response())
+}
+
+// ============================================================================
+// ADMIN ENDPOINTS (/api/admin/organizations)
+// ============================================================================
+
+/// Create an organization (admins only)
+///
+/// HTTP Endpoint: POST /admin/organizations
+///
+/// Request Body:
+/// ```json
+/// { "name": "Acme Corp", "slug": "acme" }
+/// ```
+///
+/// Success Response (201 Created):
+/// ```json
+/// { "id": "...", "name": "Acme Corp", "slug": "acme", "created_at": "..." }
+/// ```
+pub async fn create_organization(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<CreateOrganizationRequest>,
+) -> Result<(StatusCode, Json<Organization>), AppError> {
+    let organization = sso_service::create_organization(&state.pool, &req).await?;
+
+    tracing::warn!("🔑 Organization {} created by admin {}", organization.slug, admin_id);
+
+    Ok((StatusCode::CREATED, Json(organization)))
+}
+
+/// Every organization and its identity provider (admins only)
+///
+/// HTTP Endpoint: GET /admin/organizations
+///
+/// Success Response (200 OK):
+/// ```json
+/// [
+///   {
+///     "id": "...",
+///     "name": "Acme Corp",
+///     "slug": "acme",
+///     "created_at": "...",
+///     "sso": {
+///       "protocol": "SAML",
+///       "saml_idp_entity_id": "http://www.okta.com/exk1a2b3c",
+///       "saml_sso_url": "https://acme.okta.com/app/.../sso/saml",
+///       "email_domains": ["acme.com"],
+///       "jit_provisioning": true,
+///       "enabled": true,
+///       "login_url": "https://app.example.com/sso/login?organization=acme",
+///       ...
+///     }
+///   }
+/// ]
+/// ```
+pub async fn list_organizations(
+    AdminUser(_): AdminUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<OrganizationResponse>>, AppError> {
+    let organizations = sso_service::list_organizations(&state.pool, &state.config).await?;
+
+    Ok(Json(organizations))
+}
+
+/// Set up or replace an organization's identity provider (admins only)
+///
+/// HTTP Endpoint: PUT /admin/organizations/:id/sso
+///
+/// Request Body (OIDC):
+/// ```json
+/// {
+///   "protocol": "OIDC",
+///   "oidc_issuer": "https://acme.okta.com",
+///   "oidc_client_id": "0oa1b2c3",
+///   "oidc_client_secret": "...",
+///   "email_domains": ["acme.com"],
+///   "jit_provisioning": true
+/// }
+/// ```
+/// Register {PUBLIC_URL}/sso/oidc/callback as the redirect URI at the
+/// provider.
+///
+/// Request Body (SAML):
+/// ```json
+/// {
+///   "protocol": "SAML",
+///   "saml_idp_entity_id": "http://www.okta.com/exk1a2b3c",
+///   "saml_sso_url": "https://acme.okta.com/app/.../sso/saml",
+///   "saml_idp_certificate": "-----BEGIN CERTIFICATE-----\n...",
+///   "email_domains": ["acme.com"]
+/// }
+/// ```
+/// Give the provider our metadata from {PUBLIC_URL}/sso/saml/metadata.
+///
+/// Success Response (200 OK): the connection (without the client secret)
+pub async fn set_sso_connection(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<SsoConnectionRequest>,
+) -> Result<Json<SsoConnectionResponse>, AppError> {
+    let connection = sso_service::set_connection(&state.pool, &state.config, id, &req).await?;
+
+    tracing::warn!("🔑 SSO for organization {} set up by admin {}", id, admin_id);
+
+    Ok(Json(connection))
+}
+
+/// Remove an organization's identity provider (admins only)
+///
+/// HTTP Endpoint: DELETE /admin/organizations/:id/sso
+///
+/// Its people keep their accounts but can no longer sign in through it.
+pub async fn delete_sso_connection(
+    AdminUser(admin_id): AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    sso_service::delete_connection(&state.pool, id).await?;
+
+    tracing::warn!("🔑 SSO for organization {} removed by admin {}", id, admin_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,78 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use crate::domain::models::{TaxSummaryFormat, TaxSummaryQuery};
+use crate::error::AppError;
+use crate::handlers::{download, download_link};
+use crate::middleware::auth::AuthUser;
+use crate::middleware::validation::ValidatedQuery;
+use crate::routes::auth_routes::AppState;
+use crate::services::tax_service;
+
+// ============================================================================
+// TAX SUMMARY HANDLERS
+// ============================================================================
+
+/// The user's tax figures for one calendar year
+///
+/// HTTP Endpoint: GET /tax-summary/:year
+///
+/// Headers:
+/// Authorization: Bearer <token>
+///
+/// Query parameters:
+/// - `format`: `json` (default), `csv` or `pdf`; CSV and PDF come back as
+///   a download (`tax-summary-2025.csv`)
+/// - `link`: with `true`, CSV and PDF are stored instead and the answer is
+///   a link valid for 15 minutes:
+///   `{ "url": "https://...", "expires_at": "..." }`
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "year": 2025,
+///   "full_name": "Carol Martinez",
+///   "email": "carol@example.com",
+///   "currency": "USD",
+///   "interest_earned": "41.18",
+///   "fees_paid": "59.88",
+///   "transfers_received": "912.40",
+///   "transfers_sent": "1306.75",
+///   "transfer_volume": "2219.15",
+///   "transfer_count": 23,
+///   "complete": true,
+///   "generated_at": "..."
+/// }
+/// ```
+/// The current year is year to date (`"complete": false`); future years
+/// are a 400.
+pub async fn get_tax_summary(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    Path(year): Path<i32>,
+    ValidatedQuery(query): ValidatedQuery<TaxSummaryQuery>,
+) -> Result<Response, AppError> {
+    let summary = tax_service::annual_summary(&state.pool, user_id, year).await?;
+
+    let (content_type, file_name, body) = match query.format.unwrap_or_default() {
+        TaxSummaryFormat::Json => return Ok(Json(summary).into_response()),
+        TaxSummaryFormat::Csv => (
+            "text/csv; charset=utf-8",
+            tax_service::file_name(&summary, "csv"),
+            tax_service::to_csv(&summary).into_bytes(),
+        ),
+        TaxSummaryFormat::Pdf => (
+            "application/pdf",
+            tax_service::file_name(&summary, "pdf"),
+            tax_service::to_pdf(&summary),
+        ),
+    };
+
+    if query.link.unwrap_or(false) {
+        download_link(state.storage.as_ref(), user_id, content_type, file_name, body).await
+    } else {
+        Ok(download(content_type, file_name, body))
+    }
+}
@@ -1,9 +1,16 @@
-use axum::{extract::State, Json};
-use crate::domain::models::UserResponse;
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use uuid::Uuid;
+use crate::domain::models::{
+    CreateTokenRequest, CreateTokenResponse, LoginEvent, NotificationPreferences, TokenSession,
+    UpdateLocaleRequest, UserResponse,
+};
 use crate::error::AppError;
-use crate::middleware::auth::AuthUser;
+use crate::middleware::auth::{AuthToken, AuthUser, RequireScope, WalletWrite};
+use crate::middleware::client::ClientInfo;
+use crate::middleware::validation::{AppJson, ValidatedJson};
 use crate::repository::user_repo;
 use crate::routes::auth_routes::AppState;
+use crate::services::{login_history_service, token_service};
 
 // ============================================================================
 // USER HANDLERS
@@ -27,6 +34,16 @@ use crate::routes::auth_routes::AppState;
 ///   "created_at": "2024-..."
 /// }
 /// ```
+#[utoipa::path(
+    get,
+    path = "/me",
+    tag = "auth",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The signed-in user", body = UserResponse),
+        (status = 401, description = "INVALID_TOKEN", body = api_types::ErrorBody)
+    )
+)]
 pub async fn get_me(
     AuthUser(user_id): AuthUser,  // ← Automatic JWT validation!
     State(state): State<AppState>,
@@ -38,3 +55,181 @@ pub async fn get_me(
     
     Ok(Json(UserResponse::from(user)))
 }
+
+/// Set the language the user's error messages are shown in
+///
+/// HTTP Endpoint: PUT /me/locale
+///
+/// Request Body:
+/// ```json
+/// { "locale": "es" }
+/// ```
+///
+/// Supported: "en", "es", "fr". Returns 204 No Content.
+pub async fn update_locale(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    AppJson(req): AppJson<UpdateLocaleRequest>,
+) -> Result<StatusCode, AppError> {
+    user_repo::set_user_locale(&state.pool, user_id, req.locale.as_str()).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The user's notification preferences
+///
+/// HTTP Endpoint: GET /me/notifications
+///
+/// Success Response (200 OK):
+/// ```json
+/// { "login_alerts": true }
+/// ```
+pub async fn get_notification_preferences(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<NotificationPreferences>, AppError> {
+    let login_alerts = user_repo::get_login_alerts(&state.pool, user_id).await?;
+
+    Ok(Json(NotificationPreferences { login_alerts }))
+}
+
+/// Change the user's notification preferences
+///
+/// HTTP Endpoint: PUT /me/notifications
+///
+/// Request Body:
+/// ```json
+/// { "login_alerts": false }
+/// ```
+///
+/// With `login_alerts` off, sign-ins from a new device are still recorded
+/// (GET /me/logins) but no email or notification is sent. Returns the
+/// saved preferences.
+pub async fn update_notification_preferences(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    AppJson(req): AppJson<NotificationPreferences>,
+) -> Result<Json<NotificationPreferences>, AppError> {
+    user_repo::set_login_alerts(&state.pool, user_id, req.login_alerts).await?;
+
+    Ok(Json(req))
+}
+
+/// The user's latest sign-ins (at most 50), newest first
+///
+/// HTTP Endpoint: GET /me/logins
+///
+/// Success Response (200 OK):
+/// ```json
+/// [
+///   {
+///     "id": "...",
+///     "method": "PASSWORD",
+///     "ip_address": "203.0.113.7",
+///     "user_agent": "Mozilla/5.0 ...",
+///     "country_code": "DE",
+///     "country": "Germany",
+///     "city": "Berlin",
+///     "new_device": false,
+///     "created_at": "..."
+///   }
+/// ]
+/// ```
+/// Locations are null when no GeoIP database is configured.
+pub async fn get_logins(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<LoginEvent>>, AppError> {
+    let logins = login_history_service::recent_logins(&state.pool, user_id).await?;
+
+    Ok(Json(logins))
+}
+
+/// The devices the user is signed in on, most recently seen first
+///
+/// HTTP Endpoint: GET /sessions
+///
+/// Success Response (200 OK):
+/// ```json
+/// [
+///   {
+///     "id": "...",
+///     "device_name": "Firefox on Windows",
+///     "ip_address": "203.0.113.7",
+///     "user_agent": "Mozilla/5.0 ...",
+///     "created_at": "...",
+///     "last_seen_at": "...",
+///     "current": true
+///   }
+/// ]
+/// ```
+/// `current` marks the one this request was made from.
+pub async fn list_sessions(
+    AuthToken(claims): AuthToken,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TokenSession>>, AppError> {
+    let sessions = token_service::list_sessions(&state.pool, &claims).await?;
+
+    Ok(Json(sessions))
+}
+
+/// Sign one of the user's devices out
+///
+/// HTTP Endpoint: DELETE /sessions/:id
+///
+/// Returns 204 No Content; that device's token is refused from now on.
+///
+/// Error Responses:
+/// - 404 Not Found: The user has no such session
+pub async fn revoke_session(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    token_service::revoke_session(&state.pool, user_id, session_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Mint a token to hand to another app, e.g. a read-only one
+///
+/// HTTP Endpoint: POST /tokens
+///
+/// Request Body:
+/// ```json
+/// { "name": "Budget app", "scope": "wallet:read" }
+/// ```
+///
+/// Success Response (201 Created):
+/// ```json
+/// {
+///   "token": "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9...",
+///   "session_id": "...",
+///   "scope": "wallet:read",
+///   "expires_at": "..."
+/// }
+/// ```
+/// It's listed by GET /sessions under its name; DELETE /sessions/:id
+/// revokes it.
+///
+/// Error Responses:
+/// - 400 Bad Request: No scope, or one we don't know ("wallet:read",
+///   "wallet:write")
+/// - 403 Forbidden: INSUFFICIENT_SCOPE, a scope this request's token
+///   doesn't have
+pub async fn create_token(
+    AuthToken(claims): AuthToken,
+    State(state): State<AppState>,
+    client: ClientInfo,
+    ValidatedJson(req): ValidatedJson<CreateTokenRequest>,
+) -> Result<(StatusCode, Json<CreateTokenResponse>), AppError> {
+    let token = token_service::create_token(
+        &state.pool,
+        state.clock.as_ref(),
+        &state.jwt_keys,
+        &claims,
+        &req,
+        &client,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(token)))
+}
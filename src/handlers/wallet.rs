@@ -1,16 +1,39 @@
-use axum::{extract::State, http::StatusCode, Json};
-use crate::domain::models::{DepositRequest, WalletResponse, WithdrawRequest};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use uuid::Uuid;
+use crate::domain::models::{
+    ConfirmTransferRequest, CreateWalletRequest, DepositRequest, HistoryQuery,
+    MonthlyStatementsResponse, PendingTransferResponse, SearchQuery, SpendingLimitsRequest, SpendingLimitsResponse, StatementQuery, TransactionPageResponse,
+    TransactionSearchResult, TransferRequest, WalletResponse, WithdrawRequest,
+};
 use crate::error::AppError;
-use crate::middleware::auth::AuthUser;
+use crate::middleware::auth::{AuthUser, RequireScope, WalletWrite};
+use crate::middleware::validation::{ValidatedJson, ValidatedQuery};
 use crate::repository::user_repo;
 use crate::routes::auth_routes::AppState;
-use crate::services::wallet_service;
+use crate::services::referral_service::{self, ReferralTerms};
+use crate::services::wallet_service::{self, ConcurrencyPolicy, SpendingLimits};
+use crate::services::{search_service, statement_service};
 
 // ============================================================================
 // WALLET HANDLERS
 // ============================================================================
 
 /// Get the authenticated user's wallet
+#[utoipa::path(
+    get,
+    path = "/wallet",
+    tag = "wallet",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The primary wallet", body = WalletResponse),
+        (status = 401, description = "INVALID_TOKEN", body = api_types::ErrorBody)
+    )
+)]
 pub async fn get_wallet(
     AuthUser(user_id): AuthUser,
     State(state): State<AppState>,
@@ -19,13 +42,111 @@ pub async fn get_wallet(
     Ok(Json(WalletResponse::from(wallet)))
 }
 
+/// List the authenticated user's wallets, the primary one first
+///
+/// HTTP Endpoint: GET /wallets
+#[utoipa::path(
+    get,
+    path = "/wallets",
+    tag = "wallet",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Every wallet, the primary one first", body = Vec<WalletResponse>),
+        (status = 401, description = "INVALID_TOKEN", body = api_types::ErrorBody)
+    )
+)]
+pub async fn list_wallets(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<WalletResponse>>, AppError> {
+    let wallets = wallet_service::list_wallets(&state.pool, user_id).await?;
+    Ok(Json(wallets.into_iter().map(WalletResponse::from).collect()))
+}
+
+/// Open a wallet in another currency
+///
+/// HTTP Endpoint: POST /wallets
+///
+/// Request Body:
+/// ```json
+/// {
+///   "currency": "EUR"
+/// }
+/// ```
+///
+/// Success Response (201 Created):
+/// ```json
+/// {
+///   "id": "...",
+///   "balance": "0.00",
+///   "currency": "EUR"
+/// }
+/// ```
+///
+/// Error Responses:
+/// - 400 Bad Request: The user already has a wallet in that currency
+///
+/// Deposits, withdrawals and transfers pick it with `"currency": "EUR"`;
+/// without a currency they use the primary wallet.
+#[utoipa::path(
+    post,
+    path = "/wallets",
+    tag = "wallet",
+    security(("bearer" = [])),
+    request_body = CreateWalletRequest,
+    responses(
+        (status = 201, description = "The new wallet", body = WalletResponse),
+        (status = 400, description = "Already a wallet in that currency", body = api_types::ErrorBody)
+    )
+)]
+pub async fn create_wallet(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<CreateWalletRequest>,
+) -> Result<(StatusCode, Json<WalletResponse>), AppError> {
+    let wallet = wallet_service::open_wallet(&state.pool, user_id, req.currency).await?;
+    Ok((StatusCode::CREATED, Json(WalletResponse::from(wallet))))
+}
+
 /// Deposit money into the authenticated user's wallet
+#[utoipa::path(
+    post,
+    path = "/wallet/deposit",
+    tag = "wallet",
+    security(("bearer" = [])),
+    request_body = DepositRequest,
+    responses(
+        (status = 200, description = "The wallet after the deposit", body = WalletResponse),
+        (status = 400, description = "VALIDATION_ERROR", body = api_types::ErrorBody),
+        (status = 403, description = "INSUFFICIENT_SCOPE", body = api_types::ErrorBody),
+        (status = 422, description = "CURRENCY_MISMATCH or ACCOUNT_FROZEN", body = api_types::ErrorBody)
+    )
+)]
 pub async fn deposit(
-    AuthUser(user_id): AuthUser,
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
     State(state): State<AppState>,
-    Json(req): Json<DepositRequest>,
+    ValidatedJson(req): ValidatedJson<DepositRequest>,
 ) -> Result<Json<WalletResponse>, AppError> {
-    let wallet = wallet_service::deposit(&state.pool, user_id, req.amount).await?;
+    let wallet = wallet_service::deposit(
+        &state.pool,
+        &state.email_service,
+        state.notification_service.as_ref(),
+        user_id,
+        req.amount,
+        req.currency,
+    )
+    .await?;
+
+    // The bonus, if this was a referred user's qualifying deposit
+    referral_service::reward_if_qualified(
+        &state.pool,
+        &ReferralTerms::from_config(&state.config),
+        state.clock.as_ref(),
+        state.notification_service.as_ref(),
+        user_id,
+    )
+    .await;
+
     Ok(Json(WalletResponse::from(wallet)))
 }
 
@@ -39,9 +160,11 @@ pub async fn deposit(
 /// Request Body:
 /// ```json
 /// {
-///   "amount": "50.00"
+///   "amount": "50.00",
+///   "currency": "USD"
 /// }
 /// ```
+/// (`currency` is optional; it picks the wallet in that currency)
 ///
 /// Success Response (200 OK):
 /// ```json
@@ -54,13 +177,38 @@ pub async fn deposit(
 ///
 /// Error Responses:
 /// - 400 Bad Request: Amount <= 0
-/// - 422 Unprocessable Entity: Insufficient balance
+/// - 403 Forbidden: INSUFFICIENT_SCOPE (a read-only token)
+/// - 422 Unprocessable Entity: Insufficient balance, CURRENCY_MISMATCH, or
+///   LIMIT_EXCEEDED (see GET /limits)
+#[utoipa::path(
+    post,
+    path = "/wallet/withdraw",
+    tag = "wallet",
+    security(("bearer" = [])),
+    request_body = WithdrawRequest,
+    responses(
+        (status = 200, description = "The wallet after the withdrawal", body = WalletResponse),
+        (status = 400, description = "VALIDATION_ERROR", body = api_types::ErrorBody),
+        (status = 403, description = "INSUFFICIENT_SCOPE", body = api_types::ErrorBody),
+        (status = 422, description = "INSUFFICIENT_BALANCE, CURRENCY_MISMATCH or LIMIT_EXCEEDED", body = api_types::ErrorBody)
+    )
+)]
 pub async fn withdraw(
-    AuthUser(user_id): AuthUser,
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
     State(state): State<AppState>,
-    Json(req): Json<WithdrawRequest>,
+    ValidatedJson(req): ValidatedJson<WithdrawRequest>,
 ) -> Result<Json<WalletResponse>, AppError> {
-    let wallet = wallet_service::withdraw(&state.pool, user_id, req.amount).await?;
+    let limits = SpendingLimits::from_config(&state.config);
+    let wallet = wallet_service::withdraw(
+        &state.pool,
+        state.notification_service.as_ref(),
+        &limits,
+        state.clock.as_ref(),
+        user_id,
+        req.amount,
+        req.currency,
+    )
+    .await?;
     Ok(Json(WalletResponse::from(wallet)))
 }
 
@@ -87,52 +235,328 @@ pub async fn withdraw(
 ///   "currency": "USD"
 /// }
 /// ```
+///
+/// If the recipient has no wallet in the sender's currency the amount is
+/// converted into their primary wallet at the current exchange rate, which
+/// both transactions record as `exchange_rate`. With exchange rates off
+/// that's a 422 CURRENCY_MISMATCH.
+///
+/// Transfers above LIMIT_CONFIRM_ABOVE (1000.00 by default) answer
+/// 202 Accepted with the pending transfer instead:
+/// ```json
+/// {
+///   "id": "...",
+///   "recipient_email": "bob@example.com",
+///   "amount": "2500.00",
+///   "currency": "USD",
+///   "status": "PENDING",
+///   "expires_at": "2026-10-17T09:45:00Z",
+///   ...
+/// }
+/// ```
+/// The money is held, and the sender is emailed a code to send to
+/// POST /wallet/transfer/:id/confirm.
+#[utoipa::path(
+    post,
+    path = "/wallet/transfer",
+    tag = "wallet",
+    security(("bearer" = [])),
+    request_body = TransferRequest,
+    responses(
+        (status = 200, description = "The sender's wallet after the transfer", body = WalletResponse),
+        (status = 202, description = "Above LIMIT_CONFIRM_ABOVE: held until confirmed with the emailed code", body = PendingTransferResponse),
+        (status = 400, description = "VALIDATION_ERROR", body = api_types::ErrorBody),
+        (status = 403, description = "INSUFFICIENT_SCOPE", body = api_types::ErrorBody),
+        (status = 404, description = "No user with that email", body = api_types::ErrorBody),
+        (status = 422, description = "INSUFFICIENT_BALANCE, CURRENCY_MISMATCH or LIMIT_EXCEEDED", body = api_types::ErrorBody),
+        (status = 503, description = "FEATURE_DISABLED: transfers are off", body = api_types::ErrorBody)
+    )
+)]
 pub async fn transfer(
-    AuthUser(user_id): AuthUser,
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
     State(state): State<AppState>,
-    Json(req): Json<crate::domain::models::TransferRequest>,
-) -> Result<Json<WalletResponse>, AppError> {
+    ValidatedJson(req): ValidatedJson<TransferRequest>,
+) -> Result<Response, AppError> {
+    if !state.features.transfers_enabled {
+        return Err(AppError::feature_disabled("Transfers"));
+    }
+    let limits = SpendingLimits::from_config(&state.config);
+
+    if req.amount > state.config.limit_confirm_above {
+        let pending = wallet_service::begin_transfer(
+            &state.pool,
+            &state.email_service,
+            state.clock.as_ref(),
+            Some(&state.exchange_rates),
+            &limits,
+            user_id,
+            &req.recipient_email,
+            req.amount,
+            req.currency,
+        )
+        .await?;
+        return Ok((StatusCode::ACCEPTED, Json(pending)).into_response());
+    }
+
     let wallet = wallet_service::transfer(
         &state.pool,
+        &ConcurrencyPolicy::from_config(&state.config),
         &state.email_service,
-        &state.notification_service,
+        state.notification_service.as_ref(),
+        Some(&state.exchange_rates),
+        &limits,
+        state.clock.as_ref(),
         user_id,
         &req.recipient_email,
-        req.amount
+        req.amount,
+        req.currency,
     ).await?;
-    Ok(Json(WalletResponse::from(wallet)))
+    Ok(Json(WalletResponse::from(wallet)).into_response())
+}
+
+/// Confirm a transfer with the code emailed to the sender
+///
+/// HTTP Endpoint: POST /wallet/transfer/:id/confirm
+///
+/// Request Body:
+/// ```json
+/// {
+///   "code": "123456"
+/// }
+/// ```
+///
+/// Success Response (200 OK): the transfer, now CONFIRMED; the recipient
+/// has the money
+///
+/// Error Responses:
+/// - 400 Bad Request: Wrong code, or the transfer isn't PENDING any more
+///   (after 5 wrong codes, or once the code has expired, it is refunded)
+/// - 404 Not Found: No such transfer of the user's
+pub async fn confirm_transfer(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(transfer_id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<ConfirmTransferRequest>,
+) -> Result<Json<PendingTransferResponse>, AppError> {
+    let transfer = wallet_service::confirm_transfer(
+        &state.pool,
+        &state.email_service,
+        state.notification_service.as_ref(),
+        state.clock.as_ref(),
+        user_id,
+        transfer_id,
+        &req.code,
+    )
+    .await?;
+    Ok(Json(transfer))
 }
 
-/// Get transaction history
+/// Cancel a transfer waiting for confirmation; the money goes back to the
+/// sender
 ///
-/// HTTP Endpoint: GET /transactions
+/// HTTP Endpoint: POST /wallet/transfer/:id/cancel
+///
+/// Success Response (200 OK): the transfer, now CANCELLED
+///
+/// Error Responses:
+/// - 400 Bad Request: The transfer isn't PENDING any more
+/// - 404 Not Found: No such transfer of the user's
+pub async fn cancel_transfer(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    Path(transfer_id): Path<Uuid>,
+) -> Result<Json<PendingTransferResponse>, AppError> {
+    let transfer =
+        wallet_service::cancel_transfer(&state.pool, state.clock.as_ref(), user_id, transfer_id)
+            .await?;
+    Ok(Json(transfer))
+}
+
+/// Get the spending limits that apply to the authenticated user
+///
+/// HTTP Endpoint: GET /limits
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "per_transaction": "500.00",
+///   "daily": "25000.00",
+///   "max_per_transaction": "10000.00",
+///   "max_daily": "25000.00"
+/// }
+/// ```
+/// `max_*` are the configured limits, the highest the user may choose.
+pub async fn get_limits(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Json<SpendingLimitsResponse>, AppError> {
+    let limits = SpendingLimits::from_config(&state.config);
+    let response = wallet_service::get_limits(&state.pool, &limits, user_id).await?;
+    Ok(Json(response))
+}
+
+/// Set the authenticated user's own spending limits
+///
+/// HTTP Endpoint: PUT /limits
+///
+/// Request Body:
+/// ```json
+/// {
+///   "per_transaction": "500.00",
+///   "daily": null
+/// }
+/// ```
+/// A missing or null limit goes back to the configured one.
+///
+/// Success Response (200 OK): the limits now in effect, as GET /limits
+///
+/// Error Responses:
+/// - 400 Bad Request: A limit <= 0, or above the configured one
+///
+/// Withdrawals and transfers over a limit fail with 422 LIMIT_EXCEEDED.
+pub async fn set_limits(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<SpendingLimitsRequest>,
+) -> Result<Json<SpendingLimitsResponse>, AppError> {
+    let limits = SpendingLimits::from_config(&state.config);
+    let response = wallet_service::set_limits(&state.pool, &limits, user_id, &req).await?;
+    Ok(Json(response))
+}
+
+/// Get transaction history, newest first, one page at a time
+///
+/// HTTP Endpoint: GET /transactions?limit=20&cursor=...&type=TRANSFER&from=2025-01-01
 /// 
 /// Headers:
 /// Authorization: Bearer <token>
 ///
+/// Query parameters (all optional):
+/// - `limit`: page size, 1-100 (default 20)
+/// - `cursor`: the `next_cursor` of the previous page
+/// - `type`: DEPOSIT, WITHDRAWAL or TRANSFER
+/// - `status`: PENDING, COMPLETED, FAILED or REVERSED
+/// - `from`, `to`: first and last day, inclusive (UTC, e.g. 2025-01-31)
+/// - `min_amount`, `max_amount`: inclusive bounds, e.g. 10.00
+/// - `q`: words in the description (`"exact phrase"`, `or` and `-word` work)
+///
+/// Keep the same filters when following `next_cursor`.
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "transactions": [
+///     {
+///       "id": "...",
+///       "transaction_type": "DEPOSIT",
+///       "amount": "100.00",
+///       "status": "COMPLETED",
+///       "created_at": "...",
+///       "completed_at": "...",
+///       "failed_at": null,
+///       "reversed_at": null
+///     }
+///   ],
+///   "total": 57,
+///   "next_cursor": "1760668331123456_5b0f..."
+/// }
+/// ```
+/// `total` counts every matching transaction, not just this page; `next_cursor` is null on the last page. `status` is PENDING, COMPLETED,
+/// FAILED or REVERSED, with the time each state was entered.
+#[utoipa::path(
+    get,
+    path = "/transactions",
+    tag = "wallet",
+    security(("bearer" = [])),
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "A page of transactions, newest first", body = TransactionPageResponse),
+        (status = 400, description = "VALIDATION_ERROR", body = api_types::ErrorBody)
+    )
+)]
+pub async fn get_history(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<HistoryQuery>,
+) -> Result<Json<TransactionPageResponse>, AppError> {
+    let page = wallet_service::get_history(&state.pool, user_id, &query).await?;
+
+    Ok(Json(TransactionPageResponse::from(page)))
+}
+
+/// Search the transaction history by description, best matches first
+///
+/// HTTP Endpoint: GET /transactions/search?q=coffee&limit=20
+///
+/// Headers:
+/// Authorization: Bearer <token>
+///
+/// Query parameters:
+/// - `q`: search words (required); `"exact phrase"`, `or` and `-word` work
+/// - `limit`: how many results, 1-100 (default 20)
+///
 /// Success Response (200 OK):
 /// ```json
 /// [
 ///   {
 ///     "id": "...",
-///     "transaction_type": "DEPOSIT",
-///     "amount": "100.00",
+///     "transaction_type": "WITHDRAWAL",
+///     "amount": "4.50",
+///     "description": "Coffee shop",
 ///     "status": "COMPLETED",
-///     "created_at": "..."
+///     "created_at": "...",
+///     "rank": 0.0607927
 ///   }
 /// ]
 /// ```
-pub async fn get_history(
+pub async fn search_history(
     AuthUser(user_id): AuthUser,
     State(state): State<AppState>,
-) -> Result<Json<Vec<crate::domain::models::TransactionResponse>>, AppError> {
-    let transactions = wallet_service::get_history(&state.pool, user_id).await?;
-    
-    // Convert to response DTOs
-    let response: Vec<crate::domain::models::TransactionResponse> = transactions
-        .into_iter()
-        .map(crate::domain::models::TransactionResponse::from)
-        .collect();
-        
-    Ok(Json(response))
+    ValidatedQuery(query): ValidatedQuery<SearchQuery>,
+) -> Result<Json<Vec<TransactionSearchResult>>, AppError> {
+    let results =
+        search_service::search_my_transactions(&state.pool, user_id, &query.q, query.limit)
+            .await?;
+
+    Ok(Json(results))
+}
+
+/// Monthly money in/out and balances for the last few months
+///
+/// HTTP Endpoint: GET /wallet/statements?months=12
+///
+/// Headers:
+/// Authorization: Bearer <token>
+///
+/// Query parameters:
+/// - `months`: how many months back, including this one, 1-120 (default 12)
+///
+/// Success Response (200 OK):
+/// ```json
+/// {
+///   "currency": "USD",
+///   "months": [
+///     {
+///       "month": "2026-09-01",
+///       "opening_balance": "13918.41",
+///       "money_in": "5251.43",
+///       "money_out": "2914.49",
+///       "closing_balance": "16255.35",
+///       "transaction_count": 16
+///     }
+///   ],
+///   "refreshed_at": "..."
+/// }
+/// ```
+/// The totals are recomputed nightly, so the current month can lag behind
+/// the live balance until the next refresh.
+pub async fn get_statements(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<StatementQuery>,
+) -> Result<Json<MonthlyStatementsResponse>, AppError> {
+    let statements =
+        statement_service::monthly_statements(&state.pool, user_id, query.months).await?;
+
+    Ok(Json(statements))
 }
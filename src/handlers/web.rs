@@ -1,16 +1,30 @@
 use askama::Template;
 use axum::{
-    extract::State,
-    response::{Html, IntoResponse, Redirect, Response},
-    Form,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
 };
 use time::Duration;
+use uuid::Uuid;
 use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
-use crate::middleware::auth::AuthUser;
+use crate::middleware::auth::{AuthUser, RequireScope, WalletWrite};
+use crate::middleware::validation::{AppForm, ValidatedForm, ValidatedQuery};
 use crate::routes::auth_routes::AppState;
-use crate::domain::models::{UserResponse, WalletResponse, TransactionResponse};
-use crate::repository::user_repo;
-use crate::services::wallet_service;
+use crate::domain::models::{
+    ConfirmTransferRequest, DecideExpenseRequest, ExpenseRequest, ForgotPasswordRequest, HistoryQuery,
+    InternationalTransferResponse, OnboardingStep, PendingTransferResponse, ReferralOverview, RegisterPageQuery,
+    ResetPasswordPageQuery, ResetPasswordRequest, UnlockAccountPageQuery, UnlockAccountRequest, UserResponse, WalletResponse,
+    TransactionResponse,
+};
+use crate::repository::{dashboard_repo, user_repo};
+use crate::config::WebAuthMode;
+use crate::services::login_throttle_service::{self, LockoutPolicy};
+use crate::services::referral_service::{self, ReferralTerms};
+use crate::services::wallet_service::{ConcurrencyPolicy, SpendingLimits};
+use crate::services::{
+    international_service, onboarding_service, organization_service, password_reset_service,
+    session_service, token_service, wallet_service,
+};
 
 // ============================================================================
 // TEMPLATES
@@ -22,7 +36,10 @@ struct LoginTemplate;
 
 #[derive(Template)]
 #[template(path = "register.html")]
-struct RegisterTemplate;
+struct RegisterTemplate {
+    /// From a shared signup link (`?ref=`)
+    referral_code: Option<String>,
+}
 
 #[derive(Template)]
 #[template(path = "dashboard.html")]
@@ -30,6 +47,9 @@ struct DashboardTemplate {
     user: UserResponse,
     wallet: WalletResponse,
     transactions: Vec<TransactionResponse>,
+    month_in: rust_decimal::Decimal,
+    month_out: rust_decimal::Decimal,
+    flash: Option<String>,
 }
 
 // ============================================================================
@@ -42,36 +62,40 @@ pub async fn login_page() -> impl IntoResponse {
 }
 
 /// Serve the register page
-pub async fn register_page() -> impl IntoResponse {
-    RegisterTemplate
+pub async fn register_page(Query(query): Query<RegisterPageQuery>) -> impl IntoResponse {
+    RegisterTemplate {
+        referral_code: query.referral_code,
+    }
 }
 
 /// Serve the dashboard (protected)
 pub async fn dashboard_page(
     AuthUser(user_id): AuthUser,
     State(state): State<AppState>,
+    jar: CookieJar,
 ) ->  Result<impl IntoResponse, crate::error::AppError> {
-    // 1. Get User
-    let user = user_repo::find_user_by_id(&state.pool, user_id).await
-        .map(UserResponse::from)?;
-
-    // 2. Get Wallet
-    let wallet = user_repo::get_wallet_by_user_id(&state.pool, user_id).await
-        .map(WalletResponse::from)?;
-
-    // 3. Get Recent Transactions (Limit 5 for overview)
-    // Note: strict typing might need us to limit in query or slice here
-    let transactions_raw = wallet_service::get_history(&state.pool, user_id).await?;
-    let transactions: Vec<TransactionResponse> = transactions_raw
+    // 1. User, wallet, recent transactions and this month's totals
+    //    (one query against the precomputed summary row)
+    let summary = dashboard_repo::get_dashboard_summary(&state.pool, user_id).await?;
+    let transactions: Vec<TransactionResponse> = summary
+        .recent_transactions
         .into_iter()
-        .take(5)
         .map(TransactionResponse::from)
         .collect();
 
+    // 2. Pop any flash message left by the last action (session mode)
+    let flash = match jar.get(session_service::SESSION_COOKIE) {
+        Some(session) => session_service::take_flash(&state.pool, session.value()).await?,
+        None => None,
+    };
+
     let template = DashboardTemplate {
-        user,
-        wallet,
+        user: summary.user,
+        wallet: summary.wallet,
         transactions,
+        month_in: summary.month_in,
+        month_out: summary.month_out,
+        flash,
     };
 
     Ok(template)
@@ -88,14 +112,32 @@ pub async fn deposit_page() -> impl IntoResponse {
 
 /// Handle deposit form submission
 pub async fn deposit_submit(
-    AuthUser(user_id): AuthUser,
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
     State(state): State<AppState>,
-    Form(req): Form<crate::domain::models::DepositRequest>,
+    jar: CookieJar,
+    ValidatedForm(req): ValidatedForm<crate::domain::models::DepositRequest>,
 ) -> Result<impl IntoResponse, crate::error::AppError> {
     use axum::response::AppendHeaders;
 
     // Call the service
-    wallet_service::deposit(&state.pool, user_id, req.amount).await?;
+    wallet_service::deposit(
+        &state.pool,
+        &state.email_service,
+        state.notification_service.as_ref(),
+        user_id,
+        req.amount,
+        req.currency,
+    )
+    .await?;
+    referral_service::reward_if_qualified(
+        &state.pool,
+        &ReferralTerms::from_config(&state.config),
+        state.clock.as_ref(),
+        state.notification_service.as_ref(),
+        user_id,
+    )
+    .await;
+    flash(&state, &jar, &format!("Deposited ${}.", req.amount)).await?;
 
     // Return success message and redirect
     Ok((
@@ -115,14 +157,26 @@ pub async fn withdraw_page() -> impl IntoResponse {
 
 /// Handle withdraw form submission
 pub async fn withdraw_submit(
-    AuthUser(user_id): AuthUser,
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
     State(state): State<AppState>,
-    Form(req): Form<crate::domain::models::WithdrawRequest>,
+    jar: CookieJar,
+    ValidatedForm(req): ValidatedForm<crate::domain::models::WithdrawRequest>,
 ) -> Result<impl IntoResponse, crate::error::AppError> {
     use axum::response::AppendHeaders;
 
     // Call the service
-    wallet_service::withdraw(&state.pool, user_id, req.amount).await?;
+    let limits = SpendingLimits::from_config(&state.config);
+    wallet_service::withdraw(
+        &state.pool,
+        state.notification_service.as_ref(),
+        &limits,
+        state.clock.as_ref(),
+        user_id,
+        req.amount,
+        req.currency,
+    )
+    .await?;
+    flash(&state, &jar, &format!("Withdrew ${}.", req.amount)).await?;
 
     // Return success message and redirect
     Ok((
@@ -135,23 +189,42 @@ pub async fn withdraw_submit(
 #[template(path = "transactions.html")]
 struct TransactionsTemplate {
     transactions: Vec<TransactionResponse>,
+    /// Transactions in the whole history
+    total: u64,
+    /// Link to the next (older) page, if there is one
+    next_page: Option<String>,
+    /// Link back to the newest page, unless this is it
+    newest_page: Option<String>,
 }
 
-/// Serve the transactions page (full history)
+/// Serve the transactions page (history, one page at a time)
 pub async fn transactions_page(
     AuthUser(user_id): AuthUser,
     State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<HistoryQuery>,
 ) -> Result<impl IntoResponse, crate::error::AppError> {
-    // Get ALL transactions
-    let transactions_raw = wallet_service::get_history(&state.pool, user_id).await?;
-    
-    let transactions: Vec<TransactionResponse> = transactions_raw
-        .into_iter()
-        .map(TransactionResponse::from)
-        .collect();
+    let page = wallet_service::get_history(&state.pool, user_id, &query).await?;
+
+    // Paging keeps the page size and filters
+    let page_link = |cursor| {
+        let query = HistoryQuery { cursor, ..query.clone() };
+        format!(
+            "/dashboard/transactions?{}",
+            serde_urlencoded::to_string(&query).unwrap_or_default()
+        )
+    };
+    let next_page = page.next_cursor.map(|cursor| page_link(Some(cursor)));
+    let newest_page = query.cursor.map(|_| page_link(None));
 
     let template = TransactionsTemplate {
-        transactions,
+        total: page.total,
+        transactions: page
+            .transactions
+            .into_iter()
+            .map(TransactionResponse::from)
+            .collect(),
+        next_page,
+        newest_page,
     };
 
     Ok(template)
@@ -161,6 +234,12 @@ pub async fn transactions_page(
 #[template(path = "transfer.html")]
 struct TransferTemplate;
 
+#[derive(Template)]
+#[template(path = "transfer_confirm.html")]
+struct TransferConfirmTemplate {
+    transfer: PendingTransferResponse,
+}
+
 /// Serve the transfer page
 pub async fn transfer_page() -> impl IntoResponse {
     TransferTemplate
@@ -168,104 +247,816 @@ pub async fn transfer_page() -> impl IntoResponse {
 
 /// Handle transfer form submission
 pub async fn transfer_submit(
-    AuthUser(user_id): AuthUser,
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
     State(state): State<AppState>,
-    Form(req): Form<crate::domain::models::TransferRequest>,
-) -> Result<impl IntoResponse, crate::error::AppError> {
+    jar: CookieJar,
+    ValidatedForm(req): ValidatedForm<crate::domain::models::TransferRequest>,
+) -> Result<Response, crate::error::AppError> {
     use axum::response::AppendHeaders;
 
+    if !state.features.transfers_enabled {
+        return Err(crate::error::AppError::feature_disabled("Transfers"));
+    }
+
     tracing::info!("📥 Transfer request received: {:?}", req);
+    let limits = SpendingLimits::from_config(&state.config);
+
+    // Large transfers wait for the emailed code (asked for in the form)
+    if req.amount > state.config.limit_confirm_above {
+        let transfer = wallet_service::begin_transfer(
+            &state.pool,
+            &state.email_service,
+            state.clock.as_ref(),
+            Some(&state.exchange_rates),
+            &limits,
+            user_id,
+            &req.recipient_email,
+            req.amount,
+            req.currency,
+        )
+        .await?;
+        return Ok(TransferConfirmTemplate { transfer }.into_response());
+    }
 
     // Call the service
     wallet_service::transfer(
         &state.pool,
+        &ConcurrencyPolicy::from_config(&state.config),
         &state.email_service,
-        &state.notification_service,
+        state.notification_service.as_ref(),
+        Some(&state.exchange_rates),
+        &limits,
+        state.clock.as_ref(),
         user_id,
         &req.recipient_email,
-        req.amount
+        req.amount,
+        req.currency,
     ).await?;
+    flash(&state, &jar, &format!("Sent ${} to {}.", req.amount, req.recipient_email)).await?;
 
     // Return success message and redirect
     Ok((
         AppendHeaders([("HX-Redirect", "/dashboard".to_string())]),
         "Transfer successful! Redirecting..."
+    ).into_response())
+}
+
+/// Confirm a large transfer with the emailed code
+pub async fn transfer_confirm(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Path(transfer_id): Path<Uuid>,
+    ValidatedForm(req): ValidatedForm<ConfirmTransferRequest>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    use axum::response::AppendHeaders;
+
+    let transfer = wallet_service::confirm_transfer(
+        &state.pool,
+        &state.email_service,
+        state.notification_service.as_ref(),
+        state.clock.as_ref(),
+        user_id,
+        transfer_id,
+        &req.code,
+    )
+    .await?;
+    flash(
+        &state,
+        &jar,
+        &format!("Sent {} {} to {}.", transfer.amount, transfer.currency, transfer.recipient_email),
+    )
+    .await?;
+
+    Ok((
+        AppendHeaders([("HX-Redirect", "/dashboard".to_string())]),
+        "Transfer confirmed! Redirecting..."
     ))
 }
 
+/// Cancel a large transfer instead of confirming it
+pub async fn transfer_cancel(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Path(transfer_id): Path<Uuid>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    use axum::response::AppendHeaders;
+
+    wallet_service::cancel_transfer(&state.pool, state.clock.as_ref(), user_id, transfer_id).await?;
+    flash(&state, &jar, "Transfer cancelled. The money is back in your wallet.").await?;
+
+    Ok((
+        AppendHeaders([("HX-Redirect", "/dashboard".to_string())]),
+        "Transfer cancelled. Redirecting..."
+    ))
+}
+
+#[derive(Template)]
+#[template(path = "international.html")]
+struct InternationalTemplate {
+    /// The wallet's currency, which amounts are entered in
+    currency: crate::domain::models::Currency,
+    currencies: Vec<&'static str>,
+    transfers: Vec<InternationalTransferRow>,
+}
+
+/// One transfer on the international page, with its progress
+struct InternationalTransferRow {
+    transfer: InternationalTransferResponse,
+    /// (label, reached at) for each step up to SETTLED
+    steps: Vec<(&'static str, Option<chrono::DateTime<chrono::Utc>>)>,
+    returned: bool,
+}
+
+impl From<InternationalTransferResponse> for InternationalTransferRow {
+    fn from(transfer: InternationalTransferResponse) -> Self {
+        use crate::domain::models::InternationalTransferStatus::*;
+
+        InternationalTransferRow {
+            steps: vec![
+                (Submitted.label(), Some(transfer.submitted_at)),
+                (InTransit.label(), transfer.in_transit_at),
+                (Settled.label(), transfer.settled_at),
+            ],
+            returned: transfer.status == Returned,
+            transfer,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "international_quote.html")]
+struct InternationalQuoteTemplate {
+    quote: crate::domain::models::InternationalQuoteResponse,
+}
+
+/// Serve the international transfer page, with the user's transfers and
+/// where each one is
+pub async fn international_page(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    let wallet = user_repo::get_wallet_by_user_id(&state.pool, user_id).await?;
+    let transfers = international_service::list_transfers(&state.pool, user_id).await?;
+
+    let template = InternationalTemplate {
+        currency: wallet.currency,
+        currencies: crate::domain::models::Currency::ALL
+            .iter()
+            .map(|currency| currency.as_str())
+            .collect(),
+        transfers: transfers.into_iter().map(InternationalTransferRow::from).collect(),
+    };
+
+    Ok(template)
+}
+
+/// Show the fee and rate for the international transfer form
+pub async fn international_quote(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    ValidatedForm(req): ValidatedForm<crate::domain::models::InternationalQuoteRequest>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    if !state.features.transfers_enabled {
+        return Err(crate::error::AppError::feature_disabled("Transfers"));
+    }
+
+    let quote = international_service::quote(
+        &state.pool,
+        &state.exchange_rates,
+        &state.config,
+        user_id,
+        req.amount,
+        req.destination_currency,
+    )
+    .await?;
+
+    Ok(InternationalQuoteTemplate { quote })
+}
+
+/// Handle international transfer form submission
+pub async fn international_submit(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedForm(req): ValidatedForm<crate::domain::models::InternationalTransferRequest>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    use axum::response::AppendHeaders;
+
+    if !state.features.transfers_enabled {
+        return Err(crate::error::AppError::feature_disabled("Transfers"));
+    }
+
+    international_service::submit(
+        &state.pool,
+        &state.exchange_rates,
+        &state.config,
+        user_id,
+        &req,
+    )
+    .await?;
+
+    // Back to the page, where the new transfer shows up as SUBMITTED
+    Ok((
+        AppendHeaders([("HX-Redirect", "/dashboard/international".to_string())]),
+        "Transfer submitted! Redirecting..."
+    ))
+}
+
+#[derive(Template)]
+#[template(path = "approvals.html")]
+struct ApprovalsTemplate {
+    /// (organization name, request) waiting for this user's decision
+    waiting: Vec<(String, ExpenseRequest)>,
+    /// (organization name, request) this user asked for
+    mine: Vec<(String, ExpenseRequest)>,
+    flash: Option<String>,
+}
+
+/// Serve the approvals page: business expenses waiting for the user, and
+/// the ones they asked for
+pub async fn approvals_page(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    let approvals = organization_service::approvals(&state.pool, user_id).await?;
+    let flash = match jar.get(session_service::SESSION_COOKIE) {
+        Some(session) => session_service::take_flash(&state.pool, session.value()).await?,
+        None => None,
+    };
+
+    Ok(ApprovalsTemplate {
+        waiting: approvals.waiting,
+        mine: approvals.mine,
+        flash,
+    })
+}
+
+/// Approve (and pay) an expense from the approvals page
+pub async fn approve_expense(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Path((organization_id, id)): Path<(Uuid, Uuid)>,
+    ValidatedForm(req): ValidatedForm<DecideExpenseRequest>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    use axum::response::AppendHeaders;
+
+    if !state.features.transfers_enabled {
+        return Err(crate::error::AppError::feature_disabled("Transfers"));
+    }
+
+    let expense = organization_service::approve_expense(
+        &state.pool,
+        &state.email_service,
+        state.notification_service.as_ref(),
+        organization_id,
+        user_id,
+        id,
+        decision_note(&req),
+    )
+    .await?;
+    flash(
+        &state,
+        &jar,
+        &format!("Approved: sent ${} to {}.", expense.amount, expense.recipient_email),
+    )
+    .await?;
+
+    Ok((
+        AppendHeaders([("HX-Redirect", "/dashboard/approvals".to_string())]),
+        "Approved! Redirecting...",
+    ))
+}
+
+/// Reject an expense from the approvals page
+pub async fn reject_expense(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Path((organization_id, id)): Path<(Uuid, Uuid)>,
+    ValidatedForm(req): ValidatedForm<DecideExpenseRequest>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    use axum::response::AppendHeaders;
+
+    let expense = organization_service::reject_expense(
+        &state.pool,
+        &state.email_service,
+        state.notification_service.as_ref(),
+        organization_id,
+        user_id,
+        id,
+        decision_note(&req),
+    )
+    .await?;
+    flash(&state, &jar, &format!("Rejected \"{}\".", expense.description)).await?;
+
+    Ok((
+        AppendHeaders([("HX-Redirect", "/dashboard/approvals".to_string())]),
+        "Rejected. Redirecting...",
+    ))
+}
+
+#[derive(Template)]
+#[template(path = "referrals.html")]
+struct ReferralsTemplate {
+    overview: ReferralOverview,
+}
+
+/// Serve the referrals page: the user's code and link, and how the people
+/// who signed up with it are doing
+pub async fn referrals_page(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    let overview = referral_service::overview(&state.pool, &state.config, user_id).await?;
+    Ok(ReferralsTemplate { overview })
+}
+
+/// The note from a decision form (an empty box means none)
+fn decision_note(req: &DecideExpenseRequest) -> Option<&str> {
+    req.note.as_deref().map(str::trim).filter(|note| !note.is_empty())
+}
+
 /// Handle web form registration (form-encoded, not JSON)
 pub async fn register_submit(
     State(state): State<AppState>,
-    Form(req): Form<crate::domain::models::CreateUserRequest>,
+    client: crate::middleware::client::ClientInfo,
+    ValidatedForm(req): ValidatedForm<crate::domain::models::CreateUserRequest>,
 ) -> Result<impl IntoResponse, crate::error::AppError> {
     use axum::response::AppendHeaders;
+
+    if !state.features.registrations_open {
+        return Err(crate::error::AppError::feature_disabled("Registrations"));
+    }
+
+    // (The form always sends the box; empty means no code)
+    let terms = ReferralTerms::from_config(&state.config);
+    let referrer =
+        referral_service::find_referrer(&state.pool, &terms, req.referral_code.as_deref()).await?;
     
     // Call the service
+    let session_id = browser_session(&state);
     let response = crate::services::auth_service::register(
         &state.pool,
         &req.email,
         &req.password,
         &req.full_name,
-        &state.jwt_secret,
+        &state.jwt_keys,
+        session_id,
+        state.clock.as_ref(),
+    )
+    .await?;
+    if let Some(session_id) = session_id {
+        token_service::start_session(
+            &state.pool,
+            state.clock.as_ref(),
+            session_id,
+            response.user.id,
+            &client,
+        )
+        .await?;
+    }
+
+    if let Some(referrer) = referrer {
+        referral_service::attribute(
+            &state.pool,
+            state.clock.as_ref(),
+            &referrer,
+            response.user.id,
+            &response.user.email,
+            client.ip,
+        )
+        .await;
+    }
+
+    // Kick off the onboarding wizard (sends the verification email)
+    onboarding_service::start(
+        &state.pool,
+        &state.email_service,
+        response.user.id,
+        &response.user.email,
     )
     .await?;
     
-    // Build cookie header
-    let cookie_value = format!(
-        "auth_token={}; Path=/; HttpOnly; SameSite=Lax",
-        response.token
-    );
+    // Build cookie headers
+    let mut headers = login_cookies(&state, response.user.id, &response.token).await?;
+    headers.push(("HX-Redirect", "/onboarding".to_string()));
     
     // Return with Set-Cookie and HX-Redirect headers
+    Ok((AppendHeaders(headers), "Registration successful! Redirecting..."))
+}
+
+#[derive(Template)]
+#[template(path = "forgot_password.html")]
+struct ForgotPasswordTemplate;
+
+/// Serve the forgot password page
+pub async fn forgot_password_page() -> impl IntoResponse {
+    ForgotPasswordTemplate
+}
+
+/// Email a reset link (the answer is the same for unknown addresses)
+pub async fn forgot_password_submit(
+    State(state): State<AppState>,
+    ValidatedForm(req): ValidatedForm<ForgotPasswordRequest>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    password_reset_service::forgot(
+        &state.pool,
+        &state.email_service,
+        state.clock.as_ref(),
+        &state.config.public_url(),
+        &req.email,
+    )
+    .await?;
+
+    Ok("If that address has an account, a reset link is on its way.")
+}
+
+#[derive(Template)]
+#[template(path = "reset_password.html")]
+struct ResetPasswordTemplate {
+    /// From the emailed link; without it the page asks for the code
+    token: Option<String>,
+}
+
+/// Serve the reset password page (the link in the email)
+pub async fn reset_password_page(Query(query): Query<ResetPasswordPageQuery>) -> impl IntoResponse {
+    ResetPasswordTemplate { token: query.token }
+}
+
+/// Set the new password, then send the user to sign in with it
+pub async fn reset_password_submit(
+    State(state): State<AppState>,
+    ValidatedForm(req): ValidatedForm<ResetPasswordRequest>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    use axum::response::AppendHeaders;
+
+    password_reset_service::reset(&state.pool, state.clock.as_ref(), &req.token, &req.new_password)
+        .await?;
+
     Ok((
-        AppendHeaders([
-            ("Set-Cookie", cookie_value),
-            ("HX-Redirect", "/dashboard".to_string()),
-        ]),
-        "Registration successful! Redirecting..."
+        AppendHeaders([("HX-Redirect", "/login".to_string())]),
+        "Password changed! Redirecting...",
+    ))
+}
+
+#[derive(Template)]
+#[template(path = "unlock_account.html")]
+struct UnlockAccountTemplate {
+    /// From the emailed link; without it the page asks for the code
+    token: Option<String>,
+}
+
+/// Serve the unlock page (the link in the account-locked email)
+pub async fn unlock_account_page(Query(query): Query<UnlockAccountPageQuery>) -> impl IntoResponse {
+    UnlockAccountTemplate { token: query.token }
+}
+
+/// Unlock sign-in, then send the user to sign in
+pub async fn unlock_account_submit(
+    State(state): State<AppState>,
+    ValidatedForm(req): ValidatedForm<UnlockAccountRequest>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    use axum::response::AppendHeaders;
+
+    login_throttle_service::unlock(&state.pool, &req.token).await?;
+
+    Ok((
+        AppendHeaders([("HX-Redirect", "/login".to_string())]),
+        "Account unlocked! Redirecting...",
     ))
 }
 
 /// Handle web form login (form-encoded, not JSON)
 pub async fn login_submit(
     State(state): State<AppState>,
-    Form(req): Form<crate::domain::models::LoginRequest>,
+    client: crate::middleware::client::ClientInfo,
+    ValidatedForm(req): ValidatedForm<crate::domain::models::LoginRequest>,
 ) -> Result<impl IntoResponse, crate::error::AppError> {
     use axum::response::AppendHeaders;
     
-    // Call the service
-    let response = crate::services::auth_service::login(
+    // Call the service (counts wrong passwords, see login_throttle_service)
+    let session_id = browser_session(&state);
+    let response = login_throttle_service::login(
         &state.pool,
+        &state.email_service,
+        state.clock.as_ref(),
+        &LockoutPolicy::from_config(&state.config),
         &req.email,
         &req.password,
-        &state.jwt_secret,
+        &state.jwt_keys,
+        session_id,
     )
     .await?;
+    if let Some(session_id) = session_id {
+        token_service::start_session(
+            &state.pool,
+            state.clock.as_ref(),
+            session_id,
+            response.user.id,
+            &client,
+        )
+        .await?;
+    }
 
-    // Build cookie header
-    let cookie_value = format!(
-        "auth_token={}; Path=/; HttpOnly; SameSite=Lax",
-        response.token
+    crate::services::login_history_service::record_login(
+        &state.pool,
+        state.geoip.as_ref(),
+        &state.email_service,
+        &state.notification_service,
+        response.user.id,
+        crate::domain::models::LoginMethod::Password,
+        client,
     );
+
+    let redirect_to = landing_page(&state, response.user.id).await?;
+
+    // Build cookie headers
+    let mut headers = login_cookies(&state, response.user.id, &response.token).await?;
+    headers.push(("HX-Redirect", redirect_to.to_string()));
     
     // Return with Set-Cookie and HX-Redirect headers
-    Ok((
-        AppendHeaders([
-            ("Set-Cookie", cookie_value),
-            ("HX-Redirect", "/dashboard".to_string()),
-        ]),
-        "Login successful! Redirecting..."
-    ))
+    Ok((AppendHeaders(headers), "Login successful! Redirecting..."))
 }
 
-/// Handle logout (clear cookie)
-pub async fn logout(jar: CookieJar) -> impl IntoResponse {
-    let cookie = Cookie::build(("auth_token", ""))
-        .path("/")
-        .http_only(true)
-        .same_site(SameSite::Lax)
-        .max_age(Duration::seconds(0))
-        .build();
+/// Handle logout (clear cookie, and end the session or revoke the JWT)
+pub async fn logout(
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    if let Some(session) = jar.get(session_service::SESSION_COOKIE) {
+        session_service::revoke(&state.pool, session.value()).await?;
+    }
+    // (An invalid or expired token has nothing left to revoke)
+    if let Some(token) = jar.get("auth_token") {
+        let claims = crate::utils::jwt::validate_token(
+            token.value(),
+            &state.jwt_keys,
+            state.clock.as_ref(),
+        );
+        if let Ok(claims) = claims {
+            if !claims.jti.is_empty() {
+                token_service::logout(&state.pool, &claims).await?;
+            }
+        }
+    }
+
+    let jar = [
+        ("auth_token", true),
+        (session_service::SESSION_COOKIE, true),
+        (session_service::CSRF_COOKIE, false),
+    ]
+    .into_iter()
+    .fold(jar, |jar, (name, http_only)| {
+        jar.add(
+            Cookie::build((name, ""))
+                .path("/")
+                .http_only(http_only)
+                .same_site(SameSite::Lax)
+                .secure(state.config.secure_cookies)
+                .max_age(Duration::seconds(0))
+                .build(),
+        )
+    });
     
-    (jar.add(cookie), Redirect::to("/login"))
+    Ok((jar, Redirect::to("/login")))
+}
+
+/// The device session (see token_service) for a browser that's signing
+/// in, if it's handed a JWT; in session mode it gets a server-side session
+/// instead
+pub(crate) fn browser_session(state: &AppState) -> Option<uuid::Uuid> {
+    (state.web_auth_mode == WebAuthMode::Jwt).then(crate::domain::ids::new_id)
+}
+
+/// Build the Set-Cookie headers for a freshly logged-in browser
+///
+/// JWT mode stores the token itself; session mode creates a server-side
+/// session and stores its id plus the CSRF token.
+pub(crate) async fn login_cookies(
+    state: &AppState,
+    user_id: uuid::Uuid,
+    token: &str,
+) -> Result<Vec<(&'static str, String)>, crate::error::AppError> {
+    let secure = state.config.secure_cookies;
+    match state.web_auth_mode {
+        WebAuthMode::Jwt => Ok(vec![(
+            "Set-Cookie",
+            format!(
+                "auth_token={}; Path=/; HttpOnly; SameSite=Lax{}",
+                token,
+                if secure { "; Secure" } else { "" }
+            ),
+        )]),
+        WebAuthMode::Session => {
+            let session = session_service::create(&state.pool, user_id).await?;
+            Ok(session_service::cookies(&session, secure)
+                .into_iter()
+                .map(|cookie| ("Set-Cookie", cookie))
+                .collect())
+        }
+    }
+}
+
+/// Where a freshly logged-in user goes: back to an unfinished onboarding
+/// wizard, or the dashboard
+pub(crate) async fn landing_page(
+    state: &AppState,
+    user_id: uuid::Uuid,
+) -> Result<&'static str, crate::error::AppError> {
+    if onboarding_service::pending(&state.pool, user_id).await?.is_some() {
+        Ok("/onboarding")
+    } else {
+        Ok("/dashboard")
+    }
+}
+
+/// Queue a flash message for the dashboard (session mode only, no-op otherwise)
+async fn flash(state: &AppState, jar: &CookieJar, message: &str) -> Result<(), crate::error::AppError> {
+    if let Some(session) = jar.get(session_service::SESSION_COOKIE) {
+        session_service::set_flash(&state.pool, session.value(), message).await?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// ERROR PAGES
+// ============================================================================
+
+#[derive(Template)]
+#[template(path = "error.html")]
+struct ErrorTemplate {
+    status: u16,
+    title: &'static str,
+    message: String,
+    request_id: Option<String>,
+}
+
+/// Render the HTML error page for a status code
+pub fn error_page(status: StatusCode, message: &str, request_id: Option<String>) -> Response {
+    let title = match status {
+        StatusCode::NOT_FOUND => "Page not found",
+        StatusCode::FORBIDDEN => "Access denied",
+        StatusCode::TOO_MANY_REQUESTS => "Slow down",
+        s if s.is_server_error() => "Something went wrong",
+        _ => "We couldn't do that",
+    };
+
+    let template = ErrorTemplate {
+        status: status.as_u16(),
+        title,
+        message: message.to_string(),
+        request_id,
+    };
+
+    (status, template).into_response()
+}
+
+/// Fallback for unknown routes: JSON under /api, an HTML page elsewhere
+pub async fn fallback(uri: Uri, headers: HeaderMap) -> Response {
+    if uri.path().starts_with("/api") {
+        return crate::error::AppError::not_found("Route").into_response();
+    }
+
+    let request_id = headers
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+
+    error_page(
+        StatusCode::NOT_FOUND,
+        "The page you're looking for doesn't exist or has moved.",
+        request_id,
+    )
+}
+
+// ============================================================================
+// ONBOARDING WIZARD
+// ============================================================================
+
+#[derive(Template)]
+#[template(path = "onboarding.html")]
+struct OnboardingTemplate {
+    step: &'static str,
+    step_number: usize,
+    // (label, reached) for each step in the progress bar
+    steps: Vec<(&'static str, bool)>,
+    email: String,
+    notify_email: bool,
+    notify_realtime: bool,
+    login_alerts: bool,
+}
+
+/// Serve the current onboarding step (protected)
+///
+/// Users with nothing left to do are sent to the dashboard.
+pub async fn onboarding_page(
+    AuthUser(user_id): AuthUser,
+    State(state): State<AppState>,
+) -> Result<Response, crate::error::AppError> {
+    let Some(progress) = onboarding_service::pending(&state.pool, user_id).await? else {
+        return Ok(Redirect::to("/dashboard").into_response());
+    };
+    let user = user_repo::find_user_by_id(&state.pool, user_id).await?;
+    let login_alerts = user_repo::get_login_alerts(&state.pool, user_id).await?;
+    let step = progress.step();
+
+    let template = OnboardingTemplate {
+        step: step.as_str(),
+        step_number: step.number(),
+        steps: OnboardingStep::WIZARD
+            .iter()
+            .map(|s| (s.label(), s.number() <= step.number()))
+            .collect(),
+        email: user.email,
+        notify_email: progress.notify_email,
+        notify_realtime: progress.notify_realtime,
+        login_alerts,
+    };
+
+    Ok(template.into_response())
+}
+
+/// Handle the email verification code
+pub async fn onboarding_verify_email(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedForm(req): ValidatedForm<crate::domain::models::VerifyEmailRequest>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    onboarding_service::verify_email(&state.pool, user_id, &req.code).await?;
+    Ok(onboarding_redirect("Email verified!"))
+}
+
+/// Send a new verification code
+pub async fn onboarding_resend_code(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    onboarding_service::resend_code(&state.pool, &state.email_service, user_id).await?;
+    Ok("A new code is on its way.")
+}
+
+/// Handle the notification preferences step
+pub async fn onboarding_notifications(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    AppForm(req): AppForm<crate::domain::models::NotificationPrefsRequest>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    onboarding_service::save_notification_prefs(
+        &state.pool,
+        user_id,
+        req.notify_email.is_some(),
+        req.notify_realtime.is_some(),
+        req.login_alerts.is_some(),
+    )
+    .await?;
+    Ok(onboarding_redirect("Preferences saved!"))
+}
+
+/// Handle the two-factor step
+pub async fn onboarding_two_factor(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    AppForm(req): AppForm<crate::domain::models::TwoFactorChoiceRequest>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    onboarding_service::choose_two_factor(&state.pool, user_id, req.enable).await?;
+    Ok(onboarding_redirect("Saved!"))
+}
+
+/// Handle the first deposit step (finishes onboarding)
+pub async fn onboarding_deposit(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+    ValidatedForm(req): ValidatedForm<crate::domain::models::DepositRequest>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    onboarding_service::first_deposit(
+        &state.pool,
+        &state.email_service,
+        state.notification_service.as_ref(),
+        user_id,
+        req.amount,
+    )
+    .await?;
+    Ok(onboarding_redirect("You're all set!"))
+}
+
+/// Skip the first deposit (finishes onboarding)
+pub async fn onboarding_skip_deposit(
+    RequireScope(user_id, _): RequireScope<WalletWrite>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, crate::error::AppError> {
+    onboarding_service::skip_deposit(&state.pool, user_id).await?;
+    Ok(onboarding_redirect("You're all set!"))
+}
+
+/// Reload the wizard; `onboarding_page` forwards to the dashboard once done
+fn onboarding_redirect(message: &'static str) -> impl IntoResponse {
+    use axum::response::AppendHeaders;
+
+    (
+        AppendHeaders([("HX-Redirect", "/onboarding".to_string())]),
+        message,
+    )
 }
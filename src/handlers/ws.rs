@@ -1,21 +1,51 @@
 use axum::{
-    extract::{ws::{WebSocket, WebSocketUpgrade}, State},
+    extract::{ws::{close_code, CloseFrame, WebSocket, WebSocketUpgrade}, State},
     response::IntoResponse,
 };
-use futures::{sink::SinkExt, stream::StreamExt};
-use tokio::sync::mpsc;
+use futures::{sink::SinkExt, stream::{SplitSink, StreamExt}};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::time::{Instant, MissedTickBehavior};
 
+use crate::domain::models::WebSocketQuery;
 use crate::routes::auth_routes::AppState;
 use crate::middleware::auth::get_user_from_cookie;
+use crate::middleware::validation::ValidatedQuery;
+use crate::services::notification_service::{self, LiveNotification, LiveSink};
 
 /// WebSocket handler - upgrades HTTP to WebSocket
+///
+/// HTTP Endpoint: GET /ws?since=42
+///
+/// Every notification carries a "sequence". With `since`, the ones after
+/// it that the client missed while disconnected are sent first, then
+///
+/// ```json
+/// { "type": "connected", "sequence": 45 }
+/// ```
+///
+/// (the last sequence sent, or without `since` the latest so far), then
+/// live ones. However many were missed, they are all sent.
+///
+/// The server pings every WS_PING_INTERVAL_SECS. A client that sends nothing
+/// (pongs count) for WS_IDLE_TIMEOUT_SECS is closed with code 1001; it can
+/// reconnect with the last sequence it saw as `since` to pick up where it
+/// left off.
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
-    cookies: axum_extra::extract::CookieJar,
+    ValidatedQuery(query): ValidatedQuery<WebSocketQuery>,
+    headers: axum::http::HeaderMap,
 ) -> Result<impl IntoResponse, (axum::http::StatusCode, String)> {
+    if !state.features.websocket_enabled {
+        return Err((
+            axum::http::StatusCode::SERVICE_UNAVAILABLE,
+            "Live updates are temporarily disabled".to_string(),
+        ));
+    }
+
     // Extract user from cookie
-    let user_id = match get_user_from_cookie(&cookies, &state.jwt_secret) {
+    let user_id = match get_user_from_cookie(&headers, &state).await {
         Ok(id) => id,
         Err(_) => {
             return Err((
@@ -26,31 +56,81 @@ pub async fn websocket_handler(
     };
 
     // Upgrade the connection
-    Ok(ws.on_upgrade(move |socket| handle_socket(socket, state, user_id)))
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, state, user_id, query.since)))
 }
 
 /// Handle the WebSocket connection
-async fn handle_socket(socket: WebSocket, state: AppState, user_id: uuid::Uuid) {
+async fn handle_socket(socket: WebSocket, state: AppState, user_id: uuid::Uuid, since: Option<i64>) {
     let (mut sender, mut receiver) = socket.split();
     
     // Create a channel for this client
-    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    let (tx, mut rx) = mpsc::unbounded_channel::<LiveNotification>();
+
+    // When the client was last heard from (any frame, pongs included)
+    let (seen_tx, seen_rx) = watch::channel(Instant::now());
+    let ping_every = Duration::from_secs(state.config.ws_ping_interval_secs);
+    let idle_timeout = Duration::from_secs(state.config.ws_idle_timeout_secs);
     
-    // Register this client
-    state.notification_service.add_client(user_id, tx).await;
+    // Register this client before reading the inbox, so nothing sent in
+    // between is lost (it may arrive twice; duplicates are skipped below)
+    let connection_id = state.notification_service.add_client(user_id, tx).await;
 
-    // Task to send messages to the client
+    // Task to send messages to the client: what it missed, then live ones,
+    // until either side hangs up or the server shuts down
+    let pool = state.pool.clone();
+    let notifier = state.notification_service.clone();
     let mut send_task = tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            if sender.send(axum::extract::ws::Message::Text(msg)).await.is_err() {
+        let Some(replayed) = notification_service::catch_up(&pool, user_id, since, &mut sender).await
+        else {
+            return;
+        };
+
+        // The first tick is one interval from now, not immediate
+        let mut heartbeat = tokio::time::interval_at(Instant::now() + ping_every, ping_every);
+        heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            let notification = tokio::select! {
+                notification = rx.recv() => notification,
+                _ = heartbeat.tick() => {
+                    if seen_rx.borrow().elapsed() >= idle_timeout {
+                        tracing::debug!("WebSocket for user {} went idle, closing", user_id);
+                        let close = CloseFrame {
+                            code: close_code::AWAY,
+                            reason: "Idle timeout".into(),
+                        };
+                        let _ = sender.send(axum::extract::ws::Message::Close(Some(close))).await;
+                        break;
+                    }
+                    if sender.send(axum::extract::ws::Message::Ping(Vec::new())).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                _ = notifier.closing() => {
+                    let close = CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "Server is shutting down".into(),
+                    };
+                    let _ = sender.send(axum::extract::ws::Message::Close(Some(close))).await;
+                    break;
+                }
+            };
+            let Some(notification) = notification else { break };
+            if !replayed.is_new(&notification) {
+                continue;
+            }
+            if sender.send(axum::extract::ws::Message::Text(notification.message)).await.is_err() {
                 break;
             }
         }
     });
 
-    // Task to receive messages from the client (mostly just keep-alive pings)
+    // Task to receive messages from the client (mostly pongs and keep-alive
+    // pings); anything at all means it's still there
     let mut recv_task = tokio::spawn(async move {
         while let Some(Ok(msg)) = receiver.next().await {
+            seen_tx.send_replace(Instant::now());
             if matches!(msg, axum::extract::ws::Message::Close(_)) {
                 break;
             }
@@ -63,6 +143,13 @@ async fn handle_socket(socket: WebSocket, state: AppState, user_id: uuid::Uuid)
         _ = (&mut recv_task) => send_task.abort(),
     }
 
-    // Clean up: remove client from the map
-    state.notification_service.remove_client(&user_id).await;
+    // Clean up: forget this connection (the user's others stay)
+    state.notification_service.remove_client(&user_id, connection_id).await;
+}
+
+#[async_trait::async_trait]
+impl LiveSink for SplitSink<WebSocket, axum::extract::ws::Message> {
+    async fn deliver(&mut self, message: String, _sequence: i64) -> bool {
+        self.send(axum::extract::ws::Message::Text(message)).await.is_ok()
+    }
 }
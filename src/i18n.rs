@@ -0,0 +1,155 @@
+use crate::error::ErrorCode;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// LOCALIZED ERROR MESSAGES
+// ============================================================================
+// A small message catalog keyed by (ErrorCode, Locale).
+//
+// Why do we need this?
+// - Error responses used to be English only
+// - The `code` field already tells us WHAT went wrong, so we only need one
+//   translated sentence per code and language
+//
+// How the locale is picked (first match wins):
+// 1. The logged-in user's saved preference (users.locale)
+// 2. The browser's Accept-Language header
+// 3. English
+//
+// English responses keep the detailed message from the AppError
+// (e.g. "Amount must be positive"); other languages use the catalog
+// sentence for the error's code.
+
+/// Languages we have translations for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+    Fr,
+}
+
+impl Locale {
+    /// Every supported locale
+    pub const ALL: [Locale; 3] = [Locale::En, Locale::Es, Locale::Fr];
+
+    /// The language tag, e.g. "es"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Es => "es",
+            Locale::Fr => "fr",
+        }
+    }
+
+    /// Parse a language tag, ignoring the region ("es-MX" -> Es)
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        Locale::ALL.into_iter().find(|l| l.as_str() == language)
+    }
+
+    /// Pick the best supported locale from an Accept-Language header
+    ///
+    /// (format: "fr-CH, fr;q=0.9, en;q=0.8, *;q=0.5")
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        let mut candidates: Vec<(f32, Locale)> = header
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let locale = Locale::parse(parts.next()?)?;
+                let quality = parts
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                (quality > 0.0).then_some((quality, locale))
+            })
+            .collect();
+
+        // Stable sort keeps header order for equal weights
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        candidates.first().map(|(_, locale)| *locale)
+    }
+}
+
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The translated, user-facing sentence for an error code
+pub fn error_message(code: ErrorCode, locale: Locale) -> &'static str {
+    use ErrorCode::*;
+    use Locale::*;
+
+    match (code, locale) {
+        (DatabaseError | InternalError, En) => "Something went wrong on our side. Please try again in a moment.",
+        (DatabaseError | InternalError, Es) => "Algo salió mal por nuestra parte. Vuelve a intentarlo en un momento.",
+        (DatabaseError | InternalError, Fr) => "Une erreur est survenue de notre côté. Veuillez réessayer dans un instant.",
+
+        (InvalidCredentials, En) => "Invalid email or password.",
+        (InvalidCredentials, Es) => "Correo electrónico o contraseña incorrectos.",
+        (InvalidCredentials, Fr) => "Adresse e-mail ou mot de passe incorrect.",
+
+        (InvalidToken, En) => "Please log in to continue.",
+        (InvalidToken, Es) => "Inicia sesión para continuar.",
+        (InvalidToken, Fr) => "Veuillez vous connecter pour continuer.",
+
+        (Forbidden, En) => "You don't have permission to do that.",
+        (Forbidden, Es) => "No tienes permiso para hacer eso.",
+        (Forbidden, Fr) => "Vous n'avez pas l'autorisation de faire cela.",
+
+        (InsufficientScope, En) => "This token isn't allowed to do that.",
+        (InsufficientScope, Es) => "Este token no tiene permiso para hacer eso.",
+        (InsufficientScope, Fr) => "Ce jeton n'est pas autorisé à faire cela.",
+
+        (AccountDeleted, En) => "This account has been deleted.",
+        (AccountDeleted, Es) => "Esta cuenta ha sido eliminada.",
+        (AccountDeleted, Fr) => "Ce compte a été supprimé.",
+
+        (ValidationError, En) => "Some of the information you entered is invalid.",
+        (ValidationError, Es) => "Algunos de los datos introducidos no son válidos.",
+        (ValidationError, Fr) => "Certaines des informations saisies ne sont pas valides.",
+
+        (UserAlreadyExists, En) => "An account with this email already exists.",
+        (UserAlreadyExists, Es) => "Ya existe una cuenta con este correo electrónico.",
+        (UserAlreadyExists, Fr) => "Un compte existe déjà avec cette adresse e-mail.",
+
+        (NotFound, En) => "We couldn't find what you were looking for.",
+        (NotFound, Es) => "No encontramos lo que buscabas.",
+        (NotFound, Fr) => "Nous n'avons pas trouvé ce que vous cherchiez.",
+
+        (InsufficientBalance, En) => "Your balance is too low for this transaction.",
+        (InsufficientBalance, Es) => "Tu saldo es insuficiente para esta operación.",
+        (InsufficientBalance, Fr) => "Votre solde est insuffisant pour cette opération.",
+
+        (CurrencyMismatch, En) => "This transaction is in a different currency than the wallet.",
+        (CurrencyMismatch, Es) => "Esta operación está en una moneda distinta a la del monedero.",
+        (CurrencyMismatch, Fr) => "Cette opération est dans une devise différente de celle du portefeuille.",
+
+        (LimitExceeded, En) => "This would go over your spending limit.",
+        (LimitExceeded, Es) => "Esta operación superaría tu límite de gasto.",
+        (LimitExceeded, Fr) => "Cette opération dépasserait votre plafond de dépenses.",
+
+        (TransactionFailed, En) => "The transaction could not be completed.",
+        (TransactionFailed, Es) => "No se pudo completar la operación.",
+        (TransactionFailed, Fr) => "L'opération n'a pas pu être effectuée.",
+
+        (AccountFrozen, En) => "Your account is frozen. Please contact support.",
+        (AccountFrozen, Es) => "Tu cuenta está congelada. Ponte en contacto con el soporte.",
+        (AccountFrozen, Fr) => "Votre compte est gelé. Veuillez contacter le support.",
+
+        (TooManyAttempts, En) => "Too many failed sign-in attempts. Please wait, or check your email if your account was locked.",
+        (TooManyAttempts, Es) => "Demasiados intentos fallidos de inicio de sesión. Espera un momento o revisa tu correo si tu cuenta fue bloqueada.",
+        (TooManyAttempts, Fr) => "Trop de tentatives de connexion échouées. Patientez, ou consultez vos e-mails si votre compte a été verrouillé.",
+
+        (RateLimited, En) => "You've done this too often lately. Please try again later.",
+        (RateLimited, Es) => "Has hecho esto demasiadas veces últimamente. Vuelve a intentarlo más tarde.",
+        (RateLimited, Fr) => "Vous avez fait cela trop souvent récemment. Veuillez réessayer plus tard.",
+
+        (FeatureDisabled, En) => "This feature is temporarily disabled. Please try again later.",
+        (FeatureDisabled, Es) => "Esta función está desactivada temporalmente. Vuelve a intentarlo más tarde.",
+        (FeatureDisabled, Fr) => "Cette fonctionnalité est temporairement désactivée. Veuillez réessayer plus tard.",
+    }
+}
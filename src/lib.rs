@@ -1,9 +1,21 @@
 pub mod routes;
 pub mod handlers;
 pub mod services;
+pub mod sso;
 pub mod domain;
 pub mod repository;
 pub mod middleware;
 pub mod utils;
 pub mod config;
 pub mod error;
+pub mod i18n;
+pub mod telemetry;
+pub mod build_info;
+pub mod cli;
+pub mod logging;
+pub mod seeder;
+pub mod smoketest;
+pub mod loadgen;
+pub mod shutdown;
+#[cfg(feature = "memory-repo")]
+pub mod demo;
@@ -0,0 +1,397 @@
+use api_client::api_types::{
+    CreateUserRequest, DepositRequest, HistoryQuery, LoginRequest, TransferRequest,
+};
+use api_client::{ApiClient, ClientError, Session};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+// ============================================================================
+// LOAD GENERATOR
+// ============================================================================
+// `my-fintech-app loadgen --url http://localhost:3000 --users 50 --duration-secs 60`
+// puts a running instance under load, to see what the rate limiter and the
+// transfer path do under pressure:
+//
+//   1. `--users` throwaway users register and deposit money to send
+//   2. each one then loops until the time is up, picking what to do from
+//      `--mix` (weights, default login=1,read=6,transfer=3):
+//        login    - POST /api/login
+//        read     - GET /api/wallet or GET /api/transactions
+//        transfer - send 0.01 to another load user
+//   3. latency percentiles per operation are printed at the end, with how
+//      many requests were rate limited (429) or failed
+//
+// All users come from this machine, so they share one rate-limit bucket:
+// expect 429s once the total rate passes RATE_LIMIT_MAX_REQUESTS per
+// window, which is the point when testing the limiter. Run it against
+// staging with a raised limit to measure the transfer path instead.
+//
+// The users are left behind (loadgen+<run>-<n>@<--email-domain>). Never
+// point this at production.
+
+/// Money each load user starts with
+const STARTING_BALANCE: Decimal = Decimal::from_parts(100_000, 0, 0, false, 2);
+
+/// What each transfer sends
+const TRANSFER_AMOUNT: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+
+/// What a load user does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Operation {
+    Login,
+    Read,
+    Transfer,
+}
+
+impl Operation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Login => "login",
+            Operation::Read => "read",
+            Operation::Transfer => "transfer",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "login" => Some(Operation::Login),
+            "read" => Some(Operation::Read),
+            "transfer" => Some(Operation::Transfer),
+            _ => None,
+        }
+    }
+}
+
+/// How often each operation is picked, relative to the others
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mix(Vec<(Operation, u32)>);
+
+impl Default for Mix {
+    fn default() -> Self {
+        Mix(vec![(Operation::Login, 1), (Operation::Read, 6), (Operation::Transfer, 3)])
+    }
+}
+
+/// "login=1,read=6,transfer=3" (operations left out aren't done)
+impl FromStr for Mix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let (name, weight) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected operation=weight, got '{}'", part))?;
+            let operation = Operation::parse(name.trim()).ok_or_else(|| {
+                format!("unknown operation '{}' (login, read or transfer)", name.trim())
+            })?;
+            let weight: u32 = weight
+                .trim()
+                .parse()
+                .map_err(|_| format!("weight for {} must be a whole number", name.trim()))?;
+            if weights.iter().any(|(op, _)| *op == operation) {
+                return Err(format!("{} is given twice", operation.as_str()));
+            }
+            weights.push((operation, weight));
+        }
+
+        if weights.iter().all(|(_, weight)| *weight == 0) {
+            return Err("at least one operation needs a weight above 0".to_string());
+        }
+        Ok(Mix(weights))
+    }
+}
+
+impl Mix {
+    fn total(&self) -> u64 {
+        self.0.iter().map(|(_, weight)| u64::from(*weight)).sum()
+    }
+
+    /// The operation at `roll` (0..total) along the weights
+    fn pick(&self, mut roll: u64) -> Operation {
+        for (operation, weight) in &self.0 {
+            if roll < u64::from(*weight) {
+                return *operation;
+            }
+            roll -= u64::from(*weight);
+        }
+        // `roll` is always below the total
+        self.0[0].0
+    }
+
+    fn includes(&self, operation: Operation) -> bool {
+        self.0.iter().any(|(op, weight)| *op == operation && *weight > 0)
+    }
+}
+
+/// What to run and against what
+#[derive(Debug, Clone)]
+pub struct LoadOptions {
+    /// The instance, e.g. http://localhost:3000
+    pub url: String,
+    /// Simulated users, each running requests back to back
+    pub users: u32,
+    pub duration: Duration,
+    pub mix: Mix,
+    /// Domain of the load users' email addresses
+    pub email_domain: String,
+    /// Per request
+    pub timeout: Duration,
+    /// Seeds each user's choices, so runs are repeatable
+    pub seed: u64,
+}
+
+/// Latencies and outcomes of one operation
+#[derive(Debug, Default, Clone)]
+pub struct OperationStats {
+    /// Latencies of the requests that succeeded
+    pub latencies: Vec<Duration>,
+    pub rate_limited: u64,
+    pub failed: u64,
+}
+
+impl OperationStats {
+    pub fn requests(&self) -> u64 {
+        self.latencies.len() as u64 + self.rate_limited + self.failed
+    }
+
+    /// The `p`th percentile (0-100) of successful requests, nearest rank
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let mut sorted = self.latencies.clone();
+        sorted.sort_unstable();
+        let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.clamp(1, sorted.len()) - 1])
+    }
+
+    fn merge(&mut self, other: OperationStats) {
+        self.latencies.extend(other.latencies);
+        self.rate_limited += other.rate_limited;
+        self.failed += other.failed;
+    }
+}
+
+/// Everything that happened in a run
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    pub operations: BTreeMap<Operation, OperationStats>,
+    pub elapsed: Duration,
+    /// A few of the errors seen, for a hint at what went wrong
+    pub sample_errors: Vec<String>,
+}
+
+impl LoadReport {
+    /// Print the results table
+    pub fn print(&self) {
+        let seconds = self.elapsed.as_secs_f64().max(0.001);
+        let total: u64 = self.operations.values().map(OperationStats::requests).sum();
+        println!(
+            "\n📊 {} requests in {:.1}s ({:.1}/s)\n",
+            total,
+            seconds,
+            total as f64 / seconds
+        );
+        println!(
+            "{:<10} {:>8} {:>8} {:>8} {:>8} {:>9} {:>9} {:>9} {:>9}",
+            "operation", "ok", "429", "failed", "req/s", "p50 ms", "p90 ms", "p99 ms", "max ms"
+        );
+
+        let ms = |latency: Option<Duration>| {
+            latency
+                .map(|d| format!("{:.1}", d.as_secs_f64() * 1000.0))
+                .unwrap_or_else(|| "-".to_string())
+        };
+        for (operation, stats) in &self.operations {
+            println!(
+                "{:<10} {:>8} {:>8} {:>8} {:>8.1} {:>9} {:>9} {:>9} {:>9}",
+                operation.as_str(),
+                stats.latencies.len(),
+                stats.rate_limited,
+                stats.failed,
+                stats.requests() as f64 / seconds,
+                ms(stats.percentile(50.0)),
+                ms(stats.percentile(90.0)),
+                ms(stats.percentile(99.0)),
+                ms(stats.percentile(100.0)),
+            );
+        }
+
+        if !self.sample_errors.is_empty() {
+            println!("\nSome of the failures:");
+            for error in &self.sample_errors {
+                println!("  - {}", error);
+            }
+        }
+    }
+}
+
+/// Errors kept for the report
+const SAMPLE_ERRORS: usize = 5;
+
+/// Set up the users, then run the mix until `options.duration` is up
+///
+/// Fails only if the users can't be set up; errors under load are counted
+/// in the report.
+pub async fn run(options: &LoadOptions) -> Result<LoadReport, ClientError> {
+    let client = ApiClient::new(&options.url, options.timeout)?;
+    client.health().await?;
+
+    let run_id = Uuid::new_v4().simple().to_string();
+    let password = format!("Load-{}", Uuid::new_v4().simple());
+    println!("🏗️  Registering {} load users...", options.users);
+    let mut sessions = Vec::with_capacity(options.users as usize);
+    for n in 0..options.users {
+        let email = format!("loadgen+{}-{}@{}", &run_id[..12], n, options.email_domain);
+        let session = client
+            .register(&CreateUserRequest {
+                email,
+                password: password.clone(),
+                full_name: "Load Test User".to_string(),
+                referral_code: None,
+            })
+            .await?;
+        if options.mix.includes(Operation::Transfer) {
+            let deposit = DepositRequest { amount: STARTING_BALANCE, currency: None };
+            client.deposit(&session, &deposit).await?;
+        }
+        sessions.push(session);
+    }
+    let emails: Vec<String> = sessions.iter().map(|s| s.email.clone()).collect();
+
+    println!(
+        "🚀 Running {} users for {}s against {}",
+        options.users,
+        options.duration.as_secs(),
+        options.url
+    );
+    let started = Instant::now();
+    let deadline = started + options.duration;
+    let mut tasks = Vec::with_capacity(sessions.len());
+    for (n, session) in sessions.into_iter().enumerate() {
+        let user = LoadUser {
+            client: client.clone(),
+            session,
+            password: password.clone(),
+            peers: emails.clone(),
+            mix: options.mix.clone(),
+            rng: SplitMix(options.seed.wrapping_add(n as u64)),
+        };
+        tasks.push(tokio::spawn(user.run(deadline)));
+    }
+
+    let mut report = LoadReport::default();
+    for task in tasks {
+        let (operations, errors) = task.await.expect("load user panicked");
+        for (operation, stats) in operations {
+            report.operations.entry(operation).or_default().merge(stats);
+        }
+        for error in errors {
+            if report.sample_errors.len() < SAMPLE_ERRORS {
+                report.sample_errors.push(error);
+            }
+        }
+    }
+    report.elapsed = started.elapsed();
+
+    Ok(report)
+}
+
+/// One simulated user
+struct LoadUser {
+    client: ApiClient,
+    session: Session,
+    password: String,
+    /// Every load user's email, to send money to
+    peers: Vec<String>,
+    mix: Mix,
+    rng: SplitMix,
+}
+
+impl LoadUser {
+    async fn run(mut self, deadline: Instant) -> (BTreeMap<Operation, OperationStats>, Vec<String>) {
+        let mut stats: BTreeMap<Operation, OperationStats> = BTreeMap::new();
+        let mut errors = Vec::new();
+        let total = self.mix.total();
+
+        while Instant::now() < deadline {
+            let operation = self.mix.pick(self.rng.below(total));
+            let started = Instant::now();
+            let result = match operation {
+                Operation::Login => self.login().await,
+                Operation::Read => self.read().await,
+                Operation::Transfer => self.transfer().await,
+            };
+            let elapsed = started.elapsed();
+
+            let entry = stats.entry(operation).or_default();
+            match result {
+                Ok(()) => entry.latencies.push(elapsed),
+                Err(e) if e.is_rate_limited() => entry.rate_limited += 1,
+                Err(e) => {
+                    entry.failed += 1;
+                    if errors.len() < SAMPLE_ERRORS {
+                        errors.push(format!("{}: {}", operation.as_str(), e));
+                    }
+                }
+            }
+        }
+
+        (stats, errors)
+    }
+
+    async fn login(&mut self) -> Result<(), ClientError> {
+        let request = LoginRequest {
+            email: self.session.email.clone(),
+            password: self.password.clone(),
+        };
+        self.session = self.client.login(&request).await?;
+        Ok(())
+    }
+
+    async fn read(&mut self) -> Result<(), ClientError> {
+        if self.rng.below(2) == 0 {
+            self.client.wallet(&self.session).await.map(|_| ())
+        } else {
+            let first_page = HistoryQuery { limit: Some(20), ..Default::default() };
+            self.client.history(&self.session, &first_page).await.map(|_| ())
+        }
+    }
+
+    async fn transfer(&mut self) -> Result<(), ClientError> {
+        let recipient_email = loop {
+            let peer = &self.peers[self.rng.below(self.peers.len() as u64) as usize];
+            if *peer != self.session.email || self.peers.len() == 1 {
+                break peer.clone();
+            }
+        };
+        let request = TransferRequest {
+            recipient_email,
+            amount: TRANSFER_AMOUNT,
+            currency: None,
+        };
+        self.client.transfer(&self.session, &request).await.map(|_| ())
+    }
+}
+
+/// A small, fast, seedable generator (splitmix64), like the seeder's
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// 0..n
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::{Arc, RwLock};
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
+use crate::error::AppError;
+
+// ============================================================================
+// LOGGING
+// ============================================================================
+// Sets up tracing with a filter that can be swapped while the server runs.
+//
+// Why do we need this?
+// - In production we log at "info"; when something odd happens we want
+//   "debug" for ONE module without restarting (and losing the state that
+//   made it happen)
+// - `tracing_subscriber::reload` lets us replace the EnvFilter in place
+//
+// The admin API exposes this as GET/PUT /api/admin/log-level.
+
+/// Handle for reading and changing the active log filter
+#[derive(Clone)]
+pub struct LogLevelHandle {
+    handle: reload::Handle<EnvFilter, Registry>,
+    /// The filter as text (EnvFilter can't be turned back into a string reliably)
+    current: Arc<RwLock<String>>,
+}
+
+impl LogLevelHandle {
+    /// The active filter, e.g. "info,my_fintech_app::handlers=debug"
+    pub fn current(&self) -> String {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Replace the active filter
+    pub fn set(&self, filter: &str) -> Result<(), AppError> {
+        let new_filter = EnvFilter::try_new(filter).map_err(|e| {
+            AppError::validation(&format!("Invalid log filter {:?}: {}", filter, e))
+        })?;
+
+        self.handle
+            .reload(new_filter)
+            .map_err(|e| AppError::internal(&format!("Failed to change log filter: {}", e)))?;
+        *self.current.write().unwrap() = filter.to_string();
+
+        Ok(())
+    }
+}
+
+/// Install the global tracing subscriber with a reloadable filter
+pub fn init(filter: &str) -> LogLevelHandle {
+    let env_filter = EnvFilter::try_new(filter).unwrap_or_else(|_| EnvFilter::new("info"));
+    let (filter_layer, handle) = reload::Layer::new(env_filter);
+
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(tracing_subscriber::fmt::layer().with_target(false).compact())
+        .init();
+
+    LogLevelHandle {
+        handle,
+        current: Arc::new(RwLock::new(filter.to_string())),
+    }
+}
@@ -1,101 +1,250 @@
+use clap::Parser;
 use my_fintech_app::{
+    cli::{self, Cli, Command, MigrateAction},
     config, 
-    routes::auth_routes::{auth_routes, AppState},
-    handlers
+    routes::app::app,
+    routes::auth_routes::AppState,
+    shutdown::{self, Shutdown},
 };
-use axum::routing::{get, post};
-use axum::Router;
-use tower_http::services::ServeDir;
-use tower_http::trace::TraceLayer;
-use tracing_subscriber;
+use std::time::Duration;
+
+/// How long background tasks get to finish their current run once the
+/// server has stopped taking requests
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(20);
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_target(false)
-        .compact()
-        .init();
+    let cli = Cli::parse();
+
+    // Everything in memory: no config or database
+    if cli.demo {
+        tracing_subscriber::fmt().with_target(false).compact().init();
+        #[cfg(feature = "memory-repo")]
+        {
+            my_fintech_app::demo::run(cli.port.unwrap_or(3000)).await?;
+            return Ok(());
+        }
+        #[cfg(not(feature = "memory-repo"))]
+        {
+            tracing::error!("❌ --demo needs the in-memory repository: cargo run --features memory-repo -- --demo");
+            std::process::exit(1);
+        }
+    }
+
+    // Runs against a deployed instance over HTTP: no config or database
+    if let Some(Command::Smoketest { url, email_domain, admin_token, timeout_secs }) = &cli.command {
+        let options = my_fintech_app::smoketest::SmokeTestOptions {
+            url: url.clone(),
+            email_domain: email_domain.clone(),
+            admin_token: admin_token.clone(),
+            timeout: std::time::Duration::from_secs(*timeout_secs),
+        };
+        if let Err(e) = my_fintech_app::smoketest::run(&options).await {
+            eprintln!("❌ Smoke test failed at {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(Command::Loadgen { url, users, duration_secs, mix, email_domain, timeout_secs, seed }) =
+        &cli.command
+    {
+        let options = my_fintech_app::loadgen::LoadOptions {
+            url: url.clone(),
+            users: *users,
+            duration: std::time::Duration::from_secs(*duration_secs),
+            mix: mix.clone(),
+            email_domain: email_domain.clone(),
+            timeout: std::time::Duration::from_secs(*timeout_secs),
+            seed: *seed,
+        };
+        match my_fintech_app::loadgen::run(&options).await {
+            Ok(report) => report.print(),
+            Err(e) => {
+                eprintln!("❌ Couldn't set up the load users: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    // Load configuration (before logging, which depends on APP_ENV)
+    let config = match cli.load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing_subscriber::fmt().with_target(false).compact().init();
+            tracing::error!("❌ {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if cli.print_config {
+        println!("{:#?}", config);
+        return Ok(());
+    }
 
-    tracing::info!("🚀 Starting Fintech Application...");
+    // Initialize logging: --log-level, then RUST_LOG, then the profile's LOG_LEVEL
+    // (the filter can be changed later through the admin API)
+    let log_filter = cli
+        .log_level
+        .clone()
+        .or_else(|| std::env::var("RUST_LOG").ok())
+        .unwrap_or_else(|| config.log_level.clone());
+    let log_level = my_fintech_app::logging::init(&log_filter);
 
-    // Load configuration
-    let config = config::Config::from_env()?;
-    tracing::info!("✅ Configuration loaded");
+    tracing::info!("🚀 Starting Fintech Application ({})...", config.app_env);
+    tracing::info!("✅ Configuration loaded: {}", config);
+
+    // Start error reporting (no-op without SENTRY_DSN)
+    let sentry_guard = my_fintech_app::telemetry::init(&config);
+    if sentry_guard.is_some() {
+        tracing::info!("✅ Sentry error reporting enabled ({})", config.sentry_environment);
+    }
 
     // Connect to database
-    let pool = config::create_db_pool(&config.database_url).await?;
+    let pool = config::create_db_pool(&config).await?;
     tracing::info!("✅ Database connected");
 
-    // Initialize Email Service
-    let email_service = my_fintech_app::services::email_service::EmailService::new(
-        config.smtp_host.clone(),
-        config.smtp_port,
-        config.smtp_user.clone(),
-        config.smtp_password.clone(),
-        config.smtp_from.clone(),
+    // Operational subcommands run and exit; no subcommand means `serve`
+    let result = match &cli.command {
+        None | Some(Command::Serve) | Some(Command::Smoketest { .. })
+        | Some(Command::Loadgen { .. }) => Ok(()),
+        Some(Command::Migrate { action }) => {
+            cli::migrate(&pool, action.unwrap_or(MigrateAction::Run)).await
+        }
+        Some(Command::Seed) => cli::seed(&pool, &config).await,
+        Some(Command::CreateAdmin { email, password }) => {
+            cli::create_admin(&pool, &config, email, password.as_deref()).await
+        }
+    };
+    if let Err(e) = result {
+        tracing::error!("❌ {}", e);
+        std::process::exit(1);
+    }
+    if !matches!(cli.command, None | Some(Command::Serve)) {
+        return Ok(());
+    }
+
+    // Bring the schema up to date (migrations are idempotent, so this is
+    // also safe on databases that were set up by hand)
+    if cli.no_migrate {
+        tracing::info!("⏭️  Skipping migrations (--no-migrate)");
+    } else {
+        cli::MIGRATOR.run(&pool).await?;
+        tracing::info!("✅ Migrations applied");
+    }
+
+    // Ops alerts to Slack/Discord (SLACK_ALERT_*, DISCORD_ALERT_*)
+    let ops_alerts = my_fintech_app::services::ops_alerts::OpsAlerts::from_config(&config);
+
+    // Background tasks run until SIGINT/SIGTERM (see shutdown.rs)
+    let shutdown = Shutdown::new();
+
+    // Background housekeeping (partitions, orphan wallets, archive, referrals,
+    // balance check)
+    my_fintech_app::services::maintenance_service::spawn(
+        &shutdown,
+        pool.clone(),
+        config.transaction_archive_after_years,
+        my_fintech_app::services::referral_service::ReferralTerms::from_config(&config),
+        ops_alerts,
     );
 
-    // Initialize Notification Service
-    let notification_service = my_fintech_app::services::notification_service::NotificationService::new();
+    // Demo data (APP_SEED=true, never in production)
+    if config.seed_demo_data {
+        let summary = my_fintech_app::seeder::seed(&pool, &config).await?;
+        tracing::info!(
+            "🌱 Seeded {} demo users ({} transactions), {} already existed",
+            summary.created.len(),
+            summary.transactions,
+            summary.skipped.len()
+        );
+    }
 
-    // Create app state
-    let state = AppState {
-        pool,
-        jwt_secret: std::env::var("JWT_SECRET").expect("JWT_SECRET must be set"),
-        rate_limiter: std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new())),
-        email_service,
-        notification_service,
-    };
+    // Shared state: email (EMAIL_PROVIDER), bank linking (BANK_PROVIDER),
+    // Apple Pay / Google Pay top-ups (CARD_PROCESSOR, APPLE_PAY_*), crypto
+    // (CRYPTO_PROVIDER), login locations (GEOIP_DATABASE), export storage
+    // (STORAGE_BACKEND), exchange rates (RATES_PROVIDER), rate limiting
+    let state = AppState::builder(pool.clone(), config.clone(), log_level).build()?;
+
+    // Pending bank deposits are settled in the background
+    if let Some(provider) = &state.bank_provider {
+        my_fintech_app::services::bank_service::spawn_settlement(
+            &shutdown,
+            pool.clone(),
+            provider.clone(),
+        );
+    }
+
+    // Emails that failed to send are retried in the background
+    state.email_service.spawn_retries(&shutdown);
+
+    // Exchange rates are refreshed in the background
+    state.exchange_rates.spawn_refresh(&shutdown);
 
-    // Create web routes with state
-    let web_routes = Router::new()
-        .route("/", get(handlers::web::login_page))
-        .route("/login", get(handlers::web::login_page))
-        .route("/login", post(handlers::web::login_submit))
-        .route("/register", get(handlers::web::register_page))
-        .route("/register", post(handlers::web::register_submit))
-        .route("/dashboard", get(handlers::web::dashboard_page))
-        .route("/dashboard/transactions", get(handlers::web::transactions_page))
-        .route("/dashboard/deposit", get(handlers::web::deposit_page))
-        .route("/dashboard/deposit", post(handlers::web::deposit_submit))
-        .route("/dashboard/withdraw", get(handlers::web::withdraw_page))
-        .route("/dashboard/withdraw", post(handlers::web::withdraw_submit))
-        .route("/dashboard/transfer", get(handlers::web::transfer_page))
-        .route("/dashboard/transfer", post(handlers::web::transfer_submit))
-        .route("/logout", post(handlers::web::logout))
-        .with_state(state.clone());
-
-    // Build our application with routes
-    let app = Router::new()
-        .nest("/api", auth_routes(state.clone()))
-        .merge(web_routes)
-        .layer(axum::middleware::from_fn_with_state(
-            state.clone(),
-            my_fintech_app::middleware::rate_limit::rate_limit_middleware,
-        ))
-        .nest_service("/assets", ServeDir::new("assets"))
-        .layer(TraceLayer::new_for_http());
+    // Transfers nobody confirmed in time are refunded in the background
+    my_fintech_app::services::wallet_service::spawn_expiry(
+        &shutdown,
+        state.pool.clone(),
+        state.clock.clone(),
+    );
+
+    // Scheduled transfers run in the background (left waiting while
+    // transfers are paused)
+    my_fintech_app::services::scheduled_transfer_service::spawn(
+        &shutdown,
+        state.pool.clone(),
+        my_fintech_app::services::wallet_service::ConcurrencyPolicy::from_config(&config),
+        state.email_service.clone(),
+        state.notification_service.clone(),
+        state.exchange_rates.clone(),
+        my_fintech_app::services::wallet_service::SpendingLimits::from_config(&config),
+        state.features,
+        state.clock.clone(),
+    );
+
+    // Every route: API, Open Banking, web UI, health and static assets
+    let notifier = state.notification_service.clone();
+    let app = app(state);
 
     // Start the server
     let addr = config.server_address();
     tracing::info!("🌐 Server listening on http://{}", addr);
     tracing::info!("📝 Available endpoints:");
+    tracing::info!("     GET  http://{}/health", addr);
     tracing::info!("   API:");
     tracing::info!("     POST http://{}/api/register", addr);
     tracing::info!("     POST http://{}/api/login", addr);
     tracing::info!("     GET  http://{}/api/me (protected)", addr);
     tracing::info!("     GET  http://{}/api/wallet (protected)", addr);
+    tracing::info!("   Open Banking:");
+    tracing::info!("     POST http://{}/open-banking/token", addr);
     tracing::info!("   Web:");
     tracing::info!("     GET  http://{}/ (login page)", addr);
     tracing::info!("     GET  http://{}/dashboard (dashboard)", addr);
 
+    // On SIGINT/SIGTERM: stop accepting connections, let the requests in
+    // flight finish, close the WebSockets and stop the background tasks
+    let stopping = shutdown.clone();
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     axum::serve(
         listener,
         app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
     )
+    .with_graceful_shutdown(async move {
+        shutdown::signal().await;
+        tracing::info!("🛑 Shutting down, finishing requests in flight...");
+        stopping.trigger();
+        notifier.disconnect_all().await;
+    })
     .await?;
 
+    if shutdown.wait(SHUTDOWN_GRACE).await {
+        tracing::info!("✅ Background tasks stopped");
+    } else {
+        tracing::warn!("⚠️  Background tasks still running after {:?}, leaving them", SHUTDOWN_GRACE);
+    }
+    pool.close().await;
+    tracing::info!("👋 Bye");
+
     Ok(())
 }
@@ -0,0 +1,56 @@
+use axum::{
+    extract::{OriginalUri, Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use crate::middleware::auth::user_from_headers;
+use crate::repository::audit_repo;
+use crate::routes::auth_routes::AppState;
+use crate::services::admin_service;
+
+// ============================================================================
+// ADMIN AUDIT MIDDLEWARE
+// ============================================================================
+// Writes every change an admin makes through /api/admin to the audit log:
+// who, the method and full path, and the status it was answered with
+// (refused and failed attempts included). Reads (GET, HEAD) aren't logged.
+//
+// Callers who aren't admins are passed through unlogged; the handler turns
+// them away. A failure to write the entry is logged as an error but doesn't
+// change the response - the action has happened by then.
+
+pub async fn admin_audit_middleware(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if matches!(*req.method(), Method::GET | Method::HEAD) {
+        return next.run(req).await;
+    }
+
+    let Ok(admin_id) = user_from_headers(req.headers(), &state).await else {
+        return next.run(req).await;
+    };
+    if !admin_service::is_admin(&state.pool, admin_id).await.unwrap_or(false) {
+        return next.run(req).await;
+    }
+
+    // Nested routers see their path without the /api/admin prefix
+    let path = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|original| original.0.path().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let method = req.method().to_string();
+
+    let response = next.run(req).await;
+
+    let status = response.status().as_u16();
+    let at = state.clock.now();
+    if let Err(e) = audit_repo::record(&state.pool, admin_id, &method, &path, status, at).await {
+        tracing::error!("❌ Could not audit {} {} by admin {}: {:?}", method, path, admin_id, e);
+    }
+
+    response
+}
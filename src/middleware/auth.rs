@@ -1,11 +1,16 @@
 use axum::{
     async_trait,
-    extract::{FromRequestParts, State},
-    http::request::Parts,
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderMap, Method},
 };
+use crate::config::WebAuthMode;
+use crate::domain::models::AccountStatus;
+use crate::repository::user_repo;
+use crate::services::{admin_service, session_service, token_service};
 use crate::error::AppError;
 use crate::routes::auth_routes::AppState;
-use crate::utils::jwt::validate_token;
+use crate::utils::jwt::{validate_token, Claims, SCOPE_WALLET_WRITE};
+use std::marker::PhantomData;
 use uuid::Uuid;
 
 // ============================================================================
@@ -26,72 +31,231 @@ impl FromRequestParts<AppState> for AuthUser {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
-        // 1. Try to get token from Authorization header
-        let token = if let Some(auth_header) = parts.headers.get("Authorization") {
-            let auth_str = auth_header.to_str().map_err(|_| AppError::InvalidToken)?;
-            if auth_str.starts_with("Bearer ") {
-                Some(auth_str[7..].to_string())
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        user_from_headers(&parts.headers, state).await.map(AuthUser)
+    }
+}
 
-        // 2. If no header, try to parse from Cookie header
-        let token = if let Some(t) = token {
-            t
-        } else {
-            // Parse Cookie header manually
-            if let Some(cookie_header) = parts.headers.get("Cookie") {
-                let cookie_str = cookie_header.to_str().map_err(|_| AppError::InvalidToken)?;
-                
-                // Parse cookies (format: "name1=value1; name2=value2")
-                let auth_token = cookie_str
-                    .split(';')
-                    .map(|s| s.trim())
-                    .find_map(|cookie| {
-                        let mut parts = cookie.split('=');
-                        let name = parts.next()?;
-                        let value = parts.next()?;
-                        if name == "auth_token" {
-                            Some(value.to_string())
-                        } else {
-                            None
-                        }
-                    });
-                
-                auth_token.ok_or(AppError::InvalidToken)?
-            } else {
-                return Err(AppError::InvalidToken);
-            }
+/// Extractor for authenticated admins
+///
+/// Same as `AuthUser`, but also requires the ADMIN role (403 otherwise).
+/// Anything but a GET also needs the `wallet:write` scope, so an admin's
+/// read-only token can look but not freeze or reverse.
+pub struct AdminUser(pub Uuid);
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let (user_id, claims) = authenticate(&parts.headers, state).await?;
+
+        if !admin_service::is_admin(&state.pool, user_id).await? {
+            return Err(AppError::Unauthorized);
+        }
+
+        let writes = !matches!(parts.method, Method::GET | Method::HEAD);
+        if writes && claims.is_some_and(|claims| !claims.has_scope(SCOPE_WALLET_WRITE)) {
+            return Err(AppError::InsufficientScope(SCOPE_WALLET_WRITE.to_string()));
+        }
+
+        Ok(AdminUser(user_id))
+    }
+}
+
+/// A scope a `RequireScope` extractor asks for (see utils::jwt)
+pub trait Scope: Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+/// The "wallet:write" scope: moving money or changing anything else
+pub struct WalletWrite;
+
+impl Scope for WalletWrite {
+    const NAME: &'static str = SCOPE_WALLET_WRITE;
+}
+
+/// Extractor for authenticated users whose token was granted scope `S`
+///
+/// Same as `AuthUser`, but a token without the scope is turned away with
+/// `InsufficientScope` (403):
+///
+/// ```ignore
+/// pub async fn deposit(RequireScope(user_id, _): RequireScope<WalletWrite>, ...)
+/// ```
+///
+/// Web sessions (session mode) were signed in to directly, so they have
+/// every scope.
+pub struct RequireScope<S: Scope>(pub Uuid, pub PhantomData<S>);
+
+#[async_trait]
+impl<S: Scope> FromRequestParts<AppState> for RequireScope<S> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let (user_id, claims) = authenticate(&parts.headers, state).await?;
+
+        if claims.is_some_and(|claims| !claims.has_scope(S::NAME)) {
+            return Err(AppError::InsufficientScope(S::NAME.to_string()));
+        }
+
+        Ok(RequireScope(user_id, PhantomData))
+    }
+}
+
+/// Extractor for the JWT a request was made with
+///
+/// The `Bearer` token, or else the `auth_token` cookie; validated and not
+/// revoked. For endpoints that act on the token itself, like logout.
+pub struct AuthToken(pub Claims);
+
+#[async_trait]
+impl FromRequestParts<AppState> for AuthToken {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let token = match bearer_token(&parts.headers)? {
+            Some(token) => token,
+            None => cookie_value(&parts.headers, "auth_token").ok_or(AppError::InvalidToken)?,
         };
 
-        // 3. Validate the token
-        let claims = validate_token(&token, &state.jwt_secret)?;
+        verify_jwt(&token, state).await.map(AuthToken)
+    }
+}
+
+/// Authenticate a request from its headers
+///
+/// A `Bearer` token in the Authorization header wins; without one we
+/// fall back to the login cookie.
+pub async fn user_from_headers(headers: &HeaderMap, state: &AppState) -> Result<Uuid, AppError> {
+    authenticate(headers, state).await.map(|(user_id, _)| user_id)
+}
 
-        // 4. Get user ID from claims
-        let user_id = claims.user_id()?;
+/// Like `user_from_headers`, also handing back the JWT's claims (None
+/// for a web session)
+async fn authenticate(
+    headers: &HeaderMap,
+    state: &AppState,
+) -> Result<(Uuid, Option<Claims>), AppError> {
+    // 1. Try to get token from Authorization header
+    // 2. If no header, authenticate from cookies (session or JWT)
+    let Some(token) = bearer_token(headers)? else {
+        return authenticate_cookies(headers, state).await;
+    };
 
-        Ok(AuthUser(user_id))
+    // 3. Validate the token (and check it wasn't revoked)
+    let claims = verify_jwt(&token, state).await?;
+
+    // 4. Get user ID from claims, unless their account was closed
+    let user_id = ensure_not_closed(state, claims.user_id()?).await?;
+    Ok((user_id, Some(claims)))
+}
+
+/// The user named by the request's JWT (`Bearer`, else the `auth_token`
+/// cookie), if it's validly signed and unexpired
+///
+/// Doesn't touch the database, so a logged-out token still names its user;
+/// only for cheap decisions like which rate limit applies, never to let a
+/// request in.
+pub fn token_subject(headers: &HeaderMap, state: &AppState) -> Option<Uuid> {
+    let token = match bearer_token(headers).ok()? {
+        Some(token) => token,
+        None => cookie_value(headers, "auth_token")?,
+    };
+    let claims = validate_token(&token, &state.jwt_keys, state.clock.as_ref()).ok()?;
+    claims.user_id().ok()
+}
+
+/// Turn away closed accounts (`AccountFrozen`, 423)
+///
+/// Frozen accounts get through: they may still look, and `wallet_service`
+/// stops them moving money.
+async fn ensure_not_closed(state: &AppState, user_id: Uuid) -> Result<Uuid, AppError> {
+    match user_repo::get_account_status(&state.pool, user_id).await {
+        Ok(AccountStatus::Closed) => Err(AppError::AccountFrozen),
+        // (deleted accounts are turned away where they're looked up)
+        Ok(_) | Err(AppError::NotFound(_)) => Ok(user_id),
+        Err(e) => Err(e),
+    }
+}
+
+/// The token from a `Bearer` Authorization header, if there is one
+fn bearer_token(headers: &HeaderMap) -> Result<Option<String>, AppError> {
+    let Some(auth_header) = headers.get("Authorization") else {
+        return Ok(None);
+    };
+    let auth_str = auth_header.to_str().map_err(|_| AppError::InvalidToken)?;
+    Ok(auth_str.strip_prefix("Bearer ").map(|t| t.to_string()))
+}
+
+/// Validate a JWT and make sure it wasn't logged out
+async fn verify_jwt(token: &str, state: &AppState) -> Result<Claims, AppError> {
+    let claims = validate_token(token, &state.jwt_keys, state.clock.as_ref())?;
+    token_service::ensure_not_revoked(&state.pool, &claims).await?;
+    Ok(claims)
+}
+
+/// Authenticate a browser request from its cookies
+///
+/// In session mode the `session_id` cookie is looked up in the database;
+/// otherwise the `auth_token` cookie is validated as a JWT.
+pub async fn user_from_cookies(headers: &HeaderMap, state: &AppState) -> Result<Uuid, AppError> {
+    authenticate_cookies(headers, state).await.map(|(user_id, _)| user_id)
+}
+
+/// Like `user_from_cookies`, also handing back the JWT's claims (None in
+/// session mode)
+async fn authenticate_cookies(
+    headers: &HeaderMap,
+    state: &AppState,
+) -> Result<(Uuid, Option<Claims>), AppError> {
+    if state.web_auth_mode == WebAuthMode::Session {
+        let session_id = cookie_value(headers, session_service::SESSION_COOKIE)
+            .ok_or(AppError::InvalidToken)?;
+        let session = session_service::find(&state.pool, &session_id)
+            .await?
+            .ok_or(AppError::InvalidToken)?;
+        return Ok((ensure_not_closed(state, session.user_id).await?, None));
     }
+
+    let token = cookie_value(headers, "auth_token").ok_or(AppError::InvalidToken)?;
+    let claims = verify_jwt(&token, state).await?;
+    let user_id = ensure_not_closed(state, claims.user_id()?).await?;
+    Ok((user_id, Some(claims)))
+}
+
+/// Read a single cookie from the Cookie header
+///
+/// (format: "name1=value1; name2=value2")
+pub fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    let cookie_str = headers.get("Cookie")?.to_str().ok()?;
+
+    cookie_str
+        .split(';')
+        .map(|s| s.trim())
+        .find_map(|cookie| {
+            let (key, value) = cookie.split_once('=')?;
+            if key == name {
+                Some(value.to_string())
+            } else {
+                None
+            }
+        })
 }
 
 // ============================================================================
 // HELPER FUNCTION FOR WEBSOCKET AUTH
 // ============================================================================
 
-/// Extract user ID from the auth_token cookie
-pub fn get_user_from_cookie(
-    cookies: &axum_extra::extract::CookieJar,
-    jwt_secret: &str,
-) -> Result<Uuid, AppError> {
-    let token = cookies
-        .get("auth_token")
-        .ok_or(AppError::InvalidToken)?
-        .value()
-        .to_string();
-
-    let claims = validate_token(&token, jwt_secret)?;
-    claims.user_id()
+/// Extract user ID from the login cookie (JWT or session, depending on mode)
+pub async fn get_user_from_cookie(headers: &HeaderMap, state: &AppState) -> Result<Uuid, AppError> {
+    user_from_cookies(headers, state).await
 }
@@ -0,0 +1,46 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts},
+};
+use std::convert::Infallible;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+// ============================================================================
+// CLIENT INFO EXTRACTOR
+// ============================================================================
+
+/// Longest user agent we keep (matches `login_events.user_agent`)
+const MAX_USER_AGENT_LEN: usize = 512;
+
+/// Where a request came from: the peer's address and its User-Agent
+///
+/// The address is the TCP peer, like the rate limiter uses; behind a proxy
+/// that is the proxy.
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub ip: IpAddr,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|agent| !agent.is_empty())
+            .map(|agent| agent.chars().take(MAX_USER_AGENT_LEN).collect());
+
+        Ok(ClientInfo { ip, user_agent })
+    }
+}
@@ -0,0 +1,49 @@
+use axum::{
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use crate::config::WebAuthMode;
+use crate::middleware::auth::cookie_value;
+use crate::routes::auth_routes::AppState;
+use crate::services::session_service;
+
+// ============================================================================
+// CSRF PROTECTION (session mode only)
+// ============================================================================
+// Every state-changing request made with a session cookie must carry the
+// session's CSRF token in the X-CSRF-Token header. base.html copies the
+// token from the csrf_token cookie into every htmx request.
+//
+// Requests without a session cookie (login, register, API calls with a
+// Bearer token) are passed through; they can't ride on a victim's session.
+
+pub async fn csrf_middleware(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, String)> {
+    let safe_method = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+
+    if state.web_auth_mode == WebAuthMode::Session && !safe_method {
+        if let Some(session_id) = cookie_value(req.headers(), session_service::SESSION_COOKIE) {
+            let session = session_service::find(&state.pool, &session_id)
+                .await
+                .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Session lookup failed".to_string()))?;
+
+            if let Some(session) = session {
+                let sent = req
+                    .headers()
+                    .get(session_service::CSRF_HEADER)
+                    .and_then(|v| v.to_str().ok());
+
+                if sent != Some(session.csrf_token.as_str()) {
+                    return Err((StatusCode::FORBIDDEN, "Invalid CSRF token".to_string()));
+                }
+            }
+        }
+    }
+
+    Ok(next.run(req).await)
+}
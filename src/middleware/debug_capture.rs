@@ -0,0 +1,120 @@
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::{OriginalUri, Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::time::Instant;
+use crate::error::AppError;
+use crate::middleware::auth::user_from_headers;
+use crate::routes::auth_routes::AppState;
+use crate::services::debug_capture::{
+    describe_binary, is_text, sanitize_body, sanitize_header, sanitize_request_body, sanitize_uri,
+    CapturedExchange, CapturedHeader,
+};
+
+// ============================================================================
+// DEBUG CAPTURE MIDDLEWARE
+// ============================================================================
+// Records requests matching an admin's capture target, with the response
+// they got (see services/debug_capture.rs). With no targets running it
+// does nothing but check that.
+//
+// A captured request's body is read into memory so it can be both kept and
+// passed on; so is a text response's. Downloads, streamed responses and
+// WebSocket upgrades are passed through untouched.
+
+/// Bodies bigger than this aren't read in for a capture
+const MAX_BUFFERED_BYTES: usize = 2 * 1024 * 1024;
+
+pub async fn debug_capture_middleware(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let capture = state.debug_capture.clone();
+    if !capture.is_active() || req.headers().contains_key(header::UPGRADE) {
+        return next.run(req).await;
+    }
+
+    // Nested routers see their path without the prefix; targets name the
+    // full path
+    let uri = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|original| original.0.clone())
+        .unwrap_or_else(|| req.uri().clone());
+
+    // Only authenticate the caller if a target is for a user
+    let user_id = if capture.targets_users() {
+        user_from_headers(req.headers(), &state).await.ok()
+    } else {
+        None
+    };
+    let Some(target_id) = capture.matching(uri.path(), user_id) else {
+        return next.run(req).await;
+    };
+
+    let started = Instant::now();
+    let captured_at = state.clock.now();
+    let method = req.method().to_string();
+    let request_headers = sanitize_headers(req.headers());
+    let request_type = content_type(req.headers());
+
+    let (parts, body) = req.into_parts();
+    let bytes = match to_bytes(body, MAX_BUFFERED_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return AppError::validation("Request body too large").into_response(),
+    };
+    let request_body = sanitize_request_body(request_type.as_deref(), &bytes);
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+
+    let status = response.status().as_u16();
+    let response_headers = sanitize_headers(response.headers());
+    let response_type = content_type(response.headers()).unwrap_or_default();
+    // Only bodies already complete in memory (JSON, HTML pages) are read;
+    // streams (SSE, CSV exports) would have to be waited out
+    let size = response.body().size_hint().exact();
+    let (response, response_body) = match size {
+        Some(size) if is_text(&response_type) && size as usize <= MAX_BUFFERED_BYTES => {
+            let (parts, body) = response.into_parts();
+            let bytes = to_bytes(body, MAX_BUFFERED_BYTES).await.unwrap_or_default();
+            let kept = sanitize_body(Some(&response_type), &bytes);
+            (Response::from_parts(parts, Body::from(bytes)), kept)
+        }
+        Some(size) => (response, Some(describe_binary(&response_type, size as usize))),
+        None => (response, Some(format!("<streamed {}>", response_type))),
+    };
+
+    capture.record(CapturedExchange {
+        id: 0,
+        target_id,
+        captured_at,
+        duration_ms: started.elapsed().as_millis() as u64,
+        user_id,
+        method,
+        uri: sanitize_uri(uri.path(), uri.query()),
+        request_headers,
+        request_body,
+        status,
+        response_headers,
+        response_body,
+    });
+
+    response
+}
+
+fn sanitize_headers(headers: &HeaderMap) -> Vec<CapturedHeader> {
+    headers
+        .iter()
+        .map(|(name, value)| sanitize_header(name.as_str(), value.as_bytes()))
+        .collect()
+}
+
+fn content_type(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
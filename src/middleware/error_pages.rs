@@ -0,0 +1,107 @@
+use axum::{
+    body::to_bytes,
+    extract::{Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+};
+use crate::error::{ErrorCode, ErrorDetails};
+use crate::i18n::{self, Locale};
+use crate::routes::auth_routes::AppState;
+use crate::handlers::web::error_page;
+
+// ============================================================================
+// HTML ERROR PAGES (web routes only)
+// ============================================================================
+// Handlers return AppError, which renders as JSON - right for /api, wrong
+// for a browser. This middleware sits on the web routes and rewrites
+// error responses:
+// - htmx requests get the plain message, swapped into the form's error box
+// - full page loads get the error.html page (401 redirects to /login)
+// - 5xx only shows internal details outside production; in production
+//   users just get the request ID to quote to support
+
+/// Largest plain-text error body we'll read back to reuse as the message
+const MAX_ERROR_BODY: usize = 16 * 1024;
+
+pub async fn html_error_middleware(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let request_id = req
+        .headers()
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    let is_htmx = req.headers().contains_key("HX-Request");
+    let is_get = req.method() == Method::GET;
+
+    let response = next.run(req).await;
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return response;
+    }
+
+    // Not logged in (or session revoked): send them to the login page
+    if status == StatusCode::UNAUTHORIZED {
+        if is_htmx {
+            return (StatusCode::UNAUTHORIZED, [("HX-Redirect", "/login")]).into_response();
+        }
+        if is_get {
+            return Redirect::to("/login").into_response();
+        }
+    }
+
+    // Set by the locale middleware on error responses
+    let locale = response.extensions().get::<Locale>().copied().unwrap_or_default();
+
+    // Only real failures are hidden; a 503 for a switched-off feature is
+    // meant for the user
+    let is_internal = status.is_server_error()
+        && response
+            .extensions()
+            .get::<ErrorDetails>()
+            .is_none_or(|details| details.code != ErrorCode::FeatureDisabled);
+
+    let message = if is_internal && state.config.is_production() {
+        i18n::error_message(ErrorCode::InternalError, locale).to_string()
+    } else if is_internal {
+        // Development/staging: show what actually went wrong
+        let generic = i18n::error_message(ErrorCode::InternalError, locale).to_string();
+        error_message(response).await.unwrap_or(generic)
+    } else {
+        error_message(response).await.unwrap_or_else(|| {
+            status.canonical_reason().unwrap_or("Request failed").to_string()
+        })
+    };
+
+    if is_htmx {
+        return (status, message).into_response();
+    }
+
+    error_page(status, &message, request_id)
+}
+
+/// Pull the user-facing message out of an error response
+///
+/// AppError responses carry it as an extension; other rejections
+/// (rate limiter, CSRF, form parsing) send it as a plain-text body.
+async fn error_message(response: Response) -> Option<String> {
+    if let Some(details) = response.extensions().get::<ErrorDetails>() {
+        return Some(details.message.clone());
+    }
+
+    let is_text = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/plain"));
+    if !is_text {
+        return None;
+    }
+
+    let bytes = to_bytes(response.into_body(), MAX_ERROR_BODY).await.ok()?;
+    let text = String::from_utf8(bytes.to_vec()).ok()?;
+    (!text.trim().is_empty()).then_some(text)
+}
@@ -0,0 +1,83 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use sentry::{Hub, Level, SentryFutureExt};
+use std::sync::Arc;
+use crate::error::{ErrorCode, ErrorDetails};
+use crate::middleware::auth::user_from_headers;
+use crate::routes::auth_routes::AppState;
+use crate::telemetry;
+
+// ============================================================================
+// ERROR REPORTING MIDDLEWARE
+// ============================================================================
+// Gives every request its own Sentry scope tagged with the route, method
+// and request id, then reports 5xx AppErrors (internal and database
+// errors) with that context plus the user id.
+//
+// The scope stays bound while the handler runs, so a panic inside the
+// handler is reported with the same route and request id.
+//
+// This sits right around the handlers (inside the locale and HTML error
+// page middleware) so it sees the original, untranslated message.
+
+pub async fn error_reporting_middleware(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if !telemetry::is_enabled() {
+        return next.run(req).await;
+    }
+
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let method = req.method().to_string();
+    let request_id = req
+        .headers()
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    let headers = req.headers().clone();
+
+    let hub = Arc::new(Hub::new_from_top(Hub::current()));
+    hub.configure_scope(|scope| {
+        scope.set_transaction(Some(&format!("{} {}", method, route)));
+        scope.set_tag("route", &route);
+        scope.set_tag("method", &method);
+        if let Some(request_id) = &request_id {
+            scope.set_tag("request_id", request_id);
+        }
+    });
+
+    let response = next.run(req).bind_hub(hub.clone()).await;
+
+    let Some(details) = response.extensions().get::<ErrorDetails>() else {
+        return response;
+    };
+    if !matches!(details.code, ErrorCode::InternalError | ErrorCode::DatabaseError) {
+        return response;
+    }
+
+    // Only look the user up once we know there's something to report
+    let user_id = user_from_headers(&headers, &state).await.ok();
+    hub.with_scope(
+        |scope| {
+            scope.set_tag("error_code", details.code);
+            if let Some(user_id) = user_id {
+                scope.set_user(Some(sentry::User {
+                    id: Some(user_id.to_string()),
+                    ..Default::default()
+                }));
+            }
+        },
+        || hub.capture_message(&details.message, Level::Error),
+    );
+
+    response
+}
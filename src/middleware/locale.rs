@@ -0,0 +1,101 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use crate::error::ErrorDetails;
+use crate::i18n::{self, Locale};
+use crate::middleware::auth::user_from_headers;
+use crate::repository::user_repo;
+use crate::routes::auth_routes::AppState;
+
+// ============================================================================
+// LOCALE MIDDLEWARE
+// ============================================================================
+// Translates AppError responses into the user's language.
+//
+// Only error responses are touched, and the locale is only worked out
+// once we know the response is an error - so successful requests never
+// pay for the extra user lookup.
+//
+// The chosen locale is also attached to the response as an extension, so
+// the HTML error page middleware (which runs after this one) can use it.
+
+/// Largest JSON error body we'll rewrite
+const MAX_ERROR_BODY: usize = 64 * 1024;
+
+pub async fn locale_middleware(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let headers = req.headers().clone();
+    let response = next.run(req).await;
+
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return response;
+    }
+
+    let locale = resolve_locale(&headers, &state).await;
+    let mut response = localize(response, locale).await;
+    response.extensions_mut().insert(locale);
+    response
+        .headers_mut()
+        .insert(header::CONTENT_LANGUAGE, HeaderValue::from_static(locale.as_str()));
+    response
+}
+
+/// Saved user preference, then Accept-Language, then English
+pub async fn resolve_locale(headers: &HeaderMap, state: &AppState) -> Locale {
+    if let Ok(user_id) = user_from_headers(headers, state).await {
+        if let Ok(Some(tag)) = user_repo::get_user_locale(&state.pool, user_id).await {
+            if let Some(locale) = Locale::parse(&tag) {
+                return locale;
+            }
+        }
+    }
+
+    headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(Locale::from_accept_language)
+        .unwrap_or_default()
+}
+
+/// Swap the message of an AppError response for the translated one
+///
+/// English keeps the detailed message. The `code` and any per-field
+/// `errors` are left as they are.
+async fn localize(response: Response, locale: Locale) -> Response {
+    if locale == Locale::En {
+        return response;
+    }
+    let Some(details) = response.extensions().get::<ErrorDetails>().cloned() else {
+        return response;
+    };
+    let message = i18n::error_message(details.code, locale).to_string();
+
+    let (mut parts, body) = response.into_parts();
+    parts.extensions.insert(ErrorDetails {
+        code: details.code,
+        message: message.clone(),
+    });
+
+    let Ok(bytes) = to_bytes(body, MAX_ERROR_BODY).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+    let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(mut json) => {
+            json["error"] = serde_json::Value::String(message);
+            let bytes = serde_json::to_vec(&json).unwrap_or_else(|_| bytes.to_vec());
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Body::from(bytes)
+        }
+        Err(_) => Body::from(bytes),
+    };
+
+    Response::from_parts(parts, body)
+}
@@ -1,2 +1,11 @@
 pub mod auth;
+pub mod client;
+pub mod open_banking;
 pub mod rate_limit;
+pub mod csrf;
+pub mod error_pages;
+pub mod validation;
+pub mod locale;
+pub mod error_reporting;
+pub mod debug_capture;
+pub mod audit;
@@ -0,0 +1,39 @@
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use crate::error::AppError;
+use crate::routes::auth_routes::AppState;
+use crate::services::open_banking_service::{self, OpenBankingAccess};
+
+// ============================================================================
+// OPEN BANKING TOKEN EXTRACTOR
+// ============================================================================
+// Third parties authenticate with their own access tokens (see
+// open_banking_service), never with a user's JWT or session, so this is a
+// separate extractor from `AuthUser`: a first-party token here, or an Open
+// Banking token on /api, is simply rejected.
+
+/// Extractor for Open Banking access tokens
+///
+/// Reads the `Bearer` token from the Authorization header and checks that
+/// it, its client and its consent are all still valid (401 otherwise).
+/// Scope checks are left to the handler, via `OpenBankingAccess::require`.
+pub struct OpenBankingClient(pub OpenBankingAccess);
+
+#[async_trait]
+impl FromRequestParts<AppState> for OpenBankingClient {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get("Authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(AppError::InvalidToken)?;
+
+        let access = open_banking_service::authenticate(&state.pool, token).await?;
+        Ok(OpenBankingClient(access))
+    }
+}
@@ -1,54 +1,63 @@
 use axum::{
     extract::{ConnectInfo, State},
-    http::StatusCode,
+    http::{HeaderMap, HeaderValue},
     middleware::Next,
     response::{IntoResponse, Response},
 };
-use std::{net::SocketAddr, time::{Duration, Instant}};
+use std::net::SocketAddr;
+use crate::error::AppError;
+use crate::middleware::auth::token_subject;
 use crate::routes::auth_routes::AppState;
+use crate::services::rate_limiter::{RateLimitDecision, RateLimitTier};
 
-const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60); // 1 minute
-const MAX_REQUESTS: u32 = 20; // Max 20 requests per minute
+// ============================================================================
+// RATE LIMIT MIDDLEWARE
+// ============================================================================
+// Counts each request against its tier (see services::rate_limiter) and
+// tells the client where it stands:
+//
+//   X-RateLimit-Limit: 10        requests allowed in the window
+//   X-RateLimit-Remaining: 7     left after this one
+//   X-RateLimit-Reset: 42        seconds until the window ends
+//
+// Refused requests get `429 {"code": "RATE_LIMITED"}` with `Retry-After`
+// (seconds) as well.
 
 pub async fn rate_limit_middleware(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     req: axum::extract::Request,
     next: Next,
-) -> Result<Response, (StatusCode, String)> {
+) -> Response {
     let ip = addr.ip();
-    
-    // Check Rate Limit
-    let allowed = {
-        // LOCK THE MUTEX
-        // This block ensures only one thread can update the map at a time
-        let mut limiter = state.rate_limiter.lock().unwrap();
 
-        let (count, reset_time) = limiter.entry(ip).or_insert((0, Instant::now()));
+    // Money is counted per user, everything else per IP; limits depend on
+    // APP_ENV, counts are kept in RATE_LIMIT_STORE (see Config)
+    let user = token_subject(req.headers(), &state);
+    let tier = RateLimitTier::classify(req.method(), req.uri().path(), user.is_some());
+    let client = match (tier, user) {
+        (RateLimitTier::Money, Some(user_id)) => format!("user:{}", user_id),
+        _ => format!("ip:{}", ip),
+    };
+    let decision = state.rate_limiter.check(tier, &client).await;
 
-        if reset_time.elapsed() > RATE_LIMIT_WINDOW {
-            // Window expired, reset counter
-            *count = 1;
-            *reset_time = Instant::now();
-            true
-        } else {
-            // Window active, increment count
-            if *count < MAX_REQUESTS {
-                *count += 1;
-                true
-            } else {
-                // Limit exceeded!
-                false
-            }
+    let mut response = if decision.allowed {
+        next.run(req).await
+    } else {
+        // A RATE_LIMITED error like any other; it adds Retry-After itself
+        AppError::RateLimited {
+            what: "requests".to_string(),
+            retry_after_secs: decision.reset_after_secs.max(1) as i64,
         }
-    }; // Mutex is UNLOCKED here automatically when 'limiter' goes out of scope
+        .into_response()
+    };
+    set_headers(response.headers_mut(), &decision);
 
-    if allowed {
-        Ok(next.run(req).await)
-    } else {
-        Err((
-            StatusCode::TOO_MANY_REQUESTS,
-            format!("Rate limit exceeded! Ongoing abuse detected from {}", ip),
-        ))
-    }
+    response
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &RateLimitDecision) {
+    headers.insert("X-RateLimit-Limit", HeaderValue::from(decision.limit));
+    headers.insert("X-RateLimit-Remaining", HeaderValue::from(decision.remaining));
+    headers.insert("X-RateLimit-Reset", HeaderValue::from(decision.reset_after_secs));
 }
@@ -0,0 +1,222 @@
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{rejection::JsonRejection, FromRequest, FromRequestParts, Request},
+    http::{header, request::Parts},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use validator::Validate;
+use crate::error::{AppError, FieldError};
+
+// ============================================================================
+// BODY EXTRACTORS
+// ============================================================================
+// axum's own `Json<T>` / `Form<T>` / `Query<T>` reject bad input with a plain-text
+// message that doesn't match our `{ error, code, status }` format.
+// These wrappers turn every body problem into an AppError instead, and
+// point at the offending field when there is one:
+//
+//   {
+//     "error": "Validation error: amount: invalid type: integer `5`, expected a string",
+//     "code": "VALIDATION_ERROR",
+//     "status": 400,
+//     "errors": [{ "field": "amount", "code": "invalid", "message": "invalid type: ..." }]
+//   }
+//
+// Usage:
+//   async fn handler(AppJson(req): AppJson<SomeRequest>) { ... }
+
+/// JSON body, with rejections in our error format
+pub struct AppJson<T>(pub T);
+
+/// Form body, with rejections in our error format
+pub struct AppForm<T>(pub T);
+
+/// Query string, with rejections in our error format
+pub struct AppQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for AppJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(AppJson(value)),
+            Err(JsonRejection::JsonDataError(e)) => {
+                // axum deserializes through serde_path_to_error, so the
+                // failing field's path is in the error's source chain
+                let mut source = std::error::Error::source(&e);
+                while let Some(err) = source {
+                    if let Some(err) =
+                        err.downcast_ref::<serde_path_to_error::Error<serde_json::Error>>()
+                    {
+                        return Err(field_error(&err.path().to_string(), &err.inner().to_string()));
+                    }
+                    source = err.source();
+                }
+                Err(AppError::validation(&e.body_text()))
+            }
+            Err(JsonRejection::JsonSyntaxError(_)) => {
+                Err(AppError::validation("Request body is not valid JSON"))
+            }
+            Err(JsonRejection::MissingJsonContentType(_)) => Err(AppError::validation(
+                "Expected a JSON body (Content-Type: application/json)",
+            )),
+            Err(e) => Err(AppError::validation(&e.body_text())),
+        }
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for AppForm<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_form = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/x-www-form-urlencoded"));
+        if !is_form {
+            return Err(AppError::validation(
+                "Expected a form body (Content-Type: application/x-www-form-urlencoded)",
+            ));
+        }
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|e| AppError::validation(&e.body_text()))?;
+
+        // Same approach as axum's Form, but tracking the field path
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(&bytes));
+        serde_path_to_error::deserialize(deserializer)
+            .map(AppForm)
+            .map_err(|e| field_error(&e.path().to_string(), &e.inner().to_string()))
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for AppQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        serde_path_to_error::deserialize(deserializer)
+            .map(AppQuery)
+            .map_err(|e| field_error(&e.path().to_string(), &e.inner().to_string()))
+    }
+}
+
+/// Build a single-field error from a serde path and message
+///
+/// Missing fields are reported by serde at the parent path ("."), so the
+/// field name is pulled out of the message instead.
+fn field_error(path: &str, message: &str) -> AppError {
+    // serde_json appends the position, which means nothing to a form user
+    let message = message
+        .rsplit_once(" at line ")
+        .map_or(message, |(message, _)| message);
+
+    let (field, code) = match message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.strip_suffix('`'))
+    {
+        Some(missing) if path == "." => (missing.to_string(), "required"),
+        Some(missing) => (format!("{}.{}", path, missing), "required"),
+        None if path == "." => ("body".to_string(), "invalid"),
+        None => (path.to_string(), "invalid"),
+    };
+
+    let message = if code == "required" {
+        "is required".to_string()
+    } else {
+        message.to_string()
+    };
+
+    AppError::InvalidFields(vec![FieldError {
+        field,
+        code: code.to_string(),
+        message,
+    }])
+}
+
+// ============================================================================
+// VALIDATED EXTRACTORS
+// ============================================================================
+// Drop-in replacements for `Json<T>` / `Form<T>` / `Query<T>` that also run the DTO's
+// `validator::Validate` rules. A handler only runs once every field is
+// valid; otherwise the client gets a 400 listing each failing field.
+//
+// Usage:
+//   async fn deposit(ValidatedJson(req): ValidatedJson<DepositRequest>) { ... }
+
+/// JSON body that has passed validation
+pub struct ValidatedJson<T>(pub T);
+
+/// Form body that has passed validation
+pub struct ValidatedForm<T>(pub T);
+
+/// Query string that has passed validation
+pub struct ValidatedQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let AppJson(value) = AppJson::<T>::from_request(req, state).await?;
+        value.validate()?;
+        Ok(ValidatedJson(value))
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedForm<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let AppForm(value) = AppForm::<T>::from_request(req, state).await?;
+        value.validate()?;
+        Ok(ValidatedForm(value))
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let AppQuery(value) = AppQuery::<T>::from_request_parts(parts, state).await?;
+        value.validate()?;
+        Ok(ValidatedQuery(value))
+    }
+}
@@ -0,0 +1,127 @@
+use crate::domain::models::AccountCodeOverrides;
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// ACCOUNTING REPOSITORY
+// ============================================================================
+// The accounting export's inputs: each user's own account codes
+// (`accounting_account_codes`, migration 024) and the COMPLETED
+// transactions to turn into journal entries, archived ones included, each
+// with its `transaction_category` (migration 023).
+
+/// One transaction to export
+#[derive(Debug, Clone)]
+pub struct JournalRow {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    /// e.g. "FEE" (see `transaction_category`)
+    pub category: String,
+    pub amount: Decimal,
+    pub description: Option<String>,
+}
+
+/// The user's own account codes (all None if they never set any)
+pub async fn find_account_codes(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<AccountCodeOverrides, AppError> {
+    let overrides = sqlx::query_as!(
+        AccountCodeOverrides,
+        r#"
+        SELECT wallet_account as wallet, deposit_account as deposit,
+               withdrawal_account as withdrawal, interest_account as interest,
+               fee_account as fee, transfer_in_account as transfer_in,
+               transfer_out_account as transfer_out
+        FROM accounting_account_codes
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .timed("accounting_repo::find_account_codes")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(overrides.unwrap_or_default())
+}
+
+/// Replace the user's own account codes (None = back to the default)
+pub async fn set_account_codes(
+    pool: &PgPool,
+    user_id: Uuid,
+    codes: &AccountCodeOverrides,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO accounting_account_codes (
+            user_id, wallet_account, deposit_account, withdrawal_account,
+            interest_account, fee_account, transfer_in_account, transfer_out_account
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (user_id) DO UPDATE
+        SET wallet_account = EXCLUDED.wallet_account,
+            deposit_account = EXCLUDED.deposit_account,
+            withdrawal_account = EXCLUDED.withdrawal_account,
+            interest_account = EXCLUDED.interest_account,
+            fee_account = EXCLUDED.fee_account,
+            transfer_in_account = EXCLUDED.transfer_in_account,
+            transfer_out_account = EXCLUDED.transfer_out_account,
+            updated_at = NOW()
+        "#,
+        user_id,
+        codes.wallet,
+        codes.deposit,
+        codes.withdrawal,
+        codes.interest,
+        codes.fee,
+        codes.transfer_in,
+        codes.transfer_out
+    )
+    .execute(pool)
+    .timed("accounting_repo::set_account_codes")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// The wallet's COMPLETED transactions from `from` (inclusive) to `to`
+/// (exclusive), oldest first
+pub async fn list_journal_rows(
+    pool: &PgPool,
+    wallet_id: Uuid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<JournalRow>, AppError> {
+    sqlx::query_as!(
+        JournalRow,
+        r#"
+        SELECT id as "id!", created_at as "created_at!", amount as "amount!", description,
+               transaction_category(transaction_type, description) as "category!"
+        FROM (
+            SELECT id, created_at, transaction_type, amount, description
+            FROM transactions
+            WHERE wallet_id = $1 AND status = 'COMPLETED'
+              AND created_at >= $2 AND created_at < $3
+            UNION ALL
+            SELECT id, created_at, transaction_type, amount, description
+            FROM transactions_archive
+            WHERE wallet_id = $1 AND status = 'COMPLETED'
+              AND created_at >= $2 AND created_at < $3
+        ) t
+        ORDER BY created_at, id
+        "#,
+        wallet_id,
+        from,
+        to
+    )
+    .fetch_all(pool)
+    .timed("accounting_repo::list_journal_rows")
+    .await
+    .map_err(AppError::DatabaseError)
+}
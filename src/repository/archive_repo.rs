@@ -0,0 +1,162 @@
+use crate::domain::ids;
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use crate::repository::unit_of_work::with_transaction;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// ARCHIVE REPOSITORY
+// ============================================================================
+// Moves transactions between `transactions` and `transactions_archive`
+// (migration 014). Each move is a single DELETE ... RETURNING feeding an
+// INSERT, so a row is always in exactly one of the two tables.
+//
+// Never archived, whatever their age:
+// - transactions still listed on their wallet's dashboard (the summary
+//   points at them by id)
+// - PENDING transactions (not settled yet)
+// - rows inside a range an admin restored, until the restore's keep_until
+
+/// A range of archived transactions moved back by an admin
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveRestore {
+    pub id: Uuid,
+    /// None = every wallet
+    pub wallet_id: Option<Uuid>,
+    pub from_time: DateTime<Utc>,
+    pub to_time: DateTime<Utc>,
+    pub restored_count: i64,
+    pub restored_at: DateTime<Utc>,
+    /// The archival job leaves the range alone until then
+    pub keep_until: DateTime<Utc>,
+}
+
+/// Archive up to `limit` transactions created before `before`, oldest first
+///
+/// Returns how many were moved; fewer than `limit` means nothing is left.
+pub async fn archive_batch(
+    pool: &PgPool,
+    before: DateTime<Utc>,
+    limit: i64,
+) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        r#"
+        WITH batch AS (
+            SELECT t.id, t.created_at
+            FROM transactions t
+            WHERE t.created_at < $1
+              AND t.status IS DISTINCT FROM 'PENDING'
+              AND NOT EXISTS (
+                  SELECT 1 FROM dashboard_summaries s
+                  WHERE s.wallet_id = t.wallet_id AND t.id = ANY(s.recent_transaction_ids)
+              )
+              AND NOT EXISTS (
+                  SELECT 1 FROM transaction_restores r
+                  WHERE r.keep_until > NOW()
+                    AND (r.wallet_id IS NULL OR r.wallet_id = t.wallet_id)
+                    AND t.created_at >= r.from_time AND t.created_at < r.to_time
+              )
+            ORDER BY t.created_at
+            LIMIT $2
+        ),
+        moved AS (
+            DELETE FROM transactions t
+            USING batch b
+            WHERE t.id = b.id AND t.created_at = b.created_at
+            RETURNING t.id, t.wallet_id, t.transaction_type, t.amount, t.description, t.status, t.created_at,
+                      t.completed_at, t.failed_at, t.reversed_at, t.exchange_rate
+        )
+        INSERT INTO transactions_archive
+            (id, wallet_id, transaction_type, amount, description, status, created_at,
+             completed_at, failed_at, reversed_at, exchange_rate)
+        SELECT id, wallet_id, transaction_type, amount, description, status, created_at,
+               completed_at, failed_at, reversed_at, exchange_rate
+        FROM moved
+        "#,
+        before,
+        limit
+    )
+    .execute(pool)
+    .timed("archive_repo::archive_batch")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected())
+}
+
+/// Move archived transactions created in `from..to` back, and log the restore
+///
+/// `wallet_id` limits it to one wallet (None = every wallet).
+pub async fn restore_range(
+    pool: &PgPool,
+    wallet_id: Option<Uuid>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    restored_by: Uuid,
+    keep_until: DateTime<Utc>,
+) -> Result<ArchiveRestore, AppError> {
+    with_transaction(pool, async |conn| {
+        // Old history coming back isn't new activity: keep it off the
+        // dashboard summary (see the trigger in migration 014)
+        sqlx::query!(r#"SELECT set_config('app.restoring_archive', 'on', true)"#)
+            .fetch_one(&mut *conn)
+            .timed("archive_repo::restore_range.flag")
+            .await
+            .map_err(AppError::DatabaseError)?;
+
+        let moved = sqlx::query!(
+            r#"
+            WITH moved AS (
+                DELETE FROM transactions_archive
+                WHERE created_at >= $1 AND created_at < $2
+                  AND ($3::UUID IS NULL OR wallet_id = $3)
+                RETURNING id, wallet_id, transaction_type, amount, description, status, created_at,
+                          completed_at, failed_at, reversed_at, exchange_rate
+            )
+            INSERT INTO transactions
+                (id, wallet_id, transaction_type, amount, description, status, created_at,
+                 completed_at, failed_at, reversed_at, exchange_rate)
+            SELECT id, wallet_id, transaction_type, amount, description, status, created_at,
+                   completed_at, failed_at, reversed_at, exchange_rate
+            FROM moved
+            "#,
+            from,
+            to,
+            wallet_id
+        )
+        .execute(&mut *conn)
+        .timed("archive_repo::restore_range.move")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        sqlx::query_as!(
+            ArchiveRestore,
+            r#"
+            INSERT INTO transaction_restores
+                (id, wallet_id, from_time, to_time, restored_count, restored_by, keep_until)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, wallet_id, from_time, to_time, restored_count, restored_at, keep_until
+            "#,
+            ids::new_id(),
+            wallet_id,
+            from,
+            to,
+            moved.rows_affected() as i64,
+            restored_by,
+            keep_until
+        )
+        .fetch_one(&mut *conn)
+        .timed("archive_repo::restore_range.log")
+        .await
+        .map_err(|e| match &e {
+            sqlx::Error::Database(db_err) if db_err.is_foreign_key_violation() => {
+                AppError::not_found("Wallet")
+            }
+            _ => AppError::DatabaseError(e),
+        })
+    })
+    .await
+}
@@ -0,0 +1,109 @@
+use crate::domain::ids;
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// ADMIN AUDIT LOG REPOSITORY
+// ============================================================================
+// What admins changed through /api/admin (`admin_audit_log`, migration 040).
+// Entries are only ever added; see middleware/audit.rs for what gets one.
+
+/// One admin action
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    /// None once the admin's account is gone
+    pub admin_id: Option<Uuid>,
+    pub admin_email: Option<String>,
+    pub method: String,
+    pub path: String,
+    /// The HTTP status the action was answered with
+    pub status: i16,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Record an action by `admin_id`, taken `at`
+pub async fn record(
+    pool: &PgPool,
+    admin_id: Uuid,
+    method: &str,
+    path: &str,
+    status: u16,
+    at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO admin_audit_log (id, admin_id, method, path, status, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        ids::new_id(),
+        admin_id,
+        method,
+        path,
+        status as i16,
+        at
+    )
+    .execute(pool)
+    .timed("audit_repo::record")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// How many `method` `path` actions succeeded (2xx) after `since`, and
+/// when the oldest of those was
+pub async fn recent_successes(
+    pool: &PgPool,
+    method: &str,
+    path: &str,
+    since: DateTime<Utc>,
+) -> Result<(i64, Option<DateTime<Utc>>), AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT COUNT(*) as "count!", MIN(created_at) as oldest
+        FROM admin_audit_log
+        WHERE method = $1 AND path = $2 AND status BETWEEN 200 AND 299
+          AND created_at > $3
+        "#,
+        method,
+        path,
+        since
+    )
+    .fetch_one(pool)
+    .timed("audit_repo::recent_successes")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok((row.count, row.oldest))
+}
+
+/// The newest `limit` actions, optionally only those of one admin
+pub async fn list(
+    pool: &PgPool,
+    admin_id: Option<Uuid>,
+    limit: i64,
+) -> Result<Vec<AuditEntry>, AppError> {
+    sqlx::query_as!(
+        AuditEntry,
+        r#"
+        SELECT a.id, a.admin_id, u.email as "admin_email?", a.method, a.path, a.status,
+               a.created_at
+        FROM admin_audit_log a
+        LEFT JOIN users u ON u.id = a.admin_id
+        WHERE $1::UUID IS NULL OR a.admin_id = $1
+        ORDER BY a.created_at DESC, a.id DESC
+        LIMIT $2
+        "#,
+        admin_id,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("audit_repo::list")
+    .await
+    .map_err(AppError::DatabaseError)
+}
@@ -0,0 +1,590 @@
+use crate::domain::ids;
+use crate::domain::models::{PayoutStatus, Transaction, TransactionStatus};
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use crate::repository::transaction_repo;
+use crate::repository::unit_of_work::with_transaction;
+use crate::utils::secret::SecretString;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// BANK REPOSITORY
+// ============================================================================
+// Linked bank accounts, the deposits pulled from them (migration 018),
+// the payouts sent to them (migration 019) and the provider webhooks
+// already acted on (migration 047).
+//
+// The provider's access token is the key to the user's bank login, so it
+// is only ever read back as a `SecretString`, and only by `find_active_account`
+// (the one caller that needs it: starting a deposit or payout). Unlinking
+// clears it.
+
+/// A bank account linked to a user (without its access token)
+#[derive(Debug, Clone)]
+pub struct LinkedBankAccount {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: String,
+    pub provider_account_id: String,
+    pub institution_name: Option<String>,
+    pub account_name: String,
+    pub mask: Option<String>,
+    pub subtype: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An account to store, as reported by the provider
+#[derive(Debug, Clone)]
+pub struct NewLinkedAccount<'a> {
+    pub provider_account_id: &'a str,
+    pub account_name: &'a str,
+    pub mask: Option<&'a str>,
+    pub subtype: Option<&'a str>,
+}
+
+/// A linked account together with the token to move money from it
+#[derive(Debug, Clone)]
+pub struct FundingSource {
+    pub account: LinkedBankAccount,
+    pub access_token: SecretString,
+}
+
+/// A bank deposit the provider hasn't settled (or failed) yet
+#[derive(Debug, Clone)]
+pub struct UnsettledDeposit {
+    pub transaction_id: Uuid,
+    /// None if the provider never accepted the transfer
+    pub provider_transfer_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A payout to a linked bank account
+#[derive(Debug, Clone)]
+pub struct BankPayout {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub linked_account_id: Uuid,
+    pub transaction_id: Uuid,
+    pub amount: Decimal,
+    pub status: String,
+    /// None until the provider has accepted the transfer
+    pub provider_transfer_id: Option<String>,
+    pub return_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub settled_at: Option<DateTime<Utc>>,
+    pub returned_at: Option<DateTime<Utc>>,
+    pub failed_at: Option<DateTime<Utc>>,
+}
+
+/// Store the accounts of one provider login for a user
+///
+/// Accounts the user already has linked are refreshed (new token, names)
+/// rather than duplicated.
+pub async fn upsert_linked_accounts(
+    pool: &PgPool,
+    user_id: Uuid,
+    provider: &str,
+    provider_item_id: &str,
+    access_token: &SecretString,
+    institution_name: Option<&str>,
+    accounts: &[NewLinkedAccount<'_>],
+) -> Result<Vec<LinkedBankAccount>, AppError> {
+    with_transaction(pool, async |conn| {
+        let mut linked = Vec::with_capacity(accounts.len());
+
+        for account in accounts {
+            let row = sqlx::query_as!(
+                LinkedBankAccount,
+                r#"
+                INSERT INTO linked_bank_accounts
+                    (id, user_id, provider, provider_item_id, provider_account_id, access_token,
+                     institution_name, account_name, mask, subtype)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                ON CONFLICT (user_id, provider, provider_account_id) WHERE unlinked_at IS NULL
+                DO UPDATE SET provider_item_id = EXCLUDED.provider_item_id,
+                              access_token = EXCLUDED.access_token,
+                              institution_name = EXCLUDED.institution_name,
+                              account_name = EXCLUDED.account_name,
+                              mask = EXCLUDED.mask,
+                              subtype = EXCLUDED.subtype
+                RETURNING id, user_id, provider, provider_account_id, institution_name,
+                          account_name, mask, subtype, created_at
+                "#,
+                ids::new_id(),
+                user_id,
+                provider,
+                provider_item_id,
+                account.provider_account_id,
+                access_token.expose_secret(),
+                institution_name,
+                account.account_name,
+                account.mask,
+                account.subtype
+            )
+            .fetch_one(&mut *conn)
+            .timed("bank_repo::upsert_linked_accounts")
+            .await
+            .map_err(AppError::DatabaseError)?;
+
+            linked.push(row);
+        }
+
+        Ok(linked)
+    })
+    .await
+}
+
+/// A user's linked accounts, oldest first
+pub async fn list_linked_accounts(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<LinkedBankAccount>, AppError> {
+    sqlx::query_as!(
+        LinkedBankAccount,
+        r#"
+        SELECT id, user_id, provider, provider_account_id, institution_name,
+               account_name, mask, subtype, created_at
+        FROM linked_bank_accounts
+        WHERE user_id = $1 AND unlinked_at IS NULL
+        ORDER BY created_at, id
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .timed("bank_repo::list_linked_accounts")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// One of the user's linked accounts with its access token (None if it
+/// isn't theirs or was unlinked)
+pub async fn find_active_account(
+    pool: &PgPool,
+    user_id: Uuid,
+    account_id: Uuid,
+) -> Result<Option<FundingSource>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, user_id, provider, provider_account_id, institution_name,
+               account_name, mask, subtype, created_at, access_token as "access_token!"
+        FROM linked_bank_accounts
+        WHERE id = $1 AND user_id = $2 AND unlinked_at IS NULL
+        "#,
+        account_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .timed("bank_repo::find_active_account")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(row.map(|row| FundingSource {
+        account: LinkedBankAccount {
+            id: row.id,
+            user_id: row.user_id,
+            provider: row.provider,
+            provider_account_id: row.provider_account_id,
+            institution_name: row.institution_name,
+            account_name: row.account_name,
+            mask: row.mask,
+            subtype: row.subtype,
+            created_at: row.created_at,
+        },
+        access_token: SecretString::from(row.access_token),
+    }))
+}
+
+/// Unlink one of the user's accounts and forget its access token
+///
+/// Returns false if the user has no such linked account. Deposits already
+/// started from it still settle.
+pub async fn unlink_account(
+    pool: &PgPool,
+    user_id: Uuid,
+    account_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE linked_bank_accounts
+        SET unlinked_at = NOW(), access_token = NULL
+        WHERE id = $1 AND user_id = $2 AND unlinked_at IS NULL
+        "#,
+        account_id,
+        user_id
+    )
+    .execute(pool)
+    .timed("bank_repo::unlink_account")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record a PENDING deposit into a wallet from a linked account
+///
+/// The provider transfer is attached with `set_provider_transfer` once the
+/// provider has accepted it.
+pub async fn start_deposit(
+    pool: &PgPool,
+    wallet_id: Uuid,
+    linked_account_id: Uuid,
+    amount: Decimal,
+    description: &str,
+) -> Result<Transaction, AppError> {
+    with_transaction(pool, async |conn| {
+        let transaction =
+            transaction_repo::insert_pending(&mut *conn, wallet_id, "DEPOSIT", amount, description)
+                .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO bank_deposits (transaction_id, linked_account_id)
+            VALUES ($1, $2)
+            "#,
+            transaction.id,
+            linked_account_id
+        )
+        .execute(&mut *conn)
+        .timed("bank_repo::start_deposit")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(transaction)
+    })
+    .await
+}
+
+/// Attach the provider's transfer id to a deposit
+pub async fn set_provider_transfer(
+    pool: &PgPool,
+    transaction_id: Uuid,
+    provider_transfer_id: &str,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE bank_deposits SET provider_transfer_id = $2
+        WHERE transaction_id = $1
+        "#,
+        transaction_id,
+        provider_transfer_id
+    )
+    .execute(pool)
+    .timed("bank_repo::set_provider_transfer")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Up to `limit` unsettled deposits, oldest first
+pub async fn list_unsettled_deposits(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<UnsettledDeposit>, AppError> {
+    sqlx::query_as!(
+        UnsettledDeposit,
+        r#"
+        SELECT transaction_id, provider_transfer_id, created_at
+        FROM bank_deposits
+        WHERE settled_at IS NULL
+        ORDER BY created_at
+        LIMIT $1
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("bank_repo::list_unsettled_deposits")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Finish a deposit: move its transaction to COMPLETED (crediting the
+/// wallet) or FAILED, and stop tracking it
+pub async fn settle_deposit(
+    pool: &PgPool,
+    transaction_id: Uuid,
+    outcome: TransactionStatus,
+) -> Result<Transaction, AppError> {
+    with_transaction(pool, async |conn| {
+        let transaction = transaction_repo::transition(conn, transaction_id, outcome).await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE bank_deposits SET settled_at = NOW()
+            WHERE transaction_id = $1
+            "#,
+            transaction_id
+        )
+        .execute(&mut *conn)
+        .timed("bank_repo::settle_deposit")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(transaction)
+    })
+    .await
+}
+
+/// The unsettled deposit a provider transfer belongs to (None if it isn't
+/// one, or has settled already)
+pub async fn find_unsettled_deposit(
+    pool: &PgPool,
+    provider_transfer_id: &str,
+) -> Result<Option<UnsettledDeposit>, AppError> {
+    sqlx::query_as!(
+        UnsettledDeposit,
+        r#"
+        SELECT transaction_id, provider_transfer_id, created_at
+        FROM bank_deposits
+        WHERE provider_transfer_id = $1 AND settled_at IS NULL
+        "#,
+        provider_transfer_id
+    )
+    .fetch_optional(pool)
+    .timed("bank_repo::find_unsettled_deposit")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Take a payout out of a wallet and record it as PENDING
+///
+/// The WITHDRAWAL is completed straight away, so the money can't be spent
+/// twice while the bank transfer is on its way. Fails with
+/// `InsufficientBalance` (recording nothing) if the wallet can't cover it.
+pub async fn start_payout(
+    pool: &PgPool,
+    user_id: Uuid,
+    wallet_id: Uuid,
+    linked_account_id: Uuid,
+    amount: Decimal,
+    description: &str,
+) -> Result<BankPayout, AppError> {
+    with_transaction(pool, async |conn| {
+        let withdrawal = transaction_repo::insert_pending(
+            &mut *conn,
+            wallet_id,
+            "WITHDRAWAL",
+            amount,
+            description,
+        )
+        .await?;
+        transaction_repo::transition(conn, withdrawal.id, TransactionStatus::Completed).await?;
+
+        sqlx::query_as!(
+            BankPayout,
+            r#"
+            INSERT INTO bank_payouts (id, user_id, linked_account_id, transaction_id, amount)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, user_id, linked_account_id, transaction_id, amount, status,
+                      provider_transfer_id, return_reason, created_at, submitted_at,
+                      settled_at, returned_at, failed_at
+            "#,
+            ids::new_id(),
+            user_id,
+            linked_account_id,
+            withdrawal.id,
+            amount
+        )
+        .fetch_one(&mut *conn)
+        .timed("bank_repo::start_payout")
+        .await
+        .map_err(AppError::DatabaseError)
+    })
+    .await
+}
+
+/// Attach the provider's transfer id to a payout once it has accepted it
+pub async fn set_payout_transfer(
+    pool: &PgPool,
+    payout_id: Uuid,
+    provider_transfer_id: &str,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE bank_payouts SET provider_transfer_id = $2, submitted_at = NOW()
+        WHERE id = $1
+        "#,
+        payout_id,
+        provider_transfer_id
+    )
+    .execute(pool)
+    .timed("bank_repo::set_payout_transfer")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// A user's most recent payouts, newest first
+pub async fn list_payouts(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+) -> Result<Vec<BankPayout>, AppError> {
+    sqlx::query_as!(
+        BankPayout,
+        r#"
+        SELECT id, user_id, linked_account_id, transaction_id, amount, status,
+               provider_transfer_id, return_reason, created_at, submitted_at,
+               settled_at, returned_at, failed_at
+        FROM bank_payouts
+        WHERE user_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
+        "#,
+        user_id,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("bank_repo::list_payouts")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Up to `limit` PENDING payouts, oldest first
+pub async fn list_pending_payouts(pool: &PgPool, limit: i64) -> Result<Vec<BankPayout>, AppError> {
+    sqlx::query_as!(
+        BankPayout,
+        r#"
+        SELECT id, user_id, linked_account_id, transaction_id, amount, status,
+               provider_transfer_id, return_reason, created_at, submitted_at,
+               settled_at, returned_at, failed_at
+        FROM bank_payouts
+        WHERE status = 'PENDING'
+        ORDER BY created_at
+        LIMIT $1
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("bank_repo::list_pending_payouts")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// The payout a provider transfer belongs to (None if it isn't one)
+pub async fn find_payout_by_transfer(
+    pool: &PgPool,
+    provider_transfer_id: &str,
+) -> Result<Option<BankPayout>, AppError> {
+    sqlx::query_as!(
+        BankPayout,
+        r#"
+        SELECT id, user_id, linked_account_id, transaction_id, amount, status,
+               provider_transfer_id, return_reason, created_at, submitted_at,
+               settled_at, returned_at, failed_at
+        FROM bank_payouts
+        WHERE provider_transfer_id = $1
+        "#,
+        provider_transfer_id
+    )
+    .fetch_optional(pool)
+    .timed("bank_repo::find_payout_by_transfer")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Finish a PENDING payout as SETTLED, RETURNED or FAILED
+///
+/// Returned and failed payouts have their WITHDRAWAL reversed, putting the
+/// money back in the wallet. A payout that already finished is returned
+/// unchanged, so the same webhook can safely arrive twice.
+pub async fn finish_payout(
+    pool: &PgPool,
+    payout_id: Uuid,
+    outcome: PayoutStatus,
+    reason: Option<&str>,
+) -> Result<BankPayout, AppError> {
+    with_transaction(pool, async |conn| {
+        let payout = sqlx::query_as!(
+            BankPayout,
+            r#"
+            SELECT id, user_id, linked_account_id, transaction_id, amount, status,
+                   provider_transfer_id, return_reason, created_at, submitted_at,
+                   settled_at, returned_at, failed_at
+            FROM bank_payouts
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            payout_id
+        )
+        .fetch_optional(&mut *conn)
+        .timed("bank_repo::finish_payout.lock")
+        .await
+        .map_err(AppError::DatabaseError)?
+        .ok_or_else(|| AppError::not_found("Payout"))?;
+
+        if payout.status != PayoutStatus::Pending.as_str() || outcome == PayoutStatus::Pending {
+            return Ok(payout);
+        }
+
+        if matches!(outcome, PayoutStatus::Returned | PayoutStatus::Failed) {
+            transaction_repo::transition(conn, payout.transaction_id, TransactionStatus::Reversed)
+                .await?;
+        }
+
+        sqlx::query_as!(
+            BankPayout,
+            r#"
+            UPDATE bank_payouts
+            SET status = $2::VARCHAR,
+                return_reason = $3,
+                settled_at = CASE WHEN $2::VARCHAR = 'SETTLED' THEN NOW() END,
+                returned_at = CASE WHEN $2::VARCHAR = 'RETURNED' THEN NOW() END,
+                failed_at = CASE WHEN $2::VARCHAR = 'FAILED' THEN NOW() END
+            WHERE id = $1
+            RETURNING id, user_id, linked_account_id, transaction_id, amount, status,
+                      provider_transfer_id, return_reason, created_at, submitted_at,
+                      settled_at, returned_at, failed_at
+            "#,
+            payout_id,
+            outcome.as_str(),
+            reason
+        )
+        .fetch_one(&mut *conn)
+        .timed("bank_repo::finish_payout")
+        .await
+        .map_err(AppError::DatabaseError)
+    })
+    .await
+}
+
+/// Note that a provider webhook was received; false if it already was
+///
+/// Receipts signed before `forget_before` are dropped first: webhooks that
+/// old are refused before they get here.
+pub async fn record_webhook_receipt(
+    pool: &PgPool,
+    receipt_key: &str,
+    signed_at: DateTime<Utc>,
+    forget_before: DateTime<Utc>,
+) -> Result<bool, AppError> {
+    sqlx::query!(
+        "DELETE FROM bank_webhook_receipts WHERE signed_at < $1",
+        forget_before
+    )
+    .execute(pool)
+    .timed("bank_repo::record_webhook_receipt")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO bank_webhook_receipts (receipt_key, signed_at)
+        VALUES ($1, $2)
+        ON CONFLICT (receipt_key) DO NOTHING
+        "#,
+        receipt_key,
+        signed_at
+    )
+    .execute(pool)
+    .timed("bank_repo::record_webhook_receipt")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,288 @@
+use crate::domain::ids;
+use crate::domain::models::{Transaction, TransactionStatus, WalletPayMethod};
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use crate::repository::transaction_repo;
+use crate::repository::unit_of_work::with_transaction;
+use crate::services::card_processor::VaultedCard;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// CARD REPOSITORY
+// ============================================================================
+// Apple Pay / Google Pay top-ups (`card_payments`, migration 022), each
+// next to its DEPOSIT transaction.
+//
+// Like bank deposits, the DEPOSIT is recorded as PENDING before the
+// processor is called, so no charge can happen without a record of it,
+// and `settle_payment` moves it on together with the payment row.
+//
+// Saved cards (`saved_cards`, migration 027) hold the processor's token
+// and masked details only.
+
+/// A saved card, with the processor's token for charging it
+#[derive(Debug, Clone)]
+pub struct SavedCard {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub processor: String,
+    pub processor_token: String,
+    /// "APPLE_PAY" or "GOOGLE_PAY"
+    pub method: String,
+    pub brand: Option<String>,
+    pub last4: String,
+    pub expiry_month: i16,
+    pub expiry_year: i16,
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Record a PENDING top-up and its payment row, before the processor is called
+#[allow(clippy::too_many_arguments)]
+pub async fn start_payment(
+    pool: &PgPool,
+    wallet_id: Uuid,
+    user_id: Uuid,
+    method: WalletPayMethod,
+    processor: &str,
+    saved_card_id: Option<Uuid>,
+    amount: Decimal,
+    description: &str,
+) -> Result<Transaction, AppError> {
+    with_transaction(pool, async |conn| {
+        let transaction =
+            transaction_repo::insert_pending(&mut *conn, wallet_id, "DEPOSIT", amount, description)
+                .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO card_payments (transaction_id, user_id, method, processor, saved_card_id)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            transaction.id,
+            user_id,
+            method.as_str(),
+            processor,
+            saved_card_id
+        )
+        .execute(&mut *conn)
+        .timed("card_repo::start_payment")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(transaction)
+    })
+    .await
+}
+
+/// Finish a top-up: move its transaction to COMPLETED (crediting the
+/// wallet) or FAILED, and keep what the processor said
+pub async fn settle_payment(
+    pool: &PgPool,
+    transaction_id: Uuid,
+    outcome: TransactionStatus,
+    processor_payment_id: Option<&str>,
+    decline_reason: Option<&str>,
+) -> Result<Transaction, AppError> {
+    with_transaction(pool, async |conn| {
+        let transaction = transaction_repo::transition(conn, transaction_id, outcome).await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE card_payments
+            SET processor_payment_id = $2, decline_reason = $3, settled_at = NOW()
+            WHERE transaction_id = $1
+            "#,
+            transaction_id,
+            processor_payment_id,
+            decline_reason
+        )
+        .execute(&mut *conn)
+        .timed("card_repo::settle_payment")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(transaction)
+    })
+    .await
+}
+
+/// Save a card the processor kept; the user's first card becomes their
+/// default
+pub async fn save_card(
+    pool: &PgPool,
+    user_id: Uuid,
+    processor: &str,
+    method: WalletPayMethod,
+    card: &VaultedCard,
+) -> Result<SavedCard, AppError> {
+    sqlx::query_as!(
+        SavedCard,
+        r#"
+        INSERT INTO saved_cards (
+            id, user_id, processor, processor_token, method, brand, last4,
+            expiry_month, expiry_year, is_default
+        )
+        VALUES (
+            $1, $2, $3, $4, $5, $6, $7, $8, $9,
+            NOT EXISTS (SELECT 1 FROM saved_cards WHERE user_id = $2 AND is_default)
+        )
+        ON CONFLICT (processor, processor_token) DO UPDATE
+        SET expiry_month = EXCLUDED.expiry_month, expiry_year = EXCLUDED.expiry_year
+        RETURNING id, user_id, processor, processor_token, method, brand, last4,
+                  expiry_month, expiry_year, is_default, created_at
+        "#,
+        ids::new_id(),
+        user_id,
+        processor,
+        card.token,
+        method.as_str(),
+        card.brand,
+        card.last4,
+        card.expiry_month,
+        card.expiry_year
+    )
+    .fetch_one(pool)
+    .timed("card_repo::save_card")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// A user's saved cards, the default first, then newest first
+pub async fn list_saved_cards(pool: &PgPool, user_id: Uuid) -> Result<Vec<SavedCard>, AppError> {
+    sqlx::query_as!(
+        SavedCard,
+        r#"
+        SELECT id, user_id, processor, processor_token, method, brand, last4,
+               expiry_month, expiry_year, is_default, created_at
+        FROM saved_cards
+        WHERE user_id = $1
+        ORDER BY is_default DESC, created_at DESC, id
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .timed("card_repo::list_saved_cards")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// One of the user's saved cards, or their default one when `card_id` is
+/// None (None if there is no such card)
+pub async fn find_saved_card(
+    pool: &PgPool,
+    user_id: Uuid,
+    card_id: Option<Uuid>,
+) -> Result<Option<SavedCard>, AppError> {
+    sqlx::query_as!(
+        SavedCard,
+        r#"
+        SELECT id, user_id, processor, processor_token, method, brand, last4,
+               expiry_month, expiry_year, is_default, created_at
+        FROM saved_cards
+        WHERE user_id = $1 AND (id = $2 OR ($2::UUID IS NULL AND is_default))
+        "#,
+        user_id,
+        card_id
+    )
+    .fetch_optional(pool)
+    .timed("card_repo::find_saved_card")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Delete one of the user's saved cards (false if there is no such card)
+///
+/// When it was the default, the newest remaining card takes its place.
+pub async fn delete_saved_card(pool: &PgPool, user_id: Uuid, card_id: Uuid) -> Result<bool, AppError> {
+    with_transaction(pool, async |conn| {
+        let deleted = sqlx::query_scalar!(
+            r#"
+            DELETE FROM saved_cards
+            WHERE id = $1 AND user_id = $2
+            RETURNING is_default
+            "#,
+            card_id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .timed("card_repo::delete_saved_card")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        let Some(was_default) = deleted else {
+            return Ok(false);
+        };
+
+        if was_default {
+            sqlx::query!(
+                r#"
+                UPDATE saved_cards SET is_default = TRUE
+                WHERE id = (
+                    SELECT id FROM saved_cards
+                    WHERE user_id = $1
+                    ORDER BY created_at DESC, id
+                    LIMIT 1
+                )
+                "#,
+                user_id
+            )
+            .execute(&mut *conn)
+            .timed("card_repo::promote_default_card")
+            .await
+            .map_err(AppError::DatabaseError)?;
+        }
+
+        Ok(true)
+    })
+    .await
+}
+
+/// Make one of the user's saved cards their default (false if there is no
+/// such card)
+pub async fn set_default_card(pool: &PgPool, user_id: Uuid, card_id: Uuid) -> Result<bool, AppError> {
+    with_transaction(pool, async |conn| {
+        let exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM saved_cards WHERE id = $1 AND user_id = $2) as "exists!""#,
+            card_id,
+            user_id
+        )
+        .fetch_one(&mut *conn)
+        .timed("card_repo::saved_card_exists")
+        .await
+        .map_err(AppError::DatabaseError)?;
+        if !exists {
+            return Ok(false);
+        }
+
+        // Clear the old default first: at most one may be set at a time
+        sqlx::query!(
+            r#"
+            UPDATE saved_cards SET is_default = FALSE
+            WHERE user_id = $1 AND is_default AND id <> $2
+            "#,
+            user_id,
+            card_id
+        )
+        .execute(&mut *conn)
+        .timed("card_repo::clear_default_card")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        sqlx::query!(
+            r#"UPDATE saved_cards SET is_default = TRUE WHERE id = $1 AND user_id = $2"#,
+            card_id,
+            user_id
+        )
+        .execute(&mut *conn)
+        .timed("card_repo::set_default_card")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(true)
+    })
+    .await
+}
@@ -0,0 +1,259 @@
+use crate::domain::ids;
+use crate::domain::models::{TradeSide, TransactionStatus};
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use crate::repository::unit_of_work::with_transaction;
+use crate::repository::{transaction_repo, wallet_repo};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// CRYPTO REPOSITORY
+// ============================================================================
+// Crypto balances (`crypto_holdings`) and the trades that changed them
+// (`crypto_trades`), migration 020.
+//
+// A trade touches three things that must agree: the USD wallet, the
+// holding and the trade row. `record_trade` writes all of them in one unit
+// of work, always locking the wallet first so a buy and a sell by the same
+// user can't deadlock.
+
+/// One asset a user holds
+#[derive(Debug, Clone)]
+pub struct CryptoHolding {
+    /// "BTC" or "ETH"
+    pub asset: String,
+    pub quantity: Decimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A completed trade
+#[derive(Debug, Clone)]
+pub struct CryptoTrade {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub asset: String,
+    /// "BUY" or "SELL"
+    pub side: String,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub usd_amount: Decimal,
+    pub provider: String,
+    pub transaction_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A trade to record, already priced
+#[derive(Debug, Clone)]
+pub struct NewTrade<'a> {
+    pub asset: &'a str,
+    pub side: TradeSide,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub usd_amount: Decimal,
+    pub provider: &'a str,
+    /// For the USD transaction in the wallet history
+    pub description: &'a str,
+}
+
+/// Crypto bought and sold in one month, per asset
+#[derive(Debug, Clone)]
+pub struct MonthlyTradeTotals {
+    /// First day of the (UTC) month
+    pub month: NaiveDate,
+    pub asset: String,
+    pub bought: Decimal,
+    pub sold: Decimal,
+    pub usd_spent: Decimal,
+    pub usd_received: Decimal,
+}
+
+/// A user's non-empty holdings, by asset
+pub async fn list_holdings(pool: &PgPool, user_id: Uuid) -> Result<Vec<CryptoHolding>, AppError> {
+    sqlx::query_as!(
+        CryptoHolding,
+        r#"
+        SELECT asset, quantity, updated_at
+        FROM crypto_holdings
+        WHERE user_id = $1 AND quantity > 0
+        ORDER BY asset
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .timed("crypto_repo::list_holdings")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// A user's most recent trades, newest first
+pub async fn list_trades(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+) -> Result<Vec<CryptoTrade>, AppError> {
+    sqlx::query_as!(
+        CryptoTrade,
+        r#"
+        SELECT id, user_id, asset, side, quantity, price, usd_amount, provider,
+               transaction_id, created_at
+        FROM crypto_trades
+        WHERE user_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
+        "#,
+        user_id,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("crypto_repo::list_trades")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Execute a priced trade against the user's wallet and holding
+///
+/// A buy takes `usd_amount` from the wallet (never into the overdraft) and
+/// adds `quantity` to the holding; a sell does the opposite. Fails with
+/// `InsufficientBalance`, writing nothing, if the wallet or the holding
+/// can't cover it.
+pub async fn record_trade(
+    pool: &PgPool,
+    user_id: Uuid,
+    wallet_id: Uuid,
+    trade: &NewTrade<'_>,
+) -> Result<CryptoTrade, AppError> {
+    with_transaction(pool, async |conn| {
+        let wallet = wallet_repo::lock_wallet_by_id(conn, wallet_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Wallet"))?;
+
+        let held = sqlx::query_scalar!(
+            r#"
+            SELECT quantity FROM crypto_holdings
+            WHERE user_id = $1 AND asset = $2
+            FOR UPDATE
+            "#,
+            user_id,
+            trade.asset
+        )
+        .fetch_optional(&mut *conn)
+        .timed("crypto_repo::record_trade.lock")
+        .await
+        .map_err(AppError::DatabaseError)?
+        .unwrap_or(Decimal::ZERO);
+
+        let transaction_type = match trade.side {
+            TradeSide::Buy if wallet.balance < trade.usd_amount => {
+                return Err(AppError::InsufficientBalance)
+            }
+            TradeSide::Sell if held < trade.quantity => return Err(AppError::InsufficientBalance),
+            TradeSide::Buy => "WITHDRAWAL",
+            TradeSide::Sell => "DEPOSIT",
+        };
+
+        // The USD side, as an ordinary wallet transaction
+        let usd = transaction_repo::insert_pending(
+            &mut *conn,
+            wallet_id,
+            transaction_type,
+            trade.usd_amount,
+            trade.description,
+        )
+        .await?;
+        transaction_repo::transition(conn, usd.id, TransactionStatus::Completed).await?;
+
+        let holding = match trade.side {
+            TradeSide::Buy => sqlx::query!(
+                r#"
+                INSERT INTO crypto_holdings (user_id, asset, quantity)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (user_id, asset)
+                DO UPDATE SET quantity = crypto_holdings.quantity + EXCLUDED.quantity,
+                              updated_at = NOW()
+                "#,
+                user_id,
+                trade.asset,
+                trade.quantity
+            ),
+            TradeSide::Sell => sqlx::query!(
+                r#"
+                UPDATE crypto_holdings
+                SET quantity = quantity - $3, updated_at = NOW()
+                WHERE user_id = $1 AND asset = $2
+                "#,
+                user_id,
+                trade.asset,
+                trade.quantity
+            ),
+        };
+        holding
+            .execute(&mut *conn)
+            .timed("crypto_repo::record_trade.holding")
+            .await
+            .map_err(AppError::DatabaseError)?;
+
+        sqlx::query_as!(
+            CryptoTrade,
+            r#"
+            INSERT INTO crypto_trades
+                (id, user_id, asset, side, quantity, price, usd_amount, provider, transaction_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id, user_id, asset, side, quantity, price, usd_amount, provider,
+                      transaction_id, created_at
+            "#,
+            ids::new_id(),
+            user_id,
+            trade.asset,
+            trade.side.as_str(),
+            trade.quantity,
+            trade.price,
+            trade.usd_amount,
+            trade.provider,
+            usd.id
+        )
+        .fetch_one(&mut *conn)
+        .timed("crypto_repo::record_trade")
+        .await
+        .map_err(AppError::DatabaseError)
+    })
+    .await
+}
+
+/// A user's crypto bought and sold per month and asset, for `from` to `to`
+/// (inclusive, first days of months)
+pub async fn list_monthly_trade_totals(
+    pool: &PgPool,
+    user_id: Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<MonthlyTradeTotals>, AppError> {
+    sqlx::query_as!(
+        MonthlyTradeTotals,
+        r#"
+        SELECT month as "month!", asset,
+               COALESCE(SUM(quantity) FILTER (WHERE side = 'BUY'), 0) as "bought!",
+               COALESCE(SUM(quantity) FILTER (WHERE side = 'SELL'), 0) as "sold!",
+               COALESCE(SUM(usd_amount) FILTER (WHERE side = 'BUY'), 0) as "usd_spent!",
+               COALESCE(SUM(usd_amount) FILTER (WHERE side = 'SELL'), 0) as "usd_received!"
+        FROM (
+            SELECT date_trunc('month', created_at AT TIME ZONE 'UTC')::date as month,
+                   asset, side, quantity, usd_amount
+            FROM crypto_trades
+            WHERE user_id = $1
+        ) trades
+        WHERE month BETWEEN $2 AND $3
+        GROUP BY month, asset
+        ORDER BY month, asset
+        "#,
+        user_id,
+        from,
+        to
+    )
+    .fetch_all(pool)
+    .timed("crypto_repo::list_monthly_trade_totals")
+    .await
+    .map_err(AppError::DatabaseError)
+}
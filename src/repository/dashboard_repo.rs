@@ -0,0 +1,94 @@
+use crate::domain::models::{Currency, DashboardSummary, Transaction, UserResponse, WalletResponse};
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// DASHBOARD REPOSITORY
+// ============================================================================
+// Reads the `dashboard_summaries` read model. Nothing here writes to it: the
+// triggers from migration 011 update the row whenever a wallet balance
+// changes or a transaction is recorded.
+
+/// The dashboard overview for a user, in one query
+///
+/// Returns one row per recent transaction (or a single row with NULL
+/// transaction columns when there are none), each carrying the user and
+/// summary columns.
+pub async fn get_dashboard_summary(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<DashboardSummary, AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT u.id, u.email, u.full_name, u.created_at as "created_at!",
+               s.wallet_id, s.balance, s.currency as "currency: Currency",
+               -- Totals from an earlier month are stale, not this month's
+               CASE WHEN s.month_start = date_trunc('month', NOW() AT TIME ZONE 'UTC')::DATE
+                    THEN s.month_in ELSE 0.00 END as "month_in!",
+               CASE WHEN s.month_start = date_trunc('month', NOW() AT TIME ZONE 'UTC')::DATE
+                    THEN s.month_out ELSE 0.00 END as "month_out!",
+               t.id as "tx_id?", t.transaction_type as "tx_type?", t.amount as "tx_amount?",
+               t.description as tx_description, t.status as "tx_status?",
+               t.created_at as "tx_created_at?", t.completed_at as tx_completed_at,
+               t.failed_at as tx_failed_at, t.reversed_at as tx_reversed_at,
+               t.exchange_rate as tx_exchange_rate
+        FROM users u
+        JOIN dashboard_summaries s ON s.user_id = u.id
+        LEFT JOIN transactions t
+               ON t.wallet_id = s.wallet_id AND t.id = ANY(s.recent_transaction_ids)
+        WHERE u.id = $1 AND u.deleted_at IS NULL
+        ORDER BY t.created_at DESC, t.id DESC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .timed("dashboard_repo::get_dashboard_summary")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    let Some(first) = rows.first() else {
+        return Err(AppError::not_found("Wallet"));
+    };
+
+    let user = UserResponse {
+        id: first.id,
+        email: first.email.clone(),
+        full_name: first.full_name.clone(),
+        created_at: first.created_at,
+    };
+    let wallet = WalletResponse {
+        id: first.wallet_id,
+        balance: first.balance,
+        currency: first.currency,
+    };
+    let (month_in, month_out) = (first.month_in, first.month_out);
+
+    let recent_transactions = rows
+        .into_iter()
+        .filter_map(|row| {
+            Some(Transaction {
+                id: row.tx_id?,
+                wallet_id: row.wallet_id,
+                transaction_type: row.tx_type?,
+                amount: row.tx_amount?,
+                description: row.tx_description,
+                status: row.tx_status?,
+                created_at: row.tx_created_at?,
+                completed_at: row.tx_completed_at,
+                failed_at: row.tx_failed_at,
+                reversed_at: row.tx_reversed_at,
+                exchange_rate: row.tx_exchange_rate,
+            })
+        })
+        .collect();
+
+    Ok(DashboardSummary {
+        user,
+        wallet,
+        recent_transactions,
+        month_in,
+        month_out,
+    })
+}
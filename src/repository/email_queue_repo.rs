@@ -0,0 +1,193 @@
+use crate::domain::ids;
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// EMAIL QUEUE REPOSITORY
+// ============================================================================
+// Emails waiting to be delivered, the ones given up on, and the addresses
+// we no longer write to (migration 044); see services/email_service.rs for
+// how they move between the three.
+
+/// An email in the queue
+#[derive(Debug, Clone)]
+pub struct QueuedEmail {
+    pub id: Uuid,
+    pub to_address: String,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: Option<String>,
+    /// Tries that failed so far
+    pub attempts: i32,
+}
+
+/// Whether `address` is on the suppression list
+pub async fn is_suppressed(pool: &PgPool, address: &str) -> Result<bool, AppError> {
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM email_suppressions WHERE address = lower($1)) as "suppressed!""#,
+        address
+    )
+    .fetch_one(pool)
+    .timed("email_queue_repo::is_suppressed")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Stop emailing `address` (a no-op if it's already suppressed)
+pub async fn suppress(
+    pool: &PgPool,
+    address: &str,
+    reason: &str,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO email_suppressions (address, reason, created_at)
+        VALUES (lower($1), $2, $3)
+        ON CONFLICT (address) DO NOTHING
+        "#,
+        address,
+        reason,
+        now
+    )
+    .execute(pool)
+    .timed("email_queue_repo::suppress")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Queue an email, not to be picked up by the retry task before
+/// `next_attempt_at`
+pub async fn enqueue(
+    pool: &PgPool,
+    to_address: &str,
+    subject: &str,
+    text_body: &str,
+    html_body: Option<&str>,
+    now: DateTime<Utc>,
+    next_attempt_at: DateTime<Utc>,
+) -> Result<QueuedEmail, AppError> {
+    sqlx::query_as!(
+        QueuedEmail,
+        r#"
+        INSERT INTO email_queue
+            (id, to_address, subject, text_body, html_body, next_attempt_at, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, to_address, subject, text_body, html_body, attempts
+        "#,
+        ids::new_id(),
+        to_address,
+        subject,
+        text_body,
+        html_body,
+        next_attempt_at,
+        now
+    )
+    .fetch_one(pool)
+    .timed("email_queue_repo::enqueue")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Take up to `limit` emails due by `now`, pushing them back to
+/// `claimed_until` so no other task tries them meanwhile
+pub async fn claim_due(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+    claimed_until: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<QueuedEmail>, AppError> {
+    sqlx::query_as!(
+        QueuedEmail,
+        r#"
+        UPDATE email_queue SET next_attempt_at = $2
+        WHERE id IN (
+            SELECT id FROM email_queue
+            WHERE next_attempt_at <= $1
+            ORDER BY next_attempt_at
+            LIMIT $3
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, to_address, subject, text_body, html_body, attempts
+        "#,
+        now,
+        claimed_until,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("email_queue_repo::claim_due")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// The email was delivered: take it out of the queue
+pub async fn delete(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM email_queue WHERE id = $1", id)
+        .execute(pool)
+        .timed("email_queue_repo::delete")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Count a failed try and try again at `next_attempt_at`
+pub async fn record_failure(
+    pool: &PgPool,
+    id: Uuid,
+    error: &str,
+    next_attempt_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE email_queue
+        SET attempts = attempts + 1, last_error = $2, next_attempt_at = $3
+        WHERE id = $1
+        "#,
+        id,
+        error,
+        next_attempt_at
+    )
+    .execute(pool)
+    .timed("email_queue_repo::record_failure")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Count a failed try and give up: the email moves to the dead letters
+pub async fn dead_letter(
+    pool: &PgPool,
+    id: Uuid,
+    error: &str,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        WITH given_up AS (
+            DELETE FROM email_queue WHERE id = $1
+            RETURNING id, to_address, subject, text_body, html_body, attempts, created_at
+        )
+        INSERT INTO email_dead_letters
+            (id, to_address, subject, text_body, html_body, attempts, last_error, created_at,
+             failed_at)
+        SELECT id, to_address, subject, text_body, html_body, attempts + 1, $2, created_at, $3
+        FROM given_up
+        "#,
+        id,
+        error,
+        now
+    )
+    .execute(pool)
+    .timed("email_queue_repo::dead_letter")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
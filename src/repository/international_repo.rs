@@ -0,0 +1,288 @@
+use crate::domain::ids;
+use crate::domain::models::{Currency, InternationalTransferStatus, TransactionStatus};
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use crate::repository::transaction_repo;
+use crate::repository::unit_of_work::with_transaction;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// INTERNATIONAL TRANSFER REPOSITORY
+// ============================================================================
+// Transfers to bank accounts abroad (`international_transfers`, migration
+// 028), each next to the WITHDRAWAL that paid for it.
+//
+// As with bank payouts, the withdrawal is completed when the transfer is
+// recorded, and reversed if the transfer comes back.
+
+/// An international transfer, with the quote it was sent at
+#[derive(Debug, Clone)]
+pub struct InternationalTransfer {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub transaction_id: Uuid,
+    pub beneficiary_name: String,
+    pub iban: String,
+    pub bic: String,
+    pub reference: Option<String>,
+    pub source_currency: Currency,
+    pub amount: Decimal,
+    pub correspondent_fee: Decimal,
+    pub destination_currency: Currency,
+    pub mid_rate: Decimal,
+    pub rate: Decimal,
+    pub destination_amount: Decimal,
+    pub status: String,
+    pub tracking_reference: Option<String>,
+    pub return_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub in_transit_at: Option<DateTime<Utc>>,
+    pub settled_at: Option<DateTime<Utc>>,
+    pub returned_at: Option<DateTime<Utc>>,
+}
+
+/// What `start_transfer` records
+#[derive(Debug)]
+pub struct NewInternationalTransfer<'a> {
+    pub user_id: Uuid,
+    pub wallet_id: Uuid,
+    pub beneficiary_name: &'a str,
+    /// Compact form (see `domain::iban`)
+    pub iban: &'a str,
+    pub bic: &'a str,
+    pub reference: Option<&'a str>,
+    pub source_currency: Currency,
+    pub amount: Decimal,
+    pub correspondent_fee: Decimal,
+    pub destination_currency: Currency,
+    pub mid_rate: Decimal,
+    pub rate: Decimal,
+    pub destination_amount: Decimal,
+    /// For the WITHDRAWAL
+    pub description: &'a str,
+}
+
+/// Take a transfer (amount + fee) out of a wallet and record it as SUBMITTED
+///
+/// Fails with `InsufficientBalance` (recording nothing) if the wallet
+/// can't cover it.
+pub async fn start_transfer(
+    pool: &PgPool,
+    transfer: &NewInternationalTransfer<'_>,
+) -> Result<InternationalTransfer, AppError> {
+    with_transaction(pool, async |conn| {
+        let withdrawal = transaction_repo::insert_pending(
+            &mut *conn,
+            transfer.wallet_id,
+            "WITHDRAWAL",
+            transfer.amount + transfer.correspondent_fee,
+            transfer.description,
+        )
+        .await?;
+        transaction_repo::transition(conn, withdrawal.id, TransactionStatus::Completed).await?;
+
+        sqlx::query_as!(
+            InternationalTransfer,
+            r#"
+            INSERT INTO international_transfers (
+                id, user_id, transaction_id, beneficiary_name, iban, bic, reference,
+                source_currency, amount, correspondent_fee, destination_currency,
+                mid_rate, rate, destination_amount
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            RETURNING id, user_id, transaction_id, beneficiary_name, iban, bic, reference,
+                      source_currency as "source_currency: Currency", amount, correspondent_fee,
+                      destination_currency as "destination_currency: Currency",
+                      mid_rate, rate, destination_amount, status, tracking_reference,
+                      return_reason, created_at, in_transit_at, settled_at, returned_at
+            "#,
+            ids::new_id(),
+            transfer.user_id,
+            withdrawal.id,
+            transfer.beneficiary_name,
+            transfer.iban,
+            transfer.bic,
+            transfer.reference,
+            transfer.source_currency as Currency,
+            transfer.amount,
+            transfer.correspondent_fee,
+            transfer.destination_currency as Currency,
+            transfer.mid_rate,
+            transfer.rate,
+            transfer.destination_amount
+        )
+        .fetch_one(&mut *conn)
+        .timed("international_repo::start_transfer")
+        .await
+        .map_err(AppError::DatabaseError)
+    })
+    .await
+}
+
+/// A user's most recent international transfers, newest first
+pub async fn list_transfers(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+) -> Result<Vec<InternationalTransfer>, AppError> {
+    sqlx::query_as!(
+        InternationalTransfer,
+        r#"
+        SELECT id, user_id, transaction_id, beneficiary_name, iban, bic, reference,
+               source_currency as "source_currency: Currency", amount, correspondent_fee,
+               destination_currency as "destination_currency: Currency",
+               mid_rate, rate, destination_amount, status, tracking_reference,
+               return_reason, created_at, in_transit_at, settled_at, returned_at
+        FROM international_transfers
+        WHERE user_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
+        "#,
+        user_id,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("international_repo::list_transfers")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// One of a user's international transfers (None if there is no such one)
+pub async fn find_transfer(
+    pool: &PgPool,
+    user_id: Uuid,
+    transfer_id: Uuid,
+) -> Result<Option<InternationalTransfer>, AppError> {
+    sqlx::query_as!(
+        InternationalTransfer,
+        r#"
+        SELECT id, user_id, transaction_id, beneficiary_name, iban, bic, reference,
+               source_currency as "source_currency: Currency", amount, correspondent_fee,
+               destination_currency as "destination_currency: Currency",
+               mid_rate, rate, destination_amount, status, tracking_reference,
+               return_reason, created_at, in_transit_at, settled_at, returned_at
+        FROM international_transfers
+        WHERE id = $1 AND user_id = $2
+        "#,
+        transfer_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .timed("international_repo::find_transfer")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Up to `limit` transfers in one status, oldest first (the ops queue)
+pub async fn list_by_status(
+    pool: &PgPool,
+    status: InternationalTransferStatus,
+    limit: i64,
+) -> Result<Vec<InternationalTransfer>, AppError> {
+    sqlx::query_as!(
+        InternationalTransfer,
+        r#"
+        SELECT id, user_id, transaction_id, beneficiary_name, iban, bic, reference,
+               source_currency as "source_currency: Currency", amount, correspondent_fee,
+               destination_currency as "destination_currency: Currency",
+               mid_rate, rate, destination_amount, status, tracking_reference,
+               return_reason, created_at, in_transit_at, settled_at, returned_at
+        FROM international_transfers
+        WHERE status = $1
+        ORDER BY created_at, id
+        LIMIT $2
+        "#,
+        status.as_str(),
+        limit
+    )
+    .fetch_all(pool)
+    .timed("international_repo::list_by_status")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Move a transfer to its next status
+///
+/// A RETURNED transfer has its WITHDRAWAL reversed, putting the money
+/// (fee included) back in the wallet. Asking for the status it already
+/// has returns it unchanged, so a repeated update is harmless; any other
+/// step outside the lifecycle fails with a validation error.
+pub async fn advance_transfer(
+    pool: &PgPool,
+    transfer_id: Uuid,
+    next: InternationalTransferStatus,
+    tracking_reference: Option<&str>,
+    reason: Option<&str>,
+) -> Result<InternationalTransfer, AppError> {
+    with_transaction(pool, async |conn| {
+        let transfer = sqlx::query_as!(
+            InternationalTransfer,
+            r#"
+            SELECT id, user_id, transaction_id, beneficiary_name, iban, bic, reference,
+                   source_currency as "source_currency: Currency", amount, correspondent_fee,
+                   destination_currency as "destination_currency: Currency",
+                   mid_rate, rate, destination_amount, status, tracking_reference,
+                   return_reason, created_at, in_transit_at, settled_at, returned_at
+            FROM international_transfers
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            transfer_id
+        )
+        .fetch_optional(&mut *conn)
+        .timed("international_repo::advance_transfer.lock")
+        .await
+        .map_err(AppError::DatabaseError)?
+        .ok_or_else(|| AppError::not_found("International transfer"))?;
+
+        let current = InternationalTransferStatus::parse(&transfer.status).ok_or_else(|| {
+            AppError::internal(&format!("Unknown transfer status {}", transfer.status))
+        })?;
+        if current == next {
+            return Ok(transfer);
+        }
+        if !current.can_become(next) {
+            return Err(AppError::validation(&format!(
+                "A {} transfer can't become {}",
+                current.as_str(),
+                next.as_str()
+            )));
+        }
+
+        if next == InternationalTransferStatus::Returned {
+            transaction_repo::transition(conn, transfer.transaction_id, TransactionStatus::Reversed)
+                .await?;
+        }
+
+        sqlx::query_as!(
+            InternationalTransfer,
+            r#"
+            UPDATE international_transfers
+            SET status = $2::VARCHAR,
+                tracking_reference = COALESCE($3, tracking_reference),
+                return_reason = COALESCE($4, return_reason),
+                in_transit_at = CASE WHEN $2::VARCHAR = 'IN_TRANSIT' THEN NOW() ELSE in_transit_at END,
+                settled_at = CASE WHEN $2::VARCHAR = 'SETTLED' THEN NOW() END,
+                returned_at = CASE WHEN $2::VARCHAR = 'RETURNED' THEN NOW() END
+            WHERE id = $1
+            RETURNING id, user_id, transaction_id, beneficiary_name, iban, bic, reference,
+                      source_currency as "source_currency: Currency", amount, correspondent_fee,
+                      destination_currency as "destination_currency: Currency",
+                      mid_rate, rate, destination_amount, status, tracking_reference,
+                      return_reason, created_at, in_transit_at, settled_at, returned_at
+            "#,
+            transfer_id,
+            next.as_str(),
+            tracking_reference,
+            reason
+        )
+        .fetch_one(&mut *conn)
+        .timed("international_repo::advance_transfer")
+        .await
+        .map_err(AppError::DatabaseError)
+    })
+    .await
+}
@@ -0,0 +1,303 @@
+use crate::domain::ids;
+use crate::domain::models::Currency;
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+// ============================================================================
+// LEDGER REPOSITORY
+// ============================================================================
+// The double-entry ledger (migration 043), and totals for checking wallet
+// balances against it.
+//
+// Money only moves by posting a journal: entries that debit the accounts
+// money leaves and credit the ones it goes to, equal in each currency. A
+// wallet's balance is its credits minus its debits. `wallets.balance` is a
+// cached copy, updated by `wallet_repo::post_journal` in the same database
+// transaction as the entries, so it never has to be summed on the hot path.
+//
+// Entries are append-only and the database refuses a journal that doesn't
+// balance (at commit), so a corrupted balance shows up as a difference
+// between the cache and the entries instead of going unnoticed.
+//
+//   let journal = Journal::new("Transfer sent").movement(
+//       LedgerAccount::Wallet(sender.id),
+//       LedgerAccount::Wallet(recipient.id),
+//       sender.currency,
+//       amount,
+//   );
+//   wallet_repo::post_journal(conn, &journal).await?;
+
+/// One side of a movement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerAccount {
+    Wallet(Uuid),
+    /// Outside the platform: deposits come from it, withdrawals, payouts
+    /// and money on its way go to it
+    External,
+    /// Currency conversion: a converted transfer pays into it in one
+    /// currency and out of it in the other
+    Exchange,
+    /// Balances from before the ledger (only written by migration 043)
+    Opening,
+}
+
+impl LedgerAccount {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LedgerAccount::Wallet(_) => "WALLET",
+            LedgerAccount::External => "EXTERNAL",
+            LedgerAccount::Exchange => "EXCHANGE",
+            LedgerAccount::Opening => "OPENING",
+        }
+    }
+
+    fn wallet_id(&self) -> Option<Uuid> {
+        match self {
+            LedgerAccount::Wallet(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+/// One line of a journal: a debit or a credit to one account
+#[derive(Debug, Clone)]
+pub struct LedgerEntry {
+    pub account: LedgerAccount,
+    pub currency: Currency,
+    pub debit: Decimal,
+    pub credit: Decimal,
+}
+
+/// Entries that are posted together and balance
+#[derive(Debug, Clone)]
+pub struct Journal {
+    pub id: Uuid,
+    pub description: String,
+    pub entries: Vec<LedgerEntry>,
+}
+
+impl Journal {
+    pub fn new(description: &str) -> Self {
+        Journal {
+            id: ids::new_id(),
+            description: description.to_string(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Move `amount` (positive) from one account to another: debit `from`,
+    /// credit `to`
+    pub fn movement(
+        mut self,
+        from: LedgerAccount,
+        to: LedgerAccount,
+        currency: Currency,
+        amount: Decimal,
+    ) -> Self {
+        self.entries.push(LedgerEntry { account: from, currency, debit: amount, credit: Decimal::ZERO });
+        self.entries.push(LedgerEntry { account: to, currency, debit: Decimal::ZERO, credit: amount });
+        self
+    }
+
+    /// What each wallet in the journal gains (negative: loses), in the
+    /// order they first appear
+    pub fn wallet_changes(&self) -> Vec<(Uuid, Decimal)> {
+        let mut changes: Vec<(Uuid, Decimal)> = Vec::new();
+        for entry in &self.entries {
+            let Some(wallet_id) = entry.account.wallet_id() else {
+                continue;
+            };
+            let change = entry.credit - entry.debit;
+            match changes.iter_mut().find(|(id, _)| *id == wallet_id) {
+                Some((_, total)) => *total += change,
+                None => changes.push((wallet_id, change)),
+            }
+        }
+        changes
+    }
+
+    /// Whether debits equal credits in every currency
+    fn is_balanced(&self) -> bool {
+        let mut totals: Vec<(Currency, Decimal)> = Vec::new();
+        for entry in &self.entries {
+            let change = entry.debit - entry.credit;
+            match totals.iter_mut().find(|(currency, _)| *currency == entry.currency) {
+                Some((_, total)) => *total += change,
+                None => totals.push((entry.currency, change)),
+            }
+        }
+        totals.iter().all(|(_, total)| total.is_zero())
+    }
+}
+
+/// Write a journal's entries (the database checks again at commit that it
+/// balances)
+///
+/// Only the entries: use `wallet_repo::post_journal`, which also updates
+/// the cached wallet balances.
+pub async fn insert_journal(conn: &mut PgConnection, journal: &Journal) -> Result<(), AppError> {
+    if journal.entries.is_empty() || !journal.is_balanced() {
+        return Err(AppError::internal("Ledger journal does not balance"));
+    }
+
+    let ids: Vec<Uuid> = journal.entries.iter().map(|_| ids::new_id()).collect();
+    let accounts: Vec<&str> = journal.entries.iter().map(|e| e.account.as_str()).collect();
+    let wallet_ids: Vec<Option<Uuid>> = journal.entries.iter().map(|e| e.account.wallet_id()).collect();
+    // (as text: sqlx has no array type for the enum)
+    let currencies: Vec<String> = journal.entries.iter().map(|e| e.currency.to_string()).collect();
+    let debits: Vec<Decimal> = journal.entries.iter().map(|e| e.debit).collect();
+    let credits: Vec<Decimal> = journal.entries.iter().map(|e| e.credit).collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO ledger_entries (id, journal_id, account, wallet_id, currency, debit, credit, description)
+        SELECT id, $2, account, wallet_id, currency::currency, debit, credit, $3
+        FROM UNNEST($1::uuid[], $4::text[], $5::uuid[], $6::text[], $7::numeric[], $8::numeric[])
+            AS e(id, account, wallet_id, currency, debit, credit)
+        "#,
+        &ids,
+        journal.id,
+        journal.description,
+        &accounts as &[&str],
+        &wallet_ids as &[Option<Uuid>],
+        &currencies,
+        &debits,
+        &credits
+    )
+    .execute(conn)
+    .timed("ledger_repo::insert_journal")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Journals whose debits and credits don't match in some currency
+///
+/// The database refuses to commit one, so anything here got in around it
+/// (triggers disabled, a restore gone wrong).
+pub async fn unbalanced_journals(pool: &PgPool) -> Result<Vec<Uuid>, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT journal_id as "journal_id!"
+        FROM ledger_entries
+        GROUP BY journal_id, currency
+        HAVING SUM(debit) <> SUM(credit)
+        ORDER BY journal_id
+        "#
+    )
+    .fetch_all(pool)
+    .timed("ledger_repo::unbalanced_journals")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+// ============================================================================
+// WALLET TOTALS
+// ============================================================================
+// Per wallet: the stored balance, what its ledger entries add up to, and
+// the totals of its transaction records (the history users see), so the
+// integrity check can compare all three.
+//
+// Only COMPLETED transactions count. Transfers are told apart the same way
+// as in the dashboard summary (`transaction_is_credit`, migration 011):
+// "Transfer received" adds to the balance, "Transfer sent" takes from it.
+//
+// Archived transactions (migration 014) count too: moving a row to cold
+// storage doesn't change what the balance should be.
+//
+// Wallets of deleted users are included; their records still have to add up.
+
+/// A wallet's stored balance, its ledger balance and the totals of its
+/// transaction records
+#[derive(Debug, Clone)]
+pub struct WalletLedger {
+    pub wallet_id: Uuid,
+    /// The owner: a user or an organization
+    pub user_id: Option<Uuid>,
+    pub organization_id: Option<Uuid>,
+    pub currency: Currency,
+    pub stored_balance: Decimal,
+    /// Credits minus debits of the wallet's ledger entries
+    pub ledger_balance: Decimal,
+    /// How many ledger entries the wallet has
+    pub postings: i64,
+    pub deposits: Decimal,
+    pub withdrawals: Decimal,
+    pub transfers_in: Decimal,
+    pub transfers_out: Decimal,
+    /// How many COMPLETED transactions went into the totals
+    pub entries: i64,
+}
+
+impl WalletLedger {
+    /// The balance the transaction records add up to
+    pub fn records_balance(&self) -> Decimal {
+        self.deposits + self.transfers_in - self.withdrawals - self.transfers_out
+    }
+}
+
+/// Ledger totals for one wallet
+pub async fn get_wallet_ledger(pool: &PgPool, wallet_id: Uuid) -> Result<WalletLedger, AppError> {
+    wallet_ledgers(pool, Some(wallet_id), "ledger_repo::get_wallet_ledger")
+        .await?
+        .pop()
+        .ok_or_else(|| AppError::not_found("Wallet"))
+}
+
+/// Ledger totals for every wallet, in one pass over the transactions
+pub async fn list_wallet_ledgers(pool: &PgPool) -> Result<Vec<WalletLedger>, AppError> {
+    wallet_ledgers(pool, None, "ledger_repo::list_wallet_ledgers").await
+}
+
+async fn wallet_ledgers(
+    pool: &PgPool,
+    wallet_id: Option<Uuid>,
+    query_name: &'static str,
+) -> Result<Vec<WalletLedger>, AppError> {
+    sqlx::query_as!(
+        WalletLedger,
+        r#"
+        SELECT w.id as wallet_id, w.user_id, w.organization_id, w.currency as "currency: Currency",
+               w.balance as stored_balance,
+               COALESCE(l.balance, 0) as "ledger_balance!",
+               COALESCE(l.postings, 0) as "postings!",
+               COALESCE(SUM(t.amount) FILTER (WHERE t.transaction_type = 'DEPOSIT'), 0) as "deposits!",
+               COALESCE(SUM(t.amount) FILTER (WHERE t.transaction_type = 'WITHDRAWAL'), 0) as "withdrawals!",
+               COALESCE(SUM(t.amount) FILTER (
+                   WHERE t.transaction_type = 'TRANSFER'
+                     AND transaction_is_credit(t.transaction_type, t.description)
+               ), 0) as "transfers_in!",
+               COALESCE(SUM(t.amount) FILTER (
+                   WHERE t.transaction_type = 'TRANSFER'
+                     AND transaction_is_credit(t.transaction_type, t.description) IS NOT TRUE
+               ), 0) as "transfers_out!",
+               COUNT(t.id) as "entries!"
+        FROM wallets w
+        LEFT JOIN (
+            SELECT wallet_id, id, transaction_type, amount, description
+            FROM transactions WHERE status = 'COMPLETED'
+            UNION ALL
+            SELECT wallet_id, id, transaction_type, amount, description
+            FROM transactions_archive WHERE status = 'COMPLETED'
+        ) t ON t.wallet_id = w.id
+        LEFT JOIN (
+            SELECT wallet_id, SUM(credit) - SUM(debit) as balance, COUNT(*) as postings
+            FROM ledger_entries
+            WHERE wallet_id IS NOT NULL
+            GROUP BY wallet_id
+        ) l ON l.wallet_id = w.id
+        WHERE $1::UUID IS NULL OR w.id = $1
+        GROUP BY w.id, l.balance, l.postings
+        ORDER BY w.id
+        "#,
+        wallet_id
+    )
+    .fetch_all(pool)
+    .timed(query_name)
+    .await
+    .map_err(AppError::DatabaseError)
+}
@@ -0,0 +1,135 @@
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+// ============================================================================
+// LOGIN ATTEMPT REPOSITORY
+// ============================================================================
+// Failed sign-ins per (lowercased) email address (migration 042); see
+// services/login_throttle_service.rs for what's done with them.
+
+/// Where an address stands
+#[derive(Debug, Clone)]
+pub struct LoginAttempts {
+    pub failures: i32,
+    pub retry_after: Option<DateTime<Utc>>,
+    pub locked_at: Option<DateTime<Utc>>,
+}
+
+pub async fn find(pool: &PgPool, email: &str) -> Result<Option<LoginAttempts>, AppError> {
+    sqlx::query_as!(
+        LoginAttempts,
+        r#"
+        SELECT failures, retry_after, locked_at
+        FROM login_attempts
+        WHERE email = lower($1)
+        "#,
+        email
+    )
+    .fetch_optional(pool)
+    .timed("login_attempt_repo::find")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Count a failed attempt at `now`; returns the failures in a row
+///
+/// Failures from before `forget_before` don't count any more: the count
+/// starts again at 1.
+pub async fn record_failure(
+    pool: &PgPool,
+    email: &str,
+    now: DateTime<Utc>,
+    forget_before: DateTime<Utc>,
+) -> Result<i32, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO login_attempts (email, failures, last_failed_at)
+        VALUES (lower($1), 1, $2)
+        ON CONFLICT (email) DO UPDATE SET
+            failures = CASE
+                WHEN login_attempts.last_failed_at < $3 THEN 1
+                ELSE login_attempts.failures + 1
+            END,
+            last_failed_at = $2
+        RETURNING failures
+        "#,
+        email,
+        now,
+        forget_before
+    )
+    .fetch_one(pool)
+    .timed("login_attempt_repo::record_failure")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Make the next attempt wait until `retry_after`
+pub async fn set_retry_after(
+    pool: &PgPool,
+    email: &str,
+    retry_after: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE login_attempts SET retry_after = $2 WHERE email = lower($1)",
+        email,
+        retry_after
+    )
+    .execute(pool)
+    .timed("login_attempt_repo::set_retry_after")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Lock the address until the token hashing to `unlock_token_hash` is used
+///
+/// Returns false if it was locked already (its first token stays valid).
+pub async fn lock(
+    pool: &PgPool,
+    email: &str,
+    now: DateTime<Utc>,
+    unlock_token_hash: &str,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE login_attempts SET locked_at = $2, unlock_token_hash = $3
+        WHERE email = lower($1) AND locked_at IS NULL
+        "#,
+        email,
+        now,
+        unlock_token_hash
+    )
+    .execute(pool)
+    .timed("login_attempt_repo::lock")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Forget an address's failures (and unlock it)
+pub async fn clear(pool: &PgPool, email: &str) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM login_attempts WHERE email = lower($1)", email)
+        .execute(pool)
+        .timed("login_attempt_repo::clear")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Unlock the address the token hashing to `unlock_token_hash` was sent
+/// to; returns the address, or None if the token is unknown or used
+pub async fn unlock(pool: &PgPool, unlock_token_hash: &str) -> Result<Option<String>, AppError> {
+    sqlx::query_scalar!(
+        "DELETE FROM login_attempts WHERE unlock_token_hash = $1 RETURNING email",
+        unlock_token_hash
+    )
+    .fetch_optional(pool)
+    .timed("login_attempt_repo::unlock")
+    .await
+    .map_err(AppError::DatabaseError)
+}
@@ -0,0 +1,106 @@
+use crate::domain::ids;
+use crate::domain::models::{LoginEvent, LoginMethod};
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use crate::services::geoip::GeoLocation;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// LOGIN HISTORY REPOSITORY
+// ============================================================================
+// `login_events` (migration 026): one row per successful sign-in.
+
+/// Most sign-ins GET /me/logins returns
+pub const LOGIN_HISTORY_LIMIT: i64 = 50;
+
+/// Whether a sign-in comes from an IP address and browser (User-Agent)
+/// the user hasn't signed in from together before
+///
+/// The user's very first sign-in isn't new: there is nothing to compare
+/// it with, and they know they just created the account.
+pub async fn is_new_device(
+    pool: &PgPool,
+    user_id: Uuid,
+    ip_address: &str,
+    user_agent: Option<&str>,
+) -> Result<bool, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            EXISTS (SELECT 1 FROM login_events WHERE user_id = $1) as "any_logins!",
+            EXISTS (
+                SELECT 1 FROM login_events
+                WHERE user_id = $1
+                  AND ip_address = $2
+                  AND user_agent IS NOT DISTINCT FROM $3
+            ) as "known!"
+        "#,
+        user_id,
+        ip_address,
+        user_agent
+    )
+    .fetch_one(pool)
+    .timed("login_repo::is_new_device")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(row.any_logins && !row.known)
+}
+
+/// Record a sign-in
+pub async fn insert(
+    pool: &PgPool,
+    user_id: Uuid,
+    method: LoginMethod,
+    ip_address: &str,
+    user_agent: Option<&str>,
+    location: &GeoLocation,
+    new_device: bool,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO login_events (
+            id, user_id, method, ip_address, user_agent,
+            country_code, country, city, new_device
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        ids::new_id(),
+        user_id,
+        method.as_str(),
+        ip_address,
+        user_agent,
+        location.country_code,
+        location.country,
+        location.city,
+        new_device
+    )
+    .execute(pool)
+    .timed("login_repo::insert")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// The user's latest sign-ins, newest first
+pub async fn list_recent(pool: &PgPool, user_id: Uuid) -> Result<Vec<LoginEvent>, AppError> {
+    sqlx::query_as!(
+        LoginEvent,
+        r#"
+        SELECT id, method, ip_address, user_agent, country_code, country, city,
+               new_device, created_at
+        FROM login_events
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+        user_id,
+        LOGIN_HISTORY_LIMIT
+    )
+    .fetch_all(pool)
+    .timed("login_repo::list_recent")
+    .await
+    .map_err(AppError::DatabaseError)
+}
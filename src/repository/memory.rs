@@ -0,0 +1,559 @@
+use crate::domain::ids;
+use crate::domain::models::{
+    AccountStatus, Currency, Transaction, TransactionCursor, TransactionFilter, TransactionStatus,
+    User, UserRole, Wallet,
+};
+use crate::error::AppError;
+use crate::i18n::Locale;
+use crate::repository::{
+    ChosenLimits, DailyLimit, TransferOrder, TransferResult, UserRepository, WalletRepository,
+    WriteMode,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+// ============================================================================
+// IN-MEMORY REPOSITORY (feature "memory-repo")
+// ============================================================================
+// A `UserRepository` + `WalletRepository` that keeps everything in a
+// HashMap, for exercising the services without provisioning Postgres:
+//
+//   cargo test --features memory-repo
+//
+//   let repo = InMemoryRepository::new();
+//   let wallet = wallet_service::deposit(&repo, &email_service, &LogNotifier, user_id, amount, None).await?;
+//
+// It mirrors the Postgres behaviour the services rely on: unique emails,
+// soft-deleted users and their wallets being invisible, the balance floor
+// (overdraft limit 0 unless set) and newest-first keyset history. Every
+// method takes one lock, so each money movement is atomic like the SQL
+// transaction it stands in for (and never loses a race, whatever the
+// `WriteMode`).
+//
+// It only covers the trait surface. Sessions, onboarding, scheduled
+// transfers (an order's `scheduled` is ignored) and the dashboard summary
+// still go straight to Postgres, so the web server itself needs a database.
+
+#[derive(Debug)]
+struct StoredUser {
+    user: User,
+    role: UserRole,
+    account_status: AccountStatus,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+struct StoredWallet {
+    wallet: Wallet,
+    overdraft_limit: Decimal,
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    users: HashMap<Uuid, StoredUser>,
+    /// Keyed by user id, the primary wallet first
+    wallets: HashMap<Uuid, Vec<StoredWallet>>,
+    transactions: Vec<Transaction>,
+    limits: HashMap<Uuid, ChosenLimits>,
+}
+
+impl MemoryState {
+    fn active_user(&self, user_id: Uuid) -> Option<&StoredUser> {
+        self.users.get(&user_id).filter(|u| u.deleted_at.is_none())
+    }
+
+    /// An active (not deleted) user's wallet in `currency` (None = primary)
+    fn active_wallet(&mut self, user_id: Uuid, currency: Option<Currency>) -> Option<&mut StoredWallet> {
+        self.active_user(user_id)?;
+        let wallets = self.wallets.get_mut(&user_id)?;
+        match currency {
+            Some(currency) => wallets.iter_mut().find(|w| w.wallet.currency == currency),
+            None => wallets.first_mut(),
+        }
+    }
+
+    /// A wallet of an active user, by its own id
+    fn active_wallet_by_id(&mut self, wallet_id: Uuid) -> Option<&mut StoredWallet> {
+        let users = &self.users;
+        self.wallets
+            .values_mut()
+            .flatten()
+            .find(|w| w.wallet.id == wallet_id)
+            .filter(|w| {
+                let user_id = w.wallet.user_id.expect("memory wallets belong to users");
+                users.get(&user_id).is_some_and(|u| u.deleted_at.is_none())
+            })
+    }
+
+    fn insert_user(
+        &mut self,
+        email: &str,
+        password_hash: &str,
+        full_name: &str,
+    ) -> Result<User, AppError> {
+        // Like the UNIQUE constraint, deleted accounts still hold their email
+        if self.users.values().any(|u| u.user.email == email) {
+            return Err(AppError::UserAlreadyExists);
+        }
+
+        let now = Utc::now();
+        let user = User {
+            id: ids::new_id(),
+            email: email.to_string(),
+            password_hash: password_hash.to_string(),
+            full_name: full_name.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        self.users.insert(
+            user.id,
+            StoredUser {
+                user: user.clone(),
+                role: UserRole::User,
+                account_status: AccountStatus::Active,
+                deleted_at: None,
+            },
+        );
+
+        Ok(user)
+    }
+
+    fn insert_wallet(&mut self, user_id: Uuid, currency: Currency) -> Result<Wallet, AppError> {
+        if !self.users.contains_key(&user_id) {
+            return Err(AppError::internal("Cannot create wallet for this user"));
+        }
+        let wallets = self.wallets.entry(user_id).or_default();
+        if wallets.iter().any(|w| w.wallet.currency == currency) {
+            return Err(AppError::validation(&format!("You already have a {} wallet", currency)));
+        }
+
+        let now = Utc::now();
+        let wallet = Wallet {
+            id: ids::new_id(),
+            user_id: Some(user_id),
+            organization_id: None,
+            balance: Decimal::ZERO,
+            currency,
+            created_at: now,
+            updated_at: now,
+        };
+        wallets.push(StoredWallet {
+            wallet: wallet.clone(),
+            overdraft_limit: Decimal::ZERO,
+        });
+
+        Ok(wallet)
+    }
+
+    fn record(
+        &mut self,
+        wallet_id: Uuid,
+        transaction_type: &str,
+        amount: Decimal,
+        description: &str,
+        exchange_rate: Option<Decimal>,
+    ) {
+        let now = Utc::now();
+        self.transactions.push(Transaction {
+            id: ids::new_id(),
+            wallet_id,
+            transaction_type: transaction_type.to_string(),
+            amount,
+            description: Some(description.to_string()),
+            status: TransactionStatus::Completed.to_string(),
+            created_at: now,
+            completed_at: Some(now),
+            failed_at: None,
+            reversed_at: None,
+            exchange_rate,
+        });
+    }
+
+    /// What a wallet has paid out since `since`: withdrawals and sent
+    /// transfers, pending or completed
+    fn spent_since(&self, wallet_id: Uuid, since: DateTime<Utc>) -> Decimal {
+        self.transactions
+            .iter()
+            .filter(|tx| tx.wallet_id == wallet_id && tx.created_at >= since)
+            .filter(|tx| tx.status == "PENDING" || tx.status == "COMPLETED")
+            .filter(|tx| {
+                tx.transaction_type == "WITHDRAWAL"
+                    || (tx.transaction_type == "TRANSFER"
+                        && tx.description.as_deref() != Some("Transfer received"))
+            })
+            .map(|tx| tx.amount)
+            .sum()
+    }
+}
+
+/// Users, wallets and transactions held in memory
+#[derive(Debug, Default)]
+pub struct InMemoryRepository {
+    state: Mutex<MemoryState>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Let a user's balance go down to `-limit` (Postgres: wallets.overdraft_limit)
+    pub fn set_overdraft_limit(&self, user_id: Uuid, limit: Decimal) -> Result<(), AppError> {
+        let mut state = self.lock();
+        let stored = state
+            .wallets
+            .get_mut(&user_id)
+            .and_then(|wallets| wallets.first_mut())
+            .ok_or_else(|| AppError::not_found("Wallet"))?;
+        stored.overdraft_limit = limit;
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MemoryState> {
+        // A panic while holding the lock can't leave a half-applied change
+        // behind (every method validates before it mutates), so carry on
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait::async_trait]
+impl UserRepository for InMemoryRepository {
+    async fn create_user(
+        &self,
+        email: &str,
+        password_hash: &str,
+        full_name: &str,
+    ) -> Result<User, AppError> {
+        self.lock().insert_user(email, password_hash, full_name)
+    }
+
+    async fn create_user_with_wallet(
+        &self,
+        email: &str,
+        password_hash: &str,
+        full_name: &str,
+    ) -> Result<(User, Wallet), AppError> {
+        let mut state = self.lock();
+        let user = state.insert_user(email, password_hash, full_name)?;
+        let wallet = state.insert_wallet(user.id, Currency::Usd)?;
+        Ok((user, wallet))
+    }
+
+    async fn find_user_by_email(&self, email: &str) -> Result<User, AppError> {
+        self.lock()
+            .users
+            .values()
+            .find(|u| u.deleted_at.is_none() && u.user.email == email)
+            .map(|u| u.user.clone())
+            .ok_or_else(|| AppError::not_found("User"))
+    }
+
+    async fn find_user_by_id(&self, user_id: Uuid) -> Result<User, AppError> {
+        self.lock()
+            .active_user(user_id)
+            .map(|u| u.user.clone())
+            .ok_or_else(|| AppError::not_found("User"))
+    }
+
+    async fn find_deleted_user_by_email(&self, email: &str) -> Result<User, AppError> {
+        self.lock()
+            .users
+            .values()
+            .find(|u| u.deleted_at.is_some() && u.user.email == email)
+            .map(|u| u.user.clone())
+            .ok_or_else(|| AppError::not_found("User"))
+    }
+
+    async fn get_user_deleted_at(&self, user_id: Uuid) -> Result<Option<DateTime<Utc>>, AppError> {
+        self.lock()
+            .users
+            .get(&user_id)
+            .map(|u| u.deleted_at)
+            .ok_or_else(|| AppError::not_found("User"))
+    }
+
+    async fn soft_delete_user(&self, user_id: Uuid) -> Result<DateTime<Utc>, AppError> {
+        let mut state = self.lock();
+        let stored = state
+            .users
+            .get_mut(&user_id)
+            .filter(|u| u.deleted_at.is_none())
+            .ok_or_else(|| AppError::not_found("User"))?;
+
+        let now = Utc::now();
+        stored.deleted_at = Some(now);
+        stored.user.updated_at = now;
+        Ok(now)
+    }
+
+    async fn restore_user(&self, user_id: Uuid) -> Result<User, AppError> {
+        let mut state = self.lock();
+        let stored = state
+            .users
+            .get_mut(&user_id)
+            .filter(|u| u.deleted_at.is_some())
+            .ok_or_else(|| AppError::not_found("Deleted user"))?;
+
+        stored.deleted_at = None;
+        stored.user.updated_at = Utc::now();
+        Ok(stored.user.clone())
+    }
+
+    async fn get_user_role(&self, user_id: Uuid) -> Result<UserRole, AppError> {
+        self.lock()
+            .active_user(user_id)
+            .map(|u| u.role)
+            .ok_or_else(|| AppError::not_found("User"))
+    }
+
+    async fn set_user_role(&self, user_id: Uuid, role: UserRole) -> Result<(), AppError> {
+        // (a no-op for unknown or deleted users, like the UPDATE)
+        if let Some(stored) = self
+            .lock()
+            .users
+            .get_mut(&user_id)
+            .filter(|u| u.deleted_at.is_none())
+        {
+            stored.role = role;
+            stored.user.updated_at = Utc::now();
+        }
+        Ok(())
+    }
+
+    async fn get_account_status(&self, user_id: Uuid) -> Result<AccountStatus, AppError> {
+        self.lock()
+            .active_user(user_id)
+            .map(|u| u.account_status)
+            .ok_or_else(|| AppError::not_found("User"))
+    }
+
+    async fn set_account_status(&self, user_id: Uuid, status: AccountStatus) -> Result<(), AppError> {
+        let mut state = self.lock();
+        let stored = state
+            .users
+            .get_mut(&user_id)
+            .filter(|u| u.deleted_at.is_none())
+            .ok_or_else(|| AppError::not_found("User"))?;
+
+        stored.account_status = status;
+        stored.user.updated_at = Utc::now();
+        Ok(())
+    }
+
+    async fn preferred_locale(&self, _user_id: Uuid) -> Result<Locale, AppError> {
+        // (nobody can choose one here)
+        Ok(Locale::default())
+    }
+}
+
+#[async_trait::async_trait]
+impl WalletRepository for InMemoryRepository {
+    async fn create_wallet(&self, user_id: Uuid) -> Result<Wallet, AppError> {
+        self.lock().insert_wallet(user_id, Currency::Usd)
+    }
+
+    async fn create_currency_wallet(&self, user_id: Uuid, currency: Currency) -> Result<Wallet, AppError> {
+        let mut state = self.lock();
+        if state.active_wallet(user_id, None).is_none() {
+            return Err(AppError::not_found("Wallet"));
+        }
+        state.insert_wallet(user_id, currency)
+    }
+
+    async fn get_wallet_by_user_id(&self, user_id: Uuid) -> Result<Wallet, AppError> {
+        self.lock()
+            .active_wallet(user_id, None)
+            .map(|w| w.wallet.clone())
+            .ok_or_else(|| AppError::not_found("Wallet"))
+    }
+
+    async fn list_wallets(&self, user_id: Uuid) -> Result<Vec<Wallet>, AppError> {
+        let state = self.lock();
+        if state.active_user(user_id).is_none() {
+            return Ok(Vec::new());
+        }
+
+        let mut wallets: Vec<Wallet> = state
+            .wallets
+            .get(&user_id)
+            .map(|wallets| wallets.iter().map(|w| w.wallet.clone()).collect())
+            .unwrap_or_default();
+        // Primary first, like ORDER BY is_primary DESC, currency
+        if let Some((_, others)) = wallets.split_first_mut() {
+            others.sort_by_key(|w| w.currency.as_str());
+        }
+        Ok(wallets)
+    }
+
+    async fn deposit(
+        &self,
+        user_id: Uuid,
+        currency: Option<Currency>,
+        amount: Decimal,
+    ) -> Result<Wallet, AppError> {
+        let mut state = self.lock();
+        let stored = state
+            .active_wallet(user_id, currency)
+            .ok_or_else(|| AppError::not_found("Wallet"))?;
+
+        stored.wallet.balance += amount;
+        stored.wallet.updated_at = Utc::now();
+        let wallet = stored.wallet.clone();
+
+        state.record(wallet.id, "DEPOSIT", amount, "Deposit funds", None);
+        Ok(wallet)
+    }
+
+    async fn withdraw(
+        &self,
+        user_id: Uuid,
+        currency: Option<Currency>,
+        amount: Decimal,
+        daily_limit: Option<DailyLimit>,
+    ) -> Result<Wallet, AppError> {
+        let mut state = self.lock();
+        let stored = state
+            .active_wallet(user_id, currency)
+            .ok_or_else(|| AppError::not_found("Wallet"))?;
+        let (wallet_id, wallet_currency) = (stored.wallet.id, stored.wallet.currency);
+        if let Some(daily_limit) = daily_limit {
+            daily_limit.check(state.spent_since(wallet_id, daily_limit.since), amount, wallet_currency)?;
+        }
+
+        let stored = state.active_wallet(user_id, currency).expect("checked above");
+        if stored.wallet.balance - amount < -stored.overdraft_limit {
+            return Err(AppError::InsufficientBalance);
+        }
+        stored.wallet.balance -= amount;
+        stored.wallet.updated_at = Utc::now();
+        let wallet = stored.wallet.clone();
+
+        state.record(wallet.id, "WITHDRAWAL", amount, "Withdraw funds", None);
+        Ok(wallet)
+    }
+
+    async fn transfer(
+        &self,
+        order: &TransferOrder,
+        _mode: WriteMode,
+    ) -> Result<Option<TransferResult>, AppError> {
+        let mut state = self.lock();
+        let amount = order.amount;
+
+        // Check both sides before touching either balance
+        let sender = state
+            .active_wallet_by_id(order.sender_wallet_id)
+            .ok_or_else(|| AppError::not_found("Sender wallet"))?;
+        let (sender_id, sender_currency) = (sender.wallet.id, sender.wallet.currency);
+        if let Some(daily_limit) = order.daily_limit {
+            daily_limit.check(state.spent_since(sender_id, daily_limit.since), amount, sender_currency)?;
+        }
+        let sender = state.active_wallet_by_id(order.sender_wallet_id).expect("checked above");
+        if sender.wallet.balance - amount < -sender.overdraft_limit {
+            return Err(AppError::InsufficientBalance);
+        }
+        let sender_currency = sender.wallet.currency;
+        let recipient = state
+            .active_wallet_by_id(order.recipient_wallet_id)
+            .ok_or_else(|| AppError::not_found("Recipient wallet"))?;
+        let (recipient_amount, exchange_rate) = match order.exchange_rate {
+            _ if recipient.wallet.currency == sender_currency => (amount, None),
+            Some(rate) => ((amount * rate).round_dp(2), Some(rate)),
+            None => {
+                return Err(AppError::currency_mismatch(sender_currency, recipient.wallet.currency))
+            }
+        };
+        if recipient_amount <= Decimal::ZERO {
+            return Err(AppError::validation("Amount is too small to convert"));
+        }
+
+        let now = Utc::now();
+
+        let sender = state.active_wallet_by_id(order.sender_wallet_id).expect("checked above");
+        sender.wallet.balance -= amount;
+        sender.wallet.updated_at = now;
+        let sender_wallet = sender.wallet.clone();
+
+        let recipient = state.active_wallet_by_id(order.recipient_wallet_id).expect("checked above");
+        recipient.wallet.balance += recipient_amount;
+        recipient.wallet.updated_at = now;
+        let recipient_wallet = recipient.wallet.clone();
+
+        state.record(sender_wallet.id, "TRANSFER", amount, "Transfer sent", exchange_rate);
+        state.record(
+            recipient_wallet.id,
+            "TRANSFER",
+            recipient_amount,
+            "Transfer received",
+            exchange_rate,
+        );
+
+        Ok(Some(TransferResult {
+            sender_wallet,
+            recipient_amount,
+            recipient_currency: recipient_wallet.currency,
+            recipient_balance: recipient_wallet.balance,
+        }))
+    }
+
+    async fn list_transactions(
+        &self,
+        wallet_id: Uuid,
+        filter: &TransactionFilter,
+        after: Option<TransactionCursor>,
+        limit: u32,
+    ) -> Result<Vec<Transaction>, AppError> {
+        let state = self.lock();
+
+        let mut transactions: Vec<Transaction> = state
+            .transactions
+            .iter()
+            .filter(|tx| tx.wallet_id == wallet_id && matches_filter(tx, filter))
+            .filter(|tx| after.is_none_or(|c| (tx.created_at, tx.id) < (c.created_at, c.id)))
+            .cloned()
+            .collect();
+
+        transactions.sort_by_key(|tx| Reverse((tx.created_at, tx.id)));
+        transactions.truncate(limit as usize);
+        Ok(transactions)
+    }
+
+    async fn count_transactions(&self, wallet_id: Uuid, filter: &TransactionFilter) -> Result<u64, AppError> {
+        let state = self.lock();
+        Ok(state
+            .transactions
+            .iter()
+            .filter(|tx| tx.wallet_id == wallet_id && matches_filter(tx, filter))
+            .count() as u64)
+    }
+
+    async fn chosen_limits(&self, user_id: Uuid) -> Result<ChosenLimits, AppError> {
+        Ok(self.lock().limits.get(&user_id).copied().unwrap_or_default())
+    }
+
+    async fn set_chosen_limits(&self, user_id: Uuid, limits: &ChosenLimits) -> Result<(), AppError> {
+        self.lock().limits.insert(user_id, *limits);
+        Ok(())
+    }
+}
+
+/// Whether a transaction passes a history filter. Text matching is
+/// simpler than Postgres full-text search: every word of the query has to
+/// appear in the description (ignoring case).
+fn matches_filter(tx: &Transaction, filter: &TransactionFilter) -> bool {
+    let description = tx.description.as_deref().unwrap_or_default().to_lowercase();
+
+    filter.transaction_type.as_deref().is_none_or(|t| tx.transaction_type == t)
+        && filter.status.is_none_or(|s| tx.status == s.as_str())
+        && filter.from.is_none_or(|from| tx.created_at >= from)
+        && filter.until.is_none_or(|until| tx.created_at < until)
+        && filter.min_amount.is_none_or(|min| tx.amount >= min)
+        && filter.max_amount.is_none_or(|max| tx.amount <= max)
+        && filter.text.as_deref().is_none_or(|text| {
+            text.split_whitespace().all(|word| description.contains(&word.to_lowercase()))
+        })
+}
@@ -0,0 +1,169 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+// ============================================================================
+// QUERY METRICS
+// ============================================================================
+// Every repository query is tagged with a name and timed:
+//
+//   sqlx::query_as!(...)
+//       .fetch_one(pool)
+//       .timed("user_repo::find_user_by_id")
+//       .await
+//
+// Why do we need this?
+// - A latency histogram per query shows which ones get slow as tables grow
+//   (GET /api/admin/metrics/queries)
+// - Queries slower than SLOW_QUERY_THRESHOLD_MS are logged as warnings
+//
+// The slow-query warning only names the query; it never includes the bound
+// values (emails, amounts...). sqlx logs the SQL of the same slow statement
+// (see `create_db_pool`), with `$1`-style placeholders in place of values.
+
+/// Upper bounds (in milliseconds) of the histogram buckets
+pub const LATENCY_BUCKETS_MS: [u64; 10] = [1, 5, 10, 25, 50, 100, 250, 500, 1000, 2500];
+
+/// Default for SLOW_QUERY_THRESHOLD_MS
+pub const DEFAULT_SLOW_QUERY_THRESHOLD_MS: u64 = 500;
+
+static SLOW_QUERY_THRESHOLD_MS: AtomicU64 = AtomicU64::new(DEFAULT_SLOW_QUERY_THRESHOLD_MS);
+
+static QUERIES: Mutex<BTreeMap<&'static str, Histogram>> = Mutex::new(BTreeMap::new());
+
+/// Log queries that take at least this long (set once at startup)
+pub fn set_slow_query_threshold(threshold: Duration) {
+    let millis = u64::try_from(threshold.as_millis()).unwrap_or(u64::MAX);
+    SLOW_QUERY_THRESHOLD_MS.store(millis, Ordering::Relaxed);
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// One count per bucket in LATENCY_BUCKETS_MS, plus one for "slower"
+    buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl Histogram {
+    fn record(&mut self, elapsed: Duration) {
+        let millis = elapsed.as_secs_f64() * 1000.0;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&bound| millis <= bound as f64)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+}
+
+/// Latency figures for one query
+#[derive(Debug, Serialize)]
+pub struct QueryStats {
+    pub query: &'static str,
+    pub count: u64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+    pub buckets: Vec<LatencyBucket>,
+}
+
+/// How many calls finished within `le_ms` (None = slower than every bound)
+///
+/// Counts are per bucket, not cumulative.
+#[derive(Debug, Serialize)]
+pub struct LatencyBucket {
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+/// Stats for every query run since startup, by name
+pub fn snapshot() -> Vec<QueryStats> {
+    let queries = QUERIES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    queries
+        .iter()
+        .map(|(&query, histogram)| QueryStats {
+            query,
+            count: histogram.count,
+            mean_ms: as_millis(histogram.total) / histogram.count.max(1) as f64,
+            max_ms: as_millis(histogram.max),
+            buckets: LATENCY_BUCKETS_MS
+                .iter()
+                .map(|&bound| Some(bound))
+                .chain([None])
+                .zip(histogram.buckets)
+                .map(|(le_ms, count)| LatencyBucket { le_ms, count })
+                .collect(),
+        })
+        .collect()
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn record(query: &'static str, elapsed: Duration) {
+    QUERIES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .entry(query)
+        .or_default()
+        .record(elapsed);
+
+    let threshold = SLOW_QUERY_THRESHOLD_MS.load(Ordering::Relaxed);
+    if elapsed >= Duration::from_millis(threshold) {
+        tracing::warn!(
+            query,
+            elapsed_ms = elapsed.as_millis() as u64,
+            threshold_ms = threshold,
+            "🐢 Slow query (parameters redacted)"
+        );
+    }
+}
+
+// ============================================================================
+// TIMING WRAPPER
+// ============================================================================
+
+/// Adds `.timed(name)` to query futures
+pub trait TimedQuery: Future + Sized {
+    /// Record how long this query takes under `name`
+    fn timed(self, name: &'static str) -> Timed<Self> {
+        Timed {
+            query: Box::pin(self),
+            name,
+            started: None,
+        }
+    }
+}
+
+impl<F: Future> TimedQuery for F {}
+
+/// A query future that records its latency when it completes
+pub struct Timed<F> {
+    query: Pin<Box<F>>,
+    name: &'static str,
+    /// Set on the first poll, when the query actually starts
+    started: Option<Instant>,
+}
+
+impl<F: Future> Future for Timed<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let started = *self.started.get_or_insert_with(Instant::now);
+
+        let output = std::task::ready!(self.query.as_mut().poll(cx));
+        record(self.name, started.elapsed());
+        Poll::Ready(output)
+    }
+}
@@ -1 +1,41 @@
 pub mod user_repo;
+pub mod wallet_repo;
+pub mod onboarding_repo;
+pub mod session_repo;
+pub mod dashboard_repo;
+pub mod email_queue_repo;
+pub mod ledger_repo;
+pub mod login_repo;
+pub mod login_attempt_repo;
+pub mod notification_repo;
+pub mod accounting_repo;
+pub mod archive_repo;
+pub mod audit_repo;
+pub mod bank_repo;
+pub mod card_repo;
+pub mod international_repo;
+pub mod crypto_repo;
+pub mod open_banking_repo;
+pub mod search_repo;
+pub mod organization_repo;
+pub mod password_reset_repo;
+pub mod payment_request_repo;
+pub mod pending_transfer_repo;
+pub mod referral_repo;
+pub mod scheduled_transfer_repo;
+pub mod sso_repo;
+pub mod statement_repo;
+pub mod tax_repo;
+pub mod token_repo;
+pub mod transaction_repo;
+pub mod metrics;
+pub mod unit_of_work;
+#[cfg(feature = "memory-repo")]
+pub mod memory;
+
+pub use user_repo::UserRepository;
+pub use wallet_repo::{
+    ChosenLimits, DailyLimit, ScheduledRun, TransferOrder, TransferResult, WalletRepository, WriteMode,
+};
+#[cfg(feature = "memory-repo")]
+pub use memory::InMemoryRepository;
@@ -0,0 +1,104 @@
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// NOTIFICATION REPOSITORY
+// ============================================================================
+// The notification inbox (migration 029): every live notification a user
+// was sent, numbered per user so a reconnecting client can ask for what it
+// missed.
+
+/// One notification in a user's inbox
+#[derive(Debug, Clone)]
+pub struct StoredNotification {
+    pub sequence: i64,
+    /// JSON text, without the sequence
+    pub payload: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Put a notification in the user's inbox under their next sequence number
+pub async fn insert(
+    pool: &PgPool,
+    user_id: Uuid,
+    payload: &str,
+) -> Result<StoredNotification, AppError> {
+    sqlx::query_as!(
+        StoredNotification,
+        r#"
+        WITH next AS (
+            INSERT INTO notification_sequences (user_id, last_sequence)
+            VALUES ($1, 1)
+            ON CONFLICT (user_id)
+            DO UPDATE SET last_sequence = notification_sequences.last_sequence + 1
+            RETURNING last_sequence
+        )
+        INSERT INTO notifications (user_id, sequence, payload)
+        SELECT $1, last_sequence, $2 FROM next
+        RETURNING sequence, payload, created_at
+        "#,
+        user_id,
+        payload
+    )
+    .fetch_one(pool)
+    .timed("notification_repo::insert")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Up to `limit` of the user's notifications after `since`, oldest first
+pub async fn list_since(
+    pool: &PgPool,
+    user_id: Uuid,
+    since: i64,
+    limit: i64,
+) -> Result<Vec<StoredNotification>, AppError> {
+    sqlx::query_as!(
+        StoredNotification,
+        r#"
+        SELECT sequence, payload, created_at
+        FROM notifications
+        WHERE user_id = $1 AND sequence > $2
+        ORDER BY sequence
+        LIMIT $3
+        "#,
+        user_id,
+        since,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("notification_repo::list_since")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// The user's latest sequence number (0 before their first notification)
+pub async fn latest_sequence(pool: &PgPool, user_id: Uuid) -> Result<i64, AppError> {
+    let latest = sqlx::query_scalar!(
+        "SELECT last_sequence FROM notification_sequences WHERE user_id = $1",
+        user_id
+    )
+    .fetch_optional(pool)
+    .timed("notification_repo::latest_sequence")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(latest.unwrap_or(0))
+}
+
+/// Delete notifications older than `days`; sequence numbers carry on
+pub async fn delete_older_than(pool: &PgPool, days: i32) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM notifications WHERE created_at < NOW() - make_interval(days => $1)",
+        days
+    )
+    .execute(pool)
+    .timed("notification_repo::delete_older_than")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,194 @@
+use crate::domain::models::{OnboardingProgress, OnboardingStep};
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// ONBOARDING REPOSITORY
+// ============================================================================
+
+/// Start onboarding for a freshly registered user
+pub async fn create_progress(
+    pool: &PgPool,
+    user_id: Uuid,
+    verification_code: &str,
+    verification_expires_at: DateTime<Utc>,
+) -> Result<OnboardingProgress, AppError> {
+    let progress = sqlx::query_as!(
+        OnboardingProgress,
+        r#"
+        INSERT INTO onboarding_progress (user_id, verification_code, verification_expires_at)
+        VALUES ($1, $2, $3)
+        RETURNING user_id, current_step, verification_code, verification_expires_at,
+                  email_verified_at, notify_email, notify_realtime, two_factor_opt_in,
+                  completed_at, created_at, updated_at
+        "#,
+        user_id,
+        verification_code,
+        verification_expires_at
+    )
+    .fetch_one(pool)
+    .timed("onboarding_repo::create_progress")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(progress)
+}
+
+/// Get a user's onboarding progress
+///
+/// Returns `None` for users who never went through onboarding
+/// (e.g. accounts created before the wizard existed).
+pub async fn find_progress(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<OnboardingProgress>, AppError> {
+    let progress = sqlx::query_as!(
+        OnboardingProgress,
+        r#"
+        SELECT user_id, current_step, verification_code, verification_expires_at,
+               email_verified_at, notify_email, notify_realtime, two_factor_opt_in,
+               completed_at, created_at, updated_at
+        FROM onboarding_progress
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .timed("onboarding_repo::find_progress")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(progress)
+}
+
+/// Replace the pending verification code (used by "resend code")
+pub async fn set_verification_code(
+    pool: &PgPool,
+    user_id: Uuid,
+    verification_code: &str,
+    verification_expires_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE onboarding_progress
+        SET verification_code = $1, verification_expires_at = $2
+        WHERE user_id = $3
+        "#,
+        verification_code,
+        verification_expires_at,
+        user_id
+    )
+    .execute(pool)
+    .timed("onboarding_repo::set_verification_code")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Mark the email as verified and move on to the next step
+pub async fn mark_email_verified(
+    pool: &PgPool,
+    user_id: Uuid,
+    next_step: OnboardingStep,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE onboarding_progress
+        SET email_verified_at = NOW(),
+            verification_code = NULL,
+            verification_expires_at = NULL,
+            current_step = $1
+        WHERE user_id = $2
+        "#,
+        next_step.as_str(),
+        user_id
+    )
+    .execute(pool)
+    .timed("onboarding_repo::mark_email_verified")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Save notification preferences and move on to the next step
+pub async fn set_notification_prefs(
+    pool: &PgPool,
+    user_id: Uuid,
+    notify_email: bool,
+    notify_realtime: bool,
+    next_step: OnboardingStep,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE onboarding_progress
+        SET notify_email = $1, notify_realtime = $2, current_step = $3
+        WHERE user_id = $4
+        "#,
+        notify_email,
+        notify_realtime,
+        next_step.as_str(),
+        user_id
+    )
+    .execute(pool)
+    .timed("onboarding_repo::set_notification_prefs")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Save the two-factor choice and move on to the next step
+pub async fn set_two_factor_opt_in(
+    pool: &PgPool,
+    user_id: Uuid,
+    opt_in: bool,
+    next_step: OnboardingStep,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE onboarding_progress
+        SET two_factor_opt_in = $1, current_step = $2
+        WHERE user_id = $3
+        "#,
+        opt_in,
+        next_step.as_str(),
+        user_id
+    )
+    .execute(pool)
+    .timed("onboarding_repo::set_two_factor_opt_in")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Move to a step without changing anything else
+///
+/// Reaching `Complete` also stamps `completed_at`.
+pub async fn set_step(
+    pool: &PgPool,
+    user_id: Uuid,
+    step: OnboardingStep,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE onboarding_progress
+        SET current_step = $1::VARCHAR,
+            completed_at = CASE WHEN $1::VARCHAR = 'COMPLETE' THEN NOW() ELSE completed_at END
+        WHERE user_id = $2
+        "#,
+        step.as_str(),
+        user_id
+    )
+    .execute(pool)
+    .timed("onboarding_repo::set_step")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
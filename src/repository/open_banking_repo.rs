@@ -0,0 +1,338 @@
+use crate::domain::ids;
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// OPEN BANKING REPOSITORY
+// ============================================================================
+// Third-party clients, the consents users give them and the access tokens
+// issued against those consents (migration 021).
+//
+// Token lookups do all the checking in one query: a token only counts
+// while it is unexpired, its client isn't revoked and (for consent-bound
+// tokens) its consent is still AUTHORISED and unexpired. Revoking a
+// client or a consent therefore cuts off every token at once, with no
+// token bookkeeping.
+
+/// A registered third party
+#[derive(Debug, Clone)]
+pub struct OpenBankingClient {
+    pub id: Uuid,
+    pub name: String,
+    pub client_id: String,
+    /// Scope names its consents may ask for
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What the token endpoint needs to check a client's credentials
+#[derive(Debug, Clone)]
+pub struct ClientCredentials {
+    pub id: Uuid,
+    /// SHA-256 hex of the client secret
+    pub client_secret_hash: String,
+}
+
+/// A consent, with the client it was given to
+#[derive(Debug, Clone)]
+pub struct Consent {
+    pub id: Uuid,
+    /// The client's row id (`open_banking_clients.id`)
+    pub client_id: Uuid,
+    /// The client's public id and name
+    pub client_public_id: String,
+    pub client_name: String,
+    /// None until the user answers
+    pub user_id: Option<Uuid>,
+    pub scopes: Vec<String>,
+    /// "AWAITING_AUTHORISATION", "AUTHORISED", "REJECTED" or "REVOKED"
+    pub status: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub authorised_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// What a valid access token grants
+#[derive(Debug, Clone)]
+pub struct TokenGrant {
+    pub client_id: Uuid,
+    /// None for tokens that can only manage consents
+    pub consent_id: Option<Uuid>,
+    /// The user who authorised the consent
+    pub user_id: Option<Uuid>,
+    pub scopes: Vec<String>,
+}
+
+/// Register a client; the secret is already hashed
+pub async fn create_client(
+    pool: &PgPool,
+    name: &str,
+    client_id: &str,
+    client_secret_hash: &str,
+    scopes: &[String],
+) -> Result<OpenBankingClient, AppError> {
+    sqlx::query_as!(
+        OpenBankingClient,
+        r#"
+        INSERT INTO open_banking_clients (id, name, client_id, client_secret_hash, scopes)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, name, client_id, scopes, created_at
+        "#,
+        ids::new_id(),
+        name,
+        client_id,
+        client_secret_hash,
+        scopes
+    )
+    .fetch_one(pool)
+    .timed("open_banking_repo::create_client")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Revoke a client, cutting off all of its tokens
+///
+/// Returns false if there is no such (unrevoked) client.
+pub async fn revoke_client(pool: &PgPool, id: Uuid) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE open_banking_clients
+        SET revoked_at = NOW()
+        WHERE id = $1 AND revoked_at IS NULL
+        "#,
+        id
+    )
+    .execute(pool)
+    .timed("open_banking_repo::revoke_client")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// An unrevoked client by its row id
+pub async fn find_client_by_id(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<OpenBankingClient>, AppError> {
+    sqlx::query_as!(
+        OpenBankingClient,
+        r#"
+        SELECT id, name, client_id, scopes, created_at
+        FROM open_banking_clients
+        WHERE id = $1 AND revoked_at IS NULL
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .timed("open_banking_repo::find_client_by_id")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// The credentials of an unrevoked client, by its public id
+pub async fn find_client_credentials(
+    pool: &PgPool,
+    client_id: &str,
+) -> Result<Option<ClientCredentials>, AppError> {
+    sqlx::query_as!(
+        ClientCredentials,
+        r#"
+        SELECT id, client_secret_hash
+        FROM open_banking_clients
+        WHERE client_id = $1 AND revoked_at IS NULL
+        "#,
+        client_id
+    )
+    .fetch_optional(pool)
+    .timed("open_banking_repo::find_client_credentials")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Create a consent awaiting the user's answer
+pub async fn create_consent(
+    pool: &PgPool,
+    client_id: Uuid,
+    scopes: &[String],
+    expires_at: DateTime<Utc>,
+) -> Result<Consent, AppError> {
+    let id = ids::new_id();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO open_banking_consents (id, client_id, scopes, expires_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        id,
+        client_id,
+        scopes,
+        expires_at
+    )
+    .execute(pool)
+    .timed("open_banking_repo::create_consent")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    find_consent(pool, id)
+        .await?
+        .ok_or_else(|| AppError::internal("Created consent disappeared"))
+}
+
+/// A consent by id
+pub async fn find_consent(pool: &PgPool, id: Uuid) -> Result<Option<Consent>, AppError> {
+    sqlx::query_as!(
+        Consent,
+        r#"
+        SELECT c.id, c.client_id, cl.client_id AS client_public_id, cl.name AS client_name,
+               c.user_id, c.scopes, c.status, c.expires_at, c.created_at,
+               c.authorised_at, c.revoked_at
+        FROM open_banking_consents c
+        JOIN open_banking_clients cl ON cl.id = c.client_id
+        WHERE c.id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .timed("open_banking_repo::find_consent")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Every consent a user has answered, newest first
+pub async fn list_user_consents(pool: &PgPool, user_id: Uuid) -> Result<Vec<Consent>, AppError> {
+    sqlx::query_as!(
+        Consent,
+        r#"
+        SELECT c.id, c.client_id, cl.client_id AS client_public_id, cl.name AS client_name,
+               c.user_id, c.scopes, c.status, c.expires_at, c.created_at,
+               c.authorised_at, c.revoked_at
+        FROM open_banking_consents c
+        JOIN open_banking_clients cl ON cl.id = c.client_id
+        WHERE c.user_id = $1
+        ORDER BY c.created_at DESC, c.id DESC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .timed("open_banking_repo::list_user_consents")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Record the user's answer to an awaiting, unexpired consent
+///
+/// `authorised` picks AUTHORISED or REJECTED. Returns false if the consent
+/// was already answered or has expired.
+pub async fn answer_consent(
+    pool: &PgPool,
+    id: Uuid,
+    user_id: Uuid,
+    authorised: bool,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE open_banking_consents
+        SET user_id = $2,
+            status = CASE WHEN $3::BOOLEAN THEN 'AUTHORISED' ELSE 'REJECTED' END,
+            authorised_at = CASE WHEN $3::BOOLEAN THEN NOW() END
+        WHERE id = $1
+          AND status = 'AWAITING_AUTHORISATION'
+          AND expires_at > NOW()
+        "#,
+        id,
+        user_id,
+        authorised
+    )
+    .execute(pool)
+    .timed("open_banking_repo::answer_consent")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Revoke a consent that isn't already rejected or revoked
+///
+/// Returns false if there was nothing to revoke.
+pub async fn revoke_consent(pool: &PgPool, id: Uuid) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE open_banking_consents
+        SET status = 'REVOKED', revoked_at = NOW()
+        WHERE id = $1 AND status IN ('AWAITING_AUTHORISATION', 'AUTHORISED')
+        "#,
+        id
+    )
+    .execute(pool)
+    .timed("open_banking_repo::revoke_consent")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Store a newly issued access token (by its hash)
+pub async fn insert_token(
+    pool: &PgPool,
+    token_hash: &str,
+    client_id: Uuid,
+    consent_id: Option<Uuid>,
+    scopes: &[String],
+    expires_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO open_banking_tokens (token_hash, client_id, consent_id, scopes, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        token_hash,
+        client_id,
+        consent_id,
+        scopes,
+        expires_at
+    )
+    .execute(pool)
+    .timed("open_banking_repo::insert_token")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// What the token with this hash grants, if it is still valid
+pub async fn find_token(pool: &PgPool, token_hash: &str) -> Result<Option<TokenGrant>, AppError> {
+    sqlx::query_as!(
+        TokenGrant,
+        r#"
+        SELECT t.client_id, t.consent_id, c.user_id AS "user_id?", t.scopes
+        FROM open_banking_tokens t
+        JOIN open_banking_clients cl ON cl.id = t.client_id
+        LEFT JOIN open_banking_consents c ON c.id = t.consent_id
+        WHERE t.token_hash = $1
+          AND t.expires_at > NOW()
+          AND cl.revoked_at IS NULL
+          AND (t.consent_id IS NULL OR (c.status = 'AUTHORISED' AND c.expires_at > NOW()))
+        "#,
+        token_hash
+    )
+    .fetch_optional(pool)
+    .timed("open_banking_repo::find_token")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Delete expired access tokens; returns how many were removed
+pub async fn delete_expired_tokens(pool: &PgPool) -> Result<u64, AppError> {
+    let result = sqlx::query!("DELETE FROM open_banking_tokens WHERE expires_at <= NOW()")
+        .execute(pool)
+        .timed("open_banking_repo::delete_expired_tokens")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,857 @@
+use crate::domain::ids;
+use crate::domain::models::{
+    Currency, ExpenseRequest, ExpenseStatus, Organization, OrganizationInvitation,
+    OrganizationMember, OrganizationRole, Wallet,
+};
+use crate::error::AppError;
+use crate::repository::ledger_repo::{Journal, LedgerAccount};
+use crate::repository::metrics::TimedQuery;
+use crate::repository::unit_of_work::with_transaction;
+use crate::repository::wallet_repo::{self, LockedWallet, TransferResult};
+use crate::repository::sso_repo;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+// ============================================================================
+// ORGANIZATION REPOSITORY
+// ============================================================================
+// Business accounts (migration 030): organization members, invitations and
+// the organization's wallet, and expense requests against it (migration
+// 031).
+//
+// Money moves like it does for users, except that deposits and withdrawals
+// take the row lock (FOR UPDATE) and check the balance themselves instead
+// of leaving it to one UPDATE and the CHECK constraint: an organization's
+// wallet sees a handful of people, not a busy hot path.
+//
+// Changes to the member list lock the organization's member rows first,
+// so two owners can't both step down at once and leave nobody in charge.
+
+/// Create an organization with `owner_id` as its owner, and its USD wallet
+pub async fn create_business_account(
+    pool: &PgPool,
+    name: &str,
+    slug: &str,
+    owner_id: Uuid,
+) -> Result<Organization, AppError> {
+    with_transaction(pool, async |conn| {
+        let organization = sso_repo::create_organization(&mut *conn, name, slug).await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO organization_members (organization_id, user_id, role)
+            VALUES ($1, $2, 'OWNER')
+            "#,
+            organization.id,
+            owner_id
+        )
+        .execute(&mut *conn)
+        .timed("organization_repo::create_business_account.owner")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO wallets (id, organization_id, balance, currency)
+            VALUES ($1, $2, 0.00, 'USD')
+            "#,
+            ids::new_id(),
+            organization.id
+        )
+        .execute(&mut *conn)
+        .timed("organization_repo::create_business_account.wallet")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(organization)
+    })
+    .await
+}
+
+// ============================================================================
+// MEMBERS
+// ============================================================================
+
+/// The organizations a user belongs to, with their role in each
+pub async fn list_memberships(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<(Organization, OrganizationRole)>, AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT o.id, o.name, o.slug, o.created_at, m.role
+        FROM organization_members m
+        JOIN organizations o ON o.id = m.organization_id
+        WHERE m.user_id = $1
+        ORDER BY o.name
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .timed("organization_repo::list_memberships")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    rows.into_iter()
+        .map(|row| {
+            let organization = Organization {
+                id: row.id,
+                name: row.name,
+                slug: row.slug,
+                created_at: row.created_at,
+            };
+            Ok((organization, parse_role(&row.role)?))
+        })
+        .collect()
+}
+
+/// A user's role in an organization (None if they aren't a member)
+pub async fn find_role(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<OrganizationRole>, AppError> {
+    let role = sqlx::query_scalar!(
+        r#"
+        SELECT role
+        FROM organization_members
+        WHERE organization_id = $1 AND user_id = $2
+        "#,
+        organization_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .timed("organization_repo::find_role")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    role.as_deref().map(parse_role).transpose()
+}
+
+/// Everyone in an organization, owners first
+pub async fn list_members(
+    pool: &PgPool,
+    organization_id: Uuid,
+) -> Result<Vec<OrganizationMember>, AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT m.user_id, u.email, u.full_name, m.role, m.created_at
+        FROM organization_members m
+        JOIN users u ON u.id = m.user_id
+        WHERE m.organization_id = $1
+        ORDER BY CASE m.role WHEN 'OWNER' THEN 0 WHEN 'FINANCE' THEN 1 ELSE 2 END, u.email
+        "#,
+        organization_id
+    )
+    .fetch_all(pool)
+    .timed("organization_repo::list_members")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(OrganizationMember {
+                user_id: row.user_id,
+                email: row.email,
+                full_name: row.full_name,
+                role: parse_role(&row.role)?,
+                joined_at: row.created_at,
+            })
+        })
+        .collect()
+}
+
+/// Change a member's role
+///
+/// Fails with `NotFound` if they aren't a member, and refuses to demote
+/// the last owner.
+pub async fn set_role(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    role: OrganizationRole,
+) -> Result<(), AppError> {
+    with_transaction(pool, async |conn| {
+        let members = lock_members(conn, organization_id).await?;
+        ensure_owner_remains(&members, user_id, Some(role))?;
+
+        sqlx::query!(
+            r#"
+            UPDATE organization_members
+            SET role = $3
+            WHERE organization_id = $1 AND user_id = $2
+            "#,
+            organization_id,
+            user_id,
+            role.as_str()
+        )
+        .execute(&mut *conn)
+        .timed("organization_repo::set_role")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Take someone out of an organization
+///
+/// Fails with `NotFound` if they aren't a member, and refuses to remove
+/// the last owner.
+pub async fn remove_member(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    with_transaction(pool, async |conn| {
+        let members = lock_members(conn, organization_id).await?;
+        ensure_owner_remains(&members, user_id, None)?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM organization_members
+            WHERE organization_id = $1 AND user_id = $2
+            "#,
+            organization_id,
+            user_id
+        )
+        .execute(&mut *conn)
+        .timed("organization_repo::remove_member")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Lock an organization's member rows until the transaction ends
+async fn lock_members(
+    conn: &mut PgConnection,
+    organization_id: Uuid,
+) -> Result<Vec<(Uuid, OrganizationRole)>, AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT user_id, role
+        FROM organization_members
+        WHERE organization_id = $1
+        FOR UPDATE
+        "#,
+        organization_id
+    )
+    .fetch_all(conn)
+    .timed("organization_repo::lock_members")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    rows.into_iter()
+        .map(|row| Ok((row.user_id, parse_role(&row.role)?)))
+        .collect()
+}
+
+/// Fail unless `user_id` is a member and changing them to `new_role`
+/// (None = removing them) still leaves an owner
+fn ensure_owner_remains(
+    members: &[(Uuid, OrganizationRole)],
+    user_id: Uuid,
+    new_role: Option<OrganizationRole>,
+) -> Result<(), AppError> {
+    let Some((_, current)) = members.iter().find(|(id, _)| *id == user_id) else {
+        return Err(AppError::not_found("Member"));
+    };
+
+    let owners = members
+        .iter()
+        .filter(|(_, role)| *role == OrganizationRole::Owner)
+        .count();
+    if *current == OrganizationRole::Owner
+        && new_role != Some(OrganizationRole::Owner)
+        && owners == 1
+    {
+        return Err(AppError::validation(
+            "An organization needs an owner; make someone else owner first",
+        ));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// INVITATIONS
+// ============================================================================
+
+/// Record an invitation; the token itself is only ever emailed
+pub async fn create_invitation(
+    pool: &PgPool,
+    organization_id: Uuid,
+    email: &str,
+    role: OrganizationRole,
+    invited_by: Uuid,
+    token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<OrganizationInvitation, AppError> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO organization_invitations
+            (id, organization_id, email, role, invited_by, token_hash, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, organization_id, email, role, invited_by, created_at, expires_at, accepted_at
+        "#,
+        ids::new_id(),
+        organization_id,
+        email,
+        role.as_str(),
+        invited_by,
+        token_hash,
+        expires_at
+    )
+    .fetch_one(pool)
+    .timed("organization_repo::create_invitation")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(OrganizationInvitation {
+        id: row.id,
+        organization_id: row.organization_id,
+        email: row.email,
+        role: parse_role(&row.role)?,
+        invited_by: row.invited_by,
+        created_at: row.created_at,
+        expires_at: row.expires_at,
+        accepted_at: row.accepted_at,
+    })
+}
+
+/// Invitations not yet accepted or expired, newest first
+pub async fn list_pending_invitations(
+    pool: &PgPool,
+    organization_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Vec<OrganizationInvitation>, AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, organization_id, email, role, invited_by, created_at, expires_at, accepted_at
+        FROM organization_invitations
+        WHERE organization_id = $1 AND accepted_at IS NULL AND expires_at > $2
+        ORDER BY created_at DESC
+        "#,
+        organization_id,
+        now
+    )
+    .fetch_all(pool)
+    .timed("organization_repo::list_pending_invitations")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(OrganizationInvitation {
+                id: row.id,
+                organization_id: row.organization_id,
+                email: row.email,
+                role: parse_role(&row.role)?,
+                invited_by: row.invited_by,
+                created_at: row.created_at,
+                expires_at: row.expires_at,
+                accepted_at: row.accepted_at,
+            })
+        })
+        .collect()
+}
+
+/// Accept an invitation for `user_id`, whose email must be the invited one
+///
+/// An unknown, expired or used token, or someone else's, fails with
+/// `NotFound`.
+pub async fn accept_invitation(
+    pool: &PgPool,
+    token_hash: &str,
+    user_id: Uuid,
+    user_email: &str,
+    now: DateTime<Utc>,
+) -> Result<(Organization, OrganizationRole), AppError> {
+    with_transaction(pool, async |conn| {
+        let invitation = sqlx::query!(
+            r#"
+            SELECT i.id, i.organization_id, i.email, i.role, o.name, o.slug, o.created_at
+            FROM organization_invitations i
+            JOIN organizations o ON o.id = i.organization_id
+            WHERE i.token_hash = $1 AND i.accepted_at IS NULL AND i.expires_at > $2
+            FOR UPDATE OF i
+            "#,
+            token_hash,
+            now
+        )
+        .fetch_optional(&mut *conn)
+        .timed("organization_repo::accept_invitation.find")
+        .await
+        .map_err(AppError::DatabaseError)?
+        .ok_or_else(|| AppError::not_found("Invitation"))?;
+
+        if !invitation.email.eq_ignore_ascii_case(user_email) {
+            return Err(AppError::not_found("Invitation"));
+        }
+        let role = parse_role(&invitation.role)?;
+
+        let joined = sqlx::query!(
+            r#"
+            INSERT INTO organization_members (organization_id, user_id, role)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (organization_id, user_id) DO NOTHING
+            "#,
+            invitation.organization_id,
+            user_id,
+            role.as_str()
+        )
+        .execute(&mut *conn)
+        .timed("organization_repo::accept_invitation.join")
+        .await
+        .map_err(AppError::DatabaseError)?;
+        if joined.rows_affected() == 0 {
+            return Err(AppError::validation("You are already a member of this organization"));
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE organization_invitations SET accepted_at = NOW() WHERE id = $1
+            "#,
+            invitation.id
+        )
+        .execute(&mut *conn)
+        .timed("organization_repo::accept_invitation.use")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        let organization = Organization {
+            id: invitation.organization_id,
+            name: invitation.name,
+            slug: invitation.slug,
+            created_at: invitation.created_at,
+        };
+        Ok((organization, role))
+    })
+    .await
+}
+
+// ============================================================================
+// WALLET
+// ============================================================================
+
+/// An organization's wallet
+pub async fn get_wallet(pool: &PgPool, organization_id: Uuid) -> Result<Wallet, AppError> {
+    sqlx::query_as!(
+        Wallet,
+        r#"
+        SELECT id, user_id, organization_id, balance, currency as "currency: Currency",
+               created_at as "created_at!", updated_at as "updated_at!"
+        FROM wallets
+        WHERE organization_id = $1
+        "#,
+        organization_id
+    )
+    .fetch_optional(pool)
+    .timed("organization_repo::get_wallet")
+    .await
+    .map_err(AppError::DatabaseError)?
+    .ok_or_else(|| AppError::not_found("Organization wallet"))
+}
+
+/// Add `amount` to an organization's wallet and record a DEPOSIT
+pub async fn deposit(
+    pool: &PgPool,
+    organization_id: Uuid,
+    amount: Decimal,
+) -> Result<Wallet, AppError> {
+    with_transaction(pool, async |conn| {
+        let wallet = lock_wallet(conn, organization_id).await?;
+        let journal = Journal::new("Deposit funds").movement(
+            LedgerAccount::External,
+            LedgerAccount::Wallet(wallet.id),
+            wallet.currency,
+            amount,
+        );
+        let updated = wallet_repo::post_journal(conn, &journal).await?.wallet(wallet.id)?;
+        wallet_repo::record_transaction(conn, wallet.id, "DEPOSIT", amount, "Deposit funds")
+            .await?;
+        Ok(updated)
+    })
+    .await
+}
+
+/// Take `amount` out of an organization's wallet and record a WITHDRAWAL
+///
+/// Fails with `InsufficientBalance` if it would go below the overdraft limit.
+pub async fn withdraw(
+    pool: &PgPool,
+    organization_id: Uuid,
+    amount: Decimal,
+) -> Result<Wallet, AppError> {
+    with_transaction(pool, async |conn| {
+        let wallet = lock_wallet(conn, organization_id).await?;
+        if wallet.balance - amount < -wallet.overdraft_limit {
+            return Err(AppError::InsufficientBalance);
+        }
+
+        let journal = Journal::new("Withdraw funds").movement(
+            LedgerAccount::Wallet(wallet.id),
+            LedgerAccount::External,
+            wallet.currency,
+            amount,
+        );
+        let updated = wallet_repo::post_journal(conn, &journal).await?.wallet(wallet.id)?;
+        wallet_repo::record_transaction(conn, wallet.id, "WITHDRAWAL", amount, "Withdraw funds")
+            .await?;
+        Ok(updated)
+    })
+    .await
+}
+
+/// Move `amount` from an organization's wallet to a user's
+///
+/// Fails like `WalletRepository::transfer`.
+pub async fn transfer_to_user(
+    pool: &PgPool,
+    organization_id: Uuid,
+    recipient_id: Uuid,
+    amount: Decimal,
+) -> Result<TransferResult, AppError> {
+    with_transaction(pool, async |conn| {
+        transfer_in(conn, organization_id, recipient_id, amount, "Transfer sent").await
+    })
+    .await
+}
+
+/// `transfer_to_user` inside the caller's transaction, with `description`
+/// on the organization's side
+async fn transfer_in(
+    conn: &mut PgConnection,
+    organization_id: Uuid,
+    recipient_id: Uuid,
+    amount: Decimal,
+    description: &str,
+) -> Result<TransferResult, AppError> {
+    // The organization's wallet first, then the user's. Unlike user
+    // transfers this needs no id order: nothing locks an organization's
+    // wallet after a user's, so there is no opposite order to deadlock with
+    let sender = lock_wallet(conn, organization_id).await?;
+    if sender.balance - amount < -sender.overdraft_limit {
+        return Err(AppError::InsufficientBalance);
+    }
+
+    let recipient = wallet_repo::lock_wallet(conn, recipient_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Recipient wallet"))?;
+    if recipient.currency != sender.currency {
+        return Err(AppError::currency_mismatch(sender.currency, recipient.currency));
+    }
+
+    let journal = Journal::new(description).movement(
+        LedgerAccount::Wallet(sender.id),
+        LedgerAccount::Wallet(recipient.id),
+        sender.currency,
+        amount,
+    );
+    let posted = wallet_repo::post_journal(conn, &journal).await?;
+    wallet_repo::record_transaction(conn, sender.id, "TRANSFER", amount, description).await?;
+    wallet_repo::record_transaction(conn, recipient.id, "TRANSFER", amount, "Transfer received")
+        .await?;
+
+    Ok(TransferResult {
+        sender_wallet: posted.wallet(sender.id)?,
+        recipient_amount: amount,
+        recipient_currency: recipient.currency,
+        recipient_balance: posted.wallet(recipient.id)?.balance,
+    })
+}
+
+/// Lock an organization's wallet row until the transaction ends
+async fn lock_wallet(
+    conn: &mut PgConnection,
+    organization_id: Uuid,
+) -> Result<LockedWallet, AppError> {
+    sqlx::query_as!(
+        LockedWallet,
+        r#"
+        SELECT id, balance, overdraft_limit, currency as "currency: Currency", version
+        FROM wallets
+        WHERE organization_id = $1
+        FOR UPDATE
+        "#,
+        organization_id
+    )
+    .fetch_optional(conn)
+    .timed("organization_repo::lock_wallet")
+    .await
+    .map_err(AppError::DatabaseError)?
+    .ok_or_else(|| AppError::not_found("Organization wallet"))
+}
+
+// ============================================================================
+// EXPENSE REQUESTS
+// ============================================================================
+// Transfers out of the organization's wallet that wait for approval
+// (migration 031). Deciding locks the request row, so two approvers
+// clicking at once pay it out once; approving makes the transfer in the
+// same transaction, so a request that can't be paid (too little money)
+// stays PENDING.
+
+struct ExpenseRow {
+    id: Uuid,
+    organization_id: Uuid,
+    requested_by: Option<Uuid>,
+    requester_email: Option<String>,
+    recipient_id: Uuid,
+    recipient_email: String,
+    amount: Decimal,
+    currency: Currency,
+    description: String,
+    status: String,
+    decided_by: Option<Uuid>,
+    decided_at: Option<DateTime<Utc>>,
+    decision_note: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<ExpenseRow> for ExpenseRequest {
+    type Error = AppError;
+
+    fn try_from(row: ExpenseRow) -> Result<Self, AppError> {
+        let status = ExpenseStatus::parse(&row.status).ok_or_else(|| {
+            AppError::internal(&format!("Unknown expense status {:?}", row.status))
+        })?;
+        Ok(ExpenseRequest {
+            id: row.id,
+            organization_id: row.organization_id,
+            requested_by: row.requested_by,
+            requester_email: row.requester_email,
+            recipient_id: row.recipient_id,
+            recipient_email: row.recipient_email,
+            amount: row.amount,
+            currency: row.currency,
+            description: row.description,
+            status,
+            decided_by: row.decided_by,
+            decided_at: row.decided_at,
+            decision_note: row.decision_note,
+            created_at: row.created_at,
+        })
+    }
+}
+
+/// Ask for `amount` to be sent to `recipient_id`
+pub async fn create_expense_request(
+    pool: &PgPool,
+    organization_id: Uuid,
+    requested_by: Uuid,
+    recipient_id: Uuid,
+    amount: Decimal,
+    description: &str,
+) -> Result<ExpenseRequest, AppError> {
+    let id = ids::new_id();
+    sqlx::query!(
+        r#"
+        INSERT INTO organization_expense_requests
+            (id, organization_id, requested_by, recipient_id, amount, description)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        id,
+        organization_id,
+        requested_by,
+        recipient_id,
+        amount,
+        description
+    )
+    .execute(pool)
+    .timed("organization_repo::create_expense_request")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    find_expense_request(pool, organization_id, id).await
+}
+
+/// An organization's expense requests, newest first, optionally only
+/// those with `status`
+pub async fn list_expense_requests(
+    pool: &PgPool,
+    organization_id: Uuid,
+    status: Option<ExpenseStatus>,
+) -> Result<Vec<ExpenseRequest>, AppError> {
+    let rows = sqlx::query_as!(
+        ExpenseRow,
+        r#"
+        SELECT e.id, e.organization_id, e.requested_by, r.email as "requester_email?",
+               e.recipient_id, u.email as recipient_email, e.amount,
+               w.currency as "currency: Currency", e.description, e.status,
+               e.decided_by, e.decided_at, e.decision_note, e.created_at
+        FROM organization_expense_requests e
+        JOIN users u ON u.id = e.recipient_id
+        JOIN wallets w ON w.organization_id = e.organization_id
+        LEFT JOIN users r ON r.id = e.requested_by
+        WHERE e.organization_id = $1 AND ($2::text IS NULL OR e.status = $2)
+        ORDER BY e.created_at DESC
+        "#,
+        organization_id,
+        status.map(|s| s.as_str())
+    )
+    .fetch_all(pool)
+    .timed("organization_repo::list_expense_requests")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    rows.into_iter().map(ExpenseRequest::try_from).collect()
+}
+
+/// One of an organization's expense requests
+pub async fn find_expense_request(
+    executor: impl PgExecutor<'_>,
+    organization_id: Uuid,
+    id: Uuid,
+) -> Result<ExpenseRequest, AppError> {
+    let row = sqlx::query_as!(
+        ExpenseRow,
+        r#"
+        SELECT e.id, e.organization_id, e.requested_by, r.email as "requester_email?",
+               e.recipient_id, u.email as recipient_email, e.amount,
+               w.currency as "currency: Currency", e.description, e.status,
+               e.decided_by, e.decided_at, e.decision_note, e.created_at
+        FROM organization_expense_requests e
+        JOIN users u ON u.id = e.recipient_id
+        JOIN wallets w ON w.organization_id = e.organization_id
+        LEFT JOIN users r ON r.id = e.requested_by
+        WHERE e.organization_id = $1 AND e.id = $2
+        "#,
+        organization_id,
+        id
+    )
+    .fetch_optional(executor)
+    .timed("organization_repo::find_expense_request")
+    .await
+    .map_err(AppError::DatabaseError)?
+    .ok_or_else(|| AppError::not_found("Expense request"))?;
+
+    ExpenseRequest::try_from(row)
+}
+
+/// Approve a pending request and pay it out
+///
+/// Fails with a validation error if it was already decided or
+/// `approver_id` asked for it, and like `transfer_to_user` if it can't be
+/// paid (the request then stays pending).
+pub async fn approve_expense_request(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+    approver_id: Uuid,
+    note: Option<&str>,
+) -> Result<(ExpenseRequest, TransferResult), AppError> {
+    with_transaction(pool, async |conn| {
+        let pending = lock_pending_expense(conn, organization_id, id, approver_id).await?;
+        let transfer = transfer_in(
+            conn,
+            organization_id,
+            pending.recipient_id,
+            pending.amount,
+            &pending.description,
+        )
+        .await?;
+        decide_expense(conn, id, ExpenseStatus::Approved, approver_id, note).await?;
+
+        let expense = find_expense_request(&mut *conn, organization_id, id).await?;
+        Ok((expense, transfer))
+    })
+    .await
+}
+
+/// Reject a pending request
+///
+/// Fails like `approve_expense_request` for decided or own requests.
+pub async fn reject_expense_request(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+    approver_id: Uuid,
+    note: Option<&str>,
+) -> Result<ExpenseRequest, AppError> {
+    with_transaction(pool, async |conn| {
+        lock_pending_expense(conn, organization_id, id, approver_id).await?;
+        decide_expense(conn, id, ExpenseStatus::Rejected, approver_id, note).await?;
+        find_expense_request(&mut *conn, organization_id, id).await
+    })
+    .await
+}
+
+struct PendingExpense {
+    recipient_id: Uuid,
+    amount: Decimal,
+    description: String,
+}
+
+/// Lock a request that `approver_id` may decide: still pending, and
+/// someone else's
+async fn lock_pending_expense(
+    conn: &mut PgConnection,
+    organization_id: Uuid,
+    id: Uuid,
+    approver_id: Uuid,
+) -> Result<PendingExpense, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT requested_by, recipient_id, amount, description, status
+        FROM organization_expense_requests
+        WHERE organization_id = $1 AND id = $2
+        FOR UPDATE
+        "#,
+        organization_id,
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .timed("organization_repo::lock_pending_expense")
+    .await
+    .map_err(AppError::DatabaseError)?
+    .ok_or_else(|| AppError::not_found("Expense request"))?;
+
+    if row.status != ExpenseStatus::Pending.as_str() {
+        return Err(AppError::validation("This request has already been decided"));
+    }
+    if row.requested_by == Some(approver_id) {
+        return Err(AppError::validation("Someone else has to decide on your own request"));
+    }
+
+    Ok(PendingExpense {
+        recipient_id: row.recipient_id,
+        amount: row.amount,
+        description: row.description,
+    })
+}
+
+async fn decide_expense(
+    conn: &mut PgConnection,
+    id: Uuid,
+    status: ExpenseStatus,
+    decided_by: Uuid,
+    note: Option<&str>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE organization_expense_requests
+        SET status = $2, decided_by = $3, decided_at = NOW(), decision_note = $4
+        WHERE id = $1
+        "#,
+        id,
+        status.as_str(),
+        decided_by,
+        note
+    )
+    .execute(conn)
+    .timed("organization_repo::decide_expense")
+    .await
+    .map_err(AppError::DatabaseError)?;
+    Ok(())
+}
+
+fn parse_role(role: &str) -> Result<OrganizationRole, AppError> {
+    OrganizationRole::parse(role)
+        .ok_or_else(|| AppError::internal(&format!("Unknown organization role {:?}", role)))
+}
@@ -0,0 +1,100 @@
+use crate::domain::ids;
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use crate::repository::unit_of_work::with_transaction;
+use crate::repository::user_repo;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// PASSWORD RESET REPOSITORY
+// ============================================================================
+// One-time password reset tokens (migration 034), stored hashed. A user has
+// at most one that works: asking again replaces the last one.
+
+/// Store a new reset token for `user_id`, replacing any unused one
+pub async fn create(
+    pool: &PgPool,
+    user_id: Uuid,
+    token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    with_transaction(pool, async |conn| {
+        sqlx::query!(
+            "DELETE FROM password_resets WHERE user_id = $1 AND used_at IS NULL",
+            user_id
+        )
+        .execute(&mut *conn)
+        .timed("password_reset_repo::create.replace")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO password_resets (id, user_id, token_hash, expires_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            ids::new_id(),
+            user_id,
+            token_hash,
+            expires_at
+        )
+        .execute(&mut *conn)
+        .timed("password_reset_repo::create")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Use a reset token: set the password of the user it was issued to
+///
+/// Marking the token used and changing the password happen together, so a
+/// token works exactly once. Returns the user, or None if the token is
+/// unknown, used or expired by `now`.
+pub async fn reset_password(
+    pool: &PgPool,
+    token_hash: &str,
+    password_hash: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<Uuid>, AppError> {
+    with_transaction(pool, async |conn| {
+        let user_id = sqlx::query_scalar!(
+            r#"
+            UPDATE password_resets SET used_at = $2
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2
+            RETURNING user_id
+            "#,
+            token_hash,
+            now
+        )
+        .fetch_optional(&mut *conn)
+        .timed("password_reset_repo::reset_password.use")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        let Some(user_id) = user_id else {
+            return Ok(None);
+        };
+        user_repo::update_password(&mut *conn, user_id, password_hash).await?;
+
+        Ok(Some(user_id))
+    })
+    .await
+}
+
+/// Delete tokens that expired before `before` (used or not)
+///
+/// Returns how many were deleted.
+pub async fn delete_expired(pool: &PgPool, before: DateTime<Utc>) -> Result<u64, AppError> {
+    let result = sqlx::query!("DELETE FROM password_resets WHERE expires_at < $1", before)
+        .execute(pool)
+        .timed("password_reset_repo::delete_expired")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected())
+}
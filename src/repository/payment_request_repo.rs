@@ -0,0 +1,210 @@
+use crate::domain::ids;
+use crate::domain::models::Currency;
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// PAYMENT REQUEST REPOSITORY
+// ============================================================================
+// Users asking each other for money (`payment_requests`, migration 039).
+// The money itself moves through the normal transfer path when the payer
+// approves; this table remembers who asked whom for what, and the answer.
+//
+// Approving marks the request PAID before the transfer runs, so two
+// approvals can't both pay; the service puts it back to PENDING if the
+// transfer then fails.
+
+/// A payment request, with both sides' email addresses
+#[derive(Debug, Clone)]
+pub struct PaymentRequest {
+    pub id: Uuid,
+    pub requester_id: Uuid,
+    pub requester_email: String,
+    pub payer_id: Uuid,
+    pub payer_email: String,
+    pub amount: Decimal,
+    /// Which of the payer's wallets pays (None = the primary one)
+    pub currency: Option<Currency>,
+    pub note: Option<String>,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+/// Record a request from `requester_id` to `payer_id`
+pub async fn create(
+    pool: &PgPool,
+    requester_id: Uuid,
+    payer_id: Uuid,
+    amount: Decimal,
+    currency: Option<Currency>,
+    note: Option<&str>,
+) -> Result<PaymentRequest, AppError> {
+    sqlx::query_as!(
+        PaymentRequest,
+        r#"
+        WITH created AS (
+            INSERT INTO payment_requests (id, requester_id, payer_id, amount, currency, note)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+        )
+        SELECT c.id, c.requester_id, r.email as requester_email, c.payer_id,
+               p.email as payer_email, c.amount, c.currency as "currency: Currency", c.note,
+               c.status, c.created_at, c.decided_at
+        FROM created c
+        JOIN users r ON r.id = c.requester_id
+        JOIN users p ON p.id = c.payer_id
+        "#,
+        ids::new_id(),
+        requester_id,
+        payer_id,
+        amount,
+        currency as Option<Currency>,
+        note
+    )
+    .fetch_one(pool)
+    .timed("payment_request_repo::create")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Requests others made of `payer_id`, newest first
+pub async fn list_incoming(
+    pool: &PgPool,
+    payer_id: Uuid,
+    limit: i64,
+) -> Result<Vec<PaymentRequest>, AppError> {
+    sqlx::query_as!(
+        PaymentRequest,
+        r#"
+        SELECT pr.id, pr.requester_id, r.email as requester_email, pr.payer_id,
+               p.email as payer_email, pr.amount, pr.currency as "currency: Currency", pr.note,
+               pr.status, pr.created_at, pr.decided_at
+        FROM payment_requests pr
+        JOIN users r ON r.id = pr.requester_id
+        JOIN users p ON p.id = pr.payer_id
+        WHERE pr.payer_id = $1
+        ORDER BY pr.created_at DESC, pr.id DESC
+        LIMIT $2
+        "#,
+        payer_id,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("payment_request_repo::list_incoming")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Requests `requester_id` made of others, newest first
+pub async fn list_outgoing(
+    pool: &PgPool,
+    requester_id: Uuid,
+    limit: i64,
+) -> Result<Vec<PaymentRequest>, AppError> {
+    sqlx::query_as!(
+        PaymentRequest,
+        r#"
+        SELECT pr.id, pr.requester_id, r.email as requester_email, pr.payer_id,
+               p.email as payer_email, pr.amount, pr.currency as "currency: Currency", pr.note,
+               pr.status, pr.created_at, pr.decided_at
+        FROM payment_requests pr
+        JOIN users r ON r.id = pr.requester_id
+        JOIN users p ON p.id = pr.payer_id
+        WHERE pr.requester_id = $1
+        ORDER BY pr.created_at DESC, pr.id DESC
+        LIMIT $2
+        "#,
+        requester_id,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("payment_request_repo::list_outgoing")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// A request made of `payer_id` (None if there is no such one)
+pub async fn find_incoming(
+    pool: &PgPool,
+    payer_id: Uuid,
+    request_id: Uuid,
+) -> Result<Option<PaymentRequest>, AppError> {
+    sqlx::query_as!(
+        PaymentRequest,
+        r#"
+        SELECT pr.id, pr.requester_id, r.email as requester_email, pr.payer_id,
+               p.email as payer_email, pr.amount, pr.currency as "currency: Currency", pr.note,
+               pr.status, pr.created_at, pr.decided_at
+        FROM payment_requests pr
+        JOIN users r ON r.id = pr.requester_id
+        JOIN users p ON p.id = pr.payer_id
+        WHERE pr.id = $1 AND pr.payer_id = $2
+        "#,
+        request_id,
+        payer_id
+    )
+    .fetch_optional(pool)
+    .timed("payment_request_repo::find_incoming")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Answer a PENDING request made of `payer_id` with `status` (PAID or
+/// DECLINED)
+///
+/// Returns None (changing nothing) if the payer has no such request still
+/// PENDING.
+pub async fn decide(
+    pool: &PgPool,
+    payer_id: Uuid,
+    request_id: Uuid,
+    status: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<PaymentRequest>, AppError> {
+    sqlx::query_as!(
+        PaymentRequest,
+        r#"
+        WITH decided AS (
+            UPDATE payment_requests SET status = $3, decided_at = $4
+            WHERE id = $1 AND payer_id = $2 AND status = 'PENDING'
+            RETURNING *
+        )
+        SELECT d.id, d.requester_id, r.email as requester_email, d.payer_id,
+               p.email as payer_email, d.amount, d.currency as "currency: Currency", d.note,
+               d.status, d.created_at, d.decided_at
+        FROM decided d
+        JOIN users r ON r.id = d.requester_id
+        JOIN users p ON p.id = d.payer_id
+        "#,
+        request_id,
+        payer_id,
+        status,
+        now
+    )
+    .fetch_optional(pool)
+    .timed("payment_request_repo::decide")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Put a request marked PAID back to PENDING (its transfer failed)
+pub async fn reopen(pool: &PgPool, request_id: Uuid) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE payment_requests SET status = 'PENDING', decided_at = NULL
+        WHERE id = $1 AND status = 'PAID'
+        "#,
+        request_id
+    )
+    .execute(pool)
+    .timed("payment_request_repo::reopen")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
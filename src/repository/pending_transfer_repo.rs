@@ -0,0 +1,332 @@
+use crate::domain::ids;
+use crate::domain::models::{Currency, PendingTransferStatus, TransactionStatus};
+use crate::error::AppError;
+use crate::repository::ledger_repo::{Journal, LedgerAccount};
+use crate::repository::metrics::TimedQuery;
+use crate::repository::unit_of_work::with_transaction;
+use crate::repository::wallet_repo::LockedWallet;
+use crate::repository::{transaction_repo, wallet_repo, TransferOrder};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+// ============================================================================
+// PENDING TRANSFER REPOSITORY
+// ============================================================================
+// Transfers waiting for the sender's confirmation code (`pending_transfers`,
+// migration 038), and the money they hold.
+//
+// Creating one takes the money from the sender straight away (a COMPLETED
+// debit) and records the recipient's credit as PENDING, so the sender can't
+// spend it twice while we wait. From there every change goes through the
+// transaction lifecycle (see transaction_repo), in the same database
+// transaction as the status change here:
+//
+//   confirm:          credit PENDING --> COMPLETED
+//   cancel / expire:  debit COMPLETED --> REVERSED (the refund),
+//                     credit PENDING --> FAILED
+//
+// Only a PENDING transfer changes, so a transfer is settled or refunded
+// exactly once however many requests race for it.
+
+/// A transfer waiting for (or past) confirmation
+#[derive(Debug, Clone)]
+pub struct PendingTransfer {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub recipient_id: Uuid,
+    pub recipient_email: String,
+    /// Taken from the sender, in their wallet's currency
+    pub amount: Decimal,
+    pub currency: Currency,
+    /// What the recipient gets, in their wallet's currency
+    pub recipient_amount: Decimal,
+    pub debit_id: Uuid,
+    pub credit_id: Uuid,
+    pub code_hash: String,
+    /// Wrong codes entered so far
+    pub attempts: i32,
+    pub status: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+/// Hold the money for `order` and record the transfer as PENDING
+///
+/// Fails (and changes nothing) with `LimitExceeded` if it goes over the
+/// order's daily limit, and with `InsufficientBalance` if it would take
+/// the sender below their overdraft limit.
+pub async fn create(
+    pool: &PgPool,
+    user_id: Uuid,
+    recipient_id: Uuid,
+    recipient_email: &str,
+    order: &TransferOrder,
+    code_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<PendingTransfer, AppError> {
+    let amount = order.amount;
+
+    with_transaction(pool, async |conn| {
+        let sender = wallet_repo::lock_wallet_by_id(conn, order.sender_wallet_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Sender wallet"))?;
+
+        wallet_repo::check_daily_limit(conn, order.daily_limit, &sender, amount).await?;
+        if sender.balance - amount < -sender.overdraft_limit {
+            return Err(AppError::InsufficientBalance);
+        }
+
+        let recipient_amount = match order.exchange_rate {
+            Some(rate) => (amount * rate).round_dp(2),
+            None => amount,
+        };
+        if recipient_amount <= Decimal::ZERO {
+            return Err(AppError::validation("Amount is too small to convert"));
+        }
+
+        // Take the money now (it's on its way until confirmed)...
+        let journal = Journal::new("Transfer awaiting confirmation").movement(
+            LedgerAccount::Wallet(sender.id),
+            LedgerAccount::External,
+            sender.currency,
+            amount,
+        );
+        wallet_repo::post_journal(conn, &journal).await?;
+        let debit_id = insert_transfer(
+            conn,
+            sender.id,
+            amount,
+            "Transfer sent",
+            TransactionStatus::Completed,
+            order.exchange_rate,
+        )
+        .await?;
+
+        // ...and give it once the sender confirms
+        let credit_id = insert_transfer(
+            conn,
+            order.recipient_wallet_id,
+            recipient_amount,
+            "Transfer received",
+            TransactionStatus::Pending,
+            order.exchange_rate,
+        )
+        .await?;
+
+        sqlx::query_as!(
+            PendingTransfer,
+            r#"
+            INSERT INTO pending_transfers (
+                id, user_id, recipient_id, recipient_email, amount, currency,
+                recipient_amount, debit_id, credit_id, code_hash, expires_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING id, user_id, recipient_id, recipient_email, amount,
+                      currency as "currency: Currency", recipient_amount, debit_id, credit_id,
+                      code_hash, attempts, status, expires_at, created_at, confirmed_at,
+                      cancelled_at
+            "#,
+            ids::new_id(),
+            user_id,
+            recipient_id,
+            recipient_email,
+            amount,
+            sender.currency as Currency,
+            recipient_amount,
+            debit_id,
+            credit_id,
+            code_hash,
+            expires_at
+        )
+        .fetch_one(&mut *conn)
+        .timed("pending_transfer_repo::create")
+        .await
+        .map_err(AppError::DatabaseError)
+    })
+    .await
+}
+
+/// One of a user's pending transfers (None if there is no such one)
+pub async fn find(
+    pool: &PgPool,
+    user_id: Uuid,
+    transfer_id: Uuid,
+) -> Result<Option<PendingTransfer>, AppError> {
+    sqlx::query_as!(
+        PendingTransfer,
+        r#"
+        SELECT id, user_id, recipient_id, recipient_email, amount,
+               currency as "currency: Currency", recipient_amount, debit_id, credit_id,
+               code_hash, attempts, status, expires_at, created_at, confirmed_at, cancelled_at
+        FROM pending_transfers
+        WHERE id = $1 AND user_id = $2
+        "#,
+        transfer_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .timed("pending_transfer_repo::find")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Count a wrong code against a PENDING transfer
+///
+/// Returns the wrong codes entered so far, or None if the transfer isn't
+/// PENDING any more.
+pub async fn record_wrong_code(pool: &PgPool, transfer_id: Uuid) -> Result<Option<i32>, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        UPDATE pending_transfers SET attempts = attempts + 1
+        WHERE id = $1 AND status = 'PENDING'
+        RETURNING attempts
+        "#,
+        transfer_id
+    )
+    .fetch_optional(pool)
+    .timed("pending_transfer_repo::record_wrong_code")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Give the held money to the recipient and mark the transfer CONFIRMED
+///
+/// Returns the transfer and the recipient's wallet (with its new balance),
+/// or None (changing nothing) if it isn't PENDING or has expired by `now`.
+pub async fn confirm(
+    pool: &PgPool,
+    transfer_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Option<(PendingTransfer, LockedWallet)>, AppError> {
+    with_transaction(pool, async |conn| {
+        let transfer = sqlx::query_as!(
+            PendingTransfer,
+            r#"
+            UPDATE pending_transfers SET status = 'CONFIRMED', confirmed_at = $2
+            WHERE id = $1 AND status = 'PENDING' AND expires_at > $2
+            RETURNING id, user_id, recipient_id, recipient_email, amount,
+                      currency as "currency: Currency", recipient_amount, debit_id, credit_id,
+                      code_hash, attempts, status, expires_at, created_at, confirmed_at,
+                      cancelled_at
+            "#,
+            transfer_id,
+            now
+        )
+        .fetch_optional(&mut *conn)
+        .timed("pending_transfer_repo::confirm")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        let Some(transfer) = transfer else {
+            return Ok(None);
+        };
+
+        let credit =
+            transaction_repo::transition(conn, transfer.credit_id, TransactionStatus::Completed)
+                .await?;
+        let recipient = wallet_repo::lock_wallet_by_id(conn, credit.wallet_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Recipient wallet"))?;
+
+        Ok(Some((transfer, recipient)))
+    })
+    .await
+}
+
+/// Give the held money back to the sender, marking the transfer `outcome`
+/// (CANCELLED or EXPIRED)
+///
+/// Returns None (changing nothing) if it isn't PENDING.
+pub async fn release(
+    pool: &PgPool,
+    transfer_id: Uuid,
+    outcome: PendingTransferStatus,
+    now: DateTime<Utc>,
+) -> Result<Option<PendingTransfer>, AppError> {
+    with_transaction(pool, async |conn| {
+        let transfer = sqlx::query_as!(
+            PendingTransfer,
+            r#"
+            UPDATE pending_transfers SET status = $2, cancelled_at = $3
+            WHERE id = $1 AND status = 'PENDING'
+            RETURNING id, user_id, recipient_id, recipient_email, amount,
+                      currency as "currency: Currency", recipient_amount, debit_id, credit_id,
+                      code_hash, attempts, status, expires_at, created_at, confirmed_at,
+                      cancelled_at
+            "#,
+            transfer_id,
+            outcome.as_str(),
+            now
+        )
+        .fetch_optional(&mut *conn)
+        .timed("pending_transfer_repo::release")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        let Some(transfer) = transfer else {
+            return Ok(None);
+        };
+
+        transaction_repo::transition(conn, transfer.debit_id, TransactionStatus::Reversed).await?;
+        transaction_repo::transition(conn, transfer.credit_id, TransactionStatus::Failed).await?;
+
+        Ok(Some(transfer))
+    })
+    .await
+}
+
+/// Up to `limit` PENDING transfers that expired by `now`, oldest first
+pub async fn list_expired(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<Uuid>, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT id FROM pending_transfers
+        WHERE status = 'PENDING' AND expires_at <= $1
+        ORDER BY expires_at
+        LIMIT $2
+        "#,
+        now,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("pending_transfer_repo::list_expired")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Record one side of a held transfer; returns its id
+async fn insert_transfer(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+    amount: Decimal,
+    description: &str,
+    status: TransactionStatus,
+    exchange_rate: Option<Decimal>,
+) -> Result<Uuid, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO transactions
+            (id, wallet_id, transaction_type, amount, description, status, completed_at, exchange_rate)
+        VALUES ($1, $2, 'TRANSFER', $3, $4, $5::VARCHAR,
+                CASE WHEN $5 = 'COMPLETED' THEN NOW() END, $6)
+        RETURNING id
+        "#,
+        ids::new_id(),
+        wallet_id,
+        amount,
+        description,
+        status.as_str(),
+        exchange_rate
+    )
+    .fetch_one(conn)
+    .timed("pending_transfer_repo::insert_transfer")
+    .await
+    .map_err(AppError::DatabaseError)
+}
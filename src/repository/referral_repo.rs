@@ -0,0 +1,425 @@
+use crate::domain::ids;
+use crate::domain::models::{Referral, ReferralStatus};
+use crate::error::AppError;
+use crate::repository::ledger_repo::{Journal, LedgerAccount};
+use crate::repository::metrics::TimedQuery;
+use crate::repository::unit_of_work::with_transaction;
+use crate::repository::wallet_repo;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+// ============================================================================
+// REFERRAL REPOSITORY
+// ============================================================================
+// Referral codes and the signups made with them (migration 032).
+//
+// Paying a bonus locks the referrer's code row first: it serializes the
+// REFERRAL_MAX_REWARDS check across the referrer's referrals, so two
+// referees qualifying at the same moment can't both squeeze under the
+// limit. The two wallets are then locked in user id order, like transfers.
+
+/// The description of the bonus transactions (never counts as a
+/// qualifying deposit itself)
+pub const BONUS_DESCRIPTION: &str = "Referral bonus";
+
+/// A user's referral code (None until they first ask for it)
+pub async fn find_code(pool: &PgPool, user_id: Uuid) -> Result<Option<String>, AppError> {
+    sqlx::query_scalar!("SELECT code FROM referral_codes WHERE user_id = $1", user_id)
+        .fetch_optional(pool)
+        .timed("referral_repo::find_code")
+        .await
+        .map_err(AppError::DatabaseError)
+}
+
+/// Give `user_id` the referral code `code`
+///
+/// Returns false without changing anything when the user already has a
+/// code or someone else has this one.
+pub async fn insert_code(pool: &PgPool, user_id: Uuid, code: &str) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "INSERT INTO referral_codes (user_id, code) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        user_id,
+        code
+    )
+    .execute(pool)
+    .timed("referral_repo::insert_code")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Who owns the referral code `code`: their id and email (None for
+/// unknown codes and deleted users)
+pub async fn find_code_owner(pool: &PgPool, code: &str) -> Result<Option<(Uuid, String)>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT u.id, u.email
+        FROM referral_codes c
+        JOIN users u ON u.id = c.user_id
+        WHERE c.code = $1 AND u.deleted_at IS NULL
+        "#,
+        code
+    )
+    .fetch_optional(pool)
+    .timed("referral_repo::find_code_owner")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(row.map(|row| (row.id, row.email)))
+}
+
+/// Whether `user_id` ever signed in from `ip`
+pub async fn has_signed_in_from(pool: &PgPool, user_id: Uuid, ip: &str) -> Result<bool, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM login_events WHERE user_id = $1 AND ip_address = $2
+        ) as "exists!"
+        "#,
+        user_id,
+        ip
+    )
+    .fetch_one(pool)
+    .timed("referral_repo::has_signed_in_from")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Whether one of `referrer_id`'s referees already signed up from `ip`
+pub async fn has_referral_from(pool: &PgPool, referrer_id: Uuid, ip: &str) -> Result<bool, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM referrals WHERE referrer_id = $1 AND signup_ip = $2
+        ) as "exists!"
+        "#,
+        referrer_id,
+        ip
+    )
+    .fetch_one(pool)
+    .timed("referral_repo::has_referral_from")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Record that `referee_id` signed up with `referrer_id`'s code
+///
+/// With a `rejected_reason` the referral is recorded as REJECTED straight
+/// away (it failed an anti-abuse check), so it shows up but never pays.
+pub async fn create_referral(
+    pool: &PgPool,
+    referrer_id: Uuid,
+    referee_id: Uuid,
+    code: &str,
+    signup_ip: &str,
+    rejected_reason: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let status = match rejected_reason {
+        Some(_) => ReferralStatus::Rejected,
+        None => ReferralStatus::Pending,
+    };
+
+    sqlx::query!(
+        r#"
+        INSERT INTO referrals (id, referrer_id, referee_id, code, signup_ip, status, rejected_reason, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        ids::new_id(),
+        referrer_id,
+        referee_id,
+        code,
+        signup_ip,
+        status.as_str(),
+        rejected_reason,
+        now
+    )
+    .execute(pool)
+    .timed("referral_repo::create_referral")
+    .await
+    .map_err(|e| match &e {
+        sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+            AppError::validation("This account was already referred")
+        }
+        _ => AppError::DatabaseError(e),
+    })?;
+
+    Ok(())
+}
+
+#[derive(Debug)]
+struct ReferralRow {
+    id: Uuid,
+    referrer_id: Uuid,
+    referee_id: Uuid,
+    referee_email: String,
+    status: String,
+    rejected_reason: Option<String>,
+    referrer_reward: Option<Decimal>,
+    referee_reward: Option<Decimal>,
+    created_at: DateTime<Utc>,
+    rewarded_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<ReferralRow> for Referral {
+    type Error = AppError;
+
+    fn try_from(row: ReferralRow) -> Result<Self, AppError> {
+        let status = ReferralStatus::parse(&row.status).ok_or_else(|| {
+            AppError::internal(&format!("Unknown referral status {:?}", row.status))
+        })?;
+        Ok(Referral {
+            id: row.id,
+            referrer_id: row.referrer_id,
+            referee_id: row.referee_id,
+            referee_email: row.referee_email,
+            status,
+            rejected_reason: row.rejected_reason,
+            referrer_reward: row.referrer_reward,
+            referee_reward: row.referee_reward,
+            created_at: row.created_at,
+            rewarded_at: row.rewarded_at,
+        })
+    }
+}
+
+/// The signups made with `referrer_id`'s code, newest first
+pub async fn list_for_referrer(pool: &PgPool, referrer_id: Uuid) -> Result<Vec<Referral>, AppError> {
+    let rows = sqlx::query_as!(
+        ReferralRow,
+        r#"
+        SELECT r.id, r.referrer_id, r.referee_id, u.email as referee_email, r.status,
+               r.rejected_reason, r.referrer_reward, r.referee_reward, r.created_at, r.rewarded_at
+        FROM referrals r
+        JOIN users u ON u.id = r.referee_id
+        WHERE r.referrer_id = $1
+        ORDER BY r.created_at DESC
+        "#,
+        referrer_id
+    )
+    .fetch_all(pool)
+    .timed("referral_repo::list_for_referrer")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    rows.into_iter().map(Referral::try_from).collect()
+}
+
+/// Pending referrals made after `since` whose referee has deposited at
+/// least `min_deposit` in one go since signing up
+///
+/// With `referee_id`, only that user's referral (if it qualifies).
+pub async fn find_qualified(
+    pool: &PgPool,
+    min_deposit: Decimal,
+    since: DateTime<Utc>,
+    referee_id: Option<Uuid>,
+) -> Result<Vec<Uuid>, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT r.id
+        FROM referrals r
+        WHERE r.status = 'PENDING'
+          AND r.created_at > $2
+          AND ($3::uuid IS NULL OR r.referee_id = $3)
+          AND EXISTS (
+              SELECT 1
+              FROM transactions t
+              JOIN wallets w ON w.id = t.wallet_id
+              WHERE w.user_id = r.referee_id AND w.is_primary
+                AND t.transaction_type = 'DEPOSIT'
+                AND t.status = 'COMPLETED'
+                AND t.amount >= $1
+                AND t.description IS DISTINCT FROM $4
+                AND t.created_at >= r.created_at
+          )
+        ORDER BY r.created_at
+        "#,
+        min_deposit,
+        since,
+        referee_id,
+        BONUS_DESCRIPTION
+    )
+    .fetch_all(pool)
+    .timed("referral_repo::find_qualified")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Pay both sides of a pending referral
+///
+/// Past `max_rewards` rewarded referrals for the referrer (or with either
+/// account gone) the referral is REJECTED instead. Returns the referral as
+/// it ends up, or None if it wasn't pending any more.
+pub async fn reward(
+    pool: &PgPool,
+    id: Uuid,
+    referrer_reward: Decimal,
+    referee_reward: Decimal,
+    max_rewards: u32,
+    now: DateTime<Utc>,
+) -> Result<Option<Referral>, AppError> {
+    with_transaction(pool, async |conn| {
+        let Some((referrer_id, referee_id)) = lock_pending(conn, id).await? else {
+            return Ok(None);
+        };
+
+        // Serializes the limit check across this referrer's referrals
+        sqlx::query!(
+            "SELECT user_id FROM referral_codes WHERE user_id = $1 FOR UPDATE",
+            referrer_id
+        )
+        .fetch_optional(&mut *conn)
+        .timed("referral_repo::reward.lock_code")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        let rewarded = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM referrals
+            WHERE referrer_id = $1 AND status = 'REWARDED'
+            "#,
+            referrer_id
+        )
+        .fetch_one(&mut *conn)
+        .timed("referral_repo::reward.count")
+        .await
+        .map_err(AppError::DatabaseError)?;
+        if rewarded >= i64::from(max_rewards) {
+            reject(conn, id, "Referral limit reached").await?;
+            return find(conn, id).await.map(Some);
+        }
+
+        // Both wallets, in user id order (like transfers) so two bonuses
+        // between the same pair can't deadlock
+        let (first, second) = if referrer_id < referee_id {
+            (referrer_id, referee_id)
+        } else {
+            (referee_id, referrer_id)
+        };
+        let first_wallet = wallet_repo::lock_wallet(conn, first).await?;
+        let second_wallet = wallet_repo::lock_wallet(conn, second).await?;
+        let (Some(first_wallet), Some(second_wallet)) = (first_wallet, second_wallet) else {
+            reject(conn, id, "Account closed").await?;
+            return find(conn, id).await.map(Some);
+        };
+
+        for (wallet, user_id) in [(first_wallet, first), (second_wallet, second)] {
+            let amount = if user_id == referrer_id { referrer_reward } else { referee_reward };
+            if amount > Decimal::ZERO {
+                let journal = Journal::new(BONUS_DESCRIPTION).movement(
+                    LedgerAccount::External,
+                    LedgerAccount::Wallet(wallet.id),
+                    wallet.currency,
+                    amount,
+                );
+                wallet_repo::post_journal(conn, &journal).await?;
+                wallet_repo::record_transaction(conn, wallet.id, "DEPOSIT", amount, BONUS_DESCRIPTION)
+                    .await?;
+            }
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE referrals
+            SET status = 'REWARDED', referrer_reward = $2, referee_reward = $3, rewarded_at = $4
+            WHERE id = $1
+            "#,
+            id,
+            referrer_reward,
+            referee_reward,
+            now
+        )
+        .execute(&mut *conn)
+        .timed("referral_repo::reward.update")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        find(conn, id).await.map(Some)
+    })
+    .await
+}
+
+/// Lock a referral if it's still pending; returns (referrer, referee)
+async fn lock_pending(conn: &mut PgConnection, id: Uuid) -> Result<Option<(Uuid, Uuid)>, AppError> {
+    let row = sqlx::query!(
+        "SELECT referrer_id, referee_id FROM referrals WHERE id = $1 AND status = 'PENDING' FOR UPDATE",
+        id
+    )
+    .fetch_optional(conn)
+    .timed("referral_repo::lock_pending")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(row.map(|row| (row.referrer_id, row.referee_id)))
+}
+
+async fn reject(conn: &mut PgConnection, id: Uuid, reason: &str) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE referrals SET status = 'REJECTED', rejected_reason = $2 WHERE id = $1",
+        id,
+        reason
+    )
+    .execute(conn)
+    .timed("referral_repo::reject")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+async fn find(conn: &mut PgConnection, id: Uuid) -> Result<Referral, AppError> {
+    let row = sqlx::query_as!(
+        ReferralRow,
+        r#"
+        SELECT r.id, r.referrer_id, r.referee_id, u.email as referee_email, r.status,
+               r.rejected_reason, r.referrer_reward, r.referee_reward, r.created_at, r.rewarded_at
+        FROM referrals r
+        JOIN users u ON u.id = r.referee_id
+        WHERE r.id = $1
+        "#,
+        id
+    )
+    .fetch_one(conn)
+    .timed("referral_repo::find")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Referral::try_from(row)
+}
+
+/// Expire the pending referrals made before `before`
+///
+/// Returns how many expired.
+pub async fn expire_pending(pool: &PgPool, before: DateTime<Utc>) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "UPDATE referrals SET status = 'EXPIRED' WHERE status = 'PENDING' AND created_at <= $1",
+        before
+    )
+    .execute(pool)
+    .timed("referral_repo::expire_pending")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected())
+}
+
+/// Referral bonuses `user_id` has been paid, as referrer and as referee
+pub async fn total_earned(pool: &PgPool, user_id: Uuid) -> Result<Decimal, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(SUM(CASE WHEN referrer_id = $1 THEN referrer_reward ELSE referee_reward END), 0) as "total!"
+        FROM referrals
+        WHERE status = 'REWARDED' AND (referrer_id = $1 OR referee_id = $1)
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .timed("referral_repo::total_earned")
+    .await
+    .map_err(AppError::DatabaseError)
+}
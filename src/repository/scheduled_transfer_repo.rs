@@ -0,0 +1,249 @@
+use crate::domain::ids;
+use crate::domain::models::{Currency, ScheduledTransferStatus};
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+// ============================================================================
+// SCHEDULED TRANSFER REPOSITORY
+// ============================================================================
+// Transfers waiting for their time (`scheduled_transfers`, migration 036).
+// The money only moves when one runs, through the normal transfer path;
+// this table just remembers what to send, when, and how it went.
+//
+// The background task claims due transfers by pushing `next_attempt_at`
+// past the time an attempt can take, so two instances never run the same
+// one at once. A transfer is marked COMPLETED in the same database
+// transaction as its money moving, so even an attempt that outlives its
+// claim (or a crash right after it) can't pay twice.
+
+/// A scheduled transfer
+#[derive(Debug, Clone)]
+pub struct ScheduledTransfer {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub recipient_email: String,
+    pub amount: Decimal,
+    /// Which of the sender's wallets pays (None = the primary one)
+    pub currency: Option<Currency>,
+    pub execute_at: DateTime<Utc>,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+/// Record a transfer to run at `execute_at`
+pub async fn create(
+    pool: &PgPool,
+    user_id: Uuid,
+    recipient_email: &str,
+    amount: Decimal,
+    currency: Option<Currency>,
+    execute_at: DateTime<Utc>,
+) -> Result<ScheduledTransfer, AppError> {
+    sqlx::query_as!(
+        ScheduledTransfer,
+        r#"
+        INSERT INTO scheduled_transfers (
+            id, user_id, recipient_email, amount, currency, execute_at, next_attempt_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $6)
+        RETURNING id, user_id, recipient_email, amount, currency as "currency: Currency",
+                  execute_at, status, attempts, next_attempt_at, last_error, created_at,
+                  completed_at, cancelled_at
+        "#,
+        ids::new_id(),
+        user_id,
+        recipient_email,
+        amount,
+        currency as Option<Currency>,
+        execute_at
+    )
+    .fetch_one(pool)
+    .timed("scheduled_transfer_repo::create")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// A user's scheduled transfers, in the order they run
+pub async fn list_for_user(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+) -> Result<Vec<ScheduledTransfer>, AppError> {
+    sqlx::query_as!(
+        ScheduledTransfer,
+        r#"
+        SELECT id, user_id, recipient_email, amount, currency as "currency: Currency",
+               execute_at, status, attempts, next_attempt_at, last_error, created_at,
+               completed_at, cancelled_at
+        FROM scheduled_transfers
+        WHERE user_id = $1
+        ORDER BY execute_at, id
+        LIMIT $2
+        "#,
+        user_id,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("scheduled_transfer_repo::list_for_user")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// One of a user's scheduled transfers (None if there is no such one)
+pub async fn find(
+    pool: &PgPool,
+    user_id: Uuid,
+    transfer_id: Uuid,
+) -> Result<Option<ScheduledTransfer>, AppError> {
+    sqlx::query_as!(
+        ScheduledTransfer,
+        r#"
+        SELECT id, user_id, recipient_email, amount, currency as "currency: Currency",
+               execute_at, status, attempts, next_attempt_at, last_error, created_at,
+               completed_at, cancelled_at
+        FROM scheduled_transfers
+        WHERE id = $1 AND user_id = $2
+        "#,
+        transfer_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .timed("scheduled_transfer_repo::find")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Cancel a transfer that hasn't run yet
+///
+/// Returns None (changing nothing) if the user has no such transfer still
+/// SCHEDULED.
+pub async fn cancel(
+    pool: &PgPool,
+    user_id: Uuid,
+    transfer_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Option<ScheduledTransfer>, AppError> {
+    sqlx::query_as!(
+        ScheduledTransfer,
+        r#"
+        UPDATE scheduled_transfers SET status = 'CANCELLED', cancelled_at = $3
+        WHERE id = $1 AND user_id = $2 AND status = 'SCHEDULED'
+        RETURNING id, user_id, recipient_email, amount, currency as "currency: Currency",
+                  execute_at, status, attempts, next_attempt_at, last_error, created_at,
+                  completed_at, cancelled_at
+        "#,
+        transfer_id,
+        user_id,
+        now
+    )
+    .fetch_optional(pool)
+    .timed("scheduled_transfer_repo::cancel")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Claim up to `limit` transfers due by `now`, oldest first
+///
+/// Claimed transfers aren't due again before `claimed_until`, by which
+/// time the caller has recorded how the attempt went.
+pub async fn claim_due(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+    claimed_until: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<ScheduledTransfer>, AppError> {
+    sqlx::query_as!(
+        ScheduledTransfer,
+        r#"
+        UPDATE scheduled_transfers SET next_attempt_at = $2
+        WHERE id IN (
+            SELECT id FROM scheduled_transfers
+            WHERE status = 'SCHEDULED' AND next_attempt_at <= $1
+            ORDER BY next_attempt_at
+            LIMIT $3
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, user_id, recipient_email, amount, currency as "currency: Currency",
+                  execute_at, status, attempts, next_attempt_at, last_error, created_at,
+                  completed_at, cancelled_at
+        "#,
+        now,
+        claimed_until,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("scheduled_transfer_repo::claim_due")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Record that a transfer has run, inside the database transaction that
+/// moves the money (see wallet_repo's `transfer`)
+///
+/// False (changing nothing) if it isn't SCHEDULED any more: it already ran,
+/// or the sender cancelled it meanwhile, and the money mustn't move.
+pub async fn complete(
+    conn: &mut PgConnection,
+    transfer_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE scheduled_transfers
+        SET status = 'COMPLETED', completed_at = $2, last_error = NULL
+        WHERE id = $1 AND status = 'SCHEDULED'
+        "#,
+        transfer_id,
+        now
+    )
+    .execute(conn)
+    .timed("scheduled_transfer_repo::complete")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Record a failed attempt: try again at `retry_at`, or give up (FAILED)
+/// when it's None
+pub async fn record_failure(
+    pool: &PgPool,
+    transfer_id: Uuid,
+    error: &str,
+    retry_at: Option<DateTime<Utc>>,
+) -> Result<(), AppError> {
+    let status = match retry_at {
+        Some(_) => ScheduledTransferStatus::Scheduled,
+        None => ScheduledTransferStatus::Failed,
+    };
+
+    sqlx::query!(
+        r#"
+        UPDATE scheduled_transfers
+        SET attempts = attempts + 1,
+            last_error = $2,
+            next_attempt_at = COALESCE($3, next_attempt_at),
+            status = $4
+        WHERE id = $1 AND status = 'SCHEDULED'
+        "#,
+        transfer_id,
+        error,
+        retry_at,
+        status.as_str()
+    )
+    .execute(pool)
+    .timed("scheduled_transfer_repo::record_failure")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// SEARCH REPOSITORY
+// ============================================================================
+// Full-text search over transaction descriptions, using the GIN-indexed
+// `search_vector` column (migration 015).
+//
+// Queries are parsed with `websearch_to_tsquery`, so users can type what
+// they would into a search box: `coffee -refund`, `"opening deposit"`,
+// `rent or fuel`. It never fails on odd input; a query made only of stop
+// words ("the") simply matches nothing.
+//
+// Results are ordered by `ts_rank`, then newest first. Archived
+// transactions (migration 014) are not searched.
+
+/// A transaction that matched a search, with its relevance
+#[derive(Debug, Clone)]
+pub struct TransactionMatch {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub transaction_type: String,
+    pub amount: Decimal,
+    pub description: Option<String>,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub failed_at: Option<DateTime<Utc>>,
+    pub reversed_at: Option<DateTime<Utc>>,
+    pub exchange_rate: Option<Decimal>,
+    /// Higher is more relevant
+    pub rank: f32,
+}
+
+/// A match across every wallet, with its owner (for admins)
+#[derive(Debug, Clone, Serialize)]
+pub struct OwnedTransactionMatch {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub user_id: Uuid,
+    pub email: String,
+    pub transaction_type: String,
+    pub amount: Decimal,
+    pub description: Option<String>,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub failed_at: Option<DateTime<Utc>>,
+    pub reversed_at: Option<DateTime<Utc>>,
+    pub rank: f32,
+}
+
+/// Search one wallet's transactions
+pub async fn search_wallet_transactions(
+    pool: &PgPool,
+    wallet_id: Uuid,
+    query: &str,
+    limit: u32,
+) -> Result<Vec<TransactionMatch>, AppError> {
+    sqlx::query_as!(
+        TransactionMatch,
+        r#"
+        SELECT t.id, t.wallet_id, t.transaction_type, t.amount, t.description,
+               t.status, t.created_at, t.completed_at, t.failed_at, t.reversed_at, t.exchange_rate,
+               ts_rank(t.search_vector, q) as "rank!"
+        FROM transactions t, websearch_to_tsquery('english', $2) q
+        WHERE t.wallet_id = $1 AND t.search_vector @@ q
+        ORDER BY 12 DESC, t.created_at DESC, t.id DESC
+        LIMIT $3
+        "#,
+        wallet_id,
+        query,
+        i64::from(limit)
+    )
+    .fetch_all(pool)
+    .timed("search_repo::search_wallet_transactions")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Search every wallet's transactions (deleted users included)
+pub async fn search_all_transactions(
+    pool: &PgPool,
+    query: &str,
+    limit: u32,
+) -> Result<Vec<OwnedTransactionMatch>, AppError> {
+    sqlx::query_as!(
+        OwnedTransactionMatch,
+        r#"
+        SELECT t.id, t.wallet_id, w.user_id as "user_id!", u.email, t.transaction_type, t.amount,
+               t.description, t.status, t.created_at, t.completed_at, t.failed_at, t.reversed_at,
+               ts_rank(t.search_vector, q) as "rank!"
+        FROM transactions t
+        CROSS JOIN websearch_to_tsquery('english', $1) q
+        JOIN wallets w ON w.id = t.wallet_id
+        JOIN users u ON u.id = w.user_id
+        WHERE t.search_vector @@ q
+        ORDER BY 13 DESC, t.created_at DESC, t.id DESC
+        LIMIT $2
+        "#,
+        query,
+        i64::from(limit)
+    )
+    .fetch_all(pool)
+    .timed("search_repo::search_all_transactions")
+    .await
+    .map_err(AppError::DatabaseError)
+}
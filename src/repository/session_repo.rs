@@ -0,0 +1,111 @@
+use crate::domain::models::Session;
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// SESSION REPOSITORY
+// ============================================================================
+
+/// Store a new session
+pub async fn create_session(
+    pool: &PgPool,
+    id: &str,
+    user_id: Uuid,
+    csrf_token: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<Session, AppError> {
+    let session = sqlx::query_as!(
+        Session,
+        r#"
+        INSERT INTO sessions (id, user_id, csrf_token, expires_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, user_id, csrf_token, flash, created_at, expires_at
+        "#,
+        id,
+        user_id,
+        csrf_token,
+        expires_at
+    )
+    .fetch_one(pool)
+    .timed("session_repo::create_session")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(session)
+}
+
+/// Find a session that hasn't expired yet
+pub async fn find_active_session(pool: &PgPool, id: &str) -> Result<Option<Session>, AppError> {
+    let session = sqlx::query_as!(
+        Session,
+        r#"
+        SELECT id, user_id, csrf_token, flash, created_at, expires_at
+        FROM sessions
+        WHERE id = $1 AND expires_at > NOW()
+          AND user_id IN (SELECT id FROM users WHERE deleted_at IS NULL)
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .timed("session_repo::find_active_session")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(session)
+}
+
+/// Delete a single session (logout)
+pub async fn delete_session(pool: &PgPool, id: &str) -> Result<(), AppError> {
+    sqlx::query!(r#"DELETE FROM sessions WHERE id = $1"#, id)
+        .execute(pool)
+        .timed("session_repo::delete_session")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Delete every session belonging to a user (log out everywhere)
+pub async fn delete_user_sessions(pool: &PgPool, user_id: Uuid) -> Result<u64, AppError> {
+    let result = sqlx::query!(r#"DELETE FROM sessions WHERE user_id = $1"#, user_id)
+        .execute(pool)
+        .timed("session_repo::delete_user_sessions")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected())
+}
+
+/// Set the flash message for the next page view
+pub async fn set_flash(pool: &PgPool, id: &str, message: &str) -> Result<(), AppError> {
+    sqlx::query!(r#"UPDATE sessions SET flash = $1 WHERE id = $2"#, message, id)
+        .execute(pool)
+        .timed("session_repo::set_flash")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Read and clear the flash message in one statement
+pub async fn take_flash(pool: &PgPool, id: &str) -> Result<Option<String>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        UPDATE sessions new
+        SET flash = NULL
+        FROM sessions old
+        WHERE new.id = old.id AND new.id = $1 AND old.flash IS NOT NULL
+        RETURNING old.flash
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .timed("session_repo::take_flash")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(row.and_then(|r| r.flash))
+}
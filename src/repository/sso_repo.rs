@@ -0,0 +1,457 @@
+use crate::domain::ids;
+use crate::domain::models::{Organization, User};
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use crate::repository::unit_of_work::with_transaction;
+use crate::repository::user_repo;
+use crate::utils::secret::SecretString;
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+// ============================================================================
+// SSO REPOSITORY
+// ============================================================================
+// Organizations, their identity providers, the IdP identities linked to
+// local users and sign-ins in flight (migration 025).
+//
+// A login request is answered at most once: `take_login_request` deletes
+// the row as it reads it, so a replayed callback or assertion finds
+// nothing.
+
+/// An organization's identity provider
+#[derive(Debug, Clone)]
+pub struct SsoConnection {
+    pub organization_id: Uuid,
+    /// "OIDC" or "SAML"
+    pub protocol: String,
+    pub oidc_issuer: Option<String>,
+    pub oidc_client_id: Option<String>,
+    pub oidc_client_secret: Option<SecretString>,
+    pub saml_idp_entity_id: Option<String>,
+    pub saml_sso_url: Option<String>,
+    /// PEM
+    pub saml_idp_certificate: Option<String>,
+    /// Lowercase, e.g. ["acme.com"]
+    pub email_domains: Vec<String>,
+    pub jit_provisioning: bool,
+    pub enabled: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What an admin sets up; the fields of the other protocol are None
+#[derive(Debug)]
+pub struct NewSsoConnection<'a> {
+    pub protocol: &'a str,
+    pub oidc_issuer: Option<&'a str>,
+    pub oidc_client_id: Option<&'a str>,
+    pub oidc_client_secret: Option<&'a str>,
+    pub saml_idp_entity_id: Option<&'a str>,
+    pub saml_sso_url: Option<&'a str>,
+    pub saml_idp_certificate: Option<&'a str>,
+    pub email_domains: &'a [String],
+    pub jit_provisioning: bool,
+    pub enabled: bool,
+}
+
+/// A sign-in waiting for the identity provider's answer
+#[derive(Debug, Clone)]
+pub struct LoginRequest {
+    /// The OIDC `state` or the SAML AuthnRequest ID
+    pub id: String,
+    pub organization_id: Uuid,
+    pub nonce: Option<String>,
+    pub code_verifier: Option<String>,
+}
+
+// ============================================================================
+// ORGANIZATIONS
+// ============================================================================
+
+/// Create an organization
+///
+/// Takes a pool or an open transaction (see
+/// `organization_repo::create_business_account`).
+pub async fn create_organization(
+    executor: impl PgExecutor<'_>,
+    name: &str,
+    slug: &str,
+) -> Result<Organization, AppError> {
+    sqlx::query_as!(
+        Organization,
+        r#"
+        INSERT INTO organizations (id, name, slug)
+        VALUES ($1, $2, $3)
+        RETURNING id, name, slug, created_at
+        "#,
+        ids::new_id(),
+        name,
+        slug
+    )
+    .fetch_one(executor)
+    .timed("sso_repo::create_organization")
+    .await
+    .map_err(|e| {
+        if let sqlx::Error::Database(db_err) = &e {
+            if db_err.is_unique_violation() {
+                return AppError::validation("An organization with this slug already exists");
+            }
+        }
+        AppError::DatabaseError(e)
+    })
+}
+
+/// Every organization, oldest first
+pub async fn list_organizations(pool: &PgPool) -> Result<Vec<Organization>, AppError> {
+    sqlx::query_as!(
+        Organization,
+        r#"
+        SELECT id, name, slug, created_at
+        FROM organizations
+        ORDER BY created_at
+        "#
+    )
+    .fetch_all(pool)
+    .timed("sso_repo::list_organizations")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// An organization by id
+pub async fn find_organization(pool: &PgPool, id: Uuid) -> Result<Option<Organization>, AppError> {
+    sqlx::query_as!(
+        Organization,
+        r#"
+        SELECT id, name, slug, created_at
+        FROM organizations
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .timed("sso_repo::find_organization")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// An organization by slug
+pub async fn find_organization_by_slug(
+    pool: &PgPool,
+    slug: &str,
+) -> Result<Option<Organization>, AppError> {
+    sqlx::query_as!(
+        Organization,
+        r#"
+        SELECT id, name, slug, created_at
+        FROM organizations
+        WHERE slug = $1
+        "#,
+        slug
+    )
+    .fetch_optional(pool)
+    .timed("sso_repo::find_organization_by_slug")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+// ============================================================================
+// CONNECTIONS
+// ============================================================================
+
+/// Set up or replace an organization's identity provider
+pub async fn upsert_connection(
+    pool: &PgPool,
+    organization_id: Uuid,
+    connection: &NewSsoConnection<'_>,
+) -> Result<SsoConnection, AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO sso_connections
+            (organization_id, protocol, oidc_issuer, oidc_client_id, oidc_client_secret,
+             saml_idp_entity_id, saml_sso_url, saml_idp_certificate,
+             email_domains, jit_provisioning, enabled)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        ON CONFLICT (organization_id) DO UPDATE
+        SET protocol = EXCLUDED.protocol,
+            oidc_issuer = EXCLUDED.oidc_issuer,
+            oidc_client_id = EXCLUDED.oidc_client_id,
+            oidc_client_secret = EXCLUDED.oidc_client_secret,
+            saml_idp_entity_id = EXCLUDED.saml_idp_entity_id,
+            saml_sso_url = EXCLUDED.saml_sso_url,
+            saml_idp_certificate = EXCLUDED.saml_idp_certificate,
+            email_domains = EXCLUDED.email_domains,
+            jit_provisioning = EXCLUDED.jit_provisioning,
+            enabled = EXCLUDED.enabled,
+            updated_at = NOW()
+        "#,
+        organization_id,
+        connection.protocol,
+        connection.oidc_issuer,
+        connection.oidc_client_id,
+        connection.oidc_client_secret,
+        connection.saml_idp_entity_id,
+        connection.saml_sso_url,
+        connection.saml_idp_certificate,
+        connection.email_domains,
+        connection.jit_provisioning,
+        connection.enabled
+    )
+    .execute(pool)
+    .timed("sso_repo::upsert_connection")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    find_connection(pool, organization_id)
+        .await?
+        .ok_or_else(|| AppError::internal("Saved SSO connection disappeared"))
+}
+
+/// An organization's identity provider (enabled or not)
+pub async fn find_connection(
+    pool: &PgPool,
+    organization_id: Uuid,
+) -> Result<Option<SsoConnection>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT organization_id, protocol, oidc_issuer, oidc_client_id, oidc_client_secret,
+               saml_idp_entity_id, saml_sso_url, saml_idp_certificate,
+               email_domains, jit_provisioning, enabled, updated_at
+        FROM sso_connections
+        WHERE organization_id = $1
+        "#,
+        organization_id
+    )
+    .fetch_optional(pool)
+    .timed("sso_repo::find_connection")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(row.map(|row| SsoConnection {
+        organization_id: row.organization_id,
+        protocol: row.protocol,
+        oidc_issuer: row.oidc_issuer,
+        oidc_client_id: row.oidc_client_id,
+        oidc_client_secret: row.oidc_client_secret.map(SecretString::from),
+        saml_idp_entity_id: row.saml_idp_entity_id,
+        saml_sso_url: row.saml_sso_url,
+        saml_idp_certificate: row.saml_idp_certificate,
+        email_domains: row.email_domains,
+        jit_provisioning: row.jit_provisioning,
+        enabled: row.enabled,
+        updated_at: row.updated_at,
+    }))
+}
+
+/// Remove an organization's identity provider
+///
+/// Linked identities stay, so people are recognised again if it comes
+/// back. Returns false if there was none.
+pub async fn delete_connection(pool: &PgPool, organization_id: Uuid) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM sso_connections
+        WHERE organization_id = $1
+        "#,
+        organization_id
+    )
+    .execute(pool)
+    .timed("sso_repo::delete_connection")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// ============================================================================
+// LOGIN REQUESTS
+// ============================================================================
+
+/// Remember a sign-in sent to the identity provider
+///
+/// Also clears out expired ones, which are never answered.
+pub async fn create_login_request(
+    pool: &PgPool,
+    request: &LoginRequest,
+    expires_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        DELETE FROM sso_login_requests
+        WHERE expires_at < NOW()
+        "#
+    )
+    .execute(pool)
+    .timed("sso_repo::purge_login_requests")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO sso_login_requests (id, organization_id, nonce, code_verifier, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        request.id,
+        request.organization_id,
+        request.nonce,
+        request.code_verifier,
+        expires_at
+    )
+    .execute(pool)
+    .timed("sso_repo::create_login_request")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Take (and delete) a sign-in, if it hasn't expired by `now`
+pub async fn take_login_request(
+    pool: &PgPool,
+    id: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<LoginRequest>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        DELETE FROM sso_login_requests
+        WHERE id = $1
+        RETURNING id, organization_id, nonce, code_verifier, expires_at
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .timed("sso_repo::take_login_request")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(row
+        .filter(|row| row.expires_at > now)
+        .map(|row| LoginRequest {
+            id: row.id,
+            organization_id: row.organization_id,
+            nonce: row.nonce,
+            code_verifier: row.code_verifier,
+        }))
+}
+
+// ============================================================================
+// IDENTITIES
+// ============================================================================
+
+/// The user an IdP identity is linked to, noting the sign-in
+pub async fn find_identity_user(
+    pool: &PgPool,
+    organization_id: Uuid,
+    subject: &str,
+) -> Result<Option<Uuid>, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        UPDATE sso_identities
+        SET last_login_at = NOW()
+        WHERE organization_id = $1 AND subject = $2
+        RETURNING user_id
+        "#,
+        organization_id,
+        subject
+    )
+    .fetch_optional(pool)
+    .timed("sso_repo::find_identity_user")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// The organization a user belongs to, if any
+pub async fn find_user_organization(pool: &PgPool, user_id: Uuid) -> Result<Option<Uuid>, AppError> {
+    let organization_id = sqlx::query_scalar!(
+        r#"
+        SELECT organization_id
+        FROM users
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .timed("sso_repo::find_user_organization")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(organization_id.flatten())
+}
+
+/// Link an IdP identity to an existing user, making them a member of the
+/// organization
+pub async fn link_identity(
+    pool: &PgPool,
+    organization_id: Uuid,
+    subject: &str,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    with_transaction(pool, async |conn| {
+        insert_identity(&mut *conn, organization_id, subject, user_id).await
+    })
+    .await
+}
+
+/// Create a user (with a wallet) for an IdP identity signing in for the
+/// first time (just-in-time provisioning)
+pub async fn provision_user(
+    pool: &PgPool,
+    organization_id: Uuid,
+    subject: &str,
+    email: &str,
+    password_hash: &str,
+    full_name: &str,
+) -> Result<User, AppError> {
+    with_transaction(pool, async |conn| {
+        let user = user_repo::create_user(&mut *conn, email, password_hash, full_name).await?;
+        user_repo::create_wallet(&mut *conn, user.id).await?;
+        insert_identity(&mut *conn, organization_id, subject, user.id).await?;
+        Ok(user)
+    })
+    .await
+}
+
+async fn insert_identity(
+    conn: &mut PgConnection,
+    organization_id: Uuid,
+    subject: &str,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET organization_id = $1
+        WHERE id = $2
+        "#,
+        organization_id,
+        user_id
+    )
+    .execute(&mut *conn)
+    .timed("sso_repo::set_user_organization")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO sso_identities (organization_id, subject, user_id)
+        VALUES ($1, $2, $3)
+        "#,
+        organization_id,
+        subject,
+        user_id
+    )
+    .execute(&mut *conn)
+    .timed("sso_repo::insert_identity")
+    .await
+    .map_err(|e| {
+        if let sqlx::Error::Database(db_err) = &e {
+            if db_err.is_unique_violation() {
+                return AppError::validation(
+                    "This account is already linked to another identity at this organization",
+                );
+            }
+        }
+        AppError::DatabaseError(e)
+    })?;
+
+    Ok(())
+}
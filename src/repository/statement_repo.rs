@@ -0,0 +1,70 @@
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// STATEMENT REPOSITORY
+// ============================================================================
+// Reads the `monthly_wallet_totals` materialized view (migration 016): one
+// row per wallet per month with money in/out, the number of COMPLETED
+// transactions and the closing balance, archived transactions included.
+//
+// The view is refreshed once a day by the maintenance job, so the current
+// month lags behind until the next refresh; `refreshed_at` says how much.
+// Months without any transaction have no row.
+
+/// One month of a wallet's activity
+#[derive(Debug, Clone)]
+pub struct MonthlyTotals {
+    pub wallet_id: Uuid,
+    /// First day of the (UTC) month
+    pub month: NaiveDate,
+    pub money_in: Decimal,
+    pub money_out: Decimal,
+    pub transaction_count: i64,
+    /// Balance at the end of the month
+    pub closing_balance: Decimal,
+    /// When the view was last refreshed
+    pub refreshed_at: DateTime<Utc>,
+}
+
+/// The wallet's months from `from` to `to` (inclusive), oldest first
+pub async fn list_monthly_totals(
+    pool: &PgPool,
+    wallet_id: Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<MonthlyTotals>, AppError> {
+    sqlx::query_as!(
+        MonthlyTotals,
+        r#"
+        SELECT wallet_id as "wallet_id!", month as "month!", money_in as "money_in!",
+               money_out as "money_out!", transaction_count as "transaction_count!",
+               closing_balance as "closing_balance!", refreshed_at as "refreshed_at!"
+        FROM monthly_wallet_totals
+        WHERE wallet_id = $1 AND month BETWEEN $2 AND $3
+        ORDER BY month
+        "#,
+        wallet_id,
+        from,
+        to
+    )
+    .fetch_all(pool)
+    .timed("statement_repo::list_monthly_totals")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Recompute the view (readers keep seeing the old data until it's done)
+pub async fn refresh_monthly_totals(pool: &PgPool) -> Result<(), AppError> {
+    sqlx::query!(r#"REFRESH MATERIALIZED VIEW CONCURRENTLY monthly_wallet_totals"#)
+        .execute(pool)
+        .timed("statement_repo::refresh_monthly_totals")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
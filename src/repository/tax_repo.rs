@@ -0,0 +1,64 @@
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// TAX REPOSITORY
+// ============================================================================
+// Yearly totals for the tax summary, summed straight from the transaction
+// records (archived ones included) rather than from the monthly view, so a
+// summary is exact even for a year the nightly refresh hasn't caught up on.
+//
+// Each COMPLETED transaction is sorted by `transaction_category`
+// (migration 023): interest and fees are recognised by their description,
+// transfers by direction.
+
+/// A wallet's totals for one period
+#[derive(Debug, Clone)]
+pub struct PeriodTotals {
+    pub interest: Decimal,
+    pub fees: Decimal,
+    pub transfers_in: Decimal,
+    pub transfers_out: Decimal,
+    pub transfer_count: i64,
+}
+
+/// The wallet's totals for `from` (inclusive) to `to` (exclusive)
+pub async fn period_totals(
+    pool: &PgPool,
+    wallet_id: Uuid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<PeriodTotals, AppError> {
+    sqlx::query_as!(
+        PeriodTotals,
+        r#"
+        SELECT COALESCE(SUM(amount) FILTER (WHERE category = 'INTEREST'), 0) as "interest!",
+               COALESCE(SUM(amount) FILTER (WHERE category = 'FEE'), 0) as "fees!",
+               COALESCE(SUM(amount) FILTER (WHERE category = 'TRANSFER_IN'), 0) as "transfers_in!",
+               COALESCE(SUM(amount) FILTER (WHERE category = 'TRANSFER_OUT'), 0) as "transfers_out!",
+               COUNT(*) FILTER (WHERE category IN ('TRANSFER_IN', 'TRANSFER_OUT')) as "transfer_count!"
+        FROM (
+            SELECT amount, transaction_category(transaction_type, description) as category
+            FROM transactions
+            WHERE wallet_id = $1 AND status = 'COMPLETED'
+              AND created_at >= $2 AND created_at < $3
+            UNION ALL
+            SELECT amount, transaction_category(transaction_type, description)
+            FROM transactions_archive
+            WHERE wallet_id = $1 AND status = 'COMPLETED'
+              AND created_at >= $2 AND created_at < $3
+        ) t
+        "#,
+        wallet_id,
+        from,
+        to
+    )
+    .fetch_one(pool)
+    .timed("tax_repo::period_totals")
+    .await
+    .map_err(AppError::DatabaseError)
+}
@@ -0,0 +1,206 @@
+use crate::domain::models::TokenSession;
+use crate::error::AppError;
+use crate::repository::metrics::TimedQuery;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// TOKEN REPOSITORY
+// ============================================================================
+// Revoked JWTs (migration 033): single tokens by their `jti`, and
+// everything a user was issued up to `users.tokens_revoked_at`. Also the
+// sessions tokens are handed out for (migration 045).
+
+/// Put a token on the denylist until it expires
+pub async fn revoke(
+    pool: &PgPool,
+    jti: &str,
+    user_id: Uuid,
+    expires_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO revoked_tokens (jti, user_id, expires_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (jti) DO NOTHING
+        "#,
+        jti,
+        user_id,
+        expires_at
+    )
+    .execute(pool)
+    .timed("token_repo::revoke")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Revoke every token `user_id` was issued up to `at`
+pub async fn revoke_all(pool: &PgPool, user_id: Uuid, at: DateTime<Utc>) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE users SET tokens_revoked_at = $2 WHERE id = $1",
+        user_id,
+        at
+    )
+    .execute(pool)
+    .timed("token_repo::revoke_all")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Whether a token of `user_id`'s, issued at `issued_at`, was revoked
+/// (by its `jti`, or by a "log out everywhere" since)
+pub async fn is_revoked(
+    pool: &PgPool,
+    jti: &str,
+    user_id: Uuid,
+    issued_at: DateTime<Utc>,
+) -> Result<bool, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (SELECT 1 FROM revoked_tokens WHERE jti = $1)
+            OR EXISTS (SELECT 1 FROM users WHERE id = $2 AND tokens_revoked_at >= $3)
+            as "revoked!"
+        "#,
+        jti,
+        user_id,
+        issued_at
+    )
+    .fetch_one(pool)
+    .timed("token_repo::is_revoked")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Forget revoked tokens that have expired by now (they're refused anyway)
+///
+/// Returns how many were deleted.
+pub async fn delete_expired(pool: &PgPool) -> Result<u64, AppError> {
+    let result = sqlx::query!("DELETE FROM revoked_tokens WHERE expires_at < NOW()")
+        .execute(pool)
+        .timed("token_repo::delete_expired")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected())
+}
+
+/// A new session whose token expires at `expires_at`
+pub async fn insert_session(
+    pool: &PgPool,
+    id: Uuid,
+    user_id: Uuid,
+    device_name: &str,
+    user_agent: Option<&str>,
+    ip_address: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO token_sessions (id, user_id, device_name, user_agent, ip_address, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        id,
+        user_id,
+        device_name,
+        user_agent,
+        ip_address,
+        expires_at
+    )
+    .execute(pool)
+    .timed("token_repo::insert_session")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Whether `user_id`'s session `id` is still there, marking it seen (at
+/// most once a minute, so not every request writes)
+pub async fn touch_session(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        WITH session AS (
+            SELECT id, last_seen_at FROM token_sessions WHERE id = $1 AND user_id = $2
+        ), seen AS (
+            UPDATE token_sessions SET last_seen_at = NOW()
+            WHERE id IN (SELECT id FROM session WHERE last_seen_at < NOW() - INTERVAL '1 minute')
+        )
+        SELECT EXISTS (SELECT 1 FROM session) as "live!"
+        "#,
+        id,
+        user_id
+    )
+    .fetch_one(pool)
+    .timed("token_repo::touch_session")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// The user's sessions whose tokens haven't expired, most recently seen
+/// first; `current` marks the one with id `current`
+pub async fn list_sessions(
+    pool: &PgPool,
+    user_id: Uuid,
+    current: Option<Uuid>,
+) -> Result<Vec<TokenSession>, AppError> {
+    sqlx::query_as!(
+        TokenSession,
+        r#"
+        SELECT id, device_name, ip_address, user_agent, created_at, last_seen_at,
+               COALESCE(id = $2, FALSE) as "current!"
+        FROM token_sessions
+        WHERE user_id = $1 AND expires_at > NOW()
+        ORDER BY last_seen_at DESC
+        "#,
+        user_id,
+        current
+    )
+    .fetch_all(pool)
+    .timed("token_repo::list_sessions")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// End one of the user's sessions; false if they have no such session
+pub async fn delete_session(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM token_sessions WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .execute(pool)
+    .timed("token_repo::delete_session")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// End all of the user's sessions; returns how many there were
+pub async fn delete_user_sessions(pool: &PgPool, user_id: Uuid) -> Result<u64, AppError> {
+    let result = sqlx::query!("DELETE FROM token_sessions WHERE user_id = $1", user_id)
+        .execute(pool)
+        .timed("token_repo::delete_user_sessions")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected())
+}
+
+/// Forget sessions whose tokens have expired
+///
+/// Returns how many were deleted.
+pub async fn delete_expired_sessions(pool: &PgPool) -> Result<u64, AppError> {
+    let result = sqlx::query!("DELETE FROM token_sessions WHERE expires_at < NOW()")
+        .execute(pool)
+        .timed("token_repo::delete_expired_sessions")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected())
+}
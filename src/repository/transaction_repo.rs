@@ -0,0 +1,275 @@
+use crate::domain::ids;
+use crate::domain::models::{Transaction, TransactionStatus};
+use crate::error::AppError;
+use crate::repository::ledger_repo::{Journal, LedgerAccount};
+use crate::repository::metrics::TimedQuery;
+use crate::repository::unit_of_work::with_transaction;
+use crate::repository::wallet_repo;
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+// ============================================================================
+// TRANSACTION REPOSITORY
+// ============================================================================
+// The transaction lifecycle (migration 017):
+//
+//   PENDING --> COMPLETED --> REVERSED
+//      \
+//       `----> FAILED
+//
+// Why do we need this?
+// - Deposits, withdrawals and transfers settle at once and are still
+//   written straight as COMPLETED (`wallet_repo::record_transaction`).
+// - Scheduled transfers, holds and disputes need money that moves later,
+//   or moves back: they start a PENDING transaction and settle it with
+//   `complete_transaction` / `fail_transaction`, or undo a settled one with
+//   `reverse_transaction`.
+//
+// The balance only ever reflects COMPLETED transactions, so completing
+// applies the amount to the wallet and reversing takes it back, in the same
+// database transaction as the status change. Each state records when it was
+// entered (`completed_at`, `failed_at`, `reversed_at`; PENDING is
+// `created_at`). Any other transition fails with `TransactionFailed` and
+// changes nothing.
+
+/// A transaction row locked with FOR UPDATE until the transaction ends
+#[derive(Debug, Clone)]
+pub struct LockedTransaction {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub amount: Decimal,
+    pub status: String,
+    /// Money coming into the wallet (see `transaction_is_credit` in SQL)
+    pub is_credit: bool,
+}
+
+/// Record a PENDING transaction; no money moves until it is completed
+///
+/// Takes a pool or an open transaction.
+pub async fn insert_pending(
+    executor: impl PgExecutor<'_>,
+    wallet_id: Uuid,
+    transaction_type: &str,
+    amount: Decimal,
+    description: &str,
+) -> Result<Transaction, AppError> {
+    sqlx::query_as!(
+        Transaction,
+        r#"
+        INSERT INTO transactions (id, wallet_id, transaction_type, amount, description, status)
+        VALUES ($1, $2, $3, $4, $5, 'PENDING')
+        RETURNING id, wallet_id, transaction_type, amount, description, status, created_at,
+                  completed_at, failed_at, reversed_at, exchange_rate
+        "#,
+        ids::new_id(),
+        wallet_id,
+        transaction_type,
+        amount,
+        description
+    )
+    .fetch_one(executor)
+    .timed("transaction_repo::insert_pending")
+    .await
+    .map_err(|e| match &e {
+        sqlx::Error::Database(db_err) if db_err.is_foreign_key_violation() => {
+            AppError::not_found("Wallet")
+        }
+        _ => AppError::DatabaseError(e),
+    })
+}
+
+/// Lock a transaction by id (None if there is none)
+pub async fn lock_transaction(
+    conn: &mut PgConnection,
+    id: Uuid,
+) -> Result<Option<LockedTransaction>, AppError> {
+    sqlx::query_as!(
+        LockedTransaction,
+        r#"
+        SELECT id, wallet_id, amount, status,
+               transaction_is_credit(transaction_type, description) as "is_credit!"
+        FROM transactions
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_optional(conn)
+    .timed("transaction_repo::lock_transaction")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Move a transaction from `from` to `to`, stamping the time it entered `to`
+///
+/// Only the status changes; `transition` moves any money first.
+pub async fn set_status(
+    conn: &mut PgConnection,
+    id: Uuid,
+    from: TransactionStatus,
+    to: TransactionStatus,
+) -> Result<Transaction, AppError> {
+    sqlx::query_as!(
+        Transaction,
+        r#"
+        UPDATE transactions
+        SET status = $3::VARCHAR,
+            completed_at = CASE WHEN $3 = 'COMPLETED' THEN NOW() ELSE completed_at END,
+            failed_at = CASE WHEN $3 = 'FAILED' THEN NOW() ELSE failed_at END,
+            reversed_at = CASE WHEN $3 = 'REVERSED' THEN NOW() ELSE reversed_at END
+        WHERE id = $1 AND status = $2
+        RETURNING id, wallet_id, transaction_type, amount, description, status, created_at,
+                  completed_at, failed_at, reversed_at, exchange_rate
+        "#,
+        id,
+        from.as_str(),
+        to.as_str()
+    )
+    .fetch_optional(conn)
+    .timed("transaction_repo::set_status")
+    .await
+    .map_err(AppError::DatabaseError)?
+    .ok_or_else(|| AppError::internal("Transaction changed during status update"))
+}
+
+/// Settle a PENDING transaction: apply it to the wallet and mark it COMPLETED
+///
+/// Fails with `InsufficientBalance` (and stays PENDING) if a debit would
+/// take the wallet below its overdraft limit.
+pub async fn complete_transaction(pool: &PgPool, id: Uuid) -> Result<Transaction, AppError> {
+    with_transaction(pool, async |conn| {
+        transition(conn, id, TransactionStatus::Completed).await
+    })
+    .await
+}
+
+/// Give up on a PENDING transaction; no money ever moved
+pub async fn fail_transaction(pool: &PgPool, id: Uuid) -> Result<Transaction, AppError> {
+    with_transaction(pool, async |conn| {
+        transition(conn, id, TransactionStatus::Failed).await
+    })
+    .await
+}
+
+/// Undo a COMPLETED transaction: take its amount back and mark it REVERSED
+///
+/// Fails with `InsufficientBalance` (and stays COMPLETED) if taking back a
+/// credit would leave the wallet below its overdraft limit, and with
+/// `TransactionFailed` while something still holds it (see `holder`).
+pub async fn reverse_transaction(pool: &PgPool, id: Uuid) -> Result<Transaction, AppError> {
+    with_transaction(pool, async |conn| {
+        lock_transaction(conn, id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Transaction"))?;
+        if let Some(holder) = holder(conn, id).await? {
+            return Err(AppError::TransactionFailed(format!(
+                "the transaction is held by {}; that has to finish first",
+                holder
+            )));
+        }
+        transition(conn, id, TransactionStatus::Reversed).await
+    })
+    .await
+}
+
+/// What still has a say over a transaction, if anything
+///
+/// A transfer waiting to be confirmed, a bank payout in flight or an
+/// international transfer under way reverses its transaction itself when
+/// it is cancelled or comes back; reversing it underneath them would pay
+/// the money back twice.
+async fn holder(conn: &mut PgConnection, id: Uuid) -> Result<Option<&'static str>, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            EXISTS (
+                SELECT 1 FROM pending_transfers
+                WHERE (debit_id = $1 OR credit_id = $1) AND status = 'PENDING'
+            ) as "pending_transfer!",
+            EXISTS (
+                SELECT 1 FROM bank_payouts
+                WHERE transaction_id = $1 AND status = 'PENDING'
+            ) as "bank_payout!",
+            EXISTS (
+                SELECT 1 FROM international_transfers
+                WHERE transaction_id = $1 AND status IN ('SUBMITTED', 'IN_TRANSIT')
+            ) as "international_transfer!"
+        "#,
+        id
+    )
+    .fetch_one(&mut *conn)
+    .timed("transaction_repo::holder")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(if row.pending_transfer {
+        Some("a transfer waiting to be confirmed")
+    } else if row.bank_payout {
+        Some("a bank payout still in progress")
+    } else if row.international_transfer {
+        Some("an international transfer still under way")
+    } else {
+        None
+    })
+}
+
+/// Move a transaction to `next`, applying any balance change it brings
+///
+/// The composable form of the functions above, for callers that have more
+/// to write in the same transaction.
+pub async fn transition(
+    conn: &mut PgConnection,
+    id: Uuid,
+    next: TransactionStatus,
+) -> Result<Transaction, AppError> {
+    let transaction = lock_transaction(conn, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Transaction"))?;
+
+    let current = TransactionStatus::parse(&transaction.status).ok_or_else(|| {
+        AppError::internal(&format!("Unknown transaction status {}", transaction.status))
+    })?;
+    if !current.can_become(next) {
+        return Err(AppError::TransactionFailed(format!(
+            "a {} transaction cannot become {}",
+            current, next
+        )));
+    }
+
+    // What the wallet gains: the amount when a transaction settles,
+    // minus it when one is reversed (the other way round for debits)
+    let signed = if transaction.is_credit {
+        transaction.amount
+    } else {
+        -transaction.amount
+    };
+    let change = match next {
+        TransactionStatus::Completed => signed,
+        TransactionStatus::Reversed => -signed,
+        TransactionStatus::Pending | TransactionStatus::Failed => Decimal::ZERO,
+    };
+
+    if !change.is_zero() {
+        let wallet = wallet_repo::lock_wallet_by_id(conn, transaction.wallet_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Wallet"))?;
+
+        if change.is_sign_negative() && wallet.balance + change < -wallet.overdraft_limit {
+            return Err(AppError::InsufficientBalance);
+        }
+
+        // The other side is the outside world (or, for a transfer waiting
+        // to be confirmed, the money on its way)
+        let description = format!("Transaction {}", next.as_str().to_lowercase());
+        let (from, to) = if change.is_sign_positive() {
+            (LedgerAccount::External, LedgerAccount::Wallet(wallet.id))
+        } else {
+            (LedgerAccount::Wallet(wallet.id), LedgerAccount::External)
+        };
+        let journal = Journal::new(&description).movement(from, to, wallet.currency, change.abs());
+        wallet_repo::post_journal(conn, &journal).await?;
+    }
+
+    set_status(conn, id, current, next).await
+}
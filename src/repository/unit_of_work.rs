@@ -0,0 +1,55 @@
+use crate::error::AppError;
+use sqlx::{PgConnection, PgPool};
+
+// ============================================================================
+// UNIT OF WORK
+// ============================================================================
+// Runs several repository calls in one database transaction:
+//
+//   let wallet = with_transaction(pool, async |conn| {
+//       let wallet = wallet_repo::lock_wallet(conn, user_id)
+//           .await?
+//           .ok_or_else(|| AppError::not_found("Wallet"))?;
+//       wallet_repo::record_transaction(conn, wallet.id, "WITHDRAWAL", fee, "Monthly fee").await?;
+//       let journal = Journal::new("Monthly fee").movement(
+//           LedgerAccount::Wallet(wallet.id),
+//           LedgerAccount::External,
+//           wallet.currency,
+//           fee,
+//       );
+//       wallet_repo::post_journal(conn, &journal).await?.wallet(wallet.id)
+//   })
+//   .await?;
+//
+// Repository functions that are meant to be composed like this take
+// `&mut PgConnection` instead of `&PgPool`. They never begin or commit
+// anything themselves, so the caller decides what belongs together and the
+// SQL stays in the repository.
+//
+// The transaction commits if the closure returns Ok and rolls back if it
+// returns Err (or panics, or the request is cancelled: dropping an
+// unfinished transaction rolls it back too).
+
+/// Run `work` inside a transaction, committing only if it succeeds
+pub async fn with_transaction<T>(
+    pool: &PgPool,
+    work: impl AsyncFnOnce(&mut PgConnection) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+
+    match work(&mut tx).await {
+        Ok(value) => {
+            tx.commit().await.map_err(AppError::DatabaseError)?;
+            Ok(value)
+        }
+        Err(e) => {
+            // The original error is the interesting one; a failed rollback
+            // only means the connection is gone, and Postgres discards the
+            // transaction anyway
+            if let Err(rollback_error) = tx.rollback().await {
+                tracing::warn!("Failed to roll back transaction: {}", rollback_error);
+            }
+            Err(e)
+        }
+    }
+}
@@ -1,15 +1,26 @@
-use crate::domain::models::{User, Wallet};
+use crate::domain::ids;
+use crate::domain::models::{AccountStatus, Currency, User, UserRole, Wallet};
 use crate::error::AppError;
-use sqlx::PgPool;
+use crate::i18n::Locale;
+use crate::repository::metrics::TimedQuery;
+use crate::repository::unit_of_work::with_transaction;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgExecutor, PgPool};
 use uuid::Uuid;
 
 // ============================================================================
 // USER REPOSITORY
 // ============================================================================
+// Deleted accounts are soft deleted (users.deleted_at is set) so an admin
+// can restore them. Every lookup below skips them unless its name says
+// otherwise, e.g. `find_deleted_user_by_email`.
 
 /// Create a new user in the database
+///
+/// Takes a pool or an open transaction (see `create_user_with_wallet`).
 pub async fn create_user(
-    pool: &PgPool,
+    executor: impl PgExecutor<'_>,
     email: &str,
     password_hash: &str,
     full_name: &str,
@@ -17,17 +28,19 @@ pub async fn create_user(
     let user = sqlx::query_as!(
         User,
         r#"
-        INSERT INTO users (email, password_hash, full_name)
-        VALUES ($1, $2, $3)
+        INSERT INTO users (id, email, password_hash, full_name)
+        VALUES ($1, $2, $3, $4)
         RETURNING id, email, password_hash, full_name, 
                   created_at as "created_at!", 
                   updated_at as "updated_at!"
         "#,
+        ids::new_id(),
         email,
         password_hash,
         full_name
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
+    .timed("user_repo::create_user")
     .await
     .map_err(|e| {
         if let sqlx::Error::Database(db_err) = &e {
@@ -50,11 +63,12 @@ pub async fn find_user_by_email(pool: &PgPool, email: &str) -> Result<User, AppE
                created_at as "created_at!", 
                updated_at as "updated_at!"
         FROM users
-        WHERE email = $1
+        WHERE email = $1 AND deleted_at IS NULL
         "#,
         email
     )
     .fetch_one(pool)
+    .timed("user_repo::find_user_by_email")
     .await
     .map_err(|e| match e {
         sqlx::Error::RowNotFound => AppError::not_found("User"),
@@ -73,11 +87,183 @@ pub async fn find_user_by_id(pool: &PgPool, user_id: Uuid) -> Result<User, AppEr
                created_at as "created_at!", 
                updated_at as "updated_at!"
         FROM users
-        WHERE id = $1
+        WHERE id = $1 AND deleted_at IS NULL
         "#,
         user_id
     )
     .fetch_one(pool)
+    .timed("user_repo::find_user_by_id")
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => AppError::not_found("User"),
+        _ => AppError::DatabaseError(e),
+    })?;
+
+    Ok(user)
+}
+
+/// Get a user's preferred language tag (None = no preference saved)
+pub async fn get_user_locale(pool: &PgPool, user_id: Uuid) -> Result<Option<String>, AppError> {
+    let row = sqlx::query!(r#"SELECT locale FROM users WHERE id = $1 AND deleted_at IS NULL"#, user_id)
+        .fetch_optional(pool)
+        .timed("user_repo::get_user_locale")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(row.and_then(|r| r.locale))
+}
+
+/// Save a user's preferred language tag
+pub async fn set_user_locale(pool: &PgPool, user_id: Uuid, locale: &str) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"UPDATE users SET locale = $1, updated_at = NOW() WHERE id = $2 AND deleted_at IS NULL"#,
+        locale,
+        user_id
+    )
+    .execute(pool)
+    .timed("user_repo::set_user_locale")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Whether a user wants to hear about sign-ins from new devices
+pub async fn get_login_alerts(pool: &PgPool, user_id: Uuid) -> Result<bool, AppError> {
+    let row = sqlx::query!(
+        r#"SELECT login_alerts FROM users WHERE id = $1 AND deleted_at IS NULL"#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .timed("user_repo::get_login_alerts")
+    .await
+    .map_err(AppError::DatabaseError)?
+    .ok_or_else(|| AppError::not_found("User"))?;
+
+    Ok(row.login_alerts)
+}
+
+/// Turn a user's new-device sign-in alerts on or off
+pub async fn set_login_alerts(pool: &PgPool, user_id: Uuid, enabled: bool) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"UPDATE users SET login_alerts = $1, updated_at = NOW() WHERE id = $2 AND deleted_at IS NULL"#,
+        enabled,
+        user_id
+    )
+    .execute(pool)
+    .timed("user_repo::set_login_alerts")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Get a user's role
+pub async fn get_user_role(pool: &PgPool, user_id: Uuid) -> Result<UserRole, AppError> {
+    let row = sqlx::query!(r#"SELECT role FROM users WHERE id = $1 AND deleted_at IS NULL"#, user_id)
+        .fetch_optional(pool)
+        .timed("user_repo::get_user_role")
+        .await
+        .map_err(AppError::DatabaseError)?
+        .ok_or_else(|| AppError::not_found("User"))?;
+
+    UserRole::parse(&row.role)
+        .ok_or_else(|| AppError::internal(&format!("Unknown user role: {}", row.role)))
+}
+
+/// Replace a user's password hash
+///
+/// Takes a pool or an open transaction (see `password_reset_repo`).
+pub async fn update_password(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    password_hash: &str,
+) -> Result<(), AppError> {
+    let result = sqlx::query!(
+        r#"UPDATE users SET password_hash = $1, updated_at = NOW() WHERE id = $2 AND deleted_at IS NULL"#,
+        password_hash,
+        user_id
+    )
+    .execute(executor)
+    .timed("user_repo::update_password")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("User"));
+    }
+    Ok(())
+}
+
+/// Change a user's role
+pub async fn set_user_role(pool: &PgPool, user_id: Uuid, role: UserRole) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"UPDATE users SET role = $1, updated_at = NOW() WHERE id = $2 AND deleted_at IS NULL"#,
+        role.as_str(),
+        user_id
+    )
+    .execute(pool)
+    .timed("user_repo::set_user_role")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Get whether a user's account may move money
+pub async fn get_account_status(pool: &PgPool, user_id: Uuid) -> Result<AccountStatus, AppError> {
+    let row = sqlx::query!(
+        r#"SELECT account_status FROM users WHERE id = $1 AND deleted_at IS NULL"#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .timed("user_repo::get_account_status")
+    .await
+    .map_err(AppError::DatabaseError)?
+    .ok_or_else(|| AppError::not_found("User"))?;
+
+    AccountStatus::parse(&row.account_status).ok_or_else(|| {
+        AppError::internal(&format!("Unknown account status: {}", row.account_status))
+    })
+}
+
+/// Freeze, unfreeze or close a user's account
+pub async fn set_account_status(
+    pool: &PgPool,
+    user_id: Uuid,
+    status: AccountStatus,
+) -> Result<(), AppError> {
+    let result = sqlx::query!(
+        r#"UPDATE users SET account_status = $1, updated_at = NOW() WHERE id = $2 AND deleted_at IS NULL"#,
+        status.as_str(),
+        user_id
+    )
+    .execute(pool)
+    .timed("user_repo::set_account_status")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("User"));
+    }
+    Ok(())
+}
+
+/// Find a soft-deleted user by email (to tell "deleted" apart from "unknown")
+pub async fn find_deleted_user_by_email(pool: &PgPool, email: &str) -> Result<User, AppError> {
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        SELECT id, email, password_hash, full_name, 
+               created_at as "created_at!", 
+               updated_at as "updated_at!"
+        FROM users
+        WHERE email = $1 AND deleted_at IS NOT NULL
+        "#,
+        email
+    )
+    .fetch_one(pool)
+    .timed("user_repo::find_deleted_user_by_email")
     .await
     .map_err(|e| match e {
         sqlx::Error::RowNotFound => AppError::not_found("User"),
@@ -87,48 +273,203 @@ pub async fn find_user_by_id(pool: &PgPool, user_id: Uuid) -> Result<User, AppEr
     Ok(user)
 }
 
+/// When a user was deleted (None = active account)
+///
+/// Unlike the other lookups this sees deleted users, so restoring works.
+pub async fn get_user_deleted_at(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<DateTime<Utc>>, AppError> {
+    let row = sqlx::query!(r#"SELECT deleted_at FROM users WHERE id = $1"#, user_id)
+        .fetch_optional(pool)
+        .timed("user_repo::get_user_deleted_at")
+        .await
+        .map_err(AppError::DatabaseError)?
+        .ok_or_else(|| AppError::not_found("User"))?;
+
+    Ok(row.deleted_at)
+}
+
+/// Soft delete a user and end their web sessions
+pub async fn soft_delete_user(pool: &PgPool, user_id: Uuid) -> Result<DateTime<Utc>, AppError> {
+    with_transaction(pool, async |conn| {
+        let row = sqlx::query!(
+            r#"
+            UPDATE users SET deleted_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING deleted_at as "deleted_at!"
+            "#,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .timed("user_repo::soft_delete_user")
+        .await
+        .map_err(AppError::DatabaseError)?
+        .ok_or_else(|| AppError::not_found("User"))?;
+
+        sqlx::query!(r#"DELETE FROM sessions WHERE user_id = $1"#, user_id)
+            .execute(&mut *conn)
+            .timed("user_repo::soft_delete_user.sessions")
+            .await
+            .map_err(AppError::DatabaseError)?;
+
+        Ok(row.deleted_at)
+    })
+    .await
+}
+
+/// Undo a soft delete
+pub async fn restore_user(pool: &PgPool, user_id: Uuid) -> Result<User, AppError> {
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        UPDATE users SET deleted_at = NULL, updated_at = NOW()
+        WHERE id = $1 AND deleted_at IS NOT NULL
+        RETURNING id, email, password_hash, full_name, 
+                  created_at as "created_at!", 
+                  updated_at as "updated_at!"
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .timed("user_repo::restore_user")
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => AppError::not_found("Deleted user"),
+        _ => AppError::DatabaseError(e),
+    })?;
+
+    Ok(user)
+}
+
 // ============================================================================
 // WALLET REPOSITORY
 // ============================================================================
+// Wallets of deleted users are hidden too (the money stays put in case the
+// account is restored).
 
 /// Create a wallet for a user
-pub async fn create_wallet(pool: &PgPool, user_id: Uuid) -> Result<Wallet, AppError> {
+///
+/// Takes a pool or an open transaction (see `create_user_with_wallet`).
+pub async fn create_wallet(executor: impl PgExecutor<'_>, user_id: Uuid) -> Result<Wallet, AppError> {
     let wallet = sqlx::query_as!(
         Wallet,
         r#"
-        INSERT INTO wallets (user_id, balance, currency)
-        VALUES ($1, 0.00, 'USD')
-        RETURNING id, user_id, 
+        INSERT INTO wallets (id, user_id, balance, currency)
+        VALUES ($1, $2, 0.00, 'USD')
+        RETURNING id, user_id, organization_id,
                   balance as "balance!", 
-                  currency, 
+                  currency as "currency: Currency", 
                   created_at as "created_at!", 
                   updated_at as "updated_at!"
         "#,
+        ids::new_id(),
         user_id
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
+    .timed("user_repo::create_wallet")
     .await
     .map_err(AppError::DatabaseError)?;
 
     Ok(wallet)
 }
 
-/// Get a user's wallet
+/// Create a user and their wallet in one database transaction
+///
+/// Either both rows exist afterwards or neither does, so a crash can't
+/// leave an account without a wallet.
+pub async fn create_user_with_wallet(
+    pool: &PgPool,
+    email: &str,
+    password_hash: &str,
+    full_name: &str,
+) -> Result<(User, Wallet), AppError> {
+    with_transaction(pool, async |conn| {
+        let user = create_user(&mut *conn, email, password_hash, full_name).await?;
+        let wallet = create_wallet(&mut *conn, user.id).await?;
+        Ok((user, wallet))
+    })
+    .await
+}
+
+/// Users (deleted or not) that have no wallet
+///
+/// Registration creates both in one transaction now, but accounts from
+/// before that can still be missing theirs.
+pub async fn find_users_without_wallet(pool: &PgPool) -> Result<Vec<Uuid>, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT u.id
+        FROM users u
+        WHERE NOT EXISTS (SELECT 1 FROM wallets w WHERE w.user_id = u.id AND w.is_primary)
+        ORDER BY u.created_at
+        "#
+    )
+    .fetch_all(pool)
+    .timed("user_repo::find_users_without_wallet")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// A user as admins see them in searches
+#[derive(Debug, Clone, Serialize)]
+pub struct UserSummary {
+    pub id: Uuid,
+    pub email: String,
+    pub full_name: String,
+    pub role: String,
+    /// ACTIVE, FROZEN or CLOSED
+    pub account_status: String,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Set for soft-deleted accounts (still listed, so they can be restored)
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Users (deleted ones too) whose email or name contains `query`, or every
+/// user without one; newest first
+pub async fn search_users(
+    pool: &PgPool,
+    query: Option<&str>,
+    limit: i64,
+) -> Result<Vec<UserSummary>, AppError> {
+    sqlx::query_as!(
+        UserSummary,
+        r#"
+        SELECT id, email, full_name, role, account_status, created_at, deleted_at
+        FROM users
+        WHERE $1::TEXT IS NULL
+           OR strpos(lower(email), lower($1)) > 0
+           OR strpos(lower(full_name), lower($1)) > 0
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
+        "#,
+        query,
+        limit
+    )
+    .fetch_all(pool)
+    .timed("user_repo::search_users")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Get a user's primary wallet
 pub async fn get_wallet_by_user_id(pool: &PgPool, user_id: Uuid) -> Result<Wallet, AppError> {
     let wallet = sqlx::query_as!(
         Wallet,
         r#"
-        SELECT id, user_id, 
+        SELECT id, user_id, organization_id,
                balance as "balance!", 
-               currency, 
+               currency as "currency: Currency", 
                created_at as "created_at!", 
                updated_at as "updated_at!"
         FROM wallets
-        WHERE user_id = $1
+        WHERE user_id = $1 AND is_primary
+          AND user_id IN (SELECT id FROM users WHERE deleted_at IS NULL)
         "#,
         user_id
     )
     .fetch_one(pool)
+    .timed("user_repo::get_wallet_by_user_id")
     .await
     .map_err(|e| match e {
         sqlx::Error::RowNotFound => AppError::not_found("Wallet"),
@@ -138,33 +479,130 @@ pub async fn get_wallet_by_user_id(pool: &PgPool, user_id: Uuid) -> Result<Walle
     Ok(wallet)
 }
 
-/// Update wallet balance
-pub async fn update_wallet_balance(
-    pool: &PgPool,
-    wallet_id: Uuid,
-    new_balance: rust_decimal::Decimal,
-) -> Result<Wallet, AppError> {
-    let wallet = sqlx::query_as!(
-        Wallet,
-        r#"
-        UPDATE wallets
-        SET balance = $1, updated_at = NOW()
-        WHERE id = $2
-        RETURNING id, user_id, 
-                  balance as "balance!", 
-                  currency, 
-                  created_at as "created_at!", 
-                  updated_at as "updated_at!"
-        "#,
-        new_balance,
-        wallet_id
-    )
-    .fetch_one(pool)
-    .await
-    .map_err(|e| match e {
-        sqlx::Error::RowNotFound => AppError::not_found("Wallet"),
-        _ => AppError::DatabaseError(e),
-    })?;
+// ============================================================================
+// REPOSITORY TRAITS
+// ============================================================================
+// Services depend on these traits instead of a concrete `PgPool`, so the
+// business rules can be exercised against an in-memory fake. `PgPool`
+// implements them by delegating to the functions above, which means every
+// existing `&state.pool` call site keeps working unchanged.
 
-    Ok(wallet)
+/// Storage for user accounts
+#[async_trait::async_trait]
+pub trait UserRepository: Send + Sync {
+    /// Fails with `UserAlreadyExists` if the email is taken
+    async fn create_user(
+        &self,
+        email: &str,
+        password_hash: &str,
+        full_name: &str,
+    ) -> Result<User, AppError>;
+
+    /// Create a user and their empty USD wallet atomically
+    ///
+    /// Fails with `UserAlreadyExists` (and creates nothing) if the email is taken
+    async fn create_user_with_wallet(
+        &self,
+        email: &str,
+        password_hash: &str,
+        full_name: &str,
+    ) -> Result<(User, Wallet), AppError>;
+
+    /// Fails with `NotFound` if there is no such user
+    async fn find_user_by_email(&self, email: &str) -> Result<User, AppError>;
+
+    /// Fails with `NotFound` if there is no such user
+    async fn find_user_by_id(&self, user_id: Uuid) -> Result<User, AppError>;
+
+    /// Only matches soft-deleted users
+    async fn find_deleted_user_by_email(&self, email: &str) -> Result<User, AppError>;
+
+    /// `Some(when)` for deleted users; `NotFound` if the id doesn't exist at all
+    async fn get_user_deleted_at(&self, user_id: Uuid) -> Result<Option<DateTime<Utc>>, AppError>;
+
+    /// Fails with `NotFound` if there is no active user with this id
+    async fn soft_delete_user(&self, user_id: Uuid) -> Result<DateTime<Utc>, AppError>;
+
+    /// Fails with `NotFound` if there is no deleted user with this id
+    async fn restore_user(&self, user_id: Uuid) -> Result<User, AppError>;
+
+    async fn get_user_role(&self, user_id: Uuid) -> Result<UserRole, AppError>;
+
+    async fn set_user_role(&self, user_id: Uuid, role: UserRole) -> Result<(), AppError>;
+
+    /// Fails with `NotFound` if there is no active user with this id
+    async fn get_account_status(&self, user_id: Uuid) -> Result<AccountStatus, AppError>;
+
+    /// Fails with `NotFound` if there is no active user with this id
+    async fn set_account_status(&self, user_id: Uuid, status: AccountStatus) -> Result<(), AppError>;
+
+    /// The language the user chose (users.locale), English if none or one
+    /// we don't support; used for the emails we send them
+    async fn preferred_locale(&self, user_id: Uuid) -> Result<Locale, AppError>;
+}
+
+#[async_trait::async_trait]
+impl UserRepository for PgPool {
+    async fn create_user(
+        &self,
+        email: &str,
+        password_hash: &str,
+        full_name: &str,
+    ) -> Result<User, AppError> {
+        create_user(self, email, password_hash, full_name).await
+    }
+
+    async fn create_user_with_wallet(
+        &self,
+        email: &str,
+        password_hash: &str,
+        full_name: &str,
+    ) -> Result<(User, Wallet), AppError> {
+        create_user_with_wallet(self, email, password_hash, full_name).await
+    }
+
+    async fn find_user_by_email(&self, email: &str) -> Result<User, AppError> {
+        find_user_by_email(self, email).await
+    }
+
+    async fn find_user_by_id(&self, user_id: Uuid) -> Result<User, AppError> {
+        find_user_by_id(self, user_id).await
+    }
+
+    async fn find_deleted_user_by_email(&self, email: &str) -> Result<User, AppError> {
+        find_deleted_user_by_email(self, email).await
+    }
+
+    async fn get_user_deleted_at(&self, user_id: Uuid) -> Result<Option<DateTime<Utc>>, AppError> {
+        get_user_deleted_at(self, user_id).await
+    }
+
+    async fn soft_delete_user(&self, user_id: Uuid) -> Result<DateTime<Utc>, AppError> {
+        soft_delete_user(self, user_id).await
+    }
+
+    async fn restore_user(&self, user_id: Uuid) -> Result<User, AppError> {
+        restore_user(self, user_id).await
+    }
+
+    async fn get_user_role(&self, user_id: Uuid) -> Result<UserRole, AppError> {
+        get_user_role(self, user_id).await
+    }
+
+    async fn set_user_role(&self, user_id: Uuid, role: UserRole) -> Result<(), AppError> {
+        set_user_role(self, user_id, role).await
+    }
+
+    async fn get_account_status(&self, user_id: Uuid) -> Result<AccountStatus, AppError> {
+        get_account_status(self, user_id).await
+    }
+
+    async fn set_account_status(&self, user_id: Uuid, status: AccountStatus) -> Result<(), AppError> {
+        set_account_status(self, user_id, status).await
+    }
+
+    async fn preferred_locale(&self, user_id: Uuid) -> Result<Locale, AppError> {
+        let tag = get_user_locale(self, user_id).await?;
+        Ok(tag.as_deref().and_then(Locale::parse).unwrap_or_default())
+    }
 }
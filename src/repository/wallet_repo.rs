@@ -0,0 +1,854 @@
+use crate::domain::ids;
+use crate::domain::models::{Currency, Transaction, TransactionCursor, TransactionFilter, Wallet};
+use crate::error::AppError;
+use crate::repository::ledger_repo::{self, Journal, LedgerAccount};
+use crate::repository::metrics::TimedQuery;
+use crate::repository::scheduled_transfer_repo;
+use crate::repository::unit_of_work::with_transaction;
+use crate::repository::user_repo;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+// ============================================================================
+// WALLET REPOSITORY
+// ============================================================================
+// Balance changes and the transaction records that go with them.
+//
+// Each money movement is one method so the implementation can make it
+// atomic: the ledger journal (see ledger_repo), the cached balance and the
+// transaction record are written inside one database transaction. The
+// service layer keeps the business rules (positive amounts, no transfers
+// to yourself).
+//
+// Deposits and withdrawals touch one wallet, so they are a single
+// `UPDATE wallets SET balance = balance + $1 ... RETURNING`: Postgres adds
+// to whatever the balance is when the row is written, so there is nothing
+// read into Rust that a concurrent request could make stale. The
+// `balance_within_overdraft` CHECK refuses a withdrawal that would take the
+// balance too low (the service maps that to InsufficientBalance).
+//
+// Transfers check the sender's balance before moving anything, and have
+// two ways of stopping concurrent updates from losing money (`WriteMode`):
+// - OPTIMISTIC: read the wallets and their `version`s without locking, then
+//   only write if every version is unchanged (compare-and-swap). No row
+//   lock is held while we check and compute, so requests on a hot wallet
+//   don't queue up behind each other. If another request got there first,
+//   nothing is written and the method returns None.
+// - LOCKING: lock the wallets (`FOR UPDATE`) before reading them. Can't
+//   lose a race, but holds the rows until the transaction ends.
+// wallet_service tries optimistically a few times (WALLET_OPTIMISTIC_ATTEMPTS)
+// and falls back to locking, so requests never fail just for being
+// concurrent.
+//
+// Every UPDATE of a wallet bumps `version`, so both kinds see each other.
+// That also makes the daily spending limit (`DailyLimit`) safe to check
+// inside the write: what the wallet paid out today is read after the
+// wallet is locked (or its version read), so two payments at once can't
+// each fit under the limit and together go over it.
+// Both take the two wallets in id order (`ordered`), never sender first,
+// so opposite transfers can't deadlock; wallet_service retries the rare
+// deadlock Postgres still reports (40P01) all the same.
+//
+// A user can hold one wallet per currency (migration 035). The one made at
+// signup is their PRIMARY wallet, which everything keyed by user id means.
+//
+// Like the user lookups, these never touch wallets of deleted users.
+
+/// A transfer between two users' wallets
+#[derive(Debug, Clone, Copy)]
+pub struct TransferOrder {
+    pub sender_wallet_id: Uuid,
+    pub recipient_wallet_id: Uuid,
+    /// Taken from the sender, in their wallet's currency
+    pub amount: Decimal,
+    /// For wallets in different currencies: what one unit of the sender's
+    /// currency is worth in the recipient's
+    pub exchange_rate: Option<Decimal>,
+    /// The scheduled transfer this runs, if any
+    pub scheduled: Option<ScheduledRun>,
+    /// The sender's daily limit (None for a wallet without one)
+    pub daily_limit: Option<DailyLimit>,
+}
+
+/// The most a wallet may pay out (withdrawals and sent transfers) in a day,
+/// checked by the method that debits it
+#[derive(Debug, Clone, Copy)]
+pub struct DailyLimit {
+    pub limit: Decimal,
+    /// When the day started
+    pub since: DateTime<Utc>,
+}
+
+impl DailyLimit {
+    /// Fail with LimitExceeded if paying `amount` on top of `spent` today
+    /// goes over the limit
+    pub fn check(&self, spent: Decimal, amount: Decimal, currency: Currency) -> Result<(), AppError> {
+        if spent + amount > self.limit {
+            let remaining = (self.limit - spent).max(Decimal::ZERO);
+            return Err(AppError::LimitExceeded {
+                message: format!(
+                    "{} {} would take today's payments over your daily limit of {} ({} left)",
+                    amount, currency, self.limit, remaining
+                ),
+                limit: self.limit,
+                remaining: Some(remaining),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A scheduled transfer being run: marked COMPLETED (at `at`) in the same
+/// database transaction as the money moving
+#[derive(Debug, Clone, Copy)]
+pub struct ScheduledRun {
+    pub transfer_id: Uuid,
+    pub at: DateTime<Utc>,
+}
+
+/// How a write guards against concurrent writes to the same wallets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Read without locking; write only if the wallets' versions are
+    /// unchanged (the method returns None if they aren't)
+    Optimistic,
+    /// Lock the wallets first (`FOR UPDATE`); never returns None
+    Locking,
+}
+
+/// The spending limits a user chose for themselves (migration 037)
+///
+/// None = the configured limit applies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChosenLimits {
+    pub per_transaction: Option<Decimal>,
+    pub daily: Option<Decimal>,
+}
+
+/// Outcome of a transfer
+#[derive(Debug, Clone)]
+pub struct TransferResult {
+    /// The sender's wallet after the debit
+    pub sender_wallet: Wallet,
+    /// What the recipient was credited, in their wallet's currency
+    pub recipient_amount: Decimal,
+    /// The recipient's wallet's currency
+    pub recipient_currency: Currency,
+    /// The recipient's balance after the credit
+    pub recipient_balance: Decimal,
+}
+
+/// Storage for wallets and their transaction history
+#[async_trait::async_trait]
+pub trait WalletRepository: Send + Sync {
+    /// Create an empty USD wallet (the user's primary one)
+    async fn create_wallet(&self, user_id: Uuid) -> Result<Wallet, AppError>;
+
+    /// Open an empty wallet in `currency` next to the user's primary one
+    ///
+    /// Fails with a validation error if they hold one in that currency.
+    async fn create_currency_wallet(&self, user_id: Uuid, currency: Currency) -> Result<Wallet, AppError>;
+
+    /// The user's primary wallet (the one made at signup)
+    ///
+    /// Fails with `NotFound` if the user has no wallet
+    async fn get_wallet_by_user_id(&self, user_id: Uuid) -> Result<Wallet, AppError>;
+
+    /// Every wallet the user holds, the primary one first
+    async fn list_wallets(&self, user_id: Uuid) -> Result<Vec<Wallet>, AppError>;
+
+    /// Add `amount` to the user's wallet in `currency` (None = the primary
+    /// one) and record a DEPOSIT
+    async fn deposit(
+        &self,
+        user_id: Uuid,
+        currency: Option<Currency>,
+        amount: Decimal,
+    ) -> Result<Wallet, AppError>;
+
+    /// Subtract `amount` from the user's wallet in `currency` (None = the
+    /// primary one) and record a WITHDRAWAL
+    ///
+    /// Fails (and changes nothing) with `LimitExceeded` if it goes over
+    /// `daily_limit`, or if it would take the balance below the wallet's
+    /// overdraft limit (usually 0): with `InsufficientBalance`, or with the
+    /// `balance_within_overdraft` violation it stands for (see
+    /// `wallet_service::insufficient_balance_on_violation`).
+    async fn withdraw(
+        &self,
+        user_id: Uuid,
+        currency: Option<Currency>,
+        amount: Decimal,
+        daily_limit: Option<DailyLimit>,
+    ) -> Result<Wallet, AppError>;
+
+    /// Move money between two users' wallets, recording both sides
+    ///
+    /// Fails (and changes nothing) with `LimitExceeded` if it goes over the
+    /// order's daily limit, with `InsufficientBalance` if it would take the
+    /// sender below their overdraft limit, and with
+    /// `CurrencyMismatch` if the two wallets hold different currencies but
+    /// the order has no exchange rate. None if an optimistic write lost a
+    /// race. An order running a scheduled transfer fails with a validation
+    /// error if that already ran or was cancelled.
+    async fn transfer(
+        &self,
+        order: &TransferOrder,
+        mode: WriteMode,
+    ) -> Result<Option<TransferResult>, AppError>;
+
+    /// Up to `limit` of a wallet's transactions matching `filter`, newest
+    /// first, starting after `after` (keyset pagination; None = from the newest)
+    async fn list_transactions(
+        &self,
+        wallet_id: Uuid,
+        filter: &TransactionFilter,
+        after: Option<TransactionCursor>,
+        limit: u32,
+    ) -> Result<Vec<Transaction>, AppError>;
+
+    /// How many of a wallet's transactions match `filter` in all
+    async fn count_transactions(&self, wallet_id: Uuid, filter: &TransactionFilter) -> Result<u64, AppError>;
+
+    /// The spending limits the user chose (all None if they never did)
+    async fn chosen_limits(&self, user_id: Uuid) -> Result<ChosenLimits, AppError>;
+
+    /// Replace the spending limits the user chose
+    async fn set_chosen_limits(&self, user_id: Uuid, limits: &ChosenLimits) -> Result<(), AppError>;
+}
+
+#[async_trait::async_trait]
+impl WalletRepository for PgPool {
+    async fn create_wallet(&self, user_id: Uuid) -> Result<Wallet, AppError> {
+        user_repo::create_wallet(self, user_id).await
+    }
+
+    async fn create_currency_wallet(&self, user_id: Uuid, currency: Currency) -> Result<Wallet, AppError> {
+        sqlx::query_as!(
+            Wallet,
+            r#"
+            INSERT INTO wallets (id, user_id, balance, currency, is_primary)
+            VALUES ($1, $2, 0.00, $3, FALSE)
+            RETURNING id, user_id, organization_id, balance as "balance!", currency as "currency: Currency", created_at as "created_at!", updated_at as "updated_at!"
+            "#,
+            ids::new_id(),
+            user_id,
+            currency as Currency
+        )
+        .fetch_one(self)
+        .timed("wallet_repo::create_currency_wallet")
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+                AppError::validation(&format!("You already have a {} wallet", currency))
+            }
+            _ => AppError::DatabaseError(e),
+        })
+    }
+
+    async fn get_wallet_by_user_id(&self, user_id: Uuid) -> Result<Wallet, AppError> {
+        user_repo::get_wallet_by_user_id(self, user_id).await
+    }
+
+    async fn list_wallets(&self, user_id: Uuid) -> Result<Vec<Wallet>, AppError> {
+        sqlx::query_as!(
+            Wallet,
+            r#"
+            SELECT id, user_id, organization_id, balance as "balance!", currency as "currency: Currency", created_at as "created_at!", updated_at as "updated_at!"
+            FROM wallets
+            WHERE user_id = $1
+              AND user_id IN (SELECT id FROM users WHERE deleted_at IS NULL)
+            ORDER BY is_primary DESC, currency
+            "#,
+            user_id
+        )
+        .fetch_all(self)
+        .timed("wallet_repo::list_wallets")
+        .await
+        .map_err(AppError::DatabaseError)
+    }
+
+    async fn deposit(
+        &self,
+        user_id: Uuid,
+        currency: Option<Currency>,
+        amount: Decimal,
+    ) -> Result<Wallet, AppError> {
+        change_balance(self, user_id, currency, amount, BalanceChange::Credit).await
+    }
+
+    async fn withdraw(
+        &self,
+        user_id: Uuid,
+        currency: Option<Currency>,
+        amount: Decimal,
+        daily_limit: Option<DailyLimit>,
+    ) -> Result<Wallet, AppError> {
+        change_balance(self, user_id, currency, amount, BalanceChange::Debit(daily_limit)).await
+    }
+
+    async fn transfer(
+        &self,
+        order: &TransferOrder,
+        mode: WriteMode,
+    ) -> Result<Option<TransferResult>, AppError> {
+        let amount = order.amount;
+
+        with_transaction(self, async |conn| {
+            // Both wallets in id order, whichever way the money goes, so two
+            // opposite transfers can't each lock one and wait for the other
+            let [first, second] = ordered(order.sender_wallet_id, order.recipient_wallet_id);
+            let first = read_wallet_by_id(conn, first, mode).await?;
+            let second = read_wallet_by_id(conn, second, mode).await?;
+            let (sender, recipient) = match first {
+                Some(ref wallet) if wallet.id == order.sender_wallet_id => (first, second),
+                _ => (second, first),
+            };
+            let sender = sender.ok_or_else(|| AppError::not_found("Sender wallet"))?;
+
+            check_daily_limit(conn, order.daily_limit, &sender, amount).await?;
+            if sender.balance - amount < -sender.overdraft_limit {
+                return Err(AppError::InsufficientBalance);
+            }
+
+            let recipient = recipient.ok_or_else(|| AppError::not_found("Recipient wallet"))?;
+
+            let (recipient_amount, exchange_rate) = match order.exchange_rate {
+                _ if recipient.currency == sender.currency => (amount, None),
+                Some(rate) => ((amount * rate).round_dp(2), Some(rate)),
+                None => {
+                    return Err(AppError::currency_mismatch(sender.currency, recipient.currency))
+                }
+            };
+            if recipient_amount <= Decimal::ZERO {
+                return Err(AppError::validation("Amount is too small to convert"));
+            }
+
+            // Optimistic: only if neither wallet changed since we read it,
+            // claimed in id order too (one claimed wallet next to a lost one
+            // is only a version bump, so committing it is harmless)
+            if mode == WriteMode::Optimistic {
+                let mut wallets = [&sender, &recipient];
+                wallets.sort_by_key(|wallet| wallet.id);
+                for wallet in wallets {
+                    if !claim_version(conn, wallet.id, wallet.version).await? {
+                        return Ok(None);
+                    }
+                }
+            }
+
+            // A scheduled transfer completes with the money moving, or not
+            // at all if it already ran (or was cancelled)
+            if let Some(run) = order.scheduled {
+                if !scheduled_transfer_repo::complete(conn, run.transfer_id, run.at).await? {
+                    return Err(AppError::validation(
+                        "This scheduled transfer already ran or was cancelled",
+                    ));
+                }
+            }
+
+            // Debit the sender, credit the recipient (through the exchange
+            // account if the currencies differ)
+            let from = LedgerAccount::Wallet(sender.id);
+            let to = LedgerAccount::Wallet(recipient.id);
+            let journal = match exchange_rate {
+                None => Journal::new("Transfer").movement(from, to, sender.currency, amount),
+                Some(_) => Journal::new("Transfer")
+                    .movement(from, LedgerAccount::Exchange, sender.currency, amount)
+                    .movement(LedgerAccount::Exchange, to, recipient.currency, recipient_amount),
+            };
+            let posted = post_journal(conn, &journal).await?;
+
+            record_transfer(conn, sender.id, amount, "Transfer sent", exchange_rate).await?;
+            record_transfer(conn, recipient.id, recipient_amount, "Transfer received", exchange_rate)
+                .await?;
+
+            Ok(Some(TransferResult {
+                sender_wallet: posted.wallet(sender.id)?,
+                recipient_amount,
+                recipient_currency: recipient.currency,
+                recipient_balance: posted.wallet(recipient.id)?.balance,
+            }))
+        })
+        .await
+    }
+
+    async fn list_transactions(
+        &self,
+        wallet_id: Uuid,
+        filter: &TransactionFilter,
+        after: Option<TransactionCursor>,
+        limit: u32,
+    ) -> Result<Vec<Transaction>, AppError> {
+        // Row comparison on (created_at, id) matches the index order, and
+        // `id` breaks ties between transactions from the same instant.
+        // Filters left out are NULL and match everything.
+        sqlx::query_as!(
+            Transaction,
+            r#"
+            SELECT id, wallet_id, transaction_type, amount, description, status, created_at as "created_at!",
+                   completed_at, failed_at, reversed_at, exchange_rate
+            FROM transactions
+            WHERE wallet_id = $1
+              AND ($2::timestamptz IS NULL OR (created_at, id) < ($2, $3::uuid))
+              AND ($4::text IS NULL OR transaction_type = $4)
+              AND ($5::text IS NULL OR status = $5)
+              AND ($6::timestamptz IS NULL OR created_at >= $6)
+              AND ($7::timestamptz IS NULL OR created_at < $7)
+              AND ($8::numeric IS NULL OR amount >= $8)
+              AND ($9::numeric IS NULL OR amount <= $9)
+              AND ($10::text IS NULL OR search_vector @@ websearch_to_tsquery('english', $10))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $11
+            "#,
+            wallet_id,
+            after.map(|c| c.created_at),
+            after.map(|c| c.id),
+            filter.transaction_type.as_deref(),
+            filter.status.map(|s| s.as_str()),
+            filter.from,
+            filter.until,
+            filter.min_amount,
+            filter.max_amount,
+            filter.text.as_deref(),
+            i64::from(limit)
+        )
+        .fetch_all(self)
+        .timed("wallet_repo::list_transactions")
+        .await
+        .map_err(AppError::DatabaseError)
+    }
+
+    async fn count_transactions(&self, wallet_id: Uuid, filter: &TransactionFilter) -> Result<u64, AppError> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM transactions
+            WHERE wallet_id = $1
+              AND ($2::text IS NULL OR transaction_type = $2)
+              AND ($3::text IS NULL OR status = $3)
+              AND ($4::timestamptz IS NULL OR created_at >= $4)
+              AND ($5::timestamptz IS NULL OR created_at < $5)
+              AND ($6::numeric IS NULL OR amount >= $6)
+              AND ($7::numeric IS NULL OR amount <= $7)
+              AND ($8::text IS NULL OR search_vector @@ websearch_to_tsquery('english', $8))
+            "#,
+            wallet_id,
+            filter.transaction_type.as_deref(),
+            filter.status.map(|s| s.as_str()),
+            filter.from,
+            filter.until,
+            filter.min_amount,
+            filter.max_amount,
+            filter.text.as_deref()
+        )
+        .fetch_one(self)
+        .timed("wallet_repo::count_transactions")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(count as u64)
+    }
+
+    async fn chosen_limits(&self, user_id: Uuid) -> Result<ChosenLimits, AppError> {
+        let limits = sqlx::query_as!(
+            ChosenLimits,
+            "SELECT per_transaction, daily FROM spending_limits WHERE user_id = $1",
+            user_id
+        )
+        .fetch_optional(self)
+        .timed("wallet_repo::chosen_limits")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(limits.unwrap_or_default())
+    }
+
+    async fn set_chosen_limits(&self, user_id: Uuid, limits: &ChosenLimits) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO spending_limits (user_id, per_transaction, daily)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE
+            SET per_transaction = EXCLUDED.per_transaction,
+                daily = EXCLUDED.daily,
+                updated_at = NOW()
+            "#,
+            user_id,
+            limits.per_transaction,
+            limits.daily
+        )
+        .execute(self)
+        .timed("wallet_repo::set_chosen_limits")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// COMPOSABLE QUERIES
+// ============================================================================
+// Single statements on an open connection, for use inside
+// `unit_of_work::with_transaction`. None of them commits anything.
+
+/// A wallet row locked with FOR UPDATE until the transaction ends (or,
+/// from `read_wallet_by_id` in optimistic mode, only read)
+#[derive(Debug, Clone)]
+pub struct LockedWallet {
+    pub id: Uuid,
+    pub balance: Decimal,
+    pub overdraft_limit: Decimal,
+    pub currency: Currency,
+    /// Bumped by every update (see `claim_version`)
+    pub version: i64,
+}
+
+/// What a wallet has paid out since `since`: withdrawals and sent
+/// transfers, pending or completed
+pub async fn spent_since(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+    since: DateTime<Utc>,
+) -> Result<Decimal, AppError> {
+    // Recent, so never archived yet
+    sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(SUM(amount), 0) as "spent!"
+        FROM transactions
+        WHERE wallet_id = $1 AND created_at >= $2
+          AND status IN ('PENDING', 'COMPLETED')
+          AND transaction_type IN ('WITHDRAWAL', 'TRANSFER')
+          AND transaction_is_credit(transaction_type, description) IS NOT TRUE
+        "#,
+        wallet_id,
+        since
+    )
+    .fetch_one(conn)
+    .timed("wallet_repo::spent_since")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Fail with LimitExceeded if paying `amount` out of `wallet` goes over
+/// `daily_limit` (None = no limit)
+///
+/// Call with the wallet locked, or its version read for an optimistic
+/// write: a payment committed since is then either counted here or makes
+/// the write lose its race.
+pub async fn check_daily_limit(
+    conn: &mut PgConnection,
+    daily_limit: Option<DailyLimit>,
+    wallet: &LockedWallet,
+    amount: Decimal,
+) -> Result<(), AppError> {
+    let Some(daily_limit) = daily_limit else {
+        return Ok(());
+    };
+    let spent = spent_since(conn, wallet.id, daily_limit.since).await?;
+    daily_limit.check(spent, amount, wallet.currency)
+}
+
+/// Lock a user's primary wallet (None if the user has none or is deleted)
+pub async fn lock_wallet(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<Option<LockedWallet>, AppError> {
+    sqlx::query_as!(
+        LockedWallet,
+        r#"
+        SELECT id, balance, overdraft_limit, currency as "currency: Currency", version
+        FROM wallets
+        WHERE user_id = $1 AND is_primary
+          AND user_id IN (SELECT id FROM users WHERE deleted_at IS NULL)
+        FOR UPDATE
+        "#,
+        user_id
+    )
+    .fetch_optional(conn)
+    .timed("wallet_repo::lock_wallet")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Lock a wallet by its own id (None if there is none or its user is deleted)
+pub async fn lock_wallet_by_id(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+) -> Result<Option<LockedWallet>, AppError> {
+    sqlx::query_as!(
+        LockedWallet,
+        r#"
+        SELECT id, balance, overdraft_limit, currency as "currency: Currency", version
+        FROM wallets
+        WHERE id = $1
+          AND user_id IN (SELECT id FROM users WHERE deleted_at IS NULL)
+        FOR UPDATE
+        "#,
+        wallet_id
+    )
+    .fetch_optional(conn)
+    .timed("wallet_repo::lock_wallet_by_id")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// Two wallet ids in the order transfers lock their rows, so two transfers
+/// between the same wallets (whichever way) can't deadlock each other
+pub fn ordered(a: Uuid, b: Uuid) -> [Uuid; 2] {
+    if a <= b {
+        [a, b]
+    } else {
+        [b, a]
+    }
+}
+
+/// A wallet by its own id, locked or only read depending on `mode` (None
+/// if there is none or its user is deleted)
+pub async fn read_wallet_by_id(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+    mode: WriteMode,
+) -> Result<Option<LockedWallet>, AppError> {
+    if mode == WriteMode::Locking {
+        return lock_wallet_by_id(conn, wallet_id).await;
+    }
+
+    sqlx::query_as!(
+        LockedWallet,
+        r#"
+        SELECT id, balance, overdraft_limit, currency as "currency: Currency", version
+        FROM wallets
+        WHERE id = $1
+          AND user_id IN (SELECT id FROM users WHERE deleted_at IS NULL)
+        "#,
+        wallet_id
+    )
+    .fetch_optional(conn)
+    .timed("wallet_repo::read_wallet_by_id")
+    .await
+    .map_err(AppError::DatabaseError)
+}
+
+/// The compare-and-swap of an optimistic write: bump the wallet's version
+/// if it is still `version` (and hold the row until the transaction ends);
+/// false if another write got there first
+pub async fn claim_version(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+    version: i64,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "UPDATE wallets SET version = version + 1 WHERE id = $1 AND version = $2",
+        wallet_id,
+        version
+    )
+    .execute(conn)
+    .timed("wallet_repo::claim_version")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// The wallets a journal changed, as they are after it
+#[derive(Debug, Clone)]
+pub struct PostedJournal {
+    pub wallets: Vec<Wallet>,
+}
+
+impl PostedJournal {
+    /// One of the wallets in the journal
+    pub fn wallet(&self, wallet_id: Uuid) -> Result<Wallet, AppError> {
+        self.wallets
+            .iter()
+            .find(|w| w.id == wallet_id)
+            .cloned()
+            .ok_or_else(|| AppError::internal("Wallet is not part of the journal"))
+    }
+}
+
+/// Post a journal: write its ledger entries and move the cached balances
+/// of the wallets in it (the caller has checked them against the
+/// overdraft limits)
+///
+/// This is how balances change, apart from deposits and withdrawals (see
+/// `change_balance`), which move the balance first to learn which wallet
+/// the journal is for.
+pub async fn post_journal(conn: &mut PgConnection, journal: &Journal) -> Result<PostedJournal, AppError> {
+    ledger_repo::insert_journal(conn, journal).await?;
+
+    let mut wallets = Vec::new();
+    for (wallet_id, change) in journal.wallet_changes() {
+        let wallet = sqlx::query_as!(
+            Wallet,
+            r#"
+            UPDATE wallets
+            SET balance = balance + $1, version = version + 1, updated_at = NOW()
+            WHERE id = $2
+            RETURNING id, user_id, organization_id, balance as "balance!", currency as "currency: Currency", created_at as "created_at!", updated_at as "updated_at!"
+            "#,
+            change,
+            wallet_id
+        )
+        .fetch_one(&mut *conn)
+        .timed("wallet_repo::post_journal")
+        .await
+        .map_err(AppError::DatabaseError)?;
+        wallets.push(wallet);
+    }
+
+    Ok(PostedJournal { wallets })
+}
+
+/// Record a transaction that settled immediately (COMPLETED) against a wallet
+///
+/// For money that moves later, see `transaction_repo::insert_pending`.
+pub async fn record_transaction(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+    transaction_type: &str,
+    amount: Decimal,
+    description: &str,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO transactions (id, wallet_id, transaction_type, amount, description, status, completed_at)
+        VALUES ($1, $2, $3, $4, $5, 'COMPLETED', NOW())
+        "#,
+        ids::new_id(),
+        wallet_id,
+        transaction_type,
+        amount,
+        description
+    )
+    .execute(conn)
+    .timed("wallet_repo::record_transaction")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Record one side of a transfer (COMPLETED), with the rate it was
+/// converted at if the two wallets hold different currencies
+async fn record_transfer(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+    amount: Decimal,
+    description: &str,
+    exchange_rate: Option<Decimal>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO transactions
+            (id, wallet_id, transaction_type, amount, description, status, completed_at, exchange_rate)
+        VALUES ($1, $2, 'TRANSFER', $3, $4, 'COMPLETED', NOW(), $5)
+        "#,
+        ids::new_id(),
+        wallet_id,
+        amount,
+        description,
+        exchange_rate
+    )
+    .execute(conn)
+    .timed("wallet_repo::record_transfer")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BalanceChange {
+    Credit,
+    Debit(Option<DailyLimit>),
+}
+
+/// Deposit or withdraw in one statement: the UPDATE adds to the balance
+/// as it is when the row is written, and finds the wallet while it's at
+/// it (the CHECK constraint refuses a balance below the overdraft limit).
+/// A withdrawal with a daily limit locks the wallet first to check it.
+async fn change_balance(
+    pool: &PgPool,
+    user_id: Uuid,
+    currency: Option<Currency>,
+    amount: Decimal,
+    change: BalanceChange,
+) -> Result<Wallet, AppError> {
+    let (transaction_type, description, signed) = match change {
+        BalanceChange::Credit => ("DEPOSIT", "Deposit funds", amount),
+        BalanceChange::Debit(_) => ("WITHDRAWAL", "Withdraw funds", -amount),
+    };
+
+    with_transaction(pool, async |conn| {
+        // 1. Check the daily limit, with the wallet locked
+        if let BalanceChange::Debit(Some(daily_limit)) = change {
+            let wallet = sqlx::query_as!(
+                LockedWallet,
+                r#"
+                SELECT id, balance, overdraft_limit, currency as "currency: Currency", version
+                FROM wallets
+                WHERE user_id = $1
+                  AND CASE WHEN $2::currency IS NULL THEN is_primary ELSE currency = $2 END
+                  AND user_id IN (SELECT id FROM users WHERE deleted_at IS NULL)
+                FOR UPDATE
+                "#,
+                user_id,
+                currency as Option<Currency>
+            )
+            .fetch_optional(&mut *conn)
+            .timed("wallet_repo::change_balance")
+            .await
+            .map_err(AppError::DatabaseError)?
+            .ok_or_else(|| AppError::not_found("Wallet"))?;
+            check_daily_limit(conn, Some(daily_limit), &wallet, amount).await?;
+        }
+
+        // 2. Move the balance
+        let wallet = sqlx::query_as!(
+            Wallet,
+            r#"
+            UPDATE wallets
+            SET balance = balance + $1, version = version + 1, updated_at = NOW()
+            WHERE user_id = $2
+              AND CASE WHEN $3::currency IS NULL THEN is_primary ELSE currency = $3 END
+              AND user_id IN (SELECT id FROM users WHERE deleted_at IS NULL)
+            RETURNING id, user_id, organization_id, balance as "balance!", currency as "currency: Currency", created_at as "created_at!", updated_at as "updated_at!"
+            "#,
+            signed,
+            user_id,
+            currency as Option<Currency>
+        )
+        .fetch_optional(&mut *conn)
+        .timed("wallet_repo::change_balance")
+        .await
+        .map_err(AppError::DatabaseError)?
+        .ok_or_else(|| AppError::not_found("Wallet"))?;
+
+        // 3. Write the journal behind it and record the transaction
+        let account = LedgerAccount::Wallet(wallet.id);
+        let journal = match change {
+            BalanceChange::Credit => Journal::new(description).movement(
+                LedgerAccount::External,
+                account,
+                wallet.currency,
+                amount,
+            ),
+            BalanceChange::Debit(_) => Journal::new(description).movement(
+                account,
+                LedgerAccount::External,
+                wallet.currency,
+                amount,
+            ),
+        };
+        ledger_repo::insert_journal(conn, &journal).await?;
+        record_transaction(conn, wallet.id, transaction_type, amount, description).await?;
+
+        Ok(wallet)
+    })
+    .await
+}
@@ -0,0 +1,57 @@
+use axum::{routing::{delete, get, post, put}, Router};
+use crate::handlers::{admin, international, open_banking, sso};
+use crate::middleware::audit::admin_audit_middleware;
+use crate::routes::auth_routes::AppState;
+
+// ============================================================================
+// ADMIN ROUTES
+// ============================================================================
+
+/// Create the admin routes (nested at /api/admin)
+///
+/// Every handler here takes `AdminUser`, so only admins get in. Every
+/// change made through these routes is written to the audit log (see
+/// middleware/audit.rs).
+pub fn admin_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/log-level", get(admin::get_log_level).put(admin::set_log_level))
+        .route("/metrics/queries", get(admin::query_metrics))
+        .route("/audit-log", get(admin::get_audit_log))
+        .route("/broadcast", post(admin::broadcast))
+        .route(
+            "/debug-capture",
+            get(admin::list_debug_captures).delete(admin::clear_debug_captures),
+        )
+        .route("/debug-capture/targets", post(admin::start_debug_capture))
+        .route("/debug-capture/targets/:id", delete(admin::stop_debug_capture))
+        .route("/users", get(admin::list_users))
+        .route("/users/:id", delete(admin::delete_user))
+        .route("/users/:id/restore", post(admin::restore_user))
+        .route("/users/:id/freeze", post(admin::freeze_user))
+        .route("/users/:id/unfreeze", post(admin::unfreeze_user))
+        .route("/users/:id/wallets", get(admin::get_user_wallets))
+        .route("/wallets/:id/verify", get(admin::verify_wallet))
+        .route("/transactions/:id/reverse", post(admin::reverse_transaction))
+        .route("/transactions/restore", post(admin::restore_archived_transactions))
+        .route("/transactions/search", get(admin::search_transactions))
+        .route("/open-banking/clients", post(open_banking::register_client))
+        .route("/open-banking/clients/:id", delete(open_banking::revoke_client))
+        .route(
+            "/organizations",
+            get(sso::list_organizations).post(sso::create_organization),
+        )
+        .route(
+            "/organizations/:id/sso",
+            put(sso::set_sso_connection).delete(sso::delete_sso_connection),
+        )
+        .route("/international-transfers", get(international::queue))
+        .route(
+            "/international-transfers/:id/status",
+            put(international::update_status),
+        )
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            admin_audit_middleware,
+        ))
+        .with_state(state)
+}
@@ -0,0 +1,164 @@
+use axum::routing::{get, post};
+use axum::Router;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::services::ServeDir;
+use tower_http::trace::TraceLayer;
+use crate::handlers;
+use crate::middleware;
+use crate::routes::auth_routes::{auth_routes, AppState};
+use crate::routes::open_banking_routes::open_banking_routes;
+use crate::routes::openapi;
+
+// ============================================================================
+// APPLICATION ROUTER
+// ============================================================================
+// Everything the server answers, with its middleware:
+//
+// - /api/*          first-party JSON API (auth_routes)
+// - /api/docs, /api/openapi.json
+//                   its OpenAPI spec, and Swagger UI (openapi)
+// - /open-banking/* third-party account access (open_banking_routes)
+//                   (both can be recorded by an admin's debug capture)
+// - /, /dashboard/* web UI, with CSRF checks and HTML error pages
+// - /health, /healthz, /readyz
+//                   never rate limited, for probes
+// - /assets/*       static files
+//
+// The binary serves it; the integration tests (tests/common) call it
+// directly, so both exercise the same routes and layers.
+
+/// Build the whole application for `state`
+///
+/// Handlers that look at the caller's address (rate limiting, login
+/// history) need `ConnectInfo<SocketAddr>`: serve it with
+/// `into_make_service_with_connect_info`, or add the extension yourself.
+pub fn app(state: AppState) -> Router {
+    // Web UI (HTML pages, htmx forms)
+    let web_routes = Router::new()
+        .route("/", get(handlers::web::login_page))
+        .route("/login", get(handlers::web::login_page))
+        .route("/login", post(handlers::web::login_submit))
+        .route("/register", get(handlers::web::register_page))
+        .route("/register", post(handlers::web::register_submit))
+        .route("/password/forgot", get(handlers::web::forgot_password_page))
+        .route("/password/forgot", post(handlers::web::forgot_password_submit))
+        .route("/password/reset", get(handlers::web::reset_password_page))
+        .route("/password/reset", post(handlers::web::reset_password_submit))
+        .route("/account/unlock", get(handlers::web::unlock_account_page))
+        .route("/account/unlock", post(handlers::web::unlock_account_submit))
+        .route("/onboarding", get(handlers::web::onboarding_page))
+        .route("/onboarding/verify-email", post(handlers::web::onboarding_verify_email))
+        .route("/onboarding/resend-code", post(handlers::web::onboarding_resend_code))
+        .route("/onboarding/notifications", post(handlers::web::onboarding_notifications))
+        .route("/onboarding/two-factor", post(handlers::web::onboarding_two_factor))
+        .route("/onboarding/deposit", post(handlers::web::onboarding_deposit))
+        .route("/onboarding/skip-deposit", post(handlers::web::onboarding_skip_deposit))
+        .route("/dashboard", get(handlers::web::dashboard_page))
+        .route("/dashboard/transactions", get(handlers::web::transactions_page))
+        .route("/dashboard/deposit", get(handlers::web::deposit_page))
+        .route("/dashboard/deposit", post(handlers::web::deposit_submit))
+        .route("/dashboard/withdraw", get(handlers::web::withdraw_page))
+        .route("/dashboard/withdraw", post(handlers::web::withdraw_submit))
+        .route("/dashboard/transfer", get(handlers::web::transfer_page))
+        .route("/dashboard/transfer", post(handlers::web::transfer_submit))
+        .route("/dashboard/transfer/:id/confirm", post(handlers::web::transfer_confirm))
+        .route("/dashboard/transfer/:id/cancel", post(handlers::web::transfer_cancel))
+        .route("/dashboard/international", get(handlers::web::international_page))
+        .route("/dashboard/international", post(handlers::web::international_submit))
+        .route("/dashboard/international/quote", post(handlers::web::international_quote))
+        .route("/dashboard/approvals", get(handlers::web::approvals_page))
+        .route(
+            "/dashboard/approvals/:organization_id/:id/approve",
+            post(handlers::web::approve_expense),
+        )
+        .route(
+            "/dashboard/approvals/:organization_id/:id/reject",
+            post(handlers::web::reject_expense),
+        )
+        .route("/dashboard/referrals", get(handlers::web::referrals_page))
+        .route("/logout", post(handlers::web::logout))
+        // Single sign-on. SAML responses are posted from the identity
+        // provider's site, so (SameSite=Lax) they never carry a session
+        // cookie and the CSRF check lets them through
+        .route("/sso/login", get(handlers::sso::login))
+        .route("/sso/oidc/callback", get(handlers::sso::oidc_callback))
+        .route("/sso/saml/acs", post(handlers::sso::saml_acs))
+        .route("/sso/saml/metadata", get(handlers::sso::saml_metadata))
+        // Signed links to stored files (local storage backend)
+        .route("/files/*key", get(handlers::files::get_file))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::error_reporting::error_reporting_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::csrf::csrf_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::locale::locale_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::error_pages::html_error_middleware,
+        ))
+        .with_state(state.clone());
+
+    Router::new()
+        .nest(
+            "/api",
+            auth_routes(state.clone())
+                .layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    middleware::error_reporting::error_reporting_middleware,
+                ))
+                .layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    middleware::locale::locale_middleware,
+                ))
+                .layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    middleware::debug_capture::debug_capture_middleware,
+                )),
+        )
+        // Third-party account information, kept apart from the first-party API
+        .nest(
+            "/open-banking",
+            open_banking_routes(state.clone())
+                .layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    middleware::error_reporting::error_reporting_middleware,
+                ))
+                .layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    middleware::debug_capture::debug_capture_middleware,
+                )),
+        )
+        .merge(web_routes)
+        .merge(openapi::docs_routes().with_state(state.clone()))
+        // Our token signing keys, for services that check our tokens
+        .merge(
+            Router::new()
+                .route("/.well-known/jwks.json", get(handlers::auth::jwks))
+                .with_state(state.clone()),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::rate_limit::rate_limit_middleware,
+        ))
+        // Added after the rate limiter so probes are never throttled
+        .merge(
+            Router::new()
+                .route("/health", get(handlers::health::health))
+                .route("/healthz", get(handlers::health::healthz))
+                .route("/readyz", get(handlers::health::readyz))
+                .with_state(state.clone()),
+        )
+        .nest_service("/assets", ServeDir::new("assets"))
+        // Domain verification files, e.g. Apple Pay's merchant id association
+        .nest_service("/.well-known", ServeDir::new("assets/.well-known"))
+        .fallback(handlers::web::fallback)
+        .layer(TraceLayer::new_for_http())
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+}
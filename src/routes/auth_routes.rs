@@ -1,41 +1,304 @@
-use axum::{routing::{get, post}, Router};
-use crate::handlers::{auth, user, wallet};
+use axum::{routing::{delete, get, post, put}, Router};
+use crate::handlers::{
+    accounting, auth, bank, card, crypto, health, international, open_banking,
+    organization, payment_request, rates, referral, scheduled_transfer, tax, user, wallet,
+};
+use crate::config::Config;
+use crate::routes::admin_routes::admin_routes;
+use crate::routes::openapi::ApiRouter;
+use crate::error::AppError;
+use crate::logging::LogLevelHandle;
+use crate::services::clock::{Clock, SystemClock};
+use crate::services::debug_capture::DebugCapture;
+use crate::services::email_service::{self, EmailService, EmailTransport};
+use crate::services::exchange_rate_service::ExchangeRateService;
+use crate::services::notification_service::{NotificationService, Notifier};
+use crate::services::rate_limiter::{FixedWindowRateLimiter, RateLimiter};
 use sqlx::PgPool;
+use std::sync::Arc;
 
 // ============================================================================
 // APP STATE
 // ============================================================================
-// Combined state that holds both database pool and config
+// Everything handlers share: the database pool, the configuration, and the
+// services they talk to. Services with more than one implementation are
+// trait objects, so tests and other deployments can swap them in through
+// `AppState::builder`; anything not given to the builder comes from the
+// configuration.
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
-    pub jwt_secret: String,
-    // (Count, ResetTime)
-    pub rate_limiter: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<std::net::IpAddr, (u32, std::time::Instant)>>>,
-    pub email_service: crate::services::email_service::EmailService,
-    pub notification_service: crate::services::notification_service::NotificationService,
+    /// Signs download links (see `storage`)
+    pub jwt_secret: crate::utils::secret::SecretString,
+    /// Signs and checks access tokens (JWT_ALGORITHM)
+    pub jwt_keys: Arc<crate::utils::jwt::JwtKeys>,
+    /// Who may make another request (RATE_LIMIT_*)
+    pub rate_limiter: Arc<dyn RateLimiter>,
+    /// Writes our emails; delivered by the EMAIL_PROVIDER transport
+    pub email_service: EmailService,
+    /// Live messages to connected users (WebSockets)
+    pub notification_service: Arc<dyn Notifier>,
+    /// What time it is (rate-limit windows, token expiry, ...)
+    pub clock: Arc<dyn Clock>,
+    pub web_auth_mode: crate::config::WebAuthMode,
+    pub features: crate::config::FeatureFlags,
+    pub log_level: LogLevelHandle,
+    /// Where bank accounts are linked (None when BANK_PROVIDER=off)
+    pub bank_provider: Option<Arc<dyn crate::services::bank_provider::BankProvider>>,
+    /// Who charges Apple Pay / Google Pay top-ups (None when CARD_PROCESSOR=off)
+    pub card_processor: Option<Arc<dyn crate::services::card_processor::CardProcessor>>,
+    /// The Apple Pay merchant (None unless APPLE_PAY_MERCHANT_ID is set)
+    pub apple_pay: Option<Arc<crate::services::apple_pay::ApplePayMerchant>>,
+    /// Where crypto prices come from (None when CRYPTO_PROVIDER=off)
+    pub crypto_provider: Option<Arc<dyn crate::services::crypto_provider::CryptoProvider>>,
+    /// Where sign-ins come from (None without GEOIP_DATABASE)
+    pub geoip: Option<Arc<dyn crate::services::geoip::GeoIp>>,
+    /// Where exports and other files are kept (STORAGE_BACKEND)
+    pub storage: Arc<dyn crate::services::storage::StorageService>,
+    /// Cached exchange rates (refreshed in the background)
+    pub exchange_rates: crate::services::exchange_rate_service::ExchangeRateService,
+    /// Admin-started request/response captures (DEBUG_CAPTURE_CAPACITY)
+    pub debug_capture: Arc<DebugCapture>,
+    /// The full configuration, for profile checks like `is_production()`
+    pub config: Arc<Config>,
+}
+
+impl AppState {
+    /// Start building the state for `config`
+    ///
+    /// ```rust,ignore
+    /// let state = AppState::builder(pool, config, log_level)
+    ///     .email_transport(Arc::new(LogTransport))
+    ///     .build()?;
+    /// ```
+    pub fn builder(pool: PgPool, config: Config, log_level: LogLevelHandle) -> AppStateBuilder {
+        AppStateBuilder {
+            pool,
+            config,
+            log_level,
+            email_transport: None,
+            notifier: None,
+            clock: None,
+            rate_limiter: None,
+        }
+    }
+}
+
+/// Builds an `AppState`, with the configured services unless told otherwise
+pub struct AppStateBuilder {
+    pool: PgPool,
+    config: Config,
+    log_level: LogLevelHandle,
+    email_transport: Option<Arc<dyn EmailTransport>>,
+    notifier: Option<Arc<dyn Notifier>>,
+    clock: Option<Arc<dyn Clock>>,
+    rate_limiter: Option<Arc<dyn RateLimiter>>,
+}
+
+impl AppStateBuilder {
+    /// Deliver email with `transport` instead of the EMAIL_PROVIDER one
+    pub fn email_transport(mut self, transport: Arc<dyn EmailTransport>) -> Self {
+        self.email_transport = Some(transport);
+        self
+    }
+
+    /// Push live messages with `notifier` instead of over WebSockets
+    pub fn notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Tell the time with `clock` (token expiry and the default rate
+    /// limiter use it too)
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Limit requests with `rate_limiter` instead of RATE_LIMIT_*
+    pub fn rate_limiter(mut self, rate_limiter: Arc<dyn RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Put the state together
+    ///
+    /// Fails if a configured service can't be set up (an unreadable
+    /// GEOIP_DATABASE, JWT key or Apple Pay certificate, a malformed
+    /// REDIS_URL).
+    pub fn build(self) -> Result<AppState, AppError> {
+        let config = self.config;
+        let notifier = self
+            .notifier
+            .unwrap_or_else(|| Arc::new(NotificationService::new(self.pool.clone())));
+        let clock = self.clock.unwrap_or_else(|| Arc::new(SystemClock));
+        let rate_limiter: Arc<dyn RateLimiter> = match self.rate_limiter {
+            Some(rate_limiter) => rate_limiter,
+            None => Arc::new(FixedWindowRateLimiter::from_config(&config, clock.clone())?),
+        };
+        let email_transport = self
+            .email_transport
+            .unwrap_or_else(|| email_service::transport_from_config(&config));
+        let debug_capture = Arc::new(DebugCapture::from_config(&config, clock.clone()));
+        let bank_provider = crate::services::bank_provider::from_config(&config, &self.pool);
+        let email_service = EmailService::queued(email_transport, self.pool.clone());
+
+        Ok(AppState {
+            pool: self.pool,
+            jwt_secret: config
+                .jwt_secret()
+                .cloned()
+                .ok_or_else(|| AppError::internal("No JWT secret configured"))?,
+            jwt_keys: Arc::new(crate::utils::jwt::JwtKeys::from_config(&config)?),
+            rate_limiter,
+            email_service,
+            notification_service: notifier,
+            clock,
+            web_auth_mode: config.web_auth_mode,
+            features: config.features,
+            log_level: self.log_level,
+            bank_provider,
+            card_processor: crate::services::card_processor::from_config(&config),
+            apple_pay: crate::services::apple_pay::ApplePayMerchant::from_config(&config)?
+                .map(Arc::new),
+            crypto_provider: crate::services::crypto_provider::from_config(&config),
+            geoip: crate::services::geoip::from_config(&config)?,
+            storage: crate::services::storage::from_config(&config)?,
+            exchange_rates: ExchangeRateService::from_config(&config),
+            debug_capture,
+            config: Arc::new(config),
+        })
+    }
 }
 
 // ============================================================================
 // AUTH ROUTES  
 // ============================================================================
 
+/// The routes documented in the OpenAPI spec (see routes/openapi.rs)
+pub fn documented_routes() -> ApiRouter {
+    ApiRouter::new()
+        .route(auth::__path_register_handler, auth::register_handler)
+        .route(auth::__path_login_handler, auth::login_handler)
+        .route(auth::__path_forgot_password_handler, auth::forgot_password_handler)
+        .route(auth::__path_reset_password_handler, auth::reset_password_handler)
+        .route(auth::__path_unlock_account_handler, auth::unlock_account_handler)
+        .route(auth::__path_logout_handler, auth::logout_handler)
+        .route(auth::__path_logout_all_handler, auth::logout_all_handler)
+        .route(user::__path_get_me, user::get_me)
+        .route(wallet::__path_get_wallet, wallet::get_wallet)
+        .route(wallet::__path_list_wallets, wallet::list_wallets)
+        .route(wallet::__path_create_wallet, wallet::create_wallet)
+        .route(wallet::__path_deposit, wallet::deposit)
+        .route(wallet::__path_withdraw, wallet::withdraw)
+        .route(wallet::__path_transfer, wallet::transfer)
+        .route(wallet::__path_get_history, wallet::get_history)
+}
+
 /// Create the authentication routes
 pub fn auth_routes(state: AppState) -> Router {
-    Router::new()
+    documented_routes()
+        .into_router()
         // Public routes (no authentication required)
-        .route("/register", post(auth::register_handler))
-        .route("/login", post(auth::login_handler))
+        .route("/webhooks/bank", post(bank::webhook))
+        .route("/rates", get(rates::get_rates))
+        .route("/version", get(health::version))
         // Protected routes (authentication required)
-        .route("/me", get(user::get_me))
-        .route("/wallet", get(wallet::get_wallet))
-        .route("/wallet/deposit", post(wallet::deposit))
-        .route("/wallet/withdraw", post(wallet::withdraw))
-        .route("/wallet/transfer", post(wallet::transfer))
-        .route("/transactions", get(wallet::get_history))
-        // WebSocket route
+        .route("/me/locale", put(user::update_locale))
+        .route("/me/logins", get(user::get_logins))
+        .route(
+            "/me/notifications",
+            get(user::get_notification_preferences).put(user::update_notification_preferences),
+        )
+        .route("/sessions", get(user::list_sessions))
+        .route("/sessions/:id", delete(user::revoke_session))
+        .route("/tokens", post(user::create_token))
+        .route("/referrals", get(referral::get_referrals))
+        .route("/wallet/transfer/:id/confirm", post(wallet::confirm_transfer))
+        .route("/wallet/transfer/:id/cancel", post(wallet::cancel_transfer))
+        .route("/limits", get(wallet::get_limits).put(wallet::set_limits))
+        .route("/transfers/schedule", post(scheduled_transfer::schedule))
+        .route("/transfers/scheduled", get(scheduled_transfer::list))
+        .route("/transfers/scheduled/:id", delete(scheduled_transfer::cancel))
+        .route("/requests", post(payment_request::create))
+        .route("/requests/incoming", get(payment_request::incoming))
+        .route("/requests/outgoing", get(payment_request::outgoing))
+        .route("/requests/:id/approve", post(payment_request::approve))
+        .route("/requests/:id/decline", post(payment_request::decline))
+        .route("/wallet/statements", get(wallet::get_statements))
+        .route("/tax-summary/:year", get(tax::get_tax_summary))
+        .route(
+            "/accounting/account-codes",
+            get(accounting::get_account_codes).put(accounting::set_account_codes),
+        )
+        .route("/accounting/journal", get(accounting::get_journal))
+        .route("/wallet/deposit/bank", post(bank::deposit))
+        .route("/wallet/payouts", get(bank::list_payouts).post(bank::payout))
+        .route("/wallet/deposit/card", post(card::deposit))
+        .route("/wallet/deposit/card/config", get(card::get_config))
+        .route("/wallet/deposit/saved-card", post(card::deposit_saved))
+        .route("/wallet/cards", get(card::list_cards))
+        .route("/wallet/cards/:id", delete(card::delete_card))
+        .route("/wallet/cards/:id/default", put(card::set_default_card))
+        .route("/apple-pay/merchant-session", post(card::apple_pay_session))
+        .route(
+            "/wallet/international-transfers",
+            get(international::list_transfers).post(international::submit),
+        )
+        .route("/wallet/international-transfers/quote", post(international::quote))
+        .route("/wallet/international-transfers/:id", get(international::get_transfer))
+        .route("/bank/link-token", post(bank::create_link_token))
+        .route("/bank/accounts", get(bank::list_accounts).post(bank::link_accounts))
+        .route("/bank/accounts/:id", delete(bank::unlink_account))
+        .route("/crypto/quotes", get(crypto::get_quotes))
+        .route("/crypto/holdings", get(crypto::get_holdings))
+        .route("/crypto/trades", get(crypto::list_trades).post(crypto::create_trade))
+        .route("/open-banking/consents", get(open_banking::list_my_consents))
+        .route(
+            "/open-banking/consents/:id",
+            get(open_banking::get_my_consent).delete(open_banking::revoke_my_consent),
+        )
+        .route("/open-banking/consents/:id/authorise", post(open_banking::authorise_consent))
+        .route("/open-banking/consents/:id/reject", post(open_banking::reject_consent))
+        .route("/organizations", get(organization::list_mine).post(organization::create))
+        .route(
+            "/organizations/invitations/accept",
+            post(organization::accept_invitation),
+        )
+        .route("/organizations/:id", get(organization::get_organization))
+        .route("/organizations/:id/wallet", get(organization::get_wallet))
+        .route("/organizations/:id/wallet/deposit", post(organization::deposit))
+        .route("/organizations/:id/wallet/withdraw", post(organization::withdraw))
+        .route("/organizations/:id/wallet/transfer", post(organization::transfer))
+        .route("/organizations/:id/transactions", get(organization::get_history))
+        .route(
+            "/organizations/:id/expenses",
+            get(organization::list_expenses).post(organization::submit_expense),
+        )
+        .route(
+            "/organizations/:id/expenses/:expense_id/approve",
+            post(organization::approve_expense),
+        )
+        .route(
+            "/organizations/:id/expenses/:expense_id/reject",
+            post(organization::reject_expense),
+        )
+        .route(
+            "/organizations/:id/invitations",
+            get(organization::list_invitations).post(organization::invite),
+        )
+        .route(
+            "/organizations/:id/members/:user_id",
+            put(organization::update_member).delete(organization::remove_member),
+        )
+        .route("/transactions/search", get(wallet::search_history))
+        // Live notifications: WebSocket, or Server-Sent Events where
+        // proxies block WebSockets
         .route("/ws", get(crate::handlers::ws::websocket_handler))
-        .with_state(state)
+        .route("/events", get(crate::handlers::events::events_handler))
+        .with_state(state.clone())
+        // Admin routes (admin role required)
+        .nest("/admin", admin_routes(state))
 }
 
 // ============================================================================
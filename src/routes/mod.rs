@@ -1 +1,5 @@
+pub mod admin_routes;
+pub mod app;
 pub mod auth_routes;
+pub mod open_banking_routes;
+pub mod openapi;
@@ -0,0 +1,25 @@
+use axum::{routing::{get, post}, Router};
+use crate::handlers::open_banking;
+use crate::routes::auth_routes::AppState;
+
+// ============================================================================
+// OPEN BANKING ROUTES
+// ============================================================================
+
+/// Create the third-party Open Banking routes (nested at /open-banking)
+///
+/// Everything here except the token endpoint needs an Open Banking access
+/// token; first-party JWTs and sessions are not accepted.
+pub fn open_banking_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/token", post(open_banking::token))
+        .route("/consents", post(open_banking::create_consent))
+        .route(
+            "/consents/:id",
+            get(open_banking::get_consent).delete(open_banking::revoke_consent),
+        )
+        .route("/accounts", get(open_banking::list_accounts))
+        .route("/accounts/:id/balances", get(open_banking::get_balance))
+        .route("/accounts/:id/transactions", get(open_banking::list_transactions))
+        .with_state(state)
+}
@@ -0,0 +1,155 @@
+use axum::handler::Handler;
+use axum::routing::{MethodFilter, MethodRouter};
+use axum::Router;
+use utoipa::openapi::path::HttpMethod;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::OpenApi as Spec;
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+use crate::routes::auth_routes::{documented_routes, AppState};
+
+// ============================================================================
+// OPENAPI
+// ============================================================================
+// The JSON API describes itself: /api/openapi.json is its OpenAPI 3.1 spec,
+// /api/docs the same in Swagger UI.
+//
+// Handlers are documented with `#[utoipa::path]` next to their code, and
+// their request and response types derive `ToSchema` (api-types, feature
+// `openapi`). Documented routes are added with `ApiRouter::route`, which
+// serves the handler at the path and methods of its annotation and adds
+// the operation to the spec in one go, so a documented route is served
+// exactly as documented. (utoipa-axum does the same, but needs axum 0.8.)
+//
+// Only the core API (the api-types endpoints: accounts, sign-in, wallets,
+// transfers, history) is documented so far; the rest of /api is routed
+// the plain way and doesn't show up in the spec.
+
+/// Where the API is nested in the app (annotations are relative to it,
+/// like the routes in auth_routes)
+pub const API_PREFIX: &str = "/api";
+
+/// The parts of the spec that aren't operations
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "MyFintechApp API",
+        description = "Accounts, wallets and transfers. Errors are an `ErrorBody`; switch on its `code`."
+    ),
+    // Not referenced by an operation (`ErrorBody.code` is a string, so old
+    // clients survive new codes), listed so clients know the codes there are
+    components(schemas(api_types::ErrorCode)),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "auth", description = "Registration, sign-in and passwords"),
+        (name = "wallet", description = "Balances and moving money")
+    )
+)]
+struct ApiDoc;
+
+/// The `bearer` security scheme operations refer to
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut Spec) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build(),
+            ),
+        );
+    }
+}
+
+/// A router that documents what it serves
+pub struct ApiRouter {
+    router: Router<AppState>,
+    spec: Spec,
+}
+
+impl ApiRouter {
+    pub fn new() -> Self {
+        Self { router: Router::new(), spec: Spec::default() }
+    }
+
+    /// Serve `handler` as documented by its `#[utoipa::path]` (pass the
+    /// `__path_<handler>` type the annotation generates)
+    pub fn route<P, H, T>(mut self, _operation: P, handler: H) -> Self
+    where
+        P: utoipa::Path + utoipa::__dev::SchemaReferences,
+        H: Handler<T, AppState>,
+        T: 'static,
+    {
+        let path = P::path();
+        let methods = P::methods();
+
+        let method_router = methods.iter().fold(MethodRouter::new(), |router, method| {
+            router.on(method_filter(method), handler.clone())
+        });
+        self.router = self.router.route(&axum_path(&path), method_router);
+
+        self.spec.paths.add_path_operation(&path, methods, P::operation());
+        let mut schemas = Vec::new();
+        P::schemas(&mut schemas);
+        self.spec
+            .components
+            .get_or_insert_with(Default::default)
+            .schemas
+            .extend(schemas);
+
+        self
+    }
+
+    /// The router, to add the undocumented routes to
+    pub fn into_router(self) -> Router<AppState> {
+        self.router
+    }
+
+    /// The spec, relative to where the router is nested
+    pub fn into_spec(self) -> Spec {
+        self.spec
+    }
+}
+
+impl Default for ApiRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The whole spec of the JSON API
+pub fn spec() -> Spec {
+    ApiDoc::openapi().nest(API_PREFIX, documented_routes().into_spec())
+}
+
+/// /api/openapi.json and the Swagger UI at /api/docs
+pub fn docs_routes() -> Router<AppState> {
+    SwaggerUi::new(format!("{}/docs", API_PREFIX))
+        .url(format!("{}/openapi.json", API_PREFIX), spec())
+        .into()
+}
+
+fn method_filter(method: &HttpMethod) -> MethodFilter {
+    match method {
+        HttpMethod::Get => MethodFilter::GET,
+        HttpMethod::Post => MethodFilter::POST,
+        HttpMethod::Put => MethodFilter::PUT,
+        HttpMethod::Delete => MethodFilter::DELETE,
+        HttpMethod::Options => MethodFilter::OPTIONS,
+        HttpMethod::Head => MethodFilter::HEAD,
+        HttpMethod::Patch => MethodFilter::PATCH,
+        HttpMethod::Trace => MethodFilter::TRACE,
+    }
+}
+
+/// OpenAPI's `/wallets/{id}` is axum's `/wallets/:id`
+fn axum_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(param) => format!(":{}", param),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,403 @@
+use crate::config::Config;
+use crate::domain::ids;
+use crate::domain::models::Currency;
+use crate::error::AppError;
+use crate::repository::ledger_repo::{Journal, LedgerAccount};
+use crate::repository::{user_repo, wallet_repo};
+use crate::services::clock::SystemClock;
+use crate::services::{auth_service, maintenance_service};
+use crate::utils::jwt::JwtKeys;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// DEMO DATA SEEDER
+// ============================================================================
+// Fills a development or demo database with users that look lived-in:
+// different incomes and spending habits, so balances vary, and several
+// months of deposits, withdrawals and transfers between them.
+//
+// Run it with `my-fintech-app seed`, or set APP_SEED=true to seed when the
+// server starts. Either way it refuses to run in production.
+//
+// The history is generated from a fixed seed, so every fresh database gets
+// the same numbers. Users that already exist are skipped, so seeding twice
+// is safe; history is only generated for the users created by this run.
+//
+// Transactions are written directly (with backdated timestamps) instead of
+// through wallet_service, which always stamps NOW(). They are inserted
+// oldest first and each balance is set once at the end, so the dashboard
+// summary triggers see them in order.
+
+/// Password for every demo account
+pub const SEED_PASSWORD: &str = "password123";
+
+/// How many months of history each demo user gets (including this one)
+pub const HISTORY_MONTHS: u32 = 6;
+
+/// Seed for the history generator (fixed, so demos are reproducible)
+const RNG_SEED: u64 = 0x5EED_F1A7;
+
+/// One demo account and its money habits (amounts in cents)
+struct DemoProfile {
+    email: &'static str,
+    full_name: &'static str,
+    opening_balance: i64,
+    monthly_salary: i64,
+    monthly_rent: i64,
+    /// Card purchases per month
+    purchases_per_month: u32,
+}
+
+const DEMO_PROFILES: [DemoProfile; 6] = [
+    DemoProfile {
+        email: "alice@example.com",
+        full_name: "Alice Demo",
+        opening_balance: 250_000,
+        monthly_salary: 520_000,
+        monthly_rent: 180_000,
+        purchases_per_month: 12,
+    },
+    DemoProfile {
+        email: "bob@example.com",
+        full_name: "Bob Demo",
+        opening_balance: 40_000,
+        monthly_salary: 310_000,
+        monthly_rent: 120_000,
+        purchases_per_month: 15,
+    },
+    DemoProfile {
+        email: "carol@example.com",
+        full_name: "Carol Martinez",
+        opening_balance: 1_200_000,
+        monthly_salary: 890_000,
+        monthly_rent: 260_000,
+        purchases_per_month: 8,
+    },
+    DemoProfile {
+        email: "dave@example.com",
+        full_name: "Dave Okafor",
+        opening_balance: 5_000,
+        monthly_salary: 180_000,
+        monthly_rent: 75_000,
+        purchases_per_month: 18,
+    },
+    DemoProfile {
+        email: "erin@example.com",
+        full_name: "Erin Nakamura",
+        opening_balance: 90_000,
+        monthly_salary: 420_000,
+        monthly_rent: 0,
+        purchases_per_month: 10,
+    },
+    DemoProfile {
+        email: "frank@example.com",
+        full_name: "Frank Dubois",
+        opening_balance: 15_000,
+        monthly_salary: 0,
+        monthly_rent: 60_000,
+        purchases_per_month: 6,
+    },
+];
+
+/// Card purchases: (description, min cents, max cents)
+const PURCHASES: [(&str, i64, i64); 8] = [
+    ("Groceries", 2_500, 14_000),
+    ("Coffee shop", 350, 1_200),
+    ("Restaurant", 1_800, 9_500),
+    ("Fuel", 3_000, 8_000),
+    ("Online shopping", 1_500, 25_000),
+    ("Pharmacy", 800, 4_500),
+    ("Cinema", 1_200, 3_500),
+    ("ATM withdrawal", 2_000, 20_000),
+];
+
+/// Monthly account fee, charged to everyone (cents)
+const MONTHLY_FEE: i64 = 499;
+
+/// Yearly interest on the opening balance, in basis points (paid monthly)
+const INTEREST_RATE_BPS: i64 = 150;
+
+/// Occasional extra income: (description, min cents, max cents)
+const EXTRA_INCOME: [(&str, i64, i64); 3] = [
+    ("Freelance payment", 20_000, 150_000),
+    ("Refund", 1_000, 12_000),
+    ("Gift", 5_000, 30_000),
+];
+
+/// What a seeding run did
+#[derive(Debug, Default)]
+pub struct SeedSummary {
+    pub created: Vec<&'static str>,
+    pub skipped: Vec<&'static str>,
+    pub transactions: usize,
+}
+
+/// Create the demo users and their history
+pub async fn seed(pool: &PgPool, config: &Config) -> Result<SeedSummary, AppError> {
+    if config.is_production() {
+        return Err(AppError::validation("Refusing to seed demo data in production"));
+    }
+
+    let mut summary = SeedSummary::default();
+    let jwt_keys = JwtKeys::from_config(config)?;
+
+    // 1. Accounts (register creates the empty wallet too)
+    let mut accounts = Vec::new();
+    for profile in &DEMO_PROFILES {
+        if user_repo::find_user_by_email(pool, profile.email).await.is_ok() {
+            summary.skipped.push(profile.email);
+            continue;
+        }
+
+        let response = auth_service::register(
+            pool,
+            profile.email,
+            SEED_PASSWORD,
+            profile.full_name,
+            &jwt_keys,
+            None,
+            &SystemClock,
+        )
+        .await?;
+        let wallet = user_repo::get_wallet_by_user_id(pool, response.user.id).await?;
+
+        summary.created.push(profile.email);
+        accounts.push(DemoAccount {
+            profile,
+            wallet_id: wallet.id,
+            currency: wallet.currency,
+            balance: Decimal::ZERO,
+        });
+    }
+
+    if accounts.is_empty() {
+        return Ok(summary);
+    }
+
+    // 2. History, oldest first
+    let now = Utc::now();
+    let events = generate_history(&accounts, now);
+
+    // Partitions for the backdated months (otherwise rows pile up in
+    // transactions_default)
+    let months_back = HISTORY_MONTHS as i32 - 1;
+    maintenance_service::ensure_transaction_partitions(pool, -months_back..=0).await?;
+
+    // 3. Write everything in one transaction
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+
+    for event in events {
+        let entries = match event.kind {
+            EventKind::Credit { account, description } => {
+                accounts[account].balance += event.amount;
+                vec![(account, "DEPOSIT", description)]
+            }
+            EventKind::Debit { account, description } => {
+                // Never let the demo history overdraw a wallet
+                if accounts[account].balance < event.amount {
+                    continue;
+                }
+                accounts[account].balance -= event.amount;
+                vec![(account, "WITHDRAWAL", description)]
+            }
+            EventKind::Transfer { from, to } => {
+                if accounts[from].balance < event.amount {
+                    continue;
+                }
+                accounts[from].balance -= event.amount;
+                accounts[to].balance += event.amount;
+                // Same descriptions as wallet_repo::transfer (the dashboard
+                // summary uses them to tell sent from received)
+                vec![
+                    (from, "TRANSFER", "Transfer sent"),
+                    (to, "TRANSFER", "Transfer received"),
+                ]
+            }
+        };
+
+        for (account, transaction_type, description) in entries {
+            sqlx::query!(
+                r#"
+                INSERT INTO transactions (id, wallet_id, transaction_type, amount, description, status, created_at, completed_at)
+                VALUES ($1, $2, $3, $4, $5, 'COMPLETED', $6, $6)
+                "#,
+                ids::id_at(event.at),
+                accounts[account].wallet_id,
+                transaction_type,
+                event.amount,
+                description,
+                event.at
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::DatabaseError)?;
+
+            summary.transactions += 1;
+        }
+    }
+
+    // One journal per wallet for where its history leaves it (the history
+    // is made up, so there's nothing to book it against but the outside)
+    for account in accounts.iter().filter(|a| a.balance > Decimal::ZERO) {
+        let journal = Journal::new("Demo history").movement(
+            LedgerAccount::External,
+            LedgerAccount::Wallet(account.wallet_id),
+            account.currency,
+            account.balance,
+        );
+        wallet_repo::post_journal(&mut tx, &journal).await?;
+    }
+
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    Ok(summary)
+}
+
+// ============================================================================
+// HISTORY GENERATION
+// ============================================================================
+
+struct DemoAccount {
+    profile: &'static DemoProfile,
+    wallet_id: Uuid,
+    currency: Currency,
+    balance: Decimal,
+}
+
+struct Event {
+    at: DateTime<Utc>,
+    amount: Decimal,
+    kind: EventKind,
+}
+
+enum EventKind {
+    Credit { account: usize, description: &'static str },
+    Debit { account: usize, description: &'static str },
+    Transfer { from: usize, to: usize },
+}
+
+/// Every event for the last HISTORY_MONTHS months, sorted by time
+///
+/// Nothing is dated after `now`, so the current month is only partly filled.
+fn generate_history(accounts: &[DemoAccount], now: DateTime<Utc>) -> Vec<Event> {
+    let mut rng = DemoRng(RNG_SEED);
+    let mut events = Vec::new();
+    let this_month = NaiveDate::from_ymd_opt(now.year(), now.month(), 1).expect("valid date");
+
+    for months_ago in (0..HISTORY_MONTHS).rev() {
+        let month = this_month - Months::new(months_ago);
+        let first_month = months_ago == HISTORY_MONTHS - 1;
+
+        for (index, account) in accounts.iter().enumerate() {
+            let profile = account.profile;
+            let mut push = |at, amount, kind| events.push(Event { at, amount, kind });
+            let credit = |description| EventKind::Credit { account: index, description };
+            let debit = |description| EventKind::Debit { account: index, description };
+
+            if first_month && profile.opening_balance > 0 {
+                let at = rng.time_in(month, 0..1);
+                push(at, cents(profile.opening_balance), credit("Opening deposit"));
+            }
+
+            if profile.monthly_salary > 0 {
+                // Around the 1st, give or take a few percent
+                let amount = rng.vary(profile.monthly_salary, 3);
+                push(rng.time_in(month, 0..2), amount, credit("Salary"));
+            }
+
+            if profile.monthly_rent > 0 {
+                push(rng.time_in(month, 2..5), cents(profile.monthly_rent), debit("Rent"));
+            }
+
+            // Fees and interest use the descriptions the tax summary looks
+            // for (transaction_category, migration 023)
+            push(rng.time_in(month, 27..28), cents(MONTHLY_FEE), debit("Monthly account fee"));
+            let interest = profile.opening_balance * INTEREST_RATE_BPS / 10_000 / 12;
+            if interest > 0 {
+                push(rng.time_in(month, 27..28), cents(interest), credit("Savings interest"));
+            }
+
+            for _ in 0..profile.purchases_per_month {
+                let (description, min, max) = *rng.pick(&PURCHASES);
+                let amount = cents(rng.between(min, max));
+                push(rng.time_in(month, 0..28), amount, debit(description));
+            }
+
+            // One month in three brings something extra
+            if rng.below(3) == 0 {
+                let (description, min, max) = *rng.pick(&EXTRA_INCOME);
+                let amount = cents(rng.between(min, max));
+                push(rng.time_in(month, 5..28), amount, credit(description));
+            }
+        }
+
+        // A few transfers between the demo users each month
+        if accounts.len() > 1 {
+            for _ in 0..accounts.len() {
+                let from = rng.below(accounts.len() as u64) as usize;
+                let offset = 1 + rng.below(accounts.len() as u64 - 1) as usize;
+                let to = (from + offset) % accounts.len();
+                events.push(Event {
+                    at: rng.time_in(month, 3..28),
+                    amount: cents(rng.between(1_000, 20_000)),
+                    kind: EventKind::Transfer { from, to },
+                });
+            }
+        }
+    }
+
+    events.retain(|event| event.at <= now);
+    events.sort_by_key(|event| event.at);
+    events
+}
+
+fn cents(amount: i64) -> Decimal {
+    Decimal::new(amount, 2)
+}
+
+/// Tiny deterministic generator (SplitMix64); demo data doesn't need more
+struct DemoRng(u64);
+
+impl DemoRng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// 0..n
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    /// min..=max
+    fn between(&mut self, min: i64, max: i64) -> i64 {
+        min + self.below((max - min + 1) as u64) as i64
+    }
+
+    /// `amount` cents, plus or minus up to `percent`%
+    fn vary(&mut self, amount: i64, percent: i64) -> Decimal {
+        let spread = amount * percent / 100;
+        cents(self.between(amount - spread, amount + spread))
+    }
+
+    /// A random moment on one of `days` (0-based) of `month`, in the daytime
+    fn time_in(&mut self, month: NaiveDate, days: std::ops::Range<u64>) -> DateTime<Utc> {
+        let day = days.start + self.below(days.end - days.start);
+        let start = month.and_hms_opt(0, 0, 0).expect("valid time").and_utc();
+        start
+            + Duration::days(day as i64)
+            + Duration::hours(8 + self.below(13) as i64)
+            + Duration::minutes(self.below(60) as i64)
+            + Duration::seconds(self.below(60) as i64)
+    }
+}
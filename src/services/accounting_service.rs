@@ -0,0 +1,241 @@
+use crate::config::Config;
+use crate::domain::models::{
+    AccountCodeOverrides, AccountCodes, AccountCodesResponse, JournalCategory, JournalEntry,
+    JournalLine, JournalResponse,
+};
+use crate::error::AppError;
+use crate::repository::{accounting_repo, user_repo};
+use chrono::{Days, NaiveDate};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// ACCOUNTING SERVICE
+// ============================================================================
+// Exports wallet activity as journal entries the user's bookkeeping can
+// import. Nothing is pushed anywhere: the user (or a script of theirs)
+// pulls a date range from GET /accounting/journal, as JSON or as the CSV
+// import file of QuickBooks Online or Xero.
+//
+// Every COMPLETED transaction is one balanced, two-line entry:
+//
+//   money in  (deposit, interest, transfer received)
+//     Dr wallet account          Cr deposit / interest / transfer-in account
+//   money out (withdrawal, fee, transfer sent)
+//     Dr withdrawal / fee / transfer-out account          Cr wallet account
+//
+// The account codes are the server's defaults (ACCOUNTING_*_ACCOUNT)
+// unless the user has set their own.
+
+/// Longest range one export can cover
+const MAX_EXPORT_DAYS: u64 = 366;
+
+/// The user's account codes, with the defaults they fall back to
+pub async fn account_codes(
+    pool: &PgPool,
+    config: &Config,
+    user_id: Uuid,
+) -> Result<AccountCodesResponse, AppError> {
+    let overrides = accounting_repo::find_account_codes(pool, user_id).await?;
+    Ok(codes_response(config, overrides))
+}
+
+/// Replace the user's own account codes; fields left out go back to the
+/// default
+pub async fn set_account_codes(
+    pool: &PgPool,
+    config: &Config,
+    user_id: Uuid,
+    overrides: AccountCodeOverrides,
+) -> Result<AccountCodesResponse, AppError> {
+    let overrides = AccountCodeOverrides {
+        wallet: trimmed(overrides.wallet)?,
+        deposit: trimmed(overrides.deposit)?,
+        withdrawal: trimmed(overrides.withdrawal)?,
+        interest: trimmed(overrides.interest)?,
+        fee: trimmed(overrides.fee)?,
+        transfer_in: trimmed(overrides.transfer_in)?,
+        transfer_out: trimmed(overrides.transfer_out)?,
+    };
+
+    accounting_repo::set_account_codes(pool, user_id, &overrides).await?;
+    Ok(codes_response(config, overrides))
+}
+
+fn trimmed(code: Option<String>) -> Result<Option<String>, AppError> {
+    match code.as_deref().map(str::trim) {
+        Some("") => Err(AppError::validation("Account codes can't be blank")),
+        Some(code) => Ok(Some(code.to_string())),
+        None => Ok(None),
+    }
+}
+
+fn codes_response(config: &Config, overrides: AccountCodeOverrides) -> AccountCodesResponse {
+    let defaults = config.accounting_account_codes.clone();
+    let pick = |code: &Option<String>, default: &String| code.clone().unwrap_or(default.clone());
+
+    AccountCodesResponse {
+        account_codes: AccountCodes {
+            wallet: pick(&overrides.wallet, &defaults.wallet),
+            deposit: pick(&overrides.deposit, &defaults.deposit),
+            withdrawal: pick(&overrides.withdrawal, &defaults.withdrawal),
+            interest: pick(&overrides.interest, &defaults.interest),
+            fee: pick(&overrides.fee, &defaults.fee),
+            transfer_in: pick(&overrides.transfer_in, &defaults.transfer_in),
+            transfer_out: pick(&overrides.transfer_out, &defaults.transfer_out),
+        },
+        overrides,
+        defaults,
+    }
+}
+
+/// The journal entries for `from` to `to` (UTC days, both inclusive)
+pub async fn journal(
+    pool: &PgPool,
+    config: &Config,
+    user_id: Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<JournalResponse, AppError> {
+    if from > to {
+        return Err(AppError::validation("from must not be after to"));
+    }
+    if (to - from).num_days() as u64 >= MAX_EXPORT_DAYS {
+        return Err(AppError::validation(&format!(
+            "An export can cover at most {} days",
+            MAX_EXPORT_DAYS
+        )));
+    }
+    let end = to
+        .checked_add_days(Days::new(1))
+        .ok_or_else(|| AppError::validation("to is out of range"))?;
+
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+    let codes = account_codes(pool, config, user_id).await?.account_codes;
+    let rows = accounting_repo::list_journal_rows(
+        pool,
+        wallet.id,
+        from.and_hms_opt(0, 0, 0).expect("valid time").and_utc(),
+        end.and_hms_opt(0, 0, 0).expect("valid time").and_utc(),
+    )
+    .await?;
+
+    let entries = rows
+        .into_iter()
+        .filter_map(|row| {
+            let Some(category) = JournalCategory::parse(&row.category) else {
+                tracing::warn!("Transaction {} has unknown category {}", row.id, row.category);
+                return None;
+            };
+            let counter_account = codes.for_category(category).to_string();
+            let (debit_account, credit_account) = if category.is_credit() {
+                (codes.wallet.clone(), counter_account)
+            } else {
+                (counter_account, codes.wallet.clone())
+            };
+
+            Some(JournalEntry {
+                reference: reference(row.id),
+                transaction_id: row.id,
+                date: row.created_at.date_naive(),
+                category,
+                description: row.description.unwrap_or_else(|| "Wallet transaction".to_string()),
+                lines: vec![
+                    JournalLine {
+                        account_code: debit_account,
+                        debit: row.amount,
+                        credit: Decimal::ZERO,
+                    },
+                    JournalLine {
+                        account_code: credit_account,
+                        debit: Decimal::ZERO,
+                        credit: row.amount,
+                    },
+                ],
+            })
+        })
+        .collect();
+
+    Ok(JournalResponse {
+        from,
+        to,
+        currency: wallet.currency,
+        entries,
+    })
+}
+
+/// A short reference that fits QuickBooks' 21-character journal number:
+/// the random tail of the (v7) transaction id, e.g. "FA-8c2e41d90b7f"
+fn reference(transaction_id: Uuid) -> String {
+    let hex = transaction_id.simple().to_string();
+    format!("FA-{}", &hex[hex.len() - 12..])
+}
+
+/// Suggested download name, e.g. "journal-2026-01-01-to-2026-03-31-xero.csv"
+pub fn file_name(journal: &JournalResponse, format: &str) -> String {
+    format!("journal-{}-to-{}-{}.csv", journal.from, journal.to, format)
+}
+
+// ============================================================================
+// IMPORT FILES
+// ============================================================================
+// Both tools import journals from CSV; dates are written the way their
+// US templates expect (Xero follows the organisation's region, so
+// non-US Xero users pick "mm/dd/yyyy" when importing).
+//
+// Descriptions are user text, so they are quoted, and ones that would
+// start a spreadsheet formula get a leading apostrophe.
+
+/// Xero manual journal import: one row per line, lines of the same
+/// journal share its narration and date; positive amounts are debits
+pub fn to_xero_csv(journal: &JournalResponse, tax_rate: &str) -> String {
+    let mut csv = String::from("*Narration,*Date,Description,*AccountCode,*TaxRate,*Amount\n");
+    for entry in &journal.entries {
+        let narration = format!("{} {}", entry.reference, entry.description);
+        for line in &entry.lines {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                csv_field(&narration),
+                entry.date.format("%m/%d/%Y"),
+                csv_field(&entry.description),
+                csv_field(&line.account_code),
+                csv_field(tax_rate),
+                line.debit - line.credit,
+            ));
+        }
+    }
+    csv
+}
+
+/// QuickBooks Online journal entry import: one row per line, lines of the
+/// same journal share its number
+pub fn to_quickbooks_csv(journal: &JournalResponse) -> String {
+    let mut csv = String::from("Journal No,Journal Date,Account,Debits,Credits,Description,Currency Code\n");
+    let amount = |value: Decimal| if value.is_zero() { String::new() } else { value.to_string() };
+    for entry in &journal.entries {
+        for line in &entry.lines {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                entry.reference,
+                entry.date.format("%m/%d/%Y"),
+                csv_field(&line.account_code),
+                amount(line.debit),
+                amount(line.credit),
+                csv_field(&entry.description),
+                journal.currency,
+            ));
+        }
+    }
+    csv
+}
+
+/// A quoted CSV field
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
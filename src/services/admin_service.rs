@@ -0,0 +1,239 @@
+use crate::domain::models::{
+    AccountStatus, Transaction, User, UserRole, Wallet, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+    validate_password,
+};
+use crate::error::AppError;
+use crate::repository::audit_repo::{self, AuditEntry};
+use crate::repository::user_repo::{self, UserSummary};
+use crate::repository::{transaction_repo, UserRepository, WalletRepository};
+use crate::services::{auth_service, wallet_service};
+use crate::services::clock::{Clock, SystemClock};
+use crate::services::notification_service::Notifier;
+use crate::utils::jwt::JwtKeys;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// ADMIN SERVICE
+// ============================================================================
+// Role and account management. The very first admin can't be created through the API
+// (nobody is allowed to grant the role yet), so it's bootstrapped from the
+// command line: `my-fintech-app create-admin <email>`.
+//
+// Everything an admin changes through the API is written to the audit log
+// by the admin router (see middleware/audit.rs); `audit_log` reads it back.
+// The log is also what limits broadcasts: at most BROADCAST_LIMIT went out
+// (from any admin, on any instance) in the last BROADCAST_WINDOW.
+
+/// Broadcasts allowed per BROADCAST_WINDOW
+const BROADCAST_LIMIT: i64 = 5;
+
+/// The window broadcasts are counted over
+const BROADCAST_WINDOW: Duration = Duration::hours(1);
+
+/// Where broadcasts are sent from, as the audit log records it
+const BROADCAST_PATH: &str = "/api/admin/broadcast";
+
+/// Result of `create_admin`
+pub struct CreatedAdmin {
+    pub user: User,
+    /// Set when a new account was created with a generated password
+    pub generated_password: Option<String>,
+}
+
+/// Promote an existing user to admin, or create a new admin account
+///
+/// New accounts get `password` if given, otherwise a random one that is
+/// returned so it can be shown once.
+pub async fn create_admin(
+    repo: &(impl UserRepository + WalletRepository),
+    jwt_keys: &JwtKeys,
+    email: &str,
+    password: Option<&str>,
+) -> Result<CreatedAdmin, AppError> {
+    let (user, generated_password) = match repo.find_user_by_email(email).await {
+        Ok(user) => (user, None),
+        Err(AppError::NotFound(_)) => {
+            let generated = password.is_none().then(generate_password);
+            let password = password.or(generated.as_deref()).unwrap_or_default();
+
+            let response =
+                auth_service::register(
+                repo,
+                email,
+                password,
+                "Administrator",
+                jwt_keys,
+                None,
+                &SystemClock,
+            )
+            .await?;
+            let user = repo.find_user_by_id(response.user.id).await?;
+            (user, generated)
+        }
+        Err(e) => return Err(e),
+    };
+
+    repo.set_user_role(user.id, UserRole::Admin).await?;
+
+    Ok(CreatedAdmin {
+        user,
+        generated_password,
+    })
+}
+
+/// Is this user an admin?
+pub async fn is_admin(repo: &impl UserRepository, user_id: Uuid) -> Result<bool, AppError> {
+    Ok(repo.get_user_role(user_id).await? == UserRole::Admin)
+}
+
+/// Soft delete a user's account
+///
+/// The account can be brought back with `restore_user` until the
+/// retention window runs out.
+pub async fn delete_user(
+    repo: &impl UserRepository,
+    admin_id: Uuid,
+    user_id: Uuid,
+) -> Result<DateTime<Utc>, AppError> {
+    if user_id == admin_id {
+        return Err(AppError::validation("You cannot delete your own account"));
+    }
+
+    repo.soft_delete_user(user_id).await
+}
+
+/// Freeze or unfreeze a user's account
+///
+/// A frozen account can still sign in and look at its wallets and history,
+/// but can't move money until it's unfrozen.
+pub async fn set_account_status(
+    repo: &impl UserRepository,
+    admin_id: Uuid,
+    user_id: Uuid,
+    status: AccountStatus,
+) -> Result<(), AppError> {
+    if user_id == admin_id {
+        return Err(AppError::validation("You cannot freeze or unfreeze your own account"));
+    }
+    if repo.get_account_status(user_id).await? == AccountStatus::Closed {
+        return Err(AppError::validation("This account is closed"));
+    }
+
+    repo.set_account_status(user_id, status).await
+}
+
+/// Restore a soft-deleted account deleted less than `window_days` ago
+pub async fn restore_user(
+    repo: &impl UserRepository,
+    user_id: Uuid,
+    window_days: u32,
+    clock: &dyn Clock,
+) -> Result<User, AppError> {
+    let Some(deleted_at) = repo.get_user_deleted_at(user_id).await? else {
+        return Err(AppError::validation("This account is not deleted"));
+    };
+
+    if clock.now() - deleted_at > Duration::days(window_days.into()) {
+        return Err(AppError::validation(&format!(
+            "Accounts can only be restored within {} days of deletion",
+            window_days
+        )));
+    }
+
+    repo.restore_user(user_id).await
+}
+
+/// Users whose email or name contains `query` (every user without one),
+/// newest first; deleted accounts are included
+pub async fn search_users(
+    pool: &PgPool,
+    query: Option<&str>,
+    limit: Option<u32>,
+) -> Result<Vec<UserSummary>, AppError> {
+    let query = query.map(str::trim).filter(|query| !query.is_empty());
+    user_repo::search_users(pool, query, page_size(limit)).await
+}
+
+/// Any user's wallets, the primary one first
+pub async fn user_wallets(pool: &PgPool, user_id: Uuid) -> Result<Vec<Wallet>, AppError> {
+    // Fails with NotFound for unknown users (deleted ones are still shown)
+    user_repo::get_user_deleted_at(pool, user_id).await?;
+    wallet_service::list_wallets(pool, user_id).await
+}
+
+/// Undo a COMPLETED transaction, taking its amount back from (or giving it
+/// back to) its wallet
+pub async fn reverse_transaction(
+    pool: &PgPool,
+    admin_id: Uuid,
+    transaction_id: Uuid,
+) -> Result<Transaction, AppError> {
+    let transaction = transaction_repo::reverse_transaction(pool, transaction_id).await?;
+    tracing::warn!("↩️ Transaction {} reversed by admin {}", transaction.id, admin_id);
+    Ok(transaction)
+}
+
+/// Push an announcement to everyone connected right now; returns how many
+/// connections it went to
+///
+/// `kind` tells clients how to show it (e.g. "maintenance" as a banner).
+/// Fails with `RateLimited` (429) once BROADCAST_LIMIT have gone out in
+/// the last BROADCAST_WINDOW.
+pub async fn broadcast(
+    pool: &PgPool,
+    notifier: &dyn Notifier,
+    admin_id: Uuid,
+    kind: &str,
+    message: &str,
+    clock: &dyn Clock,
+) -> Result<usize, AppError> {
+    let now = clock.now();
+    let (recent, oldest) =
+        audit_repo::recent_successes(pool, "POST", BROADCAST_PATH, now - BROADCAST_WINDOW).await?;
+    if recent >= BROADCAST_LIMIT {
+        // The oldest one leaving the window frees a slot
+        let retry_after_secs = oldest
+            .map(|oldest| (oldest + BROADCAST_WINDOW - now).num_seconds())
+            .unwrap_or(0)
+            .max(1);
+        return Err(AppError::RateLimited {
+            what: "broadcasts".to_string(),
+            retry_after_secs,
+        });
+    }
+
+    let payload = serde_json::json!({ "type": "announcement", "kind": kind, "message": message });
+    let sent = notifier.broadcast_all(payload).await;
+    tracing::warn!("📣 {} broadcast by admin {} to {} connections: {}", kind, admin_id, sent, message);
+    Ok(sent)
+}
+
+/// The newest audit log entries, optionally only one admin's
+pub async fn audit_log(
+    pool: &PgPool,
+    admin_id: Option<Uuid>,
+    limit: Option<u32>,
+) -> Result<Vec<AuditEntry>, AppError> {
+    audit_repo::list(pool, admin_id, page_size(limit)).await
+}
+
+fn page_size(limit: Option<u32>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE).into()
+}
+
+/// 20 random letters and digits
+fn generate_password() -> String {
+    const CHARS: &[u8] = b"abcdefghijkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    loop {
+        let password: String = (0..20)
+            .map(|_| CHARS[(OsRng.next_u32() as usize) % CHARS.len()] as char)
+            .collect();
+        // Now and then it's all letters, which registration refuses
+        if validate_password(&password).is_ok() {
+            return password;
+        }
+    }
+}
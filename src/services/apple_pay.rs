@@ -0,0 +1,117 @@
+use crate::config::Config;
+use crate::error::AppError;
+use std::fs;
+
+// ============================================================================
+// APPLE PAY MERCHANT VALIDATION
+// ============================================================================
+// Before Apple Pay JS shows the payment sheet it asks the merchant to
+// prove who it is: the browser hands us a validation URL on Apple's
+// servers (`onvalidatemerchant`), we POST our merchant details to it over
+// TLS with the Apple Pay merchant identity certificate, and pass Apple's
+// opaque merchant session back to the browser unchanged.
+//
+// The validation URL comes from the browser, so it is only followed if it
+// points at Apple's Apple Pay gateway; anything else would let a caller
+// make this server send its client certificate wherever they like.
+//
+// Apple Pay on the web also needs the domain verification file from the
+// Apple developer account, served at
+// /.well-known/apple-developer-merchantid-domain-association (put it in
+// `assets/.well-known/`).
+
+/// Apple's merchant validation is quick; don't keep the payment sheet waiting
+const APPLE_PAY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// The Apple Pay merchant, able to start merchant sessions
+pub struct ApplePayMerchant {
+    http: reqwest::Client,
+    pub merchant_id: String,
+    pub display_name: String,
+    domain: String,
+}
+
+impl ApplePayMerchant {
+    /// The merchant from the APPLE_PAY_* settings (None when Apple Pay is
+    /// not configured)
+    ///
+    /// Fails if the certificate or key can't be read, so a broken Apple
+    /// Pay setup stops the server at startup instead of at checkout.
+    pub fn from_config(config: &Config) -> Result<Option<Self>, AppError> {
+        let Some(merchant_id) = config.apple_pay_merchant_id.clone() else {
+            return Ok(None);
+        };
+
+        let mut pem = read_pem(&config.apple_pay_cert_file)?;
+        pem.extend(read_pem(&config.apple_pay_key_file)?);
+        let identity = reqwest::Identity::from_pem(&pem).map_err(|e| {
+            AppError::internal(&format!("Invalid Apple Pay certificate or key: {}", e))
+        })?;
+
+        let http = reqwest::Client::builder()
+            .timeout(APPLE_PAY_TIMEOUT)
+            .identity(identity)
+            .build()
+            .map_err(|e| AppError::internal(&format!("Apple Pay client setup failed: {}", e)))?;
+
+        Ok(Some(ApplePayMerchant {
+            http,
+            merchant_id,
+            display_name: config.apple_pay_display_name.clone(),
+            domain: config.apple_pay_domain.clone(),
+        }))
+    }
+
+    /// Start a merchant session at Apple's `validation_url`
+    ///
+    /// Returns Apple's session object, for `completeMerchantValidation`.
+    pub async fn start_session(&self, validation_url: &str) -> Result<serde_json::Value, AppError> {
+        if !is_apple_pay_gateway(validation_url) {
+            return Err(AppError::validation("validation_url is not an Apple Pay server"));
+        }
+
+        let response = self
+            .http
+            .post(validation_url)
+            .json(&serde_json::json!({
+                "merchantIdentifier": self.merchant_id,
+                "displayName": self.display_name,
+                "initiative": "web",
+                "initiativeContext": self.domain,
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| {
+                tracing::error!("Apple Pay merchant validation failed: {}", e);
+                AppError::internal("Apple Pay merchant validation failed")
+            })?;
+
+        response.json().await.map_err(|e| {
+            tracing::error!("Unexpected Apple Pay merchant session: {}", e);
+            AppError::internal("Apple Pay merchant validation failed")
+        })
+    }
+}
+
+/// Is `url` an https URL on Apple's Apple Pay gateway, e.g.
+/// https://apple-pay-gateway-cert.apple.com/paymentservices/startSession ?
+fn is_apple_pay_gateway(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+
+    url.scheme() == "https"
+        && url.port().is_none()
+        && url.host_str().is_some_and(|host| {
+            host.strip_suffix(".apple.com").is_some_and(|name| {
+                !name.contains('.')
+                    && (name.starts_with("apple-pay-gateway")
+                        || name.starts_with("cn-apple-pay-gateway"))
+            })
+        })
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>, AppError> {
+    fs::read(path).map_err(|e| AppError::internal(&format!("Cannot read {}: {}", path, e)))
+}
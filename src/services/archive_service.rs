@@ -0,0 +1,71 @@
+use crate::error::AppError;
+use crate::repository::archive_repo::{self, ArchiveRestore};
+use chrono::{DateTime, Duration, Months, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// ARCHIVE SERVICE
+// ============================================================================
+// Cold storage for old transactions.
+//
+// Why do we need this?
+// - The transactions table only grows. Years-old rows are almost never read,
+//   but every index and partition still carries them.
+// - We can't delete them (statements, audits, the ledger check), so they
+//   move to `transactions_archive` instead.
+//
+// The maintenance job archives everything older than
+// TRANSACTION_ARCHIVE_AFTER_YEARS once a day, in batches so no single
+// statement holds locks for long. Balances, the dashboard summary and the
+// ledger check are unaffected. Archived rows no longer appear in the
+// wallet's transaction history; an admin can bring a range back with
+// POST /api/admin/transactions/restore.
+
+/// Transactions moved per statement
+const ARCHIVE_BATCH_SIZE: i64 = 5_000;
+
+/// How long restored transactions are kept out of the archive
+pub const RESTORE_KEEP_DAYS: i64 = 30;
+
+/// Archive every transaction older than `after_years` years
+///
+/// Returns how many were moved. `after_years` = 0 turns archiving off.
+pub async fn archive_old_transactions(pool: &PgPool, after_years: u32) -> Result<u64, AppError> {
+    if after_years == 0 {
+        return Ok(0);
+    }
+
+    let cutoff = Utc::now()
+        .checked_sub_months(Months::new(after_years.saturating_mul(12)))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+    let mut archived = 0;
+    loop {
+        let moved = archive_repo::archive_batch(pool, cutoff, ARCHIVE_BATCH_SIZE).await?;
+        archived += moved;
+
+        if moved < ARCHIVE_BATCH_SIZE as u64 {
+            return Ok(archived);
+        }
+    }
+}
+
+/// Move archived transactions created in `from..to` back into the live table
+///
+/// They stay there for RESTORE_KEEP_DAYS before the nightly job may archive
+/// them again.
+pub async fn restore_transactions(
+    pool: &PgPool,
+    admin_id: Uuid,
+    wallet_id: Option<Uuid>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<ArchiveRestore, AppError> {
+    if from >= to {
+        return Err(AppError::validation("`from` must be before `to`"));
+    }
+
+    let keep_until = Utc::now() + Duration::days(RESTORE_KEEP_DAYS);
+    archive_repo::restore_range(pool, wallet_id, from, to, admin_id, keep_until).await
+}
@@ -1,8 +1,10 @@
-use crate::domain::models::{LoginResponse, UserResponse};
+use crate::domain::models::{AccountStatus, CreateUserRequest, LoginResponse, UserResponse, UserRole};
 use crate::error::AppError;
-use crate::repository::user_repo;
-use crate::utils::jwt::{generate_token, hash_password, verify_password};
-use sqlx::PgPool;
+use crate::repository::{UserRepository, WalletRepository};
+use crate::services::clock::Clock;
+use crate::utils::jwt::{generate_token, hash_password, verify_password, JwtKeys};
+use uuid::Uuid;
+use validator::Validate;
 
 // ============================================================================
 // AUTH SERVICE
@@ -14,24 +16,26 @@ use sqlx::PgPool;
 // - Service: "What the app should do" (business rules, orchestration)
 //
 // Example:
-// - Repository: create_user(), create_wallet()
-// - Service: register() - calls both, generates token, enforces rules
+// - Repository: create_user_with_wallet()
+// - Service: register() - hashes the password, generates token, enforces rules
 
 /// Register a new user
 ///
 /// This orchestrates the entire registration process:
 /// 1. Hash the password (security)
-/// 2. Create user in database
-/// 3. Create wallet for user (every user gets a wallet)
-/// 4. Generate JWT token
-/// 5. Return user info and token
+/// 2. Create user and wallet in database (in one transaction)
+/// 3. Generate JWT token
+/// 4. Return user info and token
 ///
 /// # Arguments
-/// * `pool` - Database connection pool
+/// * `repo` - User and wallet storage (the database pool in production)
 /// * `email` - User's email
 /// * `password` - Plain text password (will be hashed)
 /// * `full_name` - User's full name
-/// * `jwt_secret` - Secret key for signing JWT tokens
+/// * `jwt_keys` - The key that signs JWT tokens
+/// * `session_id` - The device session the token is for (see
+///   `token_service::start_session`), or None
+/// * `clock` - What time it is (when the token is issued)
 ///
 /// # Returns
 /// LoginResponse with token and user info (without password hash)
@@ -42,13 +46,15 @@ use sqlx::PgPool;
 /// - `AppError::DatabaseError` for database issues
 ///
 /// # Example
-/// ```
+/// ```ignore
 /// let response = register(
 ///     &pool,
 ///     "user@example.com",
 ///     "mypassword123",
 ///     "John Doe",
-///     &config.jwt_secret
+///     &state.jwt_keys,
+///     None,
+///     state.clock.as_ref(),
 /// ).await?;
 ///
 /// // Returns:
@@ -58,30 +64,27 @@ use sqlx::PgPool;
 /// // }
 /// ```
 pub async fn register(
-    pool: &PgPool,
+    repo: &(impl UserRepository + WalletRepository),
     email: &str,
     password: &str,
     full_name: &str,
-    jwt_secret: &str,
+    jwt_keys: &JwtKeys,
+    session_id: Option<Uuid>,
+    clock: &dyn Clock,
 ) -> Result<LoginResponse, AppError> {
     // ========================================================================
     // STEP 1: Validate input
     // ========================================================================
     
-    // Check email is not empty
-    if email.trim().is_empty() {
-        return Err(AppError::validation("Email cannot be empty"));
-    }
-    
-    // Check password length (at least 8 characters)
-    if password.len() < 8 {
-        return Err(AppError::validation("Password must be at least 8 characters"));
-    }
-    
-    // Check full name is not empty
-    if full_name.trim().is_empty() {
-        return Err(AppError::validation("Full name cannot be empty"));
+    // The same rules as a POST /register body (handlers have checked them
+    // already; the seeder, the demo and `create-admin` haven't)
+    CreateUserRequest {
+        email: email.to_string(),
+        password: password.to_string(),
+        full_name: full_name.to_string(),
+        referral_code: None,
     }
+    .validate()?;
     
     // ========================================================================
     // STEP 2: Hash the password
@@ -90,25 +93,24 @@ pub async fn register(
     let password_hash = hash_password(password)?;
     
     // ========================================================================
-    // STEP 3: Create user in database
+    // STEP 3: Create user and wallet in database
     // ========================================================================
+    // Every user gets a wallet with $0.00 balance. Both are written in one
+    // transaction, so there is never a user without a wallet.
     // This will error if email already exists (unique constraint)
-    let user = user_repo::create_user(pool, email, &password_hash, full_name).await?;
+    let (user, _wallet) = repo
+        .create_user_with_wallet(email, &password_hash, full_name)
+        .await?;
     
     // ========================================================================
-    // STEP 4: Create wallet for user
-    // ========================================================================
-    // Every user gets a wallet with $0.00 balance
-    let _wallet = user_repo::create_wallet(pool, user.id).await?;
-    
-    // ========================================================================
-    // STEP 5: Generate JWT token
+    // STEP 4: Generate JWT token
     // ========================================================================
     // Token expires in 24 hours
-    let token = generate_token(user.id, jwt_secret)?;
+    // (New users are always plain users)
+    let token = generate_token(user.id, session_id, UserRole::User, jwt_keys, clock)?;
     
     // ========================================================================
-    // STEP 6: Return response
+    // STEP 5: Return response
     // ========================================================================
     // Convert User to UserResponse (removes password_hash for security)
     let user_response = UserResponse::from(user);
@@ -128,34 +130,43 @@ pub async fn register(
 /// 4. Return user info and token
 ///
 /// # Arguments
-/// * `pool` - Database connection pool
+/// * `repo` - User storage (the database pool in production)
 /// * `email` - User's email
 /// * `password` - Plain text password
-/// * `jwt_secret` - Secret key for signing JWT tokens
+/// * `jwt_keys` - The key that signs JWT tokens
+/// * `session_id` - The device session the token is for (see
+///   `token_service::start_session`), or None
+/// * `clock` - What time it is (when the token is issued)
 ///
 /// # Returns
 /// LoginResponse with token and user info
 ///
 /// # Errors
 /// - `AppError::InvalidCredentials` if email or password is wrong
+/// - `AppError::AccountDeleted` if the account has been deleted
+/// - `AppError::AccountFrozen` if the account has been closed
 /// - `AppError::DatabaseError` for database issues
 ///
 /// # Example
-/// ```
+/// ```ignore
 /// let response = login(
 ///     &pool,
 ///     "user@example.com",
 ///     "mypassword123",
-///     &config.jwt_secret
+///     &state.jwt_keys,
+///     None,
+///     state.clock.as_ref(),
 /// ).await?;
 ///
 /// // Returns same format as register()
 /// ```
 pub async fn login(
-    pool: &PgPool,
+    repo: &impl UserRepository,
     email: &str,
     password: &str,
-    jwt_secret: &str,
+    jwt_keys: &JwtKeys,
+    session_id: Option<Uuid>,
+    clock: &dyn Clock,
 ) -> Result<LoginResponse, AppError> {
     // ========================================================================
     // STEP 1: Find user by email
@@ -163,9 +174,20 @@ pub async fn login(
     // If user doesn't exist, this returns AppError::NotFound
     // We convert it to InvalidCredentials for security
     // (don't reveal whether email exists or not)
-    let user = user_repo::find_user_by_email(pool, email)
-        .await
-        .map_err(|_| AppError::InvalidCredentials)?;
+    let user = match repo.find_user_by_email(email).await {
+        Ok(user) => user,
+        Err(AppError::NotFound(_)) => {
+            // A deleted account only gets the specific error once the
+            // password checks out, so it doesn't reveal anything either
+            if let Ok(deleted) = repo.find_deleted_user_by_email(email).await {
+                if verify_password(password, &deleted.password_hash).is_ok() {
+                    return Err(AppError::AccountDeleted);
+                }
+            }
+            return Err(AppError::InvalidCredentials);
+        }
+        Err(e) => return Err(e),
+    };
     
     // ========================================================================
     // STEP 2: Verify password
@@ -173,11 +195,17 @@ pub async fn login(
     // Compare the provided password with the stored hash
     // If wrong, returns AppError::InvalidCredentials
     verify_password(password, &user.password_hash)?;
+
+    // Closed accounts can't sign in (frozen ones can, to look)
+    if repo.get_account_status(user.id).await? == AccountStatus::Closed {
+        return Err(AppError::AccountFrozen);
+    }
     
     // ========================================================================
     // STEP 3: Generate JWT token
     // ========================================================================
-    let token = generate_token(user.id, jwt_secret)?;
+    let role = repo.get_user_role(user.id).await?;
+    let token = generate_token(user.id, session_id, role, jwt_keys, clock)?;
     
     // ========================================================================
     // STEP 4: Return response
@@ -221,7 +249,7 @@ async fn register_handler(
         &req.email,
         &req.password,
         &req.full_name,
-        &config.jwt_secret,
+        &state.jwt_keys,
     ).await?;
     
     Ok(Json(response))
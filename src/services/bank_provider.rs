@@ -0,0 +1,703 @@
+use crate::config::{BankProviderKind, Config, PlaidEnvironment};
+use crate::domain::ids;
+use crate::error::AppError;
+use crate::repository::bank_repo;
+use crate::utils::secret::SecretString;
+use axum::http::HeaderMap;
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::jwk::Jwk;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+// ============================================================================
+// BANK PROVIDERS
+// ============================================================================
+// Everything we need from the outside world to link bank accounts and move
+// money in and out of them, behind one trait:
+//
+//   1. `create_link_token` - the client opens the provider's Link widget
+//      with this token, and the user logs in to their bank there
+//   2. `exchange_public_token` - Link hands the client a short-lived public
+//      token; we swap it for a long-lived access token and the accounts
+//   3. `start_deposit` / `start_payout` - debit or credit a linked account
+//      (ACH)
+//   4. `transfer_status` / `parse_webhook` - find out later whether the
+//      money arrived (or came back)
+//
+// Why a trait?
+// - Handlers and services don't care whether it's Plaid or something else
+// - `FakeBankProvider` stands in for Plaid in development, so bank linking
+//   works without credentials or network access, and deposits settle at once
+//
+// BANK_PROVIDER picks the implementation (see config.rs).
+
+/// A token for the client to open the provider's Link widget with
+#[derive(Debug, Clone)]
+pub struct LinkToken {
+    pub link_token: String,
+    pub expiration: DateTime<Utc>,
+}
+
+/// One login at a bank, with the accounts the user chose to share
+#[derive(Debug, Clone)]
+pub struct BankConnection {
+    pub item_id: String,
+    pub access_token: SecretString,
+    pub institution_name: Option<String>,
+    pub accounts: Vec<BankAccount>,
+}
+
+/// An account at the bank, as the provider describes it
+#[derive(Debug, Clone)]
+pub struct BankAccount {
+    pub account_id: String,
+    pub name: String,
+    pub mask: Option<String>,
+    pub subtype: Option<String>,
+}
+
+/// Where a transfer the provider accepted is now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferStatus {
+    /// Still on its way
+    Pending,
+    /// The money has arrived
+    Settled,
+    /// Declined or cancelled before any money moved
+    Failed,
+    /// The receiving bank sent it back (closed account, wrong details, ...)
+    Returned,
+}
+
+/// The latest status of one transfer
+#[derive(Debug, Clone)]
+pub struct TransferUpdate {
+    pub transfer_id: String,
+    pub status: TransferStatus,
+    /// Why it failed or was returned, when the provider says
+    pub reason: Option<String>,
+}
+
+/// What a webhook call from the provider tells us
+#[derive(Debug, Clone)]
+pub enum WebhookEvent {
+    /// These transfers changed status
+    TransferUpdates(Vec<TransferUpdate>),
+    /// Something changed; ask about every outstanding transfer
+    SyncNeeded,
+    /// Nothing we act on
+    Ignored,
+}
+
+/// A transfer to start: debit (deposit) or credit (payout) a linked account
+#[derive(Debug, Clone)]
+pub struct TransferOrder<'a> {
+    /// Our transaction id; the provider won't start two transfers for it
+    pub reference: Uuid,
+    pub access_token: &'a SecretString,
+    pub account_id: &'a str,
+    pub amount: Decimal,
+    /// The account holder's name, for the ACH authorization
+    pub legal_name: &'a str,
+    pub description: &'a str,
+}
+
+/// Links bank accounts and moves money in and out of them
+#[async_trait::async_trait]
+pub trait BankProvider: Send + Sync {
+    /// Stored with every linked account, e.g. "plaid"
+    fn name(&self) -> &'static str;
+
+    /// A Link token for `user_id`
+    async fn create_link_token(&self, user_id: Uuid) -> Result<LinkToken, AppError>;
+
+    /// Trade the public token from Link for access to the user's accounts
+    ///
+    /// Fails with a validation error if the token is invalid or expired.
+    async fn exchange_public_token(&self, public_token: &str) -> Result<BankConnection, AppError>;
+
+    /// Start pulling money from a linked account; returns the provider's
+    /// transfer id
+    ///
+    /// Fails with `TransactionFailed` if the provider declines the debit.
+    async fn start_deposit(&self, order: TransferOrder<'_>) -> Result<String, AppError>;
+
+    /// Start paying money out to a linked account; returns the provider's
+    /// transfer id
+    ///
+    /// Fails with `TransactionFailed` if the provider declines the credit.
+    async fn start_payout(&self, order: TransferOrder<'_>) -> Result<String, AppError>;
+
+    /// Where a started transfer (either direction) is now
+    async fn transfer_status(&self, transfer_id: &str) -> Result<TransferUpdate, AppError>;
+
+    /// Check that a webhook call really comes from the provider and read it
+    ///
+    /// Fails with `Unauthorized` if the signature doesn't check out.
+    async fn parse_webhook(&self, headers: &HeaderMap, body: &[u8])
+        -> Result<WebhookEvent, AppError>;
+}
+
+/// The provider chosen by BANK_PROVIDER (None when bank linking is off)
+pub fn from_config(config: &Config, pool: &PgPool) -> Option<Arc<dyn BankProvider>> {
+    match config.bank_provider {
+        BankProviderKind::Plaid => Some(Arc::new(PlaidProvider::new(
+            config.plaid_environment,
+            config.plaid_client_id.clone(),
+            config.plaid_secret.clone(),
+            pool.clone(),
+        ))),
+        BankProviderKind::Fake => Some(Arc::new(FakeBankProvider)),
+        BankProviderKind::Off => None,
+    }
+}
+
+// ============================================================================
+// PLAID
+// ============================================================================
+// Plaid Link for linking and Plaid Transfer for ACH debits and credits. Every call
+// is a JSON POST carrying our client id and secret; errors come back as
+// `{ error_type, error_code, error_message }`.
+//
+// Transfers are authorized first (Plaid checks the account and its balance)
+// and only created once approved. Their status moves through pending ->
+// posted -> settled (-> funds_available), or ends as failed, cancelled or
+// returned.
+//
+// Transfer webhooks carry no details, only "something changed"
+// (TRANSFER_EVENTS_UPDATE), so we answer them by asking about every
+// outstanding transfer. Each webhook is signed: the Plaid-Verification
+// header is an ES256 JWT with the SHA-256 of the body, and the public keys
+// come from /webhook_verification_key/get (cached by key id). A signature
+// is good for PLAID_WEBHOOK_MAX_AGE_SECS, and only once: its signing time
+// and body hash are recorded (bank_webhook_receipts), so a webhook sent
+// again within that window is refused like a forged one.
+
+/// Plaid API client
+pub struct PlaidProvider {
+    http: reqwest::Client,
+    base_url: &'static str,
+    client_id: String,
+    secret: SecretString,
+    /// Webhook verification keys by key id
+    webhook_keys: Mutex<HashMap<String, Jwk>>,
+    /// Where webhooks already received are recorded
+    pool: PgPool,
+}
+
+/// Webhooks signed longer ago than this are rejected (replays)
+const PLAID_WEBHOOK_MAX_AGE_SECS: i64 = 5 * 60;
+
+/// Plaid rejects requests that take longer than this on their side anyway
+const PLAID_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+#[derive(Debug, Deserialize)]
+struct PlaidError {
+    error_type: String,
+    error_code: String,
+    error_message: String,
+}
+
+impl PlaidProvider {
+    pub fn new(
+        environment: PlaidEnvironment,
+        client_id: String,
+        secret: SecretString,
+        pool: PgPool,
+    ) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(PLAID_TIMEOUT)
+            .build()
+            .expect("TLS backend is available");
+
+        PlaidProvider {
+            http,
+            base_url: environment.base_url(),
+            client_id,
+            secret,
+            webhook_keys: Mutex::new(HashMap::new()),
+            pool,
+        }
+    }
+
+    /// Authorize and create a transfer; `direction` is "debit" or "credit"
+    async fn start_transfer(
+        &self,
+        order: TransferOrder<'_>,
+        direction: &str,
+    ) -> Result<String, AppError> {
+        // Plaid wants a string with exactly two decimals
+        let amount = format!("{:.2}", order.amount);
+
+        let authorization: PlaidAuthorization = self
+            .post(
+                "/transfer/authorization/create",
+                json!({
+                    "access_token": order.access_token.expose_secret(),
+                    "account_id": order.account_id,
+                    "idempotency_key": order.reference.to_string(),
+                    "type": direction,
+                    "network": "ach",
+                    "amount": amount,
+                    "ach_class": "web",
+                    "user": { "legal_name": order.legal_name },
+                }),
+            )
+            .await?;
+
+        let decision = authorization.authorization;
+        if decision.decision != "approved" {
+            let reason = decision
+                .decision_rationale
+                .map(|rationale| rationale.description)
+                .unwrap_or_else(|| "declined by the bank provider".to_string());
+            return Err(AppError::TransactionFailed(reason));
+        }
+
+        // Plaid caps transfer descriptions at 15 characters
+        let description: String = order.description.chars().take(15).collect();
+        let transfer: PlaidTransfer = self
+            .post(
+                "/transfer/create",
+                json!({
+                    "access_token": order.access_token.expose_secret(),
+                    "account_id": order.account_id,
+                    "authorization_id": decision.id,
+                    "description": description,
+                    "idempotency_key": order.reference.to_string(),
+                }),
+            )
+            .await?;
+
+        Ok(transfer.transfer.id)
+    }
+
+    /// The public key a webhook was signed with
+    async fn webhook_key(&self, key_id: &str) -> Result<Jwk, AppError> {
+        if let Some(key) = self.webhook_keys.lock().unwrap().get(key_id) {
+            return Ok(key.clone());
+        }
+
+        let found: PlaidWebhookKey = self
+            .post("/webhook_verification_key/get", json!({ "key_id": key_id }))
+            .await?;
+        self.webhook_keys
+            .lock()
+            .unwrap()
+            .insert(key_id.to_string(), found.key.clone());
+
+        Ok(found.key)
+    }
+
+    /// Check the Plaid-Verification signature of a webhook body, and that
+    /// it hasn't been received before
+    async fn verify_webhook(&self, headers: &HeaderMap, body: &[u8]) -> Result<(), AppError> {
+        let token = headers
+            .get("plaid-verification")
+            .and_then(|value| value.to_str().ok())
+            .ok_or(AppError::Unauthorized)?;
+
+        let header = jsonwebtoken::decode_header(token).map_err(|_| AppError::Unauthorized)?;
+        let key_id = match (header.alg, header.kid) {
+            (Algorithm::ES256, Some(key_id)) => key_id,
+            _ => return Err(AppError::Unauthorized),
+        };
+
+        let key = DecodingKey::from_jwk(&self.webhook_key(&key_id).await?)
+            .map_err(|_| AppError::Unauthorized)?;
+        let mut validation = Validation::new(Algorithm::ES256);
+        // Plaid only sets `iat`; the age check below stands in for `exp`
+        validation.validate_exp = false;
+        validation.required_spec_claims = HashSet::new();
+
+        let claims = jsonwebtoken::decode::<PlaidWebhookClaims>(token, &key, &validation)
+            .map_err(|_| AppError::Unauthorized)?
+            .claims;
+
+        let now = Utc::now();
+        let age = now.timestamp() - claims.iat;
+        let body_hash = hex::encode(Sha256::digest(body));
+        if age > PLAID_WEBHOOK_MAX_AGE_SECS || body_hash != claims.request_body_sha256 {
+            return Err(AppError::Unauthorized);
+        }
+
+        // A replay carries the very same signature, so the same iat and hash
+        let signed_at = DateTime::from_timestamp(claims.iat, 0).ok_or(AppError::Unauthorized)?;
+        let receipt_key = format!("plaid:{}:{}", claims.iat, body_hash);
+        let forget_before = now - Duration::seconds(PLAID_WEBHOOK_MAX_AGE_SECS);
+        if !bank_repo::record_webhook_receipt(&self.pool, &receipt_key, signed_at, forget_before)
+            .await?
+        {
+            tracing::warn!("🏦 Refused a Plaid webhook received twice (signed at {})", signed_at);
+            return Err(AppError::Unauthorized);
+        }
+
+        Ok(())
+    }
+
+    /// POST `body` (plus our credentials) to a Plaid endpoint
+    async fn post<T: DeserializeOwned>(&self, path: &str, mut body: Value) -> Result<T, AppError> {
+        body["client_id"] = json!(self.client_id);
+        body["secret"] = json!(self.secret.expose_secret());
+
+        let response = self
+            .http
+            .post(format!("{}{}", self.base_url, path))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Plaid request to {} failed: {}", path, e);
+                AppError::internal("Bank provider is unreachable")
+            })?;
+
+        if response.status().is_success() {
+            return response.json::<T>().await.map_err(|e| {
+                tracing::error!("Unexpected Plaid response from {}: {}", path, e);
+                AppError::internal("Unexpected response from bank provider")
+            });
+        }
+
+        let status = response.status();
+        match response.json::<PlaidError>().await {
+            // Bad input from the user's side, e.g. an expired public token
+            Ok(error) if error.error_type == "INVALID_INPUT" => {
+                Err(AppError::validation(&error.error_message))
+            }
+            Ok(error) => {
+                tracing::error!(
+                    "Plaid {} returned {} {}: {}",
+                    path,
+                    error.error_type,
+                    error.error_code,
+                    error.error_message
+                );
+                Err(AppError::internal("Bank provider request failed"))
+            }
+            Err(_) => {
+                tracing::error!("Plaid {} returned {}", path, status);
+                Err(AppError::internal("Bank provider request failed"))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidLinkToken {
+    link_token: String,
+    expiration: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidTokenExchange {
+    access_token: String,
+    item_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidAccounts {
+    accounts: Vec<PlaidAccount>,
+    item: PlaidItem,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidAccount {
+    account_id: String,
+    name: String,
+    mask: Option<String>,
+    subtype: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidItem {
+    institution_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidInstitution {
+    institution: PlaidInstitutionName,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidInstitutionName {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidAuthorization {
+    authorization: PlaidAuthorizationDecision,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidAuthorizationDecision {
+    id: String,
+    decision: String,
+    decision_rationale: Option<PlaidDecisionRationale>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidDecisionRationale {
+    description: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidTransfer {
+    transfer: PlaidTransferStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidTransferStatus {
+    id: String,
+    status: String,
+    failure_reason: Option<PlaidFailureReason>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidFailureReason {
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidWebhookKey {
+    key: Jwk,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidWebhookClaims {
+    iat: i64,
+    request_body_sha256: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaidWebhook {
+    webhook_type: String,
+    webhook_code: String,
+}
+
+#[async_trait::async_trait]
+impl BankProvider for PlaidProvider {
+    fn name(&self) -> &'static str {
+        "plaid"
+    }
+
+    async fn create_link_token(&self, user_id: Uuid) -> Result<LinkToken, AppError> {
+        let token: PlaidLinkToken = self
+            .post(
+                "/link/token/create",
+                json!({
+                    "client_name": "MyFintechApp",
+                    "user": { "client_user_id": user_id.to_string() },
+                    "products": ["auth", "transfer"],
+                    "country_codes": ["US"],
+                    "language": "en",
+                }),
+            )
+            .await?;
+
+        Ok(LinkToken {
+            link_token: token.link_token,
+            expiration: token.expiration,
+        })
+    }
+
+    async fn exchange_public_token(&self, public_token: &str) -> Result<BankConnection, AppError> {
+        let exchange: PlaidTokenExchange = self
+            .post(
+                "/item/public_token/exchange",
+                json!({ "public_token": public_token }),
+            )
+            .await?;
+
+        let accounts: PlaidAccounts = self
+            .post("/accounts/get", json!({ "access_token": exchange.access_token }))
+            .await?;
+
+        // The name is only for display; linking works without it
+        let institution_name = match &accounts.item.institution_id {
+            Some(institution_id) => self
+                .post::<PlaidInstitution>(
+                    "/institutions/get_by_id",
+                    json!({ "institution_id": institution_id, "country_codes": ["US"] }),
+                )
+                .await
+                .map(|found| found.institution.name)
+                .ok(),
+            None => None,
+        };
+
+        Ok(BankConnection {
+            item_id: exchange.item_id,
+            access_token: SecretString::from(exchange.access_token),
+            institution_name,
+            accounts: accounts
+                .accounts
+                .into_iter()
+                .map(|account| BankAccount {
+                    account_id: account.account_id,
+                    name: account.name,
+                    mask: account.mask,
+                    subtype: account.subtype,
+                })
+                .collect(),
+        })
+    }
+
+    async fn start_deposit(&self, order: TransferOrder<'_>) -> Result<String, AppError> {
+        self.start_transfer(order, "debit").await
+    }
+
+    async fn start_payout(&self, order: TransferOrder<'_>) -> Result<String, AppError> {
+        self.start_transfer(order, "credit").await
+    }
+
+    async fn transfer_status(&self, transfer_id: &str) -> Result<TransferUpdate, AppError> {
+        let found: PlaidTransfer = self
+            .post("/transfer/get", json!({ "transfer_id": transfer_id }))
+            .await?;
+        let transfer = found.transfer;
+
+        let status = match transfer.status.as_str() {
+            "settled" | "funds_available" => TransferStatus::Settled,
+            "failed" | "cancelled" => TransferStatus::Failed,
+            "returned" => TransferStatus::Returned,
+            // "pending", "posted" and anything Plaid adds later
+            _ => TransferStatus::Pending,
+        };
+
+        Ok(TransferUpdate {
+            transfer_id: transfer.id,
+            status,
+            reason: transfer.failure_reason.and_then(|reason| reason.description),
+        })
+    }
+
+    async fn parse_webhook(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<WebhookEvent, AppError> {
+        self.verify_webhook(headers, body).await?;
+
+        let webhook: PlaidWebhook = serde_json::from_slice(body)
+            .map_err(|_| AppError::validation("Invalid webhook body"))?;
+
+        Ok(match (webhook.webhook_type.as_str(), webhook.webhook_code.as_str()) {
+            ("TRANSFER", "TRANSFER_EVENTS_UPDATE") => WebhookEvent::SyncNeeded,
+            _ => WebhookEvent::Ignored,
+        })
+    }
+}
+
+// ============================================================================
+// FAKE BANK
+// ============================================================================
+// A stand-in provider for development (BANK_PROVIDER=fake, never allowed in
+// production). Public tokens are anything starting with "public-fake-";
+// every login has a checking and a savings account, and deposits settle
+// the moment they are started.
+//
+// Payouts stay pending until a (unsigned) webhook says otherwise, so
+// returns can be tried out locally:
+//
+//   POST /api/webhooks/bank
+//   {"transfer_id": "fake-payout-...", "status": "returned", "reason": "Account closed"}
+
+/// Offline bank provider for development
+pub struct FakeBankProvider;
+
+#[async_trait::async_trait]
+impl BankProvider for FakeBankProvider {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    async fn create_link_token(&self, _user_id: Uuid) -> Result<LinkToken, AppError> {
+        Ok(LinkToken {
+            link_token: format!("link-fake-{}", ids::new_id()),
+            expiration: Utc::now() + Duration::hours(4),
+        })
+    }
+
+    async fn exchange_public_token(&self, public_token: &str) -> Result<BankConnection, AppError> {
+        let Some(item) = public_token.strip_prefix("public-fake-") else {
+            return Err(AppError::validation("Invalid public token"));
+        };
+
+        // The same public token always links the same accounts
+        let account = |suffix: &str, name: &str, mask: &str, subtype: &str| BankAccount {
+            account_id: format!("fake-{}-{}", item, suffix),
+            name: name.to_string(),
+            mask: Some(mask.to_string()),
+            subtype: Some(subtype.to_string()),
+        };
+
+        Ok(BankConnection {
+            item_id: format!("fake-item-{}", item),
+            access_token: SecretString::new(format!("access-fake-{}", ids::new_id())),
+            institution_name: Some("Fake Bank".to_string()),
+            accounts: vec![
+                account("checking", "Fake Checking", "0000", "checking"),
+                account("savings", "Fake Savings", "1111", "savings"),
+            ],
+        })
+    }
+
+    async fn start_deposit(&self, _order: TransferOrder<'_>) -> Result<String, AppError> {
+        Ok(format!("fake-transfer-{}", ids::new_id()))
+    }
+
+    async fn start_payout(&self, _order: TransferOrder<'_>) -> Result<String, AppError> {
+        Ok(format!("fake-payout-{}", ids::new_id()))
+    }
+
+    async fn transfer_status(&self, transfer_id: &str) -> Result<TransferUpdate, AppError> {
+        let status = if transfer_id.starts_with("fake-payout-") {
+            TransferStatus::Pending
+        } else {
+            TransferStatus::Settled
+        };
+
+        Ok(TransferUpdate {
+            transfer_id: transfer_id.to_string(),
+            status,
+            reason: None,
+        })
+    }
+
+    async fn parse_webhook(
+        &self,
+        _headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<WebhookEvent, AppError> {
+        #[derive(Deserialize)]
+        struct FakeWebhook {
+            transfer_id: String,
+            status: String,
+            reason: Option<String>,
+        }
+
+        let webhook: FakeWebhook = serde_json::from_slice(body)
+            .map_err(|_| AppError::validation("Invalid webhook body"))?;
+        let status = match webhook.status.as_str() {
+            "pending" => TransferStatus::Pending,
+            "settled" => TransferStatus::Settled,
+            "failed" => TransferStatus::Failed,
+            "returned" => TransferStatus::Returned,
+            _ => return Err(AppError::validation("Unknown transfer status")),
+        };
+
+        Ok(WebhookEvent::TransferUpdates(vec![TransferUpdate {
+            transfer_id: webhook.transfer_id,
+            status,
+            reason: webhook.reason,
+        }]))
+    }
+}
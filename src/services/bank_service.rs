@@ -0,0 +1,527 @@
+use crate::domain::models::{
+    BankPayoutResponse, Currency, LinkTokenResponse, LinkedBankAccountResponse, PayoutStatus,
+    Transaction, TransactionStatus, validate_amount,
+};
+use crate::error::AppError;
+use crate::repository::bank_repo::{
+    self, BankPayout, FundingSource, LinkedBankAccount, NewLinkedAccount, UnsettledDeposit,
+};
+use crate::repository::user_repo;
+use crate::services::bank_provider::{
+    BankProvider, TransferOrder, TransferStatus, TransferUpdate, WebhookEvent,
+};
+use crate::services::wallet_service;
+use crate::shutdown::Shutdown;
+use axum::http::HeaderMap;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// ============================================================================
+// BANK SERVICE
+// ============================================================================
+// Linking external bank accounts, depositing from them and paying out to
+// them.
+//
+// A bank deposit is an ACH debit, which takes days to arrive and can still
+// fail. So it starts as a PENDING DEPOSIT (no money in the wallet yet), and
+// the settlement job asks the provider about it every few minutes:
+// settled -> COMPLETED (the wallet is credited), failed/returned -> FAILED.
+// With the fake provider deposits settle straight away.
+//
+// A payout is an ACH credit. The money leaves the wallet at once (a
+// COMPLETED WITHDRAWAL) and the payout stays PENDING until the provider
+// reports it SETTLED - or RETURNED/FAILED, which reverses the withdrawal.
+// Provider webhooks (POST /api/webhooks/bank) bring those answers early;
+// the settlement job catches anything a webhook missed.
+//
+// Bank transfers are in US dollars, so only USD wallets can use them.
+
+/// How often unsettled bank transfers are checked with the provider
+const SETTLEMENT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Deposits (and payouts) checked per settlement run
+const SETTLEMENT_BATCH_SIZE: i64 = 500;
+
+/// A transfer the provider never accepted is given up after this long (the
+/// server stopped between recording it and calling the provider)
+const UNACCEPTED_TRANSFER_TIMEOUT_MINUTES: i64 = 60;
+
+/// Payouts shown by GET /wallet/payouts
+const PAYOUT_HISTORY_LIMIT: i64 = 50;
+
+/// A token for the client to open the provider's Link widget with
+pub async fn create_link_token(
+    provider: &dyn BankProvider,
+    user_id: Uuid,
+) -> Result<LinkTokenResponse, AppError> {
+    let token = provider.create_link_token(user_id).await?;
+
+    Ok(LinkTokenResponse {
+        link_token: token.link_token,
+        expiration: token.expiration,
+    })
+}
+
+/// Exchange the public token from Link and store the accounts it grants
+pub async fn link_accounts(
+    pool: &PgPool,
+    provider: &dyn BankProvider,
+    user_id: Uuid,
+    public_token: &str,
+) -> Result<Vec<LinkedBankAccountResponse>, AppError> {
+    let connection = provider.exchange_public_token(public_token.trim()).await?;
+    if connection.accounts.is_empty() {
+        return Err(AppError::validation("No bank accounts were shared"));
+    }
+
+    let accounts: Vec<NewLinkedAccount> = connection
+        .accounts
+        .iter()
+        .map(|account| NewLinkedAccount {
+            provider_account_id: &account.account_id,
+            account_name: &account.name,
+            mask: account.mask.as_deref(),
+            subtype: account.subtype.as_deref(),
+        })
+        .collect();
+
+    let linked = bank_repo::upsert_linked_accounts(
+        pool,
+        user_id,
+        provider.name(),
+        &connection.item_id,
+        &connection.access_token,
+        connection.institution_name.as_deref(),
+        &accounts,
+    )
+    .await?;
+
+    tracing::info!("🏦 User {} linked {} bank accounts", user_id, linked.len());
+
+    Ok(linked.into_iter().map(LinkedBankAccountResponse::from).collect())
+}
+
+/// The user's linked bank accounts
+pub async fn list_accounts(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<LinkedBankAccountResponse>, AppError> {
+    let accounts = bank_repo::list_linked_accounts(pool, user_id).await?;
+
+    Ok(accounts.into_iter().map(LinkedBankAccountResponse::from).collect())
+}
+
+/// Unlink one of the user's bank accounts
+pub async fn unlink_account(pool: &PgPool, user_id: Uuid, account_id: Uuid) -> Result<(), AppError> {
+    if !bank_repo::unlink_account(pool, user_id, account_id).await? {
+        return Err(AppError::not_found("Bank account"));
+    }
+
+    Ok(())
+}
+
+/// Start a deposit from a linked bank account into the user's wallet
+///
+/// Returns the DEPOSIT transaction: PENDING until the money arrives, or
+/// already COMPLETED if the provider settled it at once. Fails with
+/// `TransactionFailed` if the provider declines the debit.
+pub async fn deposit(
+    pool: &PgPool,
+    provider: &dyn BankProvider,
+    user_id: Uuid,
+    bank_account_id: Uuid,
+    amount: Decimal,
+) -> Result<Transaction, AppError> {
+    validate_amount(&amount).map_err(|e| AppError::invalid_field("amount", e))?;
+    wallet_service::ensure_can_move_money(pool, user_id).await?;
+
+    let source = usable_account(pool, provider, user_id, bank_account_id).await?;
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+    if wallet.currency != Currency::Usd {
+        return Err(AppError::currency_mismatch(wallet.currency, Currency::Usd));
+    }
+    let user = user_repo::find_user_by_id(pool, user_id).await?;
+
+    // 1. Record the PENDING deposit first, so we never move money we have
+    //    no record of
+    let description = deposit_description(&source.account);
+    let transaction =
+        bank_repo::start_deposit(pool, wallet.id, source.account.id, amount, &description).await?;
+
+    // 2. Ask the provider to pull the money
+    let order = TransferOrder {
+        reference: transaction.id,
+        access_token: &source.access_token,
+        account_id: &source.account.provider_account_id,
+        amount,
+        legal_name: &user.full_name,
+        description: "Wallet deposit",
+    };
+    let transfer_id = match provider.start_deposit(order).await {
+        Ok(transfer_id) => transfer_id,
+        Err(e) => {
+            bank_repo::settle_deposit(pool, transaction.id, TransactionStatus::Failed).await?;
+            return Err(e);
+        }
+    };
+    bank_repo::set_provider_transfer(pool, transaction.id, &transfer_id).await?;
+
+    // 3. Some providers settle at once; otherwise the settlement job will
+    let deposit = UnsettledDeposit {
+        transaction_id: transaction.id,
+        provider_transfer_id: Some(transfer_id),
+        created_at: transaction.created_at,
+    };
+    Ok(settle(pool, provider, &deposit).await?.unwrap_or(transaction))
+}
+
+/// Pay money out from the user's wallet to a linked bank account
+///
+/// The wallet is debited straight away; the payout is PENDING until the
+/// bank transfer settles, and the money comes back if it is returned.
+/// Fails with `InsufficientBalance` if the wallet can't cover it, or
+/// `TransactionFailed` if the provider declines the credit (nothing is
+/// taken from the wallet then).
+pub async fn payout(
+    pool: &PgPool,
+    provider: &dyn BankProvider,
+    user_id: Uuid,
+    bank_account_id: Uuid,
+    amount: Decimal,
+) -> Result<BankPayoutResponse, AppError> {
+    validate_amount(&amount).map_err(|e| AppError::invalid_field("amount", e))?;
+    wallet_service::ensure_can_move_money(pool, user_id).await?;
+
+    let destination = usable_account(pool, provider, user_id, bank_account_id).await?;
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+    if wallet.currency != Currency::Usd {
+        return Err(AppError::currency_mismatch(wallet.currency, Currency::Usd));
+    }
+    let user = user_repo::find_user_by_id(pool, user_id).await?;
+
+    // 1. Take the money out of the wallet and record the PENDING payout,
+    //    before any money can leave
+    let description = payout_description(&destination.account);
+    let payout = bank_repo::start_payout(
+        pool,
+        user_id,
+        wallet.id,
+        destination.account.id,
+        amount,
+        &description,
+    )
+    .await?;
+
+    // 2. Ask the provider to send it
+    let order = TransferOrder {
+        reference: payout.transaction_id,
+        access_token: &destination.access_token,
+        account_id: &destination.account.provider_account_id,
+        amount,
+        legal_name: &user.full_name,
+        description: "Wallet payout",
+    };
+    match provider.start_payout(order).await {
+        Ok(transfer_id) => bank_repo::set_payout_transfer(pool, payout.id, &transfer_id).await?,
+        Err(e) => {
+            bank_repo::finish_payout(pool, payout.id, PayoutStatus::Failed, None).await?;
+            return Err(e);
+        }
+    }
+
+    tracing::info!("🏦 User {} started a {} payout to their bank", user_id, amount);
+
+    Ok(BankPayoutResponse::from(payout))
+}
+
+/// The user's most recent payouts, newest first
+pub async fn list_payouts(pool: &PgPool, user_id: Uuid) -> Result<Vec<BankPayoutResponse>, AppError> {
+    let payouts = bank_repo::list_payouts(pool, user_id, PAYOUT_HISTORY_LIMIT).await?;
+
+    Ok(payouts.into_iter().map(BankPayoutResponse::from).collect())
+}
+
+/// Act on a webhook call from the bank provider
+///
+/// Fails with `Unauthorized` if it isn't really from the provider. Updates
+/// for transfers we don't know (or have finished already) are ignored.
+pub async fn handle_webhook(
+    pool: &PgPool,
+    provider: &dyn BankProvider,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<(), AppError> {
+    match provider.parse_webhook(headers, body).await? {
+        WebhookEvent::TransferUpdates(updates) => {
+            for update in &updates {
+                apply_update(pool, update).await?;
+            }
+        }
+        WebhookEvent::SyncNeeded => {
+            let summary = settle_pending_transfers(pool, provider).await?;
+            tracing::info!(
+                "🏦 Bank webhook: {} transfers completed, {} failed",
+                summary.completed,
+                summary.failed
+            );
+        }
+        WebhookEvent::Ignored => {}
+    }
+
+    Ok(())
+}
+
+/// How many bank transfers a settlement run finished
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SettlementSummary {
+    /// Deposits completed and payouts settled
+    pub completed: usize,
+    /// Deposits failed and payouts returned or failed
+    pub failed: usize,
+}
+
+impl SettlementSummary {
+    fn count(&mut self, finished: Option<bool>) {
+        match finished {
+            Some(true) => self.completed += 1,
+            Some(false) => self.failed += 1,
+            None => {}
+        }
+    }
+}
+
+/// Check every unsettled bank deposit and payout with the provider and
+/// finish the ones it has an answer for
+pub async fn settle_pending_transfers(
+    pool: &PgPool,
+    provider: &dyn BankProvider,
+) -> Result<SettlementSummary, AppError> {
+    let mut summary = SettlementSummary::default();
+
+    // One bad transfer (or provider hiccup) shouldn't hold up the rest
+    for deposit in bank_repo::list_unsettled_deposits(pool, SETTLEMENT_BATCH_SIZE).await? {
+        match settle(pool, provider, &deposit).await {
+            Ok(transaction) => summary.count(transaction.map(|transaction| {
+                transaction.status == TransactionStatus::Completed.as_str()
+            })),
+            Err(e) => tracing::error!(
+                "❌ Failed to settle bank deposit {}: {}",
+                deposit.transaction_id,
+                e
+            ),
+        }
+    }
+
+    for payout in bank_repo::list_pending_payouts(pool, SETTLEMENT_BATCH_SIZE).await? {
+        match settle_payout(pool, provider, &payout).await {
+            Ok(payout) => summary.count(payout.map(|payout| {
+                payout.status == PayoutStatus::Settled.as_str()
+            })),
+            Err(e) => tracing::error!("❌ Failed to settle bank payout {}: {}", payout.id, e),
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Settle pending bank transfers now and then every few minutes, in the
+/// background, until shutdown
+pub fn spawn_settlement(shutdown: &Shutdown, pool: PgPool, provider: Arc<dyn BankProvider>) {
+    let stopped = shutdown.clone();
+    shutdown.spawn(async move {
+        let mut interval = tokio::time::interval(SETTLEMENT_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = stopped.stopped() => break,
+            }
+
+            match settle_pending_transfers(&pool, provider.as_ref()).await {
+                Ok(SettlementSummary { completed: 0, failed: 0 }) => {
+                    tracing::debug!("🏦 No bank transfers settled")
+                }
+                Ok(summary) => tracing::info!(
+                    "🏦 Bank transfers settled: {} completed, {} failed",
+                    summary.completed,
+                    summary.failed
+                ),
+                Err(e) => tracing::error!("❌ Failed to settle bank transfers: {}", e),
+            }
+        }
+    });
+}
+
+/// One of the user's linked accounts, if it can move money with `provider`
+async fn usable_account(
+    pool: &PgPool,
+    provider: &dyn BankProvider,
+    user_id: Uuid,
+    bank_account_id: Uuid,
+) -> Result<FundingSource, AppError> {
+    let source = bank_repo::find_active_account(pool, user_id, bank_account_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Bank account"))?;
+    if source.account.provider != provider.name() {
+        return Err(AppError::validation(
+            "This bank account was linked through another provider; please link it again",
+        ));
+    }
+
+    Ok(source)
+}
+
+/// Finish one deposit if the provider has an answer (None = still pending)
+async fn settle(
+    pool: &PgPool,
+    provider: &dyn BankProvider,
+    deposit: &UnsettledDeposit,
+) -> Result<Option<Transaction>, AppError> {
+    let update = match &deposit.provider_transfer_id {
+        Some(transfer_id) => provider.transfer_status(transfer_id).await?,
+        None if accepted_in_time(deposit.created_at) => return Ok(None),
+        None => unaccepted(),
+    };
+
+    finish_deposit(pool, deposit, &update).await
+}
+
+/// Finish one payout if the provider has an answer (None = still pending)
+async fn settle_payout(
+    pool: &PgPool,
+    provider: &dyn BankProvider,
+    payout: &BankPayout,
+) -> Result<Option<BankPayout>, AppError> {
+    let update = match &payout.provider_transfer_id {
+        Some(transfer_id) => provider.transfer_status(transfer_id).await?,
+        None if accepted_in_time(payout.created_at) => return Ok(None),
+        None => unaccepted(),
+    };
+
+    finish_payout(pool, payout, &update).await
+}
+
+/// Apply one transfer update from a webhook to the deposit or payout it is for
+async fn apply_update(pool: &PgPool, update: &TransferUpdate) -> Result<(), AppError> {
+    if let Some(deposit) = bank_repo::find_unsettled_deposit(pool, &update.transfer_id).await? {
+        finish_deposit(pool, &deposit, update).await?;
+    } else if let Some(payout) = bank_repo::find_payout_by_transfer(pool, &update.transfer_id).await? {
+        finish_payout(pool, &payout, update).await?;
+    } else {
+        tracing::warn!("🏦 Bank webhook for unknown transfer {}", update.transfer_id);
+    }
+
+    Ok(())
+}
+
+async fn finish_deposit(
+    pool: &PgPool,
+    deposit: &UnsettledDeposit,
+    update: &TransferUpdate,
+) -> Result<Option<Transaction>, AppError> {
+    let outcome = match update.status {
+        TransferStatus::Pending => return Ok(None),
+        TransferStatus::Settled => TransactionStatus::Completed,
+        TransferStatus::Failed | TransferStatus::Returned => TransactionStatus::Failed,
+    };
+
+    bank_repo::settle_deposit(pool, deposit.transaction_id, outcome)
+        .await
+        .map(Some)
+}
+
+async fn finish_payout(
+    pool: &PgPool,
+    payout: &BankPayout,
+    update: &TransferUpdate,
+) -> Result<Option<BankPayout>, AppError> {
+    let outcome = match update.status {
+        TransferStatus::Pending => return Ok(None),
+        TransferStatus::Settled => PayoutStatus::Settled,
+        TransferStatus::Returned => PayoutStatus::Returned,
+        TransferStatus::Failed => PayoutStatus::Failed,
+    };
+
+    let finished =
+        bank_repo::finish_payout(pool, payout.id, outcome, update.reason.as_deref()).await?;
+    if outcome == PayoutStatus::Returned {
+        tracing::warn!(
+            "🏦 Payout {} was returned by the bank ({}); the withdrawal was reversed",
+            payout.id,
+            update.reason.as_deref().unwrap_or("no reason given")
+        );
+    }
+
+    Ok(Some(finished))
+}
+
+/// Whether a transfer recorded at `created_at` may still be waiting for the
+/// provider to accept it
+fn accepted_in_time(created_at: DateTime<Utc>) -> bool {
+    Utc::now() - created_at < Duration::minutes(UNACCEPTED_TRANSFER_TIMEOUT_MINUTES)
+}
+
+/// The update for a transfer the provider never accepted
+fn unaccepted() -> TransferUpdate {
+    TransferUpdate {
+        transfer_id: String::new(),
+        status: TransferStatus::Failed,
+        reason: Some("never accepted by the bank provider".to_string()),
+    }
+}
+
+/// e.g. "Bank deposit from Chase ••0000"
+fn deposit_description(account: &LinkedBankAccount) -> String {
+    let bank = account
+        .institution_name
+        .as_deref()
+        .unwrap_or(&account.account_name);
+    match &account.mask {
+        Some(mask) => format!("Bank deposit from {} ••{}", bank, mask),
+        None => format!("Bank deposit from {}", bank),
+    }
+}
+
+/// e.g. "Payout to Chase ••0000"
+fn payout_description(account: &LinkedBankAccount) -> String {
+    let bank = account
+        .institution_name
+        .as_deref()
+        .unwrap_or(&account.account_name);
+    match &account.mask {
+        Some(mask) => format!("Payout to {} ••{}", bank, mask),
+        None => format!("Payout to {}", bank),
+    }
+}
+
+impl From<LinkedBankAccount> for LinkedBankAccountResponse {
+    fn from(account: LinkedBankAccount) -> Self {
+        LinkedBankAccountResponse {
+            id: account.id,
+            institution_name: account.institution_name,
+            account_name: account.account_name,
+            mask: account.mask,
+            subtype: account.subtype,
+            linked_at: account.created_at,
+        }
+    }
+}
+
+impl From<BankPayout> for BankPayoutResponse {
+    fn from(payout: BankPayout) -> Self {
+        BankPayoutResponse {
+            id: payout.id,
+            bank_account_id: payout.linked_account_id,
+            transaction_id: payout.transaction_id,
+            amount: payout.amount,
+            status: payout.status,
+            return_reason: payout.return_reason,
+            created_at: payout.created_at,
+            settled_at: payout.settled_at,
+            returned_at: payout.returned_at,
+            failed_at: payout.failed_at,
+        }
+    }
+}
@@ -0,0 +1,401 @@
+use crate::config::{CardProcessorKind, Config};
+use crate::domain::models::{Currency, WalletPayMethod};
+use crate::error::AppError;
+use crate::utils::secret::SecretString;
+use chrono::{Datelike, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// ============================================================================
+// CARD PROCESSORS
+// ============================================================================
+// Who charges the Apple Pay / Google Pay tokens behind wallet top-ups,
+// behind one trait:
+//
+// - `CheckoutProcessor` - Checkout.com, which decrypts both wallets' tokens
+//   itself, so we never hold the keys (or see a card number)
+// - `FakeCardProcessor` - approves everything, for development
+//
+// Charges are synchronous: the processor answers approved or declined
+// while the user waits. Every charge carries the DEPOSIT's id as its
+// idempotency key, so a retried request can't charge twice.
+//
+// A charged card can be kept by the processor for later top-ups; we only
+// get back its token and masked details (`VaultedCard`).
+//
+// CARD_PROCESSOR picks the implementation (see config.rs).
+
+/// One charge to make
+#[derive(Debug, Clone, Copy)]
+pub struct ChargeOrder<'a> {
+    /// Our id for the charge (the DEPOSIT transaction's id)
+    pub reference: Uuid,
+    pub method: WalletPayMethod,
+    pub amount: Decimal,
+    pub currency: Currency,
+    pub source: ChargeSource<'a>,
+}
+
+/// What a charge is paid with
+#[derive(Debug, Clone, Copy)]
+pub enum ChargeSource<'a> {
+    /// The wallet's encrypted payment token, as the browser got it; with
+    /// `save`, the processor keeps the card and reports it back
+    WalletToken {
+        payment_data: &'a serde_json::Value,
+        save: bool,
+    },
+    /// A card the processor kept earlier (its `VaultedCard::token`)
+    SavedCard(&'a str),
+}
+
+/// A card the processor keeps for us: its token and the masked details
+/// shown to the user, never the card number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultedCard {
+    pub token: String,
+    /// e.g. "Visa"
+    pub brand: Option<String>,
+    pub last4: String,
+    pub expiry_month: i16,
+    pub expiry_year: i16,
+}
+
+/// What the processor said about a charge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChargeOutcome {
+    /// `card` is the kept card when a wallet token was charged with `save`
+    Approved { payment_id: String, card: Option<VaultedCard> },
+    Declined { payment_id: Option<String>, reason: String },
+}
+
+/// The Google Pay tokenization gateway a processor is known by
+#[derive(Debug, Clone)]
+pub struct GooglePayGateway {
+    /// e.g. "checkoutltd"
+    pub gateway: &'static str,
+    pub gateway_merchant_id: String,
+}
+
+/// Charges device wallet payment tokens
+#[async_trait::async_trait]
+pub trait CardProcessor: Send + Sync {
+    /// Stored with every payment, e.g. "checkout"
+    fn name(&self) -> &'static str;
+
+    /// What Google Pay should encrypt its tokens for
+    fn google_pay_gateway(&self) -> GooglePayGateway;
+
+    /// Charge a payment token
+    ///
+    /// A declined card is an `Ok(Declined)`; `Err` means we couldn't get
+    /// an answer at all.
+    async fn charge(&self, order: ChargeOrder<'_>) -> Result<ChargeOutcome, AppError>;
+}
+
+/// The processor chosen by CARD_PROCESSOR (None when card top-ups are off)
+pub fn from_config(config: &Config) -> Option<Arc<dyn CardProcessor>> {
+    match config.card_processor {
+        CardProcessorKind::Checkout => Some(Arc::new(CheckoutProcessor::new(
+            config.checkout_environment.base_url(),
+            config.checkout_secret_key.clone(),
+            config.checkout_public_key.clone(),
+        ))),
+        CardProcessorKind::Fake => Some(Arc::new(FakeCardProcessor)),
+        CardProcessorKind::Off => None,
+    }
+}
+
+// ============================================================================
+// CHECKOUT.COM
+// ============================================================================
+// Two calls per top-up:
+//
+// 1. POST /tokens (public key) swaps the wallet's payment data for a
+//    Checkout.com token:
+//      { "type": "applepay", "token_data": { "version": "EC_v1", ... } }
+//    -> { "token": "tok_..." }
+// 2. POST /payments (secret key) charges and captures it:
+//      { "source": { "type": "token", "token": "tok_...", "store_for_future_use": true },
+//        "amount": 5000, "currency": "USD", "reference": "...", "capture": true }
+//    -> { "id": "pay_...", "approved": true, "status": "Captured",
+//         "source": { "id": "src_...", "scheme": "Visa", "last4": "4242",
+//                     "expiry_month": 6, "expiry_year": 2029 }, ... }
+//
+// A saved card skips step 1 and is charged by its source id:
+//   { "source": { "type": "id", "id": "src_..." }, ... }
+
+/// Checkout.com API client
+pub struct CheckoutProcessor {
+    http: reqwest::Client,
+    base_url: String,
+    secret_key: SecretString,
+    public_key: String,
+}
+
+/// The user is waiting on the charge, but a slow one is better than a
+/// charge we never heard back from
+const CHECKOUT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+#[derive(Debug, Deserialize)]
+struct CheckoutToken {
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckoutPayment {
+    id: Option<String>,
+    #[serde(default)]
+    approved: bool,
+    status: Option<String>,
+    response_summary: Option<String>,
+    source: Option<CheckoutSource>,
+}
+
+/// The card a payment was made with, as Checkout.com stored it
+#[derive(Debug, Deserialize)]
+struct CheckoutSource {
+    id: Option<String>,
+    scheme: Option<String>,
+    last4: Option<String>,
+    expiry_month: Option<i16>,
+    expiry_year: Option<i16>,
+}
+
+impl CheckoutSource {
+    /// The card to save (None if Checkout.com didn't keep it)
+    fn into_vaulted(self) -> Option<VaultedCard> {
+        Some(VaultedCard {
+            token: self.id?,
+            brand: self.scheme,
+            last4: self.last4?,
+            expiry_month: self.expiry_month?,
+            expiry_year: self.expiry_year?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckoutError {
+    error_type: Option<String>,
+    #[serde(default)]
+    error_codes: Vec<String>,
+}
+
+impl CheckoutProcessor {
+    pub fn new(base_url: &str, secret_key: SecretString, public_key: String) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(CHECKOUT_TIMEOUT)
+            .build()
+            .expect("TLS backend is available");
+
+        CheckoutProcessor {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            secret_key,
+            public_key,
+        }
+    }
+
+    /// Swap the wallet's payment data for a Checkout.com token
+    ///
+    /// None when Checkout.com refused the payment data (e.g. it was
+    /// tampered with or has expired).
+    async fn tokenize(
+        &self,
+        method: WalletPayMethod,
+        payment_data: &serde_json::Value,
+    ) -> Result<Option<String>, AppError> {
+        let token_type = match method {
+            WalletPayMethod::ApplePay => "applepay",
+            WalletPayMethod::GooglePay => "googlepay",
+        };
+
+        let response = self
+            .http
+            .post(format!("{}/tokens", self.base_url))
+            .bearer_auth(&self.public_key)
+            .json(&serde_json::json!({
+                "type": token_type,
+                "token_data": payment_data,
+            }))
+            .send()
+            .await
+            .map_err(request_failed)?;
+
+        if response.status().is_client_error() {
+            let error: Option<CheckoutError> = response.json().await.ok();
+            tracing::warn!("Checkout.com refused a {} token: {:?}", token_type, error);
+            return Ok(None);
+        }
+
+        let token: CheckoutToken = response
+            .error_for_status()
+            .map_err(request_failed)?
+            .json()
+            .await
+            .map_err(unexpected)?;
+
+        Ok(Some(token.token))
+    }
+}
+
+#[async_trait::async_trait]
+impl CardProcessor for CheckoutProcessor {
+    fn name(&self) -> &'static str {
+        "checkout"
+    }
+
+    fn google_pay_gateway(&self) -> GooglePayGateway {
+        GooglePayGateway {
+            gateway: "checkoutltd",
+            gateway_merchant_id: self.public_key.clone(),
+        }
+    }
+
+    async fn charge(&self, order: ChargeOrder<'_>) -> Result<ChargeOutcome, AppError> {
+        let (source, save) = match order.source {
+            ChargeSource::WalletToken { payment_data, save } => {
+                let Some(token) = self.tokenize(order.method, payment_data).await? else {
+                    return Ok(ChargeOutcome::Declined {
+                        payment_id: None,
+                        reason: "The payment token was not accepted".to_string(),
+                    });
+                };
+                let source = serde_json::json!({
+                    "type": "token",
+                    "token": token,
+                    "store_for_future_use": save,
+                });
+                (source, save)
+            }
+            ChargeSource::SavedCard(id) => (serde_json::json!({ "type": "id", "id": id }), false),
+        };
+
+        let response = self
+            .http
+            .post(format!("{}/payments", self.base_url))
+            .bearer_auth(self.secret_key.expose_secret())
+            .header("Cko-Idempotency-Key", order.reference.to_string())
+            .json(&serde_json::json!({
+                "source": source,
+                "amount": minor_units(order.amount, order.currency)?,
+                "currency": order.currency.as_str(),
+                "reference": order.reference,
+                "capture": true,
+            }))
+            .send()
+            .await
+            .map_err(request_failed)?;
+
+        let status = response.status();
+        if status.is_client_error() {
+            let error: Option<CheckoutError> = response.json().await.ok();
+            tracing::warn!("Checkout.com rejected payment {}: {:?}", order.reference, error);
+            let reason = error
+                .and_then(|e| e.error_codes.first().cloned().or(e.error_type))
+                .unwrap_or_else(|| status.to_string());
+            return Ok(ChargeOutcome::Declined { payment_id: None, reason });
+        }
+
+        let payment: CheckoutPayment = response
+            .error_for_status()
+            .map_err(request_failed)?
+            .json()
+            .await
+            .map_err(unexpected)?;
+        let payment_id = payment.id.ok_or_else(|| unexpected("payment without an id"))?;
+
+        // 202 Pending means 3-D Secure is needed; top-ups only ask the
+        // wallets for cryptogram tokens, which never need it
+        if status != reqwest::StatusCode::CREATED || !payment.approved {
+            let reason = payment
+                .response_summary
+                .or(payment.status)
+                .unwrap_or_else(|| "Declined".to_string());
+            return Ok(ChargeOutcome::Declined { payment_id: Some(payment_id), reason });
+        }
+
+        let card = if save { payment.source.and_then(CheckoutSource::into_vaulted) } else { None };
+        Ok(ChargeOutcome::Approved { payment_id, card })
+    }
+}
+
+/// The amount in the currency's smallest unit (cents; whole yen)
+fn minor_units(amount: Decimal, currency: Currency) -> Result<i64, AppError> {
+    let exponent = match currency {
+        Currency::Jpy => 0,
+        _ => 2,
+    };
+
+    let minor = amount * Decimal::from(10_i64.pow(exponent));
+    if minor.fract() != Decimal::ZERO {
+        return Err(AppError::validation(&format!(
+            "{} amounts can have at most {} decimal places",
+            currency, exponent
+        )));
+    }
+    minor
+        .to_i64()
+        .ok_or_else(|| AppError::validation("Amount is too large"))
+}
+
+fn request_failed(e: reqwest::Error) -> AppError {
+    tracing::error!("Checkout.com request failed: {}", e);
+    AppError::internal("Card processor is unreachable")
+}
+
+fn unexpected(e: impl std::fmt::Display) -> AppError {
+    tracing::error!("Unexpected response from Checkout.com: {}", e);
+    AppError::internal("Unexpected response from card processor")
+}
+
+// ============================================================================
+// FAKE PROCESSOR
+// ============================================================================
+
+/// Approves every charge, except payment data with `"decline": true`
+/// (development); saved cards are a test Visa ending in 4242
+pub struct FakeCardProcessor;
+
+#[async_trait::async_trait]
+impl CardProcessor for FakeCardProcessor {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    /// Google's own test gateway, which works with its TEST environment
+    fn google_pay_gateway(&self) -> GooglePayGateway {
+        GooglePayGateway {
+            gateway: "example",
+            gateway_merchant_id: "exampleGatewayMerchantId".to_string(),
+        }
+    }
+
+    async fn charge(&self, order: ChargeOrder<'_>) -> Result<ChargeOutcome, AppError> {
+        let payment_id = format!("fake-card-{}", order.reference);
+
+        let ChargeSource::WalletToken { payment_data, save } = order.source else {
+            return Ok(ChargeOutcome::Approved { payment_id, card: None });
+        };
+
+        if payment_data.get("decline") == Some(&serde_json::Value::Bool(true)) {
+            return Ok(ChargeOutcome::Declined {
+                payment_id: Some(payment_id),
+                reason: "Declined (test card)".to_string(),
+            });
+        }
+
+        let card = save.then(|| VaultedCard {
+            token: format!("fake-src-{}", order.reference),
+            brand: Some("Visa".to_string()),
+            last4: "4242".to_string(),
+            expiry_month: 12,
+            expiry_year: Utc::now().year() as i16 + 3,
+        });
+        Ok(ChargeOutcome::Approved { payment_id, card })
+    }
+}
@@ -0,0 +1,295 @@
+use crate::config::Config;
+use crate::domain::models::{
+    ApplePayConfig, CardDepositRequest, CardPayConfigResponse, GooglePayConfig,
+    SavedCardDepositRequest, SavedCardResponse, Transaction, TransactionStatus, WalletPayMethod,
+};
+use crate::error::AppError;
+use crate::repository::card_repo::{self, SavedCard};
+use crate::repository::user_repo;
+use crate::services::apple_pay::ApplePayMerchant;
+use crate::services::card_processor::{
+    CardProcessor, ChargeOrder, ChargeOutcome, ChargeSource, VaultedCard,
+};
+use crate::services::wallet_service;
+use chrono::{Datelike, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// CARD SERVICE
+// ============================================================================
+// Wallet top-ups with Apple Pay and Google Pay.
+//
+// 1. The deposit page asks GET /wallet/deposit/card/config which wallets
+//    are available, and the browser gets a payment token from one of them
+//    (Apple Pay first validates the merchant, see apple_pay.rs)
+// 2. POST /wallet/deposit/card records a PENDING DEPOSIT and hands the
+//    token to the card processor
+// 3. The processor's answer completes the DEPOSIT (the wallet is credited)
+//    or fails it
+//
+// Unlike bank deposits there is nothing to wait for: card payments are
+// approved or declined on the spot.
+//
+// With `save_card` the processor keeps the card and we keep its token and
+// masked details (card_repo's saved cards), so later top-ups can go
+// through POST /wallet/deposit/saved-card without the device wallet. The
+// card number itself never reaches us.
+
+/// Card networks offered on the Apple Pay sheet
+const APPLE_PAY_NETWORKS: [&str; 4] = ["visa", "masterCard", "amex", "discover"];
+
+/// Card networks offered in Google Pay
+const GOOGLE_PAY_NETWORKS: [&str; 4] = ["VISA", "MASTERCARD", "AMEX", "DISCOVER"];
+
+/// Which wallets the deposit page can offer, and how to set them up
+///
+/// Apple Pay needs a configured merchant; Google Pay needs a merchant id
+/// in production only (Google's TEST environment works without one).
+pub async fn pay_config(
+    pool: &PgPool,
+    processor: &dyn CardProcessor,
+    apple_pay: Option<&ApplePayMerchant>,
+    config: &Config,
+    user_id: Uuid,
+) -> Result<CardPayConfigResponse, AppError> {
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+
+    let apple_pay = apple_pay.map(|merchant| ApplePayConfig {
+        merchant_id: merchant.merchant_id.clone(),
+        display_name: merchant.display_name.clone(),
+        supported_networks: APPLE_PAY_NETWORKS.to_vec(),
+    });
+
+    let google_pay = if config.is_production() && config.google_pay_merchant_id.is_none() {
+        None
+    } else {
+        let gateway = processor.google_pay_gateway();
+        Some(GooglePayConfig {
+            environment: if config.is_production() { "PRODUCTION" } else { "TEST" },
+            merchant_id: config.google_pay_merchant_id.clone(),
+            gateway: gateway.gateway,
+            gateway_merchant_id: gateway.gateway_merchant_id,
+            allowed_card_networks: GOOGLE_PAY_NETWORKS.to_vec(),
+        })
+    };
+
+    Ok(CardPayConfigResponse {
+        apple_pay,
+        google_pay,
+        currency: wallet.currency,
+    })
+}
+
+/// Top up the wallet with an Apple Pay or Google Pay token
+///
+/// Returns the COMPLETED deposit. A declined payment leaves a FAILED
+/// deposit in the history and fails with `TransactionFailed`. With
+/// `save_card`, the card is also saved for later top-ups.
+pub async fn deposit(
+    pool: &PgPool,
+    processor: &dyn CardProcessor,
+    user_id: Uuid,
+    req: &CardDepositRequest,
+) -> Result<Transaction, AppError> {
+    check_amount(req.amount)?;
+    wallet_service::ensure_can_move_money(pool, user_id).await?;
+    if !req.payment_data.is_object() {
+        return Err(AppError::validation("payment_data must be the wallet's payment token object"));
+    }
+
+    let source = ChargeSource::WalletToken {
+        payment_data: &req.payment_data,
+        save: req.save_card,
+    };
+    let description = format!("{} top-up", req.method.label());
+    let (transaction, card) =
+        charge(pool, processor, user_id, req.method, None, req.amount, &description, source).await?;
+
+    // The money is in either way; a card that couldn't be saved can be
+    // saved with the next top-up
+    if let Some(card) = card {
+        if let Err(e) = card_repo::save_card(pool, user_id, processor.name(), req.method, &card).await {
+            tracing::error!("Failed to save card for user {}: {}", user_id, e);
+        }
+    }
+
+    Ok(transaction)
+}
+
+/// Top up the wallet from a saved card (the default one unless `card_id`
+/// names another)
+pub async fn deposit_saved(
+    pool: &PgPool,
+    processor: &dyn CardProcessor,
+    user_id: Uuid,
+    req: &SavedCardDepositRequest,
+) -> Result<Transaction, AppError> {
+    check_amount(req.amount)?;
+    wallet_service::ensure_can_move_money(pool, user_id).await?;
+
+    let card = card_repo::find_saved_card(pool, user_id, req.card_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Saved card"))?;
+
+    // Tokens only mean something to the processor that issued them
+    if card.processor != processor.name() {
+        return Err(AppError::validation(
+            "This card was saved with another card processor; add it again with Apple Pay or Google Pay",
+        ));
+    }
+    let today = Utc::now().date_naive();
+    if (card.expiry_year as i32, card.expiry_month as u32) < (today.year(), today.month()) {
+        return Err(AppError::validation("This card has expired"));
+    }
+    let method = WalletPayMethod::parse(&card.method)
+        .ok_or_else(|| AppError::InternalError(format!("Unknown card method {}", card.method)))?;
+
+    let description = format!(
+        "{} •••• {} top-up",
+        card.brand.as_deref().unwrap_or("Card"),
+        card.last4
+    );
+    let source = ChargeSource::SavedCard(&card.processor_token);
+    let (transaction, _) =
+        charge(pool, processor, user_id, method, Some(card.id), req.amount, &description, source)
+            .await?;
+
+    Ok(transaction)
+}
+
+/// The user's saved cards, the default first
+pub async fn list_cards(pool: &PgPool, user_id: Uuid) -> Result<Vec<SavedCardResponse>, AppError> {
+    let cards = card_repo::list_saved_cards(pool, user_id).await?;
+
+    Ok(cards.into_iter().map(SavedCardResponse::from).collect())
+}
+
+/// Forget a saved card
+///
+/// Only our copy of the token goes; past top-ups from it stay in the
+/// history.
+pub async fn delete_card(pool: &PgPool, user_id: Uuid, card_id: Uuid) -> Result<(), AppError> {
+    if !card_repo::delete_saved_card(pool, user_id, card_id).await? {
+        return Err(AppError::not_found("Saved card"));
+    }
+
+    Ok(())
+}
+
+/// Make a saved card the default funding source
+pub async fn set_default_card(pool: &PgPool, user_id: Uuid, card_id: Uuid) -> Result<(), AppError> {
+    if !card_repo::set_default_card(pool, user_id, card_id).await? {
+        return Err(AppError::not_found("Saved card"));
+    }
+
+    Ok(())
+}
+
+fn check_amount(amount: Decimal) -> Result<(), AppError> {
+    if amount.round_dp(2) != amount {
+        return Err(AppError::validation("Amount can have at most 2 decimal places"));
+    }
+    Ok(())
+}
+
+/// Record, charge and settle one top-up; returns the COMPLETED deposit and
+/// the card the processor kept, if asked to
+#[allow(clippy::too_many_arguments)]
+async fn charge(
+    pool: &PgPool,
+    processor: &dyn CardProcessor,
+    user_id: Uuid,
+    method: WalletPayMethod,
+    saved_card_id: Option<Uuid>,
+    amount: Decimal,
+    description: &str,
+    source: ChargeSource<'_>,
+) -> Result<(Transaction, Option<VaultedCard>), AppError> {
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+
+    // 1. Record the PENDING deposit first, so we never charge a card
+    //    without a record of it
+    let transaction = card_repo::start_payment(
+        pool,
+        wallet.id,
+        user_id,
+        method,
+        processor.name(),
+        saved_card_id,
+        amount,
+        description,
+    )
+    .await?;
+
+    // 2. Charge the token
+    let order = ChargeOrder {
+        reference: transaction.id,
+        method,
+        amount,
+        currency: wallet.currency,
+        source,
+    };
+    let outcome = match processor.charge(order).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            card_repo::settle_payment(
+                pool,
+                transaction.id,
+                TransactionStatus::Failed,
+                None,
+                Some("No answer from the card processor"),
+            )
+            .await?;
+            return Err(e);
+        }
+    };
+
+    // 3. Credit the wallet, or record the decline
+    match outcome {
+        ChargeOutcome::Approved { payment_id, card } => {
+            let transaction = card_repo::settle_payment(
+                pool,
+                transaction.id,
+                TransactionStatus::Completed,
+                Some(&payment_id),
+                None,
+            )
+            .await?;
+            Ok((transaction, card))
+        }
+        ChargeOutcome::Declined { payment_id, reason } => {
+            card_repo::settle_payment(
+                pool,
+                transaction.id,
+                TransactionStatus::Failed,
+                payment_id.as_deref(),
+                Some(&reason),
+            )
+            .await?;
+            tracing::info!("💳 {} top-up {} declined: {}", method.label(), transaction.id, reason);
+            Err(declined(method, &reason))
+        }
+    }
+}
+
+fn declined(method: WalletPayMethod, reason: &str) -> AppError {
+    AppError::TransactionFailed(format!("{} payment declined: {}", method.label(), reason))
+}
+
+impl From<SavedCard> for SavedCardResponse {
+    fn from(card: SavedCard) -> Self {
+        SavedCardResponse {
+            id: card.id,
+            // Guarded by a CHECK constraint
+            method: WalletPayMethod::parse(&card.method).unwrap_or(WalletPayMethod::ApplePay),
+            brand: card.brand,
+            last4: card.last4,
+            expiry_month: card.expiry_month,
+            expiry_year: card.expiry_year,
+            is_default: card.is_default,
+            saved_at: card.created_at,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+
+// ============================================================================
+// CLOCK
+// ============================================================================
+// What time it is, for code that has to decide something by it in-process:
+// rate-limit windows, token expiry, the account restore window, debug
+// capture expiry. It comes from `AppState::clock`; tests swap in a clock
+// they can move forward instead of sleeping. Timestamps written to the
+// database still come from NOW().
+//
+// New time-dependent logic should take the clock too, rather than calling
+// `Utc::now()` itself.
+
+/// Tells the time
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The real time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
@@ -0,0 +1,170 @@
+use crate::config::{Config, CryptoProviderKind};
+use crate::domain::models::CryptoAsset;
+use crate::error::AppError;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::str::FromStr;
+use std::sync::Arc;
+
+// ============================================================================
+// CRYPTO PRICE PROVIDERS
+// ============================================================================
+// The prices crypto trades execute at, behind one trait:
+//
+// - `CoinbaseProvider` - Coinbase's public spot buy and sell prices (no key)
+// - `FixedCryptoProvider` - built-in prices for development, no network
+//
+// Quotes are fetched fresh for every trade; a crypto price from a minute
+// ago is already wrong. Buy prices sit above sell prices (the spread), so
+// buying and selling straight back never makes money.
+//
+// CRYPTO_PROVIDER picks the implementation (see config.rs).
+
+/// A price for one asset, in USD per whole coin
+#[derive(Debug, Clone, Copy)]
+pub struct CryptoQuote {
+    pub asset: CryptoAsset,
+    /// What buying one coin costs
+    pub buy_price: Decimal,
+    /// What selling one coin raises
+    pub sell_price: Decimal,
+    pub quoted_at: DateTime<Utc>,
+}
+
+/// Quotes crypto prices
+#[async_trait::async_trait]
+pub trait CryptoProvider: Send + Sync {
+    /// Stored with every trade, e.g. "coinbase"
+    fn name(&self) -> &'static str;
+
+    /// The current buy and sell price of `asset`
+    async fn quote(&self, asset: CryptoAsset) -> Result<CryptoQuote, AppError>;
+}
+
+/// The provider chosen by CRYPTO_PROVIDER (None when crypto trading is off)
+pub fn from_config(config: &Config) -> Option<Arc<dyn CryptoProvider>> {
+    match config.crypto_provider {
+        CryptoProviderKind::Coinbase => Some(Arc::new(CoinbaseProvider::new())),
+        CryptoProviderKind::Fixed => Some(Arc::new(FixedCryptoProvider)),
+        CryptoProviderKind::Off => None,
+    }
+}
+
+// ============================================================================
+// COINBASE
+// ============================================================================
+// GET https://api.coinbase.com/v2/prices/BTC-USD/buy (and /sell)
+//
+//   { "data": { "amount": "64012.55", "base": "BTC", "currency": "USD" } }
+
+/// Coinbase price API client
+pub struct CoinbaseProvider {
+    http: reqwest::Client,
+}
+
+const COINBASE_URL: &str = "https://api.coinbase.com/v2";
+
+/// A trade waits on this, so keep it short
+const COINBASE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Debug, Deserialize)]
+struct CoinbasePrice {
+    data: CoinbaseAmount,
+}
+
+#[derive(Debug, Deserialize)]
+struct CoinbaseAmount {
+    amount: String,
+}
+
+impl CoinbaseProvider {
+    pub fn new() -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(COINBASE_TIMEOUT)
+            .build()
+            .expect("TLS backend is available");
+
+        CoinbaseProvider { http }
+    }
+
+    /// One price; `side` is "buy" or "sell"
+    async fn price(&self, asset: CryptoAsset, side: &str) -> Result<Decimal, AppError> {
+        let url = format!("{}/prices/{}-USD/{}", COINBASE_URL, asset, side);
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| {
+                tracing::error!("Crypto price request failed: {}", e);
+                AppError::internal("Crypto price provider is unreachable")
+            })?;
+
+        let body: CoinbasePrice = response.json().await.map_err(|e| {
+            tracing::error!("Unexpected crypto price response from {}: {}", url, e);
+            AppError::internal("Unexpected response from crypto price provider")
+        })?;
+
+        Decimal::from_str(&body.data.amount)
+            .ok()
+            .filter(|price| *price > Decimal::ZERO)
+            .ok_or_else(|| AppError::internal("Unexpected response from crypto price provider"))
+    }
+}
+
+impl Default for CoinbaseProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl CryptoProvider for CoinbaseProvider {
+    fn name(&self) -> &'static str {
+        "coinbase"
+    }
+
+    async fn quote(&self, asset: CryptoAsset) -> Result<CryptoQuote, AppError> {
+        let (buy_price, sell_price) =
+            tokio::try_join!(self.price(asset, "buy"), self.price(asset, "sell"))?;
+
+        Ok(CryptoQuote {
+            asset,
+            buy_price,
+            sell_price,
+            quoted_at: Utc::now(),
+        })
+    }
+}
+
+// ============================================================================
+// FIXED PRICES
+// ============================================================================
+// Round numbers for development (CRYPTO_PROVIDER=fixed, never allowed in
+// production), with a 1% spread like a real exchange.
+
+/// Offline crypto price provider for development
+pub struct FixedCryptoProvider;
+
+#[async_trait::async_trait]
+impl CryptoProvider for FixedCryptoProvider {
+    fn name(&self) -> &'static str {
+        "fixed"
+    }
+
+    async fn quote(&self, asset: CryptoAsset) -> Result<CryptoQuote, AppError> {
+        let (buy_price, sell_price) = match asset {
+            CryptoAsset::Btc => (Decimal::new(6_030_000, 2), Decimal::new(5_970_000, 2)),
+            CryptoAsset::Eth => (Decimal::new(301_500, 2), Decimal::new(298_500, 2)),
+        };
+
+        Ok(CryptoQuote {
+            asset,
+            buy_price,
+            sell_price,
+            quoted_at: Utc::now(),
+        })
+    }
+}
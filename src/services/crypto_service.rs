@@ -0,0 +1,213 @@
+use crate::domain::models::{
+    CryptoAsset, CryptoHoldingResponse, CryptoQuoteResponse, CryptoTradeRequest,
+    CryptoTradeResponse, Currency, TradeSide,
+};
+use crate::error::AppError;
+use crate::repository::crypto_repo::{self, CryptoTrade, NewTrade};
+use crate::repository::user_repo;
+use crate::services::crypto_provider::CryptoProvider;
+use crate::services::wallet_service;
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// CRYPTO SERVICE
+// ============================================================================
+// Buying and selling BTC/ETH against the USD wallet. The coins are ledger
+// balances only: nothing is sent on-chain, and they can't be withdrawn.
+//
+// Precision:
+// - Quantities are cut to the asset's smallest unit (8 places for BTC, 18
+//   for ETH); USD amounts to cents
+// - Rounding always favours the ledger, never the user's balance going
+//   negative: spending $250 buys the coins $250 covers, rounded down; a
+//   quantity bought costs its price rounded up to the next cent; a sale
+//   raises its price rounded down
+//
+// Every trade shows in the wallet history as a WITHDRAWAL (buy) or
+// DEPOSIT (sell) labelled e.g. "Bought 0.00414593 BTC @ 60300.00 USD".
+
+/// Trades shown by GET /crypto/trades
+const TRADE_HISTORY_LIMIT: i64 = 50;
+
+/// The current buy and sell price of every asset
+pub async fn quotes(provider: &dyn CryptoProvider) -> Result<Vec<CryptoQuoteResponse>, AppError> {
+    let mut quotes = Vec::with_capacity(CryptoAsset::ALL.len());
+    for asset in CryptoAsset::ALL {
+        let quote = provider.quote(asset).await?;
+        quotes.push(CryptoQuoteResponse {
+            asset,
+            buy_price: quote.buy_price,
+            sell_price: quote.sell_price,
+            provider: provider.name().to_string(),
+            quoted_at: quote.quoted_at,
+        });
+    }
+
+    Ok(quotes)
+}
+
+/// The user's crypto balances, valued at the current sell price
+///
+/// A holding whose price can't be fetched right now is still listed, with
+/// no value.
+pub async fn holdings(
+    pool: &PgPool,
+    provider: &dyn CryptoProvider,
+    user_id: Uuid,
+) -> Result<Vec<CryptoHoldingResponse>, AppError> {
+    let mut holdings = Vec::new();
+    for holding in crypto_repo::list_holdings(pool, user_id).await? {
+        let Some(asset) = CryptoAsset::parse(&holding.asset) else {
+            continue;
+        };
+        let usd_value = match provider.quote(asset).await {
+            Ok(quote) => Some(cents_down(holding.quantity * quote.sell_price)),
+            Err(e) => {
+                tracing::warn!("⚠️  No {} price for valuing holdings: {}", asset, e);
+                None
+            }
+        };
+
+        holdings.push(CryptoHoldingResponse {
+            asset,
+            quantity: holding.quantity.normalize(),
+            usd_value,
+        });
+    }
+
+    Ok(holdings)
+}
+
+/// Buy or sell crypto at the provider's current price
+///
+/// Fails with `InsufficientBalance` if the wallet (buy) or the holding
+/// (sell) can't cover it, and with a validation error for amounts finer
+/// than the asset or USD allow, or too small to trade.
+pub async fn trade(
+    pool: &PgPool,
+    provider: &dyn CryptoProvider,
+    user_id: Uuid,
+    request: &CryptoTradeRequest,
+) -> Result<CryptoTradeResponse, AppError> {
+    wallet_service::ensure_can_move_money(pool, user_id).await?;
+    let asset = request.asset;
+    let decimals = asset.decimals();
+
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+    if wallet.currency != Currency::Usd {
+        return Err(AppError::currency_mismatch(wallet.currency, Currency::Usd));
+    }
+
+    let quote = provider.quote(asset).await?;
+    let (quantity, price, usd_amount) = match (request.side, request.quantity, request.usd_amount) {
+        (side, Some(quantity), None) => {
+            if quantity.normalize().scale() > decimals {
+                return Err(AppError::validation(&format!(
+                    "{} quantities have at most {} decimal places",
+                    asset, decimals
+                )));
+            }
+            match side {
+                TradeSide::Buy => (quantity, quote.buy_price, cents_up(quantity * quote.buy_price)),
+                TradeSide::Sell => {
+                    (quantity, quote.sell_price, cents_down(quantity * quote.sell_price))
+                }
+            }
+        }
+        (side, None, Some(usd_amount)) => {
+            if usd_amount.normalize().scale() > 2 {
+                return Err(AppError::validation("USD amounts have at most 2 decimal places"));
+            }
+            match side {
+                TradeSide::Buy => (
+                    (usd_amount / quote.buy_price)
+                        .round_dp_with_strategy(decimals, RoundingStrategy::ToZero),
+                    quote.buy_price,
+                    usd_amount,
+                ),
+                TradeSide::Sell => (
+                    (usd_amount / quote.sell_price)
+                        .round_dp_with_strategy(decimals, RoundingStrategy::AwayFromZero),
+                    quote.sell_price,
+                    usd_amount,
+                ),
+            }
+        }
+        _ => return Err(AppError::validation("Give either quantity or usd_amount")),
+    };
+
+    if quantity.is_zero() || usd_amount.is_zero() {
+        return Err(AppError::validation(&format!("Amount is too small to trade {}", asset)));
+    }
+
+    let description = trade_description(request.side, asset, quantity, price);
+    let trade = crypto_repo::record_trade(
+        pool,
+        user_id,
+        wallet.id,
+        &NewTrade {
+            asset: asset.as_str(),
+            side: request.side,
+            quantity,
+            price,
+            usd_amount,
+            provider: provider.name(),
+            description: &description,
+        },
+    )
+    .await?;
+
+    tracing::info!("🪙 User {}: {}", user_id, description);
+
+    CryptoTradeResponse::try_from(trade)
+}
+
+/// The user's most recent trades, newest first
+pub async fn list_trades(pool: &PgPool, user_id: Uuid) -> Result<Vec<CryptoTradeResponse>, AppError> {
+    crypto_repo::list_trades(pool, user_id, TRADE_HISTORY_LIMIT)
+        .await?
+        .into_iter()
+        .map(CryptoTradeResponse::try_from)
+        .collect()
+}
+
+/// e.g. "Bought 0.00414593 BTC @ 60300.00 USD"
+fn trade_description(side: TradeSide, asset: CryptoAsset, quantity: Decimal, price: Decimal) -> String {
+    let verb = match side {
+        TradeSide::Buy => "Bought",
+        TradeSide::Sell => "Sold",
+    };
+    format!("{} {} {} @ {:.2} USD", verb, quantity.normalize(), asset, price)
+}
+
+fn cents_up(amount: Decimal) -> Decimal {
+    amount.round_dp_with_strategy(2, RoundingStrategy::AwayFromZero)
+}
+
+fn cents_down(amount: Decimal) -> Decimal {
+    amount.round_dp_with_strategy(2, RoundingStrategy::ToZero)
+}
+
+impl TryFrom<CryptoTrade> for CryptoTradeResponse {
+    type Error = AppError;
+
+    fn try_from(trade: CryptoTrade) -> Result<Self, AppError> {
+        let asset = CryptoAsset::parse(&trade.asset)
+            .ok_or_else(|| AppError::internal(&format!("Unknown crypto asset {}", trade.asset)))?;
+        let side = TradeSide::parse(&trade.side)
+            .ok_or_else(|| AppError::internal(&format!("Unknown trade side {}", trade.side)))?;
+
+        Ok(CryptoTradeResponse {
+            id: trade.id,
+            asset,
+            side,
+            quantity: trade.quantity.normalize(),
+            price: trade.price,
+            usd_amount: trade.usd_amount,
+            transaction_id: trade.transaction_id,
+            created_at: trade.created_at,
+        })
+    }
+}
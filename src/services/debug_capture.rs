@@ -0,0 +1,410 @@
+use crate::config::Config;
+use crate::error::AppError;
+use crate::services::clock::Clock;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+
+// ============================================================================
+// DEBUG CAPTURE
+// ============================================================================
+// When a client integration misbehaves ("your API says 400 but our request
+// is fine"), an admin can switch on capture for that user or route for a
+// while and read back exactly what was sent and answered:
+//
+//   POST   /api/admin/debug-capture/targets  { "user_id": "...", "minutes": 15 }
+//   GET    /api/admin/debug-capture          what was captured, newest first
+//   DELETE /api/admin/debug-capture/targets/:id
+//
+// Only requests matching an active target are captured, so the cost is nil
+// the rest of the time. Targets expire on their own (at most a day).
+//
+// Everything is sanitized before it is kept: credentials in headers,
+// and passwords, tokens, card and account numbers in JSON bodies, form
+// bodies and query strings are replaced with "[REDACTED]", as are the
+// one-time codes requests carry (transfer confirmations, email
+// verification, the OIDC callback's `?code=`). Bodies are
+// truncated to MAX_BODY_BYTES, and non-text responses (PDF exports, ...)
+// are described instead of kept.
+//
+// The ring buffer (DEBUG_CAPTURE_CAPACITY pairs, oldest dropped first) and
+// the targets live in this process's memory: with several instances, turn
+// capture on and read it back on each, and expect it to be gone after a
+// restart. That's deliberate; captures are for a quick look, not an audit
+// log.
+
+/// Longest a capture can run
+pub const MAX_CAPTURE_MINUTES: i64 = 24 * 60;
+
+/// Bodies are cut off after this many bytes
+pub const MAX_BODY_BYTES: usize = 16 * 1024;
+
+/// What replaces a sanitized value
+const REDACTED: &str = "[REDACTED]";
+
+/// Headers that are never kept
+const SENSITIVE_HEADERS: [&str; 6] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-csrf-token",
+    "x-api-key",
+];
+
+/// Body and query fields are redacted if their name contains one of these
+const SENSITIVE_FIELDS: [&str; 9] = [
+    "password",
+    "token",
+    "secret",
+    "card_number",
+    "cvv",
+    "cvc",
+    "account_number",
+    "iban",
+    "otp",
+];
+
+/// Request body and query fields redacted if their name is exactly one of
+/// these: one-time codes. Matched exactly and only in requests, since error
+/// responses use `code` for the error code, which is worth keeping.
+const SENSITIVE_REQUEST_FIELDS: [&str; 1] = ["code"];
+
+/// Which requests to capture: a user's, a route's, or a user's on a route
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureTarget {
+    pub id: Uuid,
+    /// Requests authenticated as this user
+    pub user_id: Option<Uuid>,
+    /// Requests whose path starts with this, e.g. "/api/wallet"
+    pub route: Option<String>,
+    pub created_by: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl CaptureTarget {
+    fn matches(&self, path: &str, user_id: Option<Uuid>) -> bool {
+        let user_matches = self.user_id.is_none() || self.user_id == user_id;
+        let route_matches = self
+            .route
+            .as_deref()
+            .is_none_or(|route| path.starts_with(route));
+        user_matches && route_matches
+    }
+}
+
+/// A header as captured (credentials already redacted)
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedHeader {
+    pub name: String,
+    pub value: String,
+}
+
+/// One request and the response it got, sanitized
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedExchange {
+    pub id: u64,
+    /// The target that captured it
+    pub target_id: Uuid,
+    pub captured_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub user_id: Option<Uuid>,
+    pub method: String,
+    /// Path and query string
+    pub uri: String,
+    pub request_headers: Vec<CapturedHeader>,
+    pub request_body: Option<String>,
+    pub status: u16,
+    pub response_headers: Vec<CapturedHeader>,
+    pub response_body: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct CaptureState {
+    targets: Vec<CaptureTarget>,
+    exchanges: VecDeque<CapturedExchange>,
+    next_id: u64,
+}
+
+/// The capture targets and the ring buffer of what they caught
+pub struct DebugCapture {
+    capacity: usize,
+    clock: Arc<dyn Clock>,
+    state: Mutex<CaptureState>,
+}
+
+impl DebugCapture {
+    /// Keep up to `capacity` exchanges (0 = capture is off)
+    pub fn new(capacity: usize, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            capacity,
+            clock,
+            state: Mutex::new(CaptureState::default()),
+        }
+    }
+
+    /// From DEBUG_CAPTURE_CAPACITY
+    pub fn from_config(config: &Config, clock: Arc<dyn Clock>) -> Self {
+        Self::new(config.debug_capture_capacity, clock)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Start capturing a user's and/or a route's requests for `minutes`
+    pub fn add_target(
+        &self,
+        user_id: Option<Uuid>,
+        route: Option<String>,
+        minutes: i64,
+        created_by: Uuid,
+    ) -> Result<CaptureTarget, AppError> {
+        if self.capacity == 0 {
+            return Err(AppError::feature_disabled("Debug captures"));
+        }
+        let route = route.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+        if user_id.is_none() && route.is_none() {
+            return Err(AppError::validation("Give a user_id, a route, or both"));
+        }
+        if route.as_deref().is_some_and(|r| !r.starts_with('/')) {
+            return Err(AppError::validation("The route must start with /"));
+        }
+        if !(1..=MAX_CAPTURE_MINUTES).contains(&minutes) {
+            return Err(AppError::validation(&format!(
+                "minutes must be between 1 and {}",
+                MAX_CAPTURE_MINUTES
+            )));
+        }
+
+        let target = CaptureTarget {
+            id: Uuid::new_v4(),
+            user_id,
+            route,
+            created_by,
+            expires_at: self.clock.now() + Duration::minutes(minutes),
+        };
+        let mut state = self.lock();
+        self.prune(&mut state);
+        state.targets.push(target.clone());
+        Ok(target)
+    }
+
+    /// Stop a capture early; false if there was no such target
+    pub fn remove_target(&self, id: Uuid) -> bool {
+        let mut state = self.lock();
+        let before = state.targets.len();
+        state.targets.retain(|t| t.id != id);
+        state.targets.len() != before
+    }
+
+    /// The captures still running
+    pub fn targets(&self) -> Vec<CaptureTarget> {
+        let mut state = self.lock();
+        self.prune(&mut state);
+        state.targets.clone()
+    }
+
+    /// Whether anything is being captured (cheap; checked on every request)
+    pub fn is_active(&self) -> bool {
+        let mut state = self.lock();
+        self.prune(&mut state);
+        !state.targets.is_empty()
+    }
+
+    /// Whether any target is for a user (so requests need authenticating
+    /// to match)
+    pub fn targets_users(&self) -> bool {
+        self.lock().targets.iter().any(|t| t.user_id.is_some())
+    }
+
+    /// The target a request falls under, if any
+    pub fn matching(&self, path: &str, user_id: Option<Uuid>) -> Option<Uuid> {
+        let mut state = self.lock();
+        self.prune(&mut state);
+        state
+            .targets
+            .iter()
+            .find(|t| t.matches(path, user_id))
+            .map(|t| t.id)
+    }
+
+    /// Keep an exchange (its `id` is assigned here), dropping the oldest
+    /// when full
+    pub fn record(&self, mut exchange: CapturedExchange) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.lock();
+        state.next_id += 1;
+        exchange.id = state.next_id;
+        if state.exchanges.len() == self.capacity {
+            state.exchanges.pop_front();
+        }
+        state.exchanges.push_back(exchange);
+    }
+
+    /// What was captured, newest first, optionally only one target's
+    pub fn exchanges(&self, target_id: Option<Uuid>, limit: usize) -> Vec<CapturedExchange> {
+        self.lock()
+            .exchanges
+            .iter()
+            .rev()
+            .filter(|e| target_id.is_none_or(|id| e.target_id == id))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Forget every captured exchange (targets keep running)
+    pub fn clear(&self) -> usize {
+        let mut state = self.lock();
+        let cleared = state.exchanges.len();
+        state.exchanges.clear();
+        cleared
+    }
+
+    fn prune(&self, state: &mut CaptureState) {
+        let now = self.clock.now();
+        state.targets.retain(|t| t.expires_at > now);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CaptureState> {
+        // Nothing here can be left half-updated by a panic
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// ============================================================================
+// SANITIZING
+// ============================================================================
+
+/// Whether a field is redacted; `exact` are names matched whole (see
+/// SENSITIVE_REQUEST_FIELDS)
+fn is_sensitive_field(name: &str, exact: &[&str]) -> bool {
+    let name = name.to_ascii_lowercase();
+    SENSITIVE_FIELDS.iter().any(|field| name.contains(field)) || exact.contains(&name.as_str())
+}
+
+/// A header as it may be kept
+pub fn sanitize_header(name: &str, value: &[u8]) -> CapturedHeader {
+    let lower = name.to_ascii_lowercase();
+    let value = if SENSITIVE_HEADERS.contains(&lower.as_str()) {
+        // Keep the scheme ("Bearer"), it's often the problem
+        match std::str::from_utf8(value).ok().and_then(|v| v.split_once(' ')) {
+            Some((scheme, _)) if lower.ends_with("authorization") => {
+                format!("{} {}", scheme, REDACTED)
+            }
+            _ => REDACTED.to_string(),
+        }
+    } else {
+        String::from_utf8_lossy(value).into_owned()
+    };
+    CapturedHeader { name: lower, value }
+}
+
+/// A path and query string with sensitive query values redacted
+pub fn sanitize_uri(path: &str, query: Option<&str>) -> String {
+    match query {
+        Some(query) if !query.is_empty() => {
+            format!("{}?{}", path, sanitize_form(query, &SENSITIVE_REQUEST_FIELDS))
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// A request body as it may be kept: like `sanitize_body`, also
+/// redacting one-time codes
+pub fn sanitize_request_body(content_type: Option<&str>, body: &[u8]) -> Option<String> {
+    sanitize(content_type, body, &SENSITIVE_REQUEST_FIELDS)
+}
+
+/// A body as it may be kept: sensitive JSON/form fields redacted, cut off
+/// at MAX_BODY_BYTES
+pub fn sanitize_body(content_type: Option<&str>, body: &[u8]) -> Option<String> {
+    sanitize(content_type, body, &[])
+}
+
+fn sanitize(content_type: Option<&str>, body: &[u8], exact: &[&str]) -> Option<String> {
+    if body.is_empty() {
+        return None;
+    }
+    let content_type = content_type.unwrap_or("").to_ascii_lowercase();
+
+    let text = if content_type.contains("json") {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(mut json) => {
+                redact_json(&mut json, exact);
+                json.to_string()
+            }
+            // Broken JSON is often the very thing being debugged
+            Err(_) => String::from_utf8_lossy(body).into_owned(),
+        }
+    } else if content_type.contains("x-www-form-urlencoded") {
+        sanitize_form(&String::from_utf8_lossy(body), exact)
+    } else if is_text(&content_type) {
+        String::from_utf8_lossy(body).into_owned()
+    } else {
+        return Some(describe_binary(&content_type, body.len()));
+    };
+
+    Some(truncate(text))
+}
+
+/// Whether a body of this type is worth keeping as text
+pub fn is_text(content_type: &str) -> bool {
+    let content_type = content_type.to_ascii_lowercase();
+    content_type.is_empty()
+        || content_type.starts_with("text/")
+        || content_type.contains("json")
+        || content_type.contains("xml")
+        || content_type.contains("x-www-form-urlencoded")
+}
+
+/// What's kept instead of a binary body
+pub fn describe_binary(content_type: &str, len: usize) -> String {
+    format!("<{} bytes of {}>", len, content_type)
+}
+
+fn redact_json(value: &mut Value, exact: &[&str]) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if is_sensitive_field(name, exact) && !field.is_null() {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(field, exact);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| redact_json(item, exact)),
+        _ => {}
+    }
+}
+
+fn sanitize_form(form: &str, exact: &[&str]) -> String {
+    form.split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if is_sensitive_field(name, exact) => format!("{}={}", name, REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn truncate(mut text: String) -> String {
+    if text.len() <= MAX_BODY_BYTES {
+        return text;
+    }
+    let mut end = MAX_BODY_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let total = text.len();
+    text.truncate(end);
+    text.push_str(&format!("... ({} bytes in all)", total));
+    text
+}
@@ -1,56 +1,725 @@
+use hmac::{Hmac, Mac};
 use lettre::{
-    message::header::ContentType,
+    message::{header::ContentType, Mailbox, MultiPart},
     transport::smtp::authentication::Credentials,
     AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
 };
+use chrono::{DateTime, Duration, Utc};
 use rust_decimal::Decimal;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::sync::Arc;
+use crate::config::{Config, EmailProviderKind};
+use crate::domain::models::Currency;
+use crate::error::AppError;
+use crate::i18n::Locale;
+use crate::repository::email_queue_repo::{self, QueuedEmail};
+use crate::services::email_templates::{
+    DepositReceived, EmailTemplate, PasswordReset, TransferReceived, TransferSent, Verification,
+};
+use crate::shutdown::Shutdown;
+use crate::utils::secret::SecretString;
+
+// ============================================================================
+// EMAIL SERVICE
+// ============================================================================
+// `EmailService` writes the emails we send (transfer receipts, verification
+// codes, new-device alerts); an `EmailTransport` delivers them. EMAIL_PROVIDER
+// picks the transport:
+//
+// - `SmtpTransport` - an SMTP relay (SMTP_*)
+// - `SesTransport` - the AWS SES v2 API (SES_*)
+// - `LogTransport` - the log; the development default, so running locally
+//   needs no mail credentials
+//
+// Tests hand the state builder a transport of their own to see what would
+// have been sent.
+//
+// Receipts, password resets and verification codes come from templates, in
+// the recipient's language and with an HTML part (see email_templates).
+//
+// Delivery queue:
+// Every email is written to `email_queue` (migration 044) before the first
+// try and deleted once the transport takes it, so an SMTP outage delays mail
+// instead of losing it.
+// 1. The first try happens straight away, in the sender's task.
+// 2. A failed try is retried by a background task (`spawn_retries`) after
+//    RETRY_DELAY, doubling each time. After MAX_ATTEMPTS tries the email
+//    moves to `email_dead_letters`, to be looked into by hand.
+// 3. A hard bounce (the server says the mailbox or domain doesn't exist)
+//    isn't retried: the email is dead-lettered at once and the address goes
+//    on `email_suppressions`, and nothing is sent to it again.
+// Without a database (the demo) emails are tried once and not queued.
+
+/// Tries before an email is given up on
+const MAX_ATTEMPTS: i32 = 10;
+
+/// The wait before the first retry; it doubles with every further one
+const RETRY_DELAY: Duration = Duration::minutes(1);
+
+/// How often the background task looks for emails to retry
+const RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Emails retried per pass
+const RETRY_BATCH_SIZE: i64 = 100;
+
+/// How long a queued email is left alone while it is being tried (a try
+/// takes far less)
+const CLAIM_FOR: Duration = Duration::minutes(10);
+
+/// One email, ready to deliver
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    /// The plain-text body (every email has one)
+    pub text: String,
+    /// The same as HTML, for clients that show it
+    pub html: Option<String>,
+}
+
+/// Why a transport couldn't deliver an email
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum DeliveryError {
+    /// Worth trying again later (server unreachable, mailbox full, ...)
+    #[error("{0}")]
+    Transient(String),
+    /// A hard bounce: the address doesn't exist or can't be written to
+    #[error("{0}")]
+    Permanent(String),
+}
+
+/// Delivers an email that's already written
+#[async_trait::async_trait]
+pub trait EmailTransport: Send + Sync {
+    /// Send (or record) one email
+    async fn send(&self, email: &Email) -> Result<(), DeliveryError>;
+
+    /// Whether mail could be delivered right now (used by GET /readyz);
+    /// transports without a server to reach are always ready
+    async fn check(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// The transport chosen by EMAIL_PROVIDER
+pub fn transport_from_config(config: &Config) -> Arc<dyn EmailTransport> {
+    match config.email_provider {
+        EmailProviderKind::Smtp => Arc::new(SmtpTransport::new(
+            &config.smtp_host,
+            config.smtp_port,
+            config.smtp_user.clone(),
+            &config.smtp_password,
+            config.email_from.clone(),
+        )),
+        EmailProviderKind::Ses => Arc::new(SesTransport::new(
+            config.ses_endpoint.as_deref(),
+            &config.ses_region,
+            config.ses_access_key_id.clone(),
+            config.ses_secret_access_key.clone(),
+            config.email_from.clone(),
+        )),
+        EmailProviderKind::Log => Arc::new(LogTransport),
+    }
+}
+
+/// What one pass of the retry task did
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RetrySummary {
+    pub delivered: usize,
+    /// Failed tries that will be retried
+    pub retrying: usize,
+    /// Emails given up on this pass
+    pub dead_lettered: usize,
+}
+
+/// What came of one try
+enum Outcome {
+    Delivered,
+    Retrying,
+    DeadLettered,
+}
 
 #[derive(Clone)]
 pub struct EmailService {
-    mailer: AsyncSmtpTransport<Tokio1Executor>,
-    from: String,
+    transport: Arc<dyn EmailTransport>,
+    /// Where emails wait to be (re)tried; None sends them once, unqueued
+    queue: Option<PgPool>,
 }
 
 impl EmailService {
+    /// Send through `transport` without a queue: one try per email
+    pub fn new(transport: Arc<dyn EmailTransport>) -> Self {
+        Self { transport, queue: None }
+    }
+
+    /// Send through `transport`, queueing every email in `pool` until it's
+    /// delivered
+    pub fn queued(transport: Arc<dyn EmailTransport>, pool: PgPool) -> Self {
+        Self {
+            transport,
+            queue: Some(pool),
+        }
+    }
+
+    /// Receipt for the sender of a transfer
+    pub async fn send_transfer_sent(
+        &self,
+        to: &str,
+        locale: Locale,
+        amount: Decimal,
+        currency: Currency,
+        recipient: &str,
+    ) {
+        let email = TransferSent { locale, amount, currency, recipient };
+        self.send_template(to, &email).await;
+    }
+
+    /// Receipt for the recipient of a transfer, with their new balance
+    pub async fn send_transfer_received(
+        &self,
+        to: &str,
+        locale: Locale,
+        amount: Decimal,
+        currency: Currency,
+        balance: Decimal,
+    ) {
+        let email = TransferReceived { locale, amount, currency, balance };
+        self.send_template(to, &email).await;
+    }
+
+    /// Receipt for a deposit, with the new balance
+    pub async fn send_deposit_received(
+        &self,
+        to: &str,
+        locale: Locale,
+        amount: Decimal,
+        currency: Currency,
+        balance: Decimal,
+    ) {
+        let email = DepositReceived { locale, amount, currency, balance };
+        self.send_template(to, &email).await;
+    }
+
+    pub async fn send_transfer_confirmation(
+        &self,
+        to: &str,
+        amount: Decimal,
+        currency: Currency,
+        recipient: &str,
+        code: &str,
+    ) {
+        let subject = "MyFintechApp: Confirm your transfer";
+        let body = format!(
+            "You're sending {} {} to {}.\n\nConfirm it with this code:\n\n{}\n\nIt expires in 15 minutes; until then the money is held. If you didn't start this transfer, cancel it and change your password.",
+            amount, currency, recipient, code
+        );
+
+        self.send(to, subject, body).await;
+    }
+
+    pub async fn send_verification_code(&self, to: &str, locale: Locale, code: &str) {
+        self.send_template(to, &Verification { locale, code }).await;
+    }
+
+    pub async fn send_password_reset(&self, to: &str, locale: Locale, link: &str, token: &str) {
+        self.send_template(to, &PasswordReset { locale, link, token }).await;
+    }
+
+    pub async fn send_account_locked(&self, to: &str, link: &str, token: &str) {
+        let subject = "MyFintechApp: Your account has been locked";
+        let body = format!(
+            "We locked your account after too many wrong passwords in a row. If that was you, unlock it here:\n{}\n\nOr use this code:\n\n{}\n\nIf it wasn't you, someone may be guessing your password: reset it from the sign-in page (that unlocks the account too).",
+            link, token
+        );
+
+        self.send(to, subject, body).await;
+    }
+
+    pub async fn send_new_device_alert(
+        &self,
+        to: &str,
+        device: &str,
+        location: &str,
+        ip: &str,
+        at: DateTime<Utc>,
+    ) {
+        let subject = "MyFintechApp: New sign-in to your account";
+        let body = format!(
+            "Your account was just signed in to from a new device.\n\nDevice: {}\nLocation: {}\nIP address: {}\nTime: {}\n\nIf this was you, there's nothing to do. If not, change your password now.",
+            device,
+            location,
+            ip,
+            at.format("%Y-%m-%d %H:%M UTC")
+        );
+
+        self.send(to, subject, body).await;
+    }
+
+    pub async fn send_organization_invitation(
+        &self,
+        to: &str,
+        organization: &str,
+        role: &str,
+        token: &str,
+    ) {
+        let subject = format!("MyFintechApp: Join {} on MyFintechApp", organization);
+        let body = format!(
+            "You've been invited to join {} as {}.\n\nSign in (or create an account with this email address) and accept the invitation with this code:\n\n{}\n\nIt expires in 7 days.",
+            organization, role, token
+        );
+
+        self.send(to, &subject, body).await;
+    }
+
+    pub async fn send_expense_decision(
+        &self,
+        to: &str,
+        organization: &str,
+        description: &str,
+        amount: Decimal,
+        approved: bool,
+        note: Option<&str>,
+    ) {
+        let outcome = if approved { "approved" } else { "rejected" };
+        let subject = format!("MyFintechApp: Expense {}", outcome);
+        let mut body = format!(
+            "Your expense request \"{}\" for ${} at {} was {}.",
+            description, amount, organization, outcome
+        );
+        if approved {
+            body.push_str(" The money has been sent.");
+        }
+        if let Some(note) = note {
+            body.push_str(&format!("\n\nNote: {}", note));
+        }
+
+        self.send(to, &subject, body).await;
+    }
+
+    async fn send(&self, to: &str, subject: &str, body: String) {
+        let email = Email {
+            to: to.to_string(),
+            subject: subject.to_string(),
+            text: body,
+            html: None,
+        };
+        self.deliver(&email).await;
+    }
+
+    async fn send_template(&self, to: &str, template: &impl EmailTemplate) {
+        match template.render(to) {
+            Ok(email) => self.deliver(&email).await,
+            Err(e) => tracing::error!("❌ Failed to render the {:?} email: {}", template.subject(), e),
+        }
+    }
+
+    /// Queue the email and make the first try; errors are logged, not
+    /// returned, since no caller can do anything about them
+    async fn deliver(&self, email: &Email) {
+        let Some(pool) = &self.queue else {
+            if let Err(e) = self.transport.send(email).await {
+                tracing::warn!("⚠️ Failed to send an email to {}: {}", email.to, e);
+            }
+            return;
+        };
+
+        match self.enqueue(pool, email).await {
+            Ok(Some(queued)) => {
+                if let Err(e) = self.attempt(pool, queued, Utc::now()).await {
+                    tracing::error!("❌ Failed to update the email queue: {}", e);
+                }
+            }
+            Ok(None) => {}
+            // Better one unqueued try than none
+            Err(e) => {
+                tracing::error!("❌ Failed to queue an email to {}: {}", email.to, e);
+                if let Err(e) = self.transport.send(email).await {
+                    tracing::warn!("⚠️ Failed to send an email to {}: {}", email.to, e);
+                }
+            }
+        }
+    }
+
+    /// Put the email in the queue, held back from the retry task while the
+    /// first try runs; None if the address is suppressed
+    async fn enqueue(&self, pool: &PgPool, email: &Email) -> Result<Option<QueuedEmail>, AppError> {
+        if email_queue_repo::is_suppressed(pool, &email.to).await? {
+            tracing::debug!("🚫 Not emailing {}: the address is suppressed", email.to);
+            return Ok(None);
+        }
+
+        let now = Utc::now();
+        let queued = email_queue_repo::enqueue(
+            pool,
+            &email.to,
+            &email.subject,
+            &email.text,
+            email.html.as_deref(),
+            now,
+            now + CLAIM_FOR,
+        )
+        .await?;
+        Ok(Some(queued))
+    }
+
+    /// Try a queued email once and record what happened
+    async fn attempt(
+        &self,
+        pool: &PgPool,
+        queued: QueuedEmail,
+        now: DateTime<Utc>,
+    ) -> Result<Outcome, AppError> {
+        let email = Email {
+            to: queued.to_address,
+            subject: queued.subject,
+            text: queued.text_body,
+            html: queued.html_body,
+        };
+
+        match self.transport.send(&email).await {
+            Ok(()) => {
+                email_queue_repo::delete(pool, queued.id).await?;
+                Ok(Outcome::Delivered)
+            }
+            Err(DeliveryError::Permanent(e)) => {
+                tracing::warn!("🚫 {} bounced, no more emails to it: {}", email.to, e);
+                email_queue_repo::suppress(pool, &email.to, &e, now).await?;
+                email_queue_repo::dead_letter(pool, queued.id, &e, now).await?;
+                Ok(Outcome::DeadLettered)
+            }
+            Err(DeliveryError::Transient(e)) => {
+                let attempts = queued.attempts + 1;
+                if attempts >= MAX_ATTEMPTS {
+                    tracing::warn!(
+                        "⚠️ Giving up on an email to {} after {} tries: {}",
+                        email.to,
+                        attempts,
+                        e
+                    );
+                    email_queue_repo::dead_letter(pool, queued.id, &e, now).await?;
+                    return Ok(Outcome::DeadLettered);
+                }
+
+                tracing::warn!("⚠️ Failed to send an email to {} (try {}): {}", email.to, attempts, e);
+                let retry_at = now + RETRY_DELAY * 2_i32.pow(attempts as u32 - 1);
+                email_queue_repo::record_failure(pool, queued.id, &e, retry_at).await?;
+                Ok(Outcome::Retrying)
+            }
+        }
+    }
+
+    /// Retry every queued email that is due by `now`
+    pub async fn retry_due(&self, now: DateTime<Utc>) -> Result<RetrySummary, AppError> {
+        let mut summary = RetrySummary::default();
+        let Some(pool) = &self.queue else {
+            return Ok(summary);
+        };
+
+        let due = email_queue_repo::claim_due(pool, now, now + CLAIM_FOR, RETRY_BATCH_SIZE).await?;
+        for queued in due {
+            match self.attempt(pool, queued, now).await? {
+                Outcome::Delivered => summary.delivered += 1,
+                Outcome::Retrying => summary.retrying += 1,
+                Outcome::DeadLettered => summary.dead_lettered += 1,
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Retry queued emails every 30 seconds, in the background, until
+    /// shutdown
+    pub fn spawn_retries(&self, shutdown: &Shutdown) {
+        let email_service = self.clone();
+        let stopped = shutdown.clone();
+        shutdown.spawn(async move {
+            let mut interval = tokio::time::interval(RETRY_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = stopped.stopped() => break,
+                }
+
+                match email_service.retry_due(Utc::now()).await {
+                    Ok(RetrySummary { delivered: 0, retrying: 0, dead_lettered: 0 }) => {
+                        tracing::debug!("📧 No emails to retry")
+                    }
+                    Ok(summary) => tracing::info!(
+                        "📧 Email retries: {} delivered, {} to retry, {} given up",
+                        summary.delivered,
+                        summary.retrying,
+                        summary.dead_lettered
+                    ),
+                    Err(e) => tracing::error!("❌ Failed to retry queued emails: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Check that the transport can reach its server
+    pub async fn check(&self) -> Result<(), String> {
+        self.transport.check().await
+    }
+}
+
+// ============================================================================
+// SMTP
+// ============================================================================
+
+/// Sends through an SMTP relay (STARTTLS)
+pub struct SmtpTransport {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: String,
+}
+
+impl SmtpTransport {
     pub fn new(
-        smtp_host: String,
+        smtp_host: &str,
         smtp_port: u16,
         smtp_user: String,
-        smtp_password: String,
-        smtp_from: String,
+        smtp_password: &SecretString,
+        from: String,
     ) -> Self {
-let creds = Credentials::new(smtp_user, smtp_password);
+        let creds = Credentials::new(smtp_user, smtp_password.expose_secret().to_string());
 
-        let mailer = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp_host)
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_host)
             .unwrap()
             .port(smtp_port)
             .credentials(creds)
             .build();
 
-        Self {
-            mailer,
-            from: smtp_from,
+        Self { transport, from }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for SmtpTransport {
+    async fn send(&self, email: &Email) -> Result<(), DeliveryError> {
+        let from = self
+            .from
+            .parse()
+            .map_err(|e| DeliveryError::Transient(format!("EMAIL_FROM is invalid: {}", e)))?;
+        let to = email
+            .to
+            .parse()
+            .map_err(|e| DeliveryError::Permanent(format!("invalid address: {}", e)))?;
+        let builder = Message::builder().from(from).to(to).subject(&email.subject);
+        let message = match &email.html {
+            Some(html) => builder
+                .multipart(MultiPart::alternative_plain_html(email.text.clone(), html.clone())),
+            None => builder.header(ContentType::TEXT_PLAIN).body(email.text.clone()),
+        }
+        .map_err(|e| DeliveryError::Permanent(e.to_string()))?;
+
+        match self.transport.send(message).await {
+            Ok(_) => {
+                tracing::info!("✅ Email sent to {}", email.to);
+                Ok(())
+            }
+            Err(e) if is_hard_bounce(&e) => Err(DeliveryError::Permanent(e.to_string())),
+            Err(e) => Err(DeliveryError::Transient(e.to_string())),
         }
     }
 
-    pub async fn send_transfer_success(&self, to: &str, amount: Decimal) {
-        let subject = "MyFintechApp: Transfer Successful";
-        let body = format!(
-            "Transfer Successful!\n\nYou have successfully sent ${}.",
-            amount
+    async fn check(&self) -> Result<(), String> {
+        match self.transport.test_connection().await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("SMTP server refused the connection".to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Whether the server refused the recipient for good: 550 (no such
+/// mailbox), 551 (user not local) or 553 (mailbox name not allowed)
+///
+/// Other permanent errors (a refused login, a message too large) aren't
+/// the address's fault, so they're retried like outages.
+fn is_hard_bounce(error: &lettre::transport::smtp::Error) -> bool {
+    error.is_permanent()
+        && error
+            .status()
+            .is_some_and(|code| matches!(code.to_string().as_str(), "550" | "551" | "553"))
+}
+
+// ============================================================================
+// SES
+// ============================================================================
+// One call per email to the SES v2 API (POST /v2/email/outbound-emails),
+// signed with AWS Signature Version 4 like our S3 requests (see
+// services/storage.rs).
+//
+// SES doesn't say whether an address exists when it takes an email; it
+// bounces it later, through its own notifications (and keeps its own
+// suppression list). So every refusal here is retried, and only an address
+// we can't even parse counts as a hard bounce.
+
+/// How long one SES call may take
+const SES_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+const SES_SIGNED_HEADERS: &str = "content-type;host;x-amz-content-sha256;x-amz-date";
+
+const SES_SEND_PATH: &str = "/v2/email/outbound-emails";
+
+/// Sends through the AWS SES v2 API
+pub struct SesTransport {
+    http: reqwest::Client,
+    /// "https" or "http"
+    scheme: String,
+    /// e.g. "email.eu-west-1.amazonaws.com"
+    host: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: SecretString,
+    from: String,
+}
+
+impl SesTransport {
+    /// `endpoint` defaults to AWS's for the region
+    pub fn new(
+        endpoint: Option<&str>,
+        region: &str,
+        access_key_id: String,
+        secret_access_key: SecretString,
+        from: String,
+    ) -> Self {
+        let endpoint = endpoint
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("https://email.{}.amazonaws.com", region));
+        let (scheme, authority) = endpoint.split_once("://").unwrap_or(("https", &endpoint));
+
+        SesTransport {
+            http: reqwest::Client::builder()
+                .timeout(SES_TIMEOUT)
+                .build()
+                .expect("TLS backend is available"),
+            scheme: scheme.to_string(),
+            host: authority.trim_end_matches('/').to_string(),
+            region: region.to_string(),
+            access_key_id,
+            secret_access_key,
+            from,
+        }
+    }
+
+    fn scope(&self, now: DateTime<Utc>) -> String {
+        format!("{}/{}/ses/aws4_request", now.format("%Y%m%d"), self.region)
+    }
+
+    /// Sign a canonical request made at `now`
+    fn signature(&self, now: DateTime<Utc>, canonical_request: &str) -> String {
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            now.format("%Y%m%dT%H%M%SZ"),
+            self.scope(now),
+            hex::encode(Sha256::digest(canonical_request.as_bytes())),
+        );
+
+        let secret = format!("AWS4{}", self.secret_access_key.expose_secret());
+        let key = hmac_sha256(secret.as_bytes(), now.format("%Y%m%d").to_string().as_bytes());
+        let key = hmac_sha256(&key, self.region.as_bytes());
+        let key = hmac_sha256(&key, b"ses");
+        let key = hmac_sha256(&key, b"aws4_request");
+        hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()))
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for SesTransport {
+    async fn send(&self, email: &Email) -> Result<(), DeliveryError> {
+        if let Err(e) = email.to.parse::<Mailbox>() {
+            return Err(DeliveryError::Permanent(format!("invalid address: {}", e)));
+        }
+
+        let mut body = serde_json::json!({ "Text": { "Data": email.text, "Charset": "UTF-8" } });
+        if let Some(html) = &email.html {
+            body["Html"] = serde_json::json!({ "Data": html, "Charset": "UTF-8" });
+        }
+        let payload = serde_json::json!({
+            "FromEmailAddress": self.from,
+            "Destination": { "ToAddresses": [email.to] },
+            "Content": {
+                "Simple": {
+                    "Subject": { "Data": email.subject, "Charset": "UTF-8" },
+                    "Body": body,
+                }
+            },
+        })
+        .to_string();
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex::encode(Sha256::digest(payload.as_bytes()));
+        let canonical_request = format!(
+            "POST\n{}\n\ncontent-type:application/json\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            SES_SEND_PATH, self.host, payload_hash, amz_date, SES_SIGNED_HEADERS, payload_hash
+        );
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id,
+            self.scope(now),
+            SES_SIGNED_HEADERS,
+            self.signature(now, &canonical_request),
         );
 
-        let email = Message::builder()
-            .from(self.from.parse().unwrap())
-            .to(to.parse().unwrap())
-            .subject(subject)
-            .header(ContentType::TEXT_PLAIN)
-            .body(body)
-            .unwrap();
+        let response = self
+            .http
+            .post(format!("{}://{}{}", self.scheme, self.host, SES_SEND_PATH))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .body(payload)
+            .send()
+            .await
+            .map_err(|e| DeliveryError::Transient(format!("SES request failed: {}", e.without_url())))?;
 
-        match self.mailer.send(email).await {
-            Ok(_) => println!("✅ Email sent successfully to {}", to),
-            Err(e) => eprintln!("❌ Failed to send email: {:?}", e),
+        let status = response.status();
+        if status.is_success() {
+            tracing::info!("✅ Email sent to {}", email.to);
+            return Ok(());
         }
+
+        // e.g. "MessageRejected", "TooManyRequestsException"
+        let error_type = response
+            .headers()
+            .get("x-amzn-ErrorType")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.split(':').next().unwrap_or(value).to_string())
+            .unwrap_or_default();
+        let message = response
+            .json::<serde_json::Value>()
+            .await
+            .ok()
+            .and_then(|body| body["message"].as_str().map(str::to_string))
+            .unwrap_or_default();
+        Err(DeliveryError::Transient(format!("SES answered {} {} {}", status, error_type, message)))
+    }
+}
+
+// ============================================================================
+// LOG
+// ============================================================================
+
+/// Writes emails to the log instead of sending them (development)
+pub struct LogTransport;
+
+#[async_trait::async_trait]
+impl EmailTransport for LogTransport {
+    async fn send(&self, email: &Email) -> Result<(), DeliveryError> {
+        tracing::info!(
+            "📧 Email to {} (not sent, log transport)\n{}\n\n{}",
+            email.to,
+            email.subject,
+            email.text
+        );
+        Ok(())
     }
 }
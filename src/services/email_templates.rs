@@ -0,0 +1,237 @@
+use askama::Template;
+use rust_decimal::Decimal;
+
+use crate::domain::models::Currency;
+use crate::i18n::Locale;
+use crate::services::email_service::Email;
+
+// ============================================================================
+// EMAIL TEMPLATES
+// ============================================================================
+// The emails that matter most are written as Askama templates under
+// templates/emails/, each twice: `.txt` for the plain-text part and `.html`
+// (on emails/layout.html) for clients that show HTML. Both go out in one
+// multipart/alternative message.
+//
+// Each template holds its English, Spanish and French wording in
+// `{% if email.locale == ... %}` blocks, so a translation sits next to the
+// text it translates; the subject lines are in `subject()` below. The
+// locale is the recipient's saved preference (users.locale), English if
+// they have none.
+//
+// The other emails (invitations, alerts, ...) are still plain-text
+// English, written in `EmailService`.
+
+/// An email with a subject and a text and an HTML template
+pub trait EmailTemplate {
+    /// The subject line, in the email's locale
+    fn subject(&self) -> &'static str;
+
+    /// The plain-text part
+    fn text(&self) -> askama::Result<String>;
+
+    /// The HTML part
+    fn html(&self) -> askama::Result<String>;
+
+    /// The whole email, addressed to `to`
+    fn render(&self, to: &str) -> askama::Result<Email> {
+        Ok(Email {
+            to: to.to_string(),
+            subject: format!("MyFintechApp: {}", self.subject()),
+            text: self.text()?,
+            html: Some(self.html()?),
+        })
+    }
+}
+
+/// Receipt for the sender of a transfer
+pub struct TransferSent<'a> {
+    pub locale: Locale,
+    /// Taken from the sender, in their wallet's currency
+    pub amount: Decimal,
+    pub currency: Currency,
+    /// The recipient's email address
+    pub recipient: &'a str,
+}
+
+/// Receipt for the recipient of a transfer
+pub struct TransferReceived {
+    pub locale: Locale,
+    /// What they were credited, in their wallet's currency
+    pub amount: Decimal,
+    pub currency: Currency,
+    /// Their balance after the credit
+    pub balance: Decimal,
+}
+
+/// Receipt for a deposit
+pub struct DepositReceived {
+    pub locale: Locale,
+    pub amount: Decimal,
+    pub currency: Currency,
+    /// The balance after the deposit
+    pub balance: Decimal,
+}
+
+/// A password reset link (and the same token as a code)
+pub struct PasswordReset<'a> {
+    pub locale: Locale,
+    pub link: &'a str,
+    pub token: &'a str,
+}
+
+/// The code that verifies a new account's email address
+pub struct Verification<'a> {
+    pub locale: Locale,
+    pub code: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "emails/transfer_sent.txt")]
+struct TransferSentText<'a> {
+    email: &'a TransferSent<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "emails/transfer_sent.html")]
+struct TransferSentHtml<'a> {
+    email: &'a TransferSent<'a>,
+}
+
+impl EmailTemplate for TransferSent<'_> {
+    fn subject(&self) -> &'static str {
+        match self.locale {
+            Locale::En => "Transfer sent",
+            Locale::Es => "Transferencia enviada",
+            Locale::Fr => "Virement envoyé",
+        }
+    }
+
+    fn text(&self) -> askama::Result<String> {
+        TransferSentText { email: self }.render()
+    }
+
+    fn html(&self) -> askama::Result<String> {
+        TransferSentHtml { email: self }.render()
+    }
+}
+
+#[derive(Template)]
+#[template(path = "emails/transfer_received.txt")]
+struct TransferReceivedText<'a> {
+    email: &'a TransferReceived,
+}
+
+#[derive(Template)]
+#[template(path = "emails/transfer_received.html")]
+struct TransferReceivedHtml<'a> {
+    email: &'a TransferReceived,
+}
+
+impl EmailTemplate for TransferReceived {
+    fn subject(&self) -> &'static str {
+        match self.locale {
+            Locale::En => "Money received",
+            Locale::Es => "Dinero recibido",
+            Locale::Fr => "Argent reçu",
+        }
+    }
+
+    fn text(&self) -> askama::Result<String> {
+        TransferReceivedText { email: self }.render()
+    }
+
+    fn html(&self) -> askama::Result<String> {
+        TransferReceivedHtml { email: self }.render()
+    }
+}
+
+#[derive(Template)]
+#[template(path = "emails/deposit.txt")]
+struct DepositReceivedText<'a> {
+    email: &'a DepositReceived,
+}
+
+#[derive(Template)]
+#[template(path = "emails/deposit.html")]
+struct DepositReceivedHtml<'a> {
+    email: &'a DepositReceived,
+}
+
+impl EmailTemplate for DepositReceived {
+    fn subject(&self) -> &'static str {
+        match self.locale {
+            Locale::En => "Deposit received",
+            Locale::Es => "Depósito recibido",
+            Locale::Fr => "Dépôt reçu",
+        }
+    }
+
+    fn text(&self) -> askama::Result<String> {
+        DepositReceivedText { email: self }.render()
+    }
+
+    fn html(&self) -> askama::Result<String> {
+        DepositReceivedHtml { email: self }.render()
+    }
+}
+
+#[derive(Template)]
+#[template(path = "emails/password_reset.txt")]
+struct PasswordResetText<'a> {
+    email: &'a PasswordReset<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "emails/password_reset.html")]
+struct PasswordResetHtml<'a> {
+    email: &'a PasswordReset<'a>,
+}
+
+impl EmailTemplate for PasswordReset<'_> {
+    fn subject(&self) -> &'static str {
+        match self.locale {
+            Locale::En => "Reset your password",
+            Locale::Es => "Restablece tu contraseña",
+            Locale::Fr => "Réinitialisez votre mot de passe",
+        }
+    }
+
+    fn text(&self) -> askama::Result<String> {
+        PasswordResetText { email: self }.render()
+    }
+
+    fn html(&self) -> askama::Result<String> {
+        PasswordResetHtml { email: self }.render()
+    }
+}
+
+#[derive(Template)]
+#[template(path = "emails/verification.txt")]
+struct VerificationText<'a> {
+    email: &'a Verification<'a>,
+}
+
+#[derive(Template)]
+#[template(path = "emails/verification.html")]
+struct VerificationHtml<'a> {
+    email: &'a Verification<'a>,
+}
+
+impl EmailTemplate for Verification<'_> {
+    fn subject(&self) -> &'static str {
+        match self.locale {
+            Locale::En => "Verify your email",
+            Locale::Es => "Verifica tu correo electrónico",
+            Locale::Fr => "Vérifiez votre adresse e-mail",
+        }
+    }
+
+    fn text(&self) -> askama::Result<String> {
+        VerificationText { email: self }.render()
+    }
+
+    fn html(&self) -> askama::Result<String> {
+        VerificationHtml { email: self }.render()
+    }
+}
@@ -0,0 +1,246 @@
+use crate::config::Config;
+use crate::domain::models::{Currency, ExchangeRatesResponse};
+use crate::error::AppError;
+use crate::services::rate_provider::{self, RateProvider};
+use crate::shutdown::Shutdown;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+// ============================================================================
+// EXCHANGE RATE SERVICE
+// ============================================================================
+// Keeps the latest exchange rates in memory, for GET /api/rates and for
+// converting amounts between currencies.
+//
+// Why cache?
+// - The providers publish new rates a few times a day at most, so asking
+//   them on every request would only add latency and a point of failure
+// - A background task refreshes the rates every RATES_REFRESH_SECS
+//
+// Staleness:
+// - Rates older than RATES_MAX_AGE_SECS are never used; money converted at
+//   a days-old rate is worse than a clear "try again later"
+// - When they are too old, the next caller tries one refresh itself (at
+//   most once a minute, so a dead provider isn't hammered), and gets 503
+//   if that fails too
+
+/// How soon a failed refresh may be retried by a caller
+const RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Decimal places of the rates we hand out
+const RATE_DECIMALS: u32 = 6;
+
+/// One set of rates from the provider
+#[derive(Debug, Clone)]
+pub struct RateTable {
+    /// How many units of each currency one US dollar buys (USD itself is 1)
+    pub usd_rates: HashMap<Currency, Decimal>,
+    pub provider: &'static str,
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl RateTable {
+    /// What one unit of `from` buys in `to` (None if either isn't quoted)
+    pub fn rate(&self, from: Currency, to: Currency) -> Option<Decimal> {
+        let from = self.usd_rates.get(&from)?;
+        let to = self.usd_rates.get(&to)?;
+
+        Some(to / from)
+    }
+}
+
+/// Cached exchange rates, shared by every request
+#[derive(Clone)]
+pub struct ExchangeRateService {
+    /// None when RATES_PROVIDER=off
+    provider: Option<Arc<dyn RateProvider>>,
+    cache: Arc<RwLock<Option<RateTable>>>,
+    /// Serializes refreshes; holds when the last one was attempted
+    last_attempt: Arc<tokio::sync::Mutex<Option<Instant>>>,
+    refresh_every: std::time::Duration,
+    max_age: Duration,
+}
+
+impl ExchangeRateService {
+    pub fn new(
+        provider: Option<Arc<dyn RateProvider>>,
+        refresh_every: std::time::Duration,
+        max_age: Duration,
+    ) -> Self {
+        ExchangeRateService {
+            provider,
+            cache: Arc::new(RwLock::new(None)),
+            last_attempt: Arc::new(tokio::sync::Mutex::new(None)),
+            refresh_every,
+            max_age,
+        }
+    }
+
+    /// The service for RATES_PROVIDER and the RATES_* intervals
+    pub fn from_config(config: &Config) -> Self {
+        ExchangeRateService::new(
+            rate_provider::from_config(config),
+            std::time::Duration::from_secs(config.rates_refresh_secs),
+            Duration::seconds(config.rates_max_age_secs as i64),
+        )
+    }
+
+    /// Whether there are exchange rates at all (RATES_PROVIDER isn't off)
+    pub fn enabled(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    /// Fetch new rates from the provider and cache them
+    pub async fn refresh(&self) -> Result<RateTable, AppError> {
+        let mut last_attempt = self.last_attempt.lock().await;
+        self.fetch(&mut last_attempt).await
+    }
+
+    /// Refresh the rates now and then every RATES_REFRESH_SECS, in the
+    /// background until shutdown (does nothing when exchange rates are off)
+    pub fn spawn_refresh(&self, shutdown: &Shutdown) {
+        if self.provider.is_none() {
+            return;
+        }
+
+        let service = self.clone();
+        let stopped = shutdown.clone();
+        shutdown.spawn(async move {
+            let mut interval = tokio::time::interval(service.refresh_every);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = stopped.stopped() => break,
+                }
+
+                match service.refresh().await {
+                    Ok(table) => tracing::debug!(
+                        "💱 Exchange rates refreshed from {} ({} currencies)",
+                        table.provider,
+                        table.usd_rates.len()
+                    ),
+                    Err(e) => tracing::error!("❌ Failed to refresh exchange rates: {}", e),
+                }
+            }
+        });
+    }
+
+    /// The cached rates, if they are fresh enough to use
+    ///
+    /// Fails with `FeatureDisabled` (503) when exchange rates are off, or
+    /// when the rates are too old and can't be refreshed right now.
+    pub async fn current(&self) -> Result<RateTable, AppError> {
+        if self.provider.is_none() {
+            return Err(unavailable());
+        }
+        if let Some(table) = self.fresh() {
+            return Ok(table);
+        }
+
+        // Stale or never fetched: one caller refreshes, the rest wait for it
+        let mut last_attempt = self.last_attempt.lock().await;
+        if let Some(table) = self.fresh() {
+            return Ok(table);
+        }
+        if last_attempt.is_some_and(|at| at.elapsed() < RETRY_AFTER) {
+            return Err(unavailable());
+        }
+
+        self.fetch(&mut last_attempt).await.map_err(|e| {
+            tracing::error!("❌ Exchange rates are stale and could not be refreshed: {}", e);
+            unavailable()
+        })
+    }
+
+    /// What one unit of `from` buys in `to`
+    pub async fn rate(&self, from: Currency, to: Currency) -> Result<Decimal, AppError> {
+        let table = self.current().await?;
+
+        table
+            .rate(from, to)
+            .map(|rate| rate.round_dp(RATE_DECIMALS))
+            .ok_or_else(|| unquoted(from, to))
+    }
+
+    /// `amount` of `from` converted to `to`, rounded to cents
+    ///
+    /// The full-precision rate is used, so converting and rounding once is
+    /// as exact as the provider's quote.
+    pub async fn convert(
+        &self,
+        amount: Decimal,
+        from: Currency,
+        to: Currency,
+    ) -> Result<Decimal, AppError> {
+        if from == to {
+            return Ok(amount);
+        }
+
+        let table = self.current().await?;
+        let rate = table.rate(from, to).ok_or_else(|| unquoted(from, to))?;
+
+        Ok((amount * rate).round_dp(2))
+    }
+
+    /// Every rate from `base`, for GET /api/rates
+    pub async fn rates_from(&self, base: Currency) -> Result<ExchangeRatesResponse, AppError> {
+        let table = self.current().await?;
+        if !table.usd_rates.contains_key(&base) {
+            return Err(AppError::validation(&format!("No exchange rates for {}", base)));
+        }
+
+        let rates = Currency::ALL
+            .into_iter()
+            .filter(|currency| *currency != base)
+            .filter_map(|currency| {
+                let rate = table.rate(base, currency)?.round_dp(RATE_DECIMALS);
+                Some((currency.as_str().to_string(), rate))
+            })
+            .collect();
+
+        Ok(ExchangeRatesResponse {
+            base,
+            rates,
+            provider: table.provider.to_string(),
+            fetched_at: table.fetched_at,
+        })
+    }
+
+    /// The cached table if it isn't older than RATES_MAX_AGE_SECS
+    fn fresh(&self) -> Option<RateTable> {
+        let cache = self.cache.read().unwrap();
+        cache
+            .as_ref()
+            .filter(|table| Utc::now() - table.fetched_at <= self.max_age)
+            .cloned()
+    }
+
+    /// Ask the provider; the caller holds the refresh lock
+    async fn fetch(&self, last_attempt: &mut Option<Instant>) -> Result<RateTable, AppError> {
+        let provider = self.provider.as_ref().ok_or_else(unavailable)?;
+        *last_attempt = Some(Instant::now());
+
+        let mut usd_rates = provider.usd_rates().await?;
+        usd_rates.insert(Currency::Usd, Decimal::ONE);
+
+        let table = RateTable {
+            usd_rates,
+            provider: provider.name(),
+            fetched_at: Utc::now(),
+        };
+        *self.cache.write().unwrap() = Some(table.clone());
+
+        Ok(table)
+    }
+}
+
+fn unavailable() -> AppError {
+    AppError::feature_disabled("Exchange rates")
+}
+
+fn unquoted(from: Currency, to: Currency) -> AppError {
+    AppError::validation(&format!("No exchange rate from {} to {}", from, to))
+}
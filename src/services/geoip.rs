@@ -0,0 +1,390 @@
+use crate::config::Config;
+use crate::error::AppError;
+use serde::Serialize;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+// ============================================================================
+// GEOIP
+// ============================================================================
+// Where an IP address is, roughly (country and city), for login history
+// and new-device alerts. Behind one trait so lookups can come from
+// somewhere else later:
+//
+// - `MaxMindDb` - a MaxMind DB file (GeoLite2-City / GeoIP2-City .mmdb),
+//   read into memory at startup (GEOIP_DATABASE)
+//
+// Without GEOIP_DATABASE there is no lookup and locations are simply left
+// empty. Lookups are in-memory, so callers don't need to cache them.
+// Private, loopback and other non-public addresses have no location.
+
+/// Where an address is (any part may be unknown)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GeoLocation {
+    /// ISO 3166-1 alpha-2, e.g. "DE"
+    pub country_code: Option<String>,
+    /// English name, e.g. "Germany"
+    pub country: Option<String>,
+    /// English name, e.g. "Berlin"
+    pub city: Option<String>,
+}
+
+impl GeoLocation {
+    /// "Berlin, Germany", "Germany" or None
+    pub fn describe(&self) -> Option<String> {
+        match (&self.city, self.country.as_ref().or(self.country_code.as_ref())) {
+            (Some(city), Some(country)) => Some(format!("{}, {}", city, country)),
+            (None, Some(country)) => Some(country.clone()),
+            (Some(city), None) => Some(city.clone()),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Looks up where IP addresses are
+pub trait GeoIp: Send + Sync {
+    /// None when the address isn't in the database (or isn't public)
+    fn lookup(&self, ip: IpAddr) -> Option<GeoLocation>;
+}
+
+/// The lookup from GEOIP_DATABASE (None when it isn't set)
+///
+/// Fails if the file can't be read or isn't a MaxMind DB, so a broken
+/// setup stops the server at startup.
+pub fn from_config(config: &Config) -> Result<Option<Arc<dyn GeoIp>>, AppError> {
+    let Some(path) = &config.geoip_database else {
+        return Ok(None);
+    };
+
+    let bytes = std::fs::read(path)
+        .map_err(|e| AppError::internal(&format!("Failed to read GEOIP_DATABASE {}: {}", path, e)))?;
+    let database = MaxMindDb::from_bytes(bytes)
+        .map_err(|e| AppError::internal(&format!("Invalid GEOIP_DATABASE {}: {}", path, e)))?;
+
+    Ok(Some(Arc::new(database)))
+}
+
+/// Whether an address could be in a GeoIP database at all
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation())
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                // Unique local (fc00::/7) and link-local (fe80::/10)
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+// ============================================================================
+// MAXMIND DB
+// ============================================================================
+// The .mmdb format (https://maxmind.github.io/MaxMind-DB/), just enough of
+// it for city lookups:
+//
+//   [binary search tree][16 zero bytes][data section]...[metadata marker][metadata]
+//
+// - The metadata (a map, after the last "\xAB\xCD\xEFMaxMind.com") gives
+//   the tree's node count and record size (24, 28 or 32 bits).
+// - Each node has two records, for a 0 or 1 bit of the address. A record
+//   below node_count is the next node, node_count means "not found", and
+//   anything above points into the data section.
+// - Data is a tagged binary encoding of maps, strings, numbers, ... A
+//   city record looks like
+//     { "country": { "iso_code": "DE", "names": { "en": "Germany", ... } },
+//       "city": { "names": { "en": "Berlin", ... } }, ... }
+//   and may use pointers to share repeated values.
+//
+// IPv4 addresses in an IPv6 database live under ::/96.
+
+const METADATA_MARKER: &[u8] = b"\xAB\xCD\xEFMaxMind.com";
+
+/// A MaxMind DB held in memory
+pub struct MaxMindDb {
+    bytes: Vec<u8>,
+    node_count: usize,
+    record_size: usize,
+    ip_version: u64,
+    /// Where the data section starts
+    data_start: usize,
+    /// Node to start IPv4 lookups from (after 96 zero bits in IPv6 trees)
+    ipv4_start: usize,
+}
+
+impl MaxMindDb {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, String> {
+        let marker = bytes
+            .windows(METADATA_MARKER.len())
+            .rposition(|window| window == METADATA_MARKER)
+            .ok_or("no MaxMind DB metadata")?;
+        let metadata_start = marker + METADATA_MARKER.len();
+        let metadata = Decoder::new(&bytes[metadata_start..]).decode(0, 0)?;
+
+        let number = |key: &str| {
+            metadata
+                .get(key)
+                .and_then(Value::as_u64)
+                .ok_or_else(|| format!("metadata has no {}", key))
+        };
+        let node_count = number("node_count")? as usize;
+        let record_size = number("record_size")? as usize;
+        let ip_version = number("ip_version")?;
+        if !matches!(record_size, 24 | 28 | 32) {
+            return Err(format!("unsupported record size {}", record_size));
+        }
+
+        let tree_size = node_count * record_size * 2 / 8;
+        let data_start = tree_size + 16;
+        if data_start > marker {
+            return Err("search tree is larger than the file".to_string());
+        }
+
+        let mut database = MaxMindDb {
+            bytes,
+            node_count,
+            record_size,
+            ip_version,
+            data_start,
+            ipv4_start: 0,
+        };
+        if ip_version == 6 {
+            let mut node = 0;
+            for _ in 0..96 {
+                if node >= node_count {
+                    break;
+                }
+                node = database.record(node, false);
+            }
+            database.ipv4_start = node;
+        }
+
+        Ok(database)
+    }
+
+    /// One of a node's two records
+    fn record(&self, node: usize, right: bool) -> usize {
+        let offset = node * self.record_size * 2 / 8;
+        let bytes = &self.bytes[offset..];
+        let be = |slice: &[u8]| slice.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+
+        match (self.record_size, right) {
+            (24, false) => be(&bytes[0..3]),
+            (24, true) => be(&bytes[3..6]),
+            (28, false) => ((bytes[3] as usize & 0xf0) << 20) | be(&bytes[0..3]),
+            (28, true) => ((bytes[3] as usize & 0x0f) << 24) | be(&bytes[4..7]),
+            (_, false) => be(&bytes[0..4]),
+            (_, true) => be(&bytes[4..8]),
+        }
+    }
+
+    /// The data record for an address, if there is one
+    fn find(&self, ip: IpAddr) -> Result<Option<Value>, String> {
+        let (bits, mut node): (Vec<u8>, usize) = match ip {
+            IpAddr::V4(ip) => (ip.octets().to_vec(), self.ipv4_start),
+            IpAddr::V6(ip) if self.ip_version == 6 => (ip.octets().to_vec(), 0),
+            // IPv6 addresses can't be in an IPv4-only database
+            IpAddr::V6(_) => return Ok(None),
+        };
+
+        for i in 0..bits.len() * 8 {
+            if node >= self.node_count {
+                break;
+            }
+            let bit = (bits[i / 8] >> (7 - i % 8)) & 1;
+            node = self.record(node, bit == 1);
+        }
+
+        if node <= self.node_count {
+            return Ok(None);
+        }
+        let offset = node - self.node_count - 16;
+        Decoder::new(&self.bytes[self.data_start..]).decode(offset, 0).map(Some)
+    }
+}
+
+impl GeoIp for MaxMindDb {
+    fn lookup(&self, ip: IpAddr) -> Option<GeoLocation> {
+        if !is_public(ip) {
+            return None;
+        }
+
+        let record = match self.find(ip) {
+            Ok(record) => record?,
+            Err(e) => {
+                tracing::warn!("⚠️  GeoIP lookup of {} failed: {}", ip, e);
+                return None;
+            }
+        };
+        let text = |path: &[&str]| {
+            path.iter()
+                .try_fold(&record, |value, key| value.get(key))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+
+        let location = GeoLocation {
+            country_code: text(&["country", "iso_code"]),
+            country: text(&["country", "names", "en"]),
+            city: text(&["city", "names", "en"]),
+        };
+        (location != GeoLocation::default()).then_some(location)
+    }
+}
+
+/// A decoded data section value (the types a city record uses)
+#[derive(Debug, Clone)]
+enum Value {
+    String(String),
+    Number(u64),
+    Map(Vec<(String, Value)>),
+    /// Arrays, doubles, floats, signed and 128-bit numbers, bytes, booleans
+    Other,
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+/// Reads values out of a data section (or the metadata)
+struct Decoder<'a> {
+    data: &'a [u8],
+}
+
+/// Nesting deeper than this means a corrupt (or hostile) file
+const MAX_DEPTH: usize = 32;
+
+impl<'a> Decoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Decoder { data }
+    }
+
+    fn bytes(&self, offset: usize, len: usize) -> Result<&'a [u8], String> {
+        self.data
+            .get(offset..offset + len)
+            .ok_or_else(|| "data runs past the end of the file".to_string())
+    }
+
+    fn uint(&self, offset: usize, len: usize) -> Result<u64, String> {
+        Ok(self.bytes(offset, len)?.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64))
+    }
+
+    /// The value at `offset`
+    fn decode(&self, offset: usize, depth: usize) -> Result<Value, String> {
+        self.decode_at(offset, depth).map(|(value, _)| value)
+    }
+
+    /// The value at `offset`, and where the next value starts
+    fn decode_at(&self, offset: usize, depth: usize) -> Result<(Value, usize), String> {
+        if depth > MAX_DEPTH {
+            return Err("data nested too deeply".to_string());
+        }
+
+        let control = self.uint(offset, 1)? as usize;
+        let mut offset = offset + 1;
+        let mut kind = control >> 5;
+
+        // Pointers have their own size encoding
+        if kind == 1 {
+            let size = (control >> 3) & 0x3;
+            let high = control & 0x7;
+            let (target, len) = match size {
+                0 => ((high << 8) | self.uint(offset, 1)? as usize, 1),
+                1 => (((high << 16) | self.uint(offset, 2)? as usize) + 2048, 2),
+                2 => (((high << 24) | self.uint(offset, 3)? as usize) + 526_336, 3),
+                _ => (self.uint(offset, 4)? as usize, 4),
+            };
+            let value = self.decode(target, depth + 1)?;
+            return Ok((value, offset + len));
+        }
+
+        if kind == 0 {
+            kind = 7 + self.uint(offset, 1)? as usize;
+            offset += 1;
+        }
+
+        let mut size = control & 0x1f;
+        match size {
+            29 => {
+                size = 29 + self.uint(offset, 1)? as usize;
+                offset += 1;
+            }
+            30 => {
+                size = 285 + self.uint(offset, 2)? as usize;
+                offset += 2;
+            }
+            31 => {
+                size = 65_821 + self.uint(offset, 3)? as usize;
+                offset += 3;
+            }
+            _ => {}
+        }
+
+        match kind {
+            // UTF-8 string
+            2 => {
+                let text = String::from_utf8_lossy(self.bytes(offset, size)?).into_owned();
+                Ok((Value::String(text), offset + size))
+            }
+            // double (8 bytes), float (4 bytes)
+            3 => Ok((Value::Other, offset + 8)),
+            15 => Ok((Value::Other, offset + 4)),
+            // uint16, uint32, uint64
+            5 | 6 | 9 => Ok((Value::Number(self.uint(offset, size.min(8))?), offset + size)),
+            // bytes, int32, uint128
+            4 | 8 | 10 => Ok((Value::Other, offset + size)),
+            // map: `size` key/value pairs
+            7 => {
+                let mut entries = Vec::with_capacity(size.min(64));
+                for _ in 0..size {
+                    let (key, next) = self.decode_at(offset, depth + 1)?;
+                    let (value, next) = self.decode_at(next, depth + 1)?;
+                    let Value::String(key) = key else {
+                        return Err("map key is not a string".to_string());
+                    };
+                    entries.push((key, value));
+                    offset = next;
+                }
+                Ok((Value::Map(entries), offset))
+            }
+            // array (skipped over; nothing we read is in one)
+            11 => {
+                for _ in 0..size {
+                    offset = self.decode_at(offset, depth + 1)?.1;
+                }
+                Ok((Value::Other, offset))
+            }
+            // boolean (the value is the size)
+            14 => Ok((Value::Other, offset)),
+            other => Err(format!("unsupported data type {}", other)),
+        }
+    }
+}
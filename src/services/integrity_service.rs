@@ -0,0 +1,128 @@
+use crate::domain::models::Currency;
+use crate::error::AppError;
+use crate::repository::ledger_repo::{self, WalletLedger};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// INTEGRITY SERVICE
+// ============================================================================
+// Reconciles each wallet's stored balance (a cache) with its ledger
+// entries, and both with what its transaction records add up to (deposits
+// + transfers in - withdrawals - transfers out). Also looks for ledger
+// journals that don't balance.
+//
+// Why do we need this?
+// - The balance, the ledger entries and the transaction record are written
+//   in one database transaction, so they should never disagree. If they
+//   do, something bypassed the repository (a manual UPDATE, a bad
+//   migration, a bug) and we want to hear about it before a customer does.
+//
+// Two ways to run it:
+// - On demand for one wallet: GET /api/admin/wallets/:id/verify
+// - For every wallet once a day, from the maintenance job, which logs each
+//   mismatch as an error
+//
+// Nothing is ever corrected automatically; the result says which side is
+// off and by how much, and a person decides what to do.
+
+/// The result of replaying one wallet's ledger
+#[derive(Debug, Serialize)]
+pub struct BalanceVerification {
+    pub wallet_id: Uuid,
+    /// The owner: a user or an organization
+    pub user_id: Option<Uuid>,
+    pub organization_id: Option<Uuid>,
+    pub currency: Currency,
+    /// Balance stored on the wallet
+    pub stored_balance: Decimal,
+    /// Balance the ledger entries add up to
+    pub ledger_balance: Decimal,
+    /// Balance the transaction records add up to
+    pub records_balance: Decimal,
+    /// stored - ledger (positive: the wallet holds more than the ledger explains)
+    pub difference: Decimal,
+    /// Stored, ledger and records balances all agree
+    pub consistent: bool,
+    pub ledger: LedgerBreakdown,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Totals per kind of transaction record (COMPLETED only), and how many
+/// ledger entries the wallet has
+#[derive(Debug, Serialize)]
+pub struct LedgerBreakdown {
+    pub postings: i64,
+    pub deposits: Decimal,
+    pub withdrawals: Decimal,
+    pub transfers_in: Decimal,
+    pub transfers_out: Decimal,
+    pub entries: i64,
+}
+
+impl From<WalletLedger> for BalanceVerification {
+    fn from(ledger: WalletLedger) -> Self {
+        let ledger_balance = ledger.ledger_balance;
+        let records_balance = ledger.records_balance();
+        let difference = ledger.stored_balance - ledger_balance;
+
+        BalanceVerification {
+            wallet_id: ledger.wallet_id,
+            user_id: ledger.user_id,
+            organization_id: ledger.organization_id,
+            currency: ledger.currency,
+            stored_balance: ledger.stored_balance,
+            ledger_balance,
+            records_balance,
+            difference,
+            consistent: difference.is_zero() && records_balance == ledger_balance,
+            ledger: LedgerBreakdown {
+                postings: ledger.postings,
+                deposits: ledger.deposits,
+                withdrawals: ledger.withdrawals,
+                transfers_in: ledger.transfers_in,
+                transfers_out: ledger.transfers_out,
+                entries: ledger.entries,
+            },
+            checked_at: Utc::now(),
+        }
+    }
+}
+
+/// Outcome of checking every wallet
+#[derive(Debug)]
+pub struct IntegrityReport {
+    pub wallets_checked: usize,
+    /// Only the wallets that don't add up
+    pub mismatches: Vec<BalanceVerification>,
+    /// Ledger journals whose debits and credits differ
+    pub unbalanced_journals: Vec<Uuid>,
+}
+
+/// Replay one wallet's ledger and compare it to the stored balance
+pub async fn verify_wallet(pool: &PgPool, wallet_id: Uuid) -> Result<BalanceVerification, AppError> {
+    let ledger = ledger_repo::get_wallet_ledger(pool, wallet_id).await?;
+    Ok(BalanceVerification::from(ledger))
+}
+
+/// Check every wallet and every journal and collect the ones that don't
+/// add up
+pub async fn verify_all_wallets(pool: &PgPool) -> Result<IntegrityReport, AppError> {
+    let ledgers = ledger_repo::list_wallet_ledgers(pool).await?;
+    let wallets_checked = ledgers.len();
+
+    let mismatches = ledgers
+        .into_iter()
+        .map(BalanceVerification::from)
+        .filter(|check| !check.consistent)
+        .collect();
+
+    Ok(IntegrityReport {
+        wallets_checked,
+        mismatches,
+        unbalanced_journals: ledger_repo::unbalanced_journals(pool).await?,
+    })
+}
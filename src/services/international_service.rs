@@ -0,0 +1,316 @@
+use crate::config::Config;
+use crate::domain::iban::{Bic, Iban};
+use crate::domain::models::{
+    Currency, InternationalQuoteResponse, InternationalTransferRequest,
+    InternationalTransferResponse, InternationalTransferStatus, InternationalTransferStatusRequest, validate_amount,
+};
+use crate::error::AppError;
+use crate::repository::international_repo::{
+    self, InternationalTransfer, NewInternationalTransfer,
+};
+use crate::repository::user_repo;
+use crate::services::exchange_rate_service::ExchangeRateService;
+use crate::services::wallet_service;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// INTERNATIONAL SERVICE
+// ============================================================================
+// Sending money to bank accounts abroad, by IBAN and BIC.
+//
+// 1. POST /wallet/international-transfers/quote shows what a transfer
+//    would cost: the correspondent fee, the mid-market rate, our rate (the
+//    mid-market rate less INTL_FX_MARGIN_BPS) and what the beneficiary
+//    receives
+// 2. POST /wallet/international-transfers checks the IBAN and BIC, quotes
+//    again at the current rate and takes amount + fee out of the wallet.
+//    The transfer starts out SUBMITTED.
+// 3. Operations sends it through the correspondent bank and moves it along
+//    (PUT /admin/international-transfers/:id/status): IN_TRANSIT with the
+//    network's tracking reference, then SETTLED - or RETURNED, which puts
+//    the money back in the wallet
+//
+// The sender follows each step in GET /wallet/international-transfers/:id
+// and on the dashboard's International page.
+//
+// The correspondent fee is set in US dollars and charged in the wallet's
+// currency, so quotes for non-USD wallets need exchange rates even when no
+// conversion happens. Without rates (RATES_PROVIDER=off, or stale) quotes
+// and transfers answer 503.
+
+/// Transfers shown by GET /wallet/international-transfers
+const TRANSFER_HISTORY_LIMIT: i64 = 50;
+
+/// Transfers listed per ops queue request
+const QUEUE_LIMIT: i64 = 200;
+
+/// Decimal places of the rate a transfer is converted at
+const RATE_DECIMALS: u32 = 6;
+
+/// What a transfer of `amount` (in the wallet's currency) to
+/// `destination` costs right now
+pub async fn quote(
+    pool: &PgPool,
+    rates: &ExchangeRateService,
+    config: &Config,
+    user_id: Uuid,
+    amount: Decimal,
+    destination: Currency,
+) -> Result<InternationalQuoteResponse, AppError> {
+    check_amount(amount)?;
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+
+    price(rates, config, wallet.currency, amount, destination).await
+}
+
+/// Send money to a bank account abroad
+///
+/// The wallet is debited (amount + correspondent fee) straight away; the
+/// transfer is SUBMITTED until operations sends it. Fails with
+/// `InsufficientBalance` if the wallet can't cover it.
+pub async fn submit(
+    pool: &PgPool,
+    rates: &ExchangeRateService,
+    config: &Config,
+    user_id: Uuid,
+    req: &InternationalTransferRequest,
+) -> Result<InternationalTransferResponse, AppError> {
+    check_amount(req.amount)?;
+    wallet_service::ensure_can_move_money(pool, user_id).await?;
+    let iban = Iban::parse(&req.iban).map_err(AppError::validation)?;
+    let bic = Bic::parse(&req.bic).map_err(AppError::validation)?;
+    if iban.country() != bic.country() {
+        return Err(AppError::validation(&format!(
+            "The BIC is for a bank in {}, but the IBAN is for an account in {}",
+            bic.country(),
+            iban.country()
+        )));
+    }
+    let beneficiary_name = req.beneficiary_name.trim();
+    let reference = req
+        .reference
+        .as_deref()
+        .map(str::trim)
+        .filter(|reference| !reference.is_empty());
+
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+    let quote = price(rates, config, wallet.currency, req.amount, req.destination_currency).await?;
+
+    let description = format!("International transfer to {} ({})", beneficiary_name, iban.masked());
+    let transfer = international_repo::start_transfer(
+        pool,
+        &NewInternationalTransfer {
+            user_id,
+            wallet_id: wallet.id,
+            beneficiary_name,
+            iban: iban.as_str(),
+            bic: bic.as_str(),
+            reference,
+            source_currency: quote.source_currency,
+            amount: quote.amount,
+            correspondent_fee: quote.correspondent_fee,
+            destination_currency: quote.destination_currency,
+            mid_rate: quote.mid_rate,
+            rate: quote.rate,
+            destination_amount: quote.destination_amount,
+            description: &description,
+        },
+    )
+    .await?;
+
+    tracing::info!(
+        "🌍 User {} submitted international transfer {}: {} {} -> {} {}",
+        user_id,
+        transfer.id,
+        transfer.amount,
+        transfer.source_currency,
+        transfer.destination_amount,
+        transfer.destination_currency
+    );
+
+    Ok(InternationalTransferResponse::from(transfer))
+}
+
+/// The user's most recent international transfers, newest first
+pub async fn list_transfers(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<InternationalTransferResponse>, AppError> {
+    let transfers = international_repo::list_transfers(pool, user_id, TRANSFER_HISTORY_LIMIT).await?;
+
+    Ok(transfers.into_iter().map(InternationalTransferResponse::from).collect())
+}
+
+/// One of the user's international transfers, to track it
+pub async fn get_transfer(
+    pool: &PgPool,
+    user_id: Uuid,
+    transfer_id: Uuid,
+) -> Result<InternationalTransferResponse, AppError> {
+    international_repo::find_transfer(pool, user_id, transfer_id)
+        .await?
+        .map(InternationalTransferResponse::from)
+        .ok_or_else(|| AppError::not_found("International transfer"))
+}
+
+/// Transfers waiting for operations, oldest first
+pub async fn queue(
+    pool: &PgPool,
+    status: InternationalTransferStatus,
+) -> Result<Vec<InternationalTransferResponse>, AppError> {
+    let transfers = international_repo::list_by_status(pool, status, QUEUE_LIMIT).await?;
+
+    Ok(transfers.into_iter().map(InternationalTransferResponse::from).collect())
+}
+
+/// Move a transfer along its lifecycle (operations)
+///
+/// IN_TRANSIT needs the network's tracking reference and RETURNED a
+/// reason, so the sender can be told what happened.
+pub async fn advance(
+    pool: &PgPool,
+    admin_id: Uuid,
+    transfer_id: Uuid,
+    req: &InternationalTransferStatusRequest,
+) -> Result<InternationalTransferResponse, AppError> {
+    let tracking_reference = req
+        .tracking_reference
+        .as_deref()
+        .map(str::trim)
+        .filter(|reference| !reference.is_empty());
+    let reason = req.reason.as_deref().map(str::trim).filter(|reason| !reason.is_empty());
+    match req.status {
+        InternationalTransferStatus::InTransit if tracking_reference.is_none() => {
+            return Err(AppError::validation("tracking_reference is required for IN_TRANSIT"));
+        }
+        InternationalTransferStatus::Returned if reason.is_none() => {
+            return Err(AppError::validation("reason is required for RETURNED"));
+        }
+        InternationalTransferStatus::Submitted => {
+            return Err(AppError::validation("A transfer can't go back to SUBMITTED"));
+        }
+        _ => {}
+    }
+
+    let transfer = international_repo::advance_transfer(
+        pool,
+        transfer_id,
+        req.status,
+        tracking_reference,
+        reason,
+    )
+    .await?;
+
+    tracing::info!(
+        "🌍 Admin {} moved international transfer {} to {}",
+        admin_id,
+        transfer.id,
+        transfer.status
+    );
+    if req.status == InternationalTransferStatus::Returned {
+        tracing::warn!(
+            "🌍 International transfer {} was returned ({}); the withdrawal was reversed",
+            transfer.id,
+            reason.unwrap_or("no reason given")
+        );
+    }
+
+    Ok(InternationalTransferResponse::from(transfer))
+}
+
+fn check_amount(amount: Decimal) -> Result<(), AppError> {
+    validate_amount(&amount).map_err(|e| AppError::invalid_field("amount", e))
+}
+
+/// Quote a transfer from a `source` wallet at the current rates
+///
+/// Our margin only applies when the money is converted; a transfer in the
+/// wallet's own currency goes at 1:1.
+async fn price(
+    rates: &ExchangeRateService,
+    config: &Config,
+    source: Currency,
+    amount: Decimal,
+    destination: Currency,
+) -> Result<InternationalQuoteResponse, AppError> {
+    let table = rates.current().await?;
+    let unquoted = |from: Currency, to: Currency| {
+        AppError::validation(&format!("No exchange rate from {} to {}", from, to))
+    };
+
+    let correspondent_fee = if source == Currency::Usd {
+        config.intl_correspondent_fee
+    } else {
+        let usd_rate = table
+            .rate(Currency::Usd, source)
+            .ok_or_else(|| unquoted(Currency::Usd, source))?;
+        (config.intl_correspondent_fee * usd_rate).round_dp(2)
+    };
+
+    let (mid_rate, rate, margin_bps) = if source == destination {
+        (Decimal::ONE, Decimal::ONE, 0)
+    } else {
+        let mid_rate = table
+            .rate(source, destination)
+            .ok_or_else(|| unquoted(source, destination))?
+            .round_dp(RATE_DECIMALS);
+        let margin = Decimal::new(i64::from(config.intl_fx_margin_bps), 4);
+        let rate = (mid_rate * (Decimal::ONE - margin)).round_dp(RATE_DECIMALS);
+        (mid_rate, rate, config.intl_fx_margin_bps)
+    };
+
+    let destination_amount = (amount * rate).round_dp(2);
+    if destination_amount <= Decimal::ZERO {
+        return Err(AppError::validation("Amount is too small to send"));
+    }
+
+    Ok(InternationalQuoteResponse {
+        source_currency: source,
+        amount,
+        correspondent_fee,
+        total_debit: amount + correspondent_fee,
+        destination_currency: destination,
+        mid_rate,
+        rate,
+        fx_margin_percent: Decimal::new(i64::from(margin_bps), 2),
+        destination_amount,
+        rate_fetched_at: table.fetched_at,
+    })
+}
+
+impl From<InternationalTransfer> for InternationalTransferResponse {
+    fn from(transfer: InternationalTransfer) -> Self {
+        // Both were checked before they were stored
+        let iban = Iban::parse(&transfer.iban)
+            .map(|iban| iban.masked())
+            .unwrap_or_default();
+
+        InternationalTransferResponse {
+            id: transfer.id,
+            transaction_id: transfer.transaction_id,
+            beneficiary_name: transfer.beneficiary_name,
+            iban,
+            bic: transfer.bic,
+            reference: transfer.reference,
+            source_currency: transfer.source_currency,
+            amount: transfer.amount,
+            correspondent_fee: transfer.correspondent_fee,
+            total_debit: transfer.amount + transfer.correspondent_fee,
+            destination_currency: transfer.destination_currency,
+            mid_rate: transfer.mid_rate,
+            rate: transfer.rate,
+            destination_amount: transfer.destination_amount,
+            // Guarded by a CHECK constraint
+            status: InternationalTransferStatus::parse(&transfer.status)
+                .unwrap_or(InternationalTransferStatus::Submitted),
+            tracking_reference: transfer.tracking_reference,
+            return_reason: transfer.return_reason,
+            submitted_at: transfer.created_at,
+            in_transit_at: transfer.in_transit_at,
+            settled_at: transfer.settled_at,
+            returned_at: transfer.returned_at,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::domain::models::{LoginEvent, LoginMethod};
+use crate::error::AppError;
+use crate::middleware::client::ClientInfo;
+use crate::repository::{login_repo, user_repo};
+use crate::services::email_service::EmailService;
+use crate::services::geoip::GeoIp;
+use crate::services::notification_service::Notifier;
+use crate::services::token_service;
+use chrono::Utc;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// ============================================================================
+// LOGIN HISTORY SERVICE
+// ============================================================================
+// Records every successful sign-in with its IP, browser and (from GeoIP)
+// country and city. One from an IP address and browser the user hasn't
+// signed in from together before is a new device: they get an email and
+// an in-app notification about it, unless they turned login alerts off
+// (GET/PUT /me/notifications, or the onboarding wizard).
+//
+// Recording happens in the background: a slow database or SMTP server
+// never holds up (or fails) the sign-in itself.
+
+/// Record a sign-in, and alert the user if it's from a new device
+pub fn record_login(
+    pool: &PgPool,
+    geoip: Option<&Arc<dyn GeoIp>>,
+    email_service: &EmailService,
+    notification_service: &Arc<dyn Notifier>,
+    user_id: Uuid,
+    method: LoginMethod,
+    client: ClientInfo,
+) {
+    let pool = pool.clone();
+    let geoip = geoip.cloned();
+    let email_service = email_service.clone();
+    let notification_service = notification_service.clone();
+
+    tokio::spawn(async move {
+        let notifier = notification_service.as_ref();
+        if let Err(e) =
+            record(&pool, geoip.as_deref(), &email_service, notifier, user_id, method, client).await
+        {
+            tracing::error!("❌ Failed to record login of user {}: {}", user_id, e);
+        }
+    });
+}
+
+async fn record(
+    pool: &PgPool,
+    geoip: Option<&dyn GeoIp>,
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
+    user_id: Uuid,
+    method: LoginMethod,
+    client: ClientInfo,
+) -> Result<(), AppError> {
+    let location = geoip.and_then(|geoip| geoip.lookup(client.ip)).unwrap_or_default();
+    let user_agent = client.user_agent.as_deref();
+    let ip = client.ip.to_string();
+
+    let new_device = login_repo::is_new_device(pool, user_id, &ip, user_agent).await?;
+    login_repo::insert(pool, user_id, method, &ip, user_agent, &location, new_device).await?;
+
+    if new_device && user_repo::get_login_alerts(pool, user_id).await? {
+        let device = token_service::device_name(user_agent);
+        let location = location.describe().unwrap_or_else(|| "Unknown".to_string());
+        let user = user_repo::find_user_by_id(pool, user_id).await?;
+        email_service
+            .send_new_device_alert(&user.email, &device, &location, &ip, Utc::now())
+            .await;
+        notification_service
+            .send_to_user(
+                &user_id,
+                serde_json::json!({
+                    "type": "new_login",
+                    "message": format!("🔐 New sign-in from {} ({})", device, ip),
+                    "device": device,
+                    "location": location,
+                    "ip": ip,
+                }),
+            )
+            .await;
+    }
+
+    Ok(())
+}
+
+/// The user's latest sign-ins, newest first
+pub async fn recent_logins(pool: &PgPool, user_id: Uuid) -> Result<Vec<LoginEvent>, AppError> {
+    login_repo::list_recent(pool, user_id).await
+}
@@ -0,0 +1,179 @@
+use crate::config::Config;
+use crate::domain::models::LoginResponse;
+use crate::error::AppError;
+use crate::repository::{login_attempt_repo, user_repo};
+use crate::services::auth_service;
+use crate::services::clock::Clock;
+use crate::services::email_service::EmailService;
+use crate::services::session_service::random_token;
+use crate::utils::jwt::JwtKeys;
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// LOGIN THROTTLE SERVICE
+// ============================================================================
+// Brute-force protection for password sign-in, per email address (the IP
+// rate limiter can't stop guesses spread over many addresses or proxies):
+// 1. The first few wrong passwords in a row cost nothing. After that each
+//    one makes the next attempt wait twice as long (1s, 2s, 4s, ... up to
+//    5 minutes); attempts during the wait are refused without looking at
+//    the password.
+// 2. After LOGIN_LOCKOUT_THRESHOLD failures the address is locked, and the
+//    account's owner gets an email with a one-time unlock link. A password
+//    reset unlocks it too.
+// Unknown addresses are throttled and locked the same way (just without
+// the email), so the answers don't tell which addresses have accounts.
+// Failures are forgotten a day after the last one, or on a good sign-in.
+
+/// Wrong passwords in a row before attempts have to wait
+const FREE_ATTEMPTS: i32 = 3;
+
+/// The longest wait between attempts
+const MAX_BACKOFF_SECS: i64 = 300;
+
+/// Failures this old no longer count
+const FORGET_AFTER_HOURS: i64 = 24;
+
+/// When to lock, and where the unlock link points
+#[derive(Debug, Clone)]
+pub struct LockoutPolicy {
+    /// LOGIN_LOCKOUT_THRESHOLD
+    pub max_failures: u32,
+    /// Where the app is reached (the unlock link points at its unlock page)
+    pub public_url: String,
+}
+
+impl LockoutPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        LockoutPolicy {
+            max_failures: config.login_lockout_threshold,
+            public_url: config.public_url(),
+        }
+    }
+}
+
+/// Sign in with a password, unless the address is locked or has to wait
+///
+/// Wraps `auth_service::login`: wrong passwords are counted, a good one
+/// clears the count. Fails with `TooManyAttempts` (429) while locked or
+/// backing off.
+#[allow(clippy::too_many_arguments)]
+pub async fn login(
+    pool: &PgPool,
+    email_service: &EmailService,
+    clock: &dyn Clock,
+    policy: &LockoutPolicy,
+    email: &str,
+    password: &str,
+    jwt_keys: &JwtKeys,
+    session_id: Option<Uuid>,
+) -> Result<LoginResponse, AppError> {
+    let now = clock.now();
+    check(pool, email, now).await?;
+
+    let result = auth_service::login(pool, email, password, jwt_keys, session_id, clock).await;
+    match &result {
+        Ok(_) => login_attempt_repo::clear(pool, email).await?,
+        Err(AppError::InvalidCredentials) => {
+            record_failure(pool, email_service, policy, email, now).await?
+        }
+        Err(_) => {}
+    }
+
+    result
+}
+
+/// Use an emailed unlock token
+pub async fn unlock(pool: &PgPool, token: &str) -> Result<(), AppError> {
+    let email = login_attempt_repo::unlock(pool, &hash_token(token.trim()))
+        .await?
+        .ok_or_else(|| AppError::validation("This unlock link is invalid or has already been used"))?;
+
+    tracing::info!("🔓 Sign-in unlocked for {}", email);
+    Ok(())
+}
+
+/// Refuse the attempt if the address is locked or still has to wait
+async fn check(pool: &PgPool, email: &str, now: DateTime<Utc>) -> Result<(), AppError> {
+    let Some(attempts) = login_attempt_repo::find(pool, email).await? else {
+        return Ok(());
+    };
+
+    if attempts.locked_at.is_some() {
+        return Err(AppError::TooManyAttempts {
+            reason: "this account is locked; use the link we emailed to unlock it, or reset your password"
+                .to_string(),
+            retry_after_secs: None,
+        });
+    }
+    match attempts.retry_after {
+        Some(retry_after) if retry_after > now => {
+            let wait = (retry_after - now).num_seconds().max(1);
+            Err(AppError::TooManyAttempts {
+                reason: format!("try again in {} seconds", wait),
+                retry_after_secs: Some(wait),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Count a wrong password: back off, or lock once there were too many
+async fn record_failure(
+    pool: &PgPool,
+    email_service: &EmailService,
+    policy: &LockoutPolicy,
+    email: &str,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let forget_before = now - Duration::hours(FORGET_AFTER_HOURS);
+    let failures = login_attempt_repo::record_failure(pool, email, now, forget_before).await?;
+
+    if failures as i64 >= i64::from(policy.max_failures) {
+        return lock(pool, email_service, policy, email, now).await;
+    }
+    if failures >= FREE_ATTEMPTS {
+        let exponent = (failures - FREE_ATTEMPTS).min(16) as u32;
+        let wait = 2_i64.pow(exponent).min(MAX_BACKOFF_SECS);
+        login_attempt_repo::set_retry_after(pool, email, now + Duration::seconds(wait)).await?;
+    }
+
+    Ok(())
+}
+
+/// Lock the address and email the owner an unlock link (if it has an
+/// account)
+async fn lock(
+    pool: &PgPool,
+    email_service: &EmailService,
+    policy: &LockoutPolicy,
+    email: &str,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let token = random_token();
+    if !login_attempt_repo::lock(pool, email, now, &hash_token(&token)).await? {
+        return Ok(());
+    }
+
+    let user = match user_repo::find_user_by_email(pool, email).await {
+        Ok(user) => user,
+        Err(AppError::NotFound(_)) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    tracing::warn!("🔒 Sign-in locked for user {} after too many failed attempts", user.id);
+
+    let link = format!("{}/account/unlock?token={}", policy.public_url.trim_end_matches('/'), token);
+    let email_service = email_service.clone();
+    tokio::spawn(async move {
+        email_service.send_account_locked(&user.email, &link, &token).await;
+    });
+
+    Ok(())
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,275 @@
+use crate::config::AlertSeverity;
+use crate::error::AppError;
+use crate::repository::user_repo;
+use crate::repository::{notification_repo, open_banking_repo, password_reset_repo, token_repo};
+use crate::services::ops_alerts::{OpsAlert, OpsAlerts};
+use crate::services::referral_service::{self, ReferralTerms};
+use crate::services::{archive_service, integrity_service, statement_service};
+use crate::shutdown::Shutdown;
+use sqlx::PgPool;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+// ============================================================================
+// MAINTENANCE SERVICE
+// ============================================================================
+// Housekeeping that runs in the background while the server is up.
+//
+// Transaction partitions:
+// The transactions table is partitioned by month (migration 010). Each
+// month needs its partition before rows arrive, so we keep a few months
+// ahead. If this job ever stops, inserts still succeed (they go to
+// `transactions_default`) and are moved into the right partition once it
+// is created.
+//
+// Orphan users:
+// Registration used to create the user and the wallet in two separate
+// queries, so a crash in between left an account without a wallet (and
+// unable to do anything). Registration is atomic now; this job gives the
+// accounts from before that an empty wallet.
+//
+// Transaction archive:
+// Transactions older than TRANSACTION_ARCHIVE_AFTER_YEARS move to cold
+// storage (see archive_service).
+//
+// Monthly totals:
+// The `monthly_wallet_totals` view behind statements is recomputed after
+// archiving (see statement_service).
+//
+// Open Banking tokens:
+// Expired third-party access tokens are deleted; they stopped working at
+// expiry, this only keeps the table small.
+//
+// Referrals:
+// Referrals whose referee has made a qualifying deposit are paid (bank and
+// card deposits settle after the request that made them), and pending
+// ones past REFERRAL_WINDOW_DAYS expire (see referral_service).
+//
+// Revoked tokens:
+// Logged-out JWTs are remembered until they expire; after that they're
+// refused anyway and the row is deleted (see token_service). So are the
+// sessions of expired tokens, and expired password reset tokens.
+//
+// Notification inbox:
+// Notifications are kept for NOTIFICATION_RETENTION_DAYS so a reconnecting
+// client can catch up; that's far longer than any reconnect takes.
+//
+// Balance integrity:
+// Every wallet's balance is checked against its transaction records (see
+// integrity_service). Mismatches are logged and sent as a critical ops
+// alert, never fixed here.
+
+/// How many months ahead of the current one get a partition
+pub const PARTITION_MONTHS_AHEAD: i32 = 3;
+
+/// How long notifications stay in the inbox
+const NOTIFICATION_RETENTION_DAYS: i32 = 30;
+
+/// How often the maintenance job runs
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Make sure every month in `months` has a partition
+///
+/// Months are offsets from the current one: `0..=3` is this month and the
+/// next three, `-6..=0` the last six months. Existing partitions are left
+/// alone, so this is safe to call any time.
+pub async fn ensure_transaction_partitions(
+    pool: &PgPool,
+    months: RangeInclusive<i32>,
+) -> Result<(), AppError> {
+    for offset in months {
+        sqlx::query!(
+            r#"
+            SELECT create_transaction_partition(
+                (date_trunc('month', NOW() AT TIME ZONE 'UTC') + make_interval(months => $1))::DATE
+            )
+            "#,
+            offset
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::DatabaseError)?;
+    }
+
+    Ok(())
+}
+
+/// Give every user without a wallet an empty one
+///
+/// Returns how many wallets were created.
+pub async fn repair_orphan_users(pool: &PgPool) -> Result<usize, AppError> {
+    let orphans = user_repo::find_users_without_wallet(pool).await?;
+
+    for &user_id in &orphans {
+        let wallet = user_repo::create_wallet(pool, user_id).await?;
+        tracing::warn!("🔧 Created missing wallet {} for user {}", wallet.id, user_id);
+    }
+
+    Ok(orphans.len())
+}
+
+/// Run the maintenance tasks now and then once a day, in the background,
+/// until shutdown
+///
+/// `archive_after_years` is TRANSACTION_ARCHIVE_AFTER_YEARS (0 = never).
+pub fn spawn(
+    shutdown: &Shutdown,
+    pool: PgPool,
+    archive_after_years: u32,
+    referral_terms: ReferralTerms,
+    alerts: OpsAlerts,
+) {
+    let stopped = shutdown.clone();
+    shutdown.spawn(async move {
+        let mut interval = tokio::time::interval(MAINTENANCE_INTERVAL);
+        loop {
+            // The first tick completes immediately
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = stopped.stopped() => break,
+            }
+
+            match ensure_transaction_partitions(&pool, 0..=PARTITION_MONTHS_AHEAD).await {
+                Ok(()) => tracing::debug!("🧹 Transaction partitions are up to date"),
+                Err(e) => tracing::error!("❌ Failed to create transaction partitions: {}", e),
+            }
+
+            match repair_orphan_users(&pool).await {
+                Ok(0) => tracing::debug!("🧹 Every user has a wallet"),
+                Ok(n) => tracing::warn!("🔧 Created wallets for {} users that had none", n),
+                Err(e) => tracing::error!("❌ Failed to repair users without wallets: {}", e),
+            }
+
+            match archive_service::archive_old_transactions(&pool, archive_after_years).await {
+                Ok(0) => tracing::debug!("🧹 No transactions to archive"),
+                Ok(n) => tracing::info!("🗄️ Archived {} transactions", n),
+                Err(e) => tracing::error!("❌ Failed to archive old transactions: {}", e),
+            }
+
+            match statement_service::refresh_monthly_totals(&pool).await {
+                Ok(()) => tracing::debug!("🧹 Monthly wallet totals refreshed"),
+                Err(e) => tracing::error!("❌ Failed to refresh monthly wallet totals: {}", e),
+            }
+
+            match open_banking_repo::delete_expired_tokens(&pool).await {
+                Ok(0) => tracing::debug!("🧹 No expired Open Banking tokens"),
+                Ok(n) => tracing::info!("🧹 Deleted {} expired Open Banking tokens", n),
+                Err(e) => tracing::error!("❌ Failed to delete expired Open Banking tokens: {}", e),
+            }
+
+            match referral_service::sweep(&pool, &referral_terms, chrono::Utc::now()).await {
+                Ok((0, 0)) => tracing::debug!("🧹 No referrals to pay or expire"),
+                Ok((rewarded, expired)) => {
+                    tracing::info!("🎁 Paid {} referrals, expired {}", rewarded, expired)
+                }
+                Err(e) => tracing::error!("❌ Failed to settle referrals: {}", e),
+            }
+
+            match token_repo::delete_expired(&pool).await {
+                Ok(0) => tracing::debug!("🧹 No expired revoked tokens"),
+                Ok(n) => tracing::info!("🧹 Forgot {} expired revoked tokens", n),
+                Err(e) => tracing::error!("❌ Failed to delete expired revoked tokens: {}", e),
+            }
+
+            match token_repo::delete_expired_sessions(&pool).await {
+                Ok(0) => tracing::debug!("🧹 No expired sessions"),
+                Ok(n) => tracing::info!("🧹 Forgot {} expired sessions", n),
+                Err(e) => tracing::error!("❌ Failed to delete expired sessions: {}", e),
+            }
+
+            match password_reset_repo::delete_expired(&pool, chrono::Utc::now()).await {
+                Ok(0) => tracing::debug!("🧹 No expired password reset tokens"),
+                Ok(n) => tracing::info!("🧹 Deleted {} expired password reset tokens", n),
+                Err(e) => tracing::error!("❌ Failed to delete expired password reset tokens: {}", e),
+            }
+
+            match notification_repo::delete_older_than(&pool, NOTIFICATION_RETENTION_DAYS).await {
+                Ok(0) => tracing::debug!("🧹 No old notifications"),
+                Ok(n) => tracing::info!("🧹 Deleted {} old notifications", n),
+                Err(e) => tracing::error!("❌ Failed to delete old notifications: {}", e),
+            }
+
+            verify_balances(&pool, &alerts).await;
+        }
+    });
+}
+
+/// Check every wallet's balance against its ledger, and every journal, and
+/// report what doesn't add up
+async fn verify_balances(pool: &PgPool, alerts: &OpsAlerts) {
+    let report = match integrity_service::verify_all_wallets(pool).await {
+        Ok(report) => report,
+        Err(e) => {
+            tracing::error!("❌ Failed to verify wallet balances: {}", e);
+            return;
+        }
+    };
+
+    for mismatch in &report.mismatches {
+        tracing::error!(
+            wallet_id = %mismatch.wallet_id,
+            stored_balance = %mismatch.stored_balance,
+            ledger_balance = %mismatch.ledger_balance,
+            records_balance = %mismatch.records_balance,
+            difference = %mismatch.difference,
+            "🚨 Wallet balance does not match its ledger"
+        );
+    }
+
+    if !report.unbalanced_journals.is_empty() {
+        tracing::error!(
+            journals = ?report.unbalanced_journals,
+            "🚨 {} ledger journals do not balance",
+            report.unbalanced_journals.len()
+        );
+        alerts.send(
+            OpsAlert::new(
+                AlertSeverity::Critical,
+                format!("{} ledger journals do not balance", report.unbalanced_journals.len()),
+            )
+            .field(
+                "Journals",
+                report
+                    .unbalanced_journals
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+        );
+    }
+
+    if report.mismatches.is_empty() {
+        tracing::info!("✅ {} wallet balances match their ledgers", report.wallets_checked);
+    } else {
+        tracing::error!(
+            "🚨 {} of {} wallet balances do not match their ledgers",
+            report.mismatches.len(),
+            report.wallets_checked
+        );
+
+        let alert = report.mismatches.iter().fold(
+            OpsAlert::new(
+                AlertSeverity::Critical,
+                format!(
+                    "{} of {} wallet balances do not match their ledgers",
+                    report.mismatches.len(),
+                    report.wallets_checked
+                ),
+            ),
+            |alert, mismatch| {
+                alert.field(
+                    format!("Wallet {}", mismatch.wallet_id),
+                    format!(
+                        "stored {}, ledger {} (off by {}), records {}",
+                        mismatch.stored_balance,
+                        mismatch.ledger_balance,
+                        mismatch.difference,
+                        mismatch.records_balance
+                    ),
+                )
+            },
+        );
+        alerts.send(alert);
+    }
+}
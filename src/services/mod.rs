@@ -1,4 +1,41 @@
 pub mod auth_service;
 pub mod wallet_service;
 pub mod email_service;
+pub mod email_templates;
 pub mod notification_service;
+pub mod clock;
+pub mod rate_limiter;
+pub mod onboarding_service;
+pub mod session_service;
+pub mod login_history_service;
+pub mod sso_service;
+pub mod accounting_service;
+pub mod admin_service;
+pub mod maintenance_service;
+pub mod ops_alerts;
+pub mod storage;
+pub mod geoip;
+pub mod integrity_service;
+pub mod archive_service;
+pub mod bank_service;
+pub mod bank_provider;
+pub mod apple_pay;
+pub mod card_processor;
+pub mod card_service;
+pub mod international_service;
+pub mod crypto_provider;
+pub mod crypto_service;
+pub mod open_banking_service;
+pub mod organization_service;
+pub mod login_throttle_service;
+pub mod password_reset_service;
+pub mod payment_request_service;
+pub mod referral_service;
+pub mod scheduled_transfer_service;
+pub mod search_service;
+pub mod statement_service;
+pub mod tax_service;
+pub mod token_service;
+pub mod exchange_rate_service;
+pub mod rate_provider;
+pub mod debug_capture;
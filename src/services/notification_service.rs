@@ -1,48 +1,296 @@
+use crate::domain::ids;
+use crate::repository::notification_repo;
+use serde_json::Value;
+use sqlx::PgPool;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use uuid::Uuid;
 
+// ============================================================================
+// NOTIFICATIONS
+// ============================================================================
+// Live messages to signed-in users ("you received $25"). `Notifier` is what
+// services talk to; `NotificationService` is the in-process implementation
+// that pushes them down each user's WebSocket (see handlers/ws.rs).
+//
+// Every notification is kept in the user's inbox (notification_repo) with a
+// sequence number, which is sent along as "sequence". A client that was
+// disconnected for a moment reconnects with the last sequence it saw and is
+// sent what it missed first, so a transfer alert isn't lost to a flaky
+// connection. Notifications that can't be stored are still pushed, without
+// a sequence.
+//
+// When the server shuts down, `disconnect_all` closes every connection with
+// "going away" so clients reconnect to another instance (or to this one once
+// it's back) and catch up from their last sequence.
+//
+// A user can be connected more than once (two tabs, phone and laptop); each
+// connection gets its own id and every one of them is sent each
+// notification.
+//
+// Announcements (`broadcast_all`, e.g. "maintenance at 22:00") go to every
+// open connection. They aren't kept in anyone's inbox: a client that's
+// offline at the time doesn't get them.
+
+/// A notification on its way to a connected client
+#[derive(Debug, Clone)]
+pub struct LiveNotification {
+    /// 0 if it couldn't be stored (it can't be replayed either)
+    pub sequence: i64,
+    /// The JSON text sent to the client
+    pub message: String,
+}
+
+/// Pushes messages to connected users
+#[async_trait::async_trait]
+pub trait Notifier: Send + Sync {
+    /// A user opened a connection; their messages go to `sender` (as well
+    /// as to their other connections) from now on. Returns the connection's id
+    async fn add_client(&self, user_id: Uuid, sender: mpsc::UnboundedSender<LiveNotification>) -> Uuid;
+
+    /// The connection `connection_id` closed (the user's others stay)
+    async fn remove_client(&self, user_id: &Uuid, connection_id: Uuid);
+
+    /// Send a JSON object to a specific user (kept for replay; pushed now
+    /// to each of their connections)
+    async fn send_to_user(&self, user_id: &Uuid, payload: Value);
+
+    /// Send a JSON object to every open connection, right now (not kept
+    /// for replay). Returns how many connections it went to
+    async fn broadcast_all(&self, payload: Value) -> usize;
+
+    /// Close every connection; the server is shutting down
+    async fn disconnect_all(&self) {}
+
+    /// Resolves once `disconnect_all` has been called, at which point a
+    /// connection should close (never, by default)
+    async fn closing(&self) {
+        std::future::pending::<()>().await
+    }
+}
+
+/// The JSON text a client is sent for an inbox entry
+pub fn message(payload: &str, sequence: i64) -> String {
+    match serde_json::from_str::<Value>(payload) {
+        Ok(Value::Object(mut fields)) => {
+            fields.insert("sequence".to_string(), sequence.into());
+            Value::Object(fields).to_string()
+        }
+        _ => serde_json::json!({ "sequence": sequence, "message": payload }).to_string(),
+    }
+}
+
+// ============================================================================
+// CATCHING UP
+// ============================================================================
+// Shared by /ws and /events: a client reconnecting with the last sequence
+// it saw is sent everything after it, a page at a time until there's no
+// more, then `{ "type": "connected", "sequence": N }`. N is the last
+// sequence the client was sent, so resuming from it never skips anything.
+// The client's channel is registered before the inbox is read, so a
+// notification can come both ways; `Replayed` filters out the live copy.
+
+/// Missed notifications read from the inbox at a time
+pub const REPLAY_PAGE: i64 = 100;
+
+/// Where a live connection's messages go (a WebSocket, an event stream)
+#[async_trait::async_trait]
+pub trait LiveSink: Send {
+    /// Send one message, with its sequence (0 if it has none). False once
+    /// the client is gone
+    async fn deliver(&mut self, message: String, sequence: i64) -> bool;
+}
+
+/// What a client was sent while catching up
+#[derive(Debug, Clone, Copy)]
+pub struct Replayed {
+    up_to: i64,
+}
+
+impl Replayed {
+    /// Whether a live notification still has to be sent (the replay
+    /// didn't already)
+    pub fn is_new(&self, notification: &LiveNotification) -> bool {
+        notification.sequence == 0 || notification.sequence > self.up_to
+    }
+}
+
+/// The message telling a client it's caught up to `sequence`
+pub fn connected_message(sequence: i64) -> String {
+    serde_json::json!({ "type": "connected", "sequence": sequence }).to_string()
+}
+
+/// Send `user_id` what they missed after `since`, then the `connected`
+/// message (sent with its sequence, so an event stream can resume from it)
+///
+/// Without `since` nothing is replayed and the client starts from the
+/// latest sequence. Returns None if the client went away or the inbox
+/// couldn't be read (the connection should close: it can't be caught up).
+pub async fn catch_up(
+    pool: &PgPool,
+    user_id: Uuid,
+    since: Option<i64>,
+    sink: &mut impl LiveSink,
+) -> Option<Replayed> {
+    let up_to = match since {
+        Some(since) => {
+            let mut up_to = since;
+            loop {
+                let page = notification_repo::list_since(pool, user_id, up_to, REPLAY_PAGE)
+                    .await
+                    .map_err(|e| {
+                        tracing::error!("❌ Failed to replay notifications for user {}: {}", user_id, e)
+                    })
+                    .ok()?;
+                let full = page.len() as i64 == REPLAY_PAGE;
+                for stored in page {
+                    if !sink.deliver(message(&stored.payload, stored.sequence), stored.sequence).await {
+                        return None;
+                    }
+                    up_to = stored.sequence;
+                }
+                if !full {
+                    break up_to;
+                }
+            }
+        }
+        None => notification_repo::latest_sequence(pool, user_id)
+            .await
+            .map_err(|e| tracing::error!("❌ Failed to read latest notification for user {}: {}", user_id, e))
+            .ok()?,
+    };
+
+    if !sink.deliver(connected_message(up_to), up_to).await {
+        return None;
+    }
+    Some(Replayed { up_to })
+}
+
+/// One user's open connections, by connection id
+type Connections = HashMap<Uuid, mpsc::UnboundedSender<LiveNotification>>;
+
 /// Service to manage active WebSocket connections
 #[derive(Clone)]
 pub struct NotificationService {
-    // Map of user_id -> sender channel
-    clients: Arc<Mutex<HashMap<Uuid, mpsc::UnboundedSender<String>>>>,
+    pool: PgPool,
+    // user_id -> connection id -> sender channel
+    clients: Arc<Mutex<HashMap<Uuid, Connections>>>,
+    // Flipped by `disconnect_all`
+    closing: Arc<watch::Sender<bool>>,
 }
 
 impl NotificationService {
-    pub fn new() -> Self {
+    pub fn new(pool: PgPool) -> Self {
+        let (closing, _) = watch::channel(false);
         Self {
+            pool,
             clients: Arc::new(Mutex::new(HashMap::new())),
+            closing: Arc::new(closing),
         }
     }
+}
 
-    /// Add a new client connection
-    pub async fn add_client(&self, user_id: Uuid, sender: mpsc::UnboundedSender<String>) {
+#[async_trait::async_trait]
+impl Notifier for NotificationService {
+    async fn add_client(&self, user_id: Uuid, sender: mpsc::UnboundedSender<LiveNotification>) -> Uuid {
+        let connection_id = ids::new_id();
         let mut clients = self.clients.lock().await;
-        clients.insert(user_id, sender);
-        tracing::info!("✅ User {} connected to WebSocket", user_id);
+        let connections = clients.entry(user_id).or_default();
+        connections.insert(connection_id, sender);
+        tracing::info!(
+            "✅ User {} connected to WebSocket ({} connections)",
+            user_id,
+            connections.len()
+        );
+        connection_id
     }
 
-    /// Remove a client connection
-    pub async fn remove_client(&self, user_id: &Uuid) {
+    async fn remove_client(&self, user_id: &Uuid, connection_id: Uuid) {
         let mut clients = self.clients.lock().await;
-        clients.remove(user_id);
+        if let Some(connections) = clients.get_mut(user_id) {
+            connections.remove(&connection_id);
+            if connections.is_empty() {
+                clients.remove(user_id);
+            }
+        }
         tracing::info!("❌ User {} disconnected from WebSocket", user_id);
     }
 
-    /// Send a message to a specific user (if they're online)
-    pub async fn send_to_user(&self, user_id: &Uuid, message: String) {
+    async fn send_to_user(&self, user_id: &Uuid, payload: Value) {
+        let payload = payload.to_string();
+        let notification = match notification_repo::insert(&self.pool, *user_id, &payload).await {
+            Ok(stored) => LiveNotification {
+                sequence: stored.sequence,
+                message: message(&stored.payload, stored.sequence),
+            },
+            Err(e) => {
+                tracing::error!("❌ Failed to store notification for user {}: {}", user_id, e);
+                LiveNotification { sequence: 0, message: payload }
+            }
+        };
+
         let clients = self.clients.lock().await;
-        if let Some(sender) = clients.get(user_id) {
-            if sender.send(message.clone()).is_ok() {
-                tracing::info!("📨 Sent notification to user {}", user_id);
+        let Some(connections) = clients.get(user_id) else {
+            tracing::debug!("User {} is offline, notification kept in their inbox", user_id);
+            return;
+        };
+        for (connection_id, sender) in connections {
+            if sender.send(notification.clone()).is_ok() {
+                tracing::info!("📨 Sent notification to user {} ({})", user_id, connection_id);
             } else {
-                tracing::warn!("⚠️  Failed to send to user {}", user_id);
+                tracing::warn!("⚠️  Failed to send to user {} ({})", user_id, connection_id);
             }
-        } else {
-            tracing::debug!("User {} is offline, skipping notification", user_id);
         }
     }
+
+    async fn broadcast_all(&self, payload: Value) -> usize {
+        let notification = LiveNotification { sequence: 0, message: payload.to_string() };
+
+        let clients = self.clients.lock().await;
+        let sent = clients
+            .values()
+            .flat_map(HashMap::values)
+            .filter(|sender| sender.send(notification.clone()).is_ok())
+            .count();
+        tracing::info!("📣 Broadcast sent to {} WebSocket connections", sent);
+        sent
+    }
+
+    async fn disconnect_all(&self) {
+        self.closing.send_replace(true);
+        let mut clients = self.clients.lock().await;
+        let connections: usize = clients.values().map(HashMap::len).sum();
+        tracing::info!("👋 Closing {} WebSocket connections", connections);
+        clients.clear();
+    }
+
+    async fn closing(&self) {
+        let mut closing = self.closing.subscribe();
+        // Can't fail: `self` holds the sender
+        let _ = closing.wait_for(|closing| *closing).await;
+    }
+}
+
+/// Logs notifications instead of delivering them (no database, no
+/// WebSockets; used by the in-memory demo)
+pub struct LogNotifier;
+
+#[async_trait::async_trait]
+impl Notifier for LogNotifier {
+    async fn add_client(&self, _user_id: Uuid, _sender: mpsc::UnboundedSender<LiveNotification>) -> Uuid {
+        ids::new_id()
+    }
+
+    async fn remove_client(&self, _user_id: &Uuid, _connection_id: Uuid) {}
+
+    async fn send_to_user(&self, user_id: &Uuid, payload: Value) {
+        tracing::info!("🔔 Notification for user {} (not delivered): {}", user_id, payload);
+    }
+
+    async fn broadcast_all(&self, payload: Value) -> usize {
+        tracing::info!("📣 Broadcast (not delivered): {}", payload);
+        0
+    }
 }
@@ -0,0 +1,172 @@
+use crate::domain::models::{OnboardingProgress, OnboardingStep};
+use crate::error::AppError;
+use crate::i18n::Locale;
+use crate::repository::{onboarding_repo, user_repo, UserRepository};
+use crate::services::email_service::EmailService;
+use crate::services::notification_service::Notifier;
+use crate::services::wallet_service;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// ONBOARDING SERVICE
+// ============================================================================
+// Drives the post-registration wizard:
+// 1. Verify email (6-digit code sent by email)
+// 2. Notification preferences
+// 3. Two-factor authentication (optional)
+// 4. First deposit (optional)
+//
+// Progress is stored after every step, so a user who leaves halfway
+// picks up where they left off on their next login.
+
+/// How long an emailed verification code stays valid
+const VERIFICATION_CODE_TTL_HOURS: i64 = 24;
+
+/// Start onboarding for a newly registered user and email them a code
+pub async fn start(
+    pool: &PgPool,
+    email_service: &EmailService,
+    user_id: Uuid,
+    email: &str,
+) -> Result<OnboardingProgress, AppError> {
+    let code = generate_verification_code();
+    let expires_at = Utc::now() + Duration::hours(VERIFICATION_CODE_TTL_HOURS);
+
+    let progress = onboarding_repo::create_progress(pool, user_id, &code, expires_at).await?;
+    let locale = pool.preferred_locale(user_id).await.unwrap_or_default();
+    send_code(email_service, email, locale, code);
+
+    Ok(progress)
+}
+
+/// Get the user's progress, or `None` if they have nothing left to do
+pub async fn pending(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<OnboardingProgress>, AppError> {
+    let progress = onboarding_repo::find_progress(pool, user_id).await?;
+    Ok(progress.filter(|p| p.step() != OnboardingStep::Complete))
+}
+
+/// Check the emailed code and advance past the verification step
+pub async fn verify_email(pool: &PgPool, user_id: Uuid, code: &str) -> Result<(), AppError> {
+    let progress = require_step(pool, user_id, OnboardingStep::VerifyEmail).await?;
+
+    let expected = progress
+        .verification_code
+        .as_deref()
+        .ok_or_else(|| AppError::validation("No verification code pending, request a new one"))?;
+
+    if progress
+        .verification_expires_at
+        .is_none_or(|expires_at| expires_at < Utc::now())
+    {
+        return Err(AppError::validation("Verification code has expired, request a new one"));
+    }
+
+    if code.trim() != expected {
+        return Err(AppError::validation("Invalid verification code"));
+    }
+
+    onboarding_repo::mark_email_verified(pool, user_id, OnboardingStep::VerifyEmail.next()).await
+}
+
+/// Issue and email a fresh verification code
+pub async fn resend_code(
+    pool: &PgPool,
+    email_service: &EmailService,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    require_step(pool, user_id, OnboardingStep::VerifyEmail).await?;
+    let user = user_repo::find_user_by_id(pool, user_id).await?;
+
+    let code = generate_verification_code();
+    let expires_at = Utc::now() + Duration::hours(VERIFICATION_CODE_TTL_HOURS);
+    onboarding_repo::set_verification_code(pool, user_id, &code, expires_at).await?;
+    let locale = pool.preferred_locale(user_id).await.unwrap_or_default();
+    send_code(email_service, &user.email, locale, code);
+
+    Ok(())
+}
+
+/// Save notification preferences (`login_alerts` is kept on the user, as
+/// GET/PUT /me/notifications change it later)
+pub async fn save_notification_prefs(
+    pool: &PgPool,
+    user_id: Uuid,
+    notify_email: bool,
+    notify_realtime: bool,
+    login_alerts: bool,
+) -> Result<(), AppError> {
+    require_step(pool, user_id, OnboardingStep::Notifications).await?;
+    user_repo::set_login_alerts(pool, user_id, login_alerts).await?;
+    onboarding_repo::set_notification_prefs(
+        pool,
+        user_id,
+        notify_email,
+        notify_realtime,
+        OnboardingStep::Notifications.next(),
+    )
+    .await
+}
+
+/// Record whether the user wants two-factor authentication
+pub async fn choose_two_factor(pool: &PgPool, user_id: Uuid, enable: bool) -> Result<(), AppError> {
+    require_step(pool, user_id, OnboardingStep::TwoFactor).await?;
+    onboarding_repo::set_two_factor_opt_in(pool, user_id, enable, OnboardingStep::TwoFactor.next())
+        .await
+}
+
+/// Make the first deposit and finish onboarding
+pub async fn first_deposit(
+    pool: &PgPool,
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
+    user_id: Uuid,
+    amount: Decimal,
+) -> Result<(), AppError> {
+    require_step(pool, user_id, OnboardingStep::FirstDeposit).await?;
+    wallet_service::deposit(pool, email_service, notification_service, user_id, amount, None).await?;
+    onboarding_repo::set_step(pool, user_id, OnboardingStep::Complete).await
+}
+
+/// Skip the first deposit and finish onboarding
+pub async fn skip_deposit(pool: &PgPool, user_id: Uuid) -> Result<(), AppError> {
+    require_step(pool, user_id, OnboardingStep::FirstDeposit).await?;
+    onboarding_repo::set_step(pool, user_id, OnboardingStep::Complete).await
+}
+
+/// Load progress and make sure the user is on the expected step
+async fn require_step(
+    pool: &PgPool,
+    user_id: Uuid,
+    expected: OnboardingStep,
+) -> Result<OnboardingProgress, AppError> {
+    let progress = onboarding_repo::find_progress(pool, user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Onboarding"))?;
+
+    if progress.step() != expected {
+        return Err(AppError::validation("This onboarding step is not active"));
+    }
+
+    Ok(progress)
+}
+
+/// Random 6-digit code, zero padded
+fn generate_verification_code() -> String {
+    format!("{:06}", OsRng.next_u32() % 1_000_000)
+}
+
+/// Email the code in the background so the request isn't held up by SMTP
+fn send_code(email_service: &EmailService, email: &str, locale: Locale, code: String) {
+    let email_service = email_service.clone();
+    let email = email.to_string();
+    tokio::spawn(async move {
+        email_service.send_verification_code(&email, locale, &code).await;
+    });
+}
@@ -0,0 +1,472 @@
+use crate::domain::models::{
+    ConsentResponse, ConsentStatus, CreateConsentRequest, HistoryQuery,
+    OpenBankingAccountResponse, OpenBankingBalanceResponse, OpenBankingClientResponse,
+    OpenBankingScope, OpenBankingTokenRequest, OpenBankingTokenResponse,
+    RegisterOpenBankingClientRequest, TransactionPage, Wallet,
+};
+use crate::error::AppError;
+use crate::repository::open_banking_repo::{self, Consent};
+use crate::repository::user_repo;
+use crate::services::session_service::random_token;
+use crate::services::wallet_service;
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// OPEN BANKING SERVICE
+// ============================================================================
+// A PSD2-style account information API for third parties, kept apart from
+// the first-party API: it lives under /open-banking, uses its own tokens,
+// and can only ever read.
+//
+// The flow:
+// 1. An admin registers the third party, which gets a client_id and a
+//    client_secret (shown once)
+// 2. The third party trades its credentials for a token
+//    (grant_type=client_credentials) and creates a consent naming the
+//    scopes it wants
+// 3. The user authorises (or rejects) the consent from their own session
+// 4. The third party asks for a token bound to that consent, and reads
+//    only what its scopes allow
+//
+// Why opaque tokens instead of JWTs?
+// - A user revoking a consent must cut off access immediately, so every
+//   request has to hit the database anyway
+// - Tokens are stored hashed, like client secrets
+
+/// How long an access token lives
+pub const TOKEN_LIFETIME_SECS: i64 = 60 * 60;
+
+/// The longest a consent may last (PSD2 asks users to re-confirm every 90 days)
+pub const MAX_CONSENT_DAYS: i64 = 90;
+
+/// The `scope` of a token that can only manage consents
+const CONSENTS_SCOPE: &str = "consents";
+
+/// What an Open Banking access token lets its holder do
+#[derive(Debug, Clone)]
+pub struct OpenBankingAccess {
+    /// The client's row id
+    pub client_id: Uuid,
+    /// None for consent-management tokens
+    pub consent_id: Option<Uuid>,
+    user_id: Option<Uuid>,
+    scopes: Vec<OpenBankingScope>,
+}
+
+impl OpenBankingAccess {
+    /// The consenting user, if this token carries `scope` (403 otherwise)
+    pub fn require(&self, scope: OpenBankingScope) -> Result<Uuid, AppError> {
+        match self.user_id {
+            Some(user_id) if self.scopes.contains(&scope) => Ok(user_id),
+            _ => Err(AppError::Unauthorized),
+        }
+    }
+
+    /// Fail unless this is a consent-management token
+    fn require_consent_management(&self) -> Result<(), AppError> {
+        if self.consent_id.is_some() {
+            return Err(AppError::Unauthorized);
+        }
+        Ok(())
+    }
+}
+
+/// Register a third party and hand out its credentials
+pub async fn register_client(
+    pool: &PgPool,
+    req: &RegisterOpenBankingClientRequest,
+) -> Result<OpenBankingClientResponse, AppError> {
+    let client_id = format!("ob_{}", &random_token()[..24]);
+    let client_secret = random_token();
+    let scopes = scope_names(&req.scopes);
+
+    let client = open_banking_repo::create_client(
+        pool,
+        req.name.trim(),
+        &client_id,
+        &hash(&client_secret),
+        &scopes,
+    )
+    .await?;
+
+    Ok(OpenBankingClientResponse {
+        id: client.id,
+        name: client.name,
+        client_id: client.client_id,
+        client_secret: Some(client_secret),
+        scopes: parse_scopes(&client.scopes),
+        created_at: client.created_at,
+    })
+}
+
+/// Revoke a third party; its tokens stop working at once
+pub async fn revoke_client(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+    if !open_banking_repo::revoke_client(pool, id).await? {
+        return Err(AppError::not_found("Open Banking client"));
+    }
+    Ok(())
+}
+
+/// OAuth client credentials grant
+///
+/// Without `consent_id` the token can only manage the client's consents.
+/// With one, the consent must be this client's, AUTHORISED and unexpired,
+/// and the token gets the requested scopes (default: all the consent's).
+pub async fn issue_token(
+    pool: &PgPool,
+    req: &OpenBankingTokenRequest,
+) -> Result<OpenBankingTokenResponse, AppError> {
+    if req.grant_type != "client_credentials" {
+        return Err(AppError::validation("grant_type must be client_credentials"));
+    }
+
+    let client = open_banking_repo::find_client_credentials(pool, &req.client_id)
+        .await?
+        .filter(|client| client.client_secret_hash == hash(&req.client_secret))
+        .ok_or(AppError::InvalidCredentials)?;
+
+    let requested = req.scope.as_deref().map(str::split_whitespace);
+    let scopes = match req.consent_id {
+        None => {
+            if let Some(scope) = requested.into_iter().flatten().find(|s| *s != CONSENTS_SCOPE) {
+                return Err(AppError::validation(&format!(
+                    "Scope '{}' needs a consent_id",
+                    scope
+                )));
+            }
+            Vec::new()
+        }
+        Some(consent_id) => {
+            let consent = open_banking_repo::find_consent(pool, consent_id)
+                .await?
+                .filter(|consent| consent.client_id == client.id)
+                .ok_or_else(|| AppError::not_found("Consent"))?;
+            if status(&consent) != ConsentStatus::Authorised {
+                return Err(AppError::validation("Consent is not authorised"));
+            }
+
+            let granted = parse_scopes(&consent.scopes);
+            match requested {
+                None => granted,
+                Some(requested) => {
+                    let mut scopes = Vec::new();
+                    for name in requested {
+                        let scope = OpenBankingScope::parse(name)
+                            .filter(|scope| granted.contains(scope))
+                            .ok_or_else(|| {
+                                AppError::validation(&format!(
+                                    "Scope '{}' is not covered by the consent",
+                                    name
+                                ))
+                            })?;
+                        if !scopes.contains(&scope) {
+                            scopes.push(scope);
+                        }
+                    }
+                    scopes
+                }
+            }
+        }
+    };
+
+    let token = random_token();
+    let expires_at = Utc::now() + Duration::seconds(TOKEN_LIFETIME_SECS);
+    open_banking_repo::insert_token(
+        pool,
+        &hash(&token),
+        client.id,
+        req.consent_id,
+        &scope_names(&scopes),
+        expires_at,
+    )
+    .await?;
+
+    let scope = if req.consent_id.is_none() {
+        CONSENTS_SCOPE.to_string()
+    } else {
+        scope_names(&scopes).join(" ")
+    };
+
+    Ok(OpenBankingTokenResponse {
+        access_token: token,
+        token_type: "Bearer",
+        expires_in: TOKEN_LIFETIME_SECS,
+        scope,
+    })
+}
+
+/// Look up a bearer token (InvalidToken if it is unknown, expired or its
+/// client or consent has been revoked)
+pub async fn authenticate(pool: &PgPool, token: &str) -> Result<OpenBankingAccess, AppError> {
+    let grant = open_banking_repo::find_token(pool, &hash(token))
+        .await?
+        .ok_or(AppError::InvalidToken)?;
+
+    Ok(OpenBankingAccess {
+        client_id: grant.client_id,
+        consent_id: grant.consent_id,
+        user_id: grant.user_id,
+        scopes: parse_scopes(&grant.scopes),
+    })
+}
+
+// ============================================================================
+// CONSENTS (THIRD PARTY SIDE)
+// ============================================================================
+
+/// Ask for access; the user still has to authorise it
+pub async fn create_consent(
+    pool: &PgPool,
+    access: &OpenBankingAccess,
+    req: &CreateConsentRequest,
+) -> Result<ConsentResponse, AppError> {
+    access.require_consent_management()?;
+
+    let now = Utc::now();
+    let latest = now + Duration::days(MAX_CONSENT_DAYS);
+    let expires_at = req.expires_at.unwrap_or(latest);
+    if expires_at <= now || expires_at > latest {
+        return Err(AppError::validation(&format!(
+            "expires_at must be within the next {} days",
+            MAX_CONSENT_DAYS
+        )));
+    }
+
+    let client = open_banking_repo::find_client_by_id(pool, access.client_id)
+        .await?
+        .ok_or(AppError::InvalidToken)?;
+    let allowed = parse_scopes(&client.scopes);
+    if let Some(scope) = req.scopes.iter().find(|scope| !allowed.contains(scope)) {
+        return Err(AppError::validation(&format!(
+            "This client may not ask for the '{}' scope",
+            scope
+        )));
+    }
+
+    let mut scopes = req.scopes.clone();
+    scopes.sort_by_key(|scope| OpenBankingScope::ALL.iter().position(|s| s == scope));
+    scopes.dedup();
+
+    let consent =
+        open_banking_repo::create_consent(pool, access.client_id, &scope_names(&scopes), expires_at)
+            .await?;
+
+    Ok(ConsentResponse::from(consent))
+}
+
+/// One of the client's own consents
+pub async fn get_client_consent(
+    pool: &PgPool,
+    access: &OpenBankingAccess,
+    id: Uuid,
+) -> Result<ConsentResponse, AppError> {
+    access.require_consent_management()?;
+
+    let consent = client_consent(pool, access, id).await?;
+    Ok(ConsentResponse::from(consent))
+}
+
+/// Withdraw one of the client's own consents
+pub async fn revoke_client_consent(
+    pool: &PgPool,
+    access: &OpenBankingAccess,
+    id: Uuid,
+) -> Result<(), AppError> {
+    access.require_consent_management()?;
+
+    client_consent(pool, access, id).await?;
+    revoke(pool, id).await
+}
+
+async fn client_consent(
+    pool: &PgPool,
+    access: &OpenBankingAccess,
+    id: Uuid,
+) -> Result<Consent, AppError> {
+    open_banking_repo::find_consent(pool, id)
+        .await?
+        .filter(|consent| consent.client_id == access.client_id)
+        .ok_or_else(|| AppError::not_found("Consent"))
+}
+
+// ============================================================================
+// CONSENTS (USER SIDE)
+// ============================================================================
+
+/// Every consent the user has answered, newest first
+pub async fn list_user_consents(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<ConsentResponse>, AppError> {
+    let consents = open_banking_repo::list_user_consents(pool, user_id).await?;
+    Ok(consents.into_iter().map(ConsentResponse::from).collect())
+}
+
+/// A consent as the user sees it: awaiting ones (so they can decide) and
+/// the ones they answered
+pub async fn get_user_consent(
+    pool: &PgPool,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<ConsentResponse, AppError> {
+    let consent = user_consent(pool, user_id, id).await?;
+    Ok(ConsentResponse::from(consent))
+}
+
+/// Authorise (or reject) an awaiting consent
+pub async fn answer_consent(
+    pool: &PgPool,
+    user_id: Uuid,
+    id: Uuid,
+    authorised: bool,
+) -> Result<ConsentResponse, AppError> {
+    let consent = user_consent(pool, user_id, id).await?;
+    if status(&consent) != ConsentStatus::AwaitingAuthorisation
+        || !open_banking_repo::answer_consent(pool, id, user_id, authorised).await?
+    {
+        return Err(AppError::validation("Consent is no longer awaiting authorisation"));
+    }
+
+    tracing::info!(
+        "🏦 User {} {} consent {} for {}",
+        user_id,
+        if authorised { "authorised" } else { "rejected" },
+        id,
+        consent.client_public_id
+    );
+
+    get_user_consent(pool, user_id, id).await
+}
+
+/// Withdraw a consent the user gave
+pub async fn revoke_user_consent(pool: &PgPool, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    let consent = user_consent(pool, user_id, id).await?;
+    if consent.user_id != Some(user_id) {
+        return Err(AppError::not_found("Consent"));
+    }
+
+    revoke(pool, id).await
+}
+
+async fn user_consent(pool: &PgPool, user_id: Uuid, id: Uuid) -> Result<Consent, AppError> {
+    open_banking_repo::find_consent(pool, id)
+        .await?
+        .filter(|consent| consent.user_id.is_none_or(|owner| owner == user_id))
+        .ok_or_else(|| AppError::not_found("Consent"))
+}
+
+async fn revoke(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+    if !open_banking_repo::revoke_consent(pool, id).await? {
+        return Err(AppError::validation("Consent is already rejected or revoked"));
+    }
+    tracing::info!("🏦 Consent {} revoked", id);
+    Ok(())
+}
+
+// ============================================================================
+// ACCOUNT INFORMATION
+// ============================================================================
+
+/// The accounts the consenting user holds (`accounts` scope)
+pub async fn accounts(
+    pool: &PgPool,
+    access: &OpenBankingAccess,
+) -> Result<Vec<OpenBankingAccountResponse>, AppError> {
+    let user_id = access.require(OpenBankingScope::Accounts)?;
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+
+    Ok(vec![OpenBankingAccountResponse {
+        account_id: wallet.id,
+        currency: wallet.currency,
+    }])
+}
+
+/// One account's balance (`balances` scope)
+pub async fn balance(
+    pool: &PgPool,
+    access: &OpenBankingAccess,
+    account_id: Uuid,
+) -> Result<OpenBankingBalanceResponse, AppError> {
+    let user_id = access.require(OpenBankingScope::Balances)?;
+    let wallet = account(pool, user_id, account_id).await?;
+
+    Ok(OpenBankingBalanceResponse {
+        account_id: wallet.id,
+        balance: wallet.balance,
+        currency: wallet.currency,
+        as_of: Utc::now(),
+    })
+}
+
+/// One page of an account's transactions (`transactions` scope)
+pub async fn transactions(
+    pool: &PgPool,
+    access: &OpenBankingAccess,
+    account_id: Uuid,
+    query: &HistoryQuery,
+) -> Result<TransactionPage, AppError> {
+    let user_id = access.require(OpenBankingScope::Transactions)?;
+    account(pool, user_id, account_id).await?;
+
+    wallet_service::get_history(pool, user_id, query).await
+}
+
+/// The user's wallet, if it is the account asked for
+async fn account(pool: &PgPool, user_id: Uuid, account_id: Uuid) -> Result<Wallet, AppError> {
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+    if wallet.id != account_id {
+        return Err(AppError::not_found("Account"));
+    }
+    Ok(wallet)
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// What a consent's status is right now (awaiting and authorised consents
+/// past their expiry read as EXPIRED)
+fn status(consent: &Consent) -> ConsentStatus {
+    let status = ConsentStatus::parse(&consent.status).unwrap_or(ConsentStatus::Revoked);
+    match status {
+        ConsentStatus::AwaitingAuthorisation | ConsentStatus::Authorised
+            if consent.expires_at <= Utc::now() =>
+        {
+            ConsentStatus::Expired
+        }
+        status => status,
+    }
+}
+
+/// SHA-256 hex, for client secrets and access tokens
+fn hash(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn scope_names(scopes: &[OpenBankingScope]) -> Vec<String> {
+    scopes.iter().map(|scope| scope.as_str().to_string()).collect()
+}
+
+/// Stored scope names back to scopes (unknown names are dropped)
+fn parse_scopes(names: &[String]) -> Vec<OpenBankingScope> {
+    names.iter().filter_map(|name| OpenBankingScope::parse(name)).collect()
+}
+
+impl From<Consent> for ConsentResponse {
+    fn from(consent: Consent) -> Self {
+        ConsentResponse {
+            id: consent.id,
+            status: status(&consent),
+            scopes: parse_scopes(&consent.scopes),
+            client_id: consent.client_public_id,
+            client_name: consent.client_name,
+            expires_at: consent.expires_at,
+            created_at: consent.created_at,
+            authorised_at: consent.authorised_at,
+            revoked_at: consent.revoked_at,
+        }
+    }
+}
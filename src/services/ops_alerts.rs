@@ -0,0 +1,282 @@
+use crate::config::{AlertSeverity, Config};
+use crate::error::AppError;
+use crate::utils::secret::SecretString;
+use std::sync::Arc;
+
+// ============================================================================
+// OPS ALERTS
+// ============================================================================
+// Posts the events someone on call should hear about to chat, behind one
+// trait:
+//
+// - `SlackWebhook` - a Slack incoming webhook (SLACK_ALERT_WEBHOOK_URL)
+// - `DiscordWebhook` - a Discord channel webhook (DISCORD_ALERT_WEBHOOK_URL)
+//
+// Each channel has its own threshold (SLACK_ALERT_SEVERITY,
+// DISCORD_ALERT_SEVERITY): e.g. everything to a Discord ops channel, only
+// `critical` to the Slack channel that pages people.
+//
+// Alerts are fire-and-forget. They go out from a background task, so the
+// code raising one never waits on (or fails because of) a chat service; a
+// webhook that can't be reached is only logged.
+
+/// Chat services that take longer than this are given up on
+const WEBHOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Fields shown per alert; the rest are summarized as "and N more"
+const MAX_FIELDS: usize = 10;
+
+/// One event to tell the on-call people about
+#[derive(Debug, Clone)]
+pub struct OpsAlert {
+    pub severity: AlertSeverity,
+    /// One line, e.g. "3 wallet balances do not match their ledgers"
+    pub title: String,
+    /// Label and value pairs shown under the title
+    pub fields: Vec<(String, String)>,
+}
+
+impl OpsAlert {
+    pub fn new(severity: AlertSeverity, title: impl Into<String>) -> Self {
+        OpsAlert {
+            severity,
+            title: title.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Add a label and value pair
+    pub fn field(mut self, label: impl Into<String>, value: impl ToString) -> Self {
+        self.fields.push((label.into(), value.to_string()));
+        self
+    }
+
+    /// The fields a chat message has room for, plus a count of the rest
+    fn shown_fields(&self) -> (&[(String, String)], usize) {
+        let shown = self.fields.len().min(MAX_FIELDS);
+        (&self.fields[..shown], self.fields.len() - shown)
+    }
+}
+
+/// Somewhere alerts are posted to
+#[async_trait::async_trait]
+pub trait AlertChannel: Send + Sync {
+    /// For logs, e.g. "slack"
+    fn name(&self) -> &'static str;
+
+    /// Post one alert; `environment` is APP_ENV, so staging alerts can be
+    /// told apart from production ones
+    async fn post(&self, alert: &OpsAlert, environment: &str) -> Result<(), AppError>;
+}
+
+/// Every configured channel, each with its threshold
+///
+/// Cheap to clone; with no webhooks configured, sending is a no-op.
+#[derive(Clone)]
+pub struct OpsAlerts {
+    channels: Arc<Vec<(Arc<dyn AlertChannel>, AlertSeverity)>>,
+    environment: String,
+}
+
+impl OpsAlerts {
+    pub fn new(channels: Vec<(Arc<dyn AlertChannel>, AlertSeverity)>, environment: String) -> Self {
+        OpsAlerts {
+            channels: Arc::new(channels),
+            environment,
+        }
+    }
+
+    /// The channels for SLACK_ALERT_* and DISCORD_ALERT_*
+    pub fn from_config(config: &Config) -> Self {
+        let mut channels: Vec<(Arc<dyn AlertChannel>, AlertSeverity)> = Vec::new();
+        if let Some(url) = &config.slack_alert_webhook_url {
+            channels.push((Arc::new(SlackWebhook::new(url.clone())), config.slack_alert_severity));
+        }
+        if let Some(url) = &config.discord_alert_webhook_url {
+            channels.push((
+                Arc::new(DiscordWebhook::new(url.clone())),
+                config.discord_alert_severity,
+            ));
+        }
+
+        OpsAlerts::new(channels, config.app_env.to_string())
+    }
+
+    /// Post an alert to every channel whose threshold it meets, in the
+    /// background
+    pub fn send(&self, alert: OpsAlert) {
+        let channels: Vec<_> = self
+            .channels
+            .iter()
+            .filter(|(_, threshold)| alert.severity >= *threshold)
+            .map(|(channel, _)| channel.clone())
+            .collect();
+        if channels.is_empty() {
+            return;
+        }
+
+        let environment = self.environment.clone();
+        tokio::spawn(async move {
+            for channel in channels {
+                if let Err(e) = channel.post(&alert, &environment).await {
+                    tracing::error!(
+                        "❌ Failed to post ops alert \"{}\" to {}: {}",
+                        alert.title,
+                        channel.name(),
+                        e
+                    );
+                }
+            }
+        });
+    }
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .expect("TLS backend is available")
+}
+
+/// POST a JSON body to a webhook; any 2xx is success
+async fn post_json(
+    http: &reqwest::Client,
+    url: &SecretString,
+    body: &serde_json::Value,
+) -> Result<(), AppError> {
+    // The URL is the webhook's credential: keep it out of error messages
+    http.post(url.expose_secret())
+        .json(body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map(|_| ())
+        .map_err(|e| AppError::internal(&format!("webhook request failed: {}", e.without_url())))
+}
+
+// ============================================================================
+// SLACK
+// ============================================================================
+// Incoming webhooks take a message with one attachment, whose color bar
+// shows the severity:
+//
+//   { "text": "🚨 [production] 3 wallet balances do not match their ledgers",
+//     "attachments": [{ "color": "#d92d20",
+//                       "fields": [{ "title": "Wallet", "value": "...", "short": true }] }] }
+//
+// Slack answers 200 "ok".
+
+/// A Slack incoming webhook
+pub struct SlackWebhook {
+    http: reqwest::Client,
+    url: SecretString,
+}
+
+impl SlackWebhook {
+    pub fn new(url: SecretString) -> Self {
+        SlackWebhook {
+            http: http_client(),
+            url,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl AlertChannel for SlackWebhook {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    async fn post(&self, alert: &OpsAlert, environment: &str) -> Result<(), AppError> {
+        let (shown, hidden) = alert.shown_fields();
+        let mut fields: Vec<_> = shown
+            .iter()
+            .map(|(label, value)| serde_json::json!({ "title": label, "value": value, "short": true }))
+            .collect();
+        if hidden > 0 {
+            fields.push(serde_json::json!({ "value": format!("…and {} more", hidden), "short": false }));
+        }
+
+        let body = serde_json::json!({
+            "text": format!("{} [{}] {}", emoji(alert.severity), environment, alert.title),
+            "attachments": [{
+                "color": format!("#{:06x}", color(alert.severity)),
+                "fields": fields,
+                "footer": format!("{} alert", alert.severity.as_str()),
+            }],
+        });
+
+        post_json(&self.http, &self.url, &body).await
+    }
+}
+
+// ============================================================================
+// DISCORD
+// ============================================================================
+// Channel webhooks take a message with one embed:
+//
+//   { "embeds": [{ "title": "🚨 [production] 3 wallet balances ...",
+//                  "color": 14232864,
+//                  "fields": [{ "name": "Wallet", "value": "...", "inline": true }] }] }
+//
+// Discord answers 204 No Content.
+
+/// A Discord channel webhook
+pub struct DiscordWebhook {
+    http: reqwest::Client,
+    url: SecretString,
+}
+
+impl DiscordWebhook {
+    pub fn new(url: SecretString) -> Self {
+        DiscordWebhook {
+            http: http_client(),
+            url,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl AlertChannel for DiscordWebhook {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn post(&self, alert: &OpsAlert, environment: &str) -> Result<(), AppError> {
+        let (shown, hidden) = alert.shown_fields();
+        let fields: Vec<_> = shown
+            .iter()
+            .map(|(label, value)| serde_json::json!({ "name": label, "value": value, "inline": true }))
+            .collect();
+
+        let mut embed = serde_json::json!({
+            "title": format!("{} [{}] {}", emoji(alert.severity), environment, alert.title),
+            "color": color(alert.severity),
+            "fields": fields,
+            "footer": { "text": format!("{} alert", alert.severity.as_str()) },
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        });
+        if hidden > 0 {
+            embed["description"] = format!("…and {} more", hidden).into();
+        }
+
+        post_json(&self.http, &self.url, &serde_json::json!({ "embeds": [embed] })).await
+    }
+}
+
+fn emoji(severity: AlertSeverity) -> &'static str {
+    match severity {
+        AlertSeverity::Info => "✅",
+        AlertSeverity::Warning => "⚠️",
+        AlertSeverity::Critical => "🚨",
+    }
+}
+
+/// RGB color of the message's side bar
+fn color(severity: AlertSeverity) -> u32 {
+    match severity {
+        AlertSeverity::Info => 0x12b76a,
+        AlertSeverity::Warning => 0xf79009,
+        AlertSeverity::Critical => 0xd92d20,
+    }
+}
@@ -0,0 +1,501 @@
+use crate::domain::models::{
+    CreateOrganizationRequest, Currency, ExpenseRequest, ExpenseStatus, MembershipResponse,
+    Organization, OrganizationDetailsResponse, OrganizationInvitation, OrganizationRole,
+    HistoryQuery, SubmitExpenseRequest, TransactionPage, Wallet, validate_amount,
+};
+use crate::error::AppError;
+use crate::repository::{organization_repo, sso_repo, user_repo};
+use crate::services::clock::Clock;
+use crate::services::email_service::EmailService;
+use crate::services::notification_service::Notifier;
+use crate::services::session_service::random_token;
+use crate::services::wallet_service;
+use chrono::Duration;
+use rust_decimal::Decimal;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// ORGANIZATION SERVICE
+// ============================================================================
+// Business accounts: an organization owns a wallet, and its members act on
+// it according to their role:
+//
+//   OWNER    everything, including inviting, removing and re-roling members
+//   FINANCE  deposits, withdrawals, transfers out, and approving expenses
+//   VIEWER   the balance, history and member list, and asking for expenses
+//
+// Any member can ask for a transfer out as an expense request; an owner or
+// finance member other than the requester approves (which pays it out) or
+// rejects it. Approvers are told about new requests, and requesters about
+// the decision, as live notifications.
+//
+// Whoever creates an organization is its first owner. Owners invite
+// people by email; the invitation carries a one-time token (stored
+// hashed, like Open Banking tokens) that the invitee accepts while signed
+// in with that same email address. There is always at least one owner.
+//
+// To anyone who isn't a member an organization doesn't exist (404), so
+// ids can't be probed; members lacking the role get 403.
+
+/// How long an invitation can be accepted for
+pub const INVITATION_LIFETIME_DAYS: i64 = 7;
+
+/// Start a business account, with `user_id` as its owner
+pub async fn create(
+    pool: &PgPool,
+    user_id: Uuid,
+    req: &CreateOrganizationRequest,
+) -> Result<Organization, AppError> {
+    organization_repo::create_business_account(pool, req.name.trim(), &req.slug, user_id).await
+}
+
+/// The organizations a user belongs to, with their role in each
+pub async fn list_mine(pool: &PgPool, user_id: Uuid) -> Result<Vec<MembershipResponse>, AppError> {
+    let memberships = organization_repo::list_memberships(pool, user_id).await?;
+    Ok(memberships
+        .into_iter()
+        .map(|(organization, role)| MembershipResponse { organization, role })
+        .collect())
+}
+
+/// An organization and its members (members only)
+pub async fn details(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<OrganizationDetailsResponse, AppError> {
+    let role = require_role(pool, organization_id, user_id, |_| true).await?;
+    let organization = sso_repo::find_organization(pool, organization_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Organization"))?;
+    let members = organization_repo::list_members(pool, organization_id).await?;
+
+    Ok(OrganizationDetailsResponse { organization, role, members })
+}
+
+// ============================================================================
+// THE ORGANIZATION'S WALLET
+// ============================================================================
+
+/// The organization's wallet (any member)
+pub async fn wallet(pool: &PgPool, organization_id: Uuid, user_id: Uuid) -> Result<Wallet, AppError> {
+    require_role(pool, organization_id, user_id, |_| true).await?;
+    organization_repo::get_wallet(pool, organization_id).await
+}
+
+/// One page of the organization's history, newest first (any member)
+pub async fn history(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    query: &HistoryQuery,
+) -> Result<TransactionPage, AppError> {
+    let wallet = wallet(pool, organization_id, user_id).await?;
+    wallet_service::history_page(pool, wallet.id, query).await
+}
+
+/// Deposit into the organization's wallet (owners and finance)
+pub async fn deposit(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    amount: Decimal,
+    currency: Option<Currency>,
+) -> Result<Wallet, AppError> {
+    validate_amount(&amount).map_err(|e| AppError::invalid_field("amount", e))?;
+    require_money_mover(pool, organization_id, user_id, currency).await?;
+
+    organization_repo::deposit(pool, organization_id, amount)
+        .await
+        .map_err(wallet_service::insufficient_balance_on_violation)
+}
+
+/// Withdraw from the organization's wallet (owners and finance)
+pub async fn withdraw(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    amount: Decimal,
+    currency: Option<Currency>,
+) -> Result<Wallet, AppError> {
+    validate_amount(&amount).map_err(|e| AppError::invalid_field("amount", e))?;
+    require_money_mover(pool, organization_id, user_id, currency).await?;
+
+    organization_repo::withdraw(pool, organization_id, amount)
+        .await
+        .map_err(wallet_service::insufficient_balance_on_violation)
+}
+
+/// Send money from the organization's wallet to a user (owners and
+/// finance)
+#[allow(clippy::too_many_arguments)]
+pub async fn transfer(
+    pool: &PgPool,
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
+    organization_id: Uuid,
+    user_id: Uuid,
+    recipient_email: &str,
+    amount: Decimal,
+    currency: Option<Currency>,
+) -> Result<Wallet, AppError> {
+    validate_amount(&amount).map_err(|e| AppError::invalid_field("amount", e))?;
+    require_money_mover(pool, organization_id, user_id, currency).await?;
+    let recipient = wallet_service::find_recipient(pool, recipient_email).await?;
+
+    let result = organization_repo::transfer_to_user(pool, organization_id, recipient.id, amount)
+        .await
+        .map_err(wallet_service::insufficient_balance_on_violation)?;
+
+    wallet_service::notify_transfer_received(
+        pool,
+        email_service,
+        notification_service,
+        &recipient,
+        amount,
+        result.recipient_currency,
+        result.recipient_balance,
+    )
+    .await;
+
+    Ok(result.sender_wallet)
+}
+
+/// A member allowed to move money (and not frozen), asking in the
+/// wallet's currency (if they named one)
+async fn require_money_mover(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    currency: Option<Currency>,
+) -> Result<(), AppError> {
+    require_role(pool, organization_id, user_id, OrganizationRole::can_move_money).await?;
+    wallet_service::ensure_can_move_money(pool, user_id).await?;
+    let wallet = organization_repo::get_wallet(pool, organization_id).await?;
+    wallet_service::ensure_wallet_currency(&wallet, currency)
+}
+
+// ============================================================================
+// EXPENSE APPROVAL
+// ============================================================================
+
+/// Expense requests as the approvals page shows them
+pub struct Approvals {
+    /// Pending requests the user may decide, with the organization's name
+    pub waiting: Vec<(String, ExpenseRequest)>,
+    /// The user's own requests, with the organization's name
+    pub mine: Vec<(String, ExpenseRequest)>,
+}
+
+/// Ask for a transfer out of the organization's wallet (any member)
+pub async fn submit_expense(
+    pool: &PgPool,
+    notification_service: &dyn Notifier,
+    organization_id: Uuid,
+    user_id: Uuid,
+    req: &SubmitExpenseRequest,
+) -> Result<ExpenseRequest, AppError> {
+    validate_amount(&req.amount).map_err(|e| AppError::invalid_field("amount", e))?;
+    require_role(pool, organization_id, user_id, |_| true).await?;
+    let recipient = wallet_service::find_recipient(pool, &req.recipient_email).await?;
+
+    let expense = organization_repo::create_expense_request(
+        pool,
+        organization_id,
+        user_id,
+        recipient.id,
+        req.amount,
+        req.description.trim(),
+    )
+    .await?;
+
+    // Everyone who could approve it, except whoever asked
+    let approvers = organization_repo::list_members(pool, organization_id).await?;
+    let payload = serde_json::json!({
+        "type": "expense_submitted",
+        "message": format!("🧾 New expense to approve: ${} for {}", expense.amount, expense.description),
+        "organizationId": organization_id,
+        "expenseId": expense.id,
+        "amount": expense.amount.to_string(),
+    });
+    for approver in approvers
+        .iter()
+        .filter(|m| m.role.can_move_money() && m.user_id != user_id)
+    {
+        notification_service.send_to_user(&approver.user_id, payload.clone()).await;
+    }
+
+    Ok(expense)
+}
+
+/// The organization's expense requests, newest first (any member)
+pub async fn list_expenses(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    status: Option<ExpenseStatus>,
+) -> Result<Vec<ExpenseRequest>, AppError> {
+    require_role(pool, organization_id, user_id, |_| true).await?;
+    organization_repo::list_expense_requests(pool, organization_id, status).await
+}
+
+/// Approve an expense request and pay it out (owners and finance, not the
+/// requester)
+pub async fn approve_expense(
+    pool: &PgPool,
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
+    organization_id: Uuid,
+    user_id: Uuid,
+    expense_id: Uuid,
+    note: Option<&str>,
+) -> Result<ExpenseRequest, AppError> {
+    require_role(pool, organization_id, user_id, OrganizationRole::can_move_money).await?;
+    wallet_service::ensure_can_move_money(pool, user_id).await?;
+    let (expense, transfer) = organization_repo::approve_expense_request(
+        pool,
+        organization_id,
+        expense_id,
+        user_id,
+        note,
+    )
+    .await
+    .map_err(wallet_service::insufficient_balance_on_violation)?;
+
+    let recipient = user_repo::find_user_by_id(pool, expense.recipient_id).await?;
+    wallet_service::notify_transfer_received(
+        pool,
+        email_service,
+        notification_service,
+        &recipient,
+        expense.amount,
+        transfer.recipient_currency,
+        transfer.recipient_balance,
+    )
+    .await;
+    notify_decision(pool, email_service, notification_service, &expense).await?;
+
+    Ok(expense)
+}
+
+/// Reject an expense request (owners and finance, not the requester)
+pub async fn reject_expense(
+    pool: &PgPool,
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
+    organization_id: Uuid,
+    user_id: Uuid,
+    expense_id: Uuid,
+    note: Option<&str>,
+) -> Result<ExpenseRequest, AppError> {
+    require_role(pool, organization_id, user_id, OrganizationRole::can_move_money).await?;
+    let expense =
+        organization_repo::reject_expense_request(pool, organization_id, expense_id, user_id, note)
+            .await?;
+
+    notify_decision(pool, email_service, notification_service, &expense).await?;
+
+    Ok(expense)
+}
+
+/// What's waiting for the user's approval, and their own requests, across
+/// all their organizations
+pub async fn approvals(pool: &PgPool, user_id: Uuid) -> Result<Approvals, AppError> {
+    let mut approvals = Approvals { waiting: Vec::new(), mine: Vec::new() };
+
+    for (organization, role) in organization_repo::list_memberships(pool, user_id).await? {
+        let expenses =
+            organization_repo::list_expense_requests(pool, organization.id, None).await?;
+        for expense in expenses {
+            if expense.requested_by == Some(user_id) {
+                approvals.mine.push((organization.name.clone(), expense));
+            } else if role.can_move_money() && expense.status == ExpenseStatus::Pending {
+                approvals.waiting.push((organization.name.clone(), expense));
+            }
+        }
+    }
+
+    // Oldest waiting first (first come, first served); own requests newest first
+    approvals.waiting.sort_by_key(|(_, e)| e.created_at);
+    approvals.mine.sort_by_key(|(_, e)| std::cmp::Reverse(e.created_at));
+    Ok(approvals)
+}
+
+/// Tell the requester (live, and by email) how their request was decided
+async fn notify_decision(
+    pool: &PgPool,
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
+    expense: &ExpenseRequest,
+) -> Result<(), AppError> {
+    let (Some(requester_id), Some(requester_email)) =
+        (expense.requested_by, expense.requester_email.clone())
+    else {
+        return Ok(());
+    };
+    let approved = expense.status == ExpenseStatus::Approved;
+    let organization = sso_repo::find_organization(pool, expense.organization_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Organization"))?;
+
+    let outcome = if approved { "approved ✅" } else { "rejected ❌" };
+    let payload = serde_json::json!({
+        "type": "expense_decided",
+        "message": format!("🧾 Your expense \"{}\" was {}", expense.description, outcome),
+        "organizationId": expense.organization_id,
+        "expenseId": expense.id,
+        "status": expense.status,
+    });
+    notification_service.send_to_user(&requester_id, payload).await;
+
+    let email_service = email_service.clone();
+    let description = expense.description.clone();
+    let amount = expense.amount;
+    let note = expense.decision_note.clone();
+    tokio::spawn(async move {
+        email_service
+            .send_expense_decision(
+                &requester_email,
+                &organization.name,
+                &description,
+                amount,
+                approved,
+                note.as_deref(),
+            )
+            .await;
+    });
+
+    Ok(())
+}
+
+// ============================================================================
+// MEMBERS AND INVITATIONS
+// ============================================================================
+
+/// Invite someone by email (owners only)
+///
+/// The token is emailed to them and never stored or returned in the clear.
+pub async fn invite(
+    pool: &PgPool,
+    email_service: &EmailService,
+    clock: &dyn Clock,
+    organization_id: Uuid,
+    user_id: Uuid,
+    email: &str,
+    role: OrganizationRole,
+) -> Result<OrganizationInvitation, AppError> {
+    require_role(pool, organization_id, user_id, OrganizationRole::can_manage_members).await?;
+    let organization = details(pool, organization_id, user_id).await?;
+
+    let email = email.trim().to_lowercase();
+    if organization.members.iter().any(|m| m.email.eq_ignore_ascii_case(&email)) {
+        return Err(AppError::validation("This person is already a member"));
+    }
+
+    let token = random_token();
+    let expires_at = clock.now() + Duration::days(INVITATION_LIFETIME_DAYS);
+    let invitation = organization_repo::create_invitation(
+        pool,
+        organization_id,
+        &email,
+        role,
+        user_id,
+        &hash_token(&token),
+        expires_at,
+    )
+    .await?;
+
+    let email_service = email_service.clone();
+    let organization_name = organization.organization.name;
+    tokio::spawn(async move {
+        email_service
+            .send_organization_invitation(&email, &organization_name, role.as_str(), &token)
+            .await;
+    });
+
+    Ok(invitation)
+}
+
+/// Invitations not yet accepted or expired (owners only)
+pub async fn pending_invitations(
+    pool: &PgPool,
+    clock: &dyn Clock,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<Vec<OrganizationInvitation>, AppError> {
+    require_role(pool, organization_id, user_id, OrganizationRole::can_manage_members).await?;
+    organization_repo::list_pending_invitations(pool, organization_id, clock.now()).await
+}
+
+/// Join the organization an invitation is for
+///
+/// Only the invited email address can accept, and only once.
+pub async fn accept(
+    pool: &PgPool,
+    clock: &dyn Clock,
+    user_id: Uuid,
+    user_email: &str,
+    token: &str,
+) -> Result<MembershipResponse, AppError> {
+    let (organization, role) = organization_repo::accept_invitation(
+        pool,
+        &hash_token(token.trim()),
+        user_id,
+        user_email,
+        clock.now(),
+    )
+    .await?;
+
+    Ok(MembershipResponse { organization, role })
+}
+
+/// Change a member's role (owners only; the last owner can't step down)
+pub async fn update_member(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    member_id: Uuid,
+    role: OrganizationRole,
+) -> Result<(), AppError> {
+    require_role(pool, organization_id, user_id, OrganizationRole::can_manage_members).await?;
+    organization_repo::set_role(pool, organization_id, member_id, role).await
+}
+
+/// Remove a member (owners), or leave (anyone); the last owner can't go
+pub async fn remove_member(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    member_id: Uuid,
+) -> Result<(), AppError> {
+    if member_id == user_id {
+        require_role(pool, organization_id, user_id, |_| true).await?;
+    } else {
+        require_role(pool, organization_id, user_id, OrganizationRole::can_manage_members)
+            .await?;
+    }
+    organization_repo::remove_member(pool, organization_id, member_id).await
+}
+
+/// The caller's role, if it passes `allowed`
+///
+/// Non-members get NotFound (the organization isn't theirs to know
+/// about), members without the role get Unauthorized (403).
+async fn require_role(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    allowed: impl Fn(&OrganizationRole) -> bool,
+) -> Result<OrganizationRole, AppError> {
+    match organization_repo::find_role(pool, organization_id, user_id).await? {
+        None => Err(AppError::not_found("Organization")),
+        Some(role) if allowed(&role) => Ok(role),
+        Some(_) => Err(AppError::Unauthorized),
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,85 @@
+use crate::error::AppError;
+use crate::repository::{login_attempt_repo, password_reset_repo, user_repo, UserRepository};
+use crate::services::clock::Clock;
+use crate::services::email_service::EmailService;
+use crate::services::session_service::random_token;
+use crate::services::token_service;
+use crate::utils::jwt::hash_password;
+use chrono::Duration;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+// ============================================================================
+// PASSWORD RESET SERVICE
+// ============================================================================
+// Forgot password:
+// 1. The user gives their email address; if it has an account we email a
+//    link with a one-time token (valid for an hour). The answer is the
+//    same either way, so nobody can probe which addresses have accounts.
+// 2. The link (or the token, for API clients) sets a new password. The
+//    token is used up, and the user is logged out everywhere: whoever
+//    knew the old password shouldn't stay signed in with it.
+
+/// How long an emailed reset token works
+const RESET_TOKEN_LIFETIME_MINUTES: i64 = 60;
+
+/// Email `email` a reset link, if it belongs to an account
+///
+/// `public_url` is where the app is reached (the link points at its
+/// reset page).
+pub async fn forgot(
+    pool: &PgPool,
+    email_service: &EmailService,
+    clock: &dyn Clock,
+    public_url: &str,
+    email: &str,
+) -> Result<(), AppError> {
+    let user = match user_repo::find_user_by_email(pool, email).await {
+        Ok(user) => user,
+        Err(AppError::NotFound(_)) => {
+            tracing::debug!("🔑 Password reset asked for an unknown address");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+
+    let token = random_token();
+    let expires_at = clock.now() + Duration::minutes(RESET_TOKEN_LIFETIME_MINUTES);
+    password_reset_repo::create(pool, user.id, &hash_token(&token), expires_at).await?;
+
+    let link = format!("{}/password/reset?token={}", public_url.trim_end_matches('/'), token);
+    let locale = pool.preferred_locale(user.id).await.unwrap_or_default();
+    let email_service = email_service.clone();
+    tokio::spawn(async move {
+        email_service.send_password_reset(&user.email, locale, &link, &token).await;
+    });
+
+    Ok(())
+}
+
+/// Set a new password with an emailed reset token
+pub async fn reset(
+    pool: &PgPool,
+    clock: &dyn Clock,
+    token: &str,
+    new_password: &str,
+) -> Result<(), AppError> {
+    let password_hash = hash_password(new_password)?;
+
+    let user_id =
+        password_reset_repo::reset_password(pool, &hash_token(token.trim()), &password_hash, clock.now())
+            .await?
+            .ok_or_else(|| AppError::validation("This reset link is invalid or has expired"))?;
+
+    tracing::info!("🔑 User {} reset their password", user_id);
+
+    // A new password also lifts a sign-in lockout
+    let user = user_repo::find_user_by_id(pool, user_id).await?;
+    login_attempt_repo::clear(pool, &user.email).await?;
+
+    token_service::logout_everywhere(pool, clock, user_id).await
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
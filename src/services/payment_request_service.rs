@@ -0,0 +1,200 @@
+use crate::domain::models::{CreatePaymentRequest, PaymentRequestResponse, PaymentRequestStatus};
+use crate::error::AppError;
+use crate::repository::payment_request_repo::{self, PaymentRequest};
+use crate::services::clock::Clock;
+use crate::services::email_service::EmailService;
+use crate::services::exchange_rate_service::ExchangeRateService;
+use crate::services::notification_service::Notifier;
+use crate::services::wallet_service::{self, ConcurrencyPolicy, SpendingLimits};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// PAYMENT REQUEST SERVICE
+// ============================================================================
+// One user asking another for money.
+//
+// 1. POST /requests records the request and tells the payer over their
+//    WebSocket. No money moves.
+// 2. The payer sees it in GET /requests/incoming and either approves it,
+//    which pays it through `wallet_service::transfer` exactly like a
+//    transfer made by hand (limits, conversion, receipts), or declines it.
+// 3. Either way the requester hears about it.
+//
+// Approving marks the request PAID first, so it is paid at most once; if
+// the transfer then fails (not enough money, say) it goes back to PENDING
+// and the payer can try again.
+
+/// Requests shown by GET /requests/incoming and /requests/outgoing
+const LIST_LIMIT: i64 = 100;
+
+/// Ask `payer_email` for money on behalf of `requester_id`
+///
+/// Fails like a transfer to them would if the payer is unknown (or the
+/// requester themselves). Approving pays without the emailed code, so
+/// amounts over `confirm_above` (LIMIT_CONFIRM_ABOVE) can't be asked for.
+pub async fn create(
+    pool: &PgPool,
+    notification_service: &dyn Notifier,
+    confirm_above: Decimal,
+    requester_id: Uuid,
+    req: &CreatePaymentRequest,
+) -> Result<PaymentRequestResponse, AppError> {
+    wallet_service::ensure_no_confirmation_needed(req.amount, confirm_above)?;
+    let payer = wallet_service::find_recipient(pool, &req.payer_email).await?;
+    if payer.id == requester_id {
+        return Err(AppError::validation("Cannot request money from yourself"));
+    }
+
+    let note = req.note.as_deref().map(str::trim).filter(|note| !note.is_empty());
+    let request = payment_request_repo::create(
+        pool,
+        requester_id,
+        payer.id,
+        req.amount,
+        req.currency,
+        note,
+    )
+    .await?;
+    tracing::info!("🙏 Payment request {} of {} created", request.id, request.amount);
+
+    let payload = serde_json::json!({
+        "type": "payment_requested",
+        "message": format!("🙏 {} asked you for {}", request.requester_email, request.amount),
+        "requestId": request.id,
+        "amount": request.amount,
+    });
+    notification_service.send_to_user(&payer.id, payload).await;
+
+    Ok(PaymentRequestResponse::from(request))
+}
+
+/// Requests others made of the user, newest first
+pub async fn incoming(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<PaymentRequestResponse>, AppError> {
+    let requests = payment_request_repo::list_incoming(pool, user_id, LIST_LIMIT).await?;
+    Ok(requests.into_iter().map(PaymentRequestResponse::from).collect())
+}
+
+/// Requests the user made of others, newest first
+pub async fn outgoing(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<PaymentRequestResponse>, AppError> {
+    let requests = payment_request_repo::list_outgoing(pool, user_id, LIST_LIMIT).await?;
+    Ok(requests.into_iter().map(PaymentRequestResponse::from).collect())
+}
+
+/// Pay a request made of `user_id`
+///
+/// Fails like a transfer would (the request stays PENDING then).
+#[allow(clippy::too_many_arguments)]
+pub async fn approve(
+    pool: &PgPool,
+    concurrency: &ConcurrencyPolicy,
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
+    exchange_rates: &ExchangeRateService,
+    limits: &SpendingLimits,
+    clock: &dyn Clock,
+    user_id: Uuid,
+    request_id: Uuid,
+) -> Result<PaymentRequestResponse, AppError> {
+    let request =
+        decide(pool, user_id, request_id, PaymentRequestStatus::Paid, clock).await?;
+
+    if let Err(e) = wallet_service::transfer(
+        pool,
+        concurrency,
+        email_service,
+        notification_service,
+        Some(exchange_rates),
+        limits,
+        clock,
+        user_id,
+        &request.requester_email,
+        request.amount,
+        request.currency,
+    )
+    .await
+    {
+        payment_request_repo::reopen(pool, request.id).await?;
+        return Err(e);
+    }
+    tracing::info!("✅ Payment request {} paid", request.id);
+
+    let payload = serde_json::json!({
+        "type": "payment_request_paid",
+        "message": format!("✅ {} paid your request for {}", request.payer_email, request.amount),
+        "requestId": request.id,
+        "amount": request.amount,
+    });
+    notification_service.send_to_user(&request.requester_id, payload).await;
+
+    Ok(PaymentRequestResponse::from(request))
+}
+
+/// Decline a request made of `user_id`
+pub async fn decline(
+    pool: &PgPool,
+    notification_service: &dyn Notifier,
+    clock: &dyn Clock,
+    user_id: Uuid,
+    request_id: Uuid,
+) -> Result<PaymentRequestResponse, AppError> {
+    let request =
+        decide(pool, user_id, request_id, PaymentRequestStatus::Declined, clock).await?;
+    tracing::info!("❌ Payment request {} declined", request.id);
+
+    let payload = serde_json::json!({
+        "type": "payment_request_declined",
+        "message": format!("❌ {} declined your request for {}", request.payer_email, request.amount),
+        "requestId": request.id,
+        "amount": request.amount,
+    });
+    notification_service.send_to_user(&request.requester_id, payload).await;
+
+    Ok(PaymentRequestResponse::from(request))
+}
+
+/// Answer a PENDING request made of `user_id`
+async fn decide(
+    pool: &PgPool,
+    user_id: Uuid,
+    request_id: Uuid,
+    status: PaymentRequestStatus,
+    clock: &dyn Clock,
+) -> Result<PaymentRequest, AppError> {
+    if let Some(request) =
+        payment_request_repo::decide(pool, user_id, request_id, status.as_str(), clock.now())
+            .await?
+    {
+        return Ok(request);
+    }
+
+    match payment_request_repo::find_incoming(pool, user_id, request_id).await? {
+        Some(_) => Err(AppError::validation("This request has already been answered")),
+        None => Err(AppError::not_found("Payment request")),
+    }
+}
+
+impl From<PaymentRequest> for PaymentRequestResponse {
+    fn from(request: PaymentRequest) -> Self {
+        PaymentRequestResponse {
+            id: request.id,
+            requester_email: request.requester_email,
+            payer_email: request.payer_email,
+            amount: request.amount,
+            currency: request.currency,
+            note: request.note,
+            // Guarded by a CHECK constraint
+            status: PaymentRequestStatus::parse(&request.status)
+                .unwrap_or(PaymentRequestStatus::Pending),
+            created_at: request.created_at,
+            decided_at: request.decided_at,
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use crate::config::{Config, RateLimitStoreKind};
+use crate::error::AppError;
+use crate::services::clock::Clock;
+use axum::http::Method;
+use chrono::{DateTime, Duration, Utc};
+use redis::aio::ConnectionManager;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+// ============================================================================
+// RATE LIMITER
+// ============================================================================
+// Decides whether a client may make another request (see
+// middleware/rate_limit.rs). Requests fall into tiers, each with its own
+// allowance per RATE_LIMIT_WINDOW_SECS:
+// - auth: logins, registrations, password resets; strict, per IP
+// - read: GET and HEAD; lenient, per IP
+// - money: deposits, withdrawals, transfers; per signed-in user, so people
+//   behind one office NAT don't share an allowance
+// - standard: everything else, per IP (RATE_LIMIT_MAX_REQUESTS)
+//
+// Each tier is a fixed window, aligned to the clock (minute 0-1, 1-2, ...
+// for a 60 second window), so every instance agrees on which window a
+// request falls in.
+//
+// The counts live in a `RateLimitStore`, picked by RATE_LIMIT_STORE:
+// - `memory`: in this process; lost on restart, and each instance counts
+//   for itself. Finished windows are swept out so the map doesn't grow.
+// - `redis`: in Redis (REDIS_URL), shared by every instance and kept
+//   across restarts. If Redis can't be reached, requests are let through
+//   (and the failure logged) rather than taking the whole API down.
+//
+// `RateLimiter` itself is a trait too, so tests can use a limiter of their
+// own.
+
+/// How often the memory store drops counts of finished windows
+const SWEEP_EVERY: Duration = Duration::seconds(60);
+
+/// Which allowance a request counts against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitTier {
+    Auth,
+    Read,
+    Money,
+    Standard,
+}
+
+impl RateLimitTier {
+    /// The tier for a request to `path`
+    ///
+    /// Money only applies to signed-in users; a money route called without
+    /// a valid token counts as standard.
+    pub fn classify(method: &Method, path: &str, signed_in: bool) -> Self {
+        if *method == Method::POST && AUTH_PATHS.contains(&path) {
+            RateLimitTier::Auth
+        } else if matches!(*method, Method::GET | Method::HEAD) {
+            RateLimitTier::Read
+        } else if signed_in && is_money_path(path) {
+            RateLimitTier::Money
+        } else {
+            RateLimitTier::Standard
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateLimitTier::Auth => "auth",
+            RateLimitTier::Read => "read",
+            RateLimitTier::Money => "money",
+            RateLimitTier::Standard => "standard",
+        }
+    }
+}
+
+/// Where people sign in or prove who they are (API and web UI)
+const AUTH_PATHS: &[&str] = &[
+    "/api/login",
+    "/api/register",
+    "/api/password/forgot",
+    "/api/password/reset",
+    "/api/account/unlock",
+    "/login",
+    "/register",
+    "/password/forgot",
+    "/password/reset",
+    "/account/unlock",
+    "/open-banking/token",
+];
+
+/// Whether `path` moves money (requests for it that aren't reads)
+fn is_money_path(path: &str) -> bool {
+    const PREFIXES: &[&str] = &[
+        "/api/wallet/",
+        "/api/transfers/",
+        "/api/requests",
+        "/api/crypto/",
+        "/dashboard/deposit",
+        "/dashboard/withdraw",
+        "/dashboard/transfer",
+        "/dashboard/international",
+    ];
+
+    PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+        || (path.starts_with("/api/organizations/") && path.contains("/wallet/"))
+}
+
+/// Requests allowed per window in each tier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RateLimitTiers {
+    pub auth: u32,
+    pub read: u32,
+    pub money: u32,
+    pub standard: u32,
+}
+
+impl RateLimitTiers {
+    /// The same allowance for every tier
+    pub fn uniform(max_requests: u32) -> Self {
+        Self { auth: max_requests, read: max_requests, money: max_requests, standard: max_requests }
+    }
+
+    /// RATE_LIMIT_MAX_REQUESTS and the RATE_LIMIT_*_MAX_REQUESTS tiers
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            auth: config.rate_limit_auth_max_requests,
+            read: config.rate_limit_read_max_requests,
+            money: config.rate_limit_money_max_requests,
+            standard: config.rate_limit_max_requests,
+        }
+    }
+
+    pub fn get(&self, tier: RateLimitTier) -> u32 {
+        match tier {
+            RateLimitTier::Auth => self.auth,
+            RateLimitTier::Read => self.read,
+            RateLimitTier::Money => self.money,
+            RateLimitTier::Standard => self.standard,
+        }
+    }
+}
+
+/// Whether a request may go ahead, and the state of its allowance (sent
+/// back as `X-RateLimit-*` headers)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    /// Requests allowed in the window
+    pub limit: u32,
+    /// Requests left in the window after this one
+    pub remaining: u32,
+    /// Seconds until the window ends and the allowance is back
+    pub reset_after_secs: u64,
+}
+
+/// Counts requests per client
+#[async_trait::async_trait]
+pub trait RateLimiter: Send + Sync {
+    /// Count a `tier` request from `client` (an IP address, or a user for
+    /// the money tier)
+    async fn check(&self, tier: RateLimitTier, client: &str) -> RateLimitDecision;
+}
+
+/// Keeps the per-window request counts
+#[async_trait::async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Count one more request for `key` in window number `window`; returns
+    /// the count in that window so far. The count may be forgotten once
+    /// `ttl` has passed (the window is over by then).
+    async fn increment(&self, key: &str, window: i64, ttl: Duration) -> Result<u64, String>;
+}
+
+/// Each tier's allowance per RATE_LIMIT_WINDOW_SECS per client
+pub struct FixedWindowRateLimiter {
+    tiers: RateLimitTiers,
+    window: Duration,
+    clock: Arc<dyn Clock>,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl FixedWindowRateLimiter {
+    /// A limiter that keeps its counts in memory, with `max_requests` in
+    /// every tier
+    pub fn new(max_requests: u32, window: std::time::Duration, clock: Arc<dyn Clock>) -> Self {
+        let store = Arc::new(MemoryRateLimitStore::new(clock.clone()));
+        Self::with_store(max_requests, window, clock, store)
+    }
+
+    /// A limiter that keeps its counts in `store`, with `max_requests` in
+    /// every tier
+    pub fn with_store(
+        max_requests: u32,
+        window: std::time::Duration,
+        clock: Arc<dyn Clock>,
+        store: Arc<dyn RateLimitStore>,
+    ) -> Self {
+        Self {
+            tiers: RateLimitTiers::uniform(max_requests),
+            // At least a second: windows are numbered in whole seconds
+            window: Duration::from_std(window)
+                .unwrap_or(Duration::MAX)
+                .max(Duration::seconds(1)),
+            clock,
+            store,
+        }
+    }
+
+    /// Use different allowances per tier
+    pub fn with_tiers(mut self, tiers: RateLimitTiers) -> Self {
+        self.tiers = tiers;
+        self
+    }
+
+    /// The limits set by the RATE_LIMIT_*_MAX_REQUESTS tiers and
+    /// RATE_LIMIT_WINDOW_SECS, kept in the RATE_LIMIT_STORE
+    ///
+    /// Fails if REDIS_URL can't be parsed (nothing is connected yet).
+    pub fn from_config(config: &Config, clock: Arc<dyn Clock>) -> Result<Self, AppError> {
+        let store: Arc<dyn RateLimitStore> = match (config.rate_limit_store, &config.redis_url) {
+            (RateLimitStoreKind::Redis, Some(redis_url)) => {
+                Arc::new(RedisRateLimitStore::new(redis_url.expose_secret())?)
+            }
+            // REDIS_URL is required with `redis` (see Config)
+            _ => Arc::new(MemoryRateLimitStore::new(clock.clone())),
+        };
+
+        let limiter = Self::with_store(
+            config.rate_limit_max_requests,
+            std::time::Duration::from_secs(config.rate_limit_window_secs),
+            clock,
+            store,
+        );
+        Ok(limiter.with_tiers(RateLimitTiers::from_config(config)))
+    }
+
+}
+
+#[async_trait::async_trait]
+impl RateLimiter for FixedWindowRateLimiter {
+    async fn check(&self, tier: RateLimitTier, client: &str) -> RateLimitDecision {
+        let limit = self.tiers.get(tier);
+        let window_secs = self.window.num_seconds();
+        let now = self.clock.now().timestamp();
+        let window = now.div_euclid(window_secs);
+        let reset_after_secs = ((window + 1) * window_secs - now).max(0) as u64;
+
+        let key = format!("{}:{}", tier.as_str(), client);
+        let count = match self.store.increment(&key, window, self.window).await {
+            Ok(count) => count,
+            Err(e) => {
+                tracing::warn!("⚠️  Rate limit store unavailable, letting {} through: {}", client, e);
+                0
+            }
+        };
+
+        RateLimitDecision {
+            allowed: count <= u64::from(limit),
+            limit,
+            remaining: u64::from(limit).saturating_sub(count) as u32,
+            reset_after_secs,
+        }
+    }
+}
+
+// ============================================================================
+// STORES
+// ============================================================================
+
+/// Counts in this process
+pub struct MemoryRateLimitStore {
+    clock: Arc<dyn Clock>,
+    counts: Mutex<MemoryCounts>,
+}
+
+struct MemoryCounts {
+    // (Window number, count, forget after) per key
+    by_key: HashMap<String, (i64, u64, DateTime<Utc>)>,
+    next_sweep: DateTime<Utc>,
+}
+
+impl MemoryRateLimitStore {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        let next_sweep = clock.now() + SWEEP_EVERY;
+        Self {
+            clock,
+            counts: Mutex::new(MemoryCounts { by_key: HashMap::new(), next_sweep }),
+        }
+    }
+
+    /// How many keys are being counted
+    pub fn len(&self) -> usize {
+        self.counts.lock().unwrap().by_key.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for MemoryRateLimitStore {
+    async fn increment(&self, key: &str, window: i64, ttl: Duration) -> Result<u64, String> {
+        let now = self.clock.now();
+
+        // Only one request at a time updates the map; the lock is released
+        // at the end of this function
+        let mut counts = self.counts.lock().unwrap();
+        if now >= counts.next_sweep {
+            counts.by_key.retain(|_, (_, _, forget_after)| *forget_after > now);
+            counts.next_sweep = now + SWEEP_EVERY;
+        }
+
+        let forget_after = now + ttl;
+        let (counted_window, count, expires) = counts
+            .by_key
+            .entry(key.to_string())
+            .or_insert((window, 0, forget_after));
+        if *counted_window != window {
+            // A new window, start counting again
+            *counted_window = window;
+            *count = 0;
+        }
+        *count += 1;
+        *expires = forget_after;
+
+        Ok(*count)
+    }
+}
+
+/// Counts in Redis, shared by every instance
+///
+/// Each window is its own key (`rate_limit:{key}:{window}`), set to expire
+/// with the window, so Redis cleans up after us.
+pub struct RedisRateLimitStore {
+    client: redis::Client,
+    // Connected on first use, then reconnects by itself
+    connection: OnceCell<ConnectionManager>,
+}
+
+impl RedisRateLimitStore {
+    pub fn new(redis_url: &str) -> Result<Self, AppError> {
+        let client = redis::Client::open(redis_url)
+            .map_err(|e| AppError::internal(&format!("Invalid REDIS_URL: {}", e)))?;
+
+        Ok(Self { client, connection: OnceCell::new() })
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    async fn increment(&self, key: &str, window: i64, ttl: Duration) -> Result<u64, String> {
+        let mut connection = self
+            .connection
+            .get_or_try_init(|| self.client.get_connection_manager())
+            .await
+            .map_err(|e| e.to_string())?
+            .clone();
+
+        let key = format!("rate_limit:{}:{}", key, window);
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(&key, 1)
+            .expire(&key, ttl.num_seconds().max(1))
+            .ignore()
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(count)
+    }
+}
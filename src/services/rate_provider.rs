@@ -0,0 +1,158 @@
+use crate::config::{Config, RatesProviderKind};
+use crate::domain::models::Currency;
+use crate::error::AppError;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+// ============================================================================
+// EXCHANGE RATE PROVIDERS
+// ============================================================================
+// Where `ExchangeRateService` gets its rates from, behind one trait so the
+// service doesn't care which:
+//
+// - `FrankfurterProvider` - the Frankfurter API, which publishes the
+//   European Central Bank reference rates (once per working day, no key)
+// - `FixedRateProvider` - built-in rates for development, no network
+//
+// Every provider quotes against the US dollar: how many units of each
+// currency one USD buys. Cross rates (EUR -> GBP) are worked out from those.
+//
+// RATES_PROVIDER picks the implementation (see config.rs).
+
+/// Fetches the latest exchange rates
+#[async_trait::async_trait]
+pub trait RateProvider: Send + Sync {
+    /// Shown with the rates, e.g. "frankfurter"
+    fn name(&self) -> &'static str;
+
+    /// How many units of each supported currency one US dollar buys
+    ///
+    /// Currencies the provider doesn't quote are left out.
+    async fn usd_rates(&self) -> Result<HashMap<Currency, Decimal>, AppError>;
+}
+
+/// The provider chosen by RATES_PROVIDER (None when exchange rates are off)
+pub fn from_config(config: &Config) -> Option<Arc<dyn RateProvider>> {
+    match config.rates_provider {
+        RatesProviderKind::Frankfurter => {
+            Some(Arc::new(FrankfurterProvider::new(config.rates_url.clone())))
+        }
+        RatesProviderKind::Fixed => Some(Arc::new(FixedRateProvider)),
+        RatesProviderKind::Off => None,
+    }
+}
+
+// ============================================================================
+// FRANKFURTER
+// ============================================================================
+// GET {RATES_URL}/latest?from=USD&to=EUR,GBP,...
+//
+//   { "amount": 1.0, "base": "USD", "date": "2024-05-17",
+//     "rates": { "EUR": 0.9201, "GBP": 0.78933, ... } }
+
+/// Frankfurter API client
+pub struct FrankfurterProvider {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+/// Don't hold a refresh up for longer than this
+const FRANKFURTER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Debug, Deserialize)]
+struct FrankfurterRates {
+    rates: HashMap<String, serde_json::Number>,
+}
+
+impl FrankfurterProvider {
+    pub fn new(base_url: String) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(FRANKFURTER_TIMEOUT)
+            .build()
+            .expect("TLS backend is available");
+
+        FrankfurterProvider {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateProvider for FrankfurterProvider {
+    fn name(&self) -> &'static str {
+        "frankfurter"
+    }
+
+    async fn usd_rates(&self) -> Result<HashMap<Currency, Decimal>, AppError> {
+        let wanted: Vec<&str> = Currency::ALL
+            .iter()
+            .filter(|currency| **currency != Currency::Usd)
+            .map(|currency| currency.as_str())
+            .collect();
+
+        let response = self
+            .http
+            .get(format!("{}/latest", self.base_url))
+            .query(&[("from", "USD"), ("to", &wanted.join(","))])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| {
+                tracing::error!("Exchange rate request failed: {}", e);
+                AppError::internal("Exchange rate provider is unreachable")
+            })?;
+
+        let body: FrankfurterRates = response.json().await.map_err(|e| {
+            tracing::error!("Unexpected exchange rate response: {}", e);
+            AppError::internal("Unexpected response from exchange rate provider")
+        })?;
+
+        // The JSON numbers are read through their text, so 0.9201 stays
+        // exactly 0.9201 instead of going through f64
+        let mut rates = HashMap::new();
+        for currency in Currency::ALL {
+            let quoted = body
+                .rates
+                .get(currency.as_str())
+                .and_then(|rate| Decimal::from_str(&rate.to_string()).ok())
+                .filter(|rate| rate.is_sign_positive() && !rate.is_zero());
+            if let Some(rate) = quoted {
+                rates.insert(currency, rate);
+            }
+        }
+
+        Ok(rates)
+    }
+}
+
+// ============================================================================
+// FIXED RATES
+// ============================================================================
+// Round, roughly realistic numbers for development (RATES_PROVIDER=fixed,
+// never allowed in production). They never change.
+
+/// Offline rate provider for development
+pub struct FixedRateProvider;
+
+#[async_trait::async_trait]
+impl RateProvider for FixedRateProvider {
+    fn name(&self) -> &'static str {
+        "fixed"
+    }
+
+    async fn usd_rates(&self) -> Result<HashMap<Currency, Decimal>, AppError> {
+        Ok(HashMap::from([
+            (Currency::Eur, Decimal::new(92, 2)),
+            (Currency::Gbp, Decimal::new(79, 2)),
+            (Currency::Jpy, Decimal::new(150, 0)),
+            (Currency::Chf, Decimal::new(88, 2)),
+            (Currency::Cad, Decimal::new(137, 2)),
+            (Currency::Aud, Decimal::new(152, 2)),
+            (Currency::Inr, Decimal::new(8350, 2)),
+        ]))
+    }
+}
@@ -0,0 +1,332 @@
+use crate::config::Config;
+use crate::domain::models::{Referral, ReferralOverview, ReferralResponse, ReferralStatus};
+use crate::error::AppError;
+use crate::repository::referral_repo;
+use crate::services::clock::Clock;
+use crate::services::notification_service::Notifier;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+// ============================================================================
+// REFERRAL SERVICE
+// ============================================================================
+// Every user gets a code to share (made the first time they look at it).
+// Signing up with someone's code records a referral; once the new user
+// deposits at least REFERRAL_MIN_DEPOSIT in one go, within
+// REFERRAL_WINDOW_DAYS of signing up, both get their bonus credited as a
+// "Referral bonus" deposit. Nobody earns more than REFERRAL_MAX_REWARDS
+// referrer bonuses. With both bonuses at 0 there is no referral program.
+//
+// Deposits through the wallet pay out straight away; bank and card
+// deposits settle later and are picked up by the daily maintenance job,
+// which also expires referrals whose window has passed.
+//
+// Anti-abuse: a signup is recorded as REJECTED (and never pays) when
+// - it's the referrer's own address with a +tag (alice+2@example.com), or
+// - it comes from an IP address the referrer has signed in from, or
+// - another of the referrer's referees already signed up from that address.
+// A failed check never fails the signup itself.
+
+/// Characters of a referral code (no 0/O or 1/I to mix up)
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Length of a referral code
+const CODE_LENGTH: usize = 8;
+
+/// Tries at a code nobody has before giving up
+const MAX_CODE_ATTEMPTS: u32 = 5;
+
+/// The referral program's terms (REFERRAL_*)
+#[derive(Debug, Clone, Copy)]
+pub struct ReferralTerms {
+    pub referrer_reward: Decimal,
+    pub referee_reward: Decimal,
+    pub min_deposit: Decimal,
+    pub max_rewards: u32,
+    pub window_days: u32,
+}
+
+impl ReferralTerms {
+    pub fn from_config(config: &Config) -> Self {
+        ReferralTerms {
+            referrer_reward: config.referral_referrer_reward,
+            referee_reward: config.referral_referee_reward,
+            min_deposit: config.referral_min_deposit,
+            max_rewards: config.referral_max_rewards,
+            window_days: config.referral_window_days,
+        }
+    }
+
+    /// Whether there is a referral program at all
+    pub fn enabled(&self) -> bool {
+        self.referrer_reward > Decimal::ZERO || self.referee_reward > Decimal::ZERO
+    }
+
+    /// Referrals made before this have run out of time
+    fn window_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - Duration::days(i64::from(self.window_days))
+    }
+}
+
+/// The owner of a referral code, found at signup
+#[derive(Debug, Clone)]
+pub struct Referrer {
+    pub user_id: Uuid,
+    pub email: String,
+    pub code: String,
+}
+
+/// A user's referral code, made on first use
+pub async fn code_for(pool: &PgPool, user_id: Uuid) -> Result<String, AppError> {
+    for _ in 0..MAX_CODE_ATTEMPTS {
+        if let Some(code) = referral_repo::find_code(pool, user_id).await? {
+            return Ok(code);
+        }
+        // Loses (and retries) only if someone else has the code already
+        referral_repo::insert_code(pool, user_id, &random_code()).await?;
+    }
+
+    referral_repo::find_code(pool, user_id)
+        .await?
+        .ok_or_else(|| AppError::internal("Could not pick a unique referral code"))
+}
+
+fn random_code() -> String {
+    let mut bytes = [0u8; CODE_LENGTH];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+        .iter()
+        .map(|b| CODE_ALPHABET[*b as usize % CODE_ALPHABET.len()] as char)
+        .collect()
+}
+
+/// Look up the referral code given at signup
+///
+/// None when no code was given (or there's no referral program); an
+/// unknown code is a validation error, so the user can fix a typo.
+pub async fn find_referrer(
+    pool: &PgPool,
+    terms: &ReferralTerms,
+    code: Option<&str>,
+) -> Result<Option<Referrer>, AppError> {
+    let Some(code) = code.map(str::trim).filter(|code| !code.is_empty()) else {
+        return Ok(None);
+    };
+    if !terms.enabled() {
+        return Ok(None);
+    }
+
+    let code = code.to_ascii_uppercase();
+    let (user_id, email) = referral_repo::find_code_owner(pool, &code)
+        .await?
+        .ok_or_else(|| AppError::validation("Unknown referral code"))?;
+
+    Ok(Some(Referrer { user_id, email, code }))
+}
+
+/// Record that a new user signed up with `referrer`'s code
+///
+/// Never fails the signup: problems are logged.
+pub async fn attribute(
+    pool: &PgPool,
+    clock: &dyn Clock,
+    referrer: &Referrer,
+    referee_id: Uuid,
+    referee_email: &str,
+    ip: IpAddr,
+) {
+    if let Err(e) = record_referral(pool, clock, referrer, referee_id, referee_email, ip).await {
+        tracing::warn!(
+            "⚠️ Failed to record referral of user {} by {}: {}",
+            referee_id,
+            referrer.user_id,
+            e
+        );
+    }
+}
+
+async fn record_referral(
+    pool: &PgPool,
+    clock: &dyn Clock,
+    referrer: &Referrer,
+    referee_id: Uuid,
+    referee_email: &str,
+    ip: IpAddr,
+) -> Result<(), AppError> {
+    let ip = ip.to_string();
+
+    let rejected_reason = if same_mailbox(&referrer.email, referee_email) {
+        Some("Same email address as the referrer")
+    } else if referral_repo::has_signed_in_from(pool, referrer.user_id, &ip).await? {
+        Some("Signed up from the referrer's network")
+    } else if referral_repo::has_referral_from(pool, referrer.user_id, &ip).await? {
+        Some("Another referral signed up from this network")
+    } else {
+        None
+    };
+
+    if let Some(reason) = rejected_reason {
+        tracing::info!("🚫 Referral of user {} rejected: {}", referee_id, reason);
+    }
+
+    referral_repo::create_referral(
+        pool,
+        referrer.user_id,
+        referee_id,
+        &referrer.code,
+        &ip,
+        rejected_reason,
+        clock.now(),
+    )
+    .await
+}
+
+/// Whether two addresses reach the same mailbox, ignoring case and
+/// +tags (alice+2@example.com is alice@example.com)
+fn same_mailbox(a: &str, b: &str) -> bool {
+    fn normalize(email: &str) -> Option<(String, String)> {
+        let (local, domain) = email.rsplit_once('@')?;
+        let local = local.split('+').next().unwrap_or(local);
+        Some((local.to_lowercase(), domain.to_lowercase()))
+    }
+
+    match (normalize(a), normalize(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Pay the bonuses if `user_id` was referred and has just qualified
+///
+/// Called after a deposit; never fails it (problems are logged).
+pub async fn reward_if_qualified(
+    pool: &PgPool,
+    terms: &ReferralTerms,
+    clock: &dyn Clock,
+    notifier: &dyn Notifier,
+    user_id: Uuid,
+) {
+    if !terms.enabled() {
+        return;
+    }
+
+    let now = clock.now();
+    let result = async {
+        let qualified =
+            referral_repo::find_qualified(pool, terms.min_deposit, terms.window_start(now), Some(user_id))
+                .await?;
+        for id in qualified {
+            if let Some(referral) = pay(pool, terms, id, now).await? {
+                notify_rewarded(notifier, &referral).await;
+            }
+        }
+        Ok::<_, AppError>(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        tracing::error!("❌ Failed to pay referral bonus for user {}: {}", user_id, e);
+    }
+}
+
+/// Pay every referral that has qualified, and expire the ones whose
+/// window has passed (the maintenance job)
+///
+/// Returns how many were rewarded and how many expired.
+pub async fn sweep(pool: &PgPool, terms: &ReferralTerms, now: DateTime<Utc>) -> Result<(usize, u64), AppError> {
+    let mut rewarded = 0;
+    if terms.enabled() {
+        let window_start = terms.window_start(now);
+        for id in referral_repo::find_qualified(pool, terms.min_deposit, window_start, None).await? {
+            if pay(pool, terms, id, now).await?.is_some() {
+                rewarded += 1;
+            }
+        }
+    }
+
+    let expired = referral_repo::expire_pending(pool, terms.window_start(now)).await?;
+    Ok((rewarded, expired))
+}
+
+/// Pay a qualified referral; Some if both sides were paid
+async fn pay(
+    pool: &PgPool,
+    terms: &ReferralTerms,
+    id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Option<Referral>, AppError> {
+    let referral = referral_repo::reward(
+        pool,
+        id,
+        terms.referrer_reward,
+        terms.referee_reward,
+        terms.max_rewards,
+        now,
+    )
+    .await?;
+
+    Ok(referral.filter(|referral| {
+        if referral.status != ReferralStatus::Rewarded {
+            tracing::info!(
+                "🚫 Referral {} not paid: {}",
+                referral.id,
+                referral.rejected_reason.as_deref().unwrap_or("unknown reason")
+            );
+        }
+        referral.status == ReferralStatus::Rewarded
+    }))
+}
+
+/// Tell both sides about their bonus
+async fn notify_rewarded(notifier: &dyn Notifier, referral: &Referral) {
+    for (user_id, amount) in [
+        (referral.referrer_id, referral.referrer_reward),
+        (referral.referee_id, referral.referee_reward),
+    ] {
+        let Some(amount) = amount.filter(|amount| *amount > Decimal::ZERO) else {
+            continue;
+        };
+        notifier
+            .send_to_user(
+                &user_id,
+                serde_json::json!({
+                    "type": "referral_rewarded",
+                    "message": format!("You earned a ${} referral bonus", amount),
+                    "amount": amount,
+                }),
+            )
+            .await;
+    }
+}
+
+/// My code and link, the terms, and the signups made with my code
+pub async fn overview(
+    pool: &PgPool,
+    config: &Config,
+    user_id: Uuid,
+) -> Result<ReferralOverview, AppError> {
+    let terms = ReferralTerms::from_config(config);
+    if !terms.enabled() {
+        return Err(AppError::feature_disabled("Referrals"));
+    }
+
+    let code = code_for(pool, user_id).await?;
+    let referrals = referral_repo::list_for_referrer(pool, user_id).await?;
+    let total_earned = referral_repo::total_earned(pool, user_id).await?;
+
+    Ok(ReferralOverview {
+        link: format!("{}/register?ref={}", config.public_url().trim_end_matches('/'), code),
+        code,
+        referrer_reward: terms.referrer_reward,
+        referee_reward: terms.referee_reward,
+        min_deposit: terms.min_deposit,
+        window_days: terms.window_days,
+        max_rewards: terms.max_rewards,
+        total_earned,
+        referrals: referrals.into_iter().map(ReferralResponse::from).collect(),
+    })
+}
@@ -0,0 +1,289 @@
+use crate::domain::models::{
+    ScheduleTransferRequest, ScheduledTransferResponse, ScheduledTransferStatus,
+};
+use crate::error::AppError;
+use crate::config::FeatureFlags;
+use crate::repository::scheduled_transfer_repo::{self, ScheduledTransfer};
+use crate::repository::ScheduledRun;
+use crate::services::clock::Clock;
+use crate::services::email_service::EmailService;
+use crate::services::exchange_rate_service::ExchangeRateService;
+use crate::services::notification_service::Notifier;
+use crate::services::wallet_service::{self, ConcurrencyPolicy, SpendingLimits};
+use crate::shutdown::Shutdown;
+use chrono::Duration;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// ============================================================================
+// SCHEDULED TRANSFER SERVICE
+// ============================================================================
+// Transfers to another user at a later time.
+//
+// 1. POST /transfers/schedule records what to send and when. The recipient,
+//    the paying wallet and the amount (nothing that would need the emailed
+//    code) are checked then, but no money moves.
+// 2. A background task (`spawn`) wakes up every minute and runs the
+//    transfers that have fallen due through `wallet_service::run_scheduled`,
+//    so they behave exactly like transfers made by hand (limits, conversion,
+//    receipts). It marks the transfer COMPLETED in the same database
+//    transaction as the money moving, so it is never paid twice.
+// 3. A failed attempt - most often not enough money yet - is recorded on
+//    the transfer and retried later, waiting a little longer each time.
+//    After MAX_ATTEMPTS the transfer is FAILED.
+//
+// Until a transfer has run, DELETE /transfers/scheduled/:id cancels it.
+
+/// How far ahead a transfer may be scheduled
+const MAX_SCHEDULE_AHEAD: Duration = Duration::days(366);
+
+/// Transfers shown by GET /transfers/scheduled
+const LIST_LIMIT: i64 = 100;
+
+/// How often the background task looks for due transfers
+const RUN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Due transfers run per pass
+const BATCH_SIZE: i64 = 100;
+
+/// How long a claimed transfer is left alone before another pass may
+/// pick it up again (an attempt takes far less)
+const CLAIM_FOR: Duration = Duration::minutes(10);
+
+/// Attempts before a transfer is given up
+const MAX_ATTEMPTS: i32 = 5;
+
+/// The wait before the first retry; each further one waits this much longer
+const RETRY_DELAY: Duration = Duration::hours(1);
+
+/// Schedule a transfer from `user_id`
+///
+/// Fails like a transfer would if the recipient is unknown (or the sender
+/// themselves), or the sender has no wallet in `currency`. Amounts over
+/// `confirm_above` (LIMIT_CONFIRM_ABOVE) are refused: nobody would be
+/// there to confirm them when they run.
+pub async fn schedule(
+    pool: &PgPool,
+    clock: &dyn Clock,
+    confirm_above: Decimal,
+    user_id: Uuid,
+    req: &ScheduleTransferRequest,
+) -> Result<ScheduledTransferResponse, AppError> {
+    wallet_service::ensure_no_confirmation_needed(req.amount, confirm_above)?;
+    let now = clock.now();
+    if req.execute_at <= now {
+        return Err(AppError::validation("execute_at must be in the future"));
+    }
+    if req.execute_at > now + MAX_SCHEDULE_AHEAD {
+        return Err(AppError::validation(
+            "Transfers can be scheduled at most a year ahead",
+        ));
+    }
+
+    let recipient = wallet_service::find_recipient(pool, &req.recipient_email).await?;
+    if recipient.id == user_id {
+        return Err(AppError::validation("Cannot transfer money to yourself"));
+    }
+    wallet_service::ensure_currency(pool, user_id, req.currency).await?;
+
+    let transfer = scheduled_transfer_repo::create(
+        pool,
+        user_id,
+        &recipient.email,
+        req.amount,
+        req.currency,
+        req.execute_at,
+    )
+    .await?;
+
+    tracing::info!(
+        "🗓️ Transfer {} of {} scheduled for {}",
+        transfer.id,
+        transfer.amount,
+        transfer.execute_at
+    );
+    Ok(ScheduledTransferResponse::from(transfer))
+}
+
+/// The user's scheduled transfers (including ones that ran, failed or were
+/// cancelled), in the order they run
+pub async fn list(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<ScheduledTransferResponse>, AppError> {
+    let transfers = scheduled_transfer_repo::list_for_user(pool, user_id, LIST_LIMIT).await?;
+    Ok(transfers.into_iter().map(ScheduledTransferResponse::from).collect())
+}
+
+/// Cancel one of the user's transfers before it runs
+pub async fn cancel(
+    pool: &PgPool,
+    clock: &dyn Clock,
+    user_id: Uuid,
+    transfer_id: Uuid,
+) -> Result<(), AppError> {
+    if scheduled_transfer_repo::cancel(pool, user_id, transfer_id, clock.now())
+        .await?
+        .is_some()
+    {
+        return Ok(());
+    }
+
+    match scheduled_transfer_repo::find(pool, user_id, transfer_id).await? {
+        Some(_) => Err(AppError::validation(
+            "Only transfers that haven't run yet can be cancelled",
+        )),
+        None => Err(AppError::not_found("Scheduled transfer")),
+    }
+}
+
+/// What one pass of the background task did
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RunSummary {
+    pub completed: usize,
+    /// Failed attempts that will be retried
+    pub retrying: usize,
+    /// Transfers given up on this pass
+    pub failed: usize,
+}
+
+/// Run every transfer that is due
+///
+/// Does nothing while transfers are paused (FEATURE_TRANSFERS_ENABLED off):
+/// due transfers wait, unclaimed, until they're back on.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_due(
+    pool: &PgPool,
+    concurrency: &ConcurrencyPolicy,
+    email_service: &EmailService,
+    notifier: &dyn Notifier,
+    exchange_rates: &ExchangeRateService,
+    limits: &SpendingLimits,
+    features: &FeatureFlags,
+    clock: &dyn Clock,
+) -> Result<RunSummary, AppError> {
+    if !features.transfers_enabled {
+        return Ok(RunSummary::default());
+    }
+    let now = clock.now();
+    let due = scheduled_transfer_repo::claim_due(pool, now, now + CLAIM_FOR, BATCH_SIZE).await?;
+
+    let mut summary = RunSummary::default();
+    for transfer in due {
+        let run = ScheduledRun { transfer_id: transfer.id, at: clock.now() };
+        let result = wallet_service::run_scheduled(
+            pool,
+            concurrency,
+            email_service,
+            notifier,
+            Some(exchange_rates),
+            limits,
+            clock,
+            run,
+            transfer.user_id,
+            &transfer.recipient_email,
+            transfer.amount,
+            transfer.currency,
+        )
+        .await;
+
+        match result {
+            Ok(_) => summary.completed += 1,
+            Err(e) => {
+                let attempts = transfer.attempts + 1;
+                let retry_at = (attempts < MAX_ATTEMPTS).then(|| now + RETRY_DELAY * attempts);
+                tracing::warn!(
+                    "⚠️ Scheduled transfer {} failed (attempt {}): {}",
+                    transfer.id,
+                    attempts,
+                    e
+                );
+
+                scheduled_transfer_repo::record_failure(pool, transfer.id, &e.to_string(), retry_at)
+                    .await?;
+                match retry_at {
+                    Some(_) => summary.retrying += 1,
+                    None => summary.failed += 1,
+                }
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Run due transfers now and then every minute, in the background, until
+/// shutdown
+#[allow(clippy::too_many_arguments)]
+pub fn spawn(
+    shutdown: &Shutdown,
+    pool: PgPool,
+    concurrency: ConcurrencyPolicy,
+    email_service: EmailService,
+    notifier: Arc<dyn Notifier>,
+    exchange_rates: ExchangeRateService,
+    limits: SpendingLimits,
+    features: FeatureFlags,
+    clock: Arc<dyn Clock>,
+) {
+    let stopped = shutdown.clone();
+    shutdown.spawn(async move {
+        let mut interval = tokio::time::interval(RUN_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = stopped.stopped() => break,
+            }
+
+            let result = run_due(
+                &pool,
+                &concurrency,
+                &email_service,
+                notifier.as_ref(),
+                &exchange_rates,
+                &limits,
+                &features,
+                clock.as_ref(),
+            )
+            .await;
+            match result {
+                Ok(RunSummary { completed: 0, retrying: 0, failed: 0 }) => {
+                    tracing::debug!("🗓️ No scheduled transfers due")
+                }
+                Ok(summary) => tracing::info!(
+                    "🗓️ Scheduled transfers: {} completed, {} to retry, {} failed",
+                    summary.completed,
+                    summary.retrying,
+                    summary.failed
+                ),
+                Err(e) => tracing::error!("❌ Failed to run scheduled transfers: {}", e),
+            }
+        }
+    });
+}
+
+impl From<ScheduledTransfer> for ScheduledTransferResponse {
+    fn from(transfer: ScheduledTransfer) -> Self {
+        // Guarded by a CHECK constraint
+        let status = ScheduledTransferStatus::parse(&transfer.status)
+            .unwrap_or(ScheduledTransferStatus::Scheduled);
+
+        ScheduledTransferResponse {
+            id: transfer.id,
+            recipient_email: transfer.recipient_email,
+            amount: transfer.amount,
+            currency: transfer.currency,
+            execute_at: transfer.execute_at,
+            status,
+            attempts: transfer.attempts,
+            next_attempt_at: (status == ScheduledTransferStatus::Scheduled)
+                .then_some(transfer.next_attempt_at),
+            last_error: transfer.last_error,
+            created_at: transfer.created_at,
+            completed_at: transfer.completed_at,
+            cancelled_at: transfer.cancelled_at,
+        }
+    }
+}
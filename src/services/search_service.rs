@@ -0,0 +1,78 @@
+use crate::domain::models::{
+    Transaction, TransactionResponse, TransactionSearchResult, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+};
+use crate::error::AppError;
+use crate::repository::search_repo::{self, OwnedTransactionMatch, TransactionMatch};
+use crate::repository::user_repo;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// SEARCH SERVICE
+// ============================================================================
+// Full-text search over transaction descriptions (see search_repo):
+// - users search their own history: GET /api/transactions/search?q=...
+// - admins search everyone's, e.g. to find every "Refund" this week:
+//   GET /api/admin/transactions/search?q=...
+//
+// One page of the best matches; no cursor, since results are ordered by
+// relevance rather than time.
+
+/// Search the user's own transactions, best matches first
+pub async fn search_my_transactions(
+    pool: &PgPool,
+    user_id: Uuid,
+    query: &str,
+    limit: Option<u32>,
+) -> Result<Vec<TransactionSearchResult>, AppError> {
+    let query = search_terms(query)?;
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+
+    let matches =
+        search_repo::search_wallet_transactions(pool, wallet.id, query, page_size(limit)).await?;
+
+    Ok(matches.into_iter().map(TransactionSearchResult::from).collect())
+}
+
+/// Search every wallet's transactions (admins only), best matches first
+pub async fn search_all_transactions(
+    pool: &PgPool,
+    query: &str,
+    limit: Option<u32>,
+) -> Result<Vec<OwnedTransactionMatch>, AppError> {
+    let query = search_terms(query)?;
+    search_repo::search_all_transactions(pool, query, page_size(limit)).await
+}
+
+fn search_terms(query: &str) -> Result<&str, AppError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(AppError::validation("Search query cannot be empty"));
+    }
+    Ok(query)
+}
+
+fn page_size(limit: Option<u32>) -> u32 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+impl From<TransactionMatch> for TransactionSearchResult {
+    fn from(m: TransactionMatch) -> Self {
+        TransactionSearchResult {
+            transaction: TransactionResponse::from(Transaction {
+                id: m.id,
+                wallet_id: m.wallet_id,
+                transaction_type: m.transaction_type,
+                amount: m.amount,
+                description: m.description,
+                status: m.status,
+                created_at: m.created_at,
+                completed_at: m.completed_at,
+                failed_at: m.failed_at,
+                reversed_at: m.reversed_at,
+                exchange_rate: m.exchange_rate,
+            }),
+            rank: m.rank,
+        }
+    }
+}
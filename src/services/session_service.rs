@@ -0,0 +1,89 @@
+use crate::domain::models::Session;
+use crate::error::AppError;
+use crate::repository::session_repo;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// SESSION SERVICE
+// ============================================================================
+// Server-side sessions for the web UI, used instead of the JWT cookie when
+// WEB_AUTH_MODE=session.
+//
+// JWT cookie vs session cookie:
+// - JWT: nothing stored on the server, but a stolen token works until it expires
+// - Session: one DB lookup per request, but deleting the row revokes it instantly
+
+/// Name of the cookie holding the session id
+pub const SESSION_COOKIE: &str = "session_id";
+
+/// Name of the (JS-readable) cookie holding the CSRF token
+pub const CSRF_COOKIE: &str = "csrf_token";
+
+/// Header the browser must echo the CSRF token in
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// Sessions last as long as JWTs do
+const SESSION_TTL_HOURS: i64 = 24;
+
+/// Start a new session for a user
+pub async fn create(pool: &PgPool, user_id: Uuid) -> Result<Session, AppError> {
+    let id = random_token();
+    let csrf_token = random_token();
+    let expires_at = Utc::now() + Duration::hours(SESSION_TTL_HOURS);
+
+    session_repo::create_session(pool, &id, user_id, &csrf_token, expires_at).await
+}
+
+/// Look up a live session by id
+pub async fn find(pool: &PgPool, id: &str) -> Result<Option<Session>, AppError> {
+    session_repo::find_active_session(pool, id).await
+}
+
+/// End a session immediately
+pub async fn revoke(pool: &PgPool, id: &str) -> Result<(), AppError> {
+    session_repo::delete_session(pool, id).await
+}
+
+/// End all of a user's sessions immediately
+pub async fn revoke_all(pool: &PgPool, user_id: Uuid) -> Result<u64, AppError> {
+    session_repo::delete_user_sessions(pool, user_id).await
+}
+
+/// Queue a message to show on the next page the user loads
+pub async fn set_flash(pool: &PgPool, id: &str, message: &str) -> Result<(), AppError> {
+    session_repo::set_flash(pool, id, message).await
+}
+
+/// Pop the queued flash message, if any
+pub async fn take_flash(pool: &PgPool, id: &str) -> Result<Option<String>, AppError> {
+    session_repo::take_flash(pool, id).await
+}
+
+/// Build the Set-Cookie values for a new session
+///
+/// The session id is HttpOnly; the CSRF token is readable by our own JS so
+/// htmx can copy it into the `X-CSRF-Token` header. `secure` adds the
+/// `Secure` flag (HTTPS only), which is on outside development.
+pub fn cookies(session: &Session, secure: bool) -> [String; 2] {
+    let secure = if secure { "; Secure" } else { "" };
+    [
+        format!(
+            "{}={}; Path=/; HttpOnly; SameSite=Lax{}",
+            SESSION_COOKIE, session.id, secure
+        ),
+        format!(
+            "{}={}; Path=/; SameSite=Lax{}",
+            CSRF_COOKIE, session.csrf_token, secure
+        ),
+    ]
+}
+
+/// 32 random bytes, hex encoded
+pub(crate) fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
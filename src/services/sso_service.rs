@@ -0,0 +1,453 @@
+use crate::config::Config;
+use crate::domain::models::{
+    CreateOrganizationRequest, OidcCallbackQuery, Organization, OrganizationResponse,
+    SamlAcsForm, SsoConnectionRequest, SsoConnectionResponse, SsoProtocol,
+};
+use crate::error::AppError;
+use crate::repository::sso_repo::{self, LoginRequest, NewSsoConnection, SsoConnection};
+use crate::repository::user_repo;
+use crate::services::clock::Clock;
+use crate::services::session_service::random_token;
+use crate::sso::saml::{IdentityProvider, SamlResponse, ServiceProvider};
+use crate::sso::{oidc, rejected, xmldsig, SsoIdentity};
+use crate::utils::jwt::hash_password;
+use chrono::Duration;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// SSO SERVICE
+// ============================================================================
+// Enterprise sign-in: an organization's people log in through the
+// organization's identity provider instead of with a password.
+//
+// 1. The login page sends them to /sso/login?organization=<slug>, which
+//    hands the browser to the IdP (OIDC redirect or SAML POST) and
+//    remembers the sign-in in `sso_login_requests`
+// 2. The IdP sends them back to /sso/oidc/callback or /sso/saml/acs; the
+//    protocol code in src/sso/ checks the answer
+// 3. The IdP identity is mapped to a local user, who is then logged in
+//    like after a password login
+//
+// Mapping an identity to a user:
+// - An identity seen before signs in as the user it is linked to
+// - Otherwise the (verified) email must be in one of the organization's
+//   domains. An existing account with that email is linked, unless it
+//   belongs to another organization
+// - Otherwise, with just-in-time provisioning on, an account (and wallet)
+//   is created. It gets a random password nobody knows, so it can only
+//   sign in through the IdP (or after a password reset)
+
+/// How long the IdP has to send the user back
+pub const LOGIN_REQUEST_LIFETIME_MINS: i64 = 10;
+
+/// How a single sign-on starts in the browser
+#[derive(Debug)]
+pub enum SsoLogin {
+    /// OIDC: redirect to `url`; `state` goes in the sso_state cookie so the
+    /// callback can only finish a sign-in this browser started
+    Redirect { url: String, state: String },
+    /// SAML: post `saml_request` (SAMLRequest) to `sso_url`
+    SamlPost { sso_url: String, saml_request: String },
+}
+
+// ============================================================================
+// ADMIN
+// ============================================================================
+
+/// Create an organization
+pub async fn create_organization(
+    pool: &PgPool,
+    req: &CreateOrganizationRequest,
+) -> Result<Organization, AppError> {
+    sso_repo::create_organization(pool, req.name.trim(), &req.slug).await
+}
+
+/// Every organization, with its identity provider
+pub async fn list_organizations(
+    pool: &PgPool,
+    config: &Config,
+) -> Result<Vec<OrganizationResponse>, AppError> {
+    let mut organizations = Vec::new();
+    for organization in sso_repo::list_organizations(pool).await? {
+        let sso = sso_repo::find_connection(pool, organization.id)
+            .await?
+            .map(|connection| connection_response(config, &organization, connection));
+        organizations.push(OrganizationResponse { organization, sso });
+    }
+    Ok(organizations)
+}
+
+/// Set up (or replace) an organization's identity provider
+pub async fn set_connection(
+    pool: &PgPool,
+    config: &Config,
+    organization_id: Uuid,
+    req: &SsoConnectionRequest,
+) -> Result<SsoConnectionResponse, AppError> {
+    let organization = sso_repo::find_organization(pool, organization_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Organization"))?;
+
+    let email_domains = email_domains(&req.email_domains)?;
+    let mut connection = NewSsoConnection {
+        protocol: req.protocol.as_str(),
+        oidc_issuer: None,
+        oidc_client_id: None,
+        oidc_client_secret: None,
+        saml_idp_entity_id: None,
+        saml_sso_url: None,
+        saml_idp_certificate: None,
+        email_domains: &email_domains,
+        jit_provisioning: req.jit_provisioning.unwrap_or(true),
+        enabled: req.enabled.unwrap_or(true),
+    };
+
+    match req.protocol {
+        SsoProtocol::Oidc => {
+            let issuer = required(&req.oidc_issuer, "oidc_issuer")?;
+            check_url(config, issuer, "oidc_issuer")?;
+            connection.oidc_issuer = Some(issuer);
+            connection.oidc_client_id = Some(required(&req.oidc_client_id, "oidc_client_id")?);
+            connection.oidc_client_secret =
+                Some(required(&req.oidc_client_secret, "oidc_client_secret")?);
+        }
+        SsoProtocol::Saml => {
+            connection.saml_idp_entity_id =
+                Some(required(&req.saml_idp_entity_id, "saml_idp_entity_id")?);
+            let sso_url = required(&req.saml_sso_url, "saml_sso_url")?;
+            check_url(config, sso_url, "saml_sso_url")?;
+            connection.saml_sso_url = Some(sso_url);
+            let certificate = required(&req.saml_idp_certificate, "saml_idp_certificate")?;
+            xmldsig::certificate_key(certificate).map_err(|reason| {
+                AppError::validation(&format!("saml_idp_certificate is {}", reason))
+            })?;
+            connection.saml_idp_certificate = Some(certificate);
+        }
+    }
+
+    let connection = sso_repo::upsert_connection(pool, organization.id, &connection).await?;
+    Ok(connection_response(config, &organization, connection))
+}
+
+/// Remove an organization's identity provider
+pub async fn delete_connection(pool: &PgPool, organization_id: Uuid) -> Result<(), AppError> {
+    if !sso_repo::delete_connection(pool, organization_id).await? {
+        return Err(AppError::not_found("SSO connection"));
+    }
+    Ok(())
+}
+
+fn required<'a>(value: &'a Option<String>, field: &str) -> Result<&'a str, AppError> {
+    match value.as_deref().map(str::trim) {
+        Some(value) if !value.is_empty() => Ok(value),
+        _ => Err(AppError::validation(&format!("{} is required", field))),
+    }
+}
+
+/// IdP endpoints must be https, except outside production (local test IdPs)
+fn check_url(config: &Config, url: &str, field: &str) -> Result<(), AppError> {
+    let https = url.starts_with("https://");
+    if !https && (config.is_production() || !url.starts_with("http://")) {
+        return Err(AppError::validation(&format!("{} must be an https URL", field)));
+    }
+    Ok(())
+}
+
+/// Lowercased, without a leading "@", each a plausible domain
+fn email_domains(domains: &[String]) -> Result<Vec<String>, AppError> {
+    let mut normalized: Vec<String> = Vec::new();
+    for domain in domains {
+        let domain = domain.trim().trim_start_matches('@').to_lowercase();
+        let plausible = domain.contains('.')
+            && !domain.starts_with('.')
+            && !domain.ends_with('.')
+            && domain.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+        if !plausible {
+            return Err(AppError::validation(&format!("{:?} is not an email domain", domain)));
+        }
+        if !normalized.contains(&domain) {
+            normalized.push(domain);
+        }
+    }
+    Ok(normalized)
+}
+
+fn connection_response(
+    config: &Config,
+    organization: &Organization,
+    connection: SsoConnection,
+) -> SsoConnectionResponse {
+    SsoConnectionResponse {
+        organization_id: connection.organization_id,
+        protocol: SsoProtocol::parse(&connection.protocol).unwrap_or(SsoProtocol::Oidc),
+        oidc_issuer: connection.oidc_issuer,
+        oidc_client_id: connection.oidc_client_id,
+        saml_idp_entity_id: connection.saml_idp_entity_id,
+        saml_sso_url: connection.saml_sso_url,
+        email_domains: connection.email_domains,
+        jit_provisioning: connection.jit_provisioning,
+        enabled: connection.enabled,
+        login_url: format!("{}/sso/login?organization={}", config.public_url(), organization.slug),
+        updated_at: connection.updated_at,
+    }
+}
+
+// ============================================================================
+// SIGN-IN
+// ============================================================================
+
+/// Our SAML service provider metadata
+pub fn saml_metadata(config: &Config) -> String {
+    ServiceProvider::new(&config.public_url()).metadata()
+}
+
+/// Start signing in to an organization
+pub async fn start_login(
+    pool: &PgPool,
+    config: &Config,
+    clock: &dyn Clock,
+    slug: &str,
+) -> Result<SsoLogin, AppError> {
+    let organization = sso_repo::find_organization_by_slug(pool, slug.trim())
+        .await?
+        .ok_or_else(|| AppError::not_found("Organization"))?;
+    let connection = sso_repo::find_connection(pool, organization.id)
+        .await?
+        .filter(|connection| connection.enabled)
+        .ok_or_else(|| AppError::validation("Single sign-on isn't set up for this organization"))?;
+    let now = clock.now();
+    let expires_at = now + Duration::minutes(LOGIN_REQUEST_LIFETIME_MINS);
+
+    match SsoProtocol::parse(&connection.protocol) {
+        Some(SsoProtocol::Oidc) => {
+            let request = LoginRequest {
+                id: random_token(),
+                organization_id: organization.id,
+                nonce: Some(random_token()),
+                code_verifier: Some(random_token()),
+            };
+            let redirect_uri = redirect_uri(config);
+            let client = oidc_client(&connection, &redirect_uri)?;
+            let metadata = client.discover().await?;
+            let url = client.authorization_url(
+                &metadata,
+                &request.id,
+                request.nonce.as_deref().unwrap_or_default(),
+                request.code_verifier.as_deref().unwrap_or_default(),
+            )?;
+            sso_repo::create_login_request(pool, &request, expires_at).await?;
+
+            Ok(SsoLogin::Redirect { url, state: request.id })
+        }
+        Some(SsoProtocol::Saml) => {
+            // SAML IDs must not start with a digit
+            let request = LoginRequest {
+                id: format!("_{}", random_token()),
+                organization_id: organization.id,
+                nonce: None,
+                code_verifier: None,
+            };
+            let sso_url = connection
+                .saml_sso_url
+                .ok_or_else(|| AppError::internal("SAML connection without an SSO URL"))?;
+            let saml_request = ServiceProvider::new(&config.public_url()).authn_request(
+                &request.id,
+                &sso_url,
+                now,
+            );
+            sso_repo::create_login_request(pool, &request, expires_at).await?;
+
+            Ok(SsoLogin::SamlPost { sso_url, saml_request })
+        }
+        None => Err(AppError::internal("SSO connection with an unknown protocol")),
+    }
+}
+
+/// Finish an OIDC sign-in; `cookie_state` is the sso_state cookie
+pub async fn finish_oidc(
+    pool: &PgPool,
+    config: &Config,
+    clock: &dyn Clock,
+    cookie_state: Option<&str>,
+    query: &OidcCallbackQuery,
+) -> Result<Uuid, AppError> {
+    let state = query.state.as_deref().ok_or_else(|| rejected("callback without state"))?;
+    if cookie_state != Some(state) {
+        return Err(rejected("state doesn't match this browser's sign-in"));
+    }
+    let request = take_login_request(pool, clock, state).await?;
+
+    if let Some(error) = &query.error {
+        tracing::warn!(
+            "🔑 OIDC sign-in failed at the identity provider: {} {}",
+            error,
+            query.error_description.as_deref().unwrap_or_default()
+        );
+        return Err(AppError::validation(
+            "Your identity provider didn't complete the sign-in",
+        ));
+    }
+    let code = query.code.as_deref().ok_or_else(|| rejected("callback without code"))?;
+    let (Some(nonce), Some(code_verifier)) = (&request.nonce, &request.code_verifier) else {
+        return Err(rejected("state of a SAML sign-in"));
+    };
+
+    let connection = enabled_connection(pool, &request, SsoProtocol::Oidc).await?;
+    let redirect_uri = redirect_uri(config);
+    let client = oidc_client(&connection, &redirect_uri)?;
+    let metadata = client.discover().await?;
+    let identity = client.sign_in(&metadata, code, code_verifier, nonce).await?;
+
+    user_for_identity(pool, &connection, identity).await
+}
+
+/// Finish a SAML sign-in
+pub async fn finish_saml(
+    pool: &PgPool,
+    config: &Config,
+    clock: &dyn Clock,
+    form: &SamlAcsForm,
+) -> Result<Uuid, AppError> {
+    let response = SamlResponse::decode(&form.saml_response).map_err(rejected)?;
+    let request_id = response.in_response_to().map_err(rejected)?;
+    let request = take_login_request(pool, clock, &request_id).await?;
+
+    let connection = enabled_connection(pool, &request, SsoProtocol::Saml).await?;
+    let (Some(entity_id), Some(certificate)) =
+        (&connection.saml_idp_entity_id, &connection.saml_idp_certificate)
+    else {
+        return Err(AppError::internal("SAML connection without IdP settings"));
+    };
+    let key = xmldsig::certificate_key(certificate).map_err(rejected)?;
+    let identity = response
+        .verify(
+            &ServiceProvider::new(&config.public_url()),
+            &IdentityProvider { entity_id, key: &key },
+            &request.id,
+            clock.now(),
+        )
+        .map_err(rejected)?;
+
+    user_for_identity(pool, &connection, identity).await
+}
+
+async fn take_login_request(pool: &PgPool, clock: &dyn Clock, id: &str) -> Result<LoginRequest, AppError> {
+    sso_repo::take_login_request(pool, id, clock.now())
+        .await?
+        .ok_or_else(|| AppError::validation("This sign-in has expired, please start again"))
+}
+
+/// The connection a sign-in was started with, if it is still on and
+/// still speaks `protocol`
+async fn enabled_connection(
+    pool: &PgPool,
+    request: &LoginRequest,
+    protocol: SsoProtocol,
+) -> Result<SsoConnection, AppError> {
+    sso_repo::find_connection(pool, request.organization_id)
+        .await?
+        .filter(|connection| {
+            connection.enabled && SsoProtocol::parse(&connection.protocol) == Some(protocol)
+        })
+        .ok_or_else(|| AppError::validation("Single sign-on isn't set up for this organization"))
+}
+
+fn redirect_uri(config: &Config) -> String {
+    format!("{}/sso/oidc/callback", config.public_url())
+}
+
+fn oidc_client<'a>(
+    connection: &'a SsoConnection,
+    redirect_uri: &'a str,
+) -> Result<oidc::Client<'a>, AppError> {
+    match (
+        &connection.oidc_issuer,
+        &connection.oidc_client_id,
+        &connection.oidc_client_secret,
+    ) {
+        (Some(issuer), Some(client_id), Some(client_secret)) => Ok(oidc::Client {
+            issuer,
+            client_id,
+            client_secret,
+            redirect_uri,
+        }),
+        _ => Err(AppError::internal("OIDC connection without client settings")),
+    }
+}
+
+/// The local user an IdP identity signs in as (see the top of this file)
+async fn user_for_identity(
+    pool: &PgPool,
+    connection: &SsoConnection,
+    identity: SsoIdentity,
+) -> Result<Uuid, AppError> {
+    let organization_id = connection.organization_id;
+
+    if let Some(user_id) = sso_repo::find_identity_user(pool, organization_id, &identity.subject).await? {
+        return match user_repo::find_user_by_id(pool, user_id).await {
+            Ok(user) => Ok(user.id),
+            Err(AppError::NotFound(_)) => Err(AppError::AccountDeleted),
+            Err(e) => Err(e),
+        };
+    }
+
+    let email = identity
+        .email
+        .as_deref()
+        .map(|email| email.trim().to_lowercase())
+        .ok_or_else(|| rejected("identity provider sent no email address"))?;
+    if !identity.email_verified {
+        return Err(rejected("identity provider hasn't verified the email address"));
+    }
+    let domain = email.rsplit_once('@').map(|(_, domain)| domain).unwrap_or_default();
+    if !connection.email_domains.iter().any(|allowed| allowed == domain) {
+        return Err(AppError::validation(&format!(
+            "{} can't sign in through this organization",
+            email
+        )));
+    }
+
+    match user_repo::find_user_by_email(pool, &email).await {
+        Ok(user) => {
+            match sso_repo::find_user_organization(pool, user.id).await? {
+                Some(other) if other != organization_id => {
+                    return Err(AppError::validation(
+                        "This account belongs to another organization",
+                    ));
+                }
+                _ => {}
+            }
+            sso_repo::link_identity(pool, organization_id, &identity.subject, user.id).await?;
+            tracing::info!("🔑 Linked SSO identity to existing user {}", user.id);
+            Ok(user.id)
+        }
+        Err(AppError::NotFound(_)) if connection.jit_provisioning => {
+            let full_name: String = identity
+                .full_name
+                .as_deref()
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| email.split('@').next().unwrap_or_default())
+                .chars()
+                .take(255)
+                .collect();
+            let user = sso_repo::provision_user(
+                pool,
+                organization_id,
+                &identity.subject,
+                &email,
+                &hash_password(&random_token())?,
+                &full_name,
+            )
+            .await?;
+            tracing::info!("🔑 Provisioned user {} from SSO", user.id);
+            Ok(user.id)
+        }
+        Err(AppError::NotFound(_)) => Err(AppError::validation(&format!(
+            "There is no account for {} yet; ask your administrator for one",
+            email
+        ))),
+        Err(e) => Err(e),
+    }
+}
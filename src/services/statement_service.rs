@@ -0,0 +1,79 @@
+use crate::domain::models::{
+    CryptoAsset, CryptoStatementLine, MonthlyStatement, MonthlyStatementsResponse,
+};
+use crate::error::AppError;
+use crate::repository::{crypto_repo, statement_repo, user_repo};
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// STATEMENT SERVICE
+// ============================================================================
+// Monthly statements built from the precomputed `monthly_wallet_totals`
+// view instead of the raw transactions, so asking for a year of history
+// costs a dozen index lookups rather than a scan of every partition.
+//
+// The view is refreshed by the nightly maintenance job
+// (`refresh_monthly_totals`).
+//
+// Crypto trades are listed under the month they happened in, so the USD
+// they moved (already part of money in/out) is labelled as such.
+
+/// Months shown when the client doesn't ask for a number
+pub const DEFAULT_STATEMENT_MONTHS: u32 = 12;
+
+/// The user's last `months` months (including this one), oldest first
+pub async fn monthly_statements(
+    pool: &PgPool,
+    user_id: Uuid,
+    months: Option<u32>,
+) -> Result<MonthlyStatementsResponse, AppError> {
+    let months = months.unwrap_or(DEFAULT_STATEMENT_MONTHS).max(1);
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+
+    let today = Utc::now().date_naive();
+    let this_month = NaiveDate::from_ymd_opt(today.year(), today.month(), 1).expect("valid date");
+    let first_month = this_month - Months::new(months - 1);
+
+    let totals =
+        statement_repo::list_monthly_totals(pool, wallet.id, first_month, this_month).await?;
+    let mut trades =
+        crypto_repo::list_monthly_trade_totals(pool, user_id, first_month, this_month).await?;
+
+    let refreshed_at = totals.first().map(|t| t.refreshed_at);
+    let months = totals
+        .into_iter()
+        .map(|t| MonthlyStatement {
+            month: t.month,
+            opening_balance: t.closing_balance - t.money_in + t.money_out,
+            money_in: t.money_in,
+            money_out: t.money_out,
+            closing_balance: t.closing_balance,
+            transaction_count: t.transaction_count,
+            crypto: trades
+                .extract_if(.., |trade| trade.month == t.month)
+                .filter_map(|trade| {
+                    Some(CryptoStatementLine {
+                        asset: CryptoAsset::parse(&trade.asset)?,
+                        bought: trade.bought.normalize(),
+                        sold: trade.sold.normalize(),
+                        usd_spent: trade.usd_spent,
+                        usd_received: trade.usd_received,
+                    })
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(MonthlyStatementsResponse {
+        currency: wallet.currency,
+        months,
+        refreshed_at,
+    })
+}
+
+/// Recompute every wallet's monthly totals
+pub async fn refresh_monthly_totals(pool: &PgPool) -> Result<(), AppError> {
+    statement_repo::refresh_monthly_totals(pool).await
+}
@@ -0,0 +1,522 @@
+use crate::config::{Config, StorageBackend};
+use crate::error::AppError;
+use crate::utils::secret::SecretString;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// ============================================================================
+// FILE STORAGE
+// ============================================================================
+// Where files we keep outside the database go (export files today; KYC
+// documents, avatars and statement archives as those arrive), behind one
+// trait:
+//
+// - `LocalStorage` - a directory on this server (STORAGE_LOCAL_DIR)
+// - `S3Storage` - an S3 bucket, or MinIO / R2 / anything else that speaks
+//   the S3 API (S3_ENDPOINT)
+//
+// Keys are relative paths grouped by what they hold, so a bucket lifecycle
+// rule can expire one kind alone:
+//
+//   exports/{user_id}/{random}/{file_name}
+//   kyc/..., avatars/..., statements/...
+//
+// Files never pass through a handler on their way out: callers hand out a
+// presigned URL instead, which works for a limited time without a session.
+// S3 signs those itself (SigV4 query signing); for local storage they point
+// at our own GET /files/{key}, signed with JWT_SECRET.
+//
+// STORAGE_BACKEND picks the implementation (see config.rs).
+
+/// Longest a presigned URL may be valid (S3's own limit)
+pub const MAX_PRESIGN_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Longest a key may be (S3 allows 1024 bytes)
+const MAX_KEY_LEN: usize = 512;
+
+/// Storage services that take longer than this are given up on
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A file read back from storage
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+/// Keeps files by key
+#[async_trait::async_trait]
+pub trait StorageService: Send + Sync {
+    /// For logs, e.g. "s3"
+    fn name(&self) -> &'static str;
+
+    /// Store a file, replacing whatever was at `key`
+    async fn put(&self, key: &str, content_type: &str, body: Vec<u8>) -> Result<(), AppError>;
+
+    /// Read a file back (None when nothing is stored at `key`)
+    async fn get(&self, key: &str) -> Result<Option<StoredObject>, AppError>;
+
+    /// Delete a file; deleting a missing key is not an error
+    async fn delete(&self, key: &str) -> Result<(), AppError>;
+
+    /// A URL anyone can download the file from for the next `expires_in`
+    /// (at most MAX_PRESIGN_TTL)
+    fn presigned_url(&self, key: &str, expires_in: Duration) -> Result<String, AppError>;
+}
+
+/// The storage chosen by STORAGE_BACKEND
+///
+/// Fails only for a config that didn't pass `validate` (no JWT secret to
+/// sign local download links with).
+pub fn from_config(config: &Config) -> Result<Arc<dyn StorageService>, AppError> {
+    Ok(match config.storage_backend {
+        StorageBackend::Local => Arc::new(LocalStorage::new(
+            PathBuf::from(&config.storage_local_dir),
+            config.public_url(),
+            config
+                .jwt_secret()
+                .cloned()
+                .ok_or_else(|| AppError::internal("No JWT secret configured"))?,
+        )),
+        StorageBackend::S3 => Arc::new(S3Storage::new(
+            config.s3_endpoint.as_deref(),
+            &config.s3_bucket,
+            &config.s3_region,
+            config.s3_path_style,
+            config.s3_access_key_id.clone(),
+            config.s3_secret_access_key.clone(),
+        )),
+    })
+}
+
+/// Where a generated export file goes
+///
+/// The random segment keeps the URL unguessable and lets the same file
+/// name be exported twice without one overwriting the other.
+pub fn export_key(user_id: Uuid, file_name: &str) -> String {
+    format!("exports/{}/{}/{}", user_id, Uuid::new_v4().simple(), file_name)
+}
+
+/// Check a key is a relative path of `[A-Za-z0-9._-]` segments
+///
+/// That makes every key safe as a file path (no `..`, no absolute paths)
+/// and lets it go into URLs without escaping.
+pub fn validate_key(key: &str) -> Result<(), AppError> {
+    let valid = !key.is_empty()
+        && key.len() <= MAX_KEY_LEN
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment != "."
+                && segment != ".."
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::validation(&format!("Invalid storage key {:?}", key)))
+    }
+}
+
+fn check_ttl(expires_in: Duration) -> Result<(), AppError> {
+    if expires_in.is_zero() || expires_in > MAX_PRESIGN_TTL {
+        return Err(AppError::internal(&format!(
+            "presigned URL lifetime must be between 1s and {}s",
+            MAX_PRESIGN_TTL.as_secs()
+        )));
+    }
+    Ok(())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// ============================================================================
+// LOCAL DIRECTORY
+// ============================================================================
+// Key `exports/a/b.csv` is the file `{STORAGE_LOCAL_DIR}/exports/a/b.csv`.
+// Writes go to a temporary file that is renamed into place, so a reader
+// never sees half a file. Only one server can use it; run more than one
+// instance and they each see their own files.
+//
+// Download links look like
+//
+//   {PUBLIC_URL}/files/exports/a/b.csv?expires=1767225600&signature=9f2c...
+//
+// where the signature is an HMAC-SHA256 of the key and expiry.
+
+/// A directory on this server
+pub struct LocalStorage {
+    root: PathBuf,
+    public_url: String,
+    signing_key: SecretString,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf, public_url: String, signing_key: SecretString) -> Self {
+        LocalStorage {
+            root,
+            public_url,
+            signing_key,
+        }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, AppError> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait::async_trait]
+impl StorageService for LocalStorage {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn put(&self, key: &str, _content_type: &str, body: Vec<u8>) -> Result<(), AppError> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| AppError::internal(&format!("Failed to create {}: {}", parent.display(), e)))?;
+        }
+
+        let temp = path.with_file_name(format!(".{}.tmp", Uuid::new_v4().simple()));
+        tokio::fs::write(&temp, body)
+            .await
+            .map_err(|e| AppError::internal(&format!("Failed to write {}: {}", temp.display(), e)))?;
+        tokio::fs::rename(&temp, &path)
+            .await
+            .map_err(|e| AppError::internal(&format!("Failed to write {}: {}", path.display(), e)))
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<StoredObject>, AppError> {
+        let path = self.path(key)?;
+        match tokio::fs::read(&path).await {
+            Ok(body) => Ok(Some(StoredObject {
+                content_type: content_type_for(key).to_string(),
+                body,
+            })),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(AppError::internal(&format!("Failed to read {}: {}", path.display(), e))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        let path = self.path(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(AppError::internal(&format!("Failed to delete {}: {}", path.display(), e))),
+        }
+    }
+
+    fn presigned_url(&self, key: &str, expires_in: Duration) -> Result<String, AppError> {
+        validate_key(key)?;
+        check_ttl(expires_in)?;
+
+        let expires = Utc::now().timestamp() + expires_in.as_secs() as i64;
+        Ok(format!(
+            "{}/files/{}?expires={}&signature={}",
+            self.public_url.trim_end_matches('/'),
+            key,
+            expires,
+            hex::encode(download_mac(&self.signing_key, key, expires).finalize().into_bytes()),
+        ))
+    }
+}
+
+/// The MAC behind a local download link's signature
+fn download_mac(signing_key: &SecretString, key: &str, expires: i64) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(signing_key.expose_secret().as_bytes())
+        .expect("HMAC takes keys of any length");
+    // Prefixed so the MAC can't be mistaken for any other use of JWT_SECRET
+    mac.update(format!("file-download\n{}\n{}", key, expires).as_bytes());
+    mac
+}
+
+/// Check a local download link: signed by us, for this key, not expired
+pub fn verify_download(signing_key: &SecretString, key: &str, expires: i64, signature: &str) -> bool {
+    if expires < Utc::now().timestamp() {
+        return false;
+    }
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+
+    // Constant-time comparison
+    download_mac(signing_key, key, expires).verify_slice(&signature).is_ok()
+}
+
+/// Content type served for a local file, from its extension
+fn content_type_for(key: &str) -> &'static str {
+    match key.rsplit_once('.').map(|(_, extension)| extension.to_ascii_lowercase()) {
+        Some(extension) => match extension.as_str() {
+            "pdf" => "application/pdf",
+            "csv" => "text/csv; charset=utf-8",
+            "json" => "application/json",
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "webp" => "image/webp",
+            _ => "application/octet-stream",
+        },
+        None => "application/octet-stream",
+    }
+}
+
+// ============================================================================
+// S3
+// ============================================================================
+// Plain REST calls (PUT/GET/DELETE Object), signed with AWS Signature
+// Version 4. Requests sign the host, the payload hash and the date:
+//
+//   Authorization: AWS4-HMAC-SHA256
+//     Credential={access key}/{yyyymmdd}/{region}/s3/aws4_request,
+//     SignedHeaders=host;x-amz-content-sha256;x-amz-date,
+//     Signature={hex}
+//
+// Presigned URLs carry the same fields in the query string and sign only
+// the host, with an UNSIGNED-PAYLOAD.
+
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// An S3 (or S3-compatible) bucket
+pub struct S3Storage {
+    http: reqwest::Client,
+    /// "https" or "http"
+    scheme: String,
+    /// What goes in the Host header, e.g. "my-bucket.s3.eu-west-1.amazonaws.com"
+    host: String,
+    /// Put before every key: "/my-bucket" with path-style addressing
+    path_prefix: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: SecretString,
+}
+
+impl S3Storage {
+    /// `endpoint` defaults to AWS's for the region
+    pub fn new(
+        endpoint: Option<&str>,
+        bucket: &str,
+        region: &str,
+        path_style: bool,
+        access_key_id: String,
+        secret_access_key: SecretString,
+    ) -> Self {
+        let endpoint = endpoint
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        let (scheme, authority) = endpoint.split_once("://").unwrap_or(("https", &endpoint));
+        let authority = authority.trim_end_matches('/');
+
+        let (host, path_prefix) = if path_style {
+            (authority.to_string(), format!("/{}", bucket))
+        } else {
+            (format!("{}.{}", bucket, authority), String::new())
+        };
+
+        S3Storage {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("TLS backend is available"),
+            scheme: scheme.to_string(),
+            host,
+            path_prefix,
+            region: region.to_string(),
+            access_key_id,
+            secret_access_key,
+        }
+    }
+
+    /// The object's path; keys need no escaping (see `validate_key`)
+    fn canonical_uri(&self, key: &str) -> String {
+        format!("{}/{}", self.path_prefix, key)
+    }
+
+    fn scope(&self, now: DateTime<Utc>) -> String {
+        format!("{}/{}/s3/aws4_request", now.format("%Y%m%d"), self.region)
+    }
+
+    /// Sign a canonical request made at `now`
+    fn signature(&self, now: DateTime<Utc>, canonical_request: &str) -> String {
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            now.format("%Y%m%dT%H%M%SZ"),
+            self.scope(now),
+            hex::encode(Sha256::digest(canonical_request.as_bytes())),
+        );
+
+        let secret = format!("AWS4{}", self.secret_access_key.expose_secret());
+        let key = hmac_sha256(secret.as_bytes(), now.format("%Y%m%d").to_string().as_bytes());
+        let key = hmac_sha256(&key, self.region.as_bytes());
+        let key = hmac_sha256(&key, b"s3");
+        let key = hmac_sha256(&key, b"aws4_request");
+        hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()))
+    }
+
+    /// Make a signed request for one object
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        content_type: Option<&str>,
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response, AppError> {
+        validate_key(key)?;
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let uri = self.canonical_uri(key);
+
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, uri, self.host, payload_hash, amz_date, SIGNED_HEADERS, payload_hash
+        );
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id,
+            self.scope(now),
+            SIGNED_HEADERS,
+            self.signature(now, &canonical_request),
+        );
+
+        let mut request = self
+            .http
+            .request(method, format!("{}://{}{}", self.scheme, self.host, uri))
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header(reqwest::header::AUTHORIZATION, authorization);
+        if let Some(content_type) = content_type {
+            request = request.header(reqwest::header::CONTENT_TYPE, content_type);
+        }
+
+        request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| AppError::internal(&format!("S3 request failed: {}", e.without_url())))
+    }
+}
+
+/// Turn an S3 error response (an XML `<Error><Code>...`) into an AppError
+async fn s3_error(action: &str, key: &str, response: reqwest::Response) -> AppError {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let code = roxmltree::Document::parse(&body)
+        .ok()
+        .and_then(|doc| {
+            doc.descendants()
+                .find(|node| node.has_tag_name("Code"))
+                .and_then(|node| node.text().map(str::to_string))
+        })
+        .unwrap_or_default();
+
+    AppError::internal(&format!("S3 {} of {:?} failed: {} {}", action, key, status, code))
+}
+
+#[async_trait::async_trait]
+impl StorageService for S3Storage {
+    fn name(&self) -> &'static str {
+        "s3"
+    }
+
+    async fn put(&self, key: &str, content_type: &str, body: Vec<u8>) -> Result<(), AppError> {
+        let response = self.send(reqwest::Method::PUT, key, Some(content_type), body).await?;
+        if !response.status().is_success() {
+            return Err(s3_error("upload", key, response).await);
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<StoredObject>, AppError> {
+        let response = self.send(reqwest::Method::GET, key, None, Vec::new()).await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(s3_error("download", key, response).await);
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| AppError::internal(&format!("S3 download of {:?} failed: {}", key, e.without_url())))?;
+
+        Ok(Some(StoredObject {
+            content_type,
+            body: body.to_vec(),
+        }))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        // S3 answers 204 whether or not the object existed
+        let response = self.send(reqwest::Method::DELETE, key, None, Vec::new()).await?;
+        if !response.status().is_success() && response.status() != reqwest::StatusCode::NOT_FOUND {
+            return Err(s3_error("delete", key, response).await);
+        }
+        Ok(())
+    }
+
+    fn presigned_url(&self, key: &str, expires_in: Duration) -> Result<String, AppError> {
+        validate_key(key)?;
+        check_ttl(expires_in)?;
+
+        let now = Utc::now();
+        let uri = self.canonical_uri(key);
+        // Parameters in the canonical (sorted) order
+        let query = format!(
+            "X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Credential={}&X-Amz-Date={}&X-Amz-Expires={}&X-Amz-SignedHeaders=host",
+            uri_encode(&format!("{}/{}", self.access_key_id, self.scope(now))),
+            now.format("%Y%m%dT%H%M%SZ"),
+            expires_in.as_secs(),
+        );
+        let canonical_request = format!(
+            "GET\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD",
+            uri, query, self.host
+        );
+
+        Ok(format!(
+            "{}://{}{}?{}&X-Amz-Signature={}",
+            self.scheme,
+            self.host,
+            uri,
+            query,
+            self.signature(now, &canonical_request),
+        ))
+    }
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters, as SigV4
+/// wants query values
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
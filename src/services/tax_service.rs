@@ -0,0 +1,190 @@
+use crate::domain::models::{Currency, TaxSummary};
+use crate::error::AppError;
+use crate::repository::{tax_repo, user_repo};
+use chrono::{Datelike, TimeZone, Utc};
+use pdf_writer::{Content, Name, Pdf, Rect, Ref, Str};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// TAX SERVICE
+// ============================================================================
+// Yearly tax summaries: interest earned, fees paid and transfer volume for
+// one calendar year (UTC), summed from the ledger (see tax_repo.rs), as
+// JSON or as a CSV / one-page PDF download.
+//
+// Summaries are computed on request, not stored: the ledger is the record,
+// and a summary for the current year is simply "so far" (`complete: false`).
+
+/// The user's summary for `year`
+pub async fn annual_summary(pool: &PgPool, user_id: Uuid, year: i32) -> Result<TaxSummary, AppError> {
+    let now = Utc::now();
+    if year > now.year() {
+        return Err(AppError::validation("Tax year can't be in the future"));
+    }
+    let (Some(from), Some(to)) = (start_of_year(year), start_of_year(year + 1)) else {
+        return Err(AppError::validation("Not a valid tax year"));
+    };
+
+    let user = user_repo::find_user_by_id(pool, user_id).await?;
+    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
+    let totals = tax_repo::period_totals(pool, wallet.id, from, to).await?;
+
+    Ok(TaxSummary {
+        year,
+        full_name: user.full_name,
+        email: user.email,
+        currency: wallet.currency,
+        interest_earned: cents(totals.interest),
+        fees_paid: cents(totals.fees),
+        transfers_received: cents(totals.transfers_in),
+        transfers_sent: cents(totals.transfers_out),
+        transfer_volume: cents(totals.transfers_in + totals.transfers_out),
+        transfer_count: totals.transfer_count,
+        complete: now >= to,
+        generated_at: now,
+    })
+}
+
+/// `amount` with two decimal places, so an empty year reads "0.00"
+fn cents(mut amount: Decimal) -> Decimal {
+    amount.rescale(2);
+    amount
+}
+
+/// Midnight UTC on January 1st (None outside chrono's range)
+fn start_of_year(year: i32) -> Option<chrono::DateTime<Utc>> {
+    Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).single()
+}
+
+/// The figures as (label, value, currency) lines, shared by the CSV and
+/// the PDF; the transfer count has no currency
+fn lines(summary: &TaxSummary) -> [(&'static str, String, Option<Currency>); 6] {
+    let money = Some(summary.currency);
+    [
+        ("Interest earned", summary.interest_earned.to_string(), money),
+        ("Fees paid", summary.fees_paid.to_string(), money),
+        ("Transfers received", summary.transfers_received.to_string(), money),
+        ("Transfers sent", summary.transfers_sent.to_string(), money),
+        ("Total transfer volume", summary.transfer_volume.to_string(), money),
+        ("Number of transfers", summary.transfer_count.to_string(), None),
+    ]
+}
+
+/// Suggested download name, e.g. "tax-summary-2025.csv"
+pub fn file_name(summary: &TaxSummary, extension: &str) -> String {
+    format!("tax-summary-{}.{}", summary.year, extension)
+}
+
+// ============================================================================
+// CSV
+// ============================================================================
+// One "item,amount,currency" row per figure. Every field is a fixed label
+// or a number, so nothing needs quoting.
+
+/// The summary as CSV
+pub fn to_csv(summary: &TaxSummary) -> String {
+    let mut csv = String::from("year,item,amount,currency\n");
+    for (label, value, currency) in lines(summary) {
+        let currency = currency.map(|c| c.as_str()).unwrap_or_default();
+        csv.push_str(&format!("{},{},{},{}\n", summary.year, label, value, currency));
+    }
+    csv
+}
+
+// ============================================================================
+// PDF
+// ============================================================================
+// A single A4 page in the built-in Helvetica font, so there is no font file
+// to embed. Helvetica only covers Latin-1 (WinAnsiEncoding); other
+// characters in a name print as "?".
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+
+/// The summary as a PDF document
+pub fn to_pdf(summary: &TaxSummary) -> Vec<u8> {
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let page_id = Ref::new(3);
+    let content_id = Ref::new(4);
+    let font_id = Ref::new(5);
+    let bold_id = Ref::new(6);
+    let font = Name(b"F1");
+    let bold = Name(b"F2");
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id).kids([page_id]).count(1);
+
+    let mut page = pdf.page(page_id);
+    page.parent(page_tree_id)
+        .media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+        .contents(content_id);
+    page.resources().fonts().pair(font, font_id).pair(bold, bold_id);
+    drop(page);
+
+    pdf.type1_font(font_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.type1_font(bold_id)
+        .base_font(Name(b"Helvetica-Bold"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    let mut content = Content::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+    text(&mut content, MARGIN, y, bold, 18.0, &format!("Annual tax summary {}", summary.year));
+    y -= 28.0;
+    text(&mut content, MARGIN, y, font, 11.0, &format!("{} <{}>", summary.full_name, summary.email));
+    y -= 16.0;
+    let period = if summary.complete {
+        format!("1 January - 31 December {}", summary.year)
+    } else {
+        format!("1 January {} - {} (year to date)", summary.year, summary.generated_at.format("%-d %B"))
+    };
+    text(&mut content, MARGIN, y, font, 11.0, &period);
+    y -= 36.0;
+
+    for (label, value, currency) in lines(summary) {
+        let amount = match currency {
+            Some(currency) => format!("{} {}", value, currency),
+            None => value,
+        };
+        text(&mut content, MARGIN, y, font, 12.0, label);
+        text(&mut content, PAGE_WIDTH - MARGIN - 160.0, y, bold, 12.0, &amount);
+        y -= 22.0;
+    }
+
+    y -= 20.0;
+    let footer = format!(
+        "Figures are taken from completed transactions in the ledger. Generated {}.",
+        summary.generated_at.format("%Y-%m-%d %H:%M UTC")
+    );
+    text(&mut content, MARGIN, y, font, 9.0, &footer);
+
+    pdf.stream(content_id, &content.finish());
+    pdf.finish()
+}
+
+/// One line of text with its baseline starting at (x, y)
+fn text(content: &mut Content, x: f32, y: f32, font: Name, size: f32, s: &str) {
+    content
+        .begin_text()
+        .set_font(font, size)
+        .next_line(x, y)
+        .show(Str(&latin1(s)))
+        .end_text();
+}
+
+/// `s` in WinAnsiEncoding, which agrees with Latin-1 on printable ASCII
+/// and on 0xA0-0xFF
+fn latin1(s: &str) -> Vec<u8> {
+    s.chars()
+        .map(|c| match u32::from(c) {
+            code @ (0x20..=0x7E | 0xA0..=0xFF) => code as u8,
+            _ => b'?',
+        })
+        .collect()
+}
@@ -0,0 +1,230 @@
+use crate::domain::ids;
+use crate::domain::models::{CreateTokenRequest, CreateTokenResponse, TokenSession};
+use crate::error::AppError;
+use crate::middleware::client::ClientInfo;
+use crate::repository::{token_repo, user_repo};
+use crate::services::clock::Clock;
+use crate::services::session_service;
+use crate::utils::jwt::{encode_token, Claims, JwtKeys, ALL_SCOPES, TOKEN_LIFETIME_HOURS};
+use chrono::Duration;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// ============================================================================
+// TOKEN SERVICE
+// ============================================================================
+// Logging out of a JWT. A signed token can't be taken back, so the server
+// remembers what it no longer accepts instead:
+//
+// - Logging out puts the token's `jti` on a denylist until the token would
+//   have expired anyway (the maintenance job then deletes the row).
+// - Logging out everywhere stamps the user: every token issued up to that
+//   second is refused, and their web sessions are ended too. (A token
+//   issued within that same second is refused as well; signing in again
+//   just works a moment later.)
+//
+// Every authenticated request checks both, in one query (and its session,
+// below, in another).
+//
+// Sessions ("devices"):
+// A sign-in that hands a token to a device starts a session, recorded with
+// the device's IP and User-Agent, and the token carries its id (`sid`).
+// Users list their sessions and revoke one, which deletes it: a token whose
+// session is gone is refused, so that device is signed out. Logging out
+// ends the token's session too. Tokens without a `sid` (the seeder's, or
+// from before sessions) aren't tracked and are only revoked the ways above.
+//
+// Users can also mint a token to hand to another app, with fewer scopes
+// than their own (say, read-only): it gets a session of its own, named by
+// the user, so it's listed and revoked like a device.
+
+/// Longest device name we keep (matches `token_sessions.device_name`)
+const MAX_DEVICE_NAME_LEN: usize = 100;
+
+/// Refuse a token that was revoked, or whose session was (and note that
+/// the session was seen)
+pub async fn ensure_not_revoked(pool: &PgPool, claims: &Claims) -> Result<(), AppError> {
+    let user_id = claims.user_id()?;
+    if token_repo::is_revoked(pool, &claims.jti, user_id, claims.issued_at()).await? {
+        return Err(AppError::InvalidToken);
+    }
+    if let Some(session_id) = claims.session_id() {
+        if !token_repo::touch_session(pool, session_id, user_id).await? {
+            return Err(AppError::InvalidToken);
+        }
+    }
+
+    Ok(())
+}
+
+/// Revoke one token (logging out on one device)
+pub async fn logout(pool: &PgPool, claims: &Claims) -> Result<(), AppError> {
+    if claims.jti.is_empty() {
+        // Issued before tokens had ids; only logging out everywhere
+        // can revoke it
+        return Err(AppError::validation(
+            "This token can't be revoked on its own; log out of all devices instead",
+        ));
+    }
+
+    let user_id = claims.user_id()?;
+    token_repo::revoke(pool, &claims.jti, user_id, claims.expires_at()).await?;
+    if let Some(session_id) = claims.session_id() {
+        token_repo::delete_session(pool, session_id, user_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Revoke every token and web session `user_id` has (logging out on all
+/// devices)
+pub async fn logout_everywhere(pool: &PgPool, clock: &dyn Clock, user_id: Uuid) -> Result<(), AppError> {
+    token_repo::revoke_all(pool, user_id, clock.now()).await?;
+    token_repo::delete_user_sessions(pool, user_id).await?;
+    let sessions = session_service::revoke_all(pool, user_id).await?;
+
+    tracing::info!("🔒 User {} logged out everywhere ({} web sessions ended)", user_id, sessions);
+    Ok(())
+}
+
+/// Record a sign-in on the device `client` as session `session_id`, the
+/// id its token was issued with
+pub async fn start_session(
+    pool: &PgPool,
+    clock: &dyn Clock,
+    session_id: Uuid,
+    user_id: Uuid,
+    client: &ClientInfo,
+) -> Result<(), AppError> {
+    let user_agent = client.user_agent.as_deref();
+    token_repo::insert_session(
+        pool,
+        session_id,
+        user_id,
+        &device_name(user_agent),
+        user_agent,
+        &client.ip.to_string(),
+        clock.now() + Duration::hours(TOKEN_LIFETIME_HOURS),
+    )
+    .await
+}
+
+/// The devices `user_id` is signed in on, marking the one `claims` belong to
+pub async fn list_sessions(pool: &PgPool, claims: &Claims) -> Result<Vec<TokenSession>, AppError> {
+    token_repo::list_sessions(pool, claims.user_id()?, claims.session_id()).await
+}
+
+/// Mint a token for another app on behalf of whoever holds `claims`
+///
+/// Fails with `ValidationError` for a scope we don't know, and with
+/// `InsufficientScope` for one `claims` weren't granted (a read-only token
+/// can't mint one that moves money).
+pub async fn create_token(
+    pool: &PgPool,
+    clock: &dyn Clock,
+    keys: &JwtKeys,
+    claims: &Claims,
+    req: &CreateTokenRequest,
+    client: &ClientInfo,
+) -> Result<CreateTokenResponse, AppError> {
+    let requested: Vec<&str> = req.scope.split_whitespace().collect();
+    if requested.is_empty() {
+        return Err(AppError::validation("Name at least one scope"));
+    }
+    if let Some(unknown) = requested.iter().copied().find(|scope| !ALL_SCOPES.contains(scope)) {
+        return Err(AppError::validation(&format!("Unknown scope: {}", unknown)));
+    }
+    if let Some(missing) = requested.iter().copied().find(|scope| !claims.has_scope(scope)) {
+        return Err(AppError::InsufficientScope(missing.to_string()));
+    }
+
+    let user_id = claims.user_id()?;
+    let role = user_repo::get_user_role(pool, user_id).await?;
+    let session_id = ids::new_id();
+    let mut token_claims =
+        Claims::new(user_id, Some(session_id), role, clock.now(), TOKEN_LIFETIME_HOURS);
+    // (In the usual order, without repeats)
+    token_claims.scope = ALL_SCOPES
+        .into_iter()
+        .filter(|scope| requested.contains(scope))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let token = encode_token(&token_claims, keys)?;
+
+    let expires_at = token_claims.expires_at();
+    token_repo::insert_session(
+        pool,
+        session_id,
+        user_id,
+        &req.name,
+        client.user_agent.as_deref(),
+        &client.ip.to_string(),
+        expires_at,
+    )
+    .await?;
+
+    tracing::info!("🔑 User {} created a \"{}\" token ({})", user_id, token_claims.scope, req.name);
+    Ok(CreateTokenResponse {
+        token,
+        session_id,
+        scope: token_claims.scope,
+        expires_at,
+    })
+}
+
+/// Sign one of the user's devices out
+///
+/// Fails with `NotFound` if they have no such session.
+pub async fn revoke_session(pool: &PgPool, user_id: Uuid, session_id: Uuid) -> Result<(), AppError> {
+    if !token_repo::delete_session(pool, session_id, user_id).await? {
+        return Err(AppError::not_found("Session"));
+    }
+
+    tracing::info!("🔒 User {} ended session {}", user_id, session_id);
+    Ok(())
+}
+
+/// A short name for the device a User-Agent comes from, like "Firefox on
+/// Windows" (or the agent itself, shortened, if it isn't a browser we know)
+pub fn device_name(user_agent: Option<&str>) -> String {
+    let Some(agent) = user_agent else {
+        return "Unknown device".to_string();
+    };
+
+    // Order matters: Edge and Opera claim to be Chrome, Chrome claims to
+    // be Safari, and Android claims to be Linux
+    let browser = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+    ]
+    .into_iter()
+    .find_map(|(marker, name)| agent.contains(marker).then_some(name));
+    let os = [
+        ("iPhone", "iPhone"),
+        ("iPad", "iPad"),
+        ("Android", "Android"),
+        ("Windows", "Windows"),
+        ("Mac OS X", "macOS"),
+        ("CrOS", "ChromeOS"),
+        ("Linux", "Linux"),
+    ]
+    .into_iter()
+    .find_map(|(marker, name)| agent.contains(marker).then_some(name));
+
+    match (browser, os) {
+        (Some(browser), Some(os)) => format!("{} on {}", browser, os),
+        (Some(name), None) | (None, Some(name)) => name.to_string(),
+        // e.g. "curl/8.4.0" -> "curl"
+        (None, None) => agent
+            .split(['/', ' '])
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or("Unknown device")
+            .chars()
+            .take(MAX_DEVICE_NAME_LEN)
+            .collect(),
+    }
+}
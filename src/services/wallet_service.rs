@@ -1,365 +1,1121 @@
+use crate::config::Config;
+use crate::domain::models::{
+    AccountStatus, Currency, HistoryQuery, PendingTransferResponse, PendingTransferStatus, SpendingLimitsRequest,
+    SpendingLimitsResponse, Transaction, TransactionFilter, TransactionPage, User, Wallet,
+    DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, validate_amount,
+};
 use crate::error::AppError;
+use crate::i18n::Locale;
+use crate::repository::pending_transfer_repo::{self, PendingTransfer};
 use crate::repository::user_repo;
+use crate::repository::{
+    ChosenLimits, DailyLimit, ScheduledRun, TransferOrder, UserRepository, WalletRepository,
+    WriteMode,
+};
+use crate::services::clock::Clock;
+use crate::services::email_service::EmailService;
+use crate::services::exchange_rate_service::ExchangeRateService;
+use crate::services::notification_service::Notifier;
+use crate::shutdown::Shutdown;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
 // ============================================================================
 // WALLET SERVICE
 // ============================================================================
-// Business logic for wallet operations
+// Business logic for wallet operations. Storage (and making each money
+// movement atomic) is the repository's job; pass `&state.pool` in
+// production.
+//
+// Every operation that moves money first checks the account may: frozen
+// (and closed) accounts get `AccountFrozen` (see `ensure_can_move_money`),
+// though they can still read their wallets and history.
+
+/// The CHECK constraint that keeps `balance >= -overdraft_limit`
+const BALANCE_CONSTRAINT: &str = "balance_within_overdraft";
+
+/// The SQLSTATE Postgres fails a transaction with when it breaks a deadlock
+const DEADLOCK_DETECTED: &str = "40P01";
+
+/// Times a write that Postgres picked as a deadlock victim is run again
+const DEADLOCK_RETRIES: u32 = 3;
+
+/// The configured spending limits (LIMIT_*): the most one withdrawal or
+/// transfer may move, and the most a wallet may pay out in a day (UTC)
+///
+/// Users may lower them for themselves, never raise them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpendingLimits {
+    pub per_transaction: Decimal,
+    pub daily: Decimal,
+}
+
+impl SpendingLimits {
+    pub fn from_config(config: &Config) -> Self {
+        SpendingLimits {
+            per_transaction: config.limit_per_transaction,
+            daily: config.limit_daily,
+        }
+    }
+
+    /// These limits, lowered to what the user chose
+    fn lowered_to(&self, chosen: &ChosenLimits) -> SpendingLimits {
+        SpendingLimits {
+            per_transaction: chosen.per_transaction.map_or(self.per_transaction, |limit| {
+                limit.min(self.per_transaction)
+            }),
+            daily: chosen.daily.map_or(self.daily, |limit| limit.min(self.daily)),
+        }
+    }
+}
+
+/// How hard transfers try to write without locking
+/// (WALLET_OPTIMISTIC_ATTEMPTS)
+///
+/// Each attempt reads the wallets without locking and only writes if
+/// nothing changed them meanwhile. After `optimistic_attempts` lost races
+/// the wallet is clearly busy, so the last attempt locks the rows instead
+/// and can't lose. 0 means always lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConcurrencyPolicy {
+    pub optimistic_attempts: u32,
+}
+
+impl ConcurrencyPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        ConcurrencyPolicy {
+            optimistic_attempts: config.wallet_optimistic_attempts,
+        }
+    }
+}
+
+/// Run a wallet write optimistically until it wins (pausing briefly, with
+/// jitter, after each lost race so the competing requests don't collide
+/// again), then fall back to locking
+async fn with_retries<T, F>(
+    concurrency: &ConcurrencyPolicy,
+    what: &str,
+    mut write: impl FnMut(WriteMode) -> F,
+) -> Result<T, AppError>
+where
+    F: Future<Output = Result<Option<T>, AppError>>,
+{
+    for attempt in 1..=concurrency.optimistic_attempts {
+        if let Some(result) = retry_on_deadlock(what, || write(WriteMode::Optimistic)).await? {
+            return Ok(result);
+        }
+
+        tracing::debug!(
+            "{} raced a concurrent write, retrying ({}/{})",
+            what,
+            attempt,
+            concurrency.optimistic_attempts
+        );
+        let jitter_ms = u64::from(OsRng.next_u32() % 10);
+        tokio::time::sleep(Duration::from_millis(5 * u64::from(attempt) + jitter_ms)).await;
+    }
+
+    if concurrency.optimistic_attempts > 0 {
+        tracing::info!("{} is heavily contended, falling back to FOR UPDATE", what);
+    }
+    retry_on_deadlock(what, || write(WriteMode::Locking))
+        .await?
+        .ok_or_else(|| AppError::internal("Locked wallet changed during update"))
+}
+
+/// Run a write again (after a short, jittered pause) if Postgres rolled it
+/// back to break a deadlock, up to DEADLOCK_RETRIES times
+///
+/// Transfers lock their wallets in a fixed order, so this should be rare;
+/// but the victim did nothing wrong, and a rolled-back transaction is safe
+/// to repeat.
+async fn retry_on_deadlock<T, F>(what: &str, mut write: impl FnMut() -> F) -> Result<T, AppError>
+where
+    F: Future<Output = Result<T, AppError>>,
+{
+    let mut retries = 0;
+    loop {
+        match write().await {
+            Err(e) if is_deadlock(&e) && retries < DEADLOCK_RETRIES => {
+                retries += 1;
+                tracing::warn!("{} was a deadlock victim, retrying ({}/{})", what, retries, DEADLOCK_RETRIES);
+                let jitter_ms = u64::from(OsRng.next_u32() % 10);
+                tokio::time::sleep(Duration::from_millis(10 * u64::from(retries) + jitter_ms)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether Postgres failed the write to break a deadlock
+fn is_deadlock(error: &AppError) -> bool {
+    matches!(
+        error,
+        AppError::DatabaseError(sqlx::Error::Database(db_err))
+            if db_err.code().as_deref() == Some(DEADLOCK_DETECTED)
+    )
+}
 
 /// Deposit money into a wallet
 ///
 /// # Arguments
-/// * `pool` - Database connection pool
+/// * `repo` - Wallet storage (the database pool in production)
+/// * `email_service` - Sends the user a receipt
+/// * `notification_service` - Pushes the new balance to their dashboards
 /// * `user_id` - The user's UUID
 /// * `amount` - Amount to deposit (must be positive)
+/// * `currency` - The amount's currency, if the client named one: it goes
+///   into the user's wallet in that currency (None = the primary wallet)
 ///
 /// # Returns
 /// The updated wallet with new balance
 pub async fn deposit(
-    pool: &PgPool,
+    repo: &(impl UserRepository + WalletRepository),
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
     user_id: Uuid,
     amount: Decimal,
-) -> Result<crate::domain::models::Wallet, AppError> {
-    // 1. Validate amount
-    if amount <= Decimal::ZERO {
-        return Err(AppError::validation("Deposit amount must be greater than 0"));
-    }
+    currency: Option<Currency>,
+) -> Result<Wallet, AppError> {
+    validate_amount(&amount).map_err(|e| AppError::invalid_field("amount", e))?;
+    ensure_can_move_money(repo, user_id).await?;
+    ensure_currency(repo, user_id, currency).await?;
 
-    // 2. Start transaction
-    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
-
-    // 3. Get current wallet (locking row)
-    let wallet = sqlx::query_as!(
-        crate::domain::models::Wallet,
-        r#"
-        SELECT id, user_id, balance as "balance!", currency, created_at as "created_at!", updated_at as "updated_at!"
-        FROM wallets
-        WHERE user_id = $1
-        FOR UPDATE
-        "#,
-        user_id
-    )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| match e {
-        sqlx::Error::RowNotFound => AppError::not_found("Wallet"),
-        _ => AppError::DatabaseError(e),
-    })?;
-
-    // 4. Calculate new balance
-    let new_balance = wallet.balance + amount;
-
-    // 5. Update wallet
-    let updated_wallet = sqlx::query_as!(
-        crate::domain::models::Wallet,
-        r#"
-        UPDATE wallets
-        SET balance = $1, updated_at = NOW()
-        WHERE id = $2
-        RETURNING id, user_id, balance as "balance!", currency, created_at as "created_at!", updated_at as "updated_at!"
-        "#,
-        new_balance,
-        wallet.id
-    )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(AppError::DatabaseError)?;
-
-    // 6. Record Transaction
-    sqlx::query!(
-        r#"
-        INSERT INTO transactions (wallet_id, transaction_type, amount, description, status)
-        VALUES ($1, 'DEPOSIT', $2, 'Deposit funds', 'COMPLETED')
-        "#,
-        wallet.id,
-        amount
-    )
-    .execute(&mut *tx)
-    .await
-    .map_err(AppError::DatabaseError)?;
+    let wallet = repo
+        .deposit(user_id, currency, amount)
+        .await
+        .map_err(insufficient_balance_on_violation)?;
 
-    // 7. Commit
-    tx.commit().await.map_err(AppError::DatabaseError)?;
+    if let Some((email, locale)) = email_and_locale(repo, user_id).await {
+        let email_service = email_service.clone();
+        let (currency, balance) = (wallet.currency, wallet.balance);
+        tokio::spawn(async move {
+            email_service
+                .send_deposit_received(&email, locale, amount, currency, balance)
+                .await;
+        });
+    }
+    notify_balance_updated(
+        notification_service,
+        user_id,
+        wallet.currency,
+        wallet.balance,
+        "DEPOSIT",
+        amount,
+        "Deposit funds",
+    )
+    .await;
 
-    Ok(updated_wallet)
+    Ok(wallet)
 }
 
 /// Withdraw money from a wallet
 ///
 /// # Arguments
-/// * `pool` - Database connection pool
+/// * `repo` - Wallet storage (the database pool in production)
+/// * `notification_service` - Pushes the new balance to their dashboards
+/// * `limits` - The configured spending limits
+/// * `clock` - Whose day the daily limit is for
 /// * `user_id` - The user's UUID
 /// * `amount` - Amount to withdraw (must be positive and <= balance)
+/// * `currency` - The amount's currency, if the client named one: it comes
+///   out of the user's wallet in that currency (None = the primary wallet)
 ///
 /// # Returns
 /// The updated wallet with new balance
 pub async fn withdraw(
-    pool: &PgPool,
+    repo: &(impl UserRepository + WalletRepository),
+    notification_service: &dyn Notifier,
+    limits: &SpendingLimits,
+    clock: &dyn Clock,
     user_id: Uuid,
     amount: Decimal,
-) -> Result<crate::domain::models::Wallet, AppError> {
-    // 1. Validate amount
-    if amount <= Decimal::ZERO {
-        return Err(AppError::validation("Withdrawal amount must be greater than 0"));
-    }
+    currency: Option<Currency>,
+) -> Result<Wallet, AppError> {
+    validate_amount(&amount).map_err(|e| AppError::invalid_field("amount", e))?;
+    ensure_can_move_money(repo, user_id).await?;
+    let wallet = wallet_for(repo, user_id, currency).await?;
 
-    // 2. Start transaction
-    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
-
-    // 3. Get current wallet (locking row)
-    let wallet = sqlx::query_as!(
-        crate::domain::models::Wallet,
-        r#"
-        SELECT id, user_id, balance as "balance!", currency, created_at as "created_at!", updated_at as "updated_at!"
-        FROM wallets
-        WHERE user_id = $1
-        FOR UPDATE
-        "#,
-        user_id
-    )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| match e {
-        sqlx::Error::RowNotFound => AppError::not_found("Wallet"),
-        _ => AppError::DatabaseError(e),
-    })?;
-
-    // 4. Check balance
-    if wallet.balance < amount {
-        return Err(AppError::InsufficientBalance);
-    }
+    // Fails with LimitExceeded if it's more than the user may spend (today's
+    // total is checked with the withdrawal, so one at the same time counts)
+    let daily_limit = check_limits(repo, limits, clock, &wallet, amount).await?;
 
-    // 5. Calculate new balance
-    let new_balance = wallet.balance - amount;
-
-    // 6. Update wallet
-    let updated_wallet = sqlx::query_as!(
-        crate::domain::models::Wallet,
-        r#"
-        UPDATE wallets
-        SET balance = $1, updated_at = NOW()
-        WHERE id = $2
-        RETURNING id, user_id, balance as "balance!", currency, created_at as "created_at!", updated_at as "updated_at!"
-        "#,
-        new_balance,
-        wallet.id
-    )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(AppError::DatabaseError)?;
-
-    // 7. Record Transaction
-    sqlx::query!(
-        r#"
-        INSERT INTO transactions (wallet_id, transaction_type, amount, description, status)
-        VALUES ($1, 'WITHDRAWAL', $2, 'Withdraw funds', 'COMPLETED')
-        "#,
-        wallet.id,
-        amount
-    )
-    .execute(&mut *tx)
-    .await
-    .map_err(AppError::DatabaseError)?;
+    // Fails with InsufficientBalance if the balance is too low
+    let wallet = repo
+        .withdraw(user_id, currency, amount, daily_limit)
+        .await
+        .map_err(insufficient_balance_on_violation)?;
 
-    // 8. Commit
-    tx.commit().await.map_err(AppError::DatabaseError)?;
+    notify_balance_updated(
+        notification_service,
+        user_id,
+        wallet.currency,
+        wallet.balance,
+        "WITHDRAWAL",
+        amount,
+        "Withdraw funds",
+    )
+    .await;
 
-    Ok(updated_wallet)
+    Ok(wallet)
 }
 
 /// Transfer money to another user
 ///
+/// The money goes into the recipient's wallet in the same currency. If
+/// they have none, it is converted into their primary wallet at the current
+/// exchange rate, which is recorded on both transactions. Without exchange
+/// rates (`exchange_rates` None or RATES_PROVIDER=off) such a transfer
+/// fails with CurrencyMismatch.
+///
 /// # Arguments
-/// * `pool` - Database connection pool
+/// * `repo` - User and wallet storage (the database pool in production)
+/// * `concurrency` - How to guard against concurrent writes
+/// * `exchange_rates` - Where conversion rates come from
+/// * `limits` - The configured spending limits (the sender's own may be
+///   lower)
+/// * `clock` - Whose day the daily limit is for
 /// * `sender_id` - The sender's UUID
 /// * `recipient_email` - The recipient's email address
 /// * `amount` - Amount to transfer (must be positive and <= balance)
+/// * `currency` - The amount's currency, if the client named one: it comes
+///   out of the sender's wallet in that currency (None = the primary wallet)
 ///
 /// # Returns
 /// The updated sender's wallet
+#[allow(clippy::too_many_arguments)]
 pub async fn transfer(
-    pool: &PgPool,
-    email_service: &crate::services::email_service::EmailService,
-    notification_service: &crate::services::notification_service::NotificationService,
+    repo: &(impl UserRepository + WalletRepository),
+    concurrency: &ConcurrencyPolicy,
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
+    exchange_rates: Option<&ExchangeRateService>,
+    limits: &SpendingLimits,
+    clock: &dyn Clock,
     sender_id: Uuid,
     recipient_email: &str,
     amount: Decimal,
-) -> Result<crate::domain::models::Wallet, AppError> {
-    // 1. Validate amount
-    if amount <= Decimal::ZERO {
-        return Err(AppError::validation("Transfer amount must be greater than 0"));
-    }
-
-    // 2. Start a database transaction (Atomic Operation)
-    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
-
-    // 3. Get sender's wallet (FOR UPDATE to lock the row)
-    let sender_wallet = sqlx::query_as!(
-        crate::domain::models::Wallet,
-        r#"
-        SELECT id, user_id, balance as "balance!", currency, created_at as "created_at!", updated_at as "updated_at!"
-        FROM wallets
-        WHERE user_id = $1
-        FOR UPDATE
-        "#,
-        sender_id
+    currency: Option<Currency>,
+) -> Result<Wallet, AppError> {
+    send_transfer(
+        repo,
+        concurrency,
+        email_service,
+        notification_service,
+        exchange_rates,
+        limits,
+        clock,
+        sender_id,
+        recipient_email,
+        amount,
+        currency,
+        None,
     )
-    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| match e {
-        sqlx::Error::RowNotFound => AppError::not_found("Sender wallet"),
-        _ => AppError::DatabaseError(e),
-    })?;
-
-    // 4. Check balance
-    if sender_wallet.balance < amount {
-        return Err(AppError::InsufficientBalance);
-    }
+}
 
-    // 5. Get recipient user and wallet
-    let recipient_user = sqlx::query!(
-        r#"SELECT id FROM users WHERE email = $1"#,
-        recipient_email
+/// Run a scheduled transfer: `transfer`, except that `run` is marked
+/// COMPLETED in the same database transaction as the money moving
+///
+/// So it is paid once however often it's attempted, and not at all once
+/// cancelled (both fail with a validation error).
+#[allow(clippy::too_many_arguments)]
+pub async fn run_scheduled(
+    repo: &(impl UserRepository + WalletRepository),
+    concurrency: &ConcurrencyPolicy,
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
+    exchange_rates: Option<&ExchangeRateService>,
+    limits: &SpendingLimits,
+    clock: &dyn Clock,
+    run: ScheduledRun,
+    sender_id: Uuid,
+    recipient_email: &str,
+    amount: Decimal,
+    currency: Option<Currency>,
+) -> Result<Wallet, AppError> {
+    send_transfer(
+        repo,
+        concurrency,
+        email_service,
+        notification_service,
+        exchange_rates,
+        limits,
+        clock,
+        sender_id,
+        recipient_email,
+        amount,
+        currency,
+        Some(run),
     )
-    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| match e {
-        sqlx::Error::RowNotFound => AppError::validation("Recipient not found"),
-        _ => AppError::DatabaseError(e),
-    })?;
-
-    if recipient_user.id == sender_id {
-        return Err(AppError::validation("Cannot transfer money to yourself"));
-    }
+}
 
-    let recipient_wallet = sqlx::query!(
-        r#"
-        SELECT id FROM wallets WHERE user_id = $1 FOR UPDATE
-        "#,
-        recipient_user.id
-    )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| match e {
-        sqlx::Error::RowNotFound => AppError::not_found("Recipient wallet"),
-        _ => AppError::DatabaseError(e),
-    })?;
-
-    // 6. Deduct from sender
-    let new_sender_balance = sender_wallet.balance - amount;
-    let updated_sender_wallet = sqlx::query_as!(
-        crate::domain::models::Wallet,
-        r#"
-        UPDATE wallets
-        SET balance = $1, updated_at = NOW()
-        WHERE id = $2
-        RETURNING id, user_id, balance as "balance!", currency, created_at as "created_at!", updated_at as "updated_at!"
-        "#,
-        new_sender_balance,
-        sender_wallet.id
+#[allow(clippy::too_many_arguments)]
+async fn send_transfer(
+    repo: &(impl UserRepository + WalletRepository),
+    concurrency: &ConcurrencyPolicy,
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
+    exchange_rates: Option<&ExchangeRateService>,
+    limits: &SpendingLimits,
+    clock: &dyn Clock,
+    sender_id: Uuid,
+    recipient_email: &str,
+    amount: Decimal,
+    currency: Option<Currency>,
+    scheduled: Option<ScheduledRun>,
+) -> Result<Wallet, AppError> {
+    // 1-3. Validate, find the recipient and their wallet
+    let (recipient_user, mut order) = prepare_transfer(
+        repo,
+        exchange_rates,
+        limits,
+        clock,
+        sender_id,
+        recipient_email,
+        amount,
+        currency,
     )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(AppError::DatabaseError)?;
-
-    // Record Sender Transaction (Debit)
-    sqlx::query!(
-        r#"
-        INSERT INTO transactions (wallet_id, transaction_type, amount, description, status)
-        VALUES ($1, 'TRANSFER', $2, 'Transfer sent', 'COMPLETED')
-        "#,
-        sender_wallet.id,
-        amount
+    .await?;
+    order.scheduled = scheduled;
+
+    // 4. Move the money (one atomic operation; fails with
+    //    InsufficientBalance if the sender can't cover it)
+    let result = with_retries(concurrency, "Transfer", |mode| repo.transfer(&order, mode))
+        .await
+        .map_err(insufficient_balance_on_violation)?;
+
+    // 5. Send the sender a receipt and their new balance, and tell the
+    //    recipient (email and live notification)
+    notify_balance_updated(
+        notification_service,
+        sender_id,
+        result.sender_wallet.currency,
+        result.sender_wallet.balance,
+        "TRANSFER",
+        amount,
+        "Transfer sent",
     )
-    .execute(&mut *tx)
-    .await
-    .map_err(AppError::DatabaseError)?;
-
-    // 7. Add to recipient and get new balance
-    let recipient_new_balance = sqlx::query!(
-        r#"
-        UPDATE wallets
-        SET balance = balance + $1, updated_at = NOW()
-        WHERE id = $2
-        RETURNING balance
-        "#,
+    .await;
+    send_transfer_receipt(
+        repo,
+        email_service,
+        sender_id,
         amount,
-        recipient_wallet.id
+        result.sender_wallet.currency,
+        &recipient_user.email,
     )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(AppError::DatabaseError)?;
-
-    // Record Recipient Transaction (Credit)
-    sqlx::query!(
-        r#"
-        INSERT INTO transactions (wallet_id, transaction_type, amount, description, status)
-        VALUES ($1, 'TRANSFER', $2, 'Transfer received', 'COMPLETED')
-        "#,
-        recipient_wallet.id,
-        amount
+    .await;
+    notify_transfer_received(
+        repo,
+        email_service,
+        notification_service,
+        &recipient_user,
+        result.recipient_amount,
+        result.recipient_currency,
+        result.recipient_balance,
     )
-    .execute(&mut *tx)
-    .await
-    .map_err(AppError::DatabaseError)?;
+    .await;
+
+    Ok(result.sender_wallet)
+}
+
+/// Check a transfer and work out where the money goes: the recipient, and
+/// the order for the repository
+#[allow(clippy::too_many_arguments)]
+async fn prepare_transfer(
+    repo: &(impl UserRepository + WalletRepository),
+    exchange_rates: Option<&ExchangeRateService>,
+    limits: &SpendingLimits,
+    clock: &dyn Clock,
+    sender_id: Uuid,
+    recipient_email: &str,
+    amount: Decimal,
+    currency: Option<Currency>,
+) -> Result<(User, TransferOrder), AppError> {
+    // 1. Validate amount
+    validate_amount(&amount).map_err(|e| AppError::invalid_field("amount", e))?;
+    ensure_can_move_money(repo, sender_id).await?;
+    let sender_wallet = wallet_for(repo, sender_id, currency).await?;
 
-    // 8. Commit transaction
-    tx.commit().await.map_err(AppError::DatabaseError)?;
+    // 2. Find the recipient (frozen accounts still receive; closed ones don't)
+    let recipient_user = find_recipient(repo, recipient_email).await?;
 
-    // 9. Send Email Notification (Async)
+    if recipient_user.id == sender_id {
+        return Err(AppError::validation("Cannot transfer money to yourself"));
+    }
+    if repo.get_account_status(recipient_user.id).await? == AccountStatus::Closed {
+        return Err(AppError::validation("The recipient's account is closed"));
+    }
+    let daily_limit = check_limits(repo, limits, clock, &sender_wallet, amount).await?;
+
+    // 3. Pick their wallet, and the rate if it holds another currency
+    let recipient_wallets = repo.list_wallets(recipient_user.id).await?;
+    let recipient_wallet = recipient_wallets
+        .iter()
+        .find(|w| w.currency == sender_wallet.currency)
+        .or(recipient_wallets.first())
+        .ok_or_else(|| AppError::not_found("Recipient wallet"))?;
+
+    let (from, to) = (sender_wallet.currency, recipient_wallet.currency);
+    let exchange_rate = match exchange_rates {
+        _ if from == to => None,
+        Some(rates) if rates.enabled() => Some(rates.rate(from, to).await?),
+        _ => return Err(AppError::currency_mismatch(from, to)),
+    };
+
+    let order = TransferOrder {
+        sender_wallet_id: sender_wallet.id,
+        recipient_wallet_id: recipient_wallet.id,
+        amount,
+        exchange_rate,
+        scheduled: None,
+        daily_limit,
+    };
+    Ok((recipient_user, order))
+}
+
+/// The user a transfer goes to, by email
+///
+/// Fails with `AccountDeleted` for a deleted account, and a validation
+/// error if there's no such user.
+pub(crate) async fn find_recipient(
+    repo: &impl UserRepository,
+    recipient_email: &str,
+) -> Result<User, AppError> {
+    match repo.find_user_by_email(recipient_email).await {
+        Ok(user) => Ok(user),
+        Err(AppError::NotFound(_)) => Err(match repo.find_deleted_user_by_email(recipient_email).await {
+            Ok(_) => AppError::AccountDeleted,
+            Err(_) => AppError::validation("Recipient not found"),
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+/// A user's email address and the language to write to them in, or None
+/// (logged) if they can't be looked up; the money has moved by then, so
+/// a missing receipt mustn't fail the request
+async fn email_and_locale(repo: &impl UserRepository, user_id: Uuid) -> Option<(String, Locale)> {
+    let user = match repo.find_user_by_id(user_id).await {
+        Ok(user) => user,
+        Err(e) => {
+            tracing::warn!("⚠️ No receipt for user {}: {}", user_id, e);
+            return None;
+        }
+    };
+    let locale = repo.preferred_locale(user_id).await.unwrap_or_default();
+    Some((user.email, locale))
+}
+
+/// Email the sender of a transfer a receipt (in the background)
+async fn send_transfer_receipt(
+    repo: &impl UserRepository,
+    email_service: &EmailService,
+    sender_id: Uuid,
+    amount: Decimal,
+    currency: Currency,
+    recipient_email: &str,
+) {
+    let Some((email, locale)) = email_and_locale(repo, sender_id).await else {
+        return;
+    };
+    let email_service = email_service.clone();
+    let recipient_email = recipient_email.to_string();
+    tokio::spawn(async move {
+        email_service
+            .send_transfer_sent(&email, locale, amount, currency, &recipient_email)
+            .await;
+    });
+}
+
+/// Email the recipient of a transfer (in the background) and push them a
+/// live notification and their new balance
+pub(crate) async fn notify_transfer_received(
+    repo: &impl UserRepository,
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
+    recipient: &User,
+    amount: Decimal,
+    currency: Currency,
+    new_balance: Decimal,
+) {
+    let locale = repo.preferred_locale(recipient.id).await.unwrap_or_default();
     let email_service = email_service.clone();
-    let recipient_email_str = recipient_email.to_string();
+    let recipient_email = recipient.email.clone();
     tokio::spawn(async move {
-        email_service.send_transfer_success(&recipient_email_str, amount).await;
+        email_service
+            .send_transfer_received(&recipient_email, locale, amount, currency, new_balance)
+            .await;
     });
 
-    // 10. Send Real-Time WebSocket Notification with Balance
-    tracing::info!("🔔 Attempting to send WebSocket notification to user: {}", recipient_user.id);
+    tracing::info!("🔔 Attempting to send WebSocket notification to user: {}", recipient.id);
     let notification_json = serde_json::json!({
         "type": "transfer_received",
         "message": format!("💰 You received ${} from a transfer!", amount),
         "amount": amount.to_string(),
-        "newBalance": recipient_new_balance.balance.to_string()
+        "newBalance": new_balance.to_string()
     });
-    let notification_msg = serde_json::to_string(&notification_json).unwrap_or_else(|_| {
-        format!("💰 You received ${} from a transfer!", amount)
+    notification_service.send_to_user(&recipient.id, notification_json).await;
+    notify_balance_updated(
+        notification_service,
+        recipient.id,
+        currency,
+        new_balance,
+        "TRANSFER",
+        amount,
+        "Transfer received",
+    )
+    .await;
+}
+
+/// Push a `balance_updated` event to the user's open dashboards: the
+/// wallet's new balance and the transaction that changed it
+///
+/// ```json
+/// { "type": "balance_updated", "currency": "USD", "balance": "70.00",
+///   "transaction": { "type": "DEPOSIT", "amount": "20.00", "description": "Deposit funds" } }
+/// ```
+///
+/// Like any notification it's kept for replay, so a dashboard that was
+/// briefly disconnected still ends up on the latest balance.
+async fn notify_balance_updated(
+    notification_service: &dyn Notifier,
+    user_id: Uuid,
+    currency: Currency,
+    balance: Decimal,
+    transaction_type: &str,
+    amount: Decimal,
+    description: &str,
+) {
+    let event = serde_json::json!({
+        "type": "balance_updated",
+        "currency": currency,
+        "balance": balance.to_string(),
+        "transaction": {
+            "type": transaction_type,
+            "amount": amount.to_string(),
+            "description": description
+        }
+    });
+    notification_service.send_to_user(&user_id, event).await;
+}
+
+// ============================================================================
+// TWO-PHASE TRANSFERS
+// ============================================================================
+// Transfers above LIMIT_CONFIRM_ABOVE wait for the sender to confirm them:
+//
+// 1. `begin_transfer` makes the same checks as `transfer`, then holds the
+//    money (it leaves the sender's wallet; the recipient's credit waits as
+//    PENDING) and emails the sender a 6-digit code.
+// 2. `confirm_transfer` with that code gives the money to the recipient.
+// 3. `cancel_transfer`, MAX_CODE_ATTEMPTS wrong codes, or the code expiring
+//    (`expire_pending_transfers`, run by `spawn_expiry`) gives it back.
+//
+// The hold lives in the database (see pending_transfer_repo), so these take
+// the pool rather than a repository.
+
+/// How long the emailed code works
+const CONFIRMATION_WINDOW: chrono::Duration = chrono::Duration::minutes(15);
+
+/// Wrong codes before the transfer is cancelled
+const MAX_CODE_ATTEMPTS: i32 = 5;
+
+/// How often the background task looks for expired transfers
+const EXPIRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Expired transfers refunded per pass
+const EXPIRY_BATCH_SIZE: i64 = 100;
+
+/// Start a transfer that needs confirming: hold the money and email the
+/// sender a code
+///
+/// Fails like `transfer` would; nothing is held then.
+#[allow(clippy::too_many_arguments)]
+pub async fn begin_transfer(
+    pool: &PgPool,
+    email_service: &EmailService,
+    clock: &dyn Clock,
+    exchange_rates: Option<&ExchangeRateService>,
+    limits: &SpendingLimits,
+    sender_id: Uuid,
+    recipient_email: &str,
+    amount: Decimal,
+    currency: Option<Currency>,
+) -> Result<PendingTransferResponse, AppError> {
+    let (recipient, order) = prepare_transfer(
+        pool,
+        exchange_rates,
+        limits,
+        clock,
+        sender_id,
+        recipient_email,
+        amount,
+        currency,
+    )
+    .await?;
+    let sender = user_repo::find_user_by_id(pool, sender_id).await?;
+
+    let code = format!("{:06}", OsRng.next_u32() % 1_000_000);
+    let transfer = pending_transfer_repo::create(
+        pool,
+        sender_id,
+        recipient.id,
+        &recipient.email,
+        &order,
+        &hash_code(&code),
+        clock.now() + CONFIRMATION_WINDOW,
+    )
+    .await
+    .map_err(insufficient_balance_on_violation)?;
+
+    tracing::info!(
+        "⏳ Transfer {} of {} {} is waiting for confirmation",
+        transfer.id,
+        transfer.amount,
+        transfer.currency
+    );
+
+    // Email the code in the background so the request isn't held up by SMTP
+    let email_service = email_service.clone();
+    let (amount, currency) = (transfer.amount, transfer.currency);
+    let to = recipient.email.clone();
+    tokio::spawn(async move {
+        email_service
+            .send_transfer_confirmation(&sender.email, amount, currency, &to, &code)
+            .await;
     });
-    notification_service.send_to_user(&recipient_user.id, notification_msg).await;
 
-    Ok(updated_sender_wallet)
+    Ok(PendingTransferResponse::from(transfer))
+}
+
+/// Confirm one of the user's pending transfers with the emailed code,
+/// giving the money to the recipient
+pub async fn confirm_transfer(
+    pool: &PgPool,
+    email_service: &EmailService,
+    notification_service: &dyn Notifier,
+    clock: &dyn Clock,
+    user_id: Uuid,
+    transfer_id: Uuid,
+    code: &str,
+) -> Result<PendingTransferResponse, AppError> {
+    ensure_can_move_money(pool, user_id).await?;
+    let now = clock.now();
+    let transfer = pending_transfer(pool, user_id, transfer_id, now).await?;
+
+    if hash_code(code.trim()) != transfer.code_hash {
+        let attempts = pending_transfer_repo::record_wrong_code(pool, transfer.id).await?;
+        if attempts.is_some_and(|attempts| attempts >= MAX_CODE_ATTEMPTS) {
+            pending_transfer_repo::release(pool, transfer.id, PendingTransferStatus::Cancelled, now)
+                .await?;
+            tracing::warn!("🛑 Transfer {} cancelled after too many wrong codes", transfer.id);
+            return Err(AppError::validation(
+                "Too many wrong codes: the transfer was cancelled and the money returned",
+            ));
+        }
+        return Err(AppError::validation("Invalid confirmation code"));
+    }
+
+    let (transfer, recipient_wallet) = pending_transfer_repo::confirm(pool, transfer.id, now)
+        .await?
+        .ok_or_else(|| AppError::validation("This transfer can no longer be confirmed"))?;
+    tracing::info!("✅ Transfer {} confirmed", transfer.id);
+
+    send_transfer_receipt(
+        pool,
+        email_service,
+        user_id,
+        transfer.amount,
+        transfer.currency,
+        &transfer.recipient_email,
+    )
+    .await;
+    let recipient = user_repo::find_user_by_id(pool, transfer.recipient_id).await?;
+    notify_transfer_received(
+        pool,
+        email_service,
+        notification_service,
+        &recipient,
+        transfer.recipient_amount,
+        recipient_wallet.currency,
+        recipient_wallet.balance,
+    )
+    .await;
+
+    Ok(PendingTransferResponse::from(transfer))
+}
+
+/// Cancel one of the user's pending transfers, giving the money back
+pub async fn cancel_transfer(
+    pool: &PgPool,
+    clock: &dyn Clock,
+    user_id: Uuid,
+    transfer_id: Uuid,
+) -> Result<PendingTransferResponse, AppError> {
+    let now = clock.now();
+    let transfer = pending_transfer(pool, user_id, transfer_id, now).await?;
+
+    let transfer =
+        pending_transfer_repo::release(pool, transfer.id, PendingTransferStatus::Cancelled, now)
+            .await?
+            .ok_or_else(|| AppError::validation("This transfer can no longer be cancelled"))?;
+    tracing::info!("↩️ Transfer {} cancelled", transfer.id);
+
+    Ok(PendingTransferResponse::from(transfer))
+}
+
+/// Give back the money of every transfer whose code expired by `now`
+///
+/// Returns how many were refunded.
+pub async fn expire_pending_transfers(pool: &PgPool, now: DateTime<Utc>) -> Result<usize, AppError> {
+    let expired = pending_transfer_repo::list_expired(pool, now, EXPIRY_BATCH_SIZE).await?;
+
+    let mut refunded = 0;
+    for transfer_id in expired {
+        if pending_transfer_repo::release(pool, transfer_id, PendingTransferStatus::Expired, now)
+            .await?
+            .is_some()
+        {
+            refunded += 1;
+        }
+    }
+
+    Ok(refunded)
+}
+
+/// Refund expired transfers now and then every minute, in the background,
+/// until shutdown
+pub fn spawn_expiry(shutdown: &Shutdown, pool: PgPool, clock: Arc<dyn Clock>) {
+    let stopped = shutdown.clone();
+    shutdown.spawn(async move {
+        let mut interval = tokio::time::interval(EXPIRY_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = stopped.stopped() => break,
+            }
+
+            match expire_pending_transfers(&pool, clock.now()).await {
+                Ok(0) => tracing::debug!("⏳ No pending transfers expired"),
+                Ok(n) => tracing::info!("↩️ Refunded {} expired pending transfers", n),
+                Err(e) => tracing::error!("❌ Failed to expire pending transfers: {}", e),
+            }
+        }
+    });
+}
+
+/// One of the user's transfers that is still waiting for confirmation
+///
+/// One whose code has expired is refunded on the spot (the background task
+/// may not have got to it yet) and refused.
+async fn pending_transfer(
+    pool: &PgPool,
+    user_id: Uuid,
+    transfer_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<PendingTransfer, AppError> {
+    let transfer = pending_transfer_repo::find(pool, user_id, transfer_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Pending transfer"))?;
+
+    if transfer.status != PendingTransferStatus::Pending.as_str() {
+        return Err(AppError::ValidationError(format!(
+            "This transfer is already {}",
+            transfer.status.to_lowercase()
+        )));
+    }
+    if transfer.expires_at <= now {
+        pending_transfer_repo::release(pool, transfer.id, PendingTransferStatus::Expired, now)
+            .await?;
+        return Err(AppError::validation(
+            "This transfer has expired: the money was returned",
+        ));
+    }
+
+    Ok(transfer)
+}
+
+fn hash_code(code: &str) -> String {
+    hex::encode(Sha256::digest(code.as_bytes()))
+}
+
+impl From<PendingTransfer> for PendingTransferResponse {
+    fn from(transfer: PendingTransfer) -> Self {
+        PendingTransferResponse {
+            id: transfer.id,
+            recipient_email: transfer.recipient_email,
+            amount: transfer.amount,
+            currency: transfer.currency,
+            // Guarded by a CHECK constraint
+            status: PendingTransferStatus::parse(&transfer.status)
+                .unwrap_or(PendingTransferStatus::Pending),
+            expires_at: transfer.expires_at,
+            created_at: transfer.created_at,
+            confirmed_at: transfer.confirmed_at,
+            cancelled_at: transfer.cancelled_at,
+        }
+    }
 }
 
-/// Get transaction history for a user
+/// Get one page of a user's transaction history
 ///
 /// # Arguments
-/// * `pool` - Database connection pool
+/// * `repo` - Wallet storage (the database pool in production)
 /// * `user_id` - The user's UUID
+/// * `query` - `cursor` (the previous page's `next_cursor`; None = newest
+///   first), `limit` (defaults to 20, capped at 100) and the filters
 ///
 /// # Returns
-/// List of transactions
+/// The matching transactions, newest first, how many match in all, and the
+/// cursor for the next page
 pub async fn get_history(
-    pool: &PgPool,
+    repo: &impl WalletRepository,
     user_id: Uuid,
-) -> Result<Vec<crate::domain::models::Transaction>, AppError> {
+    query: &HistoryQuery,
+) -> Result<TransactionPage, AppError> {
     // We first need to get the wallet_id for the user
-    let wallet = user_repo::get_wallet_by_user_id(pool, user_id).await?;
-    
-    let transactions = sqlx::query_as!(
-        crate::domain::models::Transaction,
-        r#"
-        SELECT id, wallet_id, transaction_type, amount, description, status as "status!", created_at as "created_at!"
-        FROM transactions
-        WHERE wallet_id = $1
-        ORDER BY created_at DESC
-        "#,
-        wallet.id
-    )
-    .fetch_all(pool)
-    .await
-    .map_err(AppError::DatabaseError)?;
+    let wallet = repo.get_wallet_by_user_id(user_id).await?;
+    history_page(repo, wallet.id, query).await
+}
+
+/// One page of any wallet's history (see `get_history`)
+pub(crate) async fn history_page(
+    repo: &impl WalletRepository,
+    wallet_id: Uuid,
+    query: &HistoryQuery,
+) -> Result<TransactionPage, AppError> {
+    let filter = TransactionFilter::try_from(query).map_err(AppError::ValidationError)?;
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    // Ask for one extra row to find out whether there is a next page
+    let mut transactions = repo
+        .list_transactions(wallet_id, &filter, query.cursor, limit + 1)
+        .await?;
+    let has_more = transactions.len() > limit as usize;
+    transactions.truncate(limit as usize);
+
+    let next_cursor = if has_more {
+        transactions.last().map(Transaction::cursor)
+    } else {
+        None
+    };
+
+    let total = repo.count_transactions(wallet_id, &filter).await?;
+
+    Ok(TransactionPage {
+        transactions,
+        total,
+        next_cursor,
+    })
+}
+
+/// Fail with CurrencyMismatch if the client named a currency other than
+/// the wallet's (nothing to check when it didn't name one)
+pub(crate) fn ensure_wallet_currency(wallet: &Wallet, currency: Option<Currency>) -> Result<(), AppError> {
+    match currency {
+        Some(currency) if currency != wallet.currency => {
+            Err(AppError::currency_mismatch(wallet.currency, currency))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Fail with `AccountFrozen` unless the user's account is ACTIVE
+pub(crate) async fn ensure_can_move_money(
+    repo: &impl UserRepository,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    match repo.get_account_status(user_id).await? {
+        AccountStatus::Active => Ok(()),
+        AccountStatus::Frozen | AccountStatus::Closed => Err(AppError::AccountFrozen),
+    }
+}
+
+/// Fail with CurrencyMismatch if the client named a currency the user
+/// holds no wallet in (nothing to check when it didn't name one)
+pub(crate) async fn ensure_currency(
+    repo: &impl WalletRepository,
+    user_id: Uuid,
+    currency: Option<Currency>,
+) -> Result<(), AppError> {
+    if currency.is_some() {
+        wallet_for(repo, user_id, currency).await?;
+    }
+    Ok(())
+}
+
+/// The user's wallet in `currency` (None = their primary wallet)
+///
+/// Fails with CurrencyMismatch if they hold no wallet in that currency.
+async fn wallet_for(
+    repo: &impl WalletRepository,
+    user_id: Uuid,
+    currency: Option<Currency>,
+) -> Result<Wallet, AppError> {
+    let Some(currency) = currency else {
+        return repo.get_wallet_by_user_id(user_id).await;
+    };
 
-    Ok(transactions)
+    let wallets = repo.list_wallets(user_id).await?;
+    if let Some(wallet) = wallets.iter().find(|w| w.currency == currency) {
+        return Ok(wallet.clone());
+    }
+    let primary = wallets.first().ok_or_else(|| AppError::not_found("Wallet"))?;
+    Err(AppError::currency_mismatch(primary.currency, currency))
+}
+
+/// The spending limits that apply to the user: the configured ones,
+/// lowered to their own
+pub async fn get_limits(
+    repo: &impl WalletRepository,
+    limits: &SpendingLimits,
+    user_id: Uuid,
+) -> Result<SpendingLimitsResponse, AppError> {
+    let chosen = repo.chosen_limits(user_id).await?;
+    Ok(limits_response(limits, &chosen))
+}
+
+/// Set the user's own spending limits (None = the configured limit)
+///
+/// They may only be lowered: a limit above the configured one is refused.
+pub async fn set_limits(
+    repo: &impl WalletRepository,
+    limits: &SpendingLimits,
+    user_id: Uuid,
+    req: &SpendingLimitsRequest,
+) -> Result<SpendingLimitsResponse, AppError> {
+    for (name, chosen, max) in [
+        ("per_transaction", req.per_transaction, limits.per_transaction),
+        ("daily", req.daily, limits.daily),
+    ] {
+        let Some(chosen) = chosen else { continue };
+        if chosen.round_dp(2) != chosen {
+            return Err(AppError::ValidationError(format!(
+                "{} can have at most 2 decimal places",
+                name
+            )));
+        }
+        if chosen > max {
+            return Err(AppError::ValidationError(format!("{} can be at most {}", name, max)));
+        }
+    }
+
+    let chosen = ChosenLimits {
+        per_transaction: req.per_transaction,
+        daily: req.daily,
+    };
+    repo.set_chosen_limits(user_id, &chosen).await?;
+    Ok(limits_response(limits, &chosen))
+}
+
+fn limits_response(limits: &SpendingLimits, chosen: &ChosenLimits) -> SpendingLimitsResponse {
+    let applied = limits.lowered_to(chosen);
+    SpendingLimitsResponse {
+        per_transaction: applied.per_transaction,
+        daily: applied.daily,
+        max_per_transaction: limits.per_transaction,
+        max_daily: limits.daily,
+    }
+}
+
+/// Fail with LimitExceeded if paying `amount` out of `wallet` is over the
+/// owner's limit per transaction; otherwise the daily limit, for the
+/// repository to check as it debits the wallet (None if it has no owner)
+///
+/// The day is the UTC calendar day, by `clock`.
+async fn check_limits(
+    repo: &impl WalletRepository,
+    limits: &SpendingLimits,
+    clock: &dyn Clock,
+    wallet: &Wallet,
+    amount: Decimal,
+) -> Result<Option<DailyLimit>, AppError> {
+    let Some(user_id) = wallet.user_id else {
+        return Ok(None);
+    };
+    let applied = limits.lowered_to(&repo.chosen_limits(user_id).await?);
+
+    if amount > applied.per_transaction {
+        return Err(AppError::LimitExceeded {
+            message: format!(
+                "{} {} is over your limit of {} per transaction",
+                amount, wallet.currency, applied.per_transaction
+            ),
+            limit: applied.per_transaction,
+            remaining: None,
+        });
+    }
+
+    Ok(Some(DailyLimit {
+        limit: applied.daily,
+        since: start_of_day(clock.now()),
+    }))
+}
+
+/// Fail with LimitExceeded if `amount` is over LIMIT_CONFIRM_ABOVE
+///
+/// For transfers that run with nobody there to type in the emailed code
+/// (scheduled ones, paying a request): above the threshold they have to be
+/// sent by hand.
+pub fn ensure_no_confirmation_needed(amount: Decimal, confirm_above: Decimal) -> Result<(), AppError> {
+    if amount > confirm_above {
+        return Err(AppError::LimitExceeded {
+            message: format!(
+                "{} needs confirming with an emailed code (over {}), so it can only be sent by hand",
+                amount, confirm_above
+            ),
+            limit: confirm_above,
+            remaining: None,
+        });
+    }
+    Ok(())
+}
+
+/// Midnight (UTC) at the start of `now`'s day
+fn start_of_day(now: DateTime<Utc>) -> DateTime<Utc> {
+    now.date_naive().and_time(chrono::NaiveTime::MIN).and_utc()
+}
+
+/// Every wallet the user holds, the primary one first
+pub async fn list_wallets(repo: &impl WalletRepository, user_id: Uuid) -> Result<Vec<Wallet>, AppError> {
+    repo.list_wallets(user_id).await
+}
+
+/// Open a wallet in another currency (one per currency)
+pub async fn open_wallet(
+    repo: &impl WalletRepository,
+    user_id: Uuid,
+    currency: Currency,
+) -> Result<Wallet, AppError> {
+    repo.create_currency_wallet(user_id, currency).await
+}
+
+/// Turn a hit on the balance CHECK constraint into `InsufficientBalance`
+///
+/// Withdrawals rely on it: they move the balance in one UPDATE and let the
+/// constraint refuse one that goes too low. Everything else checks the
+/// balance before writing, so there it only fires if some code path (or a
+/// race we didn't think of) skipped that check. The database refused the
+/// write either way; the client should see the same error as for the
+/// normal check, not a 500.
+pub(crate) fn insufficient_balance_on_violation(error: AppError) -> AppError {
+    match &error {
+        AppError::DatabaseError(sqlx::Error::Database(db_err))
+            if db_err.constraint() == Some(BALANCE_CONSTRAINT) =>
+        {
+            tracing::debug!("🛑 Balance constraint stopped a write: {}", db_err);
+            AppError::InsufficientBalance
+        }
+        _ => error,
+    }
 }
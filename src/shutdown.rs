@@ -0,0 +1,99 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+// ============================================================================
+// GRACEFUL SHUTDOWN
+// ============================================================================
+// On SIGINT/SIGTERM the server stops accepting connections and lets the
+// requests in flight finish, so a transfer is never cut off halfway. Then:
+// - WebSocket clients are told we're going away (NotificationService)
+// - the background loops (settlement, expiry, scheduled transfers, ...)
+//   finish the run they're in and stop, instead of being killed mid-run
+// - the database pool is closed
+//
+// `Shutdown` is the switch the background loops watch; main.rs flips it.
+
+/// Tells background tasks to stop, and waits for them
+#[derive(Clone)]
+pub struct Shutdown {
+    stopping: Arc<watch::Sender<bool>>,
+    tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (stopping, _) = watch::channel(false);
+        Self {
+            stopping: Arc::new(stopping),
+            tasks: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Run a background task; `wait` waits for it to return, so it should
+    /// return soon after `stopped` resolves
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        let handle = tokio::spawn(task);
+        self.tasks.lock().unwrap().push(handle);
+    }
+
+    /// Resolves once shutdown has started (at once if it already has)
+    pub async fn stopped(&self) {
+        let mut stopping = self.stopping.subscribe();
+        // Can't fail: `self` holds the sender
+        let _ = stopping.wait_for(|stopping| *stopping).await;
+    }
+
+    /// Start shutting down
+    pub fn trigger(&self) {
+        self.stopping.send_replace(true);
+    }
+
+    /// Wait up to `timeout` for every spawned task to finish; returns
+    /// whether they all did
+    pub async fn wait(&self, timeout: Duration) -> bool {
+        let tasks: Vec<_> = self.tasks.lock().unwrap().drain(..).collect();
+        tokio::time::timeout(timeout, futures::future::join_all(tasks))
+            .await
+            .is_ok()
+    }
+}
+
+/// Resolves on Ctrl+C (SIGINT) or, on Unix, SIGTERM (what Kubernetes and
+/// `docker stop` send)
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("❌ Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("❌ Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
@@ -0,0 +1,238 @@
+use api_client::api_types::{
+    CreateUserRequest, DepositRequest, HistoryQuery, TransactionResponse, TransferRequest,
+};
+use api_client::{ApiClient, ClientError, Session};
+use rust_decimal::Decimal;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+// ============================================================================
+// SMOKE TEST
+// ============================================================================
+// `my-fintech-app smoketest --url https://api.example.com` checks that a
+// deployed instance can move money, end to end over HTTP:
+//
+//   1. /health answers
+//   2. two throwaway users register (smoke+<random>@<--email-domain>)
+//   3. the first deposits 10.00, then sends 4.00 to the second
+//   4. both balances and both histories show exactly that
+//
+// Every step is printed with how long it took. The command exits non-zero
+// at the first step that fails or doesn't add up, so it can gate a deploy.
+//
+// It needs nothing but the URL: no database, no secrets. With
+// --admin-token (SMOKETEST_ADMIN_TOKEN) the two users are deleted again
+// afterwards; without it they stay (soft-delete them later, or point
+// --email-domain somewhere easy to find).
+//
+// Registrations and transfers must be switched on (FEATURE_*).
+
+const DEPOSIT: Decimal = Decimal::from_parts(1000, 0, 0, false, 2);
+const TRANSFER: Decimal = Decimal::from_parts(400, 0, 0, false, 2);
+
+/// What to test and how
+#[derive(Debug, Clone)]
+pub struct SmokeTestOptions {
+    /// The instance, e.g. https://api.example.com
+    pub url: String,
+    /// Domain of the throwaway users' email addresses
+    pub email_domain: String,
+    /// An admin's token, to delete the throwaway users afterwards
+    pub admin_token: Option<String>,
+    /// Per request
+    pub timeout: Duration,
+}
+
+/// The step that failed, and why
+#[derive(Debug)]
+pub struct SmokeTestFailure {
+    pub step: &'static str,
+    pub reason: String,
+}
+
+impl std::fmt::Display for SmokeTestFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.step, self.reason)
+    }
+}
+
+impl std::error::Error for SmokeTestFailure {}
+
+/// Run every step against `options.url`; Ok if all of them passed
+pub async fn run(options: &SmokeTestOptions) -> Result<(), SmokeTestFailure> {
+    let client = ApiClient::new(&options.url, options.timeout)
+        .map_err(|e| failure("set up the HTTP client", e))?;
+    println!("🔥 Smoke testing {}", options.url);
+
+    step("health check", client.health()).await?;
+
+    let run_id = Uuid::new_v4().simple().to_string();
+    let password = format!("Smoke-{}", Uuid::new_v4().simple());
+    let sender_email = format!("smoke+{}-a@{}", &run_id[..12], options.email_domain);
+    let recipient_email = format!("smoke+{}-b@{}", &run_id[..12], options.email_domain);
+
+    let sender = step(
+        "register sender",
+        client.register(&CreateUserRequest {
+            email: sender_email,
+            password: password.clone(),
+            full_name: "Smoke Test Sender".to_string(),
+            referral_code: None,
+        }),
+    )
+    .await?;
+    let recipient = match step(
+        "register recipient",
+        client.register(&CreateUserRequest {
+            email: recipient_email,
+            password,
+            full_name: "Smoke Test Recipient".to_string(),
+            referral_code: None,
+        }),
+    )
+    .await
+    {
+        Ok(recipient) => recipient,
+        Err(e) => {
+            clean_up(&client, options, &[&sender]).await;
+            return Err(e);
+        }
+    };
+
+    let result = move_money(&client, &sender, &recipient).await;
+    clean_up(&client, options, &[&sender, &recipient]).await;
+    result?;
+
+    println!("✅ Smoke test passed");
+    Ok(())
+}
+
+/// Steps 3 and 4: deposit, transfer, then check balances and histories
+async fn move_money(
+    client: &ApiClient,
+    sender: &Session,
+    recipient: &Session,
+) -> Result<(), SmokeTestFailure> {
+    let deposit = DepositRequest { amount: DEPOSIT, currency: None };
+    let wallet = step("deposit", client.deposit(sender, &deposit)).await?;
+    expect("deposit", "sender balance", wallet.balance, DEPOSIT)?;
+
+    let transfer = TransferRequest {
+        recipient_email: recipient.email.clone(),
+        amount: TRANSFER,
+        currency: None,
+    };
+    let wallet = step("transfer", client.transfer(sender, &transfer)).await?;
+    expect("transfer", "sender balance", wallet.balance, DEPOSIT - TRANSFER)?;
+
+    let wallet = step("sender balance", client.wallet(sender)).await?;
+    expect("sender balance", "balance", wallet.balance, DEPOSIT - TRANSFER)?;
+    let wallet = step("recipient balance", client.wallet(recipient)).await?;
+    expect("recipient balance", "balance", wallet.balance, TRANSFER)?;
+
+    let first_page = HistoryQuery { limit: Some(10), ..Default::default() };
+    let history = step("sender history", client.history(sender, &first_page)).await?;
+    expect_history(
+        "sender history",
+        &history.transactions,
+        &[("TRANSFER", TRANSFER), ("DEPOSIT", DEPOSIT)],
+    )?;
+    let history = step("recipient history", client.history(recipient, &first_page)).await?;
+    expect_history("recipient history", &history.transactions, &[("TRANSFER", TRANSFER)])?;
+
+    Ok(())
+}
+
+/// Delete the throwaway users if we can (failing to is only a warning)
+async fn clean_up(client: &ApiClient, options: &SmokeTestOptions, users: &[&Session]) {
+    let Some(admin_token) = &options.admin_token else {
+        for user in users {
+            println!("   (left {} behind; pass --admin-token to delete it)", user.email);
+        }
+        return;
+    };
+
+    for user in users {
+        match client.delete_user(admin_token, user.user_id).await {
+            Ok(()) => println!("   deleted {}", user.email),
+            Err(e) => println!("⚠️  Couldn't delete {}: {}", user.email, e),
+        }
+    }
+}
+
+/// Run one step, printing whether it worked and how long it took
+async fn step<T>(
+    name: &'static str,
+    request: impl Future<Output = Result<T, ClientError>>,
+) -> Result<T, SmokeTestFailure> {
+    let started = Instant::now();
+    let result = request.await;
+    let elapsed = started.elapsed().as_millis();
+
+    match result {
+        Ok(value) => {
+            println!("✅ {} ({} ms)", name, elapsed);
+            Ok(value)
+        }
+        Err(e) => {
+            println!("❌ {} ({} ms)", name, elapsed);
+            Err(failure(name, e))
+        }
+    }
+}
+
+fn expect(
+    step: &'static str,
+    what: &str,
+    actual: Decimal,
+    expected: Decimal,
+) -> Result<(), SmokeTestFailure> {
+    if actual == expected {
+        return Ok(());
+    }
+    println!("❌ {}: {} is {}, expected {}", step, what, actual, expected);
+    Err(SmokeTestFailure {
+        step,
+        reason: format!("{} is {}, expected {}", what, actual, expected),
+    })
+}
+
+/// The newest entries must be exactly `expected` (type, amount), all
+/// COMPLETED
+fn expect_history(
+    step: &'static str,
+    history: &[TransactionResponse],
+    expected: &[(&str, Decimal)],
+) -> Result<(), SmokeTestFailure> {
+    let actual: Vec<(&str, Decimal)> = history
+        .iter()
+        .map(|entry| (entry.transaction_type.as_str(), entry.amount))
+        .collect();
+    let all_completed = history.iter().all(|entry| entry.status == "COMPLETED");
+    if actual == expected && all_completed {
+        return Ok(());
+    }
+
+    let describe = |entries: &[(&str, Decimal)]| {
+        entries
+            .iter()
+            .map(|(kind, amount)| format!("{} {}", kind, amount))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let reason = if all_completed {
+        format!("has [{}], expected [{}]", describe(&actual), describe(expected))
+    } else {
+        "not every transaction is COMPLETED".to_string()
+    };
+    println!("❌ {}: {}", step, reason);
+    Err(SmokeTestFailure { step, reason })
+}
+
+fn failure(step: &'static str, e: impl std::fmt::Display) -> SmokeTestFailure {
+    SmokeTestFailure {
+        step,
+        reason: e.to_string(),
+    }
+}
@@ -0,0 +1,33 @@
+// ============================================================================
+// SINGLE SIGN-ON PROTOCOLS
+// ============================================================================
+// The identity provider side of enterprise sign-in: OpenID Connect
+// (`oidc`) and SAML 2.0 (`saml`, with XML signatures in `xmldsig`). Both
+// end in an `SsoIdentity`; what happens next (finding or creating the
+// local user, logging them in) is up to `services::sso_service`.
+
+pub mod oidc;
+pub mod saml;
+pub mod xmldsig;
+
+use crate::error::AppError;
+
+/// Who the identity provider says signed in
+#[derive(Debug, Clone)]
+pub struct SsoIdentity {
+    /// The provider's stable id for the person (OIDC `sub`, SAML NameID)
+    pub subject: String,
+    pub email: Option<String>,
+    /// False if the provider says it hasn't checked the address
+    pub email_verified: bool,
+    pub full_name: Option<String>,
+}
+
+/// A sign-in the provider's answer doesn't let through
+///
+/// The reason is logged, not shown: the user only learns that single
+/// sign-on failed.
+pub(crate) fn rejected(reason: &str) -> AppError {
+    tracing::warn!("🔑 SSO sign-in rejected: {}", reason);
+    AppError::InvalidCredentials
+}
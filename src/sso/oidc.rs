@@ -0,0 +1,236 @@
+use crate::error::AppError;
+use crate::sso::{rejected, SsoIdentity};
+use crate::utils::secret::SecretString;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+// ============================================================================
+// OPENID CONNECT
+// ============================================================================
+// The authorization code flow with PKCE:
+//
+//   1. Look the provider's endpoints up at
+//      {issuer}/.well-known/openid-configuration.
+//   2. Send the browser to its authorization endpoint with a random
+//      `state` (which sign-in this is), `nonce` (which ends up in the ID
+//      token) and PKCE code challenge.
+//   3. It comes back to /sso/oidc/callback with a `code`, which we redeem
+//      (with the client secret and the code verifier) for an ID token.
+//   4. The ID token must be signed by one of the provider's published keys,
+//      issued by the configured issuer, for our client id, unexpired and
+//      carry our nonce.
+//
+// Nothing is cached: discovery and keys are fetched on every sign-in, so a
+// provider rotating its keys never locks anyone out.
+
+/// Identity providers that take longer than this are treated as down
+const OIDC_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Signature algorithms accepted on ID tokens (never HMAC: that would
+/// make the client secret a signing key)
+const ID_TOKEN_ALGORITHMS: [Algorithm; 8] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+];
+
+/// Shared by every organization's provider
+fn http() -> &'static reqwest::Client {
+    static HTTP: OnceLock<reqwest::Client> = OnceLock::new();
+    HTTP.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(OIDC_TIMEOUT)
+            .build()
+            .expect("TLS backend is available")
+    })
+}
+
+/// The parts of a provider's discovery document we use
+#[derive(Debug, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// An organization's OIDC client registration
+pub struct Client<'a> {
+    pub issuer: &'a str,
+    pub client_id: &'a str,
+    pub client_secret: &'a SecretString,
+    /// Our callback, {PUBLIC_URL}/sso/oidc/callback
+    pub redirect_uri: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    nonce: Option<String>,
+    email: Option<String>,
+    email_verified: Option<bool>,
+    name: Option<String>,
+    given_name: Option<String>,
+    family_name: Option<String>,
+}
+
+impl Client<'_> {
+    /// Fetch the provider's discovery document
+    pub async fn discover(&self) -> Result<ProviderMetadata, AppError> {
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            self.issuer.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = get_json(&url).await?;
+        if metadata.issuer != self.issuer {
+            return Err(rejected("discovery document names another issuer"));
+        }
+        Ok(metadata)
+    }
+
+    /// Where to send the browser
+    pub fn authorization_url(
+        &self,
+        metadata: &ProviderMetadata,
+        state: &str,
+        nonce: &str,
+        code_verifier: &str,
+    ) -> Result<String, AppError> {
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+        let url = reqwest::Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            [
+                ("response_type", "code"),
+                ("client_id", self.client_id),
+                ("redirect_uri", self.redirect_uri),
+                ("scope", "openid email profile"),
+                ("state", state),
+                ("nonce", nonce),
+                ("code_challenge", challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|_| AppError::internal("OIDC authorization endpoint is not a URL"))?;
+
+        Ok(url.to_string())
+    }
+
+    /// Redeem the code from the callback and check the ID token
+    pub async fn sign_in(
+        &self,
+        metadata: &ProviderMetadata,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str,
+    ) -> Result<SsoIdentity, AppError> {
+        let response = http()
+            .post(&metadata.token_endpoint)
+            .basic_auth(self.client_id, Some(self.client_secret.expose_secret()))
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", self.redirect_uri),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("OIDC token request to {} failed: {}", metadata.token_endpoint, e);
+                AppError::internal("Identity provider unavailable")
+            })?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::warn!("OIDC token endpoint answered {}: {}", status, body);
+            return Err(rejected("the authorization code was not accepted"));
+        }
+        let tokens: TokenResponse = response
+            .json()
+            .await
+            .map_err(|_| rejected("token response has no ID token"))?;
+
+        let claims = self.verify_id_token(metadata, &tokens.id_token).await?;
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(rejected("ID token nonce mismatch"));
+        }
+
+        let full_name = claims.name.or(match (claims.given_name, claims.family_name) {
+            (Some(given), Some(family)) => Some(format!("{} {}", given, family)),
+            (given, family) => given.or(family),
+        });
+
+        Ok(SsoIdentity {
+            subject: claims.sub,
+            email: claims.email,
+            // Providers that leave the claim out only hand out addresses
+            // they manage
+            email_verified: claims.email_verified.unwrap_or(true),
+            full_name,
+        })
+    }
+
+    async fn verify_id_token(
+        &self,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+    ) -> Result<IdTokenClaims, AppError> {
+        let header =
+            jsonwebtoken::decode_header(id_token).map_err(|_| rejected("malformed ID token"))?;
+        if !ID_TOKEN_ALGORITHMS.contains(&header.alg) {
+            return Err(rejected("ID token signed with an unsupported algorithm"));
+        }
+
+        let keys: JwkSet = get_json(&metadata.jwks_uri).await?;
+        let key = match &header.kid {
+            Some(kid) => keys.find(kid),
+            None if keys.keys.len() == 1 => keys.keys.first(),
+            None => None,
+        }
+        .ok_or_else(|| rejected("ID token signed with an unknown key"))?;
+        let key = DecodingKey::from_jwk(key).map_err(|_| rejected("unusable provider key"))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[self.client_id]);
+        validation.set_issuer(&[self.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        jsonwebtoken::decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map(|token| token.claims)
+            .map_err(|e| {
+                tracing::warn!("OIDC ID token rejected: {}", e);
+                rejected("invalid ID token")
+            })
+    }
+}
+
+async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T, AppError> {
+    let response = http()
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| {
+            tracing::error!("OIDC request to {} failed: {}", url, e);
+            AppError::internal("Identity provider unavailable")
+        })?;
+
+    response.json().await.map_err(|e| {
+        tracing::error!("OIDC response from {} is malformed: {}", url, e);
+        AppError::internal("Identity provider sent a malformed response")
+    })
+}
@@ -0,0 +1,328 @@
+use crate::sso::{xmldsig, SsoIdentity};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use roxmltree::{Document, Node};
+use rsa::RsaPublicKey;
+use std::collections::HashSet;
+
+// ============================================================================
+// SAML 2.0
+// ============================================================================
+// Web Browser SSO, service provider initiated, with the HTTP-POST binding
+// both ways:
+//
+//   1. We post an unsigned <AuthnRequest> to the IdP's SSO URL (through
+//      an auto-submitting form in the user's browser).
+//   2. The IdP posts a <Response> with a signed <Assertion> back to our
+//      assertion consumer service (ACS).
+//
+// Responses are only accepted in reply to a request we sent (matched on
+// InResponseTo, once), which rules out unsolicited (IdP-initiated)
+// sign-ins and replays. Encrypted assertions aren't supported.
+//
+// Against signature wrapping, everything is read from the one Assertion
+// that is a direct child of the Response, and that same element (or the
+// Response around it) must be the one the signature covers. IDs must be
+// unique in the document.
+
+const PROTOCOL_NS: &str = "urn:oasis:names:tc:SAML:2.0:protocol";
+const ASSERTION_NS: &str = "urn:oasis:names:tc:SAML:2.0:assertion";
+const STATUS_SUCCESS: &str = "urn:oasis:names:tc:SAML:2.0:status:Success";
+const BEARER: &str = "urn:oasis:names:tc:SAML:2.0:cm:bearer";
+const HTTP_POST: &str = "urn:oasis:names:tc:SAML:2.0:bindings:HTTP-POST";
+
+/// How far our clock and the IdP's may drift apart
+const CLOCK_SKEW: Duration = Duration::minutes(2);
+
+/// Attribute names IdPs commonly use for the email address
+const EMAIL_ATTRIBUTES: [&str; 4] = [
+    "email",
+    "mail",
+    "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/emailaddress",
+    "urn:oid:0.9.2342.19200300.100.1.3",
+];
+
+/// ... and for the display name
+const NAME_ATTRIBUTES: [&str; 4] = [
+    "name",
+    "displayName",
+    "http://schemas.microsoft.com/identity/claims/displayname",
+    "urn:oid:2.16.840.1.113730.3.1.241",
+];
+
+/// ... and for first and last names, when there is no display name
+const GIVEN_NAME_ATTRIBUTES: [&str; 3] = [
+    "givenName",
+    "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/givenname",
+    "urn:oid:2.5.4.42",
+];
+const SURNAME_ATTRIBUTES: [&str; 4] = [
+    "sn",
+    "surname",
+    "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/surname",
+    "urn:oid:2.5.4.4",
+];
+
+/// This app as a SAML service provider
+pub struct ServiceProvider {
+    /// Our entity id: the URL of our metadata
+    pub entity_id: String,
+    pub acs_url: String,
+}
+
+impl ServiceProvider {
+    /// Our endpoints under PUBLIC_URL
+    pub fn new(public_url: &str) -> Self {
+        ServiceProvider {
+            entity_id: format!("{}/sso/saml/metadata", public_url),
+            acs_url: format!("{}/sso/saml/acs", public_url),
+        }
+    }
+
+    /// Our metadata, for the IdP admin to import
+    pub fn metadata(&self) -> String {
+        format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<md:EntityDescriptor xmlns:md="urn:oasis:names:tc:SAML:2.0:metadata" entityID="{}">"#,
+                r#"<md:SPSSODescriptor AuthnRequestsSigned="false" protocolSupportEnumeration="{}">"#,
+                r#"<md:NameIDFormat>urn:oasis:names:tc:SAML:2.0:nameid-format:persistent</md:NameIDFormat>"#,
+                r#"<md:AssertionConsumerService Binding="{}" Location="{}" index="0" isDefault="true"/>"#,
+                r#"</md:SPSSODescriptor>"#,
+                r#"</md:EntityDescriptor>"#,
+            ),
+            escape(&self.entity_id),
+            PROTOCOL_NS,
+            HTTP_POST,
+            escape(&self.acs_url),
+        )
+    }
+
+    /// An <AuthnRequest> to `sso_url`, base64 encoded for the SAMLRequest
+    /// form field
+    pub fn authn_request(&self, id: &str, sso_url: &str, now: DateTime<Utc>) -> String {
+        let xml = format!(
+            concat!(
+                r#"<samlp:AuthnRequest xmlns:samlp="{}" xmlns:saml="{}" ID="{}" Version="2.0" "#,
+                r#"IssueInstant="{}" Destination="{}" AssertionConsumerServiceURL="{}" "#,
+                r#"ProtocolBinding="{}">"#,
+                r#"<saml:Issuer>{}</saml:Issuer>"#,
+                r#"<samlp:NameIDPolicy AllowCreate="true"/>"#,
+                r#"</samlp:AuthnRequest>"#,
+            ),
+            PROTOCOL_NS,
+            ASSERTION_NS,
+            escape(id),
+            now.to_rfc3339_opts(SecondsFormat::Secs, true),
+            escape(sso_url),
+            escape(&self.acs_url),
+            HTTP_POST,
+            escape(&self.entity_id),
+        );
+        STANDARD.encode(xml)
+    }
+}
+
+/// The identity provider an organization configured
+pub struct IdentityProvider<'a> {
+    pub entity_id: &'a str,
+    /// From the configured signing certificate
+    pub key: &'a RsaPublicKey,
+}
+
+/// A <Response> posted to the ACS, not yet checked
+pub struct SamlResponse {
+    xml: String,
+}
+
+impl SamlResponse {
+    /// Decode the SAMLResponse form field
+    pub fn decode(saml_response: &str) -> Result<Self, &'static str> {
+        let encoded: String = saml_response.chars().filter(|c| !c.is_whitespace()).collect();
+        let xml = STANDARD.decode(encoded).map_err(|_| "SAMLResponse is not base64")?;
+        let xml = String::from_utf8(xml).map_err(|_| "SAMLResponse is not UTF-8")?;
+        Ok(SamlResponse { xml })
+    }
+
+    /// The id of the <AuthnRequest> this answers (unchecked: only for
+    /// finding which sign-in and organization it belongs to)
+    pub fn in_response_to(&self) -> Result<String, &'static str> {
+        let doc = Document::parse(&self.xml).map_err(|_| "SAMLResponse is not well-formed XML")?;
+        doc.root_element()
+            .attribute("InResponseTo")
+            .map(str::to_string)
+            .ok_or("unsolicited response (no InResponseTo)")
+    }
+
+    /// Check the response and read who signed in
+    pub fn verify(
+        &self,
+        sp: &ServiceProvider,
+        idp: &IdentityProvider,
+        request_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<SsoIdentity, &'static str> {
+        let doc = Document::parse(&self.xml).map_err(|_| "SAMLResponse is not well-formed XML")?;
+        let response = doc.root_element();
+        if !is(response, PROTOCOL_NS, "Response") || response.attribute("Version") != Some("2.0") {
+            return Err("not a SAML 2.0 Response");
+        }
+        if response.attribute("InResponseTo") != Some(request_id) {
+            return Err("response to another request");
+        }
+        if response.attribute("Destination").is_some_and(|d| d != sp.acs_url) {
+            return Err("response meant for another destination");
+        }
+
+        let status = child(response, PROTOCOL_NS, "Status")
+            .and_then(|status| child(status, PROTOCOL_NS, "StatusCode"))
+            .and_then(|code| code.attribute("Value"));
+        if status != Some(STATUS_SUCCESS) {
+            return Err("the identity provider reported a failed sign-in");
+        }
+        if let Some(issuer) = child(response, ASSERTION_NS, "Issuer") {
+            if text(issuer)? != idp.entity_id {
+                return Err("response from another issuer");
+            }
+        }
+
+        if child(response, ASSERTION_NS, "EncryptedAssertion").is_some() {
+            return Err("encrypted assertions are not supported");
+        }
+        let mut assertions = children(response, ASSERTION_NS, "Assertion");
+        let assertion = match (assertions.next(), assertions.next()) {
+            (Some(assertion), None) => assertion,
+            _ => return Err("response must hold exactly one assertion"),
+        };
+
+        let mut ids = HashSet::new();
+        for id in doc.descendants().filter_map(|node| node.attribute("ID")) {
+            if !ids.insert(id) {
+                return Err("duplicate ID in response");
+            }
+        }
+
+        // A signed assertion is checked on its own; otherwise the whole
+        // response must be signed
+        if xmldsig::is_signed(assertion) {
+            xmldsig::verify(assertion, idp.key)?;
+        } else if xmldsig::is_signed(response) {
+            xmldsig::verify(response, idp.key)?;
+        } else {
+            return Err("assertion is not signed");
+        }
+
+        if child(assertion, ASSERTION_NS, "Issuer").map(text).transpose()? != Some(idp.entity_id) {
+            return Err("assertion from another issuer");
+        }
+
+        let subject = child(assertion, ASSERTION_NS, "Subject").ok_or("assertion has no subject")?;
+        let name_id = child(subject, ASSERTION_NS, "NameID")
+            .map(text)
+            .transpose()?
+            .filter(|name_id| !name_id.is_empty())
+            .ok_or("assertion has no NameID")?;
+        let confirmed = children(subject, ASSERTION_NS, "SubjectConfirmation")
+            .filter(|confirmation| confirmation.attribute("Method") == Some(BEARER))
+            .filter_map(|confirmation| child(confirmation, ASSERTION_NS, "SubjectConfirmationData"))
+            .any(|data| {
+                data.attribute("Recipient") == Some(sp.acs_url.as_str())
+                    && data.attribute("InResponseTo").is_none_or(|id| id == request_id)
+                    && matches!(timestamp(data, "NotOnOrAfter"), Ok(Some(t)) if now < t + CLOCK_SKEW)
+            });
+        if !confirmed {
+            return Err("no usable bearer subject confirmation");
+        }
+
+        let conditions = child(assertion, ASSERTION_NS, "Conditions").ok_or("assertion has no conditions")?;
+        if timestamp(conditions, "NotBefore")?.is_some_and(|t| now + CLOCK_SKEW < t) {
+            return Err("assertion is not valid yet");
+        }
+        if timestamp(conditions, "NotOnOrAfter")?.is_some_and(|t| now >= t + CLOCK_SKEW) {
+            return Err("assertion has expired");
+        }
+        let mut restrictions = children(conditions, ASSERTION_NS, "AudienceRestriction").peekable();
+        if restrictions.peek().is_none() {
+            return Err("assertion has no audience restriction");
+        }
+        for restriction in restrictions {
+            if !children(restriction, ASSERTION_NS, "Audience").any(|a| text(a) == Ok(sp.entity_id.as_str())) {
+                return Err("assertion is meant for another service provider");
+            }
+        }
+
+        let attribute = |names: &[&str]| -> Result<Option<String>, &'static str> {
+            let value = child(assertion, ASSERTION_NS, "AttributeStatement")
+                .into_iter()
+                .flat_map(|statement| children(statement, ASSERTION_NS, "Attribute"))
+                .filter(|a| a.attribute("Name").is_some_and(|name| names.contains(&name)))
+                .find_map(|a| child(a, ASSERTION_NS, "AttributeValue"));
+            Ok(value.map(text).transpose()?.filter(|value| !value.is_empty()).map(str::to_string))
+        };
+
+        let email = attribute(&EMAIL_ATTRIBUTES)?.or_else(|| {
+            Some(name_id.to_string()).filter(|name_id| name_id.contains('@'))
+        });
+        let full_name = match attribute(&NAME_ATTRIBUTES)? {
+            Some(name) => Some(name),
+            None => match (attribute(&GIVEN_NAME_ATTRIBUTES)?, attribute(&SURNAME_ATTRIBUTES)?) {
+                (Some(given), Some(surname)) => Some(format!("{} {}", given, surname)),
+                (given, surname) => given.or(surname),
+            },
+        };
+
+        Ok(SsoIdentity {
+            subject: name_id.to_string(),
+            email,
+            email_verified: true,
+            full_name,
+        })
+    }
+}
+
+fn is(node: Node, ns: &str, name: &str) -> bool {
+    node.is_element() && node.tag_name().namespace() == Some(ns) && node.tag_name().name() == name
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, ns: &str, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|c| is(*c, ns, name))
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    ns: &'a str,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |c| is(*c, ns, name))
+}
+
+/// The text of a value element, which must hold nothing else
+///
+/// A comment would split it: the signature doesn't cover comments, so
+/// `victim@corp.com<!---->.evil.com` verifies as signed by evil.com's IdP
+/// but its first text node alone reads as someone else.
+fn text<'a>(node: Node<'a, '_>) -> Result<&'a str, &'static str> {
+    if node.children().any(|c| !c.is_text()) {
+        return Err("value element holds more than text");
+    }
+    Ok(node.text().unwrap_or_default().trim())
+}
+
+/// A time attribute (None if it isn't there)
+fn timestamp(node: Node, attribute: &str) -> Result<Option<DateTime<Utc>>, &'static str> {
+    node.attribute(attribute)
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|_| "malformed timestamp")
+        })
+        .transpose()
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,312 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use roxmltree::{Node, NodeType};
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use sha2::{Digest, Sha256};
+use x509_cert::der::{Decode, DecodePem, Encode};
+use x509_cert::Certificate;
+
+// ============================================================================
+// XML SIGNATURES
+// ============================================================================
+// Just enough of XML-DSig to check a SAML identity provider's enveloped
+// signature, the way every mainstream IdP makes them:
+//
+//   <saml:Assertion ID="_abc">
+//     ...
+//     <ds:Signature>
+//       <ds:SignedInfo>
+//         <ds:CanonicalizationMethod Algorithm="...xml-exc-c14n#"/>
+//         <ds:SignatureMethod Algorithm="...rsa-sha256"/>
+//         <ds:Reference URI="#_abc">
+//           <ds:Transforms> enveloped-signature, xml-exc-c14n# </ds:Transforms>
+//           <ds:DigestMethod Algorithm="...sha256"/>
+//           <ds:DigestValue>...</ds:DigestValue>
+//         </ds:Reference>
+//       </ds:SignedInfo>
+//       <ds:SignatureValue>...</ds:SignatureValue>
+//     </ds:Signature>
+//   </saml:Assertion>
+//
+// Anything else (other algorithms, several references, references to
+// some other element) is rejected rather than half supported. The key is
+// always the certificate configured for the IdP; a <ds:KeyInfo> in the
+// message is ignored, since anyone can put their own key there.
+//
+// The signed bytes are the element's Exclusive XML Canonicalization
+// (https://www.w3.org/TR/xml-exc-c14n/), written out below: namespace
+// declarations only where a prefix is first used, attributes sorted,
+// text and attribute values escaped, comments dropped, and the Signature
+// itself left out.
+
+const DSIG_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+const EXC_C14N: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+const ENVELOPED_SIGNATURE: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+const SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+
+/// Whether `element` has an enveloped <ds:Signature> child at all
+pub fn is_signed(element: Node) -> bool {
+    child(element, DSIG_NS, "Signature").is_some()
+}
+
+/// Check the enveloped signature of `element` against `key`
+///
+/// Err is a short reason, for the log.
+pub fn verify(element: Node, key: &RsaPublicKey) -> Result<(), &'static str> {
+    let signature = only_child(element, DSIG_NS, "Signature")?;
+    let signed_info = only_child(signature, DSIG_NS, "SignedInfo")?;
+
+    let c14n = only_child(signed_info, DSIG_NS, "CanonicalizationMethod")?;
+    if c14n.attribute("Algorithm") != Some(EXC_C14N) {
+        return Err("unsupported canonicalization method");
+    }
+    let method = only_child(signed_info, DSIG_NS, "SignatureMethod")?;
+    if method.attribute("Algorithm") != Some(RSA_SHA256) {
+        return Err("unsupported signature method");
+    }
+
+    // The one reference must be to the element the signature sits in
+    let reference = only_child(signed_info, DSIG_NS, "Reference")?;
+    let id = element.attribute("ID").ok_or("signed element has no ID")?;
+    if reference.attribute("URI").and_then(|uri| uri.strip_prefix('#')) != Some(id) {
+        return Err("signature references another element");
+    }
+
+    let transforms = only_child(reference, DSIG_NS, "Transforms")?;
+    let mut enveloped = false;
+    let mut digest_prefixes = Vec::new();
+    for transform in transforms.children().filter(Node::is_element) {
+        match transform.attribute("Algorithm") {
+            Some(ENVELOPED_SIGNATURE) => enveloped = true,
+            Some(EXC_C14N) => digest_prefixes = inclusive_prefixes(transform),
+            _ => return Err("unsupported transform"),
+        }
+    }
+    if !enveloped {
+        return Err("signature is not enveloped");
+    }
+
+    let digest_method = only_child(reference, DSIG_NS, "DigestMethod")?;
+    if digest_method.attribute("Algorithm") != Some(SHA256) {
+        return Err("unsupported digest method");
+    }
+    let expected_digest = base64_value(only_child(reference, DSIG_NS, "DigestValue")?)?;
+    let digest = Sha256::digest(canonicalize(element, Some(signature), &digest_prefixes));
+    if digest.as_slice() != expected_digest.as_slice() {
+        return Err("digest mismatch");
+    }
+
+    let signature_value = base64_value(only_child(signature, DSIG_NS, "SignatureValue")?)?;
+    let signature_value =
+        Signature::try_from(signature_value.as_slice()).map_err(|_| "malformed signature value")?;
+    let signed = canonicalize(signed_info, None, &inclusive_prefixes(c14n));
+    VerifyingKey::<Sha256>::new(key.clone())
+        .verify(signed.as_bytes(), &signature_value)
+        .map_err(|_| "bad signature")
+}
+
+/// The RSA public key of a certificate, given as PEM or as bare base64
+/// (the way IdP metadata lists it)
+pub fn certificate_key(certificate: &str) -> Result<RsaPublicKey, &'static str> {
+    let certificate = if certificate.contains("-----BEGIN") {
+        Certificate::from_pem(certificate.trim().as_bytes())
+    } else {
+        let der = base64_decode(certificate)?;
+        Certificate::from_der(&der)
+    }
+    .map_err(|_| "not an X.509 certificate")?;
+
+    let spki = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|_| "not an X.509 certificate")?;
+    RsaPublicKey::from_public_key_der(&spki).map_err(|_| "not an RSA certificate")
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, ns: &str, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|c| c.is_element() && c.tag_name().namespace() == Some(ns) && c.tag_name().name() == name)
+}
+
+/// The child `name`, which must be there exactly once
+fn only_child<'a, 'input>(
+    node: Node<'a, 'input>,
+    ns: &str,
+    name: &str,
+) -> Result<Node<'a, 'input>, &'static str> {
+    let mut matches = node.children().filter(|c| {
+        c.is_element() && c.tag_name().namespace() == Some(ns) && c.tag_name().name() == name
+    });
+    match (matches.next(), matches.next()) {
+        (Some(found), None) => Ok(found),
+        (None, _) => Err("signature is missing an element"),
+        (Some(_), Some(_)) => Err("signature has a duplicate element"),
+    }
+}
+
+/// The InclusiveNamespaces PrefixList of an exc-c14n algorithm element
+fn inclusive_prefixes(algorithm: Node) -> Vec<String> {
+    algorithm
+        .children()
+        .find(|c| c.is_element() && c.tag_name().name() == "InclusiveNamespaces")
+        .and_then(|list| list.attribute("PrefixList"))
+        .map(|list| {
+            list.split_whitespace()
+                .map(|prefix| if prefix == "#default" { String::new() } else { prefix.to_string() })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn base64_value(node: Node) -> Result<Vec<u8>, &'static str> {
+    base64_decode(node.text().unwrap_or_default())
+}
+
+fn base64_decode(value: &str) -> Result<Vec<u8>, &'static str> {
+    let value: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    STANDARD.decode(value).map_err(|_| "malformed base64")
+}
+
+// ============================================================================
+// EXCLUSIVE CANONICALIZATION
+// ============================================================================
+
+/// Exclusive canonical form of `element`, leaving out `skip` (the
+/// enveloped signature). `inclusive` prefixes are declared as in
+/// inclusive canonicalization; "" is the default namespace.
+fn canonicalize(element: Node, skip: Option<Node>, inclusive: &[String]) -> String {
+    let mut out = String::new();
+    write_element(element, skip, inclusive, &mut Vec::new(), &mut out);
+    out
+}
+
+/// `rendered` holds the (prefix, uri) declarations already written by
+/// output ancestors, innermost last
+fn write_element(
+    element: Node,
+    skip: Option<Node>,
+    inclusive: &[String],
+    rendered: &mut Vec<(String, String)>,
+    out: &mut String,
+) {
+    let input = element.document().input_text();
+
+    // Prefixes are spelled as in the input; roxmltree only resolves them
+    let qname = element_qname(input, element);
+    let prefix = qname.split_once(':').map(|(prefix, _)| prefix).unwrap_or("");
+
+    let mut attributes: Vec<(&str, &str, &str, &str)> = element
+        .attributes()
+        .map(|a| (a.namespace().unwrap_or(""), a.name(), &input[a.range_qname()], a.value()))
+        .collect();
+    attributes.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+
+    // Namespaces visibly used here, plus the inclusive ones in scope
+    let mut prefixes: Vec<&str> = vec![prefix];
+    for (_, _, name, _) in &attributes {
+        if let Some((attribute_prefix, _)) = name.split_once(':') {
+            prefixes.push(attribute_prefix);
+        }
+    }
+    prefixes.extend(inclusive.iter().map(String::as_str));
+    prefixes.retain(|p| *p != "xml");
+    prefixes.sort_unstable();
+    prefixes.dedup();
+
+    let scope = rendered.len();
+    let mut declarations = String::new();
+    for p in prefixes {
+        let uri = element
+            .namespaces()
+            .find(|ns| ns.name().unwrap_or("") == p)
+            .map(|ns| ns.uri())
+            .filter(|uri| !uri.is_empty());
+        let current = rendered
+            .iter()
+            .rev()
+            .find(|(rendered_prefix, _)| rendered_prefix == p)
+            .map(|(_, uri)| uri.as_str());
+        let uri = match (p, uri) {
+            // Undeclaring the default namespace only matters if an
+            // ancestor declared one
+            ("", None) if current.unwrap_or("").is_empty() => continue,
+            ("", None) => "",
+            (_, None) => continue,
+            (_, Some(uri)) => uri,
+        };
+        if current == Some(uri) {
+            continue;
+        }
+        if p.is_empty() {
+            declarations.push_str(&format!(" xmlns=\"{}\"", escape_attribute(uri)));
+        } else {
+            declarations.push_str(&format!(" xmlns:{}=\"{}\"", p, escape_attribute(uri)));
+        }
+        rendered.push((p.to_string(), uri.to_string()));
+    }
+
+    out.push('<');
+    out.push_str(qname);
+    out.push_str(&declarations);
+    for (_, _, name, value) in &attributes {
+        out.push_str(&format!(" {}=\"{}\"", name, escape_attribute(value)));
+    }
+    out.push('>');
+
+    for node in element.children() {
+        match node.node_type() {
+            NodeType::Element if Some(node) != skip => {
+                write_element(node, skip, inclusive, rendered, out)
+            }
+            NodeType::Text => out.push_str(&escape_text(node.text().unwrap_or_default())),
+            NodeType::PI => {
+                if let Some(pi) = node.pi() {
+                    out.push_str("<?");
+                    out.push_str(pi.target);
+                    if let Some(value) = pi.value.filter(|v| !v.is_empty()) {
+                        out.push(' ');
+                        out.push_str(value);
+                    }
+                    out.push_str("?>");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out.push_str("</");
+    out.push_str(qname);
+    out.push('>');
+
+    rendered.truncate(scope);
+}
+
+/// The element's name as written in the start tag
+fn element_qname<'input>(input: &'input str, element: Node) -> &'input str {
+    let tag = &input[element.range().start + 1..];
+    let end = tag
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .unwrap_or(tag.len());
+    &tag[..end]
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\r', "&#xD;")
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('"', "&quot;")
+        .replace('\t', "&#x9;")
+        .replace('\n', "&#xA;")
+        .replace('\r', "&#xD;")
+}
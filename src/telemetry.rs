@@ -0,0 +1,44 @@
+use crate::config::Config;
+use std::borrow::Cow;
+
+// ============================================================================
+// ERROR TELEMETRY (SENTRY)
+// ============================================================================
+// Sends server-side failures to Sentry so we hear about them before users
+// report them:
+// - `InternalError` / `DatabaseError` responses (see the error reporting
+//   middleware, which adds the route, user id and request id)
+// - panics (via Sentry's panic hook)
+//
+// Reporting is off unless SENTRY_DSN is set, so development and tests
+// never send anything.
+
+/// Start the Sentry client, if a DSN is configured
+///
+/// Keep the returned guard alive for the whole program: dropping it
+/// flushes any queued events.
+pub fn init(config: &Config) -> Option<sentry::ClientInitGuard> {
+    let dsn = config.sentry_dsn.as_ref()?.expose_secret();
+
+    let guard = sentry::init((
+        dsn,
+        sentry::ClientOptions {
+            release: sentry::release_name!(),
+            environment: Some(Cow::Owned(config.sentry_environment.clone())),
+            sample_rate: config.sentry_sample_rate,
+            traces_sample_rate: config.sentry_traces_sample_rate,
+            // Never attach cookies, auth headers or IPs to events
+            send_default_pii: false,
+            ..Default::default()
+        },
+    ));
+
+    Some(guard)
+}
+
+/// Whether events are actually being sent
+pub fn is_enabled() -> bool {
+    sentry::Hub::current()
+        .client()
+        .is_some_and(|client| client.is_enabled())
+}
@@ -1,8 +1,27 @@
+use crate::config::{Config, JwtAlgorithm};
+use crate::domain::ids;
+use crate::domain::models::UserRole;
 use crate::error::AppError;
-use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use crate::services::clock::Clock;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPublicKey;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
 use uuid::Uuid;
+use x509_cert::der::DecodePem;
+use x509_cert::spki::{ObjectIdentifier, SubjectPublicKeyInfoOwned};
 
 // ============================================================================
 // JWT CLAIMS
@@ -31,25 +50,60 @@ pub struct Claims {
     
     /// Issued at (Unix timestamp)
     pub iat: usize,   // "iat" is a standard JWT field for "issued at"
+
+    /// JWT ID - unique per token, so one token can be revoked on its own
+    /// (empty in tokens issued before logout existed)
+    #[serde(default)]
+    pub jti: String,
+
+    /// Session ID - the signed-in device this token belongs to (see
+    /// token_service); empty in tokens not handed to a device (the seeder,
+    /// the demo) or issued before sessions existed
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub sid: String,
+
+    /// What the token may do: scopes separated by spaces, like
+    /// "wallet:read wallet:write" (see SCOPES below); empty in tokens
+    /// issued before scopes, which may do everything
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub scope: String,
+
+    /// The user's role when the token was issued ("USER" or "ADMIN"), for
+    /// services that read our tokens. We check the database instead: the
+    /// role may have changed since
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub role: String,
 }
 
 impl Claims {
-    /// Create new claims for a user
+    /// Create new claims for a user, with every scope
     ///
     /// # Arguments
     /// * `user_id` - The user's UUID
+    /// * `session_id` - The device session it's for, if any
+    /// * `role` - The user's role
+    /// * `now` - When the token is issued
     /// * `expiration_hours` - How many hours until the token expires
     ///
     /// # Returns
     /// Claims with user_id and expiration time set
-    pub fn new(user_id: Uuid, expiration_hours: i64) -> Self {
-        let now = Utc::now();
+    pub fn new(
+        user_id: Uuid,
+        session_id: Option<Uuid>,
+        role: UserRole,
+        now: DateTime<Utc>,
+        expiration_hours: i64,
+    ) -> Self {
         let expiration = now + Duration::hours(expiration_hours);
         
         Claims {
             sub: user_id.to_string(),
             exp: expiration.timestamp() as usize,
             iat: now.timestamp() as usize,
+            jti: ids::new_id().to_string(),
+            sid: session_id.map(|id| id.to_string()).unwrap_or_default(),
+            scope: ALL_SCOPES.join(" "),
+            role: role.as_str().to_string(),
         }
     }
     
@@ -58,37 +112,348 @@ impl Claims {
         Uuid::parse_str(&self.sub)
             .map_err(|_| AppError::InvalidToken)
     }
+
+    /// The device session the token belongs to, if it has one
+    pub fn session_id(&self) -> Option<Uuid> {
+        Uuid::parse_str(&self.sid).ok()
+    }
+
+    /// The scopes the token was granted (every one, for tokens from
+    /// before scopes)
+    pub fn scopes(&self) -> Vec<&str> {
+        if self.scope.is_empty() {
+            return ALL_SCOPES.to_vec();
+        }
+        self.scope.split_whitespace().collect()
+    }
+
+    /// Whether the token was granted `scope`
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes().contains(&scope)
+    }
+
+    /// When the token was issued (to the second)
+    pub fn issued_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.iat as i64, 0).unwrap_or(DateTime::<Utc>::MIN_UTC)
+    }
+
+    /// When the token expires (to the second)
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.exp as i64, 0).unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+// ============================================================================
+// SCOPES
+// ============================================================================
+// What a token lets its holder do (the `scope` claim). Signing in grants
+// every scope; a user can mint a narrower token to hand to another app
+// (POST /tokens), e.g. one that can look but not move money. Endpoints
+// that need a scope say so with the `RequireScope` extractor.
+
+/// See balances, history and statements
+pub const SCOPE_WALLET_READ: &str = "wallet:read";
+
+/// Move money (deposits, withdrawals, transfers and payouts) or change
+/// anything else: cards, bank links, expenses, members, settings
+pub const SCOPE_WALLET_WRITE: &str = "wallet:write";
+
+/// Every scope there is, which signing in grants
+pub const ALL_SCOPES: [&str; 2] = [SCOPE_WALLET_READ, SCOPE_WALLET_WRITE];
+
+// ============================================================================
+// SIGNING KEYS
+// ============================================================================
+// Tokens are signed with JWT_SECRET (HS256) unless JWT_ALGORITHM picks a
+// key pair (RS256 or EdDSA). With a key pair only we can sign tokens, but
+// anyone can check them: the public key is published as a JWK Set at
+// /.well-known/jwks.json, so our other services verify tokens without
+// holding a secret that would let them mint their own.
+//
+// Every key has an id that goes in the `kid` header of the tokens it
+// signs: a public key's RFC 7638 thumbprint, or for a secret an HMAC of a
+// fixed string (which says nothing about the secret). Several keys can be
+// active at once (JWT_SECRETS): new tokens are signed with the first,
+// and a token is checked with the key its `kid` names, so a secret can be
+// replaced without signing everyone out. Each key only accepts its own
+// algorithm, so an HS256 token "signed" with a public key can't pass for
+// an RS256 one.
+
+/// ASN.1 object identifier of Ed25519 keys (RFC 8410)
+const ED25519_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+/// One key that signs and checks tokens
+#[derive(Clone)]
+pub struct JwtKey {
+    /// What the `kid` header calls it
+    kid: String,
+    algorithm: Algorithm,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    /// The public key, for the JWK Set (None with HS256: the secret
+    /// stays secret)
+    jwk: Option<Jwk>,
+}
+
+impl JwtKey {
+    /// An HMAC secret (HS256)
+    pub fn hmac(secret: &str) -> Self {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .expect("HMAC takes keys of any length");
+        mac.update(b"jwt key id");
+        let kid = hex::encode(&mac.finalize().into_bytes()[..8]);
+
+        JwtKey {
+            kid,
+            algorithm: Algorithm::HS256,
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+            jwk: None,
+        }
+    }
+
+    /// An RSA key pair (RS256)
+    ///
+    /// `private_pem` is a PKCS#1 or PKCS#8 private key, `public_pem` the
+    /// matching public key ("BEGIN PUBLIC KEY").
+    pub fn rsa(private_pem: &[u8], public_pem: &[u8]) -> Result<Self, AppError> {
+        let public_key = std::str::from_utf8(public_pem)
+            .ok()
+            .and_then(|pem| RsaPublicKey::from_public_key_pem(pem).ok())
+            .ok_or_else(|| AppError::internal("Invalid RSA public key"))?;
+        let n = URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be());
+        let e = URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be());
+        let kid = thumbprint(&format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, e, n));
+
+        Self::key_pair(
+            Algorithm::RS256,
+            EncodingKey::from_rsa_pem(private_pem),
+            DecodingKey::from_rsa_pem(public_pem),
+            Jwk {
+                common: jwk_common(KeyAlgorithm::RS256, kid),
+                algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n,
+                    e,
+                }),
+            },
+        )
+    }
+
+    /// An Ed25519 key pair (EdDSA)
+    ///
+    /// `private_pem` is a PKCS#8 private key ("BEGIN PRIVATE KEY"),
+    /// `public_pem` the matching public key ("BEGIN PUBLIC KEY").
+    pub fn ed25519(private_pem: &[u8], public_pem: &[u8]) -> Result<Self, AppError> {
+        let public_key = SubjectPublicKeyInfoOwned::from_pem(public_pem)
+            .ok()
+            .filter(|info| info.algorithm.oid == ED25519_OID)
+            .ok_or_else(|| AppError::internal("Invalid Ed25519 public key"))?;
+        let x = URL_SAFE_NO_PAD.encode(public_key.subject_public_key.raw_bytes());
+        let kid = thumbprint(&format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, x));
+
+        Self::key_pair(
+            Algorithm::EdDSA,
+            EncodingKey::from_ed_pem(private_pem),
+            DecodingKey::from_ed_pem(public_pem),
+            Jwk {
+                common: jwk_common(KeyAlgorithm::EdDSA, kid),
+                algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x,
+                }),
+            },
+        )
+    }
+
+    /// The id tokens signed with this key carry in their `kid` header
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    /// A key pair, checked by signing a token with one and verifying it
+    /// with the other
+    fn key_pair(
+        algorithm: Algorithm,
+        encoding: jsonwebtoken::errors::Result<EncodingKey>,
+        decoding: jsonwebtoken::errors::Result<DecodingKey>,
+        jwk: Jwk,
+    ) -> Result<Self, AppError> {
+        let encoding = encoding
+            .map_err(|e| AppError::internal(&format!("Invalid JWT private key: {}", e)))?;
+        let decoding = decoding
+            .map_err(|e| AppError::internal(&format!("Invalid JWT public key: {}", e)))?;
+        let kid = jwk.common.key_id.clone().unwrap_or_default();
+        let key = JwtKey { kid, algorithm, encoding, decoding, jwk: Some(jwk) };
+
+        let probe = Claims::new(Uuid::nil(), None, UserRole::User, Utc::now(), 1);
+        let token = encode(&key.header(), &probe, &key.encoding)
+            .map_err(|e| AppError::internal(&format!("Failed to generate token: {}", e)))?;
+        decode::<Claims>(&token, &key.decoding, &key.validation()).map_err(|_| {
+            AppError::internal("JWT_PUBLIC_KEY_FILE is not the public half of JWT_PRIVATE_KEY_FILE")
+        })?;
+
+        Ok(key)
+    }
+
+    fn header(&self) -> Header {
+        let mut header = Header::new(self.algorithm);
+        header.kid = Some(self.kid.clone());
+        header
+    }
+
+    /// Signature checks only; expiry is checked by `validate_token`
+    fn validation(&self) -> Validation {
+        let mut validation = Validation::new(self.algorithm);
+        validation.validate_exp = false;
+        validation
+    }
+}
+
+/// The keys that sign and check tokens: the primary, which signs new
+/// ones, then any older ones still accepted
+#[derive(Clone)]
+pub struct JwtKeys {
+    /// Never empty; the first is the primary
+    keys: Vec<JwtKey>,
+}
+
+impl JwtKeys {
+    /// Sign with `primary`, and also accept tokens signed with `previous`
+    pub fn new(primary: JwtKey, previous: Vec<JwtKey>) -> Self {
+        let mut keys = vec![primary];
+        keys.extend(previous);
+        JwtKeys { keys }
+    }
+
+    /// Sign and check with a single HMAC secret (HS256)
+    pub fn hmac(secret: &str) -> Self {
+        Self::new(JwtKey::hmac(secret), Vec::new())
+    }
+
+    /// The keys JWT_ALGORITHM asks for: every JWT_SECRETS secret with
+    /// HS256, the key pair files otherwise
+    ///
+    /// Fails if a key file can't be read or the two keys don't belong
+    /// together, so a broken key setup stops the server at startup instead
+    /// of signing everyone out.
+    pub fn from_config(config: &Config) -> Result<Self, AppError> {
+        let keys: Vec<JwtKey> = match config.jwt_algorithm {
+            JwtAlgorithm::Hs256 => config
+                .jwt_secrets
+                .iter()
+                .map(|secret| JwtKey::hmac(secret.expose_secret()))
+                .collect(),
+            JwtAlgorithm::Rs256 => vec![JwtKey::rsa(
+                &read_pem(&config.jwt_private_key_file)?,
+                &read_pem(&config.jwt_public_key_file)?,
+            )?],
+            JwtAlgorithm::EdDsa => vec![JwtKey::ed25519(
+                &read_pem(&config.jwt_private_key_file)?,
+                &read_pem(&config.jwt_public_key_file)?,
+            )?],
+        };
+
+        let mut keys = keys.into_iter();
+        let primary = keys.next().ok_or_else(|| AppError::internal("No JWT secret configured"))?;
+        Ok(Self::new(primary, keys.collect()))
+    }
+
+    /// The key new tokens are signed with
+    pub fn primary(&self) -> &JwtKey {
+        &self.keys[0]
+    }
+
+    /// The public keys as a JWK Set (empty with HS256)
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet { keys: self.keys.iter().filter_map(|key| key.jwk.clone()).collect() }
+    }
+
+    /// The keys that may have signed a token with this `kid` header (all
+    /// of them for tokens from before key ids)
+    fn candidates<'a>(&'a self, kid: Option<&'a str>) -> impl Iterator<Item = &'a JwtKey> {
+        self.keys
+            .iter()
+            .filter(move |key| kid.is_none_or(|kid| kid == key.kid))
+    }
+}
+
+fn jwk_common(algorithm: KeyAlgorithm, kid: String) -> CommonParameters {
+    CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_algorithm: Some(algorithm),
+        key_id: Some(kid),
+        ..Default::default()
+    }
+}
+
+/// RFC 7638 thumbprint of a public key, from its required members as
+/// compact JSON in lexicographic order
+fn thumbprint(canonical_jwk: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(canonical_jwk.as_bytes()))
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>, AppError> {
+    fs::read(path).map_err(|e| AppError::internal(&format!("Cannot read {}: {}", path, e)))
 }
 
 // ============================================================================
 // JWT TOKEN FUNCTIONS
 // ============================================================================
 
+/// How long a token is valid for
+pub const TOKEN_LIFETIME_HOURS: i64 = 24;
+
 /// Generate a JWT token for a user
 ///
-/// This creates a signed token that the user can use for authentication.
+/// This creates a signed token that the user can use for authentication,
+/// with every scope.
 ///
 /// # Arguments
 /// * `user_id` - The user's UUID
-/// * `secret` - The JWT secret key from config
+/// * `session_id` - The device session it's handed to (see
+///   `token_service::start_session`), or None for a token that isn't
+/// * `role` - The user's role (informational, see `Claims::role`)
+/// * `keys` - The signing key (`AppState::jwt_keys`)
+/// * `clock` - What time it is (the token is valid for 24 hours from then)
 ///
 /// # Returns
 /// A signed JWT token string
 ///
 /// # Example
-/// ```
-/// let token = generate_token(user_id, &config.jwt_secret)?;
+/// ```ignore
+/// let token = generate_token(
+///     user_id,
+///     None,
+///     UserRole::User,
+///     &state.jwt_keys,
+///     &SystemClock,
+/// )?;
 /// // Returns something like: "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9..."
 /// ```
-pub fn generate_token(user_id: Uuid, secret: &str) -> Result<String, AppError> {
+pub fn generate_token(
+    user_id: Uuid,
+    session_id: Option<Uuid>,
+    role: UserRole,
+    keys: &JwtKeys,
+    clock: &dyn Clock,
+) -> Result<String, AppError> {
     // Create claims with 24 hour expiration
-    let claims = Claims::new(user_id, 24);
+    let claims = Claims::new(user_id, session_id, role, clock.now(), TOKEN_LIFETIME_HOURS);
     
-    // Encode the token with our secret
+    encode_token(&claims, keys)
+}
+
+/// Sign `claims` as a JWT, e.g. ones narrowed to fewer scopes than
+/// `generate_token` grants
+pub fn encode_token(claims: &Claims, keys: &JwtKeys) -> Result<String, AppError> {
+    // Encode the token with our signing key
+    let key = keys.primary();
     let token = encode(
-        &Header::default(),                    // Use default header (HS256 algorithm)
-        &claims,                               // Our claims data
-        &EncodingKey::from_secret(secret.as_bytes()), // Our secret key
+        &key.header(),  // The algorithm, and which key signed it
+        claims,         // Our claims data
+        &key.encoding,  // Our secret or private key
     )
     .map_err(|e| AppError::internal(&format!("Failed to generate token: {}", e)))?;
     
@@ -101,34 +466,40 @@ pub fn generate_token(user_id: Uuid, secret: &str) -> Result<String, AppError> {
 ///
 /// # Arguments
 /// * `token` - The JWT token string
-/// * `secret` - The JWT secret key from config
+/// * `keys` - The signing key (`AppState::jwt_keys`)
+/// * `clock` - What time it is, to check expiry against
 ///
 /// # Returns
 /// The claims if valid, or an error if invalid/expired
 ///
 /// # Example
-/// ```
-/// let claims = validate_token(&token, &config.jwt_secret)?;
+/// ```ignore
+/// let claims = validate_token(&token, &state.jwt_keys, state.clock.as_ref())?;
 /// let user_id = claims.user_id()?;
 /// ```
-pub fn validate_token(token: &str, secret: &str) -> Result<Claims, AppError> {
-    // Decode and validate the token
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(), // Uses default validation (checks expiration, signature)
-    )
-    .map_err(|e| {
-        // Different error messages based on what went wrong
-        match e.kind() {
-            jsonwebtoken::errors::ErrorKind::ExpiredSignature => {
-                AppError::InvalidToken // Token expired
-            }
-            _ => AppError::InvalidToken // Invalid signature or malformed token
-        }
-    })?;
-    
-    Ok(token_data.claims)
+pub fn validate_token(token: &str, keys: &JwtKeys, clock: &dyn Clock) -> Result<Claims, AppError> {
+    // The header names the key that signed it
+    let header = decode_header(token).map_err(|_| AppError::InvalidToken)?;
+
+    // The library checks the signature; expiry is checked below against
+    // our clock, so tests can move time forward past it
+    let (claims, leeway) = keys
+        .candidates(header.kid.as_deref())
+        .find_map(|key| {
+            let validation = key.validation();
+            decode::<Claims>(token, &key.decoding, &validation)
+                .ok()
+                .map(|token_data| (token_data.claims, validation.leeway))
+        })
+        .ok_or(AppError::InvalidToken)?; // Invalid signature, unknown key or malformed token
+
+    // Same allowance for clock skew as the library's own check
+    let now = clock.now().timestamp();
+    if (claims.exp as i64) < now - leeway as i64 {
+        return Err(AppError::InvalidToken); // Token expired
+    }
+
+    Ok(claims)
 }
 
 // ============================================================================
@@ -162,7 +533,7 @@ use argon2::{
 /// A hashed password string safe to store in the database
 ///
 /// # Example
-/// ```
+/// ```ignore
 /// let hash = hash_password("mypassword123")?;
 /// // Returns: "$argon2id$v=19$m=19456,t=2,p=1$..."
 /// ```
@@ -188,7 +559,7 @@ pub fn hash_password(password: &str) -> Result<String, AppError> {
 /// Ok(()) if password matches, Err if it doesn't
 ///
 /// # Example
-/// ```
+/// ```ignore
 /// verify_password("mypassword123", &user.password_hash)?;
 /// // Returns Ok(()) if correct, Err(AppError::InvalidCredentials) if wrong
 /// ```
@@ -226,7 +597,7 @@ async fn login_user(email: &str, password: &str) -> Result<String, AppError> {
     verify_password(password, &user.password_hash)?;
     
     // Generate JWT token
-    let token = generate_token(user.id, &config.jwt_secret)?;
+    let token = generate_token(user.id, None, UserRole::User, &state.jwt_keys, &SystemClock)?;
     
     Ok(token)
 }
@@ -234,7 +605,7 @@ async fn login_user(email: &str, password: &str) -> Result<String, AppError> {
 // Example 3: Protected Route
 async fn get_user_profile(token: &str) -> Result<User, AppError> {
     // Validate token
-    let claims = validate_token(token, &config.jwt_secret)?;
+    let claims = validate_token(token, &state.jwt_keys, &SystemClock)?;
     
     // Get user ID from claims
     let user_id = claims.user_id()?;
@@ -0,0 +1,218 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use rust_decimal::Decimal;
+use serde_json::json;
+use uuid::Uuid;
+
+// ============================================================================
+// ADMIN API
+// ============================================================================
+// Looking users up, freezing their accounts, reversing their transactions,
+// and the audit log every change made through /api/admin ends up in.
+
+#[tokio::test]
+async fn admins_find_users_and_see_their_wallets() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    app.register("bob@example.com").await;
+    let admin = app.register_admin("admin@example.com").await;
+    app.deposit(&alice, "40.00").await;
+
+    let (status, body) = app.get("/api/admin/users?q=ALICE", Some(&admin.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let users = body.as_array().unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0]["email"], "alice@example.com");
+    assert_eq!(users[0]["role"], "USER");
+
+    let path = format!("/api/admin/users/{}/wallets", alice.id);
+    let (status, body) = app.get(&path, Some(&admin.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body[0]["balance"], "40.00");
+
+    // Not for everyone
+    let (status, _) = app.get("/api/admin/users", Some(&alice.token)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn reversals_are_applied_once_and_audited() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let admin = app.register_admin("admin@example.com").await;
+    app.deposit(&alice, "40.00").await;
+
+    let (_, history) = app.get("/api/transactions", Some(&alice.token)).await;
+    let deposit = history["transactions"][0]["id"].as_str().unwrap().to_string();
+    let path = format!("/api/admin/transactions/{}/reverse", deposit);
+
+    // Users can't reverse their own
+    let (status, _) = app.post_json(&path, Some(&alice.token), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = app.post_json(&path, Some(&admin.token), json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["status"], "REVERSED");
+    assert_eq!(app.balance(&alice).await, Decimal::ZERO);
+
+    let (status, body) = app.post_json(&path, Some(&admin.token), json!({})).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(app.balance(&alice).await, Decimal::ZERO);
+
+    // Both of the admin's attempts are in the log, newest first; Alice's isn't
+    let (status, body) = app.get("/api/admin/audit-log", Some(&admin.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let entries = body.as_array().unwrap();
+    assert_eq!(entries.len(), 2, "{}", body);
+    assert_eq!(entries[0]["path"], path);
+    assert_eq!(entries[0]["status"], 422);
+    assert_eq!(entries[1]["status"], 200);
+    assert_eq!(entries[1]["method"], "POST");
+    assert_eq!(entries[1]["admin_email"], "admin@example.com");
+}
+
+#[tokio::test]
+async fn frozen_accounts_can_look_but_not_move_money() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    let admin = app.register_admin("admin@example.com").await;
+    app.deposit(&alice, "40.00").await;
+    app.deposit(&bob, "40.00").await;
+
+    let (status, body) = app
+        .post_json(&format!("/api/admin/users/{}/freeze", alice.id), Some(&admin.token), json!({}))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["account_status"], "FROZEN");
+
+    let (status, body) = app
+        .post_json("/api/wallet/withdraw", Some(&alice.token), json!({ "amount": "5.00" }))
+        .await;
+    assert_eq!(status, StatusCode::LOCKED, "{}", body);
+    assert_eq!(body["code"], "ACCOUNT_FROZEN");
+    let (status, _) = app
+        .post_json(
+            "/api/wallet/transfer",
+            Some(&alice.token),
+            json!({ "recipient_email": bob.email, "amount": "5.00" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::LOCKED);
+
+    // Nor through the other ways money leaves a wallet
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/payouts",
+            Some(&alice.token),
+            json!({ "bank_account_id": Uuid::new_v4(), "amount": "5.00" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::LOCKED, "{}", body);
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/international-transfers",
+            Some(&alice.token),
+            json!({
+                "beneficiary_name": "Hans Muster",
+                "iban": "DE89370400440532013000",
+                "bic": "COBADEFFXXX",
+                "amount": "5.00",
+                "destination_currency": "EUR",
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::LOCKED, "{}", body);
+    assert_eq!(app.balance(&alice).await, "40.00".parse::<Decimal>().unwrap());
+
+    // Still signed in, and still able to receive
+    let (status, _) = app.get("/api/transactions", Some(&alice.token)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/transfer",
+            Some(&bob.token),
+            json!({ "recipient_email": alice.email, "amount": "5.00" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(app.balance(&alice).await, "45.00".parse::<Decimal>().unwrap());
+
+    let (status, body) = app
+        .post_json(&format!("/api/admin/users/{}/unfreeze", alice.id), Some(&admin.token), json!({}))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    app.deposit(&alice, "5.00").await;
+    assert_eq!(app.balance(&alice).await, "50.00".parse::<Decimal>().unwrap());
+}
+
+#[tokio::test]
+async fn balances_reconcile_with_the_ledger_and_tampering_shows() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    let admin = app.register_admin("admin@example.com").await;
+
+    app.deposit(&alice, "40.00").await;
+    let (status, body) = app
+        .post_json("/api/wallet/withdraw", Some(&alice.token), json!({ "amount": "5.00" }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/transfer",
+            Some(&alice.token),
+            json!({ "recipient_email": bob.email, "amount": "10.00" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    // Converted through the exchange account
+    let (status, body) =
+        app.post_json("/api/wallets", Some(&bob.token), json!({ "currency": "EUR" })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/deposit",
+            Some(&bob.token),
+            json!({ "amount": "10.00", "currency": "EUR" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/transfer",
+            Some(&bob.token),
+            json!({ "recipient_email": alice.email, "amount": "4.60", "currency": "EUR" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (_, wallets) = app
+        .get(&format!("/api/admin/users/{}/wallets", alice.id), Some(&admin.token))
+        .await;
+    let wallet_id = wallets[0]["id"].as_str().unwrap().to_string();
+    let path = format!("/api/admin/wallets/{}/verify", wallet_id);
+
+    let (status, body) = app.get(&path, Some(&admin.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["consistent"], true, "{}", body);
+    assert_eq!(body["ledger_balance"], "30.00");
+    assert_eq!(body["ledger"]["postings"], 4);
+
+    // Entries can't be rewritten, and an edited balance no longer matches
+    let tampered = sqlx::query("UPDATE ledger_entries SET credit = credit + 1")
+        .execute(&app.pool)
+        .await;
+    assert!(tampered.is_err());
+    sqlx::query("UPDATE wallets SET balance = balance + 1 WHERE id = $1::uuid")
+        .bind(&wallet_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let (_, body) = app.get(&path, Some(&admin.token)).await;
+    assert_eq!(body["consistent"], false, "{}", body);
+    assert_eq!(body["difference"], "1.00");
+}
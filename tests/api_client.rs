@@ -0,0 +1,90 @@
+mod common;
+
+use std::time::Duration;
+
+use api_client::api_types::{
+    CreateUserRequest, DepositRequest, ErrorCode, HistoryQuery, LoginRequest, TransferRequest,
+};
+use api_client::ApiClient;
+use common::TestApp;
+use rust_decimal::Decimal;
+
+// ============================================================================
+// TYPED API CLIENT
+// ============================================================================
+// The api-client crate against the real server: the shared types round-trip
+// and error codes come back as `ErrorCode`s.
+
+fn signup(email: &str) -> CreateUserRequest {
+    CreateUserRequest {
+        email: email.to_string(),
+        password: "correct-horse-battery".to_string(),
+        full_name: "Client Test".to_string(),
+        referral_code: None,
+    }
+}
+
+#[tokio::test]
+async fn client_moves_money_with_the_shared_types() {
+    let app = TestApp::spawn().await;
+    let addr = app.serve().await;
+    let client = ApiClient::new(&format!("http://{}", addr), Duration::from_secs(10)).unwrap();
+
+    let alice = client.register(&signup("alice@example.com")).await.unwrap();
+    client.register(&signup("bob@example.com")).await.unwrap();
+    let alice = client
+        .login(&LoginRequest {
+            email: alice.email,
+            password: "correct-horse-battery".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let deposit = DepositRequest { amount: Decimal::new(5000, 2), currency: None };
+    client.deposit(&alice, &deposit).await.unwrap();
+    let transfer = TransferRequest {
+        recipient_email: "bob@example.com".to_string(),
+        amount: Decimal::new(1250, 2),
+        currency: None,
+    };
+    let wallet = client.transfer(&alice, &transfer).await.unwrap();
+    assert_eq!(wallet.balance, Decimal::new(3750, 2));
+
+    let page = client
+        .history(&alice, &HistoryQuery { limit: Some(1), ..Default::default() })
+        .await
+        .unwrap();
+    assert_eq!(page.transactions.len(), 1);
+    assert_eq!(page.transactions[0].transaction_type, "TRANSFER");
+    assert_eq!(page.total, 2);
+
+    let older = client
+        .history(&alice, &HistoryQuery { limit: Some(1), cursor: page.next_cursor, ..Default::default() })
+        .await
+        .unwrap();
+    assert_eq!(older.transactions[0].transaction_type, "DEPOSIT");
+}
+
+#[tokio::test]
+async fn errors_carry_the_server_error_code() {
+    let app = TestApp::spawn().await;
+    let addr = app.serve().await;
+    let client = ApiClient::new(&format!("http://{}", addr), Duration::from_secs(10)).unwrap();
+    let alice = client.register(&signup("alice@example.com")).await.unwrap();
+    client.register(&signup("bob@example.com")).await.unwrap();
+
+    let transfer = TransferRequest {
+        recipient_email: "bob@example.com".to_string(),
+        amount: Decimal::new(100, 0),
+        currency: None,
+    };
+    let error = client.transfer(&alice, &transfer).await.unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::InsufficientBalance));
+
+    let error = client.register(&signup("not-an-email")).await.unwrap_err();
+    assert_eq!(error.code(), Some(ErrorCode::ValidationError));
+    let api_client::ClientError::Status { fields, .. } = error else {
+        panic!("expected an error response");
+    };
+    assert_eq!(fields[0].field, "email");
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::TestApp;
+use my_fintech_app::repository::bank_repo;
+
+// ============================================================================
+// BANK WEBHOOKS
+// ============================================================================
+// A signed provider webhook is good for a few minutes, and only once: the
+// receipt log turns away the same signature sent again.
+
+#[tokio::test]
+async fn bank_webhooks_are_only_accepted_once() {
+    let app = TestApp::spawn().await;
+    let now = Utc::now();
+    let window = now - Duration::minutes(5);
+
+    let first = bank_repo::record_webhook_receipt(&app.pool, "plaid:1:abc", now, window)
+        .await
+        .unwrap();
+    assert!(first);
+    let replay = bank_repo::record_webhook_receipt(&app.pool, "plaid:1:abc", now, window)
+        .await
+        .unwrap();
+    assert!(!replay, "the same signature is refused the second time");
+    let other = bank_repo::record_webhook_receipt(&app.pool, "plaid:1:def", now, window)
+        .await
+        .unwrap();
+    assert!(other, "another body signed at the same second is its own webhook");
+
+    // Once past the window the receipt is forgotten (the age check refuses
+    // such a webhook before it gets here)
+    let later = now + Duration::minutes(6);
+    bank_repo::record_webhook_receipt(&app.pool, "plaid:2:abc", later, later - Duration::minutes(5))
+        .await
+        .unwrap();
+    let kept: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM bank_webhook_receipts")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(kept, 1);
+}
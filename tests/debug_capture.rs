@@ -0,0 +1,139 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use my_fintech_app::services::debug_capture::{sanitize_body, sanitize_request_body, sanitize_uri};
+use serde_json::json;
+
+// ============================================================================
+// DEBUG CAPTURE
+// ============================================================================
+// An admin turns on capture for a route or a user and reads back what was
+// sent and answered, with secrets redacted.
+
+#[tokio::test]
+async fn route_capture_records_requests_with_secrets_redacted() {
+    let app = TestApp::spawn().await;
+    let admin = app.register_admin("admin@example.com").await;
+    app.register("alice@example.com").await;
+
+    let (status, target) = app
+        .post_json(
+            "/api/admin/debug-capture/targets",
+            Some(&admin.token),
+            json!({ "route": "/api/login", "minutes": 5 }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", target);
+
+    let (status, _) = app
+        .post_json(
+            "/api/login",
+            None,
+            json!({ "email": "alice@example.com", "password": "correct-horse-battery" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app.get("/api/admin/debug-capture", Some(&admin.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let exchanges = body["exchanges"].as_array().unwrap();
+    assert_eq!(exchanges.len(), 1, "only the login is captured: {}", body);
+
+    let login = &exchanges[0];
+    assert_eq!(login["target_id"], target["id"]);
+    assert_eq!(login["uri"], "/api/login");
+    assert_eq!(login["status"], 200);
+    let request: serde_json::Value =
+        serde_json::from_str(login["request_body"].as_str().unwrap()).unwrap();
+    assert_eq!(request["email"], "alice@example.com");
+    assert_eq!(request["password"], "[REDACTED]");
+    let response: serde_json::Value =
+        serde_json::from_str(login["response_body"].as_str().unwrap()).unwrap();
+    assert_eq!(response["token"], "[REDACTED]");
+    assert_eq!(response["user"]["email"], "alice@example.com");
+
+    // Stopped captures record nothing more
+    let target_path = format!(
+        "/api/admin/debug-capture/targets/{}",
+        target["id"].as_str().unwrap()
+    );
+    let (status, _) = app.send(Method::DELETE, &target_path, Some(&admin.token), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    app.post_json(
+        "/api/login",
+        None,
+        json!({ "email": "alice@example.com", "password": "correct-horse-battery" }),
+    )
+    .await;
+    let (_, body) = app.get("/api/admin/debug-capture", Some(&admin.token)).await;
+    assert_eq!(body["exchanges"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn user_capture_only_records_that_user() {
+    let app = TestApp::spawn().await;
+    let admin = app.register_admin("admin@example.com").await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+
+    let (status, _) = app
+        .post_json(
+            "/api/admin/debug-capture/targets",
+            Some(&admin.token),
+            json!({ "user_id": alice.id }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    app.get("/api/wallet", Some(&alice.token)).await;
+    app.get("/api/wallet", Some(&bob.token)).await;
+
+    let (_, body) = app.get("/api/admin/debug-capture", Some(&admin.token)).await;
+    let exchanges = body["exchanges"].as_array().unwrap();
+    assert_eq!(exchanges.len(), 1, "{}", body);
+    assert_eq!(exchanges[0]["user_id"], alice.id.to_string());
+    assert_eq!(exchanges[0]["uri"], "/api/wallet");
+    let authorization = exchanges[0]["request_headers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|h| h["name"] == "authorization")
+        .unwrap();
+    assert_eq!(authorization["value"], "Bearer [REDACTED]");
+
+    // Only admins can see captures
+    let (status, _) = app.get("/api/admin/debug-capture", Some(&alice.token)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn one_time_codes_in_requests_are_redacted() {
+    // Transfer confirmation and email verification codes
+    let body = sanitize_request_body(Some("application/json"), br#"{"code":"482913"}"#).unwrap();
+    assert_eq!(body, r#"{"code":"[REDACTED]"}"#);
+    let body = sanitize_request_body(
+        Some("application/x-www-form-urlencoded"),
+        b"code=482913&next=%2Fdashboard",
+    )
+    .unwrap();
+    assert_eq!(body, "code=[REDACTED]&next=%2Fdashboard");
+
+    // The OIDC callback's authorization code
+    assert_eq!(
+        sanitize_uri("/sso/acme/callback", Some("code=SplxlOBeZQQYbYS6WxSbIA&state=af0ifjsldkj")),
+        "/sso/acme/callback?code=[REDACTED]&state=af0ifjsldkj"
+    );
+
+    // Only the exact name: a currency or country code is kept
+    let body = sanitize_request_body(
+        Some("application/json"),
+        br#"{"currency_code":"EUR","country_code":"DE"}"#,
+    )
+    .unwrap();
+    assert_eq!(body, r#"{"country_code":"DE","currency_code":"EUR"}"#);
+
+    // And an error response's code is what's being debugged
+    let body = sanitize_body(Some("application/json"), br#"{"code":"INVALID_CODE"}"#).unwrap();
+    assert_eq!(body, r#"{"code":"INVALID_CODE"}"#);
+}
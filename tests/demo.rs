@@ -0,0 +1,88 @@
+#![cfg(feature = "memory-repo")]
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use http_body_util::BodyExt;
+use my_fintech_app::demo::{self, DemoState};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+// ============================================================================
+// IN-MEMORY DEMO
+// ============================================================================
+// `--demo` serves the core API without Postgres:
+//
+//   cargo test --features memory-repo --test demo
+
+async fn send(
+    state: &DemoState,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+    let request = match body {
+        Some(body) => request
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap();
+
+    let response = demo::router(state.clone()).oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+async fn login(state: &DemoState, email: &str) -> String {
+    let (status, body) = send(
+        state,
+        "POST",
+        "/api/login",
+        None,
+        Some(json!({ "email": email, "password": "password123" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body["token"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn demo_users_are_seeded_and_can_move_money() {
+    let state = DemoState::new();
+    demo::seed(&state).await.unwrap();
+
+    // 2500.00 - 42.50 + 120.00
+    let alice = login(&state, "alice@example.com").await;
+    let (status, wallet) = send(&state, "GET", "/api/wallet", Some(&alice), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(wallet["balance"], "2577.50");
+
+    let (status, wallet) = send(
+        &state,
+        "POST",
+        "/api/wallet/transfer",
+        Some(&alice),
+        Some(json!({ "recipient_email": "bob@example.com", "amount": "77.50" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", wallet);
+    assert_eq!(wallet["balance"], "2500.00");
+
+    let (status, page) = send(&state, "GET", "/api/transactions?limit=10", Some(&alice), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["transactions"].as_array().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn wallet_needs_a_token() {
+    let state = DemoState::new();
+
+    let (status, _) = send(&state, "GET", "/api/wallet", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
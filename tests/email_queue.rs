@@ -0,0 +1,113 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::TestApp;
+use my_fintech_app::domain::models::Currency;
+use my_fintech_app::i18n::Locale;
+use my_fintech_app::services::email_service::{DeliveryError, RetrySummary};
+use rust_decimal::Decimal;
+
+async fn send_receipt(app: &TestApp, to: &str) {
+    app.state
+        .email_service
+        .send_deposit_received(to, Locale::En, Decimal::new(1000, 2), Currency::Usd, Decimal::new(1000, 2))
+        .await;
+}
+
+async fn count(app: &TestApp, table: &str, to: &str) -> i64 {
+    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {} WHERE to_address = $1", table))
+        .bind(to)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn failed_emails_are_queued_and_retried() {
+    let app = TestApp::spawn().await;
+    let to = "alice@example.com";
+    app.outbox.fail_for(to, DeliveryError::Transient("connection refused".into()));
+
+    send_receipt(&app, to).await;
+    assert!(app.outbox.sent_to(to).is_empty());
+    assert_eq!(count(&app, "email_queue", to).await, 1);
+
+    // Not due yet
+    let summary = app.state.email_service.retry_due(Utc::now()).await.unwrap();
+    assert_eq!(summary, RetrySummary::default());
+
+    app.outbox.recover(to);
+    let summary = app
+        .state
+        .email_service
+        .retry_due(Utc::now() + Duration::minutes(2))
+        .await
+        .unwrap();
+    assert_eq!(summary.delivered, 1);
+    assert_eq!(app.outbox.sent_to(to).len(), 1);
+    assert_eq!(count(&app, "email_queue", to).await, 0);
+}
+
+#[tokio::test]
+async fn delivered_emails_leave_nothing_queued() {
+    let app = TestApp::spawn().await;
+    send_receipt(&app, "alice@example.com").await;
+
+    assert_eq!(app.outbox.sent_to("alice@example.com").len(), 1);
+    assert_eq!(count(&app, "email_queue", "alice@example.com").await, 0);
+}
+
+#[tokio::test]
+async fn emails_are_given_up_after_too_many_tries() {
+    let app = TestApp::spawn().await;
+    let to = "alice@example.com";
+    app.outbox.fail_for(to, DeliveryError::Transient("connection refused".into()));
+    send_receipt(&app, to).await;
+
+    // The waits double, so a day later is always due
+    let mut now = Utc::now();
+    let mut tries = 1;
+    loop {
+        now += Duration::days(1);
+        let summary = app.state.email_service.retry_due(now).await.unwrap();
+        tries += 1;
+        if summary.dead_lettered == 1 {
+            break;
+        }
+        assert_eq!(summary.retrying, 1);
+        assert!(tries < 20, "never given up");
+    }
+
+    assert_eq!(tries, 10);
+    assert_eq!(count(&app, "email_queue", to).await, 0);
+    assert_eq!(count(&app, "email_dead_letters", to).await, 1);
+    let attempts: i32 =
+        sqlx::query_scalar("SELECT attempts FROM email_dead_letters WHERE to_address = $1")
+            .bind(to)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(attempts, 10);
+}
+
+#[tokio::test]
+async fn hard_bounces_suppress_the_address() {
+    let app = TestApp::spawn().await;
+    let to = "nobody@example.com";
+    app.outbox.fail_for(to, DeliveryError::Permanent("550 no such user".into()));
+
+    send_receipt(&app, to).await;
+    assert_eq!(count(&app, "email_queue", to).await, 0);
+    assert_eq!(count(&app, "email_dead_letters", to).await, 1);
+
+    // Even once the transport would take it, nothing more is sent
+    app.outbox.recover(to);
+    send_receipt(&app, "Nobody@Example.com").await;
+    send_receipt(&app, to).await;
+    assert!(app.outbox.sent_to(to).is_empty());
+    assert_eq!(count(&app, "email_queue", to).await, 0);
+
+    // Other addresses aren't affected
+    send_receipt(&app, "alice@example.com").await;
+    assert_eq!(app.outbox.sent_to("alice@example.com").len(), 1);
+}
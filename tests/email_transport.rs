@@ -0,0 +1,129 @@
+use std::sync::{Arc, Mutex};
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::post;
+use axum::{Json, Router};
+use my_fintech_app::services::email_service::{DeliveryError, Email, EmailTransport, SesTransport};
+use serde_json::{json, Value};
+
+/// What the fake SES saw: the Authorization header and the JSON body
+type Received = Arc<Mutex<Vec<(String, Value)>>>;
+
+/// Answer like SES: accepted, unless the recipient is throttled@ (429)
+async fn send_email(
+    State(received): State<Received>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> impl IntoResponse {
+    let authorization = headers["authorization"].to_str().unwrap().to_string();
+    let to = body["Destination"]["ToAddresses"][0]
+        .as_str()
+        .unwrap()
+        .to_string();
+    received.lock().unwrap().push((authorization, body));
+
+    if to.starts_with("throttled@") {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [("x-amzn-ErrorType", "TooManyRequestsException:")],
+            Json(json!({ "message": "Maximum sending rate exceeded." })),
+        )
+            .into_response();
+    }
+    Json(json!({ "MessageId": "0100018c-test" })).into_response()
+}
+
+/// Start a fake SES API; returns its endpoint and what it receives
+async fn fake_ses() -> (String, Received) {
+    let received = Received::default();
+    let router = Router::new()
+        .route("/v2/email/outbound-emails", post(send_email))
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    (format!("http://{}", addr), received)
+}
+
+fn transport(endpoint: &str) -> SesTransport {
+    SesTransport::new(
+        Some(endpoint),
+        "eu-west-1",
+        "AKIDEXAMPLE".to_string(),
+        "secret".to_string().into(),
+        "noreply@example.com".to_string(),
+    )
+}
+
+fn email(to: &str) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: "MyFintechApp: Money received".to_string(),
+        text: "You received 12.50 USD.".to_string(),
+        html: Some("<p>You received 12.50 USD.</p>".to_string()),
+    }
+}
+
+#[tokio::test]
+async fn ses_sends_both_parts_signed() {
+    let (endpoint, received) = fake_ses().await;
+
+    transport(&endpoint)
+        .send(&email("alice@example.com"))
+        .await
+        .unwrap();
+
+    let received = received.lock().unwrap();
+    let (authorization, body) = &received[0];
+    assert!(
+        authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"),
+        "{}",
+        authorization
+    );
+    assert!(
+        authorization.contains("/eu-west-1/ses/aws4_request"),
+        "{}",
+        authorization
+    );
+    assert_eq!(body["FromEmailAddress"], "noreply@example.com");
+    assert_eq!(
+        body["Destination"]["ToAddresses"],
+        json!(["alice@example.com"])
+    );
+    let simple = &body["Content"]["Simple"];
+    assert_eq!(simple["Subject"]["Data"], "MyFintechApp: Money received");
+    assert_eq!(simple["Body"]["Text"]["Data"], "You received 12.50 USD.");
+    assert_eq!(
+        simple["Body"]["Html"]["Data"],
+        "<p>You received 12.50 USD.</p>"
+    );
+}
+
+#[tokio::test]
+async fn ses_refusals_are_retried() {
+    let (endpoint, _) = fake_ses().await;
+
+    let error = transport(&endpoint)
+        .send(&email("throttled@example.com"))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&error, DeliveryError::Transient(e) if e.contains("TooManyRequestsException")),
+        "{:?}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn ses_unreachable_is_retried_and_bad_addresses_are_not() {
+    // Nothing listens on port 9
+    let ses = transport("http://127.0.0.1:9");
+    let error = ses.send(&email("alice@example.com")).await.unwrap_err();
+    assert!(matches!(error, DeliveryError::Transient(_)), "{:?}", error);
+
+    let error = ses.send(&email("not an address")).await.unwrap_err();
+    assert!(matches!(error, DeliveryError::Permanent(_)), "{:?}", error);
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+
+// ============================================================================
+// HEALTH AND VERSION
+// ============================================================================
+// Unauthenticated endpoints for probes and deploy checks.
+
+#[tokio::test]
+async fn version_names_the_build() {
+    let app = TestApp::spawn().await;
+
+    let (status, body) = app.get("/api/version", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(body["git_sha"], my_fintech_app::build_info::GIT_SHA);
+    assert!(body["built_at"].is_string());
+    assert!(body["cargo_features"].is_array());
+    assert_eq!(body["features"]["transfers_enabled"], true);
+}
+
+#[tokio::test]
+async fn probes_report_liveness_and_readiness() {
+    let app = TestApp::spawn().await;
+
+    let (status, body) = app.get("/healthz", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+
+    let (status, body) = app.get("/readyz", None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["checks"]["database"], "ok");
+    assert!(body["checks"].get("smtp").is_none());
+
+    // Without a database we're still alive, but not ready
+    app.pool.close().await;
+    let (status, _) = app.get("/healthz", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = app.get("/readyz", None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "not_ready");
+    assert_eq!(body["failed"], serde_json::json!(["database"]));
+}
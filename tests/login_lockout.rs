@@ -0,0 +1,120 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use chrono::Duration;
+use common::{ManualClock, TestApp};
+use serde_json::json;
+
+// ============================================================================
+// LOGIN LOCKOUT
+// ============================================================================
+// Wrong passwords for an address make it wait longer and longer, then lock
+// it until the emailed unlock link (or a password reset) is used.
+
+async fn app_with_clock() -> (TestApp, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock::new());
+    let app = TestApp::spawn_with(|builder| builder.clock(clock.clone())).await;
+    (app, clock)
+}
+
+async fn login(app: &TestApp, email: &str, password: &str) -> (StatusCode, serde_json::Value) {
+    app.post_json("/api/login", None, json!({ "email": email, "password": password }))
+        .await
+}
+
+/// Guess wrong until the address is locked (waiting out each back-off)
+async fn guess_until_locked(app: &TestApp, clock: &ManualClock, email: &str) {
+    for _ in 0..10 {
+        let (status, body) = login(app, email, "wrong-password").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", body);
+        clock.advance(Duration::minutes(5));
+    }
+}
+
+#[tokio::test]
+async fn wrong_passwords_back_off_exponentially() {
+    let (app, clock) = app_with_clock().await;
+    app.register("alice@example.com").await;
+
+    // The first three are free
+    for _ in 0..3 {
+        let (status, _) = login(&app, "alice@example.com", "wrong-password").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    // Then the right password has to wait too
+    let (status, body) = login(&app, "alice@example.com", "correct-horse-battery").await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["code"], "TOO_MANY_ATTEMPTS");
+    assert_eq!(body["details"]["retry_after_secs"], 1);
+
+    // 1 second, then 2
+    clock.advance(Duration::seconds(1));
+    let (status, _) = login(&app, "alice@example.com", "wrong-password").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    clock.advance(Duration::seconds(1));
+    let (status, _) = login(&app, "alice@example.com", "correct-horse-battery").await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // A good sign-in after the wait starts the count again
+    clock.advance(Duration::seconds(1));
+    let (status, _) = login(&app, "alice@example.com", "correct-horse-battery").await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = login(&app, "alice@example.com", "wrong-password").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = login(&app, "alice@example.com", "correct-horse-battery").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn too_many_failures_lock_the_account_until_the_emailed_link_is_used() {
+    let (app, clock) = app_with_clock().await;
+    app.register("alice@example.com").await;
+    guess_until_locked(&app, &clock, "alice@example.com").await;
+
+    let (status, body) = login(&app, "alice@example.com", "correct-horse-battery").await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["code"], "TOO_MANY_ATTEMPTS");
+    assert!(body["error"].as_str().unwrap().contains("locked"), "{}", body);
+    assert_eq!(body["details"]["locked"], true);
+
+    let email = app.outbox.wait_for_subject("alice@example.com", "locked").await;
+    let token = email
+        .body
+        .lines()
+        .map(str::trim)
+        .find(|line| line.len() == 64 && line.bytes().all(|b| b.is_ascii_hexdigit()))
+        .expect("an unlock code in the email")
+        .to_string();
+    assert!(email.body.contains(&format!("/account/unlock?token={}", token)));
+
+    let (status, page) = app.get(&format!("/account/unlock?token={}", token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.as_str().unwrap().contains(&token));
+
+    let (status, _) = app
+        .post_json("/api/account/unlock", None, json!({ "token": token }))
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = login(&app, "alice@example.com", "correct-horse-battery").await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The link only works once
+    let (status, _) = app
+        .post_json("/api/account/unlock", None, json!({ "token": token }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn unknown_addresses_lock_the_same_way_without_an_email() {
+    let (app, clock) = app_with_clock().await;
+    guess_until_locked(&app, &clock, "nobody@example.com").await;
+
+    let (status, body) = login(&app, "nobody@example.com", "wrong-password").await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["code"], "TOO_MANY_ATTEMPTS");
+    assert!(app.outbox.sent_to("nobody@example.com").is_empty());
+}
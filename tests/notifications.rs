@@ -0,0 +1,495 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use common::{ManualClock, TestApp, TestUser};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+// ============================================================================
+// LIVE NOTIFICATIONS
+// ============================================================================
+// Transfer alerts over the WebSocket, including the ones a client missed
+// while it was disconnected, on every connection a user has open, balance
+// updates for the dashboard, admin announcements to everyone, the
+// heartbeat that closes idle ones, and the same messages as Server-Sent
+// Events.
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Open /api/ws as `user`, resuming after `since` when given
+async fn connect(addr: SocketAddr, user: &TestUser, since: Option<i64>) -> Socket {
+    let query = since.map(|since| format!("?since={}", since)).unwrap_or_default();
+    let mut request = format!("ws://{}/api/ws{}", addr, query)
+        .into_client_request()
+        .unwrap();
+    request
+        .headers_mut()
+        .insert("Cookie", format!("auth_token={}", user.token).parse().unwrap());
+
+    let (socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+    socket
+}
+
+/// The next JSON message on the socket
+async fn next_message(socket: &mut Socket) -> Value {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("no message within 5 seconds")
+            .expect("the socket closed")
+            .unwrap();
+        if let Message::Text(text) = message {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+/// The next message on the socket that isn't a balance update (every
+/// transfer alert is followed by one)
+async fn next_alert(socket: &mut Socket) -> Value {
+    loop {
+        let message = next_message(socket).await;
+        if message["type"] != "balance_updated" {
+            return message;
+        }
+    }
+}
+
+async fn send(app: &TestApp, from: &TestUser, to: &TestUser, amount: &str) {
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/transfer",
+            Some(&from.token),
+            json!({ "recipient_email": to.email, "amount": amount }),
+        )
+        .await;
+    assert!(status.is_success(), "{}", body);
+}
+
+#[tokio::test]
+async fn transfer_alerts_are_numbered_and_pushed_live() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "50.00").await;
+    let addr = app.serve().await;
+
+    let mut socket = connect(addr, &bob, None).await;
+    assert_eq!(next_message(&mut socket).await, json!({ "type": "connected", "sequence": 0 }));
+
+    send(&app, &alice, &bob, "5.00").await;
+    let alert = next_message(&mut socket).await;
+
+    assert_eq!(alert["type"], "transfer_received");
+    assert_eq!(alert["sequence"], 1);
+    assert_eq!(alert["newBalance"], "5.00");
+}
+
+#[tokio::test]
+async fn reconnecting_replays_what_was_missed() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "50.00").await;
+    let addr = app.serve().await;
+
+    let mut socket = connect(addr, &bob, None).await;
+    next_message(&mut socket).await;
+    send(&app, &alice, &bob, "1.00").await;
+    assert_eq!(next_alert(&mut socket).await["sequence"], 1);
+    socket.close(None).await.unwrap();
+
+    // Sent while Bob is away (each alert and its balance update numbered)
+    send(&app, &alice, &bob, "2.00").await;
+    send(&app, &alice, &bob, "3.00").await;
+
+    let mut socket = connect(addr, &bob, Some(1)).await;
+    let missed = [next_alert(&mut socket).await, next_alert(&mut socket).await];
+    assert_eq!(
+        missed.iter().map(|m| (m["sequence"].clone(), m["amount"].clone())).collect::<Vec<_>>(),
+        [(json!(3), json!("2.00")), (json!(5), json!("3.00"))]
+    );
+    assert_eq!(next_alert(&mut socket).await, json!({ "type": "connected", "sequence": 6 }));
+
+    // Then live ones, numbered on
+    send(&app, &alice, &bob, "4.00").await;
+    assert_eq!(next_alert(&mut socket).await["sequence"], 7);
+}
+
+#[tokio::test]
+async fn reconnecting_replays_everything_however_much_was_missed() {
+    let app = TestApp::spawn().await;
+    let bob = app.register("bob@example.com").await;
+    let addr = app.serve().await;
+
+    // Two and a half pages' worth while Bob is away
+    for n in 1..=250 {
+        let notice = json!({ "type": "notice", "n": n });
+        app.state.notification_service.send_to_user(&bob.id, notice).await;
+    }
+
+    let mut socket = connect(addr, &bob, Some(0)).await;
+    for sequence in 1..=250 {
+        assert_eq!(next_message(&mut socket).await["sequence"], sequence);
+    }
+    assert_eq!(next_message(&mut socket).await, json!({ "type": "connected", "sequence": 250 }));
+}
+
+#[tokio::test]
+async fn every_open_connection_gets_the_alerts() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "50.00").await;
+    let addr = app.serve().await;
+
+    // Two tabs
+    let mut first = connect(addr, &bob, None).await;
+    next_message(&mut first).await;
+    let mut second = connect(addr, &bob, None).await;
+    next_message(&mut second).await;
+
+    send(&app, &alice, &bob, "5.00").await;
+    assert_eq!(next_alert(&mut first).await["sequence"], 1);
+    assert_eq!(next_alert(&mut second).await["sequence"], 1);
+
+    // Closing one leaves the other connected
+    first.close(None).await.unwrap();
+    drop(first);
+    send(&app, &alice, &bob, "6.00").await;
+    let alert = next_alert(&mut second).await;
+    assert_eq!((alert["sequence"].clone(), alert["amount"].clone()), (json!(3), json!("6.00")));
+}
+
+#[tokio::test]
+async fn balance_changes_are_pushed_to_the_dashboard() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    let addr = app.serve().await;
+
+    let mut alice_socket = connect(addr, &alice, None).await;
+    next_message(&mut alice_socket).await;
+    let mut bob_socket = connect(addr, &bob, None).await;
+    next_message(&mut bob_socket).await;
+
+    app.deposit(&alice, "50.00").await;
+    assert_eq!(
+        next_message(&mut alice_socket).await,
+        json!({
+            "type": "balance_updated",
+            "sequence": 1,
+            "currency": "USD",
+            "balance": "50.00",
+            "transaction": { "type": "DEPOSIT", "amount": "50.00", "description": "Deposit funds" }
+        })
+    );
+
+    let (status, body) = app
+        .post_json("/api/wallet/withdraw", Some(&alice.token), json!({ "amount": "10.00" }))
+        .await;
+    assert!(status.is_success(), "{}", body);
+    let update = next_message(&mut alice_socket).await;
+    assert_eq!(update["balance"], "40.00");
+    assert_eq!(update["transaction"]["type"], "WITHDRAWAL");
+
+    // Both sides of a transfer
+    send(&app, &alice, &bob, "15.00").await;
+    let update = next_message(&mut alice_socket).await;
+    assert_eq!(update["balance"], "25.00");
+    assert_eq!(update["transaction"]["description"], "Transfer sent");
+
+    assert_eq!(next_message(&mut bob_socket).await["type"], "transfer_received");
+    let update = next_message(&mut bob_socket).await;
+    assert_eq!(update["type"], "balance_updated");
+    assert_eq!(update["balance"], "15.00");
+    assert_eq!(
+        update["transaction"],
+        json!({ "type": "TRANSFER", "amount": "15.00", "description": "Transfer received" })
+    );
+}
+
+#[tokio::test]
+async fn announcements_reach_everyone_connected_and_are_audited() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    let admin = app.register_admin("admin@example.com").await;
+    let addr = app.serve().await;
+
+    let mut sockets = [
+        connect(addr, &alice, None).await,
+        connect(addr, &bob, None).await,
+        connect(addr, &bob, None).await,
+    ];
+    for socket in &mut sockets {
+        next_message(socket).await;
+    }
+
+    let announcement = json!({ "kind": "maintenance", "message": "Back in 10 minutes" });
+    let (status, _) = app
+        .post_json("/api/admin/broadcast", Some(&alice.token), announcement.clone())
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = app
+        .post_json("/api/admin/broadcast", Some(&admin.token), announcement)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["delivered_to"], 3);
+
+    for socket in &mut sockets {
+        assert_eq!(
+            next_message(socket).await,
+            json!({ "type": "announcement", "kind": "maintenance", "message": "Back in 10 minutes" })
+        );
+    }
+
+    let (_, log) = app.get("/api/admin/audit-log", Some(&admin.token)).await;
+    assert_eq!(log[0]["path"], "/api/admin/broadcast");
+    assert_eq!(log[0]["admin_email"], "admin@example.com");
+}
+
+#[tokio::test]
+async fn broadcasts_are_rate_limited() {
+    let clock = Arc::new(ManualClock::new());
+    let app = TestApp::spawn_with(|builder| builder.clock(clock.clone())).await;
+    let admin = app.register_admin("admin@example.com").await;
+    let announcement = json!({ "message": "New feature: scheduled transfers" });
+
+    for _ in 0..5 {
+        let (status, body) = app
+            .post_json("/api/admin/broadcast", Some(&admin.token), announcement.clone())
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+
+    let (status, body) = app
+        .post_json("/api/admin/broadcast", Some(&admin.token), announcement.clone())
+        .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS, "{}", body);
+    assert_eq!(body["code"], "RATE_LIMITED");
+    let retry_after = body["details"]["retry_after_secs"].as_i64().unwrap();
+    assert!((3590..=3600).contains(&retry_after), "{}", retry_after);
+
+    // The window is the clock's, so it moves when the clock does
+    clock.advance(chrono::Duration::minutes(59));
+    let (status, body) = app
+        .post_json("/api/admin/broadcast", Some(&admin.token), announcement.clone())
+        .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS, "{}", body);
+    let retry_after = body["details"]["retry_after_secs"].as_i64().unwrap();
+    assert!((50..=60).contains(&retry_after), "{}", retry_after);
+
+    clock.advance(chrono::Duration::minutes(2));
+    let (status, body) = app
+        .post_json("/api/admin/broadcast", Some(&admin.token), announcement)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+/// An open GET /api/events response, and what's been read of it
+struct EventStream {
+    response: reqwest::Response,
+    buffer: String,
+}
+
+impl EventStream {
+    /// Open /api/events as `user`, resuming after `last_event_id` when given
+    async fn open(addr: SocketAddr, user: &TestUser, last_event_id: Option<i64>) -> EventStream {
+        let mut request = reqwest::Client::new()
+            .get(format!("http://{}/api/events", addr))
+            .bearer_auth(&user.token);
+        if let Some(id) = last_event_id {
+            request = request.header("Last-Event-ID", id.to_string());
+        }
+        let response = request.send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        EventStream { response, buffer: String::new() }
+    }
+
+    /// The next event's id and JSON data (keep-alive comments skipped)
+    async fn next(&mut self) -> (Option<String>, Value) {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let block: String = self.buffer.drain(..end + 2).collect();
+                let field = |name: &str| {
+                    let value = block.lines().find_map(|line| line.strip_prefix(name))?;
+                    Some(value.trim_start().to_string())
+                };
+                if let Some(data) = field("data:") {
+                    return (field("id:"), serde_json::from_str(&data).unwrap());
+                }
+                continue;
+            }
+            let chunk = tokio::time::timeout(Duration::from_secs(5), self.response.chunk())
+                .await
+                .expect("no event within 5 seconds")
+                .unwrap()
+                .expect("the stream ended");
+            self.buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+}
+
+#[tokio::test]
+async fn server_sent_events_resume_from_last_event_id() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "50.00").await;
+    let addr = app.serve().await;
+
+    // Bob saw the alert (1) but not the balance update after it (2)
+    send(&app, &alice, &bob, "1.00").await;
+    let mut events = EventStream::open(addr, &bob, Some(1)).await;
+
+    let (id, missed) = events.next().await;
+    assert_eq!((id.as_deref(), missed["type"].clone()), (Some("2"), json!("balance_updated")));
+    let (id, connected) = events.next().await;
+    assert_eq!(id.as_deref(), Some("2"));
+    assert_eq!(connected, json!({ "type": "connected", "sequence": 2 }));
+
+    send(&app, &alice, &bob, "2.00").await;
+    let (id, alert) = events.next().await;
+    assert_eq!(id.as_deref(), Some("3"));
+    assert_eq!(alert["type"], "transfer_received");
+    assert_eq!(alert["amount"], "2.00");
+}
+
+#[tokio::test]
+async fn server_sent_events_resume_after_the_last_event_actually_sent() {
+    let app = TestApp::spawn().await;
+    let bob = app.register("bob@example.com").await;
+    let addr = app.serve().await;
+
+    for n in 1..=150 {
+        let notice = json!({ "type": "notice", "n": n });
+        app.state.notification_service.send_to_user(&bob.id, notice).await;
+    }
+
+    let mut events = EventStream::open(addr, &bob, Some(0)).await;
+    for sequence in 1..=150 {
+        let (id, _) = events.next().await;
+        assert_eq!(id, Some(sequence.to_string()));
+    }
+    let (id, connected) = events.next().await;
+    assert_eq!(id.as_deref(), Some("150"));
+    assert_eq!(connected, json!({ "type": "connected", "sequence": 150 }));
+}
+
+#[tokio::test]
+async fn server_sent_events_need_a_login() {
+    let app = TestApp::spawn().await;
+
+    let (status, _) = app.get("/api/events", None).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn shutting_down_closes_connections_as_going_away() {
+    let app = TestApp::spawn().await;
+    let bob = app.register("bob@example.com").await;
+    let addr = app.serve().await;
+
+    let mut socket = connect(addr, &bob, None).await;
+    next_message(&mut socket).await;
+
+    app.state.notification_service.disconnect_all().await;
+
+    let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+        .await
+        .expect("not closed within 5 seconds")
+        .expect("closed without a close frame")
+        .unwrap();
+    let Message::Close(Some(frame)) = message else {
+        panic!("expected a close frame, got {:?}", message);
+    };
+    assert_eq!(frame.code, CloseCode::Away);
+}
+
+/// A test app that pings every second and closes after two quiet ones
+async fn spawn_with_heartbeat() -> TestApp {
+    TestApp::spawn_with_config(|config| {
+        config.ws_ping_interval_secs = 1;
+        config.ws_idle_timeout_secs = 2;
+    })
+    .await
+}
+
+#[tokio::test]
+async fn clients_are_pinged() {
+    let app = spawn_with_heartbeat().await;
+    let bob = app.register("bob@example.com").await;
+    let addr = app.serve().await;
+
+    let mut socket = connect(addr, &bob, None).await;
+    next_message(&mut socket).await;
+
+    let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+        .await
+        .expect("no ping within 5 seconds")
+        .expect("the socket closed")
+        .unwrap();
+    assert!(matches!(message, Message::Ping(_)), "{:?}", message);
+}
+
+#[tokio::test]
+async fn silent_clients_are_closed_as_idle() {
+    let app = spawn_with_heartbeat().await;
+    let bob = app.register("bob@example.com").await;
+    let addr = app.serve().await;
+
+    let mut socket = connect(addr, &bob, None).await;
+    next_message(&mut socket).await;
+
+    // Not reading means not answering the pings either
+    tokio::time::sleep(Duration::from_millis(3500)).await;
+
+    let frame = loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("not closed within 5 seconds")
+            .expect("closed without a close frame")
+            .unwrap();
+        match message {
+            Message::Ping(_) => continue,
+            Message::Close(Some(frame)) => break frame,
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+    };
+    assert_eq!(frame.code, CloseCode::Away);
+    assert_eq!(frame.reason, "Idle timeout");
+}
+
+#[tokio::test]
+async fn clients_answering_pings_stay_connected() {
+    let app = spawn_with_heartbeat().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "50.00").await;
+    let addr = app.serve().await;
+
+    let mut socket = connect(addr, &bob, None).await;
+    next_message(&mut socket).await;
+
+    // Reading answers each ping with a pong
+    let quiet_until = tokio::time::Instant::now() + Duration::from_millis(3500);
+    while let Ok(message) = tokio::time::timeout_at(quiet_until, socket.next()).await {
+        let message = message.expect("the socket closed").unwrap();
+        assert!(matches!(message, Message::Ping(_)), "{:?}", message);
+    }
+
+    send(&app, &alice, &bob, "5.00").await;
+    assert_eq!(next_message(&mut socket).await["type"], "transfer_received");
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::Value;
+
+// ============================================================================
+// OPENAPI
+// ============================================================================
+// The spec at /api/openapi.json describes routes that exist, with schemas
+// that resolve.
+
+/// Every `$ref` in `value`, e.g. "#/components/schemas/WalletResponse"
+fn refs(value: &Value, found: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    ("$ref", Value::String(reference)) => found.push(reference.clone()),
+                    _ => refs(value, found),
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| refs(item, found)),
+        _ => {}
+    }
+}
+
+#[tokio::test]
+async fn the_spec_describes_the_core_api() {
+    let app = TestApp::spawn().await;
+
+    let (status, spec) = app.get("/api/openapi.json", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+    assert!(spec["paths"]["/api/login"]["post"].is_object(), "{}", spec["paths"]);
+    assert!(spec["paths"]["/api/wallet/transfer"]["post"]["security"].is_array());
+    assert!(spec["components"]["securitySchemes"]["bearer"].is_object());
+
+    let schemas = &spec["components"]["schemas"];
+    for name in ["CreateUserRequest", "LoginResponse", "WalletResponse", "TransactionResponse", "ErrorBody"] {
+        assert!(schemas[name].is_object(), "no {} schema", name);
+    }
+    // Amounts travel as strings
+    assert_eq!(schemas["WalletResponse"]["properties"]["balance"]["type"], "string");
+
+    let mut found = Vec::new();
+    refs(&spec, &mut found);
+    for reference in found {
+        let name = reference.trim_start_matches("#/components/schemas/");
+        assert!(schemas[name].is_object(), "{} doesn't resolve", reference);
+    }
+}
+
+#[tokio::test]
+async fn every_documented_operation_is_served() {
+    let app = TestApp::spawn().await;
+    let (_, spec) = app.get("/api/openapi.json", None).await;
+
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        for method in item.as_object().unwrap().keys() {
+            let method = Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+            // No token and no body: refused, but by the handler
+            let (status, _) = app.send(method.clone(), path, None, None).await;
+            assert!(
+                status != StatusCode::NOT_FOUND && status != StatusCode::METHOD_NOT_ALLOWED,
+                "{} {} isn't routed ({})",
+                method,
+                path,
+                status
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn swagger_ui_is_served() {
+    let app = TestApp::spawn().await;
+
+    let (status, page) = app.get("/api/docs/", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.as_str().unwrap().contains("swagger"), "{}", page);
+}
@@ -0,0 +1,295 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{decimal, TestApp, TestUser};
+use rust_decimal::Decimal;
+use serde_json::json;
+
+// ============================================================================
+// BUSINESS ACCOUNTS
+// ============================================================================
+// An owner starts an organization, invites people by email, and what each
+// member can do with the organization's wallet follows their role.
+
+fn dec(amount: &str) -> Decimal {
+    amount.parse().unwrap()
+}
+
+/// Start Acme Corp with `owner` as its owner; returns its id
+async fn create_organization(app: &TestApp, owner: &TestUser) -> String {
+    let (status, body) = app
+        .post_json(
+            "/api/organizations",
+            Some(&owner.token),
+            json!({ "name": "Acme Corp", "slug": "acme" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    body["id"].as_str().unwrap().to_string()
+}
+
+/// Invite `member` with `role` and accept with the emailed code
+async fn join(app: &TestApp, org: &str, owner: &TestUser, member: &TestUser, role: &str) {
+    let (status, body) = app
+        .post_json(
+            &format!("/api/organizations/{}/invitations", org),
+            Some(&owner.token),
+            json!({ "email": member.email, "role": role }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    let email = app.outbox.wait_for_subject(&member.email, "Join").await;
+    let token = email
+        .body
+        .lines()
+        .map(str::trim)
+        .find(|line| line.len() == 64 && line.bytes().all(|b| b.is_ascii_hexdigit()))
+        .expect("an invitation code in the email");
+
+    let (status, body) = app
+        .post_json(
+            "/api/organizations/invitations/accept",
+            Some(&member.token),
+            json!({ "token": token }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["role"], role);
+}
+
+#[tokio::test]
+async fn roles_decide_who_can_move_the_organizations_money() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    let carol = app.register("carol@example.com").await;
+    let supplier = app.register("supplier@example.com").await;
+
+    let org = create_organization(&app, &alice).await;
+    join(&app, &org, &alice, &bob, "FINANCE").await;
+    join(&app, &org, &alice, &carol, "VIEWER").await;
+
+    // Finance deposits and pays a supplier from the organization's wallet
+    let (status, body) = app
+        .post_json(
+            &format!("/api/organizations/{}/wallet/deposit", org),
+            Some(&bob.token),
+            json!({ "amount": "500.00" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, body) = app
+        .post_json(
+            &format!("/api/organizations/{}/wallet/transfer", org),
+            Some(&bob.token),
+            json!({ "recipient_email": "supplier@example.com", "amount": "120.00" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(decimal(&body["balance"]), dec("380.00"));
+    assert_eq!(app.balance(&supplier).await, dec("120.00"));
+    // ...and nothing came out of Bob's own wallet
+    assert_eq!(app.balance(&bob).await, Decimal::ZERO);
+
+    // Viewers can look but not touch
+    let (status, body) = app
+        .get(&format!("/api/organizations/{}/wallet", org), Some(&carol.token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(decimal(&body["balance"]), dec("380.00"));
+    let (status, body) = app
+        .get(&format!("/api/organizations/{}/transactions", org), Some(&carol.token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["transactions"].as_array().unwrap().len(), 2);
+    let (status, _) = app
+        .post_json(
+            &format!("/api/organizations/{}/wallet/withdraw", org),
+            Some(&carol.token),
+            json!({ "amount": "10.00" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Only owners manage members
+    let (status, _) = app
+        .post_json(
+            &format!("/api/organizations/{}/invitations", org),
+            Some(&bob.token),
+            json!({ "email": "dave@example.com", "role": "OWNER" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Outsiders don't see the organization at all
+    let (status, _) = app
+        .get(&format!("/api/organizations/{}", org), Some(&supplier.token))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn invitations_are_for_the_invited_address_only_and_once() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    let mallory = app.register("mallory@example.com").await;
+    let org = create_organization(&app, &alice).await;
+
+    app.post_json(
+        &format!("/api/organizations/{}/invitations", org),
+        Some(&alice.token),
+        json!({ "email": "bob@example.com", "role": "VIEWER" }),
+    )
+    .await;
+    let email = app.outbox.wait_for_subject("bob@example.com", "Join").await;
+    assert!(email.subject.contains("Acme Corp"));
+    let token = email.body.lines().map(str::trim).find(|l| l.len() == 64).unwrap();
+
+    for (user, expected) in [
+        (&mallory, StatusCode::NOT_FOUND),
+        (&bob, StatusCode::OK),
+        (&bob, StatusCode::NOT_FOUND),
+    ] {
+        let (status, body) = app
+            .post_json(
+                "/api/organizations/invitations/accept",
+                Some(&user.token),
+                json!({ "token": token }),
+            )
+            .await;
+        assert_eq!(status, expected, "{}: {}", user.email, body);
+    }
+
+    let (_, body) = app.get("/api/organizations", Some(&bob.token)).await;
+    assert_eq!(body[0]["slug"], "acme");
+    assert_eq!(body[0]["role"], "VIEWER");
+}
+
+#[tokio::test]
+async fn an_organization_always_keeps_an_owner() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    let org = create_organization(&app, &alice).await;
+    join(&app, &org, &alice, &bob, "FINANCE").await;
+
+    let alice_path = format!("/api/organizations/{}/members/{}", org, alice.id);
+    let (status, _) = app.send(Method::DELETE, &alice_path, Some(&alice.token), None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app
+        .put_json(&alice_path, Some(&alice.token), json!({ "role": "VIEWER" }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // With Bob promoted, Alice can go
+    let bob_path = format!("/api/organizations/{}/members/{}", org, bob.id);
+    let (status, _) = app
+        .put_json(&bob_path, Some(&alice.token), json!({ "role": "OWNER" }))
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = app.send(Method::DELETE, &alice_path, Some(&alice.token), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = app
+        .get(&format!("/api/organizations/{}/wallet", org), Some(&alice.token))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn expenses_are_paid_once_someone_else_approves_them() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let carol = app.register("carol@example.com").await;
+    let supplier = app.register("supplier@example.com").await;
+    let org = create_organization(&app, &alice).await;
+    join(&app, &org, &alice, &carol, "VIEWER").await;
+    app.post_json(
+        &format!("/api/organizations/{}/wallet/deposit", org),
+        Some(&alice.token),
+        json!({ "amount": "100.00" }),
+    )
+    .await;
+
+    // Viewers can ask, not pay
+    let expenses = format!("/api/organizations/{}/expenses", org);
+    let (status, expense) = app
+        .post_json(
+            &expenses,
+            Some(&carol.token),
+            json!({
+                "recipient_email": "supplier@example.com",
+                "amount": "89.90",
+                "description": "Office chairs"
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", expense);
+    assert_eq!(expense["status"], "PENDING");
+    let expense_path = format!("{}/{}", expenses, expense["id"].as_str().unwrap());
+    let (status, _) = app
+        .post_json(&format!("{}/approve", expense_path), Some(&carol.token), json!({}))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(app.balance(&supplier).await, Decimal::ZERO);
+
+    // It's on the owner's approvals page
+    let (status, page) = app.get("/dashboard/approvals", Some(&alice.token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.as_str().unwrap().contains("Office chairs"));
+
+    let (status, body) = app
+        .post_json(
+            &format!("{}/approve", expense_path),
+            Some(&alice.token),
+            json!({ "note": "Booked under office costs" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["status"], "APPROVED");
+    assert_eq!(app.balance(&supplier).await, dec("89.90"));
+    let email = app.outbox.wait_for_subject("carol@example.com", "Expense approved").await;
+    assert!(email.body.contains("Booked under office costs"));
+
+    // Decided requests stay decided
+    let (status, _) = app
+        .post_json(&format!("{}/reject", expense_path), Some(&alice.token), json!({}))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = app
+        .get(&format!("{}?status=PENDING", expenses), Some(&carol.token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn nobody_approves_their_own_expense() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    app.register("supplier@example.com").await;
+    let org = create_organization(&app, &alice).await;
+
+    let expenses = format!("/api/organizations/{}/expenses", org);
+    let (_, expense) = app
+        .post_json(
+            &expenses,
+            Some(&alice.token),
+            json!({
+                "recipient_email": "supplier@example.com",
+                "amount": "10.00",
+                "description": "Lunch"
+            }),
+        )
+        .await;
+    let (status, _) = app
+        .post_json(
+            &format!("{}/{}/approve", expenses, expense["id"].as_str().unwrap()),
+            Some(&alice.token),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use chrono::Duration;
+use common::{ManualClock, TestApp};
+use serde_json::json;
+
+// ============================================================================
+// PASSWORD RESET
+// ============================================================================
+// A forgotten password is replaced through an emailed one-time token.
+
+async fn app_with_clock() -> (TestApp, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock::new());
+    let app = TestApp::spawn_with(|builder| builder.clock(clock.clone())).await;
+    (app, clock)
+}
+
+/// Ask for a reset link for `email` and return the token it carries
+async fn reset_token(app: &TestApp, email: &str) -> String {
+    let already_sent = app.outbox.sent_to(email).len();
+    let (status, _) = app
+        .post_json("/api/password/forgot", None, json!({ "email": email }))
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    // The email goes out in the background; wait for this one, not an
+    // earlier one
+    let mut sent = app.outbox.sent_to(email);
+    for _ in 0..50 {
+        if sent.len() > already_sent {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        sent = app.outbox.sent_to(email);
+    }
+    let email = sent.pop().expect("a reset email");
+    assert!(email.subject.contains("Reset your password"));
+    email
+        .body
+        .lines()
+        .map(str::trim)
+        .find(|line| line.len() == 64 && line.bytes().all(|b| b.is_ascii_hexdigit()))
+        .expect("a reset code in the email")
+        .to_string()
+}
+
+async fn reset(app: &TestApp, token: &str, new_password: &str) -> StatusCode {
+    let (status, _) = app
+        .post_json(
+            "/api/password/reset",
+            None,
+            json!({ "token": token, "new_password": new_password }),
+        )
+        .await;
+    status
+}
+
+async fn login(app: &TestApp, email: &str, password: &str) -> (StatusCode, Option<String>) {
+    let (status, body) = app
+        .post_json("/api/login", None, json!({ "email": email, "password": password }))
+        .await;
+    (status, body["token"].as_str().map(str::to_string))
+}
+
+#[tokio::test]
+async fn a_reset_sets_the_new_password_and_signs_out_everywhere() {
+    let (app, clock) = app_with_clock().await;
+    let alice = app.register("alice@example.com").await;
+
+    let token = reset_token(&app, "alice@example.com").await;
+    assert_eq!(reset(&app, &token, "new-horse-battery").await, StatusCode::NO_CONTENT);
+
+    let (status, _) = app.get("/api/wallet", Some(&alice.token)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = login(&app, "alice@example.com", "correct-horse-battery").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    clock.advance(Duration::seconds(1));
+    let (status, token) = login(&app, "alice@example.com", "new-horse-battery").await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.get("/api/wallet", token.as_deref()).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn reset_tokens_expire_after_an_hour_and_work_once() {
+    let (app, clock) = app_with_clock().await;
+    app.register("alice@example.com").await;
+
+    let token = reset_token(&app, "alice@example.com").await;
+    clock.advance(Duration::minutes(61));
+    assert_eq!(reset(&app, &token, "new-horse-battery").await, StatusCode::BAD_REQUEST);
+
+    let token = reset_token(&app, "alice@example.com").await;
+    assert_eq!(reset(&app, &token, "new-horse-battery").await, StatusCode::NO_CONTENT);
+    assert_eq!(reset(&app, &token, "other-horse-battery").await, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn unknown_addresses_get_the_same_answer_and_no_email() {
+    let app = TestApp::spawn().await;
+
+    let (status, _) = app
+        .post_json("/api/password/forgot", None, json!({ "email": "nobody@example.com" }))
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert!(app.outbox.sent_to("nobody@example.com").is_empty());
+}
+
+#[tokio::test]
+async fn the_emailed_link_opens_the_reset_page() {
+    let app = TestApp::spawn().await;
+    app.register("alice@example.com").await;
+    let token = reset_token(&app, "alice@example.com").await;
+
+    let email = app.outbox.wait_for_subject("alice@example.com", "Reset your password").await;
+    assert!(email.body.contains(&format!("/password/reset?token={}", token)));
+
+    let (status, page) = app
+        .get(&format!("/password/reset?token={}", token), None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.as_str().unwrap().contains(&token));
+}
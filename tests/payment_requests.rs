@@ -0,0 +1,125 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, TestUser};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+// ============================================================================
+// PAYMENT REQUESTS
+// ============================================================================
+// One user asks another for money; the payer approves (a normal transfer) or
+// declines.
+
+fn dec(amount: &str) -> Decimal {
+    amount.parse().unwrap()
+}
+
+/// Ask `payer` for `amount` on behalf of `requester`
+async fn request(app: &TestApp, requester: &TestUser, payer: &TestUser, amount: &str) -> Value {
+    let (status, body) = app
+        .post_json(
+            "/api/requests",
+            Some(&requester.token),
+            json!({ "payer_email": payer.email, "amount": amount, "note": "Pizza" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    assert_eq!(body["status"], "PENDING");
+    body
+}
+
+#[tokio::test]
+async fn approved_request_pays_the_requester() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+
+    let created = request(&app, &alice, &bob, "12.50").await;
+    let approve = format!("/api/requests/{}/approve", created["id"].as_str().unwrap());
+
+    let (status, body) = app.get("/api/requests/incoming", Some(&bob.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["requester_email"], "alice@example.com");
+    assert_eq!(body[0]["note"], "Pizza");
+
+    // Only the payer can approve it
+    let (status, body) = app.post_json(&approve, Some(&alice.token), json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+
+    // Bob can't pay yet; the request stays open
+    let (status, body) = app.post_json(&approve, Some(&bob.token), json!({})).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    let (_, body) = app.get("/api/requests/outgoing", Some(&alice.token)).await;
+    assert_eq!(body[0]["status"], "PENDING");
+
+    app.deposit(&bob, "20.00").await;
+    let (status, body) = app.post_json(&approve, Some(&bob.token), json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["status"], "PAID");
+    assert_eq!(app.balance(&alice).await, dec("12.50"));
+    assert_eq!(app.balance(&bob).await, dec("7.50"));
+
+    // Only once
+    let (status, body) = app.post_json(&approve, Some(&bob.token), json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(app.balance(&alice).await, dec("12.50"));
+}
+
+#[tokio::test]
+async fn declined_request_moves_no_money() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&bob, "50.00").await;
+
+    let created = request(&app, &alice, &bob, "10.00").await;
+    let id = created["id"].as_str().unwrap();
+
+    let path = format!("/api/requests/{}/decline", id);
+    let (status, body) = app.post_json(&path, Some(&bob.token), json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["status"], "DECLINED");
+
+    let path = format!("/api/requests/{}/approve", id);
+    let (status, body) = app.post_json(&path, Some(&bob.token), json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(app.balance(&alice).await, Decimal::ZERO);
+    assert_eq!(app.balance(&bob).await, dec("50.00"));
+}
+
+#[tokio::test]
+async fn cannot_request_money_from_yourself() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+
+    let (status, body) = app
+        .post_json(
+            "/api/requests",
+            Some(&alice.token),
+            json!({ "payer_email": alice.email, "amount": "5.00" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+}
+
+#[tokio::test]
+async fn requests_that_would_need_confirming_are_refused() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+
+    // Over LIMIT_CONFIRM_ABOVE (1000.00): approving skips the emailed code
+    let (status, body) = app
+        .post_json(
+            "/api/requests",
+            Some(&alice.token),
+            json!({ "payer_email": bob.email, "amount": "1500.00" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(body["code"], "LIMIT_EXCEEDED");
+    let (_, body) = app.get("/api/requests/incoming", Some(&bob.token)).await;
+    assert!(body.as_array().unwrap().is_empty());
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use common::{ManualClock, TestApp};
+use my_fintech_app::services::rate_limiter::{
+    FixedWindowRateLimiter, MemoryRateLimitStore, RateLimitStore, RateLimitTier, RateLimitTiers,
+    RateLimiter,
+};
+use serde_json::json;
+
+// ============================================================================
+// RATE LIMITING
+// ============================================================================
+// The API and web UI are rate limited per client address (money per user),
+// with a separate allowance per tier; /health isn't.
+
+/// Three requests a minute, on a clock the test moves
+async fn strict_app() -> (TestApp, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock::new());
+    let limiter = FixedWindowRateLimiter::new(3, Duration::from_secs(60), clock.clone());
+    let app = TestApp::spawn_with(|builder| {
+        builder.clock(clock.clone()).rate_limiter(Arc::new(limiter))
+    })
+    .await;
+
+    (app, clock)
+}
+
+#[tokio::test]
+async fn requests_over_the_limit_are_refused_until_the_window_ends() {
+    let (app, clock) = strict_app().await;
+
+    for _ in 0..3 {
+        let (status, _) = app.get("/api/rates", None).await;
+        assert_ne!(status, StatusCode::TOO_MANY_REQUESTS);
+    }
+    let (status, _) = app.get("/api/rates", None).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    clock.advance(chrono::Duration::seconds(61));
+    let (status, _) = app.get("/api/rates", None).await;
+    assert_ne!(status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn health_checks_are_never_limited() {
+    let (app, _clock) = strict_app().await;
+
+    for _ in 0..10 {
+        let (status, _) = app.get("/health", None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
+
+#[tokio::test]
+async fn limiters_sharing_a_store_share_the_count() {
+    // Two instances behind a load balancer, counting in the same place
+    let clock = Arc::new(ManualClock::new());
+    let store: Arc<dyn RateLimitStore> = Arc::new(MemoryRateLimitStore::new(clock.clone()));
+    let first = FixedWindowRateLimiter::with_store(3, Duration::from_secs(60), clock.clone(), store.clone());
+    let second = FixedWindowRateLimiter::with_store(3, Duration::from_secs(60), clock.clone(), store);
+    let tier = RateLimitTier::Standard;
+
+    assert!(first.check(tier, "ip:203.0.113.7").await.allowed);
+    assert!(second.check(tier, "ip:203.0.113.7").await.allowed);
+    assert!(first.check(tier, "ip:203.0.113.7").await.allowed);
+    assert!(!second.check(tier, "ip:203.0.113.7").await.allowed);
+}
+
+#[tokio::test]
+async fn finished_windows_are_forgotten() {
+    let clock = Arc::new(ManualClock::new());
+    let store = Arc::new(MemoryRateLimitStore::new(clock.clone()));
+    let limiter = FixedWindowRateLimiter::with_store(3, Duration::from_secs(60), clock.clone(), store.clone());
+
+    for i in 0..50 {
+        let client = format!("ip:198.51.100.{}", i);
+        assert!(limiter.check(RateLimitTier::Read, &client).await.allowed);
+    }
+    assert_eq!(store.len(), 50);
+
+    clock.advance(chrono::Duration::minutes(3));
+    assert!(limiter.check(RateLimitTier::Read, "ip:198.51.100.200").await.allowed);
+    assert_eq!(store.len(), 1);
+}
+
+#[tokio::test]
+async fn tiers_have_their_own_allowances_and_say_so_in_headers() {
+    let clock = Arc::new(ManualClock::new());
+    let tiers = RateLimitTiers { auth: 2, read: 50, money: 3, standard: 50 };
+    let limiter = FixedWindowRateLimiter::new(50, Duration::from_secs(60), clock.clone())
+        .with_tiers(tiers);
+    let app = TestApp::spawn_with(|builder| {
+        builder.clock(clock.clone()).rate_limiter(Arc::new(limiter))
+    })
+    .await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "50.00").await;
+    app.deposit(&bob, "50.00").await;
+
+    // Registering used up the auth tier; reading still works
+    let login = json!({ "email": alice.email, "password": "password123" });
+    let response = app.request(Method::POST, "/api/login", None, Some(login)).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["X-RateLimit-Limit"], "2");
+    assert_eq!(response.headers()["X-RateLimit-Remaining"], "0");
+    let retry_after: u64 = response.headers()["Retry-After"].to_str().unwrap().parse().unwrap();
+    assert!((1..=60).contains(&retry_after), "{}", retry_after);
+
+    let response = app.request(Method::GET, "/api/rates", None, None).await;
+    assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["X-RateLimit-Limit"], "50");
+
+    // Money is counted per user, even from the same address: the deposits
+    // above were one each
+    for _ in 0..2 {
+        let (status, _) = app
+            .post_json("/api/wallet/withdraw", Some(&alice.token), json!({ "amount": "1.00" }))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, body) = app
+        .post_json("/api/wallet/withdraw", Some(&alice.token), json!({ "amount": "1.00" }))
+        .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["code"], "RATE_LIMITED");
+    assert!(body["details"]["retry_after_secs"].as_i64().unwrap() >= 1, "{}", body);
+    let (status, _) = app
+        .post_json("/api/wallet/withdraw", Some(&bob.token), json!({ "amount": "1.00" }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use common::{decimal, TestApp, TestUser};
+use my_fintech_app::services::referral_service::{self, ReferralTerms};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+// ============================================================================
+// REFERRALS
+// ============================================================================
+// Signing up with someone's code pays both sides once the new user's
+// deposit reaches REFERRAL_MIN_DEPOSIT (defaults: $10 each, from $20).
+
+fn dec(amount: &str) -> Decimal {
+    amount.parse().unwrap()
+}
+
+/// `user`'s referral overview (GET /api/referrals)
+async fn referrals(app: &TestApp, user: &TestUser) -> Value {
+    let (status, body) = app.get("/api/referrals", Some(&user.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body
+}
+
+/// Sign up `email` with a referral code
+async fn register_with_code(app: &TestApp, email: &str, code: &str) -> (StatusCode, Value) {
+    app.post_json(
+        "/api/register",
+        None,
+        json!({
+            "email": email,
+            "password": "correct-horse-battery",
+            "full_name": "Test User",
+            "referral_code": code,
+        }),
+    )
+    .await
+}
+
+async fn referred(app: &TestApp, email: &str, code: &str) -> TestUser {
+    let (status, body) = register_with_code(app, email, code).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    TestUser {
+        id: body["user"]["id"].as_str().unwrap().parse().unwrap(),
+        email: email.to_string(),
+        token: body["token"].as_str().unwrap().to_string(),
+    }
+}
+
+#[tokio::test]
+async fn both_sides_get_a_bonus_once_the_new_user_deposits_enough() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let overview = referrals(&app, &alice).await;
+    let code = overview["code"].as_str().unwrap().to_string();
+    assert!(overview["link"].as_str().unwrap().ends_with(&format!("/register?ref={}", code)));
+
+    // Codes are case-insensitive
+    let bob = referred(&app, "bob@example.com", &code.to_lowercase()).await;
+
+    // Too small to qualify
+    app.deposit(&bob, "5.00").await;
+    assert_eq!(app.balance(&alice).await, Decimal::ZERO);
+    let overview = referrals(&app, &alice).await;
+    assert_eq!(overview["referrals"][0]["status"], "PENDING");
+    assert_eq!(overview["referrals"][0]["referee_email"], "b••@example.com");
+
+    app.deposit(&bob, "20.00").await;
+    assert_eq!(app.balance(&alice).await, dec("10.00"));
+    assert_eq!(app.balance(&bob).await, dec("35.00"));
+
+    // ...once
+    app.deposit(&bob, "50.00").await;
+    assert_eq!(app.balance(&alice).await, dec("10.00"));
+
+    let overview = referrals(&app, &alice).await;
+    assert_eq!(overview["referrals"][0]["status"], "REWARDED");
+    assert_eq!(decimal(&overview["total_earned"]), dec("10.00"));
+    assert_eq!(decimal(&referrals(&app, &bob).await["total_earned"]), dec("10.00"));
+}
+
+#[tokio::test]
+async fn unknown_codes_are_refused() {
+    let app = TestApp::spawn().await;
+
+    let (status, body) = register_with_code(&app, "bob@example.com", "NOPE2345").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    // Nothing was created, so the address is still free
+    app.register("bob@example.com").await;
+}
+
+#[tokio::test]
+async fn suspicious_signups_count_but_never_pay() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let code = referrals(&app, &alice).await["code"].as_str().unwrap().to_string();
+
+    // Alice under another +tag, and a second friend from Bob's network
+    // (every test request comes from the same address)
+    referred(&app, "bob@example.com", &code).await;
+    let alias = referred(&app, "alice+2@example.com", &code).await;
+    let carol = referred(&app, "carol@example.com", &code).await;
+
+    let overview = referrals(&app, &alice).await;
+    let statuses: Vec<_> = overview["referrals"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|referral| (referral["referee_email"].as_str().unwrap(), referral["status"].clone()))
+        .collect();
+    assert!(statuses.contains(&("b••@example.com", json!("PENDING"))), "{:?}", statuses);
+    assert!(statuses.contains(&("a••@example.com", json!("REJECTED"))), "{:?}", statuses);
+    assert!(statuses.contains(&("c••@example.com", json!("REJECTED"))), "{:?}", statuses);
+
+    app.deposit(&alias, "100.00").await;
+    app.deposit(&carol, "100.00").await;
+    assert_eq!(app.balance(&alice).await, Decimal::ZERO);
+    assert_eq!(app.balance(&carol).await, dec("100.00"));
+}
+
+#[tokio::test]
+async fn referrals_expire_after_the_window() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let code = referrals(&app, &alice).await["code"].as_str().unwrap().to_string();
+    let bob = referred(&app, "bob@example.com", &code).await;
+
+    let terms = ReferralTerms::from_config(&app.state.config);
+    let later = Utc::now() + Duration::days(i64::from(terms.window_days) + 1);
+    let (rewarded, expired) = referral_service::sweep(&app.pool, &terms, later).await.unwrap();
+    assert_eq!((rewarded, expired), (0, 1));
+
+    app.deposit(&bob, "20.00").await;
+    assert_eq!(app.balance(&alice).await, Decimal::ZERO);
+    assert_eq!(referrals(&app, &alice).await["referrals"][0]["status"], "EXPIRED");
+}
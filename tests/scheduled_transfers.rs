@@ -0,0 +1,304 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::http::{Method, StatusCode};
+use chrono::Duration;
+use common::{ManualClock, TestApp, TestUser};
+use my_fintech_app::config::FeatureFlags;
+use my_fintech_app::services::clock::Clock;
+use my_fintech_app::services::scheduled_transfer_service::{self, RunSummary};
+use my_fintech_app::repository::ScheduledRun;
+use my_fintech_app::services::wallet_service::{self, ConcurrencyPolicy, SpendingLimits};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+// ============================================================================
+// SCHEDULED TRANSFERS
+// ============================================================================
+// Transfers booked for later, run by the background task's `run_due` (called
+// directly here, with a clock the test moves).
+
+async fn app_with_clock() -> (TestApp, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock::new());
+    let app = TestApp::spawn_with(|builder| builder.clock(clock.clone())).await;
+    (app, clock)
+}
+
+/// One pass of the background task
+async fn run_due(app: &TestApp) -> RunSummary {
+    run_due_with(app, &app.state.features).await
+}
+
+async fn run_due_with(app: &TestApp, features: &FeatureFlags) -> RunSummary {
+    let state = &app.state;
+    scheduled_transfer_service::run_due(
+        &state.pool,
+        &ConcurrencyPolicy::from_config(&state.config),
+        &state.email_service,
+        state.notification_service.as_ref(),
+        &state.exchange_rates,
+        &SpendingLimits::from_config(&state.config),
+        features,
+        state.clock.as_ref(),
+    )
+    .await
+    .unwrap()
+}
+
+/// Move the clock on and log `users` in again (their tokens expire)
+async fn advance(app: &TestApp, clock: &ManualClock, by: Duration, users: &mut [&mut TestUser]) {
+    clock.advance(by);
+    for user in users.iter_mut() {
+        user.token = app.login(user).await;
+    }
+}
+
+async fn scheduled(app: &TestApp, token: &str) -> Vec<Value> {
+    let (status, body) = app.get("/api/transfers/scheduled", Some(token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body.as_array().unwrap().clone()
+}
+
+#[tokio::test]
+async fn scheduled_transfer_runs_when_due() {
+    let (app, clock) = app_with_clock().await;
+    let mut alice = app.register("alice@example.com").await;
+    let mut bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "50.00").await;
+
+    let (status, body) = app
+        .post_json(
+            "/api/transfers/schedule",
+            Some(&alice.token),
+            json!({
+                "recipient_email": bob.email,
+                "amount": "20.00",
+                "execute_at": clock.now() + Duration::days(1),
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    assert_eq!(body["status"], "SCHEDULED");
+
+    // Not yet
+    assert_eq!(run_due(&app).await, RunSummary::default());
+    assert_eq!(app.balance(&bob).await, Decimal::ZERO);
+
+    advance(&app, &clock, Duration::days(1), &mut [&mut alice, &mut bob]).await;
+    assert_eq!(run_due(&app).await.completed, 1);
+    assert_eq!(app.balance(&alice).await, "30.00".parse::<Decimal>().unwrap());
+    assert_eq!(app.balance(&bob).await, "20.00".parse::<Decimal>().unwrap());
+
+    let transfers = scheduled(&app, &alice.token).await;
+    assert_eq!(transfers[0]["status"], "COMPLETED");
+    assert!(transfers[0]["next_attempt_at"].is_null());
+
+    // It only runs once, and can't be cancelled afterwards
+    advance(&app, &clock, Duration::days(1), &mut [&mut alice]).await;
+    assert_eq!(run_due(&app).await, RunSummary::default());
+    let path = format!("/api/transfers/scheduled/{}", body["id"].as_str().unwrap());
+    let (status, _) = app.send(Method::DELETE, &path, Some(&alice.token), None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn failed_attempts_are_retried_then_given_up() {
+    let (app, clock) = app_with_clock().await;
+    let mut alice = app.register("alice@example.com").await;
+    let mut bob = app.register("bob@example.com").await;
+
+    let (status, _) = app
+        .post_json(
+            "/api/transfers/schedule",
+            Some(&alice.token),
+            json!({
+                "recipient_email": bob.email,
+                "amount": "20.00",
+                "execute_at": clock.now() + Duration::hours(1),
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Nothing in the wallet yet: retried later
+    clock.advance(Duration::hours(1));
+    assert_eq!(run_due(&app).await.retrying, 1);
+    let transfer = &scheduled(&app, &alice.token).await[0];
+    assert_eq!(transfer["status"], "SCHEDULED");
+    assert_eq!(transfer["attempts"], 1);
+    assert_eq!(transfer["last_error"], "Insufficient balance");
+
+    // Not retried straight away
+    assert_eq!(run_due(&app).await, RunSummary::default());
+
+    // Each retry waits longer; the fifth failure gives up
+    for _ in 0..3 {
+        clock.advance(Duration::days(1));
+        assert_eq!(run_due(&app).await.retrying, 1);
+    }
+    advance(&app, &clock, Duration::days(1), &mut [&mut alice, &mut bob]).await;
+    assert_eq!(run_due(&app).await.failed, 1);
+    let transfer = &scheduled(&app, &alice.token).await[0];
+    assert_eq!(transfer["status"], "FAILED");
+    assert_eq!(transfer["attempts"], 5);
+    assert_eq!(app.balance(&bob).await, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn scheduled_transfer_can_be_cancelled() {
+    let (app, clock) = app_with_clock().await;
+    let mut alice = app.register("alice@example.com").await;
+    let mut bob = app.register("bob@example.com").await;
+    let carol = app.register("carol@example.com").await;
+    app.deposit(&alice, "50.00").await;
+
+    let (_, body) = app
+        .post_json(
+            "/api/transfers/schedule",
+            Some(&alice.token),
+            json!({
+                "recipient_email": bob.email,
+                "amount": "20.00",
+                "execute_at": clock.now() + Duration::days(7),
+            }),
+        )
+        .await;
+    let path = format!("/api/transfers/scheduled/{}", body["id"].as_str().unwrap());
+
+    // Only by its sender
+    let (status, _) = app.send(Method::DELETE, &path, Some(&carol.token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app.send(Method::DELETE, &path, Some(&alice.token), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    advance(&app, &clock, Duration::days(7), &mut [&mut alice, &mut bob]).await;
+    assert_eq!(run_due(&app).await, RunSummary::default());
+    assert_eq!(scheduled(&app, &alice.token).await[0]["status"], "CANCELLED");
+    assert_eq!(app.balance(&bob).await, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn schedule_is_checked_up_front() {
+    let (app, clock) = app_with_clock().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    let now = clock.now();
+
+    for (recipient, execute_at) in [
+        (bob.email.as_str(), now - Duration::minutes(1)),
+        (bob.email.as_str(), now + Duration::days(400)),
+        ("nobody@example.com", now + Duration::days(1)),
+        (alice.email.as_str(), now + Duration::days(1)),
+    ] {
+        let (status, body) = app
+            .post_json(
+                "/api/transfers/schedule",
+                Some(&alice.token),
+                json!({ "recipient_email": recipient, "amount": "5.00", "execute_at": execute_at }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{} {}: {}", recipient, execute_at, body);
+    }
+    assert!(scheduled(&app, &alice.token).await.is_empty());
+}
+
+#[tokio::test]
+async fn transfers_that_need_confirming_cannot_be_scheduled() {
+    let (app, clock) = app_with_clock().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "2000.00").await;
+
+    // Over LIMIT_CONFIRM_ABOVE (1000.00): nobody would be there for the code
+    let execute_at = clock.now() + Duration::days(1);
+    let (status, body) = app
+        .post_json(
+            "/api/transfers/schedule",
+            Some(&alice.token),
+            json!({ "recipient_email": bob.email, "amount": "1500.00", "execute_at": execute_at }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(body["code"], "LIMIT_EXCEEDED");
+    assert!(scheduled(&app, &alice.token).await.is_empty());
+}
+
+#[tokio::test]
+async fn a_scheduled_transfer_is_only_ever_paid_once() {
+    let (app, clock) = app_with_clock().await;
+    let mut alice = app.register("alice@example.com").await;
+    let mut bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "50.00").await;
+
+    let (_, body) = app
+        .post_json(
+            "/api/transfers/schedule",
+            Some(&alice.token),
+            json!({
+                "recipient_email": bob.email,
+                "amount": "20.00",
+                "execute_at": clock.now() + Duration::hours(1),
+            }),
+        )
+        .await;
+    let id = body["id"].as_str().unwrap().parse().unwrap();
+    advance(&app, &clock, Duration::hours(1), &mut [&mut alice, &mut bob]).await;
+
+    // Two runs of the same transfer (one outlived its claim): the second
+    // finds it COMPLETED along with the first's payment, and moves nothing
+    let state = &app.state;
+    let run = ScheduledRun { transfer_id: id, at: clock.now() };
+    for expected_ok in [true, false] {
+        let result = wallet_service::run_scheduled(
+            &state.pool,
+            &ConcurrencyPolicy::from_config(&state.config),
+            &state.email_service,
+            state.notification_service.as_ref(),
+            Some(&state.exchange_rates),
+            &SpendingLimits::from_config(&state.config),
+            state.clock.as_ref(),
+            run,
+            alice.id,
+            &bob.email,
+            "20.00".parse().unwrap(),
+            None,
+        )
+        .await;
+        assert_eq!(result.is_ok(), expected_ok, "{:?}", result.err());
+    }
+    assert_eq!(app.balance(&alice).await, "30.00".parse::<Decimal>().unwrap());
+    assert_eq!(app.balance(&bob).await, "20.00".parse::<Decimal>().unwrap());
+    assert_eq!(scheduled(&app, &alice.token).await[0]["status"], "COMPLETED");
+    assert_eq!(run_due(&app).await, RunSummary::default());
+}
+
+#[tokio::test]
+async fn paused_transfers_leave_scheduled_ones_waiting() {
+    let (app, clock) = app_with_clock().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "50.00").await;
+
+    let (status, _) = app
+        .post_json(
+            "/api/transfers/schedule",
+            Some(&alice.token),
+            json!({
+                "recipient_email": bob.email,
+                "amount": "20.00",
+                "execute_at": clock.now() + Duration::hours(1),
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    clock.advance(Duration::hours(1));
+
+    let paused = FeatureFlags { transfers_enabled: false, ..FeatureFlags::default() };
+    assert_eq!(run_due_with(&app, &paused).await, RunSummary::default());
+    assert_eq!(app.balance(&bob).await, Decimal::ZERO);
+
+    // Not claimed meanwhile, so it runs as soon as they're back on
+    assert_eq!(run_due(&app).await.completed, 1);
+    assert_eq!(app.balance(&bob).await, "20.00".parse::<Decimal>().unwrap());
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use chrono::Duration;
+use common::{ManualClock, TestApp, TestUser};
+use my_fintech_app::services::clock::Clock;
+use my_fintech_app::services::wallet_service;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+// ============================================================================
+// TRANSFER CONFIRMATION
+// ============================================================================
+// Transfers above LIMIT_CONFIRM_ABOVE (1000.00 in tests) hold the money until
+// the sender enters the code we email them, and give it back if they cancel
+// or never do.
+
+fn dec(amount: &str) -> Decimal {
+    amount.parse().unwrap()
+}
+
+async fn app_with_clock() -> (TestApp, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock::new());
+    let app = TestApp::spawn_with(|builder| builder.clock(clock.clone())).await;
+    (app, clock)
+}
+
+/// Start a transfer that needs confirming; returns it and the emailed code
+async fn begin(app: &TestApp, sender: &TestUser, recipient: &TestUser, amount: &str) -> (Value, String) {
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/transfer",
+            Some(&sender.token),
+            json!({ "recipient_email": recipient.email, "amount": amount }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+    assert_eq!(body["status"], "PENDING");
+
+    let email = app.outbox.wait_for_subject(&sender.email, "Confirm your transfer").await;
+    let code = email
+        .body
+        .lines()
+        .map(str::trim)
+        .find(|line| line.len() == 6 && line.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or_else(|| panic!("no code in {:?}", email))
+        .to_string();
+    (body, code)
+}
+
+async fn confirm(app: &TestApp, sender: &TestUser, transfer: &Value, code: &str) -> (StatusCode, Value) {
+    let path = format!("/api/wallet/transfer/{}/confirm", transfer["id"].as_str().unwrap());
+    app.post_json(&path, Some(&sender.token), json!({ "code": code })).await
+}
+
+#[tokio::test]
+async fn large_transfer_waits_for_the_emailed_code() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "3000.00").await;
+
+    let (transfer, code) = begin(&app, &alice, &bob, "2000.00").await;
+
+    // Held: gone from Alice, not with Bob yet
+    assert_eq!(app.balance(&alice).await, dec("1000.00"));
+    assert_eq!(app.balance(&bob).await, Decimal::ZERO);
+
+    let wrong = if code == "000000" { "111111" } else { "000000" };
+    let (status, body) = confirm(&app, &alice, &transfer, wrong).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(app.balance(&bob).await, Decimal::ZERO);
+
+    let (status, body) = confirm(&app, &alice, &transfer, &code).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["status"], "CONFIRMED");
+    assert_eq!(app.balance(&alice).await, dec("1000.00"));
+    assert_eq!(app.balance(&bob).await, dec("2000.00"));
+
+    // Only once
+    let (status, body) = confirm(&app, &alice, &transfer, &code).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(app.balance(&bob).await, dec("2000.00"));
+}
+
+#[tokio::test]
+async fn cancelled_transfer_is_refunded() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "3000.00").await;
+
+    let (transfer, code) = begin(&app, &alice, &bob, "1500.00").await;
+    let path = format!("/api/wallet/transfer/{}/cancel", transfer["id"].as_str().unwrap());
+
+    // Nobody else can cancel it
+    let (status, body) = app.post_json(&path, Some(&bob.token), json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+
+    let (status, body) = app.post_json(&path, Some(&alice.token), json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["status"], "CANCELLED");
+    assert_eq!(app.balance(&alice).await, dec("3000.00"));
+
+    let (status, body) = confirm(&app, &alice, &transfer, &code).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(app.balance(&bob).await, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn too_many_wrong_codes_cancel_the_transfer() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "3000.00").await;
+
+    let (transfer, code) = begin(&app, &alice, &bob, "1500.00").await;
+    let wrong = if code == "000000" { "111111" } else { "000000" };
+    for _ in 0..5 {
+        let (status, body) = confirm(&app, &alice, &transfer, wrong).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    }
+
+    assert_eq!(app.balance(&alice).await, dec("3000.00"));
+    let (status, body) = confirm(&app, &alice, &transfer, &code).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(app.balance(&bob).await, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn unconfirmed_transfer_expires_and_is_refunded() {
+    let (app, clock) = app_with_clock().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "3000.00").await;
+
+    let (transfer, code) = begin(&app, &alice, &bob, "1500.00").await;
+
+    // Not expired yet
+    let refunded = wallet_service::expire_pending_transfers(&app.pool, clock.now()).await.unwrap();
+    assert_eq!(refunded, 0);
+
+    clock.advance(Duration::minutes(16));
+    let refunded = wallet_service::expire_pending_transfers(&app.pool, clock.now()).await.unwrap();
+    assert_eq!(refunded, 1);
+    assert_eq!(app.balance(&alice).await, dec("3000.00"));
+
+    let (status, body) = confirm(&app, &alice, &transfer, &code).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(app.balance(&bob).await, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn held_money_cannot_be_reversed_by_an_admin() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    let admin = app.register_admin("admin@example.com").await;
+    app.deposit(&alice, "3000.00").await;
+
+    let (transfer, code) = begin(&app, &alice, &bob, "1500.00").await;
+    let (_, history) = app.get("/api/transactions", Some(&alice.token)).await;
+    let debit = history["transactions"][0]["id"].as_str().unwrap().to_string();
+    let path = format!("/api/admin/transactions/{}/reverse", debit);
+
+    // Refunding the hold would let the confirmed transfer pay Bob anyway
+    let (status, body) = app.post_json(&path, Some(&admin.token), json!({})).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(body["code"], "TRANSACTION_FAILED");
+    assert_eq!(app.balance(&alice).await, dec("1500.00"));
+
+    let (status, body) = confirm(&app, &alice, &transfer, &code).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(app.balance(&alice).await, dec("1500.00"));
+    assert_eq!(app.balance(&bob).await, dec("1500.00"));
+}
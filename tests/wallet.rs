@@ -0,0 +1,578 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{decimal, ManualClock, TestApp};
+use futures::future::join_all;
+use std::sync::Arc;
+use my_fintech_app::services::wallet_service::{self, ConcurrencyPolicy, SpendingLimits};
+use rust_decimal::Decimal;
+use serde_json::json;
+
+// ============================================================================
+// MONEY PATHS
+// ============================================================================
+// Deposits, withdrawals and transfers through the API, checked against the
+// balances and history the user sees afterwards.
+
+fn dec(amount: &str) -> Decimal {
+    amount.parse().unwrap()
+}
+
+#[tokio::test]
+async fn new_wallet_starts_empty() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+
+    assert_eq!(app.balance(&alice).await, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn deposit_and_withdraw_update_the_balance() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+
+    app.deposit(&alice, "100.00").await;
+    let (status, body) = app
+        .post_json("/api/wallet/withdraw", Some(&alice.token), json!({ "amount": "30.25" }))
+        .await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(decimal(&body["balance"]), dec("69.75"));
+    assert_eq!(app.balance(&alice).await, dec("69.75"));
+}
+
+#[tokio::test]
+async fn withdrawing_more_than_the_balance_is_refused() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    app.deposit(&alice, "20.00").await;
+
+    let (status, body) = app
+        .post_json("/api/wallet/withdraw", Some(&alice.token), json!({ "amount": "20.01" }))
+        .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(body["code"], "INSUFFICIENT_BALANCE");
+    assert_eq!(app.balance(&alice).await, dec("20.00"));
+}
+
+#[tokio::test]
+async fn non_positive_amounts_are_refused() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+
+    for amount in ["0", "-5.00"] {
+        let (status, body) = app
+            .post_json("/api/wallet/deposit", Some(&alice.token), json!({ "amount": amount }))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "depositing {}: {}", amount, body);
+    }
+    assert_eq!(app.balance(&alice).await, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn amounts_are_whole_cents_up_to_a_ceiling() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+
+    for (amount, code) in [("1.005", "scale"), ("1000000000.01", "max")] {
+        let (status, body) = app
+            .post_json("/api/wallet/deposit", Some(&alice.token), json!({ "amount": amount }))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "depositing {}: {}", amount, body);
+        assert_eq!(body["errors"][0]["field"], "amount");
+        assert_eq!(body["errors"][0]["code"], code);
+    }
+
+    // Trailing zeros aren't extra cents
+    app.deposit(&alice, "1.500").await;
+    assert_eq!(app.balance(&alice).await, Decimal::new(150, 2));
+}
+
+#[tokio::test]
+async fn transfer_moves_money_between_wallets() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "50.00").await;
+
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/transfer",
+            Some(&alice.token),
+            json!({ "recipient_email": bob.email, "amount": "12.50" }),
+        )
+        .await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(app.balance(&alice).await, dec("37.50"));
+    assert_eq!(app.balance(&bob).await, dec("12.50"));
+}
+
+#[tokio::test]
+async fn concurrent_writes_to_the_same_wallets_lose_nothing() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "100.00").await;
+    app.deposit(&bob, "100.00").await;
+
+    // Transfers race each other (optimistically, retrying), and deposits
+    // into both wallets race them
+    let transfers = (0..20).map(|_| {
+        app.post_json(
+            "/api/wallet/transfer",
+            Some(&alice.token),
+            json!({ "recipient_email": bob.email, "amount": "1.00" }),
+        )
+    });
+    let deposits = (0..5).flat_map(|_| [&alice, &bob]).map(|user| {
+        app.post_json("/api/wallet/deposit", Some(&user.token), json!({ "amount": "2.00" }))
+    });
+    let (transfers, deposits) = tokio::join!(join_all(transfers), join_all(deposits));
+
+    for (status, body) in transfers.into_iter().chain(deposits) {
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+    assert_eq!(app.balance(&alice).await, dec("90.00"));
+    assert_eq!(app.balance(&bob).await, dec("130.00"));
+}
+
+#[tokio::test]
+async fn opposite_transfers_do_not_deadlock() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "100.00").await;
+    app.deposit(&bob, "100.00").await;
+
+    // Both ways at once, optimistically and under row locks
+    let state = &app.state;
+    let limits = SpendingLimits::from_config(&state.config);
+    let locking = ConcurrencyPolicy { optimistic_attempts: 0 };
+    let pairs = || (0..10).flat_map(|_| [(&alice, &bob), (&bob, &alice)]);
+    let optimistic = pairs().map(|(from, to)| {
+        app.post_json(
+            "/api/wallet/transfer",
+            Some(&from.token),
+            json!({ "recipient_email": to.email, "amount": "1.00" }),
+        )
+    });
+    let locked = pairs().map(|(from, to)| {
+        wallet_service::transfer(
+            &state.pool,
+            &locking,
+            &state.email_service,
+            state.notification_service.as_ref(),
+            None,
+            &limits,
+            state.clock.as_ref(),
+            from.id,
+            &to.email,
+            dec("2.00"),
+            None,
+        )
+    });
+    let (optimistic, locked) = tokio::join!(join_all(optimistic), join_all(locked));
+
+    for (status, body) in optimistic {
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+    for transfer in locked {
+        transfer.unwrap();
+    }
+    assert_eq!(app.balance(&alice).await, dec("100.00"));
+    assert_eq!(app.balance(&bob).await, dec("100.00"));
+}
+
+#[tokio::test]
+async fn concurrent_withdrawals_never_overdraw() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    app.deposit(&alice, "50.00").await;
+
+    let withdrawals = (0..10).map(|_| {
+        app.post_json("/api/wallet/withdraw", Some(&alice.token), json!({ "amount": "10.00" }))
+    });
+    let results = join_all(withdrawals).await;
+
+    // Exactly enough of them go through to empty the wallet
+    let paid = results.iter().filter(|(status, _)| *status == StatusCode::OK).count();
+    assert_eq!(paid, 5);
+    for (status, body) in results.iter().filter(|(status, _)| *status != StatusCode::OK) {
+        assert_eq!(*status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+        assert_eq!(body["code"], "INSUFFICIENT_BALANCE");
+    }
+    assert_eq!(app.balance(&alice).await, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn transfer_sends_a_receipt() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "50.00").await;
+
+    app.post_json(
+        "/api/wallet/transfer",
+        Some(&alice.token),
+        json!({ "recipient_email": bob.email, "amount": "12.50" }),
+    )
+    .await;
+
+    let email = app.outbox.wait_for_subject(&bob.email, "Money received").await;
+    assert!(email.body.contains("12.50"), "{:?}", email);
+    assert!(email.html.as_deref().is_some_and(|html| html.contains("12.50")), "{:?}", email);
+
+    let email = app.outbox.wait_for_subject(&alice.email, "Transfer sent").await;
+    assert!(email.body.contains("12.50"), "{:?}", email);
+    assert!(email.body.contains(&bob.email), "{:?}", email);
+}
+
+#[tokio::test]
+async fn receipts_are_written_in_the_recipients_language() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    let (status, body) = app
+        .put_json("/api/me/locale", Some(&bob.token), json!({ "locale": "es" }))
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT, "{}", body);
+    app.deposit(&alice, "50.00").await;
+
+    app.post_json(
+        "/api/wallet/transfer",
+        Some(&alice.token),
+        json!({ "recipient_email": bob.email, "amount": "12.50" }),
+    )
+    .await;
+
+    let email = app.outbox.wait_for_subject(&bob.email, "Dinero recibido").await;
+    assert!(email.body.contains("12.50"), "{:?}", email);
+    let html = email.html.expect("an HTML part");
+    assert!(html.contains("lang=\"es\""), "{}", html);
+
+    // Alice never chose, so hers is in English
+    app.outbox.wait_for_subject(&alice.email, "Deposit received").await;
+    app.outbox.wait_for_subject(&alice.email, "Transfer sent").await;
+}
+
+#[tokio::test]
+async fn transfer_without_enough_money_changes_nothing() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "10.00").await;
+
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/transfer",
+            Some(&alice.token),
+            json!({ "recipient_email": bob.email, "amount": "10.01" }),
+        )
+        .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(body["code"], "INSUFFICIENT_BALANCE");
+    assert_eq!(app.balance(&alice).await, dec("10.00"));
+    assert_eq!(app.balance(&bob).await, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn transfer_to_unknown_or_own_account_is_refused() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    app.deposit(&alice, "10.00").await;
+
+    for recipient in ["nobody@example.com", alice.email.as_str()] {
+        let (status, body) = app
+            .post_json(
+                "/api/wallet/transfer",
+                Some(&alice.token),
+                json!({ "recipient_email": recipient, "amount": "1.00" }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "sending to {}: {}", recipient, body);
+        assert_eq!(body["code"], "VALIDATION_ERROR");
+    }
+    assert_eq!(app.balance(&alice).await, dec("10.00"));
+}
+
+#[tokio::test]
+async fn money_endpoints_need_a_token() {
+    let app = TestApp::spawn().await;
+
+    let (status, _) = app.get("/api/wallet", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app
+        .post_json("/api/wallet/deposit", Some("not-a-token"), json!({ "amount": "5.00" }))
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn history_lists_every_movement_newest_first() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "40.00").await;
+    app.post_json("/api/wallet/withdraw", Some(&alice.token), json!({ "amount": "5.00" }))
+        .await;
+    app.post_json(
+        "/api/wallet/transfer",
+        Some(&alice.token),
+        json!({ "recipient_email": bob.email, "amount": "15.00" }),
+    )
+    .await;
+
+    let (status, body) = app.get("/api/transactions", Some(&alice.token)).await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let movements: Vec<(&str, Decimal)> = body["transactions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| (t["transaction_type"].as_str().unwrap(), decimal(&t["amount"])))
+        .collect();
+    assert_eq!(
+        movements,
+        [
+            ("TRANSFER", dec("15.00")),
+            ("WITHDRAWAL", dec("5.00")),
+            ("DEPOSIT", dec("40.00")),
+        ]
+    );
+    assert!(body["transactions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|t| t["status"] == "COMPLETED"));
+
+    let (_, body) = app.get("/api/transactions", Some(&bob.token)).await;
+    assert_eq!(body["transactions"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn history_can_be_filtered() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "40.00").await;
+    app.post_json("/api/wallet/withdraw", Some(&alice.token), json!({ "amount": "5.00" }))
+        .await;
+    app.post_json(
+        "/api/wallet/transfer",
+        Some(&alice.token),
+        json!({ "recipient_email": bob.email, "amount": "15.00" }),
+    )
+    .await;
+    app.deposit(&alice, "100.00").await;
+
+    let history = |query: &str| {
+        let path = format!("/api/transactions?{}", query);
+        let token = alice.token.clone();
+        let app = &app;
+        async move { app.get(&path, Some(&token)).await }
+    };
+    let amounts = |body: &serde_json::Value| -> Vec<Decimal> {
+        body["transactions"].as_array().unwrap().iter().map(|t| decimal(&t["amount"])).collect()
+    };
+
+    let (status, body) = history("type=DEPOSIT").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(amounts(&body), [dec("100.00"), dec("40.00")]);
+    assert_eq!(body["total"], 2);
+
+    let (_, body) = history("type=DEPOSIT&max_amount=50&status=COMPLETED").await;
+    assert_eq!(amounts(&body), [dec("40.00")]);
+    let (_, body) = history("q=sent").await;
+    assert_eq!(body["transactions"][0]["transaction_type"], "TRANSFER");
+    assert_eq!(body["total"], 1);
+    let (_, body) = history("from=2000-01-01&to=2000-12-31").await;
+    assert_eq!(body["total"], 0);
+
+    // The filters hold across pages
+    let (_, first) = history("type=DEPOSIT&limit=1").await;
+    let cursor = first["next_cursor"].as_str().unwrap();
+    let (_, second) = history(&format!("type=DEPOSIT&limit=1&cursor={}", cursor)).await;
+    assert_eq!(amounts(&second), [dec("40.00")]);
+    assert!(second["next_cursor"].is_null());
+
+    for query in ["type=FEE", "min_amount=10&max_amount=5", "from=2025-02-01&to=2025-01-01"] {
+        let (status, _) = history(query).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
+
+#[tokio::test]
+async fn wallets_in_other_currencies_convert_on_transfer() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+
+    let (status, body) =
+        app.post_json("/api/wallets", Some(&alice.token), json!({ "currency": "EUR" })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let (status, _) =
+        app.post_json("/api/wallets", Some(&alice.token), json!({ "currency": "EUR" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/deposit",
+            Some(&alice.token),
+            json!({ "amount": "20.00", "currency": "EUR" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["currency"], "EUR");
+    // The primary (USD) wallet is untouched
+    assert_eq!(app.balance(&alice).await, dec("0.00"));
+
+    let (_, wallets) = app.get("/api/wallets", Some(&alice.token)).await;
+    let currencies: Vec<_> = wallets.as_array().unwrap().iter().map(|w| &w["currency"]).collect();
+    assert_eq!(currencies, ["USD", "EUR"]);
+
+    // Bob has no EUR wallet: at the fixed rate (0.92 EUR to the dollar)
+    // he gets dollars
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/transfer",
+            Some(&alice.token),
+            json!({ "recipient_email": bob.email, "amount": "9.20", "currency": "EUR" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(decimal(&body["balance"]), dec("10.80"));
+    assert_eq!(app.balance(&bob).await, dec("10.00"));
+
+    let (_, history) = app.get("/api/transactions", Some(&bob.token)).await;
+    let received = &history["transactions"][0];
+    assert_eq!(decimal(&received["amount"]), dec("10.00"));
+    assert!(decimal(&received["exchange_rate"]) > Decimal::ONE);
+
+    // Naming a currency the user holds no wallet in is refused
+    let (status, _) = app
+        .post_json(
+            "/api/wallet/withdraw",
+            Some(&bob.token),
+            json!({ "amount": "1.00", "currency": "EUR" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn spending_limits_can_be_lowered_and_are_enforced() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "500.00").await;
+
+    let (status, body) = app.get("/api/limits", Some(&alice.token)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(decimal(&body["per_transaction"]), decimal(&body["max_per_transaction"]));
+
+    // Above the configured limit is refused
+    let (status, body) = app
+        .put_json("/api/limits", Some(&alice.token), json!({ "daily": "1000000.00" }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    let (status, body) = app
+        .put_json(
+            "/api/limits",
+            Some(&alice.token),
+            json!({ "per_transaction": "100.00", "daily": "150.00" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(decimal(&body["per_transaction"]), dec("100.00"));
+    assert_eq!(decimal(&body["daily"]), dec("150.00"));
+
+    let (status, body) = app
+        .post_json("/api/wallet/withdraw", Some(&alice.token), json!({ "amount": "100.01" }))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(body["code"], "LIMIT_EXCEEDED");
+    assert_eq!(body["details"]["period"], "transaction");
+    assert_eq!(decimal(&body["details"]["limit"]), dec("100.00"));
+
+    let (status, body) = app
+        .post_json("/api/wallet/withdraw", Some(&alice.token), json!({ "amount": "100.00" }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // 100 already out today; another 60 would make 160
+    let (status, body) = app
+        .post_json(
+            "/api/wallet/transfer",
+            Some(&alice.token),
+            json!({ "recipient_email": bob.email, "amount": "60.00" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(body["code"], "LIMIT_EXCEEDED");
+    assert_eq!(body["details"]["period"], "day");
+    assert_eq!(decimal(&body["details"]["limit"]), dec("150.00"));
+    assert_eq!(decimal(&body["details"]["remaining"]), dec("50.00"));
+    assert_eq!(app.balance(&alice).await, dec("400.00"));
+    assert_eq!(app.balance(&bob).await, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn concurrent_payments_stay_within_the_daily_limit() {
+    let app = TestApp::spawn().await;
+    let alice = app.register("alice@example.com").await;
+    let bob = app.register("bob@example.com").await;
+    app.deposit(&alice, "500.00").await;
+    let (status, body) = app
+        .put_json("/api/limits", Some(&alice.token), json!({ "daily": "150.00" }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // Each checks today's total with the wallet held, so only three of
+    // these fit, whichever way they go out
+    let payments = (0..10).map(|n| {
+        let (path, body) = if n % 2 == 0 {
+            ("/api/wallet/withdraw", json!({ "amount": "40.00" }))
+        } else {
+            ("/api/wallet/transfer", json!({ "recipient_email": bob.email, "amount": "40.00" }))
+        };
+        app.post_json(path, Some(&alice.token), body)
+    });
+    let results = join_all(payments).await;
+
+    let paid = results.iter().filter(|(status, _)| *status == StatusCode::OK).count();
+    assert_eq!(paid, 3);
+    for (status, body) in results.iter().filter(|(status, _)| *status != StatusCode::OK) {
+        assert_eq!(*status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+        assert_eq!(body["code"], "LIMIT_EXCEEDED");
+    }
+    assert_eq!(app.balance(&alice).await, dec("380.00"));
+}
+
+#[tokio::test]
+async fn the_daily_limit_follows_the_clock() {
+    let clock = Arc::new(ManualClock::new());
+    let app = TestApp::spawn_with(|builder| builder.clock(clock.clone())).await;
+    let mut alice = app.register("alice@example.com").await;
+    app.deposit(&alice, "500.00").await;
+    let (status, _) = app
+        .put_json("/api/limits", Some(&alice.token), json!({ "daily": "100.00" }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let withdraw = json!({ "amount": "100.00" });
+    let (status, body) = app.post_json("/api/wallet/withdraw", Some(&alice.token), withdraw.clone()).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, body) = app.post_json("/api/wallet/withdraw", Some(&alice.token), withdraw.clone()).await;
+    assert_eq!(body["code"], "LIMIT_EXCEEDED", "{}: {}", status, body);
+
+    // A new day by the app's clock, not the wall clock
+    clock.advance(chrono::Duration::days(1));
+    alice.token = app.login(&alice).await;
+    let (status, body) = app.post_json("/api/wallet/withdraw", Some(&alice.token), withdraw).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}